@@ -0,0 +1,86 @@
+use serde_json::Value;
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+/// Expose the resolved versions of the tree-sitter crates xStats depends on, as reported by
+/// `cargo metadata`, so that they can be recorded in the run manifest.
+///
+/// The build fails rather than recording no versions when they cannot be resolved.
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set by cargo");
+    let manifest_path = Path::new(&manifest_dir).join("Cargo.toml");
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={}", manifest_path.display());
+
+    let output = Command::new(&cargo)
+        .args(["metadata", "--format-version", "1", "--manifest-path"])
+        .arg(&manifest_path)
+        .output()
+        .unwrap_or_else(|e| panic!("Failed to run {} metadata: {}", cargo, e));
+    if !output.status.success() {
+        panic!(
+            "Failed to resolve the versions of the tree-sitter crates with cargo metadata: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let metadata: Value =
+        serde_json::from_slice(&output.stdout).expect("cargo metadata writes JSON");
+
+    // The lock file the versions were resolved from, if any, to resolve them again on update
+    if let Some(workspace_root) = metadata["workspace_root"].as_str() {
+        let lock_path = Path::new(workspace_root).join("Cargo.lock");
+        if lock_path.exists() {
+            println!("cargo:rerun-if-changed={}", lock_path.display());
+        }
+    }
+
+    let versions = get_grammar_versions(&metadata, &manifest_path);
+    if versions.is_empty() {
+        panic!("cargo metadata resolved no tree-sitter crate for xStats");
+    }
+    println!(
+        "cargo:rustc-env=XSTATS_GRAMMAR_VERSIONS={}",
+        versions.join(",")
+    );
+}
+
+/// The `name=version` of the tree-sitter crates among the dependencies of the package of the
+/// manifest, as resolved in the metadata
+fn get_grammar_versions(metadata: &Value, manifest_path: &Path) -> Vec<String> {
+    let empty = Vec::new();
+    let packages = metadata["packages"].as_array().unwrap_or(&empty);
+    let package_id = packages
+        .iter()
+        .find(|package| {
+            package["manifest_path"]
+                .as_str()
+                .is_some_and(|path| Path::new(path) == manifest_path)
+        })
+        .and_then(|package| package["id"].as_str());
+    let nodes = metadata["resolve"]["nodes"].as_array().unwrap_or(&empty);
+    let dependency_ids: Vec<&str> = nodes
+        .iter()
+        .find(|node| node["id"].as_str() == package_id)
+        .and_then(|node| node["dependencies"].as_array())
+        .map(|ids| ids.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let mut versions: Vec<String> = packages
+        .iter()
+        .filter(|package| {
+            package["id"]
+                .as_str()
+                .is_some_and(|id| dependency_ids.contains(&id))
+        })
+        .filter_map(|package| {
+            let name = package["name"].as_str()?;
+            let version = package["version"].as_str()?;
+            name.starts_with("tree-sitter")
+                .then(|| format!("{}={}", name, version))
+        })
+        .collect();
+    versions.sort();
+    versions
+}
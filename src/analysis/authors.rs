@@ -0,0 +1,141 @@
+use crate::metrics::{
+    ChangeType, ChurnMetrics, CodeChangesMap, CodeMetricsMap, CommitInfos, EntityKind,
+};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+/// What an author contributed to the analyzed history
+pub struct AuthorStats {
+    /// The email of the author, or their name without one.
+    pub author: String,
+    /// The number of commits authored.
+    pub commits: u32,
+    /// The number of lines added and deleted over all their commits.
+    pub lines_touched: u32,
+    /// The number of methods their commits changed, counted once per commit.
+    pub methods_touched: u32,
+    /// The mean cyclomatic complexity of the methods touched, as of the commits touching them.
+    pub avg_cc: f64,
+    /// The number of files of the last commit whose lines they wrote most of, from blame.
+    pub files_owned: u32,
+}
+
+/// The authors of the analyzed history, from the most to the least active.
+///
+/// The changes of each commit are attributed to its author, to study how developers work, e.g.
+/// whether some keep touching complex methods.
+pub struct Authors {
+    pub authors: Vec<AuthorStats>,
+}
+
+impl Authors {
+    /// Attribute the churn and the changed methods of each commit to its author, along with the
+    /// files each author owns, given by path
+    pub fn new(
+        commit_infos: &CommitInfos,
+        churn_metrics: &ChurnMetrics,
+        changes_map: &CodeChangesMap,
+        metrics_map: &CodeMetricsMap,
+        file_owners: &HashMap<String, String>,
+    ) -> Self {
+        let commit_authors: HashMap<&str, &str> = commit_infos
+            .commits
+            .iter()
+            .map(|commit| (commit.commit_id.as_str(), commit.author.as_str()))
+            .collect();
+
+        let mut authors: BTreeMap<String, AuthorStats> = BTreeMap::new();
+        let mut method_ccs: HashMap<String, u32> = HashMap::new();
+        for commit in &commit_infos.commits {
+            let stats = get_stats(&mut authors, &commit.author);
+            stats.commits += 1;
+
+            // The changed methods, as measured after the commit
+            let (Some(changes), Some(metrics)) = (
+                changes_map.get_changes(&commit.commit_id),
+                metrics_map.get_metrics(&commit.commit_id),
+            ) else {
+                continue;
+            };
+            for change in changes
+                .changes
+                .iter()
+                .filter(|change| change.change_type != ChangeType::Deleted)
+            {
+                let method = metrics.metric_blocks.iter().find(|block| {
+                    let meta_data = &block.meta_data;
+                    meta_data.node_kind == EntityKind::Method
+                        && meta_data.file_path == change.file_path
+                        && meta_data.start_row == change.start_row
+                        && meta_data.node_name == change.node_name
+                });
+                if let Some(method) = method {
+                    stats.methods_touched += 1;
+                    *method_ccs.entry(commit.author.clone()).or_default() += method.metric.cc;
+                }
+            }
+        }
+        for file_churn in &churn_metrics.file_churns {
+            if let Some(author) = commit_authors.get(file_churn.commit_id.as_str()) {
+                get_stats(&mut authors, author).lines_touched +=
+                    file_churn.lines_added + file_churn.lines_deleted;
+            }
+        }
+        for author in file_owners.values() {
+            get_stats(&mut authors, author).files_owned += 1;
+        }
+
+        let mut authors: Vec<AuthorStats> = authors.into_values().collect();
+        for stats in &mut authors {
+            if stats.methods_touched > 0 {
+                let cc = method_ccs.get(&stats.author).copied().unwrap_or_default();
+                stats.avg_cc = cc as f64 / stats.methods_touched as f64;
+            }
+        }
+        // The order of the map breaks the ties
+        authors.sort_by_key(|stats| Reverse(stats.commits));
+
+        Authors { authors }
+    }
+
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![vec![
+            "author".to_string(),
+            "commits".to_string(),
+            "lines_touched".to_string(),
+            "methods_touched".to_string(),
+            "avg_cc".to_string(),
+            "files_owned".to_string(),
+        ]];
+
+        for stats in &self.authors {
+            table.push(vec![
+                stats.author.clone(),
+                stats.commits.to_string(),
+                stats.lines_touched.to_string(),
+                stats.methods_touched.to_string(),
+                format!("{:.2}", stats.avg_cc),
+                stats.files_owned.to_string(),
+            ]);
+        }
+
+        table
+    }
+}
+
+/// The stats of an author, created empty on their first commit or file
+fn get_stats<'a>(
+    authors: &'a mut BTreeMap<String, AuthorStats>,
+    author: &str,
+) -> &'a mut AuthorStats {
+    authors
+        .entry(author.to_string())
+        .or_insert_with(|| AuthorStats {
+            author: author.to_string(),
+            commits: 0,
+            lines_touched: 0,
+            methods_touched: 0,
+            avg_cc: 0.0,
+            files_owned: 0,
+        })
+}
@@ -0,0 +1,115 @@
+use crate::metrics::{ChangeType, ChurnMetrics, CodeMetricsMap, EntityKind};
+use std::collections::HashMap;
+
+/// A file ranked by how complex it is and how often it changes
+pub struct Hotspot {
+    pub file_path: String,
+    /// The number of commits changing the file.
+    pub commits: u32,
+    /// The number of lines added and deleted over all the commits.
+    pub lines_changed: u32,
+    /// The cyclomatic complexity of the file, as of the last commit changing it.
+    pub cc: u32,
+    /// The actual lines of code of the file, as of the last commit changing it.
+    pub aloc: u32,
+    /// `commits * cc`, the higher the sooner the file is worth a look
+    pub score: u32,
+}
+
+/// The files of the analyzed history, from the most to the least complex and changed.
+///
+/// Complex code that keeps changing is where bugs and effort concentrate, while complex code
+/// that nobody touches costs little. Files deleted by the end of the history are left out.
+pub struct Hotspots {
+    pub hotspots: Vec<Hotspot>,
+}
+
+impl Hotspots {
+    /// Combine the churn of each file with its metrics in the last commit changing it
+    pub fn new(churn_metrics: &ChurnMetrics, metrics_map: &CodeMetricsMap) -> Self {
+        // The churn is recorded from the oldest to the most recent commit
+        let mut files: HashMap<&str, (u32, u32, &str, ChangeType)> = HashMap::new();
+        for file_churn in &churn_metrics.file_churns {
+            // A renamed file keeps the churn of its previous path
+            if let Some(old_file) = file_churn
+                .old_file_path
+                .as_deref()
+                .and_then(|old_file_path| files.remove(old_file_path))
+            {
+                files.insert(file_churn.file_path.as_str(), old_file);
+            }
+            let file = files.entry(file_churn.file_path.as_str()).or_insert((
+                0,
+                0,
+                "",
+                file_churn.change_type,
+            ));
+            file.0 += 1;
+            file.1 += file_churn.lines_added + file_churn.lines_deleted;
+            file.2 = &file_churn.commit_id;
+            file.3 = file_churn.change_type;
+        }
+
+        let mut hotspots: Vec<Hotspot> = files
+            .into_iter()
+            .filter(|(_, (.., change_type))| *change_type != ChangeType::Deleted)
+            .map(|(file_path, (commits, lines_changed, commit_id, _))| {
+                // Host files have a root block per sub-document
+                let (cc, aloc) = metrics_map
+                    .get_metrics(&commit_id.to_string())
+                    .map(|metrics| {
+                        metrics
+                            .metric_blocks
+                            .iter()
+                            .filter(|block| {
+                                block.meta_data.node_kind == EntityKind::File
+                                    && block.meta_data.file_path == file_path
+                            })
+                            .fold((0, 0), |(cc, aloc), block| {
+                                (cc + block.metric.cc, aloc + block.metric.aloc)
+                            })
+                    })
+                    .unwrap_or_default();
+                Hotspot {
+                    file_path: file_path.to_string(),
+                    commits,
+                    lines_changed,
+                    cc,
+                    aloc,
+                    score: commits * cc,
+                }
+            })
+            .collect();
+        hotspots.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.file_path.cmp(&b.file_path))
+        });
+
+        Hotspots { hotspots }
+    }
+
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![vec![
+            "file_path".to_string(),
+            "commits".to_string(),
+            "lines_changed".to_string(),
+            "cc".to_string(),
+            "aloc".to_string(),
+            "score".to_string(),
+        ]];
+
+        for hotspot in &self.hotspots {
+            table.push(vec![
+                hotspot.file_path.clone(),
+                hotspot.commits.to_string(),
+                hotspot.lines_changed.to_string(),
+                hotspot.cc.to_string(),
+                hotspot.aloc.to_string(),
+                hotspot.score.to_string(),
+            ]);
+        }
+
+        table
+    }
+}
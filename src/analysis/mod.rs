@@ -0,0 +1,4 @@
+mod authors;
+mod hotspots;
+pub use authors::{AuthorStats, Authors};
+pub use hotspots::{Hotspot, Hotspots};
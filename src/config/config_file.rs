@@ -0,0 +1,163 @@
+use super::Language;
+use crate::error::XStatsError;
+use crate::languages::load_language_profiles;
+use crate::metrics::{CodeMetric, ScoreWeights};
+use crate::utils::read_file;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The configuration files looked up in the target path, by order of precedence
+pub const CONFIG_FILE_NAMES: &[&str] =
+    &[".xstats.toml", "xstats.toml", ".xstats.yaml", "xstats.yaml"];
+
+/// Settings read from a `.xstats.toml` or `xstats.yaml` file.
+///
+/// Keys are named after the command line flags, e.g. `min-aloc`, which take precedence over them.
+///
+/// # Fields
+///
+/// * `languages` - The languages to analyze, all of them when empty.
+/// * `include` - Glob patterns of the files to analyze, all of them when empty.
+/// * `exclude` - Glob patterns of the files to skip, e.g. `**/test/**`.
+/// * `metrics` - The metric columns to output, all of them when empty.
+/// * `thresholds` - The maximum value of each metric, e.g. `cc = 15`.
+/// * `fail-on` - The metrics failing the run, e.g. `"cc>20,pc>8"`.
+/// * `budgets` - The maximum value of metrics by entity, e.g. `cc = 20` in `[budgets."com.foo.Bar"]`.
+/// * `score-weights` - The weight of each component of the quality scores, e.g. `size = 0.1`.
+/// * `custom-queries` - The queries counted as metrics, e.g. `"println=println.scm"`, relative to
+///   the file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    pub languages: Vec<String>,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub format: Option<String>,
+    pub cache: bool,
+    pub csv_delimiter: Option<String>,
+    pub csv_quote: Option<String>,
+    pub decimal_separator: Option<String>,
+    pub metrics: Vec<String>,
+    pub thresholds: BTreeMap<String, f64>,
+    pub fail_on: Option<String>,
+    pub budgets: BTreeMap<String, BTreeMap<String, f64>>,
+    pub sarif: bool,
+    pub graphml: bool,
+    pub gexf: bool,
+    pub naming: bool,
+    pub score_weights: ScoreWeights,
+    pub doc_comments: Vec<String>,
+    pub extension_languages: Vec<String>,
+    pub encoding: Option<String>,
+    pub symlinks: Option<String>,
+    pub max_depth: Option<usize>,
+    pub all_commits: bool,
+    pub provenance: bool,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub max_commits: Option<usize>,
+    pub commit_jobs: Option<usize>,
+    pub stream: bool,
+    pub skip_commit_messages: Vec<String>,
+    pub only_commit_messages: Vec<String>,
+    pub commit_authors: Vec<String>,
+    pub commit_paths: Vec<String>,
+    pub after: Option<String>,
+    pub before: Option<String>,
+    pub skip_merges: bool,
+    pub first_parent: bool,
+    pub active_code: bool,
+    pub ownership: bool,
+    pub half_life: Option<f64>,
+    pub only: Vec<String>,
+    pub min_aloc: Option<u32>,
+    pub exclude_boilerplate: bool,
+    pub min_similarity: Option<f64>,
+    pub baseline_ref: Option<String>,
+    pub diff_base: Option<String>,
+    pub file_timeout: Option<f64>,
+    pub total_timeout: Option<f64>,
+    pub max_trees: Option<usize>,
+    pub max_trees_memory: Option<usize>,
+    pub languages_dir: Option<String>,
+    pub custom_queries: Vec<String>,
+    pub markers: Vec<String>,
+    pub todos: bool,
+    pub track_entities: bool,
+    pub authors: bool,
+}
+
+impl ConfigFile {
+    /// Read a configuration file, as YAML if its extension is `.yaml` or `.yml` and TOML otherwise.
+    /// The language profiles of its `languages-dir`, relative to it, are loaded along, so that
+    /// its settings may name their languages.
+    pub fn load(file_path: &str) -> Result<ConfigFile, XStatsError> {
+        let content = read_file(file_path)?;
+        let mut config_file: ConfigFile = match Path::new(file_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+        {
+            Some("yaml" | "yml") => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
+            _ => toml::from_str(&content).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| XStatsError::Config(format!("Invalid config file {}: {}", file_path, e)))?;
+
+        let config_dir = Path::new(file_path).parent().unwrap_or(Path::new(""));
+        if let Some(languages_dir) = &config_file.languages_dir {
+            let languages_dir = config_dir.join(languages_dir).to_string_lossy().to_string();
+            load_language_profiles(&languages_dir)?;
+            config_file.languages_dir = Some(languages_dir);
+        }
+        for spec in &mut config_file.custom_queries {
+            if let Some((name, query_path)) = spec.split_once('=') {
+                let query_path = config_dir.join(query_path.trim());
+                *spec = format!("{}={}", name, query_path.to_string_lossy());
+            }
+        }
+        Self::validate(&config_file).map_err(|e| {
+            XStatsError::Config(format!("Invalid config file {}: {}", file_path, e))
+        })?;
+        Ok(config_file)
+    }
+
+    /// Look for a configuration file in the target path, or in its directory if it is a file
+    pub fn find(target_path: &str) -> Result<Option<ConfigFile>, XStatsError> {
+        let target_path = Path::new(target_path);
+        let dir = if target_path.is_file() {
+            target_path.parent().unwrap_or(Path::new("."))
+        } else {
+            target_path
+        };
+
+        for file_name in CONFIG_FILE_NAMES {
+            let file_path = dir.join(file_name);
+            if file_path.is_file() {
+                return Self::load(&file_path.to_string_lossy()).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(language) = self
+            .languages
+            .iter()
+            .find(|name| Language::from_name(name).is_none())
+        {
+            return Err(format!("unsupported language {}", language));
+        }
+
+        let metric_names = CodeMetric::get_names();
+        if let Some(metric) = self
+            .metrics
+            .iter()
+            .chain(self.thresholds.keys())
+            .chain(self.budgets.values().flat_map(|budget| budget.keys()))
+            .find(|name| !metric_names.contains(name))
+        {
+            return Err(format!("unknown metric {}", metric));
+        }
+        Ok(())
+    }
+}
@@ -1,13 +1,325 @@
+mod config_file;
+pub use config_file::{ConfigFile, CONFIG_FILE_NAMES};
+
+use crate::error::XStatsError;
+use crate::languages::{get_custom_languages, get_custom_name, get_profile};
+use crate::metrics::{CodeMetaData, CodeMetric, ScoreWeights, DEFAULT_MARKERS};
+use crate::ts::Query;
+use crate::utils::encoding::Encoding;
+use crate::utils::path_filter::PathFilter;
+use crate::utils::{read_file, TraversalOptions};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{Display, Formatter, Result};
+use std::time::Duration;
 
 #[derive(Hash, Eq, PartialEq, Clone, Debug, Copy)]
 pub enum Language {
     Java,
     Python,
+    Rust,
+    JavaScript,
+    TypeScript,
+    Tsx,
+    C,
+    Cpp,
+    /// A language described by a profile file, by the order it was loaded in
+    Custom(u16),
 }
 
 impl Display for Language {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f, "{:?}", self)
+        match self {
+            Language::Custom(idx) => write!(f, "{}", get_custom_name(*idx)),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+// Languages are saved by name, as custom languages are numbered in the order they are loaded
+impl Serialize for Language {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Language {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Language::all()
+            .into_iter()
+            .find(|language| language.to_string() == name)
+            .ok_or_else(|| D::Error::custom(format!("unknown language {}", name)))
+    }
+}
+
+impl Language {
+    /// All the supported languages, those loaded from profile files last
+    pub fn all() -> Vec<Language> {
+        let mut languages = vec![
+            Language::Java,
+            Language::Python,
+            Language::Rust,
+            Language::JavaScript,
+            Language::TypeScript,
+            Language::Tsx,
+            Language::C,
+            Language::Cpp,
+        ];
+        languages.extend(get_custom_languages());
+        languages
+    }
+
+    /// Get the language from its name, e.g. as declared by a notebook kernel
+    pub fn from_name(name: &str) -> Option<Language> {
+        let name = name.to_lowercase();
+        Language::all()
+            .into_iter()
+            .find(|language| get_profile(*language).names().contains(&name.as_str()))
+    }
+
+    /// The tree-sitter crate providing the grammar of the language
+    pub fn get_grammar_crate(&self) -> &'static str {
+        get_profile(*self).grammar_crate()
+    }
+}
+
+pub fn get_default_doc_comment_prefixes(language: &Language) -> Vec<String> {
+    get_profile(*language)
+        .doc_comment_prefixes()
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Settings shared by all the analyzed files.
+///
+/// # Fields
+///
+/// * `doc_comment_prefixes` - A `HashMap` where the key is a `Language`, and the value is the
+///   list of prefixes marking a comment of that language as a doc comment.
+/// * `languages` - The languages to analyze, files of the others are skipped.
+/// * `path_filter` - The glob patterns of the files to include and exclude.
+/// * `thresholds` - The maximum value of each metric, reported when exceeded.
+/// * `fail_on` - The maximum value of each metric, failing the run when exceeded.
+/// * `budgets` - The maximum value of metrics by entity, e.g. `cc = 20` for `com.foo.Bar`,
+///   checked over history.
+/// * `score_weights` - The weight of each component of the quality scores.
+/// * `file_timeout` - How long a file may take to analyze before it is abandoned, if limited.
+/// * `custom_queries` - The tree-sitter queries whose matches are counted as metrics, by name.
+/// * `markers` - The words flagging a comment as unfinished work, e.g. `TODO`.
+/// * `threads` - The number of files analyzed at the same time, one per CPU core if unset.
+/// * `extension_languages` - The language of the files of each extension, taking precedence
+///   over the built-in extensions, e.g. `.pyw` for Python.
+/// * `encoding` - The encoding of the files that are not valid UTF-8.
+/// * `traversal` - How the symbolic links and nested directories of the target are traversed.
+#[derive(Clone)]
+pub struct AnalysisConfig {
+    pub doc_comment_prefixes: HashMap<Language, Vec<String>>,
+    pub languages: Vec<Language>,
+    pub path_filter: PathFilter,
+    pub thresholds: BTreeMap<String, f64>,
+    pub fail_on: BTreeMap<String, f64>,
+    pub budgets: BTreeMap<String, BTreeMap<String, f64>>,
+    pub score_weights: ScoreWeights,
+    pub file_timeout: Option<Duration>,
+    pub custom_queries: BTreeMap<String, CustomQuery>,
+    pub markers: Vec<String>,
+    pub threads: Option<usize>,
+    pub extension_languages: BTreeMap<String, Language>,
+    pub encoding: Encoding,
+    pub traversal: TraversalOptions,
+}
+
+/// A tree-sitter query whose matches in an entity are counted as a metric of its own, e.g. the
+/// `System.out.println` calls of each method
+#[derive(Clone, Serialize)]
+pub struct CustomQuery {
+    pub query: String,
+    /// The languages whose grammar the query is valid for, it is not counted for the others
+    pub languages: Vec<Language>,
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnalysisConfig {
+    pub fn new() -> Self {
+        let doc_comment_prefixes = Language::all()
+            .into_iter()
+            .map(|language| (language, get_default_doc_comment_prefixes(&language)))
+            .collect();
+
+        Self {
+            doc_comment_prefixes,
+            languages: Language::all(),
+            path_filter: PathFilter::new(),
+            thresholds: BTreeMap::new(),
+            fail_on: BTreeMap::new(),
+            budgets: BTreeMap::new(),
+            score_weights: ScoreWeights::default(),
+            file_timeout: None,
+            custom_queries: BTreeMap::new(),
+            markers: DEFAULT_MARKERS.iter().map(|s| s.to_string()).collect(),
+            threads: None,
+            extension_languages: BTreeMap::new(),
+            encoding: Encoding::default(),
+            traversal: TraversalOptions::default(),
+        }
+    }
+
+    /// Analyze the files of an extension as a language from a `<extension>=<language>`
+    /// specification, e.g. `.pyw=python`
+    pub fn add_extension_language(&mut self, spec: &str) -> std::result::Result<(), XStatsError> {
+        let (extension, language_name) = spec
+            .split_once('=')
+            .filter(|(extension, _)| !extension.trim().trim_start_matches('.').is_empty())
+            .ok_or_else(|| {
+                XStatsError::Config(format!("Invalid extension specification: {}", spec))
+            })?;
+        let language = Language::from_name(language_name.trim()).ok_or_else(|| {
+            XStatsError::Config(format!("Unsupported language: {}", language_name))
+        })?;
+        let extension = format!(".{}", extension.trim().trim_start_matches('.'));
+        self.extension_languages.insert(extension, language);
+        Ok(())
+    }
+
+    /// Restrict the analysis to the named languages, or all of them if there are none
+    pub fn set_languages(&mut self, names: &[String]) -> std::result::Result<(), XStatsError> {
+        if names.is_empty() {
+            self.languages = Language::all();
+            return Ok(());
+        }
+
+        let mut languages = Vec::new();
+        for name in names {
+            let language = Language::from_name(name.trim())
+                .ok_or_else(|| XStatsError::Config(format!("Unsupported language: {}", name)))?;
+            if !languages.contains(&language) {
+                languages.push(language);
+            }
+        }
+        self.languages = languages;
+        Ok(())
+    }
+
+    pub fn is_language_enabled(&self, language: &Language) -> bool {
+        self.languages.contains(language)
+    }
+
+    /// Add a doc comment prefix from a `<language>=<prefix>` specification, e.g. `java=///`
+    pub fn add_doc_comment_prefix(&mut self, spec: &str) -> std::result::Result<(), XStatsError> {
+        let (language_name, prefix) = spec.split_once('=').ok_or_else(|| {
+            XStatsError::Config(format!("Invalid doc comment specification: {}", spec))
+        })?;
+        let language = Language::from_name(language_name.trim()).ok_or_else(|| {
+            XStatsError::Config(format!("Unsupported language: {}", language_name))
+        })?;
+        if prefix.is_empty() {
+            return Err(XStatsError::Config(format!(
+                "Empty doc comment prefix for {}",
+                language
+            )));
+        }
+
+        let prefixes = self.doc_comment_prefixes.entry(language).or_default();
+        if !prefixes.iter().any(|p| p == prefix) {
+            prefixes.push(prefix.to_string());
+        }
+        Ok(())
+    }
+
+    /// Set the markers of the comments flagging unfinished work, or the default ones if there are
+    /// none
+    pub fn set_markers(&mut self, markers: &[String]) -> std::result::Result<(), XStatsError> {
+        if markers.is_empty() {
+            self.markers = DEFAULT_MARKERS.iter().map(|s| s.to_string()).collect();
+            return Ok(());
+        }
+        if let Some(marker) = markers
+            .iter()
+            .find(|marker| marker.is_empty() || marker.contains(char::is_whitespace))
+        {
+            return Err(XStatsError::Config(format!("Invalid marker: {:?}", marker)));
+        }
+        self.markers = markers.to_vec();
+        Ok(())
+    }
+
+    /// Add a custom query from a `<name>=<query file>` specification, e.g.
+    /// `println=queries/println.scm`. The query must be valid for the grammar of at least one
+    /// language, its matches are counted in the files of those languages.
+    pub fn add_custom_query(&mut self, spec: &str) -> std::result::Result<(), XStatsError> {
+        let (name, file_path) = spec.split_once('=').ok_or_else(|| {
+            XStatsError::Config(format!("Invalid custom query specification: {}", spec))
+        })?;
+        let name = name.trim();
+        let is_taken = CodeMetaData::get_names()
+            .into_iter()
+            .chain(CodeMetric::get_names())
+            .any(|taken| taken == name);
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') || is_taken {
+            return Err(XStatsError::Config(format!(
+                "Invalid custom query name: {}",
+                name
+            )));
+        }
+
+        let query = read_file(file_path.trim())?;
+        let mut query_error = None;
+        let languages: Vec<Language> = Language::all()
+            .into_iter()
+            .filter(
+                |language| match Query::new(&get_profile(*language).grammar(), &query) {
+                    Ok(_) => true,
+                    Err(e) => {
+                        query_error.get_or_insert(e);
+                        false
+                    }
+                },
+            )
+            .collect();
+        if languages.is_empty() {
+            return Err(XStatsError::Config(format!(
+                "Invalid custom query {}: {}",
+                name,
+                query_error.map(|e| e.to_string()).unwrap_or_default()
+            )));
+        }
+        self.custom_queries
+            .insert(name.to_string(), CustomQuery { query, languages });
+        Ok(())
+    }
+
+    /// Set the metrics failing the run from comma-separated `<metric>><value>` expressions, e.g.
+    /// `cc>20,pc>8`
+    pub fn set_fail_on(&mut self, expressions: &str) -> std::result::Result<(), XStatsError> {
+        let metric_names = CodeMetric::get_names();
+        let mut fail_on = BTreeMap::new();
+        for expression in expressions.split(',').filter(|e| !e.trim().is_empty()) {
+            let invalid =
+                || XStatsError::Config(format!("Invalid --fail-on expression: {}", expression));
+            let (metric, value) = expression.split_once('>').ok_or_else(invalid)?;
+            let metric = metric.trim();
+            let value: f64 = value.trim().parse().map_err(|_| invalid())?;
+            if !metric_names.iter().any(|name| name == metric) {
+                return Err(XStatsError::Config(format!("Unknown metric: {}", metric)));
+            }
+            fail_on.insert(metric.to_string(), value);
+        }
+        self.fail_on = fail_on;
+        Ok(())
+    }
+
+    /// Check whether the text of a comment node is a doc comment of the language
+    pub fn is_doc_comment(&self, language: &Language, comment_text: &str) -> bool {
+        self.doc_comment_prefixes
+            .get(language)
+            .is_some_and(|prefixes| prefixes.iter().any(|p| comment_text.starts_with(p)))
     }
 }
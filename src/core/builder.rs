@@ -0,0 +1,153 @@
+use crate::core::{Progress, ProgressEvent, XStats};
+use crate::error::XStatsError;
+use crate::metrics::{CodeMetric, MetricsFilter};
+use crate::output::OutputSink;
+use crate::utils::path_filter::PathFilter;
+use crate::utils::version_control::RevwalkConfig;
+
+/// Sets up an analysis for tools embedding xStats, e.g. a CI bot, instead of the command line.
+///
+/// The results are returned by `XStats::analyze` and `XStats::analyze_history` as typed structs,
+/// and only saved to the sinks added here, if any:
+///
+/// ```no_run
+/// use xstats::core::XStatsBuilder;
+///
+/// let mut xstats = XStatsBuilder::new("path/to/repo")
+///     .languages(&["java".to_string()])
+///     .exclude(&["**/test/**".to_string()])
+///     .metrics(&["cc".to_string(), "aloc".to_string()])
+///     .threads(4)
+///     .build()
+///     .unwrap();
+/// let results = xstats.analyze().unwrap();
+/// println!("{} entities", results.metric_blocks.len());
+/// ```
+pub struct XStatsBuilder {
+    target_path: String,
+    output_path: String,
+    languages: Vec<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    metrics: Vec<String>,
+    threads: Option<usize>,
+    commit_jobs: usize,
+    revwalk_config: RevwalkConfig,
+    sinks: Vec<Box<dyn OutputSink>>,
+    progress: Progress,
+}
+
+impl XStatsBuilder {
+    /// An analysis of the target with the defaults of the command line, without saving anything
+    pub fn new(target_path: &str) -> Self {
+        XStatsBuilder {
+            target_path: target_path.to_string(),
+            output_path: String::new(),
+            languages: Vec::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            metrics: Vec::new(),
+            threads: None,
+            commit_jobs: 1,
+            revwalk_config: RevwalkConfig::default(),
+            sinks: Vec::new(),
+            progress: Progress::new(),
+        }
+    }
+
+    /// The output path of the sinks returned by `XStats::get_sink`
+    pub fn output_path(mut self, output_path: &str) -> Self {
+        self.output_path = output_path.to_string();
+        self
+    }
+
+    /// Only analyze the named languages, e.g. `java`, instead of all of them
+    pub fn languages(mut self, languages: &[String]) -> Self {
+        self.languages = languages.to_vec();
+        self
+    }
+
+    /// Only analyze the files matching one of the glob patterns, relative to the target
+    pub fn include(mut self, patterns: &[String]) -> Self {
+        self.include = patterns.to_vec();
+        self
+    }
+
+    /// Leave out the files matching one of the glob patterns, relative to the target
+    pub fn exclude(mut self, patterns: &[String]) -> Self {
+        self.exclude = patterns.to_vec();
+        self
+    }
+
+    /// Only save the named metric columns, e.g. `cc`, instead of all of them
+    pub fn metrics(mut self, metrics: &[String]) -> Self {
+        self.metrics = metrics.to_vec();
+        self
+    }
+
+    /// Analyze this many files at the same time instead of one per CPU core
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Analyze this many commits at the same time in history mode, on threads of their own
+    pub fn commit_jobs(mut self, commit_jobs: usize) -> Self {
+        self.commit_jobs = commit_jobs;
+        self
+    }
+
+    /// The range and the filters of the commits analyzed in history mode
+    pub fn revwalk_config(mut self, revwalk_config: RevwalkConfig) -> Self {
+        self.revwalk_config = revwalk_config;
+        self
+    }
+
+    /// Also save the results to the sink, which is finished once they are saved
+    pub fn sink(mut self, sink: Box<dyn OutputSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Call the listener on every step of the analysis, e.g. to show its progress
+    pub fn on_progress(
+        mut self,
+        listener: impl Fn(&ProgressEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress.add_listener(listener);
+        self
+    }
+
+    /// Check the options and set up the analysis
+    pub fn build(self) -> Result<XStats, XStatsError> {
+        let metric_names = CodeMetric::get_names();
+        if let Some(metric) = self
+            .metrics
+            .iter()
+            .find(|metric| !metric_names.contains(metric))
+        {
+            return Err(XStatsError::Config(format!("Unknown metric: {}", metric)));
+        }
+        if self.threads == Some(0) || self.commit_jobs == 0 {
+            return Err(XStatsError::Config(
+                "The number of threads and commit jobs must be at least 1".to_string(),
+            ));
+        }
+
+        let mut xstats = XStats::new(self.target_path, self.output_path);
+        xstats.config.set_languages(&self.languages)?;
+        xstats.config.path_filter = PathFilter::from_patterns(&self.include, &self.exclude)?;
+        xstats.config.threads = self.threads;
+        xstats.metrics_map.filter = MetricsFilter {
+            metrics: self.metrics,
+            ..MetricsFilter::new()
+        };
+        xstats.commit_jobs = self.commit_jobs;
+        xstats.revwalk_config = self.revwalk_config;
+        xstats.progress = self.progress;
+        for sink in self.sinks {
+            xstats.add_sink(sink);
+        }
+        Ok(xstats)
+    }
+}
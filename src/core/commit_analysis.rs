@@ -0,0 +1,465 @@
+use crate::config::{AnalysisConfig, Language};
+use crate::core::file_analysis::{
+    analyze_file, analyze_files_in_parallel, get_analyzed_extensions,
+};
+use crate::core::progress::Progress;
+use crate::error::XStatsError;
+use crate::extract::get_extractor;
+use crate::metrics::{ChangeType, CodeChanges, CodeMetrics, CommitInfo, FileChurn};
+use crate::ts::{InputEdit, Point, TSParsers, TSTreesBin, Tree};
+use crate::utils::get_file_extension;
+use crate::utils::logger::WarningKind;
+use crate::utils::version_control::{
+    get_author, get_changed_lines, get_hunks, get_line_stats, open_repo, Commit, Delta, Diff,
+    DiffFindOptions, DiffOptions, Oid, Repository,
+};
+use log::warn;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::Instant;
+
+/// The results of a commit, computed apart from the rest of the analysis so that commits can be
+/// analyzed in parallel and merged afterwards in the order of the history.
+pub struct AnalyzedCommit {
+    pub commit_info: CommitInfo,
+    /// The first parent of the commit, which the changes are compared to, if any
+    pub parent_id: Option<String>,
+    /// The metrics of the files added or modified by the commit
+    pub metrics: CodeMetrics,
+    pub changes: CodeChanges,
+    pub file_churns: Vec<FileChurn>,
+    /// The path, language, tree and source code of the files analyzed, for the graphs
+    pub trees: Vec<(String, Language, Tree, String)>,
+    /// The files abandoned on timeout
+    pub warnings: Vec<String>,
+    /// Why the commit was only analyzed in part, if it was
+    pub error: Option<XStatsError>,
+}
+
+/// Analyze the files changed by a commit, compared to its first parent.
+///
+/// The files whose tree in the trees bin was parsed from their version before the commit are
+/// parsed incrementally, and their new trees are kept in the bin for the next commits. The files
+/// are analyzed in parallel, reporting their progress, if `progress` is given, and one after the
+/// other with `parsers` otherwise, e.g. when the commits are themselves analyzed in parallel.
+pub fn analyze_commit(
+    repo: &Repository,
+    commit: &Commit,
+    config: &AnalysisConfig,
+    parsers: &mut TSParsers,
+    trees_bin: &mut TSTreesBin,
+    progress: Option<&Progress>,
+) -> AnalyzedCommit {
+    let mut analyzed_commit = AnalyzedCommit {
+        commit_info: CommitInfo::new(
+            commit.id().to_string(),
+            get_author(&commit.author()),
+            commit.time().seconds(),
+            commit.message().unwrap_or_default(),
+        ),
+        parent_id: commit
+            .parent_id(0)
+            .ok()
+            .map(|parent_id| parent_id.to_string()),
+        metrics: CodeMetrics::new(),
+        changes: CodeChanges::new(),
+        file_churns: Vec::new(),
+        trees: Vec::new(),
+        warnings: Vec::new(),
+        error: None,
+    };
+    if let Err(e) = process_tree(
+        repo,
+        commit,
+        config,
+        parsers,
+        trees_bin,
+        progress,
+        &mut analyzed_commit,
+    ) {
+        analyzed_commit.error = Some(e);
+    }
+    analyzed_commit
+}
+
+// Process each file changed by a commit, compared to its first parent
+fn process_tree(
+    repo: &Repository,
+    commit: &Commit,
+    config: &AnalysisConfig,
+    parsers: &mut TSParsers,
+    trees_bin: &mut TSTreesBin,
+    progress: Option<&Progress>,
+    analyzed_commit: &mut AnalyzedCommit,
+) -> Result<(), XStatsError> {
+    let supported_extensions = get_analyzed_extensions(parsers, config);
+
+    // Only the changed lines are needed to map hunks onto entities
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.context_lines(0);
+    let tree = commit.tree()?;
+    let parent = if commit.parent_count() > 0 {
+        Some(commit.parent(0)?.tree()?)
+    } else {
+        None
+    };
+    let mut diff = repo.diff_tree_to_tree(parent.as_ref(), Some(&tree), Some(&mut diff_opts))?;
+    // Renamed files keep their tree and the ids of their entities, instead of being deleted and
+    // added again
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    let is_analyzed = |path: &str| {
+        supported_extensions.contains(&get_file_extension(path)) && config.path_filter.matches(path)
+    };
+
+    // Collect the content of the added and modified files, to analyze them in parallel
+    let mut changed_files = Vec::new();
+    let mut old_trees = HashMap::new();
+    let mut delta_changes = Vec::new();
+    for (idx, delta) in diff.deltas().enumerate() {
+        let old_path = delta
+            .old_file()
+            .path()
+            .map(|path| path.to_string_lossy().to_string());
+        let new_path = delta
+            .new_file()
+            .path()
+            .map(|path| path.to_string_lossy().to_string());
+        // A file renamed from or to a path left out is only added or deleted
+        let status = match delta.status() {
+            Delta::Renamed if !old_path.as_deref().is_some_and(is_analyzed) => Delta::Added,
+            Delta::Renamed if !new_path.as_deref().is_some_and(is_analyzed) => Delta::Deleted,
+            status => status,
+        };
+        let path = match status {
+            Delta::Deleted => old_path.clone(),
+            _ => new_path,
+        };
+        let path = match path {
+            Some(path) => path,
+            None => continue,
+        };
+        if !is_analyzed(&path) {
+            continue;
+        }
+
+        match status {
+            Delta::Added | Delta::Modified | Delta::Renamed => {
+                let (change_type, old_path) = match status {
+                    Delta::Added => (ChangeType::Added, None),
+                    Delta::Modified => (ChangeType::Modified, None),
+                    _ => (ChangeType::Renamed, old_path),
+                };
+                if let Some(old_path) = &old_path {
+                    trees_bin.rename_tree(old_path, &path);
+                }
+                // Retrieve the file content for added or modified files
+                let blob_id = delta.new_file().id();
+                if let Ok(blob) = repo.find_blob(blob_id) {
+                    // The hunks are byte ranges of the blobs, only valid for UTF-8 content
+                    let is_utf8 = std::str::from_utf8(blob.content()).is_ok();
+                    let content = config
+                        .encoding
+                        .decode_source(&path, blob.content().to_vec());
+                    // The tree of the previous version, edited along the hunks, is only parsed
+                    // again where they changed it
+                    if is_utf8 {
+                        if let Some(old_tree) =
+                            get_edited_tree(repo, trees_bin, &diff, idx, &path, &content)?
+                        {
+                            old_trees.insert(path.clone(), old_tree);
+                        }
+                    }
+                    delta_changes.push((idx, path.clone(), old_path, Some((change_type, blob_id))));
+                    changed_files.push((path, content));
+                } else {
+                    warn!(
+                        target: WarningKind::Git.target(),
+                        "Failed to find blob for file: {}",
+                        path
+                    );
+                }
+            }
+            Delta::Deleted => delta_changes.push((idx, path, None, None)),
+            _ => {}
+        }
+    }
+
+    let analyzed_files = match progress {
+        Some(progress) => analyze_files_in_parallel(config, &changed_files, &old_trees, progress),
+        None => changed_files
+            .into_iter()
+            .map(|(file, content)| {
+                // A tree of another version than the previous one would be reused as it is
+                match old_trees.remove(&file) {
+                    Some(old_tree) => trees_bin.insert_tree(&file, old_tree),
+                    None => trees_bin.delete_tree(&file),
+                }
+                analyze_file(parsers, trees_bin, config, &file, Some(content))
+            })
+            .collect(),
+    };
+    let mut analyzed_files = analyzed_files.into_iter();
+
+    // Merge the results in the order of the deltas, so that the outputs are deterministic
+    let code_metrics = &mut analyzed_commit.metrics;
+    for (idx, path, old_path, change_type) in delta_changes {
+        let (lines_added, lines_deleted, hunks_changed) = get_line_stats(&diff, idx)?;
+        analyzed_commit.file_churns.push(FileChurn {
+            commit_id: commit.id().to_string(),
+            file_path: path.clone(),
+            change_type: change_type.map_or(ChangeType::Deleted, |(change_type, _)| change_type),
+            lines_added,
+            lines_deleted,
+            hunks_changed,
+            old_file_path: old_path,
+        });
+
+        match change_type {
+            Some((change_type, blob_id)) => {
+                let analyzed_file = match analyzed_files.next() {
+                    Some(Err(e @ XStatsError::Timeout(_))) => {
+                        trees_bin.delete_tree(&path);
+                        analyzed_commit
+                            .warnings
+                            .push(format!("{} at commit {}", e, commit.id()));
+                        continue;
+                    }
+                    Some(analyzed_file) => analyzed_file?,
+                    None => break,
+                };
+                let first_block = code_metrics.metric_blocks.len();
+                code_metrics
+                    .metric_blocks
+                    .extend(analyzed_file.metrics.metric_blocks);
+                for (file, language, tree, source_code) in analyzed_file.trees {
+                    // Keep the tree of the file for the next commits to parse it incrementally
+                    let version = (file == path).then(|| blob_id.to_string());
+                    trees_bin.insert_tree_version(&file, tree.clone(), version);
+                    analyzed_commit
+                        .trees
+                        .push((file, language, tree, source_code));
+                }
+
+                // Map the changed lines onto the entities of the file, those of a renamed file
+                // being modified along
+                let changed_lines = get_changed_lines(&diff, idx)?;
+                let change_type = match change_type {
+                    ChangeType::Renamed => ChangeType::Modified,
+                    change_type => change_type,
+                };
+                analyzed_commit.changes.map_changed_lines(
+                    &code_metrics.metric_blocks[first_block..],
+                    &changed_lines,
+                    change_type,
+                );
+            }
+            None => {
+                trees_bin.delete_tree(&path);
+                analyzed_commit.changes.add_deleted_file(&path);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Get the analyzed files changed between two commits, or all those of the new commit without an
+/// old one, with the content of those added or modified, and none for those deleted.
+pub fn get_files_between(
+    repo: &Repository,
+    old_commit: Option<&Commit>,
+    new_commit: &Commit,
+    config: &AnalysisConfig,
+    supported_extensions: &[String],
+) -> Result<Vec<(String, Option<String>)>, XStatsError> {
+    let old_tree = old_commit.map(|commit| commit.tree()).transpose()?;
+    let diff = repo.diff_tree_to_tree(old_tree.as_ref(), Some(&new_commit.tree()?), None)?;
+
+    let mut files = Vec::new();
+    for delta in diff.deltas() {
+        let file = match delta.status() {
+            Delta::Deleted => delta.old_file(),
+            _ => delta.new_file(),
+        };
+        let Some(path) = file.path().map(|path| path.to_string_lossy().to_string()) else {
+            continue;
+        };
+        if !supported_extensions.contains(&get_file_extension(&path))
+            || !config.path_filter.matches(&path)
+        {
+            continue;
+        }
+
+        match delta.status() {
+            Delta::Added | Delta::Modified => {
+                let content = repo.find_blob(file.id())?.content().to_vec();
+                let content = config.encoding.decode_source(&path, content);
+                files.push((path, Some(content)));
+            }
+            Delta::Deleted => files.push((path, None)),
+            _ => {}
+        }
+    }
+    Ok(files)
+}
+
+/// Analyze commits on a pool of threads, each with its own repository handle, parsers and trees
+/// bin sharing the budget of `trees_budget`, and hand the results to `merge` in the order of the
+/// commits, whatever the order they were analyzed in.
+///
+/// No more commits are analyzed past the deadline, if any, or once `merge` fails. Returns the
+/// number of commits analyzed, the first ones.
+pub fn analyze_commits_in_parallel(
+    repo_path: &str,
+    commit_ids: &[Oid],
+    config: &AnalysisConfig,
+    jobs: usize,
+    trees_budget: (Option<usize>, Option<usize>),
+    deadline: Option<Instant>,
+    mut merge: impl FnMut(AnalyzedCommit) -> Result<(), XStatsError>,
+) -> Result<usize, XStatsError> {
+    let worker_count = jobs.min(commit_ids.len()).max(1);
+    let repos = (0..worker_count)
+        .map(|_| open_repo(repo_path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (max_trees, max_bytes) = trees_budget;
+    let next_commit = AtomicUsize::new(0);
+
+    let (sender, receiver) = mpsc::channel();
+    let mut merged_count = 0;
+    let mut merge_result = Ok(());
+    std::thread::scope(|scope| {
+        for repo in repos {
+            let sender = sender.clone();
+            let next_commit = &next_commit;
+            scope.spawn(move || {
+                let mut parsers = TSParsers::new();
+                let mut trees_bin = TSTreesBin::new();
+                trees_bin.set_budget(
+                    max_trees.map(|max_trees| max_trees / worker_count),
+                    max_bytes.map(|max_bytes| max_bytes / worker_count),
+                );
+                loop {
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        break;
+                    }
+                    let i = next_commit.fetch_add(1, Ordering::Relaxed);
+                    let Some(oid) = commit_ids.get(i) else {
+                        break;
+                    };
+                    let analyzed_commit = repo.find_commit(*oid).ok().map(|commit| {
+                        analyze_commit(&repo, &commit, config, &mut parsers, &mut trees_bin, None)
+                    });
+                    if sender.send((i, analyzed_commit)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        // The commits are taken in order, so the ones analyzed are the first ones
+        // Dropping the receiver on failure stops the workers
+        let mut pending = BTreeMap::new();
+        'receive: for (i, analyzed_commit) in receiver {
+            pending.insert(i, analyzed_commit);
+            while let Some(analyzed_commit) = pending.remove(&merged_count) {
+                if let Some(analyzed_commit) = analyzed_commit {
+                    merge_result = merge(analyzed_commit);
+                    if merge_result.is_err() {
+                        break 'receive;
+                    }
+                }
+                merged_count += 1;
+            }
+        }
+    });
+    merge_result.map(|()| merged_count)
+}
+
+/// The tree of the version of a file before the delta at `idx`, edited along its hunks to match
+/// `new_content`, if it is in the trees bin
+fn get_edited_tree(
+    repo: &Repository,
+    trees_bin: &mut TSTreesBin,
+    diff: &Diff,
+    idx: usize,
+    path: &str,
+    new_content: &str,
+) -> Result<Option<Tree>, XStatsError> {
+    let Some(old_blob_id) = diff.get_delta(idx).map(|delta| delta.old_file().id()) else {
+        return Ok(None);
+    };
+    // The trees of host files are those of their sub-documents, parsed whole
+    if get_extractor(path).is_some()
+        || trees_bin.get_version(path) != Some(old_blob_id.to_string().as_str())
+    {
+        return Ok(None);
+    }
+    let Some(old_tree) = trees_bin.get_tree(path) else {
+        return Ok(None);
+    };
+    let mut tree = old_tree.clone();
+    let old_blob = repo.find_blob(old_blob_id)?;
+    let Ok(old_content) = std::str::from_utf8(old_blob.content()) else {
+        return Ok(None);
+    };
+
+    for edit in get_hunk_edits(old_content, new_content, &get_hunks(diff, idx)?) {
+        tree.edit(&edit);
+    }
+    Ok(Some(tree))
+}
+
+/// The edits turning the old content of a file into the new one, one per hunk of their diff, each
+/// positioned in the content as edited by the hunks before it
+fn get_hunk_edits(
+    old_content: &str,
+    new_content: &str,
+    hunks: &[(u32, u32, u32, u32)],
+) -> Vec<InputEdit> {
+    let old_lines = get_line_offsets(old_content);
+    let new_lines = get_line_offsets(new_content);
+    let offset =
+        |lines: &[usize], row: usize| lines.get(row).copied().unwrap_or(lines[lines.len() - 1]);
+
+    let mut edits = Vec::new();
+    for &(old_start, old_count, new_start, new_count) in hunks {
+        let (old_count, new_count) = (old_count as usize, new_count as usize);
+        // The 0-based rows of the first line of the hunk, or of the line following an insertion
+        // or a deletion
+        let old_row = if old_count == 0 {
+            old_start
+        } else {
+            old_start - 1
+        } as usize;
+        let new_row = if new_count == 0 {
+            new_start
+        } else {
+            new_start - 1
+        } as usize;
+
+        // The content before the hunk is already the new one
+        let start_byte = offset(&new_lines, new_row);
+        let old_length = offset(&old_lines, old_row + old_count) - offset(&old_lines, old_row);
+        edits.push(InputEdit {
+            start_byte,
+            old_end_byte: start_byte + old_length,
+            new_end_byte: offset(&new_lines, new_row + new_count),
+            start_position: Point::new(new_row, 0),
+            old_end_position: Point::new(new_row + old_count, 0),
+            new_end_position: Point::new(new_row + new_count, 0),
+        });
+    }
+    edits
+}
+
+/// The byte offset of the start of each line of a content, followed by its length
+fn get_line_offsets(content: &str) -> Vec<usize> {
+    let mut offsets = vec![0];
+    offsets.extend(content.match_indices('\n').map(|(i, _)| i + 1));
+    if offsets.last() != Some(&content.len()) {
+        offsets.push(content.len());
+    }
+    offsets
+}
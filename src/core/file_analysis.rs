@@ -0,0 +1,271 @@
+use crate::config::{AnalysisConfig, Language};
+use crate::core::progress::{Progress, ProgressEvent};
+use crate::error::XStatsError;
+use crate::extract::{detect_extractor, get_all_host_extensions, get_extractor};
+use crate::metrics::CodeMetrics;
+use crate::ts::{TSParsers, TSTreesBin, Tree};
+use crate::utils::logger::WarningKind;
+use crate::utils::{get_file_extension, get_file_name, read_file_head};
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// The number of bytes read from the files of an unknown extension to recognize their content
+const HEAD_SIZE: usize = 1024;
+
+/// The metrics and trees of a file, computed apart from the rest of the analysis so that files
+/// can be analyzed in parallel and added to the graphs afterwards.
+pub struct AnalyzedFile {
+    pub metrics: CodeMetrics,
+    /// The path, language, tree and source code of the file, or of each of its sub-documents
+    pub trees: Vec<(String, Language, Tree, String)>,
+}
+
+/// Parse a file and compute its metrics, through the code embedded in it for host files.
+///
+/// Fails with `XStatsError::Timeout` if it takes longer than the file timeout of the config.
+pub fn analyze_file(
+    parsers: &mut TSParsers,
+    trees_bin: &mut TSTreesBin,
+    config: &AnalysisConfig,
+    file: &str,
+    content: Option<String>,
+) -> Result<AnalyzedFile, XStatsError> {
+    analyze_file_cached(parsers, trees_bin, config, file, content, None)
+}
+
+/// Same as `analyze_file`, reusing the metrics computed by a previous run from the same content
+/// if given, e.g. by the file cache, in which case the file is only parsed for the graphs
+pub fn analyze_file_cached(
+    parsers: &mut TSParsers,
+    trees_bin: &mut TSTreesBin,
+    config: &AnalysisConfig,
+    file: &str,
+    content: Option<String>,
+    cached_metrics: Option<CodeMetrics>,
+) -> Result<AnalyzedFile, XStatsError> {
+    let started = Instant::now();
+    parsers.set_timeout(config.file_timeout);
+    parsers.set_extension_languages(&config.extension_languages);
+    let analyzed_file =
+        analyze_file_untimed(parsers, trees_bin, config, file, content, cached_metrics)?;
+    match config.file_timeout {
+        Some(timeout) if started.elapsed() > timeout => Err(XStatsError::Timeout(file.to_string())),
+        _ => Ok(analyzed_file),
+    }
+}
+
+/// Same as `analyze_file_cached`, where parsing only fails once it is abandoned on timeout
+fn analyze_file_untimed(
+    parsers: &mut TSParsers,
+    trees_bin: &mut TSTreesBin,
+    config: &AnalysisConfig,
+    file: &str,
+    content: Option<String>,
+    cached_metrics: Option<CodeMetrics>,
+) -> Result<AnalyzedFile, XStatsError> {
+    let is_cached = cached_metrics.is_some();
+    let mut analyzed_file = AnalyzedFile {
+        metrics: cached_metrics.unwrap_or_default(),
+        trees: Vec::new(),
+    };
+
+    // Files of an unknown extension are recognized from their first lines, e.g. a shebang
+    let mut extractor = get_extractor(file);
+    let mut language = parsers.get_language(file);
+    if extractor.is_none() && language.is_none() {
+        let head = match &content {
+            Some(content) => Some(content.chars().take(HEAD_SIZE).collect()),
+            None => read_file_head(file, HEAD_SIZE),
+        };
+        if let Some(head) = head {
+            extractor = detect_extractor(&head);
+            language = parsers.detect_language(file, &head);
+        }
+    }
+
+    // Host files are analyzed through the code embedded in them
+    if let Some(extractor) = extractor {
+        let source_code = match content {
+            Some(content) => content,
+            None => config.encoding.read_source(file)?,
+        };
+        for sub_document in extractor(&source_code) {
+            let language = sub_document.language;
+            if !config.is_language_enabled(&language) {
+                continue;
+            }
+            let source_code = sub_document.source_code;
+            let Some(tree) = parsers.parse_source(&language, &source_code) else {
+                return Err(XStatsError::Timeout(file.to_string()));
+            };
+            let sub_document_path = format!("{}[{}]", file, sub_document.index);
+            if tree.root_node().has_error() {
+                warn_broken_parse(&sub_document_path);
+            }
+            if is_cached {
+                analyzed_file
+                    .trees
+                    .push((sub_document_path, language, tree, source_code));
+                continue;
+            }
+            let metrics = &mut analyzed_file.metrics;
+            let first_block = metrics.metric_blocks.len();
+            // Generate metrics for the sub-document, positioned relative to the host file
+            metrics.generate_root_metrics(parsers, config, &source_code, language, file, &tree)?;
+            metrics.shift_positions(
+                first_block,
+                sub_document.row_offset,
+                sub_document.col_offset,
+            );
+            let sub_document_name = format!("{}[{}]", get_file_name(file), sub_document.index);
+            let meta_data = &mut metrics.metric_blocks[first_block].meta_data;
+            meta_data.node_name = sub_document_name.clone();
+            meta_data.set_qualified_name(sub_document_name);
+
+            // Sub-documents are kept apart from each other in the graphs and the trees bin
+            analyzed_file
+                .trees
+                .push((sub_document_path, language, tree, source_code));
+        }
+        return Ok(analyzed_file);
+    }
+
+    match language {
+        Some(language) if config.is_language_enabled(&language) => {}
+        Some(_) => return Ok(analyzed_file),
+        None => {
+            // Files without an extension nor a shebang, e.g. `LICENSE`, are not even candidates
+            if !get_file_extension(file).is_empty() {
+                debug!(
+                    target: WarningKind::UnsupportedFile.target(),
+                    "Skipped {}: unsupported extension",
+                    file
+                );
+            }
+            return Ok(analyzed_file);
+        }
+    }
+
+    let content = match content {
+        Some(content) => content,
+        None => config.encoding.read_source(file)?,
+    };
+    let Some((language, tree, source_code)) =
+        parsers.generate_tree(trees_bin, file, Some(content))?
+    else {
+        return Err(XStatsError::Timeout(file.to_string()));
+    };
+    if tree.root_node().has_error() {
+        warn_broken_parse(file);
+    }
+    // Generate metrics for the file
+    if !is_cached {
+        analyzed_file.metrics.generate_root_metrics(
+            parsers,
+            config,
+            &source_code,
+            language,
+            file,
+            &tree,
+        )?;
+    }
+    analyzed_file
+        .trees
+        .push((file.to_string(), language, tree, source_code));
+    Ok(analyzed_file)
+}
+
+/// The extensions of the files to analyze in history mode: those of the languages, including
+/// the ones set in the config, and of the host formats, along with none for the scripts
+/// recognized by their shebang
+pub fn get_analyzed_extensions(parsers: &TSParsers, config: &AnalysisConfig) -> Vec<String> {
+    let mut extensions: Vec<String> = parsers
+        .get_all_supported_extensions()
+        .into_iter()
+        .chain(get_all_host_extensions())
+        .map(|extension| extension.to_string())
+        .collect();
+    extensions.extend(config.extension_languages.keys().cloned());
+    extensions.push(String::new());
+    extensions
+}
+
+fn warn_broken_parse(file: &str) {
+    warn!(
+        target: WarningKind::BrokenParse.target(),
+        "Syntax errors in {}, its metrics may be partial",
+        file
+    );
+}
+
+/// Analyze the files, given with their content, on a pool of threads with a parser each,
+/// reporting their progress.
+///
+/// The results are in the order of the files, whatever the order they were analyzed in. The files
+/// with a tree in `old_trees`, already edited to match their content, are parsed incrementally.
+pub fn analyze_files_in_parallel(
+    config: &AnalysisConfig,
+    files: &[(String, String)],
+    old_trees: &HashMap<String, Tree>,
+    progress: &Progress,
+) -> Vec<Result<AnalyzedFile, XStatsError>> {
+    progress.emit(ProgressEvent::FilesStarted { total: files.len() });
+    let worker_count = config
+        .threads
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()))
+        .min(files.len());
+    let next_file = AtomicUsize::new(0);
+
+    let mut results: Vec<Option<Result<AnalyzedFile, XStatsError>>> =
+        files.iter().map(|_| None).collect();
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..worker_count)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut parsers = TSParsers::new();
+                    let mut trees_bin = TSTreesBin::new();
+                    let mut worker_results = Vec::new();
+                    loop {
+                        let i = next_file.fetch_add(1, Ordering::Relaxed);
+                        let Some((file, content)) = files.get(i) else {
+                            break;
+                        };
+                        progress.emit(ProgressEvent::FileStarted {
+                            file_path: file.to_string(),
+                        });
+                        if let Some(old_tree) = old_trees.get(file) {
+                            trees_bin.insert_tree(file, old_tree.clone());
+                        }
+                        let result = analyze_file(
+                            &mut parsers,
+                            &mut trees_bin,
+                            config,
+                            file,
+                            Some(content.clone()),
+                        );
+                        trees_bin.delete_tree(file);
+                        worker_results.push((i, result));
+                        progress.emit(ProgressEvent::FileDone {
+                            file_path: file.to_string(),
+                        });
+                    }
+                    worker_results
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            let worker_results = worker
+                .join()
+                .unwrap_or_else(|e| std::panic::resume_unwind(e));
+            for (i, result) in worker_results {
+                results[i] = Some(result);
+            }
+        }
+    });
+
+    progress.emit(ProgressEvent::FilesDone);
+    results.into_iter().flatten().collect()
+}
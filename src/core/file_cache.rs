@@ -0,0 +1,118 @@
+use super::manifest::ManifestConfig;
+use crate::config::AnalysisConfig;
+use crate::error::XStatsError;
+use crate::metrics::{CodeMetricBlock, CodeMetrics, MetricsFilter};
+use crate::utils::fnv1a_hash;
+use crate::utils::{read_file, write_atomically};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// The name of the cache directory, created in the target path
+pub const CACHE_DIR_NAME: &str = ".xstats-cache";
+
+/// The cache directory of a target: in the target path, or in its directory for a single file
+pub fn get_cache_dir(target_path: &str) -> String {
+    let path = Path::new(target_path);
+    let directory = match path.parent() {
+        Some(parent) if path.is_file() => parent,
+        _ => path,
+    };
+    directory.join(CACHE_DIR_NAME).to_string_lossy().to_string()
+}
+
+/// The metrics of a file as of a previous run
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    /// The version, settings and content hash the metrics were computed with
+    key: String,
+    metric_blocks: Vec<CodeMetricBlock>,
+}
+
+/// The metrics of the files analyzed by previous runs of the default mode, reused while the
+/// content of a file and the settings stay the same.
+///
+/// Every file has an entry named after the hash of its path, so that the cache does not grow as
+/// files change. The entries of the files no longer analyzed are removed by `prune`.
+pub struct FileCache {
+    cache_dir: String,
+    /// The xStats version and the settings affecting the metrics
+    settings_key: String,
+    /// The entries of the files analyzed by this run
+    used_entries: HashSet<String>,
+}
+
+impl FileCache {
+    pub fn new(cache_dir: &str, config: &AnalysisConfig, filter: &MetricsFilter) -> Self {
+        let languages: Vec<String> = config.languages.iter().map(|l| l.to_string()).collect();
+        FileCache {
+            cache_dir: cache_dir.to_string(),
+            settings_key: format!(
+                "{}:{}:{}",
+                env!("CARGO_PKG_VERSION"),
+                ManifestConfig::new(config, filter).get_hash(),
+                languages.join(",")
+            ),
+            used_entries: HashSet::new(),
+        }
+    }
+
+    /// Get the cached metrics of a file, if its content is the same as when they were cached
+    pub fn get(&mut self, file_path: &str, content: &str) -> Option<CodeMetrics> {
+        let entry_name = self.use_entry(file_path);
+        let entry: CacheEntry = read_file(&format!("{}/{}", self.cache_dir, entry_name))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())?;
+        (entry.key == self.get_key(content)).then_some(CodeMetrics {
+            metric_blocks: entry.metric_blocks,
+        })
+    }
+
+    /// Cache the metrics of a file, replacing those of its previous content
+    pub fn insert(
+        &mut self,
+        file_path: &str,
+        content: &str,
+        metrics: &CodeMetrics,
+    ) -> Result<(), XStatsError> {
+        let entry_name = self.use_entry(file_path);
+        let entry = CacheEntry {
+            key: self.get_key(content),
+            metric_blocks: metrics.metric_blocks.clone(),
+        };
+        write_atomically(
+            &format!("{}/{}", self.cache_dir, entry_name),
+            true,
+            |file| serde_json::to_writer(file, &entry),
+        )
+    }
+
+    /// Remove the entries of the files this run did not analyze, e.g. deleted files
+    pub fn prune(&self) -> Result<(), XStatsError> {
+        let Ok(entries) = fs::read_dir(&self.cache_dir) else {
+            return Ok(());
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name.ends_with(".json") && !self.used_entries.contains(&file_name) {
+                fs::remove_file(entry.path()).map_err(|e| XStatsError::write(&file_name, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn use_entry(&mut self, file_path: &str) -> String {
+        let entry_name = format!("{:016x}.json", fnv1a_hash(file_path.as_bytes()));
+        self.used_entries.insert(entry_name.clone());
+        entry_name
+    }
+
+    fn get_key(&self, content: &str) -> String {
+        format!(
+            "{}:{:016x}",
+            self.settings_key,
+            fnv1a_hash(content.as_bytes())
+        )
+    }
+}
@@ -0,0 +1,179 @@
+use crate::config::{AnalysisConfig, Language};
+use crate::metrics::{CodeMetric, MetricsFilter, ScoreWeights};
+use crate::utils::version_control::{discover_repo, is_dirty};
+use crate::utils::{fnv1a_hash, format_utc_time};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The settings affecting the metrics, in a stable order
+#[derive(Serialize)]
+pub struct ManifestConfig {
+    pub doc_comment_prefixes: BTreeMap<String, Vec<String>>,
+    pub node_types: Vec<String>,
+    pub min_aloc: u32,
+    pub exclude_boilerplate: bool,
+    pub include_patterns: Vec<String>,
+    pub exclude_patterns: Vec<String>,
+    pub thresholds: BTreeMap<String, f64>,
+    pub score_weights: ScoreWeights,
+    pub custom_queries: BTreeMap<String, String>,
+    pub markers: Vec<String>,
+}
+
+impl ManifestConfig {
+    pub fn new(config: &AnalysisConfig, filter: &MetricsFilter) -> Self {
+        ManifestConfig {
+            doc_comment_prefixes: config
+                .doc_comment_prefixes
+                .iter()
+                .map(|(language, prefixes)| (language.to_string(), prefixes.clone()))
+                .collect(),
+            node_types: filter.node_types.clone(),
+            min_aloc: filter.min_aloc,
+            exclude_boilerplate: filter.exclude_boilerplate,
+            include_patterns: config.path_filter.include_patterns.clone(),
+            exclude_patterns: config.path_filter.exclude_patterns.clone(),
+            thresholds: config.thresholds.clone(),
+            score_weights: config.score_weights.clone(),
+            custom_queries: config
+                .custom_queries
+                .iter()
+                .map(|(name, custom_query)| (name.clone(), custom_query.query.clone()))
+                .collect(),
+            markers: config.markers.clone(),
+        }
+    }
+
+    /// A hash of the settings, to tell at a glance whether two runs used the same ones
+    pub fn get_hash(&self) -> String {
+        format!(
+            "{:016x}",
+            fnv1a_hash(serde_json::to_string(self).unwrap_or_default().as_bytes())
+        )
+    }
+}
+
+/// The exact inputs of a run, saved along its outputs so that they can be reproduced later
+#[derive(Serialize)]
+pub struct RunManifest {
+    pub xstats_version: String,
+    pub target_path: String,
+    /// The HEAD commit of the repository containing the target, if any
+    pub commit: Option<String>,
+    /// Whether the repository has uncommitted or untracked changes
+    pub dirty: Option<bool>,
+    pub config: ManifestConfig,
+    /// A hash of `config`, to tell at a glance whether two runs used the same settings
+    pub config_hash: String,
+    pub languages: Vec<String>,
+    pub metrics: Vec<String>,
+    /// Resolved versions of the tree-sitter crates, e.g. `tree-sitter-java`
+    pub grammar_versions: BTreeMap<String, String>,
+}
+
+impl RunManifest {
+    pub fn new(target_path: &str, config: &AnalysisConfig, filter: &MetricsFilter) -> Self {
+        let (commit, dirty) = match discover_repo(target_path) {
+            Ok(repo) => {
+                let commit = repo
+                    .head()
+                    .and_then(|head| head.peel_to_commit())
+                    .map(|commit| commit.id().to_string())
+                    .ok();
+                (commit, is_dirty(&repo).ok())
+            }
+            Err(_) => (None, None),
+        };
+
+        let languages = config.languages.iter().map(|l| l.to_string()).collect();
+        let manifest_config = ManifestConfig::new(config, filter);
+        let config_hash = manifest_config.get_hash();
+
+        RunManifest {
+            xstats_version: env!("CARGO_PKG_VERSION").to_string(),
+            target_path: target_path.to_string(),
+            commit,
+            dirty,
+            config: manifest_config,
+            config_hash,
+            languages,
+            metrics: if filter.metrics.is_empty() {
+                CodeMetric::get_names()
+            } else {
+                filter.metrics.clone()
+            },
+            grammar_versions: get_grammar_versions(),
+        }
+    }
+}
+
+/// The columns added to every row of the history tables with `--provenance`, so that the
+/// outputs of many runs stay self-describing once concatenated
+pub struct Provenance {
+    /// When the run started, as an RFC 3339 UTC timestamp
+    pub analyzed_at: String,
+    pub xstats_version: String,
+    pub grammar_versions: BTreeMap<String, String>,
+}
+
+impl Default for Provenance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Provenance {
+    /// The provenance of a run starting now
+    pub fn new() -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or_default();
+        Provenance {
+            analyzed_at: format_utc_time(now),
+            xstats_version: env!("CARGO_PKG_VERSION").to_string(),
+            grammar_versions: get_grammar_versions(),
+        }
+    }
+
+    pub fn get_names() -> Vec<String> {
+        [
+            "commit_sha",
+            "analyzed_at",
+            "xstats_version",
+            "grammar_version",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+    }
+
+    /// The values of a row of the given commit, the grammar version being empty for the rows
+    /// of no language
+    pub fn get_values(&self, commit_id: &str, language: Option<Language>) -> Vec<String> {
+        let grammar_version = language
+            .and_then(|language| {
+                let grammar_crate = language.get_grammar_crate();
+                self.grammar_versions
+                    .get(grammar_crate)
+                    .map(|version| format!("{} {}", grammar_crate, version))
+            })
+            .unwrap_or_default();
+        vec![
+            commit_id.to_string(),
+            self.analyzed_at.clone(),
+            self.xstats_version.clone(),
+            grammar_version,
+        ]
+    }
+}
+
+/// Resolved versions of the tree-sitter crates, by crate name
+pub(super) fn get_grammar_versions() -> BTreeMap<String, String> {
+    env!("XSTATS_GRAMMAR_VERSIONS")
+        .split(',')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(name, version)| (name.to_string(), version.to_string()))
+        .collect()
+}
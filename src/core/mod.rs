@@ -1,19 +1,121 @@
-use crate::graph::TypeDependencyGraph;
-use crate::metrics::{CodeMetrics, CodeMetricsMap};
-use crate::ts::{TSParsers, TSTreesBin};
-use crate::utils::progress_bar::CustomProgressBar;
+mod builder;
+mod commit_analysis;
+mod file_analysis;
+mod file_cache;
+mod manifest;
+mod portfolio;
+mod progress;
+mod results;
+mod state;
+mod target_clone;
+pub use builder::XStatsBuilder;
+use commit_analysis::{
+    analyze_commit, analyze_commits_in_parallel, get_files_between, AnalyzedCommit,
+};
+use file_analysis::{
+    analyze_file, analyze_file_cached, analyze_files_in_parallel, get_analyzed_extensions,
+    AnalyzedFile,
+};
+pub use file_cache::{get_cache_dir, FileCache, CACHE_DIR_NAME};
+use manifest::get_grammar_versions;
+pub use manifest::{ManifestConfig, Provenance, RunManifest};
+pub use portfolio::{Portfolio, PortfolioManifest, PortfolioRepo, RepoSummary};
+pub use progress::{Progress, ProgressEvent};
+pub use results::{AnalysisResults, HistoryResults};
+pub use state::AnalysisState;
+pub use target_clone::TargetClone;
+
+use crate::analysis::{Authors, Hotspots};
+use crate::config::{AnalysisConfig, Language};
+use crate::error::XStatsError;
+use crate::extract::get_extractor;
+use crate::graph::analysis::{find_cycles, DependencyCycle};
+use crate::graph::{CallGraph, NodeMetricsIndex, TypeDependencyGraph};
+use crate::metrics::{
+    inherit_entity_ids, ActivityMetrics, BudgetReport, BudgetSnapshot, ChurnMetrics, CodeChanges,
+    CodeChangesMap, CodeMetric, CodeMetricBlock, CodeMetrics, CodeMetricsMap, CommitInfos,
+    EntityDiffs, EntityHistory, EntityKind, ExtractSuggestions, MarkerReport, NamingReport,
+    NearDuplicates, OwnershipReport, PackageMetrics, QualityScores, RobustnessReport,
+    ThresholdViolation,
+};
+use crate::output::{create_sink, OutputSink, StreamWriter};
+use crate::ts::{InputEdit, TSParsers, TSTreesBin};
+use crate::utils::csv_dialect::CsvDialect;
+use crate::utils::logger::WarningKind;
+use crate::utils::parquet::ColumnType;
 use crate::utils::version_control::{
-    generate_revwalk, open_repo, Delta, DiffOptions, Repository, Tree,
+    discover_repo, generate_revwalk, get_changed_files, get_commit_files, get_file_owner,
+    get_line_authors, get_line_times, open_repo, resolve_baseline, resolve_commit, Commit, Oid,
+    Repository, RepositoryError, RevwalkConfig,
+};
+use crate::utils::{
+    get_file_extension, get_file_name, load_records_from_json, save_to_sarif,
+    traverse_path_with_options,
 };
-use crate::utils::{get_file_extension, save_to_csv, save_to_json, traverse_path};
+use crate::visitor::get_grammar_problems;
+use log::{info, warn};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 pub struct XStats {
     target_path: String,
     output_path: String,
+    pub config: AnalysisConfig,
     parsers: TSParsers,
     trees_bin: TSTreesBin,
     pub metrics_map: CodeMetricsMap,
+    pub changes_map: CodeChangesMap,
+    pub churn_metrics: ChurnMetrics,
+    pub commit_infos: CommitInfos,
+    pub activity_metrics: Option<ActivityMetrics>,
+    /// Ownership is only computed on demand, from blame
+    pub ownership: Option<OwnershipReport>,
     pub tdg: TypeDependencyGraph,
+    pub call_graph: CallGraph,
+    /// Overwrite the outputs of a previous run instead of failing
+    pub force: bool,
+    /// How CSV outputs are written
+    pub csv_dialect: CsvDialect,
+    /// The range of commits analyzed by `run_multi_commit`
+    pub revwalk_config: RevwalkConfig,
+    /// The number of commits `run_multi_commit` analyzes at the same time, on threads of their own
+    pub commit_jobs: usize,
+    /// When the analysis stops, keeping the results so far, if limited
+    deadline: Option<Instant>,
+    /// The files abandoned on timeout and the analyses cut short, reported at the end of the run
+    pub warnings: Vec<String>,
+    /// Where the steps of the analysis are reported as they happen, e.g. to show its progress
+    pub progress: Progress,
+    /// The commit the default metrics are compared with, whose metrics are saved under its id
+    pub baseline_id: Option<String>,
+    /// Added as columns to every row of the history tables, if set
+    pub provenance: Option<Provenance>,
+    /// Where the metrics of the default mode are cached between runs, if anywhere
+    pub cache_dir: Option<String>,
+    /// Only the files changed since this revision are analyzed by the default run, if set
+    pub diff_base: Option<String>,
+    /// The revision whose files the default run reads from git instead of the working tree, if
+    /// set, in which case `diff_base` is ignored
+    pub rev: Option<String>,
+    /// Also save the graphs in the GraphML format
+    pub graphml: bool,
+    /// Also save the graphs in the GEXF format
+    pub gexf: bool,
+    /// Also save the metrics of every entity at every commit in long format, in multi-commit mode
+    pub track_entities: bool,
+    /// Where the results of each commit are streamed as soon as it is analyzed, if enabled
+    stream: Option<StreamWriter>,
+    /// The commits analyzed by an interrupted run, skipped by `run_multi_commit`
+    completed_commits: HashSet<String>,
+    /// The metrics of every file of the project as of `snapshot_id`, by file path, carried
+    /// forward from commit to commit in multi-commit mode
+    snapshot: BTreeMap<String, Vec<CodeMetricBlock>>,
+    /// The commit the snapshot is that of, if any
+    snapshot_id: Option<String>,
+    /// Where `analyze` and `analyze_history` save their results, finished once they are saved
+    sinks: Vec<Box<dyn OutputSink>>,
 }
 
 impl XStats {
@@ -22,289 +124,1547 @@ impl XStats {
             target_path,
             output_path,
 
+            // Initialize the analysis settings with their defaults
+            config: AnalysisConfig::new(),
             // Initialize parsers for all supported languages
             parsers: TSParsers::new(),
             // Initialize trees bin to store parsed trees
             trees_bin: TSTreesBin::new(),
             // Initialize metrics map to store code metrics
             metrics_map: CodeMetricsMap::new(),
+            // Initialize changes map to store the entities touched by each commit
+            changes_map: CodeChangesMap::new(),
+            // Initialize churn metrics to store the lines changed in each file by each commit
+            churn_metrics: ChurnMetrics::new(),
+            // Initialize commit infos to store the issues each commit refers to
+            commit_infos: CommitInfos::new(),
+            // Activity metrics are only computed on demand, from blame
+            activity_metrics: None,
+            ownership: None,
             // Initialize type dependency graph
             tdg: TypeDependencyGraph::new(),
+            // Initialize call graph, resolved once all the files are indexed
+            call_graph: CallGraph::new(),
+            force: false,
+            csv_dialect: CsvDialect::new(),
+            revwalk_config: RevwalkConfig::default(),
+            commit_jobs: 1,
+            deadline: None,
+            warnings: Vec::new(),
+            progress: Progress::new(),
+            baseline_id: None,
+            provenance: None,
+            cache_dir: None,
+            diff_base: None,
+            rev: None,
+            graphml: false,
+            gexf: false,
+            track_entities: false,
+            stream: None,
+            completed_commits: HashSet::new(),
+            snapshot: BTreeMap::new(),
+            snapshot_id: None,
+            sinks: Vec::new(),
         }
     }
 
-    pub fn run_default(&mut self) {
-        match traverse_path(&self.target_path) {
-            Ok(files) => {
-                if files.is_empty() {
-                    println!(
-                        "No files to process in the target path {}",
-                        self.target_path
-                    );
-                } else {
-                    let file_count = files.len();
-                    let main_pb = CustomProgressBar::new();
-                    let pb = main_pb.generate_files_bar(file_count as u64);
+    /// Save the results of `analyze` and `analyze_history` to the sink too
+    pub fn add_sink(&mut self, sink: Box<dyn OutputSink>) {
+        self.sinks.push(sink);
+    }
 
-                    let mut metrics = CodeMetrics::new();
+    /// Analyze the files of the target as `run_default` does, save the results to the sinks and
+    /// return them
+    pub fn analyze(&mut self) -> Result<AnalysisResults, XStatsError> {
+        self.run_default()?;
+        let mut sinks = std::mem::take(&mut self.sinks);
+        for sink in &mut sinks {
+            self.save_run_manifest(sink.as_mut())?;
+            self.save_metrics(sink.as_mut())?;
+            self.save_summary(sink.as_mut())?;
+            self.save_scores(sink.as_mut())?;
+            self.save_tdg(sink.as_mut())?;
+            self.save_call_graph(sink.as_mut())?;
+            sink.finish()?;
+        }
 
-                    // Analyze each file
-                    for file in &files {
-                        pb.set_message(format!("{}", file));
-                        self.process_file(&mut metrics, file, None);
-                        pb.inc(1);
-                    }
+        let metrics = self.metrics_map.get_default_metrics();
+        Ok(AnalysisResults {
+            metric_blocks: self.get_filtered_blocks(metrics),
+            scores: metrics
+                .map(|metrics| {
+                    QualityScores::new(metrics, &self.config.score_weights, &self.target_path)
+                        .scores
+                })
+                .unwrap_or_default(),
+            threshold_violations: self.check_thresholds(),
+            quality_gate_violations: self.check_quality_gate(),
+            warnings: self.warnings.clone(),
+        })
+    }
+
+    /// Analyze the history of the target as `run_multi_commit` does, save the results to the
+    /// sinks and return them
+    pub fn analyze_history(&mut self) -> Result<HistoryResults, XStatsError> {
+        self.run_multi_commit()?;
+        let mut sinks = std::mem::take(&mut self.sinks);
+        for sink in &mut sinks {
+            self.save_run_manifest(sink.as_mut())?;
+            self.save_metrics_map(sink.as_mut())?;
+            self.save_changes_map(sink.as_mut())?;
+            self.save_churn_metrics(sink.as_mut())?;
+            self.save_commits(sink.as_mut())?;
+            self.save_hotspots(sink.as_mut())?;
+            sink.finish()?;
+        }
+
+        let commits = self.commit_infos.commits.clone();
+        let metrics = commits
+            .last()
+            .and_then(|commit| self.metrics_map.get_metrics(&commit.commit_id));
+        Ok(HistoryResults {
+            changes: commits
+                .iter()
+                .filter_map(|commit| {
+                    let changes = self.changes_map.get_changes(&commit.commit_id)?;
+                    Some((commit.commit_id.clone(), changes.changes.clone()))
+                })
+                .collect(),
+            churn: self.churn_metrics.file_churns.clone(),
+            metric_blocks: self.get_filtered_blocks(metrics),
+            commits,
+            warnings: self.warnings.clone(),
+        })
+    }
+
+    /// The metric blocks kept by the metrics filter
+    fn get_filtered_blocks(&self, metrics: Option<&CodeMetrics>) -> Vec<CodeMetricBlock> {
+        metrics
+            .map(|metrics| {
+                metrics
+                    .metric_blocks
+                    .iter()
+                    .filter(|block| self.metrics_map.filter.matches(block))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 
-                    self.metrics_map.add_default_metrics(metrics);
+    /// Limit the number of parsed trees kept for incremental parsing, and the memory they hold in
+    /// bytes. The files whose tree is evicted are parsed again in full.
+    pub fn set_trees_budget(&mut self, max_trees: Option<usize>, max_bytes: Option<usize>) {
+        self.trees_bin.set_budget(max_trees, max_bytes);
+    }
+
+    /// Stop the analysis once it takes longer than `timeout` from now, keeping the results so far
+    pub fn set_total_timeout(&mut self, timeout: Duration) {
+        self.deadline = Some(Instant::now() + timeout);
+    }
+
+    /// Add a warning, reported to the progress listeners as it happens
+    fn add_warning(&mut self, warning: String) {
+        warn!(target: WarningKind::Timeout.target(), "{}", warning);
+        self.progress.emit(ProgressEvent::Warning {
+            message: warning.clone(),
+        });
+        self.warnings.push(warning);
+    }
 
-                    pb.finish_and_clear();
+    fn is_past_deadline(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    pub fn run_default(&mut self) -> Result<(), XStatsError> {
+        // For the files of the overridden extensions to be cached as the others
+        self.parsers
+            .set_extension_languages(&self.config.extension_languages);
+        let files: Vec<(String, Option<String>)> = match &self.rev {
+            Some(rev) => {
+                let repo = discover_repo(&self.target_path)?;
+                let commit = repo.find_commit(resolve_commit(&repo, rev)?)?;
+                self.read_commit_files(&repo, &commit)?
+                    .into_iter()
+                    .map(|(file, content)| (file, Some(content)))
+                    .collect()
+            }
+            None => {
+                let mut files = traverse_path_with_options(
+                    &self.target_path,
+                    &self.config.path_filter,
+                    &self.config.traversal,
+                )?;
+                if let Some(diff_base) = &self.diff_base {
+                    let changed_files = self.get_changed_files(diff_base)?;
+                    files.retain(|file| {
+                        Path::new(file)
+                            .canonicalize()
+                            .is_ok_and(|path| changed_files.contains(&path))
+                    });
                 }
+                files.into_iter().map(|file| (file, None)).collect()
+            }
+        };
+        if files.is_empty() {
+            warn!(
+                "No files to process in the target path {}",
+                self.target_path
+            );
+            return Ok(());
+        }
+
+        let file_count = files.len();
+        self.progress
+            .emit(ProgressEvent::FilesStarted { total: file_count });
+
+        let mut metrics = CodeMetrics::new();
+        let mut file_cache = self
+            .cache_dir
+            .as_deref()
+            .map(|cache_dir| FileCache::new(cache_dir, &self.config, &self.metrics_map.filter));
+
+        // Analyze each file
+        let mut is_complete = true;
+        for (i, (file, content)) in files.into_iter().enumerate() {
+            if self.is_past_deadline() {
+                self.add_warning(format!(
+                    "Total timeout exceeded, {} of {} files analyzed",
+                    i, file_count
+                ));
+                is_complete = false;
+                break;
             }
-            Err(e) => {
-                println!("{}", e);
+            self.progress.emit(ProgressEvent::FileStarted {
+                file_path: file.clone(),
+            });
+            match self.process_file(&mut metrics, &file, content, file_cache.as_mut()) {
+                Err(e @ XStatsError::Timeout(_)) => self.add_warning(e.to_string()),
+                result => result?,
             }
+            self.progress
+                .emit(ProgressEvent::FileDone { file_path: file });
         }
+        self.progress.emit(ProgressEvent::FilesDone);
+
+        // The entries of the files left out by a timeout or unchanged since the diff base are
+        // still valid
+        let is_complete = is_complete && self.diff_base.is_none();
+        if let Some(file_cache) = file_cache.filter(|_| is_complete) {
+            file_cache.prune()?;
+        }
+        self.call_graph.resolve_calls();
+        self.tdg.resolve_references();
+        self.tdg.calculate_coupling(&mut metrics);
+        self.metrics_map.add_default_metrics(metrics);
+        Ok(())
+    }
+
+    /// Analyze files given by path with their content, e.g. the unsaved buffers of an editor,
+    /// without traversing the target or reading anything from disk, and return their metrics.
+    ///
+    /// The files of disabled or unsupported languages are skipped, and those abandoned on timeout
+    /// reported in the warnings. The graphs are built from the files as with `run_default`, but
+    /// the default metrics are left as they are.
+    pub fn analyze_sources<P: Into<String>>(
+        &mut self,
+        sources: impl IntoIterator<Item = (P, String)>,
+    ) -> Result<CodeMetrics, XStatsError> {
+        let mut metrics = CodeMetrics::new();
+        for (file, content) in sources {
+            match self.process_file(&mut metrics, &file.into(), Some(content), None) {
+                Err(e @ XStatsError::Timeout(_)) => self.add_warning(e.to_string()),
+                result => result?,
+            }
+        }
+
+        self.call_graph.resolve_calls();
+        self.tdg.resolve_references();
+        self.tdg.calculate_coupling(&mut metrics);
+        Ok(metrics)
     }
 
-    pub fn run_multi_commit(&mut self) {
+    /// The files of the working tree changed since a revision, or since the merge-base of HEAD
+    /// and the default branch for `auto`, as canonical paths
+    fn get_changed_files(&self, diff_base: &str) -> Result<HashSet<PathBuf>, XStatsError> {
+        let repo = discover_repo(&self.target_path)?;
+        let base = resolve_baseline(&repo, diff_base)?;
+        let workdir = repo
+            .workdir()
+            .and_then(|workdir| workdir.canonicalize().ok())
+            .ok_or_else(|| RepositoryError::NotARepository(self.target_path.clone()))?;
+        let changed_files = get_changed_files(&repo, base)?
+            .into_iter()
+            .map(|path| workdir.join(path))
+            .collect();
+        Ok(changed_files)
+    }
+
+    pub fn run_multi_commit(&mut self) -> Result<(), XStatsError> {
         // Open the Git repository at target_path
-        let repo = open_repo(&self.target_path);
+        let repo = open_repo(&self.target_path)?;
 
         // Get the HEAD commit
-        let revwalk = generate_revwalk(&repo);
-        let total_commits = generate_revwalk(&repo).count();
+        let mut commit_ids = generate_revwalk(&repo, &self.revwalk_config)?;
+        commit_ids.retain(|oid| !self.completed_commits.contains(&oid.to_string()));
+        let total_commits = commit_ids.len();
+
+        self.progress.emit(ProgressEvent::CommitsStarted {
+            total: total_commits,
+        });
 
-        let main_pb = CustomProgressBar::new();
-        let pb = main_pb.generate_commits_bar(total_commits as u64);
+        if self.commit_jobs > 1 {
+            // The workers share the settings while the results are merged into the analysis
+            let config = self.config.clone();
+            let target_path = self.target_path.clone();
+            let analyzed_count = analyze_commits_in_parallel(
+                &target_path,
+                &commit_ids,
+                &config,
+                self.commit_jobs,
+                self.trees_bin.get_budget(),
+                self.deadline,
+                |analyzed_commit| {
+                    let commit_id = analyzed_commit.commit_info.commit_id.clone();
+                    self.add_analyzed_commit(analyzed_commit)?;
+                    self.progress.emit(ProgressEvent::CommitDone { commit_id });
+                    Ok(())
+                },
+            )?;
+            if analyzed_count < total_commits {
+                self.add_warning(format!(
+                    "Total timeout exceeded, {} of {} commits analyzed",
+                    analyzed_count, total_commits
+                ));
+            }
+            self.progress.emit(ProgressEvent::CommitsDone);
+            return Ok(());
+        }
 
         // Iterate through commits
-        for oid_result in revwalk {
-            if let Ok(oid) = oid_result {
-                if let Ok(commit) = repo.find_commit(oid) {
-                    pb.set_message(format!("{}", commit.id()));
-                    // Get the tree for the commit
-                    if let Ok(tree) = commit.tree() {
-                        let parent = if commit.parent_count() > 0 {
-                            Some(
-                                commit
-                                    .parent(0)
-                                    .expect("Failed to get parent commit")
-                                    .tree()
-                                    .expect("Failed to get parent tree"),
-                            )
-                        } else {
-                            None
-                        };
-                        let mut code_metrics = CodeMetrics::new();
-                        if let Err(e) =
-                            self.process_tree(&repo, &tree, &parent, &mut code_metrics, &main_pb)
-                        {
-                            println!("Failed to process tree: {}", e);
-                        }
-
-                        self.metrics_map
-                            .add_metrics(commit.id().to_string(), code_metrics);
-                    }
+        for (i, oid) in commit_ids.into_iter().enumerate() {
+            if self.is_past_deadline() {
+                self.add_warning(format!(
+                    "Total timeout exceeded, {} of {} commits analyzed",
+                    i, total_commits
+                ));
+                break;
+            }
+            if let Ok(commit) = repo.find_commit(oid) {
+                let analyzed_commit = analyze_commit(
+                    &repo,
+                    &commit,
+                    &self.config,
+                    &mut self.parsers,
+                    &mut self.trees_bin,
+                    Some(&self.progress),
+                );
+                self.add_analyzed_commit(analyzed_commit)?;
+                self.progress.emit(ProgressEvent::CommitDone {
+                    commit_id: commit.id().to_string(),
+                });
+            }
+        }
+        self.progress.emit(ProgressEvent::CommitsDone);
+        Ok(())
+    }
+
+    /// Analyze the target as of a baseline commit, to compare the default metrics with.
+    ///
+    /// The baseline is a revision, or `auto` for the merge-base of HEAD and the default branch, so
+    /// that a branch is compared with where it forked from. Must be called after `run_default`, on
+    /// a target inside a git repository.
+    pub fn run_baseline(&mut self, baseline_ref: &str) -> Result<(), XStatsError> {
+        self.baseline_id = Some(self.run_revision(baseline_ref)?);
+        Ok(())
+    }
+
+    /// Analyze the target as of a revision, or `auto` as for `run_baseline`, reading its files
+    /// from git, and keep its metrics under the id of its commit, which is returned
+    pub fn run_revision(&mut self, revision: &str) -> Result<String, XStatsError> {
+        let repo = discover_repo(&self.target_path)?;
+        let commit = repo.find_commit(resolve_baseline(&repo, revision)?)?;
+        let files = self.read_commit_files(&repo, &commit)?;
 
-                    pb.inc(1);
+        let mut metrics = CodeMetrics::new();
+        for analyzed_file in
+            analyze_files_in_parallel(&self.config, &files, &HashMap::new(), &self.progress)
+        {
+            match analyzed_file {
+                Err(e @ XStatsError::Timeout(_)) => {
+                    self.add_warning(format!("{} at commit {}", e, commit.id()))
                 }
+                analyzed_file => metrics
+                    .metric_blocks
+                    .extend(analyzed_file?.metrics.metric_blocks),
             }
         }
-        pb.finish_and_clear();
+
+        let commit_id = commit.id().to_string();
+        self.metrics_map.add_metrics(commit_id.clone(), metrics);
+        Ok(commit_id)
     }
 
-    // Process each file in a tree
-    fn process_tree(
-        &mut self,
+    /// The files of the target as of a commit, read from git, with their content. They are named
+    /// as in the default metrics, below the target path, for their entities to match. The target
+    /// of a bare repository is the repository itself.
+    fn read_commit_files(
+        &self,
         repo: &Repository,
-        tree: &Tree,
-        parent: &Option<Tree>,
-        code_metrics: &mut CodeMetrics,
-        main_pb: &CustomProgressBar,
-    ) -> Result<(), git2::Error> {
-        let supported_extensions = self.parsers.get_all_supported_extensions();
-
-        let mut diff_opts = DiffOptions::new();
-        let diff = repo
-            .diff_tree_to_tree(parent.as_ref(), Some(tree), Some(&mut diff_opts))
-            .expect("Failed to get diff");
-
-        let stats = diff.stats().expect("Failed to get diff stats");
-        let files_changed = stats.files_changed();
-
-        let pb = main_pb.generate_files_bar(files_changed as u64);
-
-        diff.foreach(
-            &mut |delta, _| {
-                match delta.status() {
-                    Delta::Added => {
-                        if let Some(path) = delta.new_file().path() {
-                            pb.set_message(format!("[ADDED] {}", path.to_string_lossy()));
-                            // Retrieve the file content for added or modified files
-                            if let Ok(blob) = repo.find_blob(delta.new_file().id()) {
-                                if !supported_extensions
-                                    .contains(&get_file_extension(&path.to_string_lossy()).as_str())
-                                {
-                                    pb.inc(1);
-                                    return true;
-                                }
-                                if let Ok(content) = std::str::from_utf8(blob.content()) {
-                                    // Pass the file content to `process_file`
-                                    self.process_file(
-                                        code_metrics,
-                                        path.to_string_lossy().as_ref(),
-                                        Some(content.to_string()),
-                                    );
-                                } else {
-                                    println!(
-                                        "Failed to read content as UTF-8 for file: {}",
-                                        path.to_string_lossy()
-                                    );
-                                }
-                            } else {
-                                println!(
-                                    "Failed to find blob for file: {}",
-                                    path.to_string_lossy()
-                                );
-                            }
-                        }
-                    }
-                    Delta::Modified => {
-                        if let Some(path) = delta.new_file().path() {
-                            pb.set_message(format!("[MODIFIED] {}", path.to_string_lossy()));
-                            // Retrieve the file content for added or modified files
-                            if let Ok(blob) = repo.find_blob(delta.new_file().id()) {
-                                if !supported_extensions
-                                    .contains(&get_file_extension(&path.to_string_lossy()).as_str())
-                                {
-                                    pb.inc(1);
-                                    return true;
-                                }
-                                if let Ok(content) = std::str::from_utf8(blob.content()) {
-                                    // Pass the file content to `process_file`
-                                    self.process_file(
-                                        code_metrics,
-                                        path.to_string_lossy().as_ref(),
-                                        Some(content.to_string()),
-                                    );
-                                } else {
-                                    println!(
-                                        "Failed to read content as UTF-8 for file: {}",
-                                        path.to_string_lossy()
-                                    );
-                                }
-                            } else {
-                                println!(
-                                    "Failed to find blob for file: {}",
-                                    path.to_string_lossy()
-                                );
-                            }
-                        }
+        commit: &Commit,
+    ) -> Result<Vec<(String, String)>, XStatsError> {
+        let target_dir = match repo.workdir() {
+            Some(workdir) => {
+                let workdir = workdir
+                    .canonicalize()
+                    .map_err(|_| RepositoryError::NotARepository(self.target_path.clone()))?;
+                let target_dir = Path::new(&self.target_path)
+                    .canonicalize()
+                    .map_err(|_| XStatsError::PathNotFound(self.target_path.clone()))?;
+                target_dir
+                    .strip_prefix(&workdir)
+                    .map(Path::to_path_buf)
+                    .unwrap_or_default()
+            }
+            None => PathBuf::new(),
+        };
+
+        let supported_extensions = get_analyzed_extensions(&self.parsers, &self.config);
+
+        let mut files = Vec::new();
+        for (relative_path, blob_id) in get_commit_files(commit, &target_dir)? {
+            // A target file is filtered by its name, as when traversing the target
+            let (file_path, relative_path) = if relative_path.as_os_str().is_empty() {
+                (self.target_path.clone(), get_file_name(&self.target_path))
+            } else {
+                let file_path = Path::new(&self.target_path).join(&relative_path);
+                (
+                    file_path.to_string_lossy().to_string(),
+                    relative_path.to_string_lossy().to_string(),
+                )
+            };
+            if !supported_extensions.contains(&get_file_extension(&file_path))
+                || !self.config.path_filter.matches(&relative_path)
+            {
+                continue;
+            }
+            let content = repo.find_blob(blob_id)?.content().to_vec();
+            let content = self.config.encoding.decode_source(&file_path, content);
+            files.push((file_path, content));
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Weight the default metrics by how recently each line was last modified, from blame.
+    ///
+    /// Must be called after `run_default`, on a target inside a git repository.
+    pub fn run_activity(&mut self, half_life_days: f64) -> Result<(), XStatsError> {
+        let repo = discover_repo(&self.target_path)?;
+        let reference_time = repo.head()?.peel_to_commit()?.time().seconds();
+        let workdir = repo
+            .workdir()
+            .and_then(|workdir| workdir.canonicalize().ok())
+            .ok_or_else(|| RepositoryError::NotARepository(self.target_path.clone()))?;
+
+        let mut activity_metrics = ActivityMetrics::new(reference_time, half_life_days);
+        let mut is_partial = false;
+        if let Some(metrics) = self.metrics_map.get_default_metrics() {
+            // Metric blocks of the same file are contiguous
+            for file_blocks in metrics
+                .metric_blocks
+                .chunk_by(|a, b| a.meta_data.file_path == b.meta_data.file_path)
+            {
+                if self.is_past_deadline() {
+                    is_partial = true;
+                    break;
+                }
+                let file_path = &file_blocks[0].meta_data.file_path;
+                let full_path = match Path::new(file_path).canonicalize() {
+                    Ok(full_path) => full_path,
+                    Err(e) => {
+                        warn!("Failed to resolve {}: {}", file_path, e);
+                        continue;
                     }
-                    Delta::Deleted => {
-                        if let Some(path) = delta.old_file().path() {
-                            if !supported_extensions
-                                .contains(&get_file_extension(&path.to_string_lossy()).as_str())
-                            {
-                                pb.inc(1);
-                                return true;
-                            }
-                            pb.set_message(format!("[DELETED] {}", path.to_string_lossy()));
-                            self.trees_bin.delete_tree(&path.to_string_lossy());
-                        }
+                };
+                let relative_path = match full_path.strip_prefix(&workdir) {
+                    Ok(relative_path) => relative_path,
+                    Err(_) => continue,
+                };
+                let content = std::fs::read(&full_path).unwrap_or_default();
+
+                match get_line_times(&repo, relative_path, &content) {
+                    Ok(line_times) => activity_metrics.add_file(file_blocks, &line_times),
+                    Err(e) => warn!(
+                        target: WarningKind::Git.target(),
+                        "Failed to blame {}: {}",
+                        file_path,
+                        e
+                    ),
+                }
+            }
+        }
+        if is_partial {
+            self.add_warning("Total timeout exceeded, activity metrics are partial".to_string());
+        }
+
+        let (aloc_share, cc_share) = activity_metrics.get_active_shares();
+        info!(
+            "Active code: {:.1}% of aloc, {:.1}% of cc (half-life of {} days)",
+            aloc_share * 100.0,
+            cc_share * 100.0,
+            half_life_days
+        );
+
+        self.activity_metrics = Some(activity_metrics);
+        Ok(())
+    }
+
+    /// Report how concentrated the knowledge of each file is among its authors, from blame.
+    ///
+    /// Must be called after `run_default`, on a target inside a git repository.
+    pub fn run_ownership(&mut self) -> Result<(), XStatsError> {
+        let repo = discover_repo(&self.target_path)?;
+        let workdir = repo
+            .workdir()
+            .and_then(|workdir| workdir.canonicalize().ok())
+            .ok_or_else(|| RepositoryError::NotARepository(self.target_path.clone()))?;
+
+        let mut ownership = OwnershipReport::new();
+        let mut is_partial = false;
+        if let Some(metrics) = self.metrics_map.get_default_metrics() {
+            // Metric blocks of the same file are contiguous
+            for file_blocks in metrics
+                .metric_blocks
+                .chunk_by(|a, b| a.meta_data.file_path == b.meta_data.file_path)
+            {
+                if self.is_past_deadline() {
+                    is_partial = true;
+                    break;
+                }
+                let file_path = &file_blocks[0].meta_data.file_path;
+                let full_path = match Path::new(file_path).canonicalize() {
+                    Ok(full_path) => full_path,
+                    Err(e) => {
+                        warn!("Failed to resolve {}: {}", file_path, e);
+                        continue;
                     }
-                    _ => {}
+                };
+                let relative_path = match full_path.strip_prefix(&workdir) {
+                    Ok(relative_path) => relative_path,
+                    Err(_) => continue,
+                };
+                let content = std::fs::read(&full_path).unwrap_or_default();
+
+                match get_line_authors(&repo, relative_path, &content) {
+                    Ok(line_authors) => ownership.add_file(file_blocks, &line_authors),
+                    Err(e) => warn!(
+                        target: WarningKind::Git.target(),
+                        "Failed to blame {}: {}",
+                        file_path,
+                        e
+                    ),
                 }
-                pb.inc(1);
-                true
+            }
+        }
+        if is_partial {
+            self.add_warning("Total timeout exceeded, ownership is partial".to_string());
+        }
+
+        let risky_count = ownership.files.iter().filter(|file| file.risky).count();
+        info!(
+            "Bus factor: {}, {} complex files with a single significant author",
+            ownership.get_bus_factor(),
+            risky_count
+        );
+
+        self.ownership = Some(ownership);
+        Ok(())
+    }
+
+    /// Check the grammars of the analyzed languages against the queries and node kinds the
+    /// analysis refers to, with one error per incompatible language
+    pub fn check_grammars(&self) -> Vec<XStatsError> {
+        let grammar_versions = get_grammar_versions();
+        let mut errors = Vec::new();
+        for language in &self.config.languages {
+            let problems = match (
+                self.parsers.get_parser(language),
+                self.parsers.load_errors.get(language),
+            ) {
+                (Some(parser), _) => get_grammar_problems(parser.get_grammar(), *language),
+                (None, Some(load_error)) => vec![load_error.clone()],
+                (None, None) => continue,
+            };
+            if problems.is_empty() {
+                continue;
+            }
+            let grammar_crate = language.get_grammar_crate();
+            let grammar = match grammar_versions.get(grammar_crate) {
+                Some(version) => format!("{} {}", grammar_crate, version),
+                None => grammar_crate.to_string(),
+            };
+            errors.push(XStatsError::IncompatibleGrammar {
+                language: *language,
+                grammar,
+                problems,
+            });
+        }
+        errors
+    }
+
+    /// Check the default metrics against the configured thresholds
+    pub fn check_thresholds(&self) -> Vec<ThresholdViolation> {
+        match self.metrics_map.get_default_metrics() {
+            Some(metrics) => ThresholdViolation::check(
+                metrics,
+                &self.metrics_map.filter,
+                &self.config.thresholds,
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    /// Check the default metrics against the thresholds of `--fail-on`, any violation failing the
+    /// run
+    pub fn check_quality_gate(&self) -> Vec<ThresholdViolation> {
+        match self.metrics_map.get_default_metrics() {
+            Some(metrics) => {
+                ThresholdViolation::check(metrics, &self.metrics_map.filter, &self.config.fail_on)
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Analyze a file of the default analysis again after an edit, e.g. from an editor, and
+    /// return how its entities changed.
+    ///
+    /// The `edits` turning the previous content of the file into `new_content` are applied to
+    /// its stored tree, so that it is parsed incrementally and only the classes and methods they
+    /// touch are measured again. Files without a stored tree, such as new or host files, are
+    /// analyzed whole. The graphs are left as they are.
+    pub fn reanalyze_file(
+        &mut self,
+        file_path: &str,
+        new_content: String,
+        edits: &[InputEdit],
+    ) -> Result<EntityDiffs, XStatsError> {
+        let old_blocks: Vec<CodeMetricBlock> = self
+            .metrics_map
+            .get_default_metrics()
+            .map(|metrics| {
+                metrics
+                    .metric_blocks
+                    .iter()
+                    .filter(|block| block.meta_data.file_path == file_path)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let new_metrics = match self.trees_bin.get_tree(file_path) {
+            Some(old_tree) => {
+                for edit in edits {
+                    old_tree.edit(edit);
+                }
+                self.reparse_file(file_path, &new_content, edits, &old_blocks)?
+            }
+            None => {
+                let analyzed_file = analyze_file(
+                    &mut self.parsers,
+                    &mut self.trees_bin,
+                    &self.config,
+                    file_path,
+                    Some(new_content),
+                )?;
+                for (file, _, tree, _) in analyzed_file.trees {
+                    self.trees_bin.insert_tree(&file, tree);
+                }
+                analyzed_file.metrics
+            }
+        };
+
+        let entity_diffs = EntityDiffs::compare(
+            &CodeMetrics {
+                metric_blocks: old_blocks,
             },
-            None,
-            None,
-            None,
+            &new_metrics,
+        );
+
+        // The blocks of a file are next to each other, new files go last
+        if self.metrics_map.get_default_metrics_mut().is_none() {
+            self.metrics_map.add_default_metrics(CodeMetrics::new());
+        }
+        if let Some(metrics) = self.metrics_map.get_default_metrics_mut() {
+            let blocks = &mut metrics.metric_blocks;
+            let start = blocks
+                .iter()
+                .position(|block| block.meta_data.file_path == file_path)
+                .unwrap_or(blocks.len());
+            let end = start
+                + blocks[start..]
+                    .iter()
+                    .take_while(|block| block.meta_data.file_path == file_path)
+                    .count();
+            blocks.splice(start..end, new_metrics.metric_blocks);
+        }
+
+        Ok(entity_diffs)
+    }
+
+    /// Parse a file again from its edited tree, and measure the entities touched by the edits
+    fn reparse_file(
+        &mut self,
+        file_path: &str,
+        new_content: &str,
+        edits: &[InputEdit],
+        old_blocks: &[CodeMetricBlock],
+    ) -> Result<CodeMetrics, XStatsError> {
+        self.parsers.set_timeout(self.config.file_timeout);
+        let Some((language, tree, source_code)) =
+            self.parsers
+                .generate_tree_from_blob(&mut self.trees_bin, file_path, new_content)
+        else {
+            // The edited tree no longer matches any content
+            self.trees_bin.delete_tree(file_path);
+            return Err(XStatsError::Timeout(file_path.to_string()));
+        };
+
+        let mut changed_ranges = get_edited_ranges(edits);
+        if let Some(old_tree) = self.trees_bin.get_tree(file_path) {
+            changed_ranges.extend(
+                old_tree
+                    .changed_ranges(&tree)
+                    .map(|range| range.start_byte..range.end_byte),
+            );
+        }
+
+        let mut metrics = CodeMetrics::new();
+        metrics.regenerate_root_metrics(
+            &self.parsers,
+            &self.config,
+            &source_code,
+            language,
+            file_path,
+            &tree,
+            old_blocks,
+            &changed_ranges,
         )?;
-        main_pb.mp.remove(&pb);
-        Ok(())
+        self.trees_bin.insert_tree(file_path, tree);
+        Ok(metrics)
     }
 
+    /// Analyze a file of the default mode, or only parse it if its metrics are in the cache. The
+    /// file is read from the disk unless its content is given.
     fn process_file(
         &mut self,
         code_metrics: &mut CodeMetrics,
         file: &str,
         content: Option<String>,
-    ) {
-        let result = self
-            .parsers
-            .generate_tree(&mut self.trees_bin, file, content);
-        if let Some((language, tree, source_code)) = result {
-            // Generate metrics for the file
-            code_metrics.generate_root_metrics(
-                &self.parsers,
-                &source_code,
-                language,
-                &file.to_string(),
-                &tree,
+        file_cache: Option<&mut FileCache>,
+    ) -> Result<(), XStatsError> {
+        // Only the files to analyze are read, the others may not even be text
+        let is_analyzed = get_extractor(file).is_some()
+            || self
+                .parsers
+                .get_language(file)
+                .is_some_and(|language| self.config.is_language_enabled(&language));
+        let Some(file_cache) = file_cache.filter(|_| is_analyzed) else {
+            let analyzed_file = analyze_file(
+                &mut self.parsers,
+                &mut self.trees_bin,
+                &self.config,
+                file,
+                content,
+            )?;
+            self.add_analyzed_file(code_metrics, analyzed_file);
+            return Ok(());
+        };
+
+        let content = match content {
+            Some(content) => content,
+            None => self.config.encoding.read_source(file)?,
+        };
+        let cached_metrics = file_cache.get(file, &content);
+        let is_cached = cached_metrics.is_some();
+        let analyzed_file = analyze_file_cached(
+            &mut self.parsers,
+            &mut self.trees_bin,
+            &self.config,
+            file,
+            Some(content.clone()),
+            cached_metrics,
+        )?;
+        if !is_cached {
+            file_cache.insert(file, &content, &analyzed_file.metrics)?;
+        }
+        self.add_analyzed_file(code_metrics, analyzed_file);
+        Ok(())
+    }
+
+    /// Add the results of an analyzed commit, and the trees of its files to the graphs, streaming
+    /// them to the output if enabled
+    fn add_analyzed_commit(&mut self, analyzed_commit: AnalyzedCommit) -> Result<(), XStatsError> {
+        if let Some(e) = analyzed_commit.error {
+            warn!(
+                target: WarningKind::Git.target(),
+                "Failed to process tree: {}",
+                e
             );
+        }
+        for (file, language, tree, source_code) in &analyzed_commit.trees {
+            self.tdg.process_tree(file, language, tree, source_code);
+            self.call_graph
+                .process_tree(file, language, tree, source_code);
+        }
+        let commit_id = analyzed_commit.commit_info.commit_id.clone();
+        let changed_files: Vec<(String, Option<String>)> = analyzed_commit
+            .file_churns
+            .iter()
+            .map(|file_churn| {
+                (
+                    file_churn.file_path.clone(),
+                    file_churn.old_file_path.clone(),
+                )
+            })
+            .collect();
+        let metrics = self.carry_forward(
+            analyzed_commit.parent_id.as_deref(),
+            &commit_id,
+            &changed_files,
+            analyzed_commit.metrics,
+        )?;
+
+        let churn_count = analyzed_commit.file_churns.len();
+        for file_churn in analyzed_commit.file_churns {
+            self.churn_metrics.add_file_churn(file_churn);
+        }
+        for warning in analyzed_commit.warnings {
+            self.add_warning(warning);
+        }
+
+        self.metrics_map.add_metrics(commit_id.clone(), metrics);
+        self.changes_map
+            .add_changes(commit_id.clone(), analyzed_commit.changes);
+        self.commit_infos.add_commit(analyzed_commit.commit_info);
+
+        match self.stream.take() {
+            Some(mut stream) => {
+                let result = self.stream_commit(&mut stream, &commit_id, churn_count);
+                self.stream = Some(stream);
+                result
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Merge the metrics of the files changed by a commit into the snapshot of the project, and
+    /// return the metrics of all the files of the commit, sorted by path. The snapshot is first
+    /// brought up to date with the parent of the commit, unless it is that of the parent, e.g.
+    /// for the first commit analyzed after `--since` or after commits skipped by a filter.
+    ///
+    /// The changed files are given with their path before the commit if it renamed them. Their
+    /// entities keep the ids they had before the commit, so that those of a renamed file are
+    /// followed across the rename and every later commit.
+    fn carry_forward(
+        &mut self,
+        parent_id: Option<&str>,
+        commit_id: &str,
+        changed_files: &[(String, Option<String>)],
+        metrics: CodeMetrics,
+    ) -> Result<CodeMetrics, XStatsError> {
+        if self.snapshot_id.as_deref() != parent_id {
+            self.sync_snapshot(parent_id)?;
+        }
+        // The files changed but left without metrics, e.g. deleted ones, are dropped
+        let mut old_blocks = HashMap::new();
+        for (file, old_file) in changed_files {
+            let old_file = old_file.as_ref().unwrap_or(file);
+            let removed = [self.snapshot.remove(file), self.snapshot.remove(old_file)];
+            if let Some(blocks) = removed.into_iter().flatten().last() {
+                old_blocks.insert(file.as_str(), blocks);
+            }
+        }
+        for block in metrics.metric_blocks {
+            self.snapshot
+                .entry(block.meta_data.file_path.clone())
+                .or_default()
+                .push(block);
+        }
+        for (file, old_blocks) in old_blocks {
+            if let Some(blocks) = self.snapshot.get_mut(file) {
+                inherit_entity_ids(blocks, &old_blocks);
+            }
+        }
+        self.snapshot_id = Some(commit_id.to_string());
+
+        let mut metrics = CodeMetrics::new();
+        metrics.metric_blocks = self.snapshot.values().flatten().cloned().collect();
+        Ok(metrics)
+    }
+
+    /// Bring the snapshot of the project up to date with a commit, analyzing the files changed
+    /// since the commit of the snapshot, or all of its files without one. Without a commit, the
+    /// snapshot is emptied, as before the first commit.
+    fn sync_snapshot(&mut self, commit_id: Option<&str>) -> Result<(), XStatsError> {
+        let Some(commit_id) = commit_id else {
+            self.snapshot.clear();
+            self.snapshot_id = None;
+            return Ok(());
+        };
+        let repo = open_repo(&self.target_path)?;
+        let commit = repo.find_commit(Oid::from_str(commit_id)?)?;
+        let old_commit = match &self.snapshot_id {
+            Some(snapshot_id) => Some(repo.find_commit(Oid::from_str(snapshot_id)?)?),
+            None => None,
+        };
+
+        let supported_extensions = get_analyzed_extensions(&self.parsers, &self.config);
+        let mut changed_files = Vec::new();
+        for (file, content) in get_files_between(
+            &repo,
+            old_commit.as_ref(),
+            &commit,
+            &self.config,
+            &supported_extensions,
+        )? {
+            self.snapshot.remove(&file);
+            if let Some(content) = content {
+                changed_files.push((file, content));
+            }
+        }
+
+        for analyzed_file in analyze_files_in_parallel(
+            &self.config,
+            &changed_files,
+            &HashMap::new(),
+            &self.progress,
+        ) {
+            match analyzed_file {
+                Err(e @ XStatsError::Timeout(_)) => {
+                    self.add_warning(format!("{} at commit {}", e, commit_id))
+                }
+                analyzed_file => {
+                    for block in analyzed_file?.metrics.metric_blocks {
+                        self.snapshot
+                            .entry(block.meta_data.file_path.clone())
+                            .or_default()
+                            .push(block);
+                    }
+                }
+            }
+        }
+        self.snapshot_id = Some(commit_id.to_string());
+        Ok(())
+    }
+
+    /// Start streaming the results of each commit to the output as soon as it is analyzed, in
+    /// the `csv` or `json` format, instead of saving them all once the history is analyzed. When
+    /// appending, the streamed files of a previous run are continued.
+    pub fn start_stream(&mut self, format: &str, append: bool) -> Result<(), XStatsError> {
+        self.stream = Some(StreamWriter::new(
+            &self.output_path,
+            format,
+            self.csv_dialect.clone(),
+            self.force,
+            append,
+        )?);
+        Ok(())
+    }
+
+    /// Continue the streamed results of an interrupted run, in the same format, skipping the
+    /// commits it analyzed in full, i.e. those whose row of the commits was appended. The rows of
+    /// the commit it was saving are dropped, to be saved again. Returns the number of commits
+    /// skipped.
+    pub fn resume_stream(&mut self, format: &str) -> Result<usize, XStatsError> {
+        self.start_stream(format, true)?;
+        let Some(stream) = &self.stream else {
+            return Ok(0);
+        };
+        let completed_commits: HashSet<String> = stream
+            .get_streamed_values("commits", "commit_id")?
+            .into_iter()
+            .collect();
+        stream.retain_streamed_rows("commits", "commit_id", &completed_commits)?;
+        stream.retain_streamed_rows("churn", "commit_id", &completed_commits)?;
+        stream.retain_streamed_rows("entities", "commit_id", &completed_commits)?;
+
+        self.completed_commits = completed_commits;
+        Ok(self.completed_commits.len())
+    }
+
+    /// Save the metrics and changes of the last commit added, and append its churn, from the
+    /// last `churn_count` rows, its entity history if tracked, and its row of the commits. The
+    /// commit row comes last, so that the commits streamed are the ones whose results are
+    /// complete.
+    fn stream_commit(
+        &self,
+        stream: &mut StreamWriter,
+        commit_id: &str,
+        churn_count: usize,
+    ) -> Result<(), XStatsError> {
+        self.save_metrics_table(stream, &format!("metrics/{}", commit_id), Some(commit_id))?;
+        let data = self.add_provenance(self.changes_map.get_table(commit_id), Some(commit_id));
+        let name = format!("changes/{}", commit_id);
+        save_typed_table(stream, &name, data, CodeChangesMap::get_column_types)?;
+
+        let file_churns = &self.churn_metrics.file_churns;
+        let churn_metrics = ChurnMetrics {
+            file_churns: file_churns[file_churns.len() - churn_count..].to_vec(),
+        };
+        let data = self.add_provenance(churn_metrics.get_table(), None);
+        if let Some((header, rows)) = data.split_first() {
+            stream.append_table("churn", header, rows)?;
+        }
+
+        if self.track_entities {
+            let commit_ids = [commit_id.to_string()];
+            let history = EntityHistory::new(&self.metrics_map, &commit_ids);
+            let data = self.add_provenance(history.get_table(), None);
+            if let Some((header, rows)) = data.split_first() {
+                stream.append_table("entities", header, rows)?;
+            }
+        }
+
+        let commit_infos = CommitInfos {
+            commits: self
+                .commit_infos
+                .commits
+                .last()
+                .cloned()
+                .into_iter()
+                .collect(),
+        };
+        let data = self.add_provenance(commit_infos.get_table(), None);
+        if let Some((header, rows)) = data.split_first() {
+            stream.append_table("commits", header, rows)?;
+        }
+        Ok(())
+    }
+
+    /// Add the metrics of an analyzed file, and its trees to the graphs and the trees bin
+    fn add_analyzed_file(&mut self, code_metrics: &mut CodeMetrics, analyzed_file: AnalyzedFile) {
+        code_metrics
+            .metric_blocks
+            .extend(analyzed_file.metrics.metric_blocks);
+        for (file, language, tree, source_code) in analyzed_file.trees {
             // Process the tree for type dependency graph
-            self.tdg.process_tree(&file.to_string(), &tree);
+            self.tdg.process_tree(&file, &language, &tree, &source_code);
+            // Index the declarations and call sites for the call graph
+            self.call_graph
+                .process_tree(&file, &language, &tree, &source_code);
 
             self.trees_bin.insert_tree(&file, tree);
         }
     }
 
-    pub fn save_metrics_map(&self, format: &str) {
-        let metrics_dir = format!("{}/metrics", self.output_path);
-        std::fs::create_dir_all(&metrics_dir).expect("Failed to create metrics directory");
-
+    pub fn save_metrics_map(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
         for (key, _) in self.metrics_map.iter() {
-            match format {
-                "csv" => self.save_data_as_csv(Some(key)),
-                "json" => self.save_data_as_json(Some(key)),
-                _ => println!("Unsupported format: {}", format),
+            self.save_metrics_table(sink, &format!("metrics/{}", key), Some(key))?;
+        }
+        Ok(())
+    }
+
+    pub fn save_changes_map(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        for (key, _) in self.changes_map.iter() {
+            let data = self.changes_map.get_table(key);
+            let data = self.add_provenance(data, Some(key));
+            let name = format!("changes/{}", key);
+            save_typed_table(sink, &name, data, CodeChangesMap::get_column_types)?;
+        }
+        Ok(())
+    }
+
+    pub fn save_churn_metrics(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        let data = self.add_provenance(self.churn_metrics.get_table(), None);
+        save_table(sink, "churn", data)
+    }
+
+    /// Save the commits analyzed in multi-commit mode, with the issues their message refers to
+    pub fn save_commits(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        let data = self.add_provenance(self.commit_infos.get_table(), None);
+        save_table(sink, "commits", data)
+    }
+
+    /// Save the metrics of every entity at every analyzed commit in long format, in the order
+    /// of the commits
+    pub fn save_entity_history(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        let commit_ids: Vec<String> = self
+            .commit_infos
+            .commits
+            .iter()
+            .map(|commit| commit.commit_id.clone())
+            .collect();
+        let history = EntityHistory::new(&self.metrics_map, &commit_ids);
+        let data = self.add_provenance(history.get_table(), None);
+        save_table(sink, "entities", data)
+    }
+
+    /// Append the provenance columns to a table, if enabled, for the given commit or that of the
+    /// `commit_id` column of each row. The grammar is that of the `language` column, or else of
+    /// the extension of the `file_path` column.
+    fn add_provenance(
+        &self,
+        mut data: Vec<Vec<String>>,
+        commit_id: Option<&str>,
+    ) -> Vec<Vec<String>> {
+        let Some(provenance) = &self.provenance else {
+            return data;
+        };
+        let Some((header, rows)) = data.split_first_mut() else {
+            return data;
+        };
+        let get_column = |name: &str| header.iter().position(|column| column == name);
+        let commit_column = get_column("commit_id");
+        let language_column = get_column("language");
+        let path_column = get_column("file_path");
+        header.extend(Provenance::get_names());
+
+        for row in rows {
+            let language = match (language_column, path_column) {
+                (Some(idx), _) => Language::all()
+                    .into_iter()
+                    .find(|language| language.to_string() == row[idx]),
+                (None, Some(idx)) => self.parsers.get_language(&row[idx]),
+                (None, None) => None,
+            };
+            let row_commit_id = commit_id
+                .or_else(|| commit_column.map(|idx| row[idx].as_str()))
+                .unwrap_or_default()
+                .to_string();
+            row.extend(provenance.get_values(&row_commit_id, language));
+        }
+        data
+    }
+
+    pub fn save_hotspots(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        let hotspots = Hotspots::new(&self.churn_metrics, &self.metrics_map);
+        save_table(sink, "hotspots", hotspots.get_table())
+    }
+
+    /// Save the contributions of the authors of the analyzed commits, with the files each owns
+    /// as of the last one
+    pub fn save_authors(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        let authors = Authors::new(
+            &self.commit_infos,
+            &self.churn_metrics,
+            &self.changes_map,
+            &self.metrics_map,
+            &self.get_file_owners()?,
+        );
+        save_table(sink, "authors", authors.get_table())
+    }
+
+    /// The author of most of the lines of every file of the last analyzed commit, by file path
+    fn get_file_owners(&self) -> Result<HashMap<String, String>, XStatsError> {
+        let mut file_owners = HashMap::new();
+        let Some(commit) = self.commit_infos.commits.last() else {
+            return Ok(file_owners);
+        };
+        let Some(metrics) = self.metrics_map.get_metrics(&commit.commit_id) else {
+            return Ok(file_owners);
+        };
+        let repo = open_repo(&self.target_path)?;
+        let commit_id = Oid::from_str(&commit.commit_id)?;
+        for block in metrics
+            .metric_blocks
+            .iter()
+            .filter(|block| block.meta_data.node_kind == EntityKind::File)
+        {
+            let file_path = &block.meta_data.file_path;
+            if file_owners.contains_key(file_path) {
+                continue;
+            }
+            if let Some(owner) = get_file_owner(&repo, Path::new(file_path), commit_id)? {
+                file_owners.insert(file_path.clone(), owner);
             }
         }
+        Ok(file_owners)
     }
 
-    pub fn save_metrics(&self, format: &str) {
-        match format {
-            "csv" => self.save_data_as_csv(None),
-            "json" => self.save_data_as_json(None),
-            _ => println!("Unsupported format: {}", format),
+    pub fn save_call_graph(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        let node_metrics = self.get_node_metrics();
+        save_table(sink, "calls", self.call_graph.get_table())?;
+        sink.write_graph("calls", &self.call_graph.to_dot(&node_metrics))?;
+        if self.graphml {
+            sink.write_graphml("calls", &self.call_graph.to_graphml(&node_metrics))?;
+        }
+        if self.gexf {
+            sink.write_gexf("calls", &self.call_graph.to_gexf(&node_metrics))?;
+        }
+        Ok(())
+    }
+
+    /// Index the key metrics of the default run, to attach them to the nodes of the graphs
+    fn get_node_metrics(&self) -> NodeMetricsIndex {
+        self.metrics_map
+            .get_default_metrics()
+            .map(NodeMetricsIndex::new)
+            .unwrap_or_default()
+    }
+
+    /// Save how the entities changed from the baseline, once `run_baseline` was called
+    pub fn save_baseline_diff(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        let diffs = self
+            .baseline_id
+            .as_ref()
+            .and_then(|baseline_id| self.metrics_map.compare(baseline_id, "default"));
+        match diffs {
+            Some(diffs) => save_table(sink, "baseline_diff", diffs.get_table()),
+            None => Ok(()),
         }
     }
 
-    pub fn save_data_as_csv(&self, metric_key: Option<&str>) {
-        let output_file = if let Some(key) = metric_key {
-            format!("{}/metrics/{}.csv", self.output_path, key)
+    /// Load the metrics saved as JSON by a previous run, e.g. its `metrics.json`, under the given
+    /// key, and return the names of the metrics they have
+    pub fn load_metrics(&mut self, key: &str, file_path: &str) -> Result<Vec<String>, XStatsError> {
+        let records = load_records_from_json(file_path)?;
+        let metrics =
+            CodeMetrics::from_records(&records).map_err(|reason| XStatsError::InvalidTable {
+                path: file_path.to_string(),
+                reason,
+            })?;
+        let names = CodeMetric::get_names()
+            .into_iter()
+            .filter(|name| records.iter().all(|record| record.get(name).is_some()))
+            .collect();
+        self.metrics_map.add_metrics(key.to_string(), metrics);
+        Ok(names)
+    }
+
+    /// Save how the entities changed from the metrics saved under one key to those saved under
+    /// another, with the metrics among `metrics` that regressed and their deltas, in
+    /// `<output>/diff.<format>`
+    pub fn save_diff(
+        &self,
+        sink: &mut dyn OutputSink,
+        old_key: &str,
+        new_key: &str,
+        metrics: &[String],
+    ) -> Result<(), XStatsError> {
+        match self.metrics_map.compare(old_key, new_key) {
+            Some(diffs) => save_table(sink, "diff", diffs.get_review_table(metrics)),
+            None => Ok(()),
+        }
+    }
+
+    /// Save when and by which commit the budgets of the config were first exceeded, over the
+    /// commits of the history, or else from the baseline to the working tree once `run_baseline`
+    /// was called
+    pub fn save_budgets(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        if self.config.budgets.is_empty() {
+            return Ok(());
+        }
+        let mut snapshots = Vec::new();
+        if let Some(baseline_id) = &self.baseline_id {
+            let metrics = self.metrics_map.get_metrics(baseline_id);
+            let default_metrics = self.metrics_map.get_default_metrics();
+            if let (Some(metrics), Some(default_metrics)) = (metrics, default_metrics) {
+                snapshots.push(BudgetSnapshot {
+                    label: baseline_id.clone(),
+                    committed_at: None,
+                    metrics,
+                });
+                snapshots.push(BudgetSnapshot {
+                    label: "working_tree".to_string(),
+                    committed_at: None,
+                    metrics: default_metrics,
+                });
+            }
         } else {
-            format!("{}/metrics.csv", self.output_path)
+            for commit in &self.commit_infos.commits {
+                if let Some(metrics) = self.metrics_map.get_metrics(&commit.commit_id) {
+                    snapshots.push(BudgetSnapshot {
+                        label: commit.commit_id.clone(),
+                        committed_at: Some(commit.committed_at),
+                        metrics,
+                    });
+                }
+            }
+        }
+        if snapshots.is_empty() {
+            return Ok(());
+        }
+        let report = BudgetReport::new(&self.config.budgets, &snapshots);
+        save_table(sink, "budgets", report.get_table())
+    }
+
+    pub fn save_activity_metrics(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        if let Some(activity_metrics) = &self.activity_metrics {
+            save_table(sink, "activity", activity_metrics.get_table())?;
+        }
+        Ok(())
+    }
+
+    pub fn save_ownership(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        if let Some(ownership) = &self.ownership {
+            save_table(sink, "ownership", ownership.get_table())?;
+        }
+        Ok(())
+    }
+
+    pub fn save_scores(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        if let Some(metrics) = self.metrics_map.get_default_metrics() {
+            let scores = QualityScores::new(metrics, &self.config.score_weights, &self.target_path);
+            save_table(sink, "scores", scores.get_table())?;
+        }
+        Ok(())
+    }
+
+    /// Save the methods of different files at least `min_similarity` similar, from 0 to 1
+    pub fn save_near_duplicates(
+        &self,
+        sink: &mut dyn OutputSink,
+        min_similarity: f64,
+    ) -> Result<(), XStatsError> {
+        if let Some(metrics) = self.metrics_map.get_default_metrics() {
+            let near_duplicates = NearDuplicates::new(metrics, min_similarity);
+            save_table(sink, "near_duplicates", near_duplicates.get_table())?;
+        }
+        Ok(())
+    }
+
+    /// Save the number of empty catch blocks, bare `except:`, catch-alls, swallowed interrupts and
+    /// panicking calls of each language
+    pub fn save_robustness(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        if let Some(metrics) = self.metrics_map.get_default_metrics() {
+            save_table(
+                sink,
+                "robustness",
+                RobustnessReport::new(metrics).get_table(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Save the comment lines flagging unfinished work, e.g. `TODO`, with their file and line
+    pub fn save_todos(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        if let Some(metrics) = self.metrics_map.get_default_metrics() {
+            save_table(sink, "todos", MarkerReport::new(metrics).get_table())?;
+        }
+        Ok(())
+    }
+
+    /// Save the metrics of the files summed by package, with their mean by file
+    pub fn save_packages(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        if let Some(metrics) = self.metrics_map.get_default_metrics() {
+            let packages = PackageMetrics::new(metrics, &self.target_path);
+            save_table(sink, "packages", packages.get_table())?;
+        }
+        Ok(())
+    }
+
+    /// Save the naming conventions of the entities: whether their names follow the casing of
+    /// their language, which of their constants do not, and the lengths of their identifiers
+    pub fn save_naming(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        if let Some(metrics) = self.metrics_map.get_default_metrics() {
+            let report = NamingReport::new(metrics, &self.metrics_map.filter);
+            save_table(sink, "naming", report.get_table())?;
+        }
+        Ok(())
+    }
+
+    /// Save the statements of the long methods worth extracting into methods of their own, at
+    /// `suggestions.json`
+    pub fn save_suggestions(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        if let Some(metrics) = self.metrics_map.get_default_metrics() {
+            let suggestions = ExtractSuggestions::new(metrics, &self.metrics_map.filter);
+            let summary = serde_json::to_value(&suggestions.suggestions)
+                .map_err(|e| XStatsError::write("suggestions", e))?;
+            sink.write_summary("suggestions", &summary)?;
+        }
+        Ok(())
+    }
+
+    /// Save the totals of the default run, e.g. its files and the complexity of its methods, in
+    /// `<output>/summary.json`
+    pub fn save_summary(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        if let Some(summary) = self.metrics_map.get_summary() {
+            let summary =
+                serde_json::to_value(&summary).map_err(|e| XStatsError::write("summary", e))?;
+            sink.write_summary("summary", &summary)?;
+        }
+        Ok(())
+    }
+
+    /// Find the dependency cycles between the classes and packages of the default run
+    pub fn find_cycles(&self) -> Vec<DependencyCycle> {
+        find_cycles(&self.tdg, self.metrics_map.get_default_metrics())
+    }
+
+    /// Save the dependency cycles between classes and packages in `<output>/cycles.json`
+    pub fn save_cycles(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        let summary = serde_json::to_value(self.find_cycles())
+            .map_err(|e| XStatsError::write("cycles", e))?;
+        sink.write_summary("cycles", &summary)
+    }
+
+    pub fn save_metrics(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        self.save_metrics_table(sink, "metrics", None)
+    }
+
+    /// Save the metrics of the given commit, or the default ones, along with their typed records
+    fn save_metrics_table(
+        &self,
+        sink: &mut dyn OutputSink,
+        name: &str,
+        key: Option<&str>,
+    ) -> Result<(), XStatsError> {
+        let mut data = self.metrics_map.get_table(key);
+        let mut records = self.metrics_map.get_records(key);
+        // Only the metrics of the commits carry their provenance
+        if let Some(commit_id) = key.filter(|_| self.provenance.is_some()) {
+            data = self.add_provenance(data, Some(commit_id));
+            let names = Provenance::get_names();
+            for (record, row) in records.iter_mut().zip(data.iter().skip(1)) {
+                let values = &row[row.len() - names.len()..];
+                for (name, value) in names.iter().zip(values) {
+                    record[name] = serde_json::Value::from(value.as_str());
+                }
+            }
+        }
+
+        let Some((header, rows)) = data.split_first() else {
+            return Ok(());
         };
-        let data = self.metrics_map.get_table(metric_key);
-        if save_to_csv(&output_file, data).is_ok() {
-            println!("Code metrics saved at {}", output_file);
-        } else {
-            println!("Failed to save metrics to CSV");
+        let column_types = self.metrics_map.get_column_types(key, header);
+        sink.write_records(name, header, rows, &records, &column_types)
+    }
+
+    pub fn save_run_manifest(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        let manifest = RunManifest::new(&self.target_path, &self.config, &self.metrics_map.filter);
+        let summary =
+            serde_json::to_value(&manifest).map_err(|e| XStatsError::write("run_manifest", e))?;
+        sink.write_summary("run_manifest", &summary)
+    }
+
+    pub fn save_tdg(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        let node_metrics = self.get_node_metrics();
+        sink.write_graph("tdg", &self.tdg.to_dot(&node_metrics))?;
+        sink.write_json_graph("tdg", &self.tdg.to_json(&node_metrics))?;
+        if self.graphml {
+            sink.write_graphml("tdg", &self.tdg.to_graphml(&node_metrics))?;
         }
+        if self.gexf {
+            sink.write_gexf("tdg", &self.tdg.to_gexf(&node_metrics))?;
+        }
+        Ok(())
     }
 
-    pub fn save_data_as_json(&self, metric_key: Option<&str>) {
-        let output_file = if let Some(key) = metric_key {
-            format!("{}//metrics/{}.json", self.output_path, key)
-        } else {
-            format!("{}/metrics.json", self.output_path)
+    /// Save the threshold violations of the default metrics at `<output>/violations.sarif`
+    pub fn save_sarif(&self) -> Result<(), XStatsError> {
+        let file_path = format!("{}/violations.sarif", self.output_path);
+        save_to_sarif(&file_path, &self.check_thresholds(), self.force)?;
+        info!("violations saved at {}", file_path);
+        Ok(())
+    }
+
+    /// Save the results of the analysis at `file_path`, so that `import_state` can load them
+    /// back without analyzing the target again
+    pub fn export_state(&self, file_path: &str) -> Result<(), XStatsError> {
+        let state = AnalysisState {
+            target_path: self.target_path.clone(),
+            metrics: self
+                .metrics_map
+                .iter()
+                .map(|(key, metrics)| (key.clone(), metrics.metric_blocks.clone()))
+                .collect(),
+            changes: self
+                .changes_map
+                .iter()
+                .map(|(key, changes)| (key.clone(), changes.changes.clone()))
+                .collect(),
+            file_churns: self.churn_metrics.file_churns.clone(),
+            commits: self.commit_infos.commits.clone(),
+            tdg: self.tdg.get_state(),
+            call_graph: self.call_graph.get_state(),
+            method_names: self.call_graph.node_names.clone(),
+            baseline_id: self.baseline_id.clone(),
         };
-        let data: Vec<Vec<String>> = self.metrics_map.get_table(metric_key);
-        if save_to_json(&output_file, data).is_ok() {
-            println!("Code metrics saved at {}", output_file);
-        } else {
-            println!("Failed to save metrics to JSON");
+        state.save(file_path, self.force)?;
+        info!("state saved at {}", file_path);
+        Ok(())
+    }
+
+    /// Load the results of an analysis saved by `export_state`, replacing those of this one.
+    ///
+    /// The target becomes that of the saved analysis, the settings stay those of this one.
+    pub fn import_state(&mut self, file_path: &str) -> Result<(), XStatsError> {
+        let state = AnalysisState::load(file_path)?;
+        self.target_path = state.target_path;
+        self.metrics_map.metrics = state
+            .metrics
+            .into_iter()
+            .map(|(key, metric_blocks)| (key, CodeMetrics { metric_blocks }))
+            .collect();
+        self.changes_map.changes = state
+            .changes
+            .into_iter()
+            .map(|(key, changes)| (key, CodeChanges { changes }))
+            .collect();
+        self.churn_metrics.file_churns = state.file_churns;
+        self.commit_infos.commits = state.commits;
+        self.tdg = TypeDependencyGraph::from_state(state.tdg);
+        self.call_graph = CallGraph::from_state(state.call_graph, state.method_names);
+        self.baseline_id = state.baseline_id;
+        Ok(())
+    }
+
+    /// The sink saving the results as files in the output path, in the given format
+    pub fn get_sink(&self, format: &str) -> Result<Box<dyn OutputSink>, XStatsError> {
+        create_sink(
+            &self.output_path,
+            format,
+            self.csv_dialect.clone(),
+            self.force,
+        )
+    }
+}
+
+/// The byte ranges of the final content written by a sequence of edits, each of them moved by the
+/// edits that follow it
+fn get_edited_ranges(edits: &[InputEdit]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for edit in edits {
+        for range in &mut ranges {
+            if range.start >= edit.old_end_byte {
+                range.start = range.start + edit.new_end_byte - edit.old_end_byte;
+                range.end = range.end + edit.new_end_byte - edit.old_end_byte;
+            } else if range.end >= edit.start_byte {
+                range.start = range.start.min(edit.start_byte);
+                range.end = (range.end + edit.new_end_byte)
+                    .saturating_sub(edit.old_end_byte)
+                    .max(edit.new_end_byte);
+            }
         }
+        ranges.push(edit.start_byte..edit.new_end_byte);
     }
+    ranges
+}
+
+/// Send a table, whose first row is the header, to a sink
+fn save_table(
+    sink: &mut dyn OutputSink,
+    name: &str,
+    data: Vec<Vec<String>>,
+) -> Result<(), XStatsError> {
+    match data.split_first() {
+        Some((header, rows)) => sink.write_table(name, header, rows),
+        None => Ok(()),
+    }
+}
 
-    pub fn save_tdg(&self) {
-        let tdg_path = format!("{}/tdg.dot", self.output_path);
-        match self.tdg.export_to_dot(&tdg_path) {
-            Ok(_) => println!("Type Dependency Graph saved at {}", tdg_path),
-            Err(e) => println!("Failed to save Type Dependency Graph: {}", e),
+/// Send a table, whose first row is the header, to a sink along with the types of its columns
+fn save_typed_table(
+    sink: &mut dyn OutputSink,
+    name: &str,
+    data: Vec<Vec<String>>,
+    get_column_types: fn(&[String]) -> Vec<ColumnType>,
+) -> Result<(), XStatsError> {
+    match data.split_first() {
+        Some((header, rows)) => {
+            sink.write_typed_table(name, header, rows, &get_column_types(header))
         }
+        None => Ok(()),
     }
 }
@@ -0,0 +1,268 @@
+use super::{save_table, Progress, RunManifest, XStats};
+use crate::analysis::Hotspots;
+use crate::config::ConfigFile;
+use crate::error::XStatsError;
+use crate::metrics::{get_grade, QualityScores};
+use crate::output::OutputSink;
+use crate::utils::path_filter::PathFilter;
+use crate::utils::version_control::RepositoryError;
+use crate::utils::{read_file, write_atomically};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A repository of the portfolio
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PortfolioRepo {
+    pub name: String,
+    /// The path of the repository, relative to the manifest
+    pub path: String,
+}
+
+/// The repositories to compare, read from a `repos.toml` file.
+///
+/// # Fields
+///
+/// * `repos` - The name and path of each repository.
+/// * `trend-ref` - The revision each repository is compared with for its trend, e.g. `HEAD~50`.
+/// * `max-commits` - The number of most recent commits the hotspots are ranked from.
+/// * `top-hotspots` - The number of hotspots listed per repository.
+/// * `cache-dir` - Where the summary of each repository is kept, and reused while its HEAD and
+///   settings stay the same.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct PortfolioManifest {
+    pub repos: Vec<PortfolioRepo>,
+    pub trend_ref: Option<String>,
+    pub max_commits: usize,
+    pub top_hotspots: usize,
+    pub cache_dir: Option<String>,
+}
+
+impl Default for PortfolioManifest {
+    fn default() -> Self {
+        PortfolioManifest {
+            repos: Vec::new(),
+            trend_ref: None,
+            max_commits: 100,
+            top_hotspots: 3,
+            cache_dir: None,
+        }
+    }
+}
+
+impl PortfolioManifest {
+    /// Read a manifest, resolving the paths it contains relative to it
+    pub fn load(file_path: &str) -> Result<PortfolioManifest, XStatsError> {
+        let content = read_file(file_path)?;
+        let mut manifest: PortfolioManifest = toml::from_str(&content).map_err(|e| {
+            XStatsError::Config(format!("Invalid portfolio manifest {}: {}", file_path, e))
+        })?;
+
+        let manifest_dir = Path::new(file_path).parent().unwrap_or(Path::new(""));
+        let resolve = |path: &str| manifest_dir.join(path).to_string_lossy().to_string();
+        for repo in &mut manifest.repos {
+            repo.path = resolve(&repo.path);
+        }
+        manifest.cache_dir = manifest.cache_dir.as_deref().map(resolve);
+        Ok(manifest)
+    }
+}
+
+/// The grade, trend and hotspots of a repository
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RepoSummary {
+    pub name: String,
+    pub file_count: usize,
+    pub aloc: u32,
+    /// The quality score of the whole repository, if it has files to analyze
+    pub score: Option<f64>,
+    pub grade: Option<char>,
+    /// The change of score since the trend revision of the manifest
+    pub trend: Option<f64>,
+    /// The paths of the top hotspots, from the hottest
+    pub hotspots: Vec<String>,
+    /// The HEAD commit, settings and manifest options the summary was computed with, to tell
+    /// whether a cached summary is still valid
+    pub cache_key: Option<String>,
+}
+
+/// A comparison of several repositories, for reporting across teams
+pub struct Portfolio {
+    pub summaries: Vec<RepoSummary>,
+    /// The repositories that could not be analyzed, reported at the end of the run
+    pub warnings: Vec<String>,
+}
+
+impl Portfolio {
+    /// Analyze each repository of the manifest, or load its summary from the cache
+    pub fn new(manifest: &PortfolioManifest) -> Self {
+        Self::with_progress(manifest, &Progress::new())
+    }
+
+    /// Same as `new`, reporting the progress of the analysis of each repository
+    pub fn with_progress(manifest: &PortfolioManifest, progress: &Progress) -> Self {
+        let mut portfolio = Portfolio {
+            summaries: Vec::new(),
+            warnings: Vec::new(),
+        };
+        for repo in &manifest.repos {
+            match summarize_repo(repo, manifest, progress) {
+                Ok(summary) => portfolio.summaries.push(summary),
+                Err(e) => portfolio
+                    .warnings
+                    .push(format!("Failed to analyze {}: {}", repo.name, e)),
+            }
+        }
+        portfolio
+    }
+
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![[
+            "repo", "files", "aloc", "score", "grade", "trend", "hotspots",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()];
+
+        for summary in &self.summaries {
+            let format_score = |score: Option<f64>| {
+                score
+                    .map(|score| format!("{:.2}", score))
+                    .unwrap_or_default()
+            };
+            table.push(vec![
+                summary.name.clone(),
+                summary.file_count.to_string(),
+                summary.aloc.to_string(),
+                format_score(summary.score),
+                summary.grade.map(String::from).unwrap_or_default(),
+                format_score(summary.trend),
+                summary.hotspots.join(", "),
+            ]);
+        }
+
+        table
+    }
+
+    pub fn save(&self, sink: &mut dyn OutputSink) -> Result<(), XStatsError> {
+        save_table(sink, "portfolio", self.get_table())
+    }
+}
+
+fn summarize_repo(
+    repo: &PortfolioRepo,
+    manifest: &PortfolioManifest,
+    progress: &Progress,
+) -> Result<RepoSummary, XStatsError> {
+    let target_path = repo.path.trim_end_matches('/').to_string();
+    let config_file = ConfigFile::find(&target_path)?.unwrap_or_default();
+    let mut xstats = XStats::new(target_path.clone(), String::new());
+    xstats.progress = progress.clone();
+    xstats.config.set_languages(&config_file.languages)?;
+    xstats.config.path_filter =
+        PathFilter::from_patterns(&config_file.include, &config_file.exclude)?;
+    xstats.config.score_weights = config_file.score_weights;
+
+    // Uncommitted changes are not part of the commit, so their summary is never cached
+    let run_manifest = RunManifest::new(&target_path, &xstats.config, &xstats.metrics_map.filter);
+    let cache_key = match (run_manifest.commit, run_manifest.dirty) {
+        (Some(commit), Some(false)) => Some(format!(
+            "{}:{}:{}:{}:{}",
+            commit,
+            run_manifest.config_hash,
+            manifest.trend_ref.as_deref().unwrap_or_default(),
+            manifest.max_commits,
+            manifest.top_hotspots
+        )),
+        _ => None,
+    };
+    let cache_path = manifest
+        .cache_dir
+        .as_ref()
+        .map(|cache_dir| format!("{}/{}.json", cache_dir, repo.name));
+    if let (Some(cache_path), Some(_)) = (&cache_path, &cache_key) {
+        let cached = read_file(cache_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<RepoSummary>(&content).ok());
+        if let Some(summary) = cached.filter(|summary| summary.cache_key == cache_key) {
+            return Ok(summary);
+        }
+    }
+
+    xstats.run_default()?;
+    let (file_count, aloc, score) = get_repo_score(&xstats, None, &target_path);
+    let trend = match &manifest.trend_ref {
+        Some(trend_ref) => {
+            xstats.run_baseline(trend_ref)?;
+            let (_, _, old_score) =
+                get_repo_score(&xstats, xstats.baseline_id.as_deref(), &target_path);
+            score
+                .zip(old_score)
+                .map(|(score, old_score)| score - old_score)
+        }
+        None => None,
+    };
+
+    // Hotspots need the history, which is only walked from the root of a repository
+    let mut history = XStats::new(target_path.clone(), String::new());
+    history.progress = progress.clone();
+    history.config.path_filter = xstats.config.path_filter.clone();
+    history.revwalk_config.max_commits = Some(manifest.max_commits);
+    let hotspots = match history.run_multi_commit() {
+        Ok(()) => Hotspots::new(&history.churn_metrics, &history.metrics_map)
+            .hotspots
+            .into_iter()
+            .take(manifest.top_hotspots)
+            .map(|hotspot| hotspot.file_path)
+            .collect(),
+        Err(XStatsError::Repository(
+            RepositoryError::NotARepository(_) | RepositoryError::NoCommits(_),
+        )) => Vec::new(),
+        Err(e) => return Err(e),
+    };
+
+    let summary = RepoSummary {
+        name: repo.name.clone(),
+        file_count,
+        aloc,
+        score,
+        grade: score.map(get_grade),
+        trend,
+        hotspots,
+        cache_key,
+    };
+    if let (Some(cache_path), Some(_)) = (&cache_path, &summary.cache_key) {
+        write_atomically(cache_path, true, |file| {
+            serde_json::to_writer_pretty(file, &summary)
+        })?;
+    }
+    Ok(summary)
+}
+
+/// The number of files, the aloc and the quality score of the whole target, from the default
+/// metrics or those saved under the given key
+fn get_repo_score(
+    xstats: &XStats,
+    key: Option<&str>,
+    target_path: &str,
+) -> (usize, u32, Option<f64>) {
+    let metrics = match key {
+        Some(key) => xstats.metrics_map.get_metrics(&key.to_string()),
+        None => xstats.metrics_map.get_default_metrics(),
+    };
+    let Some(metrics) = metrics else {
+        return (0, 0, None);
+    };
+    let scores = QualityScores::new(metrics, &xstats.config.score_weights, target_path);
+    // A single file is its own root
+    let root = scores
+        .scores
+        .iter()
+        .find(|score| score.path == target_path)
+        .or_else(|| scores.scores.iter().find(|score| !score.is_directory));
+    match root {
+        Some(root) => (root.file_count, root.aloc, Some(root.score)),
+        None => (0, 0, None),
+    }
+}
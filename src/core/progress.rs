@@ -0,0 +1,62 @@
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+
+/// A step of an analysis, reported to the progress listeners as it happens
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProgressEvent {
+    /// The analysis of `total` files started, those of the target or those changed by a commit.
+    FilesStarted { total: usize },
+    /// A file started being analyzed, possibly on another thread than the others.
+    FileStarted { file_path: String },
+    /// A file was analyzed.
+    FileDone { file_path: String },
+    /// The files were analyzed, or the analysis stopped on timeout.
+    FilesDone,
+    /// The analysis of `total` commits started.
+    CommitsStarted { total: usize },
+    /// A commit was analyzed.
+    CommitDone { commit_id: String },
+    /// The commits were analyzed, or the analysis stopped on timeout.
+    CommitsDone,
+    /// A file was abandoned on timeout or the analysis was cut short, as added to the warnings.
+    Warning { message: String },
+}
+
+type Listener = Arc<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+/// Hands the progress events of an analysis to its listeners, e.g. the progress bars of the
+/// command line, a GUI frontend, or the log of a CI job.
+///
+/// Listeners are called on the thread the event happens on, as it happens, so they should be
+/// quick. Without listeners, the analysis runs silently.
+#[derive(Clone, Default)]
+pub struct Progress {
+    listeners: Vec<Listener>,
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Progress::default()
+    }
+
+    /// Call the listener on every event
+    pub fn add_listener(&mut self, listener: impl Fn(&ProgressEvent) + Send + Sync + 'static) {
+        self.listeners.push(Arc::new(listener));
+    }
+
+    /// Receive every event on a channel, e.g. to render them on another thread. The events are
+    /// dropped once the receiver is.
+    pub fn subscribe(&mut self) -> Receiver<ProgressEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.add_listener(move |event| {
+            let _ = sender.send(event.clone());
+        });
+        receiver
+    }
+
+    pub fn emit(&self, event: ProgressEvent) {
+        for listener in &self.listeners {
+            listener(&event);
+        }
+    }
+}
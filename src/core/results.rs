@@ -0,0 +1,32 @@
+use crate::metrics::{
+    CodeChange, CodeMetricBlock, CommitInfo, FileChurn, QualityScore, ThresholdViolation,
+};
+use std::collections::BTreeMap;
+
+/// The results of an analysis of the files of the target, for tools embedding xStats
+pub struct AnalysisResults {
+    /// The metrics of every file, class and method kept by the metrics filter.
+    pub metric_blocks: Vec<CodeMetricBlock>,
+    /// The quality score of every file and directory.
+    pub scores: Vec<QualityScore>,
+    /// The metrics exceeding their thresholds.
+    pub threshold_violations: Vec<ThresholdViolation>,
+    /// The metrics exceeding their `fail_on` thresholds, failing the quality gate.
+    pub quality_gate_violations: Vec<ThresholdViolation>,
+    /// The files abandoned on timeout and the analyses cut short.
+    pub warnings: Vec<String>,
+}
+
+/// The results of an analysis of the history of the target, for tools embedding xStats
+pub struct HistoryResults {
+    /// The commits analyzed, from the oldest to the most recent.
+    pub commits: Vec<CommitInfo>,
+    /// The entities touched by each commit, by commit id.
+    pub changes: BTreeMap<String, Vec<CodeChange>>,
+    /// The lines changed in each file by each commit.
+    pub churn: Vec<FileChurn>,
+    /// The metrics of every entity kept by the metrics filter, as of the most recent commit.
+    pub metric_blocks: Vec<CodeMetricBlock>,
+    /// The analyses cut short.
+    pub warnings: Vec<String>,
+}
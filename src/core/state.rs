@@ -0,0 +1,72 @@
+use crate::error::XStatsError;
+use crate::graph::{CallEdge, DependencyEdge, GraphState, NodeId};
+use crate::metrics::{CodeChange, CodeMetricBlock, CommitInfo, FileChurn};
+use crate::utils::binary_codec::{from_bytes, to_bytes};
+use crate::utils::write_atomically;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+
+/// The first bytes of a state file, followed by the xStats version it was exported with
+const MAGIC: &[u8] = b"XSTATS-STATE\n";
+
+/// The results of an analysis, saved to run the follow-up outputs, e.g. hotspots, graphs or the
+/// comparison with a baseline, without analyzing the target again.
+///
+/// The encoding follows the layout of the structs, so a state is only loaded by the xStats
+/// version that exported it.
+#[derive(Serialize, Deserialize)]
+pub struct AnalysisState {
+    pub target_path: String,
+    /// The metric blocks of every run, by commit id, or `default` for the default mode
+    pub metrics: BTreeMap<String, Vec<CodeMetricBlock>>,
+    /// The entities touched by every commit, by commit id
+    pub changes: BTreeMap<String, Vec<CodeChange>>,
+    pub file_churns: Vec<FileChurn>,
+    pub commits: Vec<CommitInfo>,
+    pub tdg: GraphState<DependencyEdge>,
+    pub call_graph: GraphState<CallEdge>,
+    /// The qualified names of the methods of the call graph
+    pub method_names: HashMap<NodeId, String>,
+    pub baseline_id: Option<String>,
+}
+
+impl AnalysisState {
+    pub fn save(&self, file_path: &str, overwrite: bool) -> Result<(), XStatsError> {
+        let content = to_bytes(self).map_err(|e| XStatsError::write(file_path, e))?;
+        write_atomically(file_path, overwrite, |file| {
+            file.write_all(MAGIC)?;
+            file.write_all(env!("CARGO_PKG_VERSION").as_bytes())?;
+            file.write_all(b"\n")?;
+            file.write_all(&content)
+        })
+    }
+
+    pub fn load(file_path: &str) -> Result<AnalysisState, XStatsError> {
+        let invalid = |reason: String| XStatsError::InvalidState {
+            path: file_path.to_string(),
+            reason,
+        };
+        let content = fs::read(file_path).map_err(|source| XStatsError::Read {
+            path: file_path.to_string(),
+            source,
+        })?;
+        let content = content
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| invalid("not an xStats state file".to_string()))?;
+        let version_end = content
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .ok_or_else(|| invalid("missing version".to_string()))?;
+        let version = String::from_utf8_lossy(&content[..version_end]);
+        if version != env!("CARGO_PKG_VERSION") {
+            return Err(invalid(format!(
+                "exported by xStats {}, not {}",
+                version,
+                env!("CARGO_PKG_VERSION")
+            )));
+        }
+        from_bytes(&content[version_end + 1..]).map_err(|e| invalid(e.to_string()))
+    }
+}
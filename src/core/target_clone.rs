@@ -0,0 +1,59 @@
+use crate::error::XStatsError;
+use crate::utils::version_control::{clone_repo, is_remote_url, open_repo};
+use log::info;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static CLONE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// A clone of the target in a temporary directory, to analyze a remote repository, or the files
+/// of a bare one, which has no working tree. The directory is removed when the clone is dropped.
+pub struct TargetClone {
+    path: PathBuf,
+}
+
+impl TargetClone {
+    /// Clone the target if it is the URL of a remote repository, fetching only the last `depth`
+    /// commits if given, or a bare repository unless `in_place`, e.g. for the history mode, which
+    /// only reads the git objects. Other targets are analyzed as they are, without a clone.
+    pub fn new(
+        target: &str,
+        in_place: bool,
+        depth: Option<u32>,
+    ) -> Result<Option<Self>, XStatsError> {
+        let depth = if is_remote_url(target) {
+            depth
+        } else if !in_place && open_repo(target).is_ok_and(|repo| repo.is_bare()) {
+            // The local transport cannot fetch a shallow history
+            None
+        } else {
+            return Ok(None);
+        };
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos());
+        let path = std::env::temp_dir().join(format!(
+            "xstats-clone-{}-{}-{}",
+            std::process::id(),
+            nanos,
+            CLONE_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        info!("Cloning {} into {}", target, path.display());
+        let target_clone = TargetClone { path };
+        clone_repo(target, &target_clone.path, depth)?;
+        Ok(Some(target_clone))
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TargetClone {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
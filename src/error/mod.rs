@@ -0,0 +1,74 @@
+use crate::config::Language;
+use crate::utils::version_control::RepositoryError;
+use thiserror::Error;
+
+/// Errors reported by xStats, instead of panicking or exiting the process.
+#[derive(Debug, Error)]
+pub enum XStatsError {
+    /// The target path does not exist.
+    #[error("Path does not exist: {0}")]
+    PathNotFound(String),
+    /// A path cannot be represented as UTF-8.
+    #[error("Path is not valid UTF-8: {0}")]
+    InvalidPath(String),
+    /// A file or directory could not be read.
+    #[error("Failed to read {path}: {source}")]
+    Read {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    /// An output file could not be written.
+    #[error("Failed to write {path}: {source}")]
+    Write {
+        path: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// An output file already exists, and overwriting it was not requested.
+    #[error("Output file already exists: {0} (use --force to overwrite it)")]
+    OutputExists(String),
+    /// A file took longer than the per-file timeout to analyze, and was abandoned.
+    #[error("Timed out analyzing {0}")]
+    Timeout(String),
+    /// A state file is not one exported by this version of xStats, or is corrupted.
+    #[error("Invalid state file {path}: {reason}")]
+    InvalidState { path: String, reason: String },
+    /// A table is not one saved by xStats as JSON, or by another version of its schema.
+    #[error("Invalid table {path}: {reason}")]
+    InvalidTable { path: String, reason: String },
+    /// The output format is not one of the supported output formats.
+    #[error("Unsupported format: {0}")]
+    UnsupportedFormat(String),
+    /// No parser is registered for the language.
+    #[error("Parser not found for language: {0}")]
+    ParserNotFound(Language),
+    /// The grammar of a language cannot be loaded, or lacks node kinds the analysis refers to.
+    #[error("Incompatible grammar {grammar} for {language}: {}", .problems.join("; "))]
+    IncompatibleGrammar {
+        language: Language,
+        grammar: String,
+        problems: Vec<String>,
+    },
+    /// A tree-sitter query does not compile against its grammar.
+    #[error("Invalid query: {0}")]
+    Query(#[from] tree_sitter::QueryError),
+    /// An invalid setting, e.g. a malformed `--doc-comment` value.
+    #[error("{0}")]
+    Config(String),
+    /// The target has no usable git history.
+    #[error(transparent)]
+    Repository(#[from] RepositoryError),
+    /// Any other failure reported by git.
+    #[error("Git error: {0}")]
+    Git(#[from] git2::Error),
+}
+
+impl XStatsError {
+    pub fn write(path: &str, source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        XStatsError::Write {
+            path: path.to_string(),
+            source: source.into(),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use crate::config::Language;
+use crate::utils::get_file_extension;
+use crate::utils::logger::WarningKind;
+use log::warn;
+use serde_json::Value;
+
+/// A piece of code embedded in a host file, analyzed as a standalone document.
+///
+/// # Fields
+///
+/// * `language` - The language of the embedded code.
+/// * `index` - The position of the sub-document among those of the host file.
+/// * `source_code` - The embedded code itself.
+/// * `row_offset` - The number of rows preceding the sub-document in the host file.
+/// * `col_offset` - The column of the first character of the sub-document in its first row.
+pub struct SubDocument {
+    pub language: Language,
+    pub index: usize,
+    pub source_code: String,
+    pub row_offset: u32,
+    pub col_offset: u32,
+}
+
+impl SubDocument {
+    /// Create a sub-document from a byte range of the host file
+    pub fn from_range(
+        language: Language,
+        index: usize,
+        host_source: &str,
+        start_byte: usize,
+        end_byte: usize,
+    ) -> Self {
+        let (row_offset, col_offset) = get_position(host_source, start_byte);
+        Self {
+            language,
+            index,
+            source_code: host_source[start_byte..end_byte].to_string(),
+            row_offset,
+            col_offset,
+        }
+    }
+}
+
+pub type Extractor = fn(&str) -> Vec<SubDocument>;
+
+pub fn get_extractor_info() -> Vec<(Extractor, Vec<&'static str>)> {
+    vec![
+        (extract_html_scripts, vec![".html", ".htm"]),
+        (extract_notebook_cells, vec![".ipynb"]),
+    ]
+}
+
+/// Get the extractor of a host file, if its extension is one of the supported host formats
+pub fn get_extractor(file_path: &str) -> Option<Extractor> {
+    let file_extension = get_file_extension(file_path);
+    get_extractor_info()
+        .into_iter()
+        .find(|(_, extensions)| extensions.contains(&file_extension.as_str()))
+        .map(|(extractor, _)| extractor)
+}
+
+/// Get the extractor of a host file of an unknown extension from its first lines, e.g. a JSP
+/// page or a template whose markup is HTML, or a notebook
+pub fn detect_extractor(source_code: &str) -> Option<Extractor> {
+    let head: String = source_code
+        .chars()
+        .take(1024)
+        .collect::<String>()
+        .to_lowercase();
+    let head = head.trim_start();
+    if head.starts_with('{') && head.contains("\"cells\"") {
+        Some(extract_notebook_cells)
+    } else if head.contains("<!doctype html") || head.contains("<html") {
+        Some(extract_html_scripts)
+    } else {
+        None
+    }
+}
+
+pub fn get_all_host_extensions() -> Vec<&'static str> {
+    get_extractor_info()
+        .into_iter()
+        .flat_map(|(_, extensions)| extensions)
+        .collect()
+}
+
+/// Get the 0-based (row, column) position of a byte offset in the source code
+fn get_position(source_code: &str, byte_offset: usize) -> (u32, u32) {
+    let preceding = &source_code[..byte_offset];
+    let row = preceding.matches('\n').count();
+    let col = match preceding.rfind('\n') {
+        Some(newline) => byte_offset - newline - 1,
+        None => byte_offset,
+    };
+    (row as u32, col as u32)
+}
+
+/// Get the value of an attribute from the text of an opening tag
+fn get_attribute(tag: &str, name: &str) -> Option<String> {
+    let pattern = format!("{}=", name);
+    let lower_tag = tag.to_ascii_lowercase();
+    let (start, _) = lower_tag
+        .match_indices(&pattern)
+        .find(|(idx, _)| lower_tag[..*idx].ends_with(char::is_whitespace))?;
+
+    let value = &tag[start + pattern.len()..];
+    match value.chars().next() {
+        Some(quote @ ('"' | '\'')) => value[1..].split(quote).next(),
+        _ => value.split(char::is_whitespace).next(),
+    }
+    .map(|v| v.to_string())
+}
+
+/// Map the `type` attribute of a `<script>` tag to a supported language
+fn get_script_language(script_type: &str) -> Option<Language> {
+    match script_type.trim().to_ascii_lowercase().as_str() {
+        // Scripts without a type are JavaScript
+        "" | "text/javascript" | "application/javascript" | "module" => Some(Language::JavaScript),
+        "text/python" | "text/x-python" => Some(Language::Python),
+        _ => None,
+    }
+}
+
+/// Extract the inline `<script>` blocks of an HTML document written in a supported language
+pub fn extract_html_scripts(source_code: &str) -> Vec<SubDocument> {
+    // ASCII lowercasing keeps the byte offsets of the original source
+    let lower_source = source_code.to_ascii_lowercase();
+    let mut sub_documents = Vec::new();
+    let mut search_from = 0;
+
+    while let Some(found) = lower_source[search_from..].find("<script") {
+        let tag_start = search_from + found;
+        let content_start = match lower_source[tag_start..].find('>') {
+            Some(end) => tag_start + end + 1,
+            None => break,
+        };
+        let content_end = match lower_source[content_start..].find("</script") {
+            Some(end) => content_start + end,
+            None => lower_source.len(),
+        };
+        search_from = content_end;
+
+        // External scripts have no content to analyze
+        if source_code[content_start..content_end].trim().is_empty() {
+            continue;
+        }
+
+        let tag = &source_code[tag_start..content_start - 1];
+        let script_type = get_attribute(tag, "type").unwrap_or_default();
+        if let Some(language) = get_script_language(&script_type) {
+            sub_documents.push(SubDocument::from_range(
+                language,
+                sub_documents.len(),
+                source_code,
+                content_start,
+                content_end,
+            ));
+        }
+    }
+
+    sub_documents
+}
+
+/// Get the language of a notebook from its metadata, defaulting to Python
+fn get_notebook_language(notebook: &Value) -> Option<Language> {
+    let metadata = &notebook["metadata"];
+    let language_name = metadata["kernelspec"]["language"]
+        .as_str()
+        .or_else(|| metadata["language_info"]["name"].as_str());
+
+    match language_name {
+        Some(name) => Language::from_name(name),
+        None => Some(Language::Python),
+    }
+}
+
+/// Extract the code cells of a Jupyter notebook.
+///
+/// Each code cell becomes a sub-document indexed by its position in the notebook. Cells are
+/// positioned one after the other, as they would be in the script exported from the notebook.
+pub fn extract_notebook_cells(source_code: &str) -> Vec<SubDocument> {
+    let notebook: Value = match serde_json::from_str(source_code) {
+        Ok(notebook) => notebook,
+        Err(e) => {
+            warn!(
+                target: WarningKind::BrokenParse.target(),
+                "Failed to parse notebook: {}",
+                e
+            );
+            return Vec::new();
+        }
+    };
+    let language = match get_notebook_language(&notebook) {
+        Some(language) => language,
+        None => return Vec::new(),
+    };
+    let cells = match notebook["cells"].as_array() {
+        Some(cells) => cells,
+        None => return Vec::new(),
+    };
+
+    let mut sub_documents = Vec::new();
+    let mut row_offset = 0;
+    for (index, cell) in cells.iter().enumerate() {
+        if cell["cell_type"].as_str() != Some("code") {
+            continue;
+        }
+
+        // The cell source is either a list of lines or a single string
+        let cell_source = match &cell["source"] {
+            Value::Array(lines) => lines.iter().filter_map(|line| line.as_str()).collect(),
+            Value::String(source) => source.clone(),
+            _ => String::new(),
+        };
+        if cell_source.trim().is_empty() {
+            continue;
+        }
+
+        let rows = cell_source.lines().count() as u32;
+        sub_documents.push(SubDocument {
+            language,
+            index,
+            source_code: cell_source,
+            row_offset,
+            col_offset: 0,
+        });
+        row_offset += rows;
+    }
+
+    sub_documents
+}
@@ -0,0 +1,156 @@
+use super::{NodeId, TypeDependencyGraph, USES_LABEL};
+use crate::metrics::{CodeMetrics, EntityKind};
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::{Graph, NodeIndex};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// A class or a package taking part in a dependency cycle
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct CycleMember {
+    /// The name of the class, or the directory of the package
+    pub name: String,
+    /// The file declaring the class, for classes only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+    /// The 1-based line the class starts at, for classes only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_row: Option<u32>,
+}
+
+/// A dependency between two members of a cycle, with the number of references it stands for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CycleDependency {
+    pub from: String,
+    pub to: String,
+    pub weight: usize,
+}
+
+/// Classes or packages depending on each other, directly or through the others
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DependencyCycle {
+    /// `class` or `package`
+    pub level: &'static str,
+    pub members: Vec<CycleMember>,
+    pub dependencies: Vec<CycleDependency>,
+}
+
+/// Find the dependency cycles between classes, and between the packages containing them, from
+/// the strongly connected components of the `uses` edges of the dependency graph.
+///
+/// A package is the directory of the files, e.g. `com/shop` for `com/shop/Cart.java`. Classes are
+/// named after their metric blocks, or their node when `metrics` has none for them. Components
+/// of a single member, e.g. a class using itself, are not cycles. The largest cycles come first.
+pub fn find_cycles(
+    tdg: &TypeDependencyGraph,
+    metrics: Option<&CodeMetrics>,
+) -> Vec<DependencyCycle> {
+    let class_names: HashMap<(&str, &str, u32, u32), &str> = metrics
+        .map(|metrics| {
+            metrics
+                .metric_blocks
+                .iter()
+                .filter(|block| block.meta_data.node_kind == EntityKind::Class)
+                .map(|block| {
+                    let meta_data = &block.meta_data;
+                    let key = (
+                        meta_data.file_path.as_str(),
+                        meta_data.node_type.as_str(),
+                        meta_data.start_row,
+                        meta_data.start_col,
+                    );
+                    (key, meta_data.node_name.as_str())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let get_class_member = |node: &NodeId| {
+        let key = (
+            node.file.as_str(),
+            node.kind.as_str(),
+            node.start_row as u32 + 1,
+            node.start_col as u32 + 1,
+        );
+        CycleMember {
+            name: class_names
+                .get(&key)
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| node.to_string()),
+            file_path: Some(node.file.clone()),
+            start_row: Some(node.start_row as u32 + 1),
+        }
+    };
+    let get_package_member = |node: &NodeId| CycleMember {
+        name: Path::new(&node.file)
+            .parent()
+            .map(|dir| dir.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default(),
+        file_path: None,
+        start_row: None,
+    };
+
+    let mut cycles = find_member_cycles("class", tdg, get_class_member);
+    cycles.extend(find_member_cycles("package", tdg, get_package_member));
+    cycles.sort_by(|a, b| {
+        b.members
+            .len()
+            .cmp(&a.members.len())
+            .then_with(|| (a.level, &a.members).cmp(&(b.level, &b.members)))
+    });
+    cycles
+}
+
+/// Find the cycles of the graph of the members the classes map to, the `uses` edges between
+/// classes of the same member being left out
+fn find_member_cycles(
+    level: &'static str,
+    tdg: &TypeDependencyGraph,
+    get_member: impl Fn(&NodeId) -> CycleMember,
+) -> Vec<DependencyCycle> {
+    let mut graph: Graph<CycleMember, usize> = Graph::new();
+    let mut member_indices: BTreeMap<CycleMember, NodeIndex> = BTreeMap::new();
+    let mut weights: BTreeMap<(NodeIndex, NodeIndex), usize> = BTreeMap::new();
+    for edge in tdg.graph.raw_edges() {
+        if edge.weight.label != USES_LABEL {
+            continue;
+        }
+        let [from, to] = [edge.source(), edge.target()].map(|idx| {
+            let member = get_member(&tdg.graph[idx]);
+            *member_indices
+                .entry(member.clone())
+                .or_insert_with(|| graph.add_node(member))
+        });
+        if from != to {
+            *weights.entry((from, to)).or_default() += edge.weight.weight;
+        }
+    }
+    for ((from, to), weight) in &weights {
+        graph.add_edge(*from, *to, *weight);
+    }
+
+    let mut cycles = Vec::new();
+    for component in tarjan_scc(&graph) {
+        if component.len() < 2 {
+            continue;
+        }
+        let mut members: Vec<CycleMember> =
+            component.iter().map(|idx| graph[*idx].clone()).collect();
+        members.sort();
+        let dependencies = weights
+            .iter()
+            .filter(|((from, to), _)| component.contains(from) && component.contains(to))
+            .map(|((from, to), weight)| CycleDependency {
+                from: graph[*from].name.clone(),
+                to: graph[*to].name.clone(),
+                weight: *weight,
+            })
+            .collect();
+        cycles.push(DependencyCycle {
+            level,
+            members,
+            dependencies,
+        });
+    }
+    cycles
+}
@@ -0,0 +1,964 @@
+use super::gexf::to_gexf;
+use super::graphml::{to_graphml, GraphMLKey};
+use super::{GraphState, NodeId, NodeMetricsIndex};
+use crate::config::Language;
+use crate::ts::{Node, Tree};
+use petgraph::dot::{Config, Dot};
+use petgraph::graph::{EdgeReference, Graph, NodeIndex};
+use petgraph::{Directed, Direction};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Formatter, Result};
+
+/// Maximum depth followed along a superclass chain, guarding against cycles.
+const MAX_SUPERCLASS_DEPTH: usize = 16;
+
+/// How confidently a call site was resolved to its callee.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum CallConfidence {
+    /// The receiver type is unknown and several methods share the callee's name and arity.
+    Low,
+    /// The receiver type is unknown but a single method has the callee's name and arity.
+    Medium,
+    /// The receiver type was resolved from imports, fields, parameters or local variables.
+    High,
+}
+
+impl Display for CallConfidence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let label = match self {
+            CallConfidence::Low => "low",
+            CallConfidence::Medium => "medium",
+            CallConfidence::High => "high",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// A caller → callee edge of the call graph, standing for all the calls between the two.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallEdge {
+    /// The confidence of the most confidently resolved call
+    pub confidence: CallConfidence,
+    /// Row (1-based) of the first call site in the caller's file
+    pub row: usize,
+    /// The number of call sites, i.e. how strongly the caller depends on the callee
+    pub weight: usize,
+}
+
+/// The attributes of the edges of the call graph in GraphML and GEXF
+const CALL_EDGE_KEYS: &[GraphMLKey] = &[
+    ("edge", "confidence", "string"),
+    ("edge", "row", "int"),
+    ("edge", "weight", "int"),
+];
+
+impl CallEdge {
+    /// The values of the attributes of `CALL_EDGE_KEYS`
+    fn get_data(&self) -> Vec<String> {
+        vec![
+            self.confidence.to_string(),
+            self.row.to_string(),
+            self.weight.to_string(),
+        ]
+    }
+}
+
+impl Display for CallEdge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.confidence)
+    }
+}
+
+/// What a call is made on, as far as it can be told from the caller's file alone.
+#[derive(Debug, Clone)]
+enum Receiver {
+    /// No receiver, or `this`
+    Implicit,
+    /// `super`
+    Super,
+    /// An expression of a known type, e.g. a parameter, a local variable or `new Type()`
+    Typed(String),
+    /// `this.<field>`
+    Field(String),
+    /// A bare or dotted name, either a field or a type for static calls
+    Name(String),
+    /// Any other expression, e.g. the result of another call
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+struct CallSite {
+    caller: NodeId,
+    class_index: usize,
+    name: String,
+    arity: usize,
+    receiver: Receiver,
+    is_constructor: bool,
+    row: usize,
+}
+
+#[derive(Debug, Clone)]
+struct MethodInfo {
+    id: NodeId,
+    name: String,
+    arity: usize,
+    is_constructor: bool,
+}
+
+#[derive(Debug, Clone)]
+struct ClassInfo {
+    name: String,
+    qualified_name: String,
+    superclass: Option<String>,
+    fields: HashMap<String, String>,
+    methods: Vec<MethodInfo>,
+}
+
+/// Declarations and call sites collected from a single file.
+#[derive(Debug, Clone, Default)]
+struct FileIndex {
+    imports: JavaImports,
+    classes: Vec<ClassInfo>,
+    calls: Vec<CallSite>,
+}
+
+/// A class, as the file it is declared in and its index among the file's classes.
+type ClassRef<'a> = (&'a str, usize);
+
+/// The call graph structure, between methods and constructors.
+///
+/// Files are indexed one by one with `process_tree`, then `resolve_calls` links every call site
+/// to its callees once all the declarations are known. Only Java files are indexed for now.
+pub struct CallGraph {
+    pub graph: Graph<NodeId, CallEdge, Directed>,
+    pub node_indices: HashMap<NodeId, NodeIndex>,
+    /// Qualified names of the methods, e.g. `com.example.Cart.total`
+    pub node_names: HashMap<NodeId, String>,
+    files: BTreeMap<String, FileIndex>,
+}
+
+impl Default for CallGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CallGraph {
+    pub fn new() -> Self {
+        Self {
+            graph: Graph::new(),
+            node_indices: HashMap::new(),
+            node_names: HashMap::new(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// The graph as saved by `get_state`, whose calls are already resolved
+    pub fn from_state(state: GraphState<CallEdge>, node_names: HashMap<NodeId, String>) -> Self {
+        let (graph, node_indices) = state.into_graph();
+        Self {
+            graph,
+            node_indices,
+            node_names,
+            files: BTreeMap::new(),
+        }
+    }
+
+    pub fn get_state(&self) -> GraphState<CallEdge> {
+        GraphState::new(&self.graph)
+    }
+
+    /// Index the declarations and call sites of a file, replacing any previous index of it.
+    pub fn process_tree(
+        &mut self,
+        file_path: &str,
+        language: &Language,
+        tree: &Tree,
+        source: &str,
+    ) {
+        if *language != Language::Java {
+            return;
+        }
+
+        let mut index = FileIndex::default();
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+        for child in root.named_children(&mut cursor) {
+            if !index.imports.add_declaration(&child, source) {
+                let package = index.imports.package.clone();
+                index_class(file_path, &child, source, &package, &mut index);
+            }
+        }
+
+        self.files.insert(file_path.to_string(), index);
+    }
+
+    /// Link the call sites of all the indexed files to their callees, rebuilding the graph.
+    pub fn resolve_calls(&mut self) {
+        self.graph.clear();
+        self.node_indices.clear();
+        self.node_names.clear();
+
+        let mut classes: HashMap<&str, ClassRef> = HashMap::new();
+        let mut methods: HashMap<(&str, usize), Vec<&NodeId>> = HashMap::new();
+        for (file_path, index) in &self.files {
+            for (class_index, class) in index.classes.iter().enumerate() {
+                classes.insert(&class.qualified_name, (file_path, class_index));
+                for method in &class.methods {
+                    self.node_names.insert(
+                        method.id.clone(),
+                        format!("{}.{}", class.qualified_name, method.name),
+                    );
+                    if !method.is_constructor {
+                        methods
+                            .entry((&method.name, method.arity))
+                            .or_default()
+                            .push(&method.id);
+                    }
+                }
+            }
+        }
+
+        let resolver = Resolver {
+            files: &self.files,
+            classes,
+        };
+        let mut edges = Vec::new();
+        for (file_path, index) in &self.files {
+            for call in &index.calls {
+                let callees = match resolver.resolve_receiver(file_path, call) {
+                    Target::Class(class) => resolver
+                        .find_methods(class, call)
+                        .into_iter()
+                        .map(|callee| (callee, CallConfidence::High))
+                        .collect(),
+                    Target::External => Vec::new(),
+                    Target::Unknown if call.is_constructor => Vec::new(),
+                    Target::Unknown => {
+                        let candidates = methods
+                            .get(&(call.name.as_str(), call.arity))
+                            .cloned()
+                            .unwrap_or_default();
+                        let confidence = if candidates.len() == 1 {
+                            CallConfidence::Medium
+                        } else {
+                            CallConfidence::Low
+                        };
+                        candidates
+                            .into_iter()
+                            .map(|callee| (callee.clone(), confidence))
+                            .collect()
+                    }
+                };
+                for (callee, confidence) in callees {
+                    edges.push((call.caller.clone(), callee, confidence, call.row));
+                }
+            }
+        }
+
+        // Every method is a node, even when it neither calls nor is called
+        let mut method_ids: Vec<NodeId> = self.node_names.keys().cloned().collect();
+        method_ids.sort_by(|a, b| (&a.file, a.start_byte).cmp(&(&b.file, b.start_byte)));
+        for id in method_ids {
+            self.add_node(id);
+        }
+        for (caller, callee, confidence, row) in edges {
+            self.add_call(
+                caller,
+                callee,
+                CallEdge {
+                    confidence,
+                    row,
+                    weight: 1,
+                },
+            );
+        }
+    }
+
+    /// Add a node if it doesn't exist, and return its index.
+    pub fn add_node(&mut self, node: NodeId) -> NodeIndex {
+        if let Some(&idx) = self.node_indices.get(&node) {
+            idx
+        } else {
+            let idx = self.graph.add_node(node.clone());
+            self.node_indices.insert(node, idx);
+            idx
+        }
+    }
+
+    /// Add a call edge from the caller to the callee, or merge it into the edge already
+    /// between them, adding up their weights.
+    pub fn add_call(&mut self, caller: NodeId, callee: NodeId, edge: CallEdge) {
+        let caller_idx = self.add_node(caller);
+        let callee_idx = self.add_node(callee);
+        match self.graph.find_edge(caller_idx, callee_idx) {
+            Some(edge_idx) => {
+                let existing = &mut self.graph[edge_idx];
+                existing.confidence = existing.confidence.max(edge.confidence);
+                existing.row = existing.row.min(edge.row);
+                existing.weight += edge.weight;
+            }
+            None => {
+                self.graph.add_edge(caller_idx, callee_idx, edge);
+            }
+        }
+    }
+
+    /// Number of distinct methods calling the given one, through edges at least as confident as
+    /// `min_confidence`.
+    pub fn fan_in(&self, method: &NodeId, min_confidence: CallConfidence) -> usize {
+        self.count_neighbors(method, min_confidence, Direction::Incoming)
+    }
+
+    /// Number of distinct methods called by the given one, through edges at least as confident as
+    /// `min_confidence`.
+    pub fn fan_out(&self, method: &NodeId, min_confidence: CallConfidence) -> usize {
+        self.count_neighbors(method, min_confidence, Direction::Outgoing)
+    }
+
+    fn count_neighbors(
+        &self,
+        method: &NodeId,
+        min_confidence: CallConfidence,
+        direction: Direction,
+    ) -> usize {
+        let Some(&idx) = self.node_indices.get(method) else {
+            return 0;
+        };
+        let mut neighbors = HashSet::new();
+        let mut edges = self.graph.neighbors_directed(idx, direction).detach();
+        while let Some((edge, neighbor)) = edges.next(&self.graph) {
+            if self.graph[edge].confidence >= min_confidence {
+                neighbors.insert(neighbor);
+            }
+        }
+        neighbors.len()
+    }
+
+    /// One row per call edge, with the caller, the callee, the confidence of the resolution and
+    /// the number of calls.
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut data = vec![vec![
+            "caller_file".to_string(),
+            "caller".to_string(),
+            "callee_file".to_string(),
+            "callee".to_string(),
+            "row".to_string(),
+            "confidence".to_string(),
+            "weight".to_string(),
+        ]];
+
+        let mut rows = Vec::new();
+        for edge in self.graph.raw_edges() {
+            let caller = &self.graph[edge.source()];
+            let callee = &self.graph[edge.target()];
+            rows.push((
+                (caller.file.clone(), caller.start_byte, edge.weight.row),
+                vec![
+                    caller.file.clone(),
+                    self.get_name(caller),
+                    callee.file.clone(),
+                    self.get_name(callee),
+                    edge.weight.row.to_string(),
+                    edge.weight.confidence.to_string(),
+                    edge.weight.weight.to_string(),
+                ],
+            ));
+        }
+        rows.sort_by(|a, b| a.0.cmp(&b.0));
+        data.extend(rows.into_iter().map(|(_, row)| row));
+
+        data
+    }
+
+    /// Render the call graph in the DOT format, with the qualified names of the methods as labels,
+    /// the confidence of the calls as edge labels, their number as edge weights and the key
+    /// metrics of each method as attributes.
+    pub fn to_dot(&self, node_metrics: &NodeMetricsIndex) -> String {
+        let get_edge_attributes =
+            |_, edge: EdgeReference<CallEdge>| format!("weight = {}", edge.weight().weight);
+        let get_node_attributes = |_, (_, node)| {
+            format!(
+                "label = \"{}\" {}",
+                self.get_name(node),
+                node_metrics.get_dot_attributes(node)
+            )
+        };
+        let dot = Dot::with_attr_getters(
+            &self.graph,
+            &[Config::NodeNoLabel],
+            &get_edge_attributes,
+            &get_node_attributes,
+        );
+        dot.to_string()
+    }
+
+    /// Render the call graph in the GraphML format, with the qualified names of the methods as
+    /// labels, the confidence, first row and number of the calls on the edges and the key
+    /// metrics of the methods
+    pub fn to_graphml(&self, node_metrics: &NodeMetricsIndex) -> String {
+        to_graphml(
+            &self.graph,
+            node_metrics,
+            |node| self.get_name(node),
+            CALL_EDGE_KEYS,
+            CallEdge::get_data,
+        )
+    }
+
+    /// Render the call graph in the GEXF format, with the same attributes as in GraphML
+    pub fn to_gexf(&self, node_metrics: &NodeMetricsIndex) -> String {
+        to_gexf(
+            &self.graph,
+            node_metrics,
+            |node| self.get_name(node),
+            CALL_EDGE_KEYS,
+            CallEdge::get_data,
+        )
+    }
+
+    fn get_name(&self, node: &NodeId) -> String {
+        self.node_names
+            .get(node)
+            .cloned()
+            .unwrap_or_else(|| node.to_string())
+    }
+}
+
+/// Where a call site was resolved to.
+enum Target<'a> {
+    /// A class of the analyzed project
+    Class(ClassRef<'a>),
+    /// A type outside the analyzed project, e.g. from the standard library
+    External,
+    /// The receiver type could not be inferred
+    Unknown,
+}
+
+struct Resolver<'a> {
+    files: &'a BTreeMap<String, FileIndex>,
+    classes: HashMap<&'a str, ClassRef<'a>>,
+}
+
+impl<'a> Resolver<'a> {
+    fn get_class(&self, class: ClassRef<'a>) -> &'a ClassInfo {
+        &self.files[class.0].classes[class.1]
+    }
+
+    fn resolve_receiver(&self, file_path: &'a str, call: &CallSite) -> Target<'a> {
+        let class = (file_path, call.class_index);
+        let to_target = |resolved: Option<ClassRef<'a>>| match resolved {
+            Some(class) => Target::Class(class),
+            None => Target::External,
+        };
+
+        match &call.receiver {
+            Receiver::Implicit => Target::Class(class),
+            Receiver::Super => match self.get_superclass(class) {
+                Some(superclass) => Target::Class(superclass),
+                None => Target::External,
+            },
+            Receiver::Typed(type_name) => to_target(self.resolve_type(file_path, type_name)),
+            Receiver::Field(field) => match self.get_field_type(class, field) {
+                Some((owner, field_type)) => to_target(self.resolve_type(owner.0, field_type)),
+                None => Target::Unknown,
+            },
+            Receiver::Name(name) => {
+                let (first, rest) = match name.split_once('.') {
+                    Some((first, rest)) => (first, Some(rest)),
+                    None => (name.as_str(), None),
+                };
+                if let Some((owner, field_type)) = self.get_field_type(class, first) {
+                    return match rest {
+                        None => to_target(self.resolve_type(owner.0, field_type)),
+                        Some(_) => Target::Unknown,
+                    };
+                }
+                if let Some(resolved) = self.resolve_type(file_path, name) {
+                    return Target::Class(resolved);
+                }
+                // By convention only types start with an uppercase letter, e.g. `Math.max()`
+                if first.starts_with(|c: char| c.is_uppercase()) {
+                    Target::External
+                } else {
+                    Target::Unknown
+                }
+            }
+            Receiver::Unknown => Target::Unknown,
+        }
+    }
+
+    /// Resolve a type name, as written in the given file, to a class of the project.
+    fn resolve_type(&self, file_path: &'a str, type_name: &str) -> Option<ClassRef<'a>> {
+        let index = &self.files[file_path];
+        let local_classes = index
+            .classes
+            .iter()
+            .map(|class| (class.name.as_str(), class.qualified_name.as_str()));
+        index
+            .imports
+            .get_candidates(local_classes, type_name)
+            .iter()
+            .find_map(|candidate| self.classes.get(candidate.as_str()).copied())
+    }
+
+    fn get_superclass(&self, class: ClassRef<'a>) -> Option<ClassRef<'a>> {
+        let superclass = self.get_class(class).superclass.as_ref()?;
+        self.resolve_type(class.0, superclass)
+    }
+
+    /// Find the declared type of a field, in the class or its superclasses, along with the class
+    /// declaring it.
+    fn get_field_type(&self, class: ClassRef<'a>, field: &str) -> Option<(ClassRef<'a>, &'a str)> {
+        let mut current = Some(class);
+        for _ in 0..MAX_SUPERCLASS_DEPTH {
+            let owner = current?;
+            if let Some(field_type) = self.get_class(owner).fields.get(field) {
+                return Some((owner, field_type));
+            }
+            current = self.get_superclass(owner);
+        }
+        None
+    }
+
+    /// Find the methods a call may reach in the class, or in its superclasses for methods.
+    fn find_methods(&self, class: ClassRef<'a>, call: &CallSite) -> Vec<NodeId> {
+        let mut current = Some(class);
+        for _ in 0..MAX_SUPERCLASS_DEPTH {
+            let Some(owner) = current else {
+                break;
+            };
+            let candidates: Vec<&MethodInfo> = self
+                .get_class(owner)
+                .methods
+                .iter()
+                .filter(|m| m.name == call.name && m.is_constructor == call.is_constructor)
+                .collect();
+            // Prefer the overloads taking as many arguments as given, varargs aside
+            let matching: Vec<&MethodInfo> = candidates
+                .iter()
+                .filter(|m| m.arity == call.arity)
+                .copied()
+                .collect();
+            let found = if matching.is_empty() {
+                candidates
+            } else {
+                matching
+            };
+            if !found.is_empty() {
+                return found.into_iter().map(|m| m.id.clone()).collect();
+            }
+            // Constructors are not inherited
+            if call.is_constructor {
+                break;
+            }
+            current = self.get_superclass(owner);
+        }
+        Vec::new()
+    }
+}
+
+/// The package and imports of a Java file, which the types it names are resolved from
+#[derive(Debug, Clone, Default)]
+pub(super) struct JavaImports {
+    pub package: String,
+    pub imports: Vec<String>,
+    pub wildcard_imports: Vec<String>,
+}
+
+impl JavaImports {
+    /// Add a top-level declaration of the file if it is its package or an import, and tell
+    /// whether it was
+    pub fn add_declaration(&mut self, node: &Node, source: &str) -> bool {
+        match node.kind() {
+            "package_declaration" => {
+                if let Some(name) = node.named_child(0) {
+                    self.package = get_text(&name, source).to_string();
+                }
+            }
+            "import_declaration" => {
+                let import = get_text(node, source)
+                    .trim_start_matches("import")
+                    .trim_end_matches(';')
+                    .trim();
+                // Static imports name members, not types
+                if import.starts_with("static ") {
+                    return true;
+                }
+                let import: String = import.chars().filter(|c| !c.is_whitespace()).collect();
+                match import.strip_suffix(".*") {
+                    Some(package) => self.wildcard_imports.push(package.to_string()),
+                    None => self.imports.push(import),
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// The qualified names a type name may stand for, from the most likely: a class declared
+    /// in the file, given with its simple and qualified names, an import, the package, a wildcard
+    /// import, and the name itself as fully qualified.
+    pub fn get_candidates<'b>(
+        &self,
+        local_classes: impl Iterator<Item = (&'b str, &'b str)>,
+        type_name: &str,
+    ) -> Vec<String> {
+        let (first, rest) = match type_name.split_once('.') {
+            Some((first, rest)) => (first, Some(rest)),
+            None => (type_name, None),
+        };
+
+        let mut candidates = Vec::new();
+        if let Some((_, qualified_name)) =
+            local_classes.into_iter().find(|(name, _)| *name == first)
+        {
+            candidates.push(qualified_name.to_string());
+        }
+        for import in &self.imports {
+            if import.rsplit('.').next() == Some(first) {
+                candidates.push(import.clone());
+            }
+        }
+        if self.package.is_empty() {
+            candidates.push(first.to_string());
+        } else {
+            candidates.push(format!("{}.{}", self.package, first));
+        }
+        for package in &self.wildcard_imports {
+            candidates.push(format!("{}.{}", package, first));
+        }
+
+        let mut candidates: Vec<String> = candidates
+            .into_iter()
+            .map(|candidate| match rest {
+                Some(rest) => format!("{}.{}", candidate, rest),
+                None => candidate,
+            })
+            .collect();
+        candidates.push(type_name.to_string());
+        candidates
+    }
+}
+
+pub(super) fn get_text<'a>(node: &Node, source: &'a str) -> &'a str {
+    &source[node.start_byte()..node.end_byte()]
+}
+
+/// Reduce a type as written in the source to the name of the type, e.g. `List<String>[]` to `List`.
+pub(super) fn get_type_name(node: &Node, source: &str) -> String {
+    let text = get_text(node, source);
+    let text = text.split(['<', '[']).next().unwrap_or(text);
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+pub(super) fn is_class_like(kind: &str) -> bool {
+    matches!(
+        kind,
+        "class_declaration" | "interface_declaration" | "enum_declaration" | "record_declaration"
+    )
+}
+
+/// Index a class declaration, its members and its nested classes.
+fn index_class(file_path: &str, node: &Node, source: &str, outer: &str, index: &mut FileIndex) {
+    if !is_class_like(node.kind()) {
+        return;
+    }
+    let Some(name_node) = node.child_by_field_name("name") else {
+        return;
+    };
+    let name = get_text(&name_node, source).to_string();
+    let qualified_name = if outer.is_empty() {
+        name.clone()
+    } else {
+        format!("{}.{}", outer, name)
+    };
+    let superclass = node
+        .child_by_field_name("superclass")
+        .and_then(|superclass| superclass.named_child(0))
+        .map(|superclass| get_type_name(&superclass, source));
+
+    let class_index = index.classes.len();
+    index.classes.push(ClassInfo {
+        name,
+        qualified_name: qualified_name.clone(),
+        superclass,
+        fields: HashMap::new(),
+        methods: Vec::new(),
+    });
+
+    let Some(body) = node.child_by_field_name("body") else {
+        return;
+    };
+    let mut members = Vec::new();
+    let mut cursor = body.walk();
+    for member in body.named_children(&mut cursor) {
+        // Enum members follow the enum constants
+        if member.kind() == "enum_body_declarations" {
+            let mut member_cursor = member.walk();
+            members.extend(member.named_children(&mut member_cursor));
+        } else {
+            members.push(member);
+        }
+    }
+
+    for member in members {
+        match member.kind() {
+            "field_declaration" => {
+                let Some(type_node) = member.child_by_field_name("type") else {
+                    continue;
+                };
+                let field_type = get_type_name(&type_node, source);
+                let mut declarator_cursor = member.walk();
+                for declarator in
+                    member.children_by_field_name("declarator", &mut declarator_cursor)
+                {
+                    if let Some(field_name) = declarator.child_by_field_name("name") {
+                        index.classes[class_index].fields.insert(
+                            get_text(&field_name, source).to_string(),
+                            field_type.clone(),
+                        );
+                    }
+                }
+            }
+            "method_declaration" | "constructor_declaration" => {
+                let Some(method_name) = member.child_by_field_name("name") else {
+                    continue;
+                };
+                let id = NodeId::from_node(file_path, &member);
+                index.classes[class_index].methods.push(MethodInfo {
+                    id: id.clone(),
+                    name: get_text(&method_name, source).to_string(),
+                    arity: count_parameters(&member),
+                    is_constructor: member.kind() == "constructor_declaration",
+                });
+
+                let locals = collect_locals(&member, source);
+                if let Some(method_body) = member.child_by_field_name("body") {
+                    collect_calls(&method_body, source, &id, class_index, &locals, index);
+                }
+            }
+            kind if is_class_like(kind) => {
+                index_class(file_path, &member, source, &qualified_name, index);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn count_parameters(method: &Node) -> usize {
+    let Some(parameters) = method.child_by_field_name("parameters") else {
+        return 0;
+    };
+    let mut cursor = parameters.walk();
+    let count = parameters
+        .named_children(&mut cursor)
+        .filter(|p| matches!(p.kind(), "formal_parameter" | "spread_parameter"))
+        .count();
+    count
+}
+
+/// A parameter or local variable, with its type if it could be inferred.
+struct Local {
+    name: String,
+    type_name: Option<String>,
+    start_byte: usize,
+}
+
+/// Collect the parameters and local variables declared in a method.
+fn collect_locals(method: &Node, source: &str) -> Vec<Local> {
+    fn visit(node: &Node, source: &str, locals: &mut Vec<Local>) {
+        match node.kind() {
+            "formal_parameter"
+            | "catch_formal_parameter"
+            | "enhanced_for_statement"
+            | "resource" => {
+                let type_node = node.child_by_field_name("type").or_else(|| {
+                    // The catch clause type is not a field
+                    let mut cursor = node.walk();
+                    let type_node = node
+                        .named_children(&mut cursor)
+                        .find(|c| c.kind() == "catch_type");
+                    type_node
+                });
+                if let (Some(type_node), Some(name)) = (type_node, node.child_by_field_name("name"))
+                {
+                    locals.push(Local {
+                        name: get_text(&name, source).to_string(),
+                        type_name: Some(get_type_name(&type_node, source)),
+                        start_byte: node.start_byte(),
+                    });
+                }
+            }
+            "local_variable_declaration" => {
+                if let Some(type_node) = node.child_by_field_name("type") {
+                    let declared_type = get_type_name(&type_node, source);
+                    let mut cursor = node.walk();
+                    for declarator in node.children_by_field_name("declarator", &mut cursor) {
+                        let Some(name) = declarator.child_by_field_name("name") else {
+                            continue;
+                        };
+                        // `var` takes the type of the created object, if any
+                        let type_name = if declared_type == "var" {
+                            declarator
+                                .child_by_field_name("value")
+                                .filter(|value| value.kind() == "object_creation_expression")
+                                .and_then(|value| value.child_by_field_name("type"))
+                                .map(|type_node| get_type_name(&type_node, source))
+                        } else {
+                            Some(declared_type.clone())
+                        };
+                        locals.push(Local {
+                            name: get_text(&name, source).to_string(),
+                            type_name,
+                            start_byte: node.start_byte(),
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            if child.kind() != "class_body" {
+                visit(&child, source, locals);
+            }
+        }
+    }
+
+    let mut locals = Vec::new();
+    visit(method, source, &mut locals);
+    locals
+}
+
+/// Collect the method invocations and object creations in a method body.
+fn collect_calls(
+    node: &Node,
+    source: &str,
+    caller: &NodeId,
+    class_index: usize,
+    locals: &[Local],
+    index: &mut FileIndex,
+) {
+    let arity = |call: &Node| {
+        call.child_by_field_name("arguments")
+            .map_or(0, |arguments| arguments.named_child_count())
+    };
+
+    match node.kind() {
+        "method_invocation" => {
+            if let Some(name) = node.child_by_field_name("name") {
+                let receiver = match node.child_by_field_name("object") {
+                    Some(object) => get_receiver(&object, source, locals, node.start_byte()),
+                    None => Receiver::Implicit,
+                };
+                index.calls.push(CallSite {
+                    caller: caller.clone(),
+                    class_index,
+                    name: get_text(&name, source).to_string(),
+                    arity: arity(node),
+                    receiver,
+                    is_constructor: false,
+                    row: node.start_position().row + 1,
+                });
+            }
+        }
+        "object_creation_expression" => {
+            if let Some(type_node) = node.child_by_field_name("type") {
+                let type_name = get_type_name(&type_node, source);
+                // Constructors are named after the simple name of their class
+                let name = type_name
+                    .rsplit('.')
+                    .next()
+                    .unwrap_or(&type_name)
+                    .to_string();
+                index.calls.push(CallSite {
+                    caller: caller.clone(),
+                    class_index,
+                    name,
+                    arity: arity(node),
+                    receiver: Receiver::Typed(type_name),
+                    is_constructor: true,
+                    row: node.start_position().row + 1,
+                });
+            }
+        }
+        _ => {}
+    }
+
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        // Anonymous and local classes have calls of their own
+        if child.kind() != "class_body" && !is_class_like(child.kind()) {
+            collect_calls(&child, source, caller, class_index, locals, index);
+        }
+    }
+}
+
+fn get_receiver(object: &Node, source: &str, locals: &[Local], call_start: usize) -> Receiver {
+    match object.kind() {
+        "this" => Receiver::Implicit,
+        "super" => Receiver::Super,
+        "identifier" => {
+            let name = get_text(object, source);
+            // The closest declaration preceding the call shadows fields and types
+            let local = locals
+                .iter()
+                .rev()
+                .find(|local| local.name == name && local.start_byte < call_start);
+            match local {
+                Some(Local {
+                    type_name: Some(type_name),
+                    ..
+                }) => Receiver::Typed(type_name.clone()),
+                Some(_) => Receiver::Unknown,
+                None => Receiver::Name(name.to_string()),
+            }
+        }
+        "field_access" => {
+            let field_object = object.child_by_field_name("object");
+            let field = object.child_by_field_name("field");
+            match (field_object, field) {
+                (Some(field_object), Some(field)) if field_object.kind() == "this" => {
+                    Receiver::Field(get_text(&field, source).to_string())
+                }
+                _ => {
+                    let name = get_type_name(object, source);
+                    let is_name = name
+                        .split('.')
+                        .all(|part| part.chars().all(|c| c.is_alphanumeric() || c == '_'));
+                    if is_name {
+                        get_receiver_name(name, locals, call_start)
+                    } else {
+                        Receiver::Unknown
+                    }
+                }
+            }
+        }
+        "scoped_identifier" | "type_identifier" => Receiver::Name(get_type_name(object, source)),
+        "object_creation_expression" => match object.child_by_field_name("type") {
+            Some(type_node) => Receiver::Typed(get_type_name(&type_node, source)),
+            None => Receiver::Unknown,
+        },
+        "parenthesized_expression" => match object.named_child(0) {
+            Some(inner) => get_receiver(&inner, source, locals, call_start),
+            None => Receiver::Unknown,
+        },
+        _ => Receiver::Unknown,
+    }
+}
+
+/// A dotted name starting with a local variable is an expression on it, not a type.
+fn get_receiver_name(name: String, locals: &[Local], call_start: usize) -> Receiver {
+    let first = name.split('.').next().unwrap_or(&name);
+    let is_local = locals
+        .iter()
+        .any(|local| local.name == first && local.start_byte < call_start);
+    if is_local {
+        Receiver::Unknown
+    } else {
+        Receiver::Name(name)
+    }
+}
@@ -0,0 +1,95 @@
+use super::graphml::{escape, get_node_data, GraphMLKey, NODE_KEYS};
+use super::{NodeId, NodeMetricsIndex};
+use petgraph::graph::Graph;
+use petgraph::Directed;
+use std::fmt::Write;
+
+/// Render a graph of entities in the GEXF format, e.g. for Gephi or NetworkX.
+///
+/// Nodes carry the same attributes as in GraphML, their label being that of the GEXF node. Edges
+/// carry the attributes of `edge_keys`, the one named `weight` also being the GEXF weight of the
+/// edge.
+pub fn to_gexf<E>(
+    graph: &Graph<NodeId, E, Directed>,
+    node_metrics: &NodeMetricsIndex,
+    get_label: impl Fn(&NodeId) -> String,
+    edge_keys: &[GraphMLKey],
+    get_edge_data: impl Fn(&E) -> Vec<String>,
+) -> String {
+    let mut gexf = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <gexf xmlns=\"http://gexf.net/1.3\" version=\"1.3\">\n  \
+         <graph defaultedgetype=\"directed\">\n",
+    );
+    // The label is an attribute of the GEXF node itself
+    let node_keys = &NODE_KEYS[1..];
+    write_attributes(&mut gexf, "node", node_keys);
+    write_attributes(&mut gexf, "edge", edge_keys);
+
+    gexf.push_str("    <nodes>\n");
+    for idx in graph.node_indices() {
+        let data = get_node_data(&graph[idx], node_metrics, &get_label);
+        let _ = writeln!(
+            gexf,
+            "      <node id=\"n{}\" label=\"{}\">",
+            idx.index(),
+            escape(&data[0])
+        );
+        write_attvalues(&mut gexf, node_keys, &data[1..]);
+        gexf.push_str("      </node>\n");
+    }
+    gexf.push_str("    </nodes>\n    <edges>\n");
+
+    let weight_idx = edge_keys.iter().position(|(_, name, _)| *name == "weight");
+    for (idx, edge) in graph.raw_edges().iter().enumerate() {
+        let data = get_edge_data(&edge.weight);
+        let weight = weight_idx
+            .and_then(|weight_idx| data.get(weight_idx))
+            .map(|weight| format!(" weight=\"{}\"", weight))
+            .unwrap_or_default();
+        let _ = writeln!(
+            gexf,
+            "      <edge id=\"e{}\" source=\"n{}\" target=\"n{}\"{}>",
+            idx,
+            edge.source().index(),
+            edge.target().index(),
+            weight
+        );
+        write_attvalues(&mut gexf, edge_keys, &data);
+        gexf.push_str("      </edge>\n");
+    }
+
+    gexf.push_str("    </edges>\n  </graph>\n</gexf>\n");
+    gexf
+}
+
+/// Declare the attributes of the nodes or edges, with the GEXF names of their GraphML types
+fn write_attributes(gexf: &mut String, class: &str, keys: &[GraphMLKey]) {
+    let _ = writeln!(gexf, "    <attributes class=\"{}\">", class);
+    for (_, name, attr_type) in keys {
+        let attr_type = match *attr_type {
+            "int" => "integer",
+            attr_type => attr_type,
+        };
+        let _ = writeln!(
+            gexf,
+            "      <attribute id=\"{0}\" title=\"{0}\" type=\"{1}\"/>",
+            name, attr_type
+        );
+    }
+    gexf.push_str("    </attributes>\n");
+}
+
+/// Write the values of the attributes of a node or an edge, those without a value being left out
+fn write_attvalues(gexf: &mut String, keys: &[GraphMLKey], values: &[String]) {
+    gexf.push_str("        <attvalues>\n");
+    for ((_, name, _), value) in keys.iter().zip(values) {
+        let _ = writeln!(
+            gexf,
+            "          <attvalue for=\"{}\" value=\"{}\"/>",
+            name,
+            escape(value)
+        );
+    }
+    gexf.push_str("        </attvalues>\n");
+}
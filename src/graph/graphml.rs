@@ -0,0 +1,111 @@
+use super::{NodeId, NodeMetricsIndex};
+use petgraph::graph::Graph;
+use petgraph::Directed;
+use std::fmt::Write;
+
+/// An attribute of the nodes or edges of a GraphML graph: `node` or `edge`, its name and its
+/// GraphML type, e.g. `string` or `int`
+pub type GraphMLKey = (&'static str, &'static str, &'static str);
+
+/// The attributes every node carries: its file, kind and start row, then its key metrics
+pub(super) const NODE_KEYS: &[GraphMLKey] = &[
+    ("node", "label", "string"),
+    ("node", "file", "string"),
+    ("node", "kind", "string"),
+    ("node", "start_row", "int"),
+    ("node", "cc", "int"),
+    ("node", "aloc", "int"),
+    ("node", "nom", "int"),
+];
+
+/// Render a graph of entities in the GraphML format, e.g. for Gephi or yEd.
+///
+/// Nodes are labeled by `get_label` and carry the key metrics of their entity when it has any.
+/// Edges carry the attributes of `edge_keys`, whose values are given by `get_edge_data` in the
+/// same order.
+pub fn to_graphml<E>(
+    graph: &Graph<NodeId, E, Directed>,
+    node_metrics: &NodeMetricsIndex,
+    get_label: impl Fn(&NodeId) -> String,
+    edge_keys: &[GraphMLKey],
+    get_edge_data: impl Fn(&E) -> Vec<String>,
+) -> String {
+    let mut graphml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+    );
+    for (domain, name, attr_type) in NODE_KEYS.iter().chain(edge_keys) {
+        let _ = writeln!(
+            graphml,
+            "  <key id=\"{0}_{1}\" for=\"{0}\" attr.name=\"{1}\" attr.type=\"{2}\"/>",
+            domain, name, attr_type
+        );
+    }
+    graphml.push_str("  <graph edgedefault=\"directed\">\n");
+
+    for idx in graph.node_indices() {
+        let node = &graph[idx];
+        let data = get_node_data(node, node_metrics, &get_label);
+        let _ = writeln!(graphml, "    <node id=\"n{}\">", idx.index());
+        write_data(&mut graphml, NODE_KEYS, &data);
+        graphml.push_str("    </node>\n");
+    }
+
+    for edge in graph.raw_edges() {
+        let _ = writeln!(
+            graphml,
+            "    <edge source=\"n{}\" target=\"n{}\">",
+            edge.source().index(),
+            edge.target().index()
+        );
+        write_data(&mut graphml, edge_keys, &get_edge_data(&edge.weight));
+        graphml.push_str("    </edge>\n");
+    }
+
+    graphml.push_str("  </graph>\n</graphml>\n");
+    graphml
+}
+
+/// The values of the attributes of `NODE_KEYS` for a node, the metrics being left out when its
+/// entity has none
+pub(super) fn get_node_data(
+    node: &NodeId,
+    node_metrics: &NodeMetricsIndex,
+    get_label: impl Fn(&NodeId) -> String,
+) -> Vec<String> {
+    let mut data = vec![
+        get_label(node),
+        node.file.clone(),
+        node.kind.clone(),
+        (node.start_row + 1).to_string(),
+    ];
+    if let Some(metrics) = node_metrics.get(node) {
+        data.extend(
+            metrics
+                .get_attributes()
+                .iter()
+                .map(|(_, value)| value.to_string()),
+        );
+    }
+    data
+}
+
+/// Write the values of the attributes of a node or an edge, those without a value being left out
+fn write_data(graphml: &mut String, keys: &[GraphMLKey], values: &[String]) {
+    for ((domain, name, _), value) in keys.iter().zip(values) {
+        let _ = writeln!(
+            graphml,
+            "      <data key=\"{}_{}\">{}</data>",
+            domain,
+            name,
+            escape(value)
+        );
+    }
+}
+
+pub(super) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,62 @@
+use super::graphml::{get_node_data, GraphMLKey, NODE_KEYS};
+use super::{NodeId, NodeMetricsIndex};
+use petgraph::graph::Graph;
+use petgraph::Directed;
+use serde_json::{json, Map, Value};
+
+/// Render a graph of entities as JSON nodes and edges, e.g. for d3-force or cytoscape.js.
+///
+/// Nodes have an `id` and carry the same attributes as in GraphML, `label` included. Edges have
+/// an `id`, their `source` and `target` node ids, and the attributes of `edge_keys`. Attributes of
+/// the GraphML type `int` are numbers.
+pub fn to_json_graph<E>(
+    graph: &Graph<NodeId, E, Directed>,
+    node_metrics: &NodeMetricsIndex,
+    get_label: impl Fn(&NodeId) -> String,
+    edge_keys: &[GraphMLKey],
+    get_edge_data: impl Fn(&E) -> Vec<String>,
+) -> Value {
+    let nodes: Vec<Value> = graph
+        .node_indices()
+        .map(|idx| {
+            let mut node = Map::new();
+            node.insert("id".to_string(), json!(format!("n{}", idx.index())));
+            let data = get_node_data(&graph[idx], node_metrics, &get_label);
+            insert_data(&mut node, NODE_KEYS, &data);
+            Value::Object(node)
+        })
+        .collect();
+
+    let edges: Vec<Value> = graph
+        .raw_edges()
+        .iter()
+        .enumerate()
+        .map(|(idx, edge)| {
+            let mut json_edge = Map::new();
+            json_edge.insert("id".to_string(), json!(format!("e{}", idx)));
+            json_edge.insert(
+                "source".to_string(),
+                json!(format!("n{}", edge.source().index())),
+            );
+            json_edge.insert(
+                "target".to_string(),
+                json!(format!("n{}", edge.target().index())),
+            );
+            insert_data(&mut json_edge, edge_keys, &get_edge_data(&edge.weight));
+            Value::Object(json_edge)
+        })
+        .collect();
+
+    json!({ "nodes": nodes, "edges": edges })
+}
+
+/// Insert the values of the attributes of a node or an edge, those without a value being left out
+fn insert_data(object: &mut Map<String, Value>, keys: &[GraphMLKey], values: &[String]) {
+    for ((_, name, attr_type), value) in keys.iter().zip(values) {
+        let value = match (*attr_type, value.parse::<i64>()) {
+            ("int", Ok(number)) => json!(number),
+            _ => json!(value),
+        };
+        object.insert(name.to_string(), value);
+    }
+}
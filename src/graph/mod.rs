@@ -1,19 +1,35 @@
+pub mod analysis;
+mod call_graph;
+mod gexf;
+mod graphml;
+mod json_graph;
+mod node_metrics;
+mod type_references;
+use crate::config::Language;
+use crate::metrics::{CodeMetrics, EntityKind};
 use crate::ts::{Node, Tree};
+pub use call_graph::{CallConfidence, CallEdge, CallGraph};
+use graphml::GraphMLKey;
+pub use node_metrics::{NodeMetrics, NodeMetricsIndex};
 use petgraph::dot::{Config, Dot};
-use petgraph::graph::{Graph, NodeIndex};
-use petgraph::Directed;
-use std::collections::HashMap;
+use petgraph::graph::{EdgeReference, Graph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::{Directed, Direction};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter, Result};
-use std::fs::File;
-use std::io::Write;
+pub use type_references::TypeReferences;
 
 /// A lightweight identifier for a Tree-sitter node.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct NodeId {
     pub file: String,
     pub kind: String,
     pub start_byte: usize,
     pub end_byte: usize,
+    /// The 0-based start position of the node, to match it with its metrics
+    pub start_row: usize,
+    pub start_col: usize,
 }
 impl NodeId {
     pub fn from_node(file: &str, node: &Node) -> Self {
@@ -22,6 +38,8 @@ impl NodeId {
             kind: node.kind().to_string(),
             start_byte: node.start_byte(),
             end_byte: node.end_byte(),
+            start_row: node.start_position().row,
+            start_col: node.start_position().column,
         }
     }
 
@@ -31,6 +49,8 @@ impl NodeId {
             kind: "root".to_string(),
             start_byte: 0,
             end_byte: 0,
+            start_row: 0,
+            start_col: 0,
         }
     }
 }
@@ -44,10 +64,90 @@ impl Display for NodeId {
     }
 }
 
+/// The label of the edges from a class to the classes it uses, the other edges linking an
+/// entity to the one containing it
+pub const USES_LABEL: &str = "uses";
+
+/// An edge of the dependency graph, standing for every occurrence of the same dependency
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyEdge {
+    /// Empty for an entity contained in another, `uses` for a class using another
+    pub label: String,
+    /// The number of occurrences, e.g. of the references from a class to another
+    pub weight: usize,
+}
+
+/// The attributes of the edges of the dependency graph in GraphML and GEXF
+const TDG_EDGE_KEYS: &[GraphMLKey] = &[("edge", "label", "string"), ("edge", "weight", "int")];
+
+impl DependencyEdge {
+    /// The values of the attributes of `TDG_EDGE_KEYS`
+    fn get_data(&self) -> Vec<String> {
+        vec![self.label.clone(), self.weight.to_string()]
+    }
+}
+
+impl Display for DependencyEdge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.label)
+    }
+}
+
+/// The nodes and edges of a graph, to save it and load it back without the trees it was built
+/// from
+#[derive(Serialize, Deserialize)]
+pub struct GraphState<E> {
+    pub nodes: Vec<NodeId>,
+    /// The source and target of every edge, as indices in `nodes`, with its weight
+    pub edges: Vec<(usize, usize, E)>,
+}
+
+impl<E: Clone> GraphState<E> {
+    pub fn new(graph: &Graph<NodeId, E, Directed>) -> Self {
+        GraphState {
+            nodes: graph.node_weights().cloned().collect(),
+            edges: graph
+                .raw_edges()
+                .iter()
+                .map(|edge| {
+                    (
+                        edge.source().index(),
+                        edge.target().index(),
+                        edge.weight.clone(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Build the graph back, along with the index of every node. Edges between nodes that do
+    /// not exist are dropped.
+    pub fn into_graph(self) -> (Graph<NodeId, E, Directed>, HashMap<NodeId, NodeIndex>) {
+        let mut graph = Graph::new();
+        let mut node_indices = HashMap::new();
+        for node in self.nodes {
+            node_indices.insert(node.clone(), graph.add_node(node));
+        }
+        for (source, target, edge) in self.edges {
+            if source < graph.node_count() && target < graph.node_count() {
+                graph.add_edge(NodeIndex::new(source), NodeIndex::new(target), edge);
+            }
+        }
+        (graph, node_indices)
+    }
+}
+
 /// The dependency graph structure.
 pub struct TypeDependencyGraph {
-    pub graph: Graph<NodeId, String, Directed>,
+    pub graph: Graph<NodeId, DependencyEdge, Directed>,
     pub node_indices: HashMap<NodeId, NodeIndex>,
+    type_references: TypeReferences,
+}
+
+impl Default for TypeDependencyGraph {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TypeDependencyGraph {
@@ -63,10 +163,35 @@ impl TypeDependencyGraph {
         Self {
             graph,
             node_indices,
+            type_references: TypeReferences::new(),
+        }
+    }
+
+    /// The graph as saved by `get_state`, whose references are already resolved
+    pub fn from_state(state: GraphState<DependencyEdge>) -> Self {
+        let (graph, node_indices) = state.into_graph();
+        Self {
+            graph,
+            node_indices,
+            type_references: TypeReferences::new(),
         }
     }
 
-    pub fn process_tree(&mut self, file_path: &String, tree: &Tree) {
+    pub fn get_state(&self) -> GraphState<DependencyEdge> {
+        GraphState::new(&self.graph)
+    }
+
+    pub fn process_tree(
+        &mut self,
+        file_path: &str,
+        language: &Language,
+        tree: &Tree,
+        source: &str,
+    ) {
+        // Index the types used by the classes, linked once all the files are processed
+        self.type_references
+            .process_tree(file_path, language, tree, source);
+
         let capture_nodes = vec!["program", "class_declaration", "method_declaration"];
 
         fn traverse(
@@ -118,18 +243,147 @@ impl TypeDependencyGraph {
         }
     }
 
+    /// Link every class to the classes of the project it uses, replacing the previous links.
+    pub fn resolve_references(&mut self) {
+        self.graph
+            .retain_edges(|graph, edge| graph[edge].label != USES_LABEL);
+        for (class, used_class, weight) in self.type_references.resolve() {
+            self.add_weighted_dependency(class, used_class, USES_LABEL, weight);
+        }
+    }
+
+    /// Set the coupling metrics of the classes from the resolved references: `fan_out`, the
+    /// number of classes a class uses, `fan_in`, the number of classes using it, and `cbo`, the
+    /// number of classes it is coupled to either way.
+    pub fn calculate_coupling(&self, metrics: &mut CodeMetrics) {
+        let mut coupled_classes: HashMap<(&str, &str, u32, u32), [HashSet<NodeIndex>; 2]> =
+            HashMap::new();
+        for (node, &idx) in &self.node_indices {
+            let mut coupled = [HashSet::new(), HashSet::new()];
+            for (direction, neighbors) in [Direction::Incoming, Direction::Outgoing]
+                .into_iter()
+                .zip(&mut coupled)
+            {
+                let mut edges = self.graph.neighbors_directed(idx, direction).detach();
+                while let Some((edge, neighbor)) = edges.next(&self.graph) {
+                    if self.graph[edge].label == USES_LABEL {
+                        neighbors.insert(neighbor);
+                    }
+                }
+            }
+            let key = (
+                node.file.as_str(),
+                node.kind.as_str(),
+                node.start_row as u32 + 1,
+                node.start_col as u32 + 1,
+            );
+            coupled_classes.insert(key, coupled);
+        }
+
+        for block in &mut metrics.metric_blocks {
+            let meta_data = &block.meta_data;
+            if meta_data.node_kind != EntityKind::Class {
+                continue;
+            }
+            let key = (
+                meta_data.file_path.as_str(),
+                meta_data.node_type.as_str(),
+                meta_data.start_row,
+                meta_data.start_col,
+            );
+            if let Some([incoming, outgoing]) = coupled_classes.get(&key) {
+                block.metric.fan_in = incoming.len() as u32;
+                block.metric.fan_out = outgoing.len() as u32;
+                block.metric.cbo = incoming.union(outgoing).count() as u32;
+            }
+        }
+    }
+
     /// Add a dependency edge between two nodes with an empty label.
     pub fn add_dependency(&mut self, from: NodeId, to: NodeId) {
+        self.add_weighted_dependency(from, to, "", 1);
+    }
+
+    /// Add a dependency edge between two nodes, or add its weight to the edge with the same
+    /// label already between them, so that repeated dependencies make a single heavier edge.
+    pub fn add_weighted_dependency(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        label: &str,
+        weight: usize,
+    ) {
         let from_idx = self.add_node(from);
         let to_idx = self.add_node(to);
-        self.graph.add_edge(from_idx, to_idx, String::new());
+        let existing = self
+            .graph
+            .edges_connecting(from_idx, to_idx)
+            .find(|edge| edge.weight().label == label)
+            .map(|edge| edge.id());
+        match existing {
+            Some(edge_idx) => self.graph[edge_idx].weight += weight,
+            None => {
+                let edge = DependencyEdge {
+                    label: label.to_string(),
+                    weight,
+                };
+                self.graph.add_edge(from_idx, to_idx, edge);
+            }
+        }
+    }
+
+    /// Render the dependency graph in the DOT format, with the key metrics of each node as
+    /// attributes, and the label and weight of each edge.
+    pub fn to_dot(&self, node_metrics: &NodeMetricsIndex) -> String {
+        let get_edge_attributes = |_, edge: EdgeReference<DependencyEdge>| {
+            let DependencyEdge { label, weight } = edge.weight();
+            match label.as_str() {
+                "" => format!("weight = {}", weight),
+                label => format!("label = \"{}\" weight = {}", label, weight),
+            }
+        };
+        let get_node_attributes = |_, (_, node)| node_metrics.get_dot_attributes(node);
+        let dot = Dot::with_attr_getters(
+            &self.graph,
+            &[Config::EdgeNoLabel],
+            &get_edge_attributes,
+            &get_node_attributes,
+        );
+        dot.to_string()
     }
 
-    /// Export the dependency graph to a DOT file.
-    pub fn export_to_dot(&self, path: &str) -> std::io::Result<()> {
-        let dot = Dot::with_config(&self.graph, &[Config::EdgeNoLabel]);
-        let mut file = File::create(path)?;
-        write!(file, "{}", dot)?;
-        Ok(())
+    /// Render the dependency graph in the GraphML format, with the labels and weights of the
+    /// edges and the key metrics of the entities
+    pub fn to_graphml(&self, node_metrics: &NodeMetricsIndex) -> String {
+        graphml::to_graphml(
+            &self.graph,
+            node_metrics,
+            |node| node.to_string(),
+            TDG_EDGE_KEYS,
+            DependencyEdge::get_data,
+        )
+    }
+
+    /// Render the dependency graph as JSON nodes and edges, with the same attributes as in
+    /// GraphML
+    pub fn to_json(&self, node_metrics: &NodeMetricsIndex) -> serde_json::Value {
+        json_graph::to_json_graph(
+            &self.graph,
+            node_metrics,
+            |node| node.to_string(),
+            TDG_EDGE_KEYS,
+            DependencyEdge::get_data,
+        )
+    }
+
+    /// Render the dependency graph in the GEXF format, with the same attributes as in GraphML
+    pub fn to_gexf(&self, node_metrics: &NodeMetricsIndex) -> String {
+        gexf::to_gexf(
+            &self.graph,
+            node_metrics,
+            |node| node.to_string(),
+            TDG_EDGE_KEYS,
+            DependencyEdge::get_data,
+        )
     }
 }
@@ -0,0 +1,74 @@
+use super::NodeId;
+use crate::metrics::CodeMetrics;
+use std::collections::HashMap;
+
+/// The key metrics of an entity, attached to its node when a graph is exported
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NodeMetrics {
+    pub cc: u32,
+    pub aloc: u32,
+    pub nom: u32,
+}
+
+impl NodeMetrics {
+    /// Names and values of the metrics, in the order they are exported
+    pub fn get_attributes(&self) -> Vec<(&'static str, u32)> {
+        vec![("cc", self.cc), ("aloc", self.aloc), ("nom", self.nom)]
+    }
+}
+
+/// The key metrics of the entities, to look them up from the nodes of the graphs
+#[derive(Default)]
+pub struct NodeMetricsIndex {
+    /// Keyed by file path, node type and 1-based start position, as in the metrics tables
+    metrics: HashMap<(String, String, u32, u32), NodeMetrics>,
+}
+
+impl NodeMetricsIndex {
+    pub fn new(code_metrics: &CodeMetrics) -> Self {
+        let metrics = code_metrics
+            .metric_blocks
+            .iter()
+            .map(|block| {
+                let meta_data = &block.meta_data;
+                let key = (
+                    meta_data.file_path.clone(),
+                    meta_data.node_type.clone(),
+                    meta_data.start_row,
+                    meta_data.start_col,
+                );
+                let node_metrics = NodeMetrics {
+                    cc: block.metric.cc,
+                    aloc: block.metric.aloc,
+                    nom: block.metric.nom,
+                };
+                (key, node_metrics)
+            })
+            .collect();
+        NodeMetricsIndex { metrics }
+    }
+
+    pub fn get(&self, node: &NodeId) -> Option<&NodeMetrics> {
+        let key = (
+            node.file.clone(),
+            node.kind.clone(),
+            node.start_row as u32 + 1,
+            node.start_col as u32 + 1,
+        );
+        self.metrics.get(&key)
+    }
+
+    /// Format the metrics of the node as DOT attributes, e.g. `cc = 3 aloc = 12 nom = 0`
+    pub fn get_dot_attributes(&self, node: &NodeId) -> String {
+        self.get(node)
+            .map(|node_metrics| {
+                node_metrics
+                    .get_attributes()
+                    .iter()
+                    .map(|(name, value)| format!("{} = {}", name, value))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .unwrap_or_default()
+    }
+}
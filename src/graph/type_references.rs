@@ -0,0 +1,456 @@
+use super::call_graph::{get_text, get_type_name, is_class_like, JavaImports};
+use super::NodeId;
+use crate::config::Language;
+use crate::ts::{Node, Tree};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+struct TypeInfo {
+    id: NodeId,
+    name: String,
+    /// The name of the class within its file, e.g. `com.shop.Cart.Item` in Java and
+    /// `Cart.Item` in Python
+    qualified_name: String,
+    /// The names of the types used in the class, as written in its body, with the number of
+    /// times each is used
+    references: BTreeMap<String, usize>,
+}
+
+/// The classes of a file and the types they use, before they are resolved.
+#[derive(Debug, Clone, Default)]
+struct TypeIndex {
+    language: Option<Language>,
+    java_imports: JavaImports,
+    /// The qualified names the names imported by a Python file stand for, e.g. `Cart` for
+    /// `models.Cart` after `from models import Cart`, and `m` for `models` after
+    /// `import models as m`
+    python_imports: HashMap<String, String>,
+    /// The dotted module of a Python file, from its path
+    python_module: Vec<String>,
+    classes: Vec<TypeInfo>,
+}
+
+/// The types each class of the project uses in its body: the types it declares, creates,
+/// extends or calls static methods on in Java, and the imported or local classes it names in
+/// Python.
+///
+/// As for the call graph, files are indexed one by one with `process_tree`, then `resolve` links
+/// the classes to the classes of the project they use, the types outside of it being left out.
+#[derive(Default)]
+pub struct TypeReferences {
+    files: BTreeMap<String, TypeIndex>,
+}
+
+impl TypeReferences {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index the classes of a file and the types they use, replacing any previous index of it.
+    pub fn process_tree(
+        &mut self,
+        file_path: &str,
+        language: &Language,
+        tree: &Tree,
+        source: &str,
+    ) {
+        let mut index = TypeIndex {
+            language: Some(*language),
+            ..TypeIndex::default()
+        };
+        let root = tree.root_node();
+        match language {
+            Language::Java => {
+                let mut cursor = root.walk();
+                for child in root.named_children(&mut cursor) {
+                    if !index.java_imports.add_declaration(&child, source) {
+                        let package = index.java_imports.package.clone();
+                        index_java_class(file_path, &child, source, &package, &mut index);
+                    }
+                }
+            }
+            Language::Python => {
+                index.python_module = get_python_module(file_path);
+                index_python_imports(
+                    &root,
+                    source,
+                    &index.python_module,
+                    &mut index.python_imports,
+                );
+                index_python_classes(file_path, &root, source, "", &mut index);
+                // Bare names only stand for the classes declared at the top level of the file
+                let local_classes: Vec<String> = index
+                    .classes
+                    .iter()
+                    .filter(|class| class.name == class.qualified_name)
+                    .map(|class| class.name.clone())
+                    .collect();
+                for class in &mut index.classes {
+                    class.references.retain(|reference, _| {
+                        let first = reference.split('.').next().unwrap_or(reference);
+                        local_classes.iter().any(|name| name == first)
+                            || index.python_imports.contains_key(first)
+                    });
+                }
+            }
+            _ => return,
+        }
+        self.files.insert(file_path.to_string(), index);
+    }
+
+    /// Resolve the types used by each class to the classes of the project, as the using class,
+    /// the used class and the number of references from one to the other, a class using itself
+    /// aside.
+    pub fn resolve(&self) -> Vec<(NodeId, NodeId, usize)> {
+        let mut java_classes: HashMap<&str, &NodeId> = HashMap::new();
+        let mut python_classes: Vec<(&[String], &TypeInfo)> = Vec::new();
+        for index in self.files.values() {
+            for class in &index.classes {
+                match index.language {
+                    Some(Language::Java) => {
+                        java_classes.insert(&class.qualified_name, &class.id);
+                    }
+                    _ => python_classes.push((&index.python_module, class)),
+                }
+            }
+        }
+
+        let mut references = Vec::new();
+        for index in self.files.values() {
+            for class in &index.classes {
+                let mut used: Vec<(&NodeId, usize)> = Vec::new();
+                for (reference, count) in &class.references {
+                    let resolved = match index.language {
+                        Some(Language::Java) => {
+                            let local_classes = index
+                                .classes
+                                .iter()
+                                .map(|class| (class.name.as_str(), class.qualified_name.as_str()));
+                            index
+                                .java_imports
+                                .get_candidates(local_classes, reference)
+                                .iter()
+                                .find_map(|candidate| java_classes.get(candidate.as_str()).copied())
+                        }
+                        _ => resolve_python_type(index, reference, &python_classes),
+                    };
+                    let Some(id) = resolved.filter(|id| **id != class.id) else {
+                        continue;
+                    };
+                    match used.iter_mut().find(|(used_id, _)| *used_id == id) {
+                        Some((_, used_count)) => *used_count += count,
+                        None => used.push((id, *count)),
+                    }
+                }
+                references.extend(
+                    used.into_iter()
+                        .map(|(id, count)| (class.id.clone(), id.clone(), count)),
+                );
+            }
+        }
+        references
+    }
+}
+
+/// Index a Java class declaration, the types used in its body and its nested classes.
+fn index_java_class(
+    file_path: &str,
+    node: &Node,
+    source: &str,
+    outer: &str,
+    index: &mut TypeIndex,
+) {
+    if !is_class_like(node.kind()) {
+        return;
+    }
+    let Some(name_node) = node.child_by_field_name("name") else {
+        return;
+    };
+    let name = get_text(&name_node, source).to_string();
+    let qualified_name = if outer.is_empty() {
+        name.clone()
+    } else {
+        format!("{}.{}", outer, name)
+    };
+    let mut class = TypeInfo {
+        id: NodeId::from_node(file_path, node),
+        name,
+        qualified_name: qualified_name.clone(),
+        references: BTreeMap::new(),
+    };
+
+    let mut nested_classes = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if child.kind() != "identifier" {
+            collect_java_references(&child, source, &mut class.references, &mut nested_classes);
+        }
+    }
+    index.classes.push(class);
+    for nested_class in nested_classes {
+        index_java_class(file_path, &nested_class, source, &qualified_name, index);
+    }
+}
+
+/// Collect the types named in a subtree of a Java class, leaving its nested classes out.
+fn collect_java_references<'a>(
+    node: &Node<'a>,
+    source: &str,
+    references: &mut BTreeMap<String, usize>,
+    nested_classes: &mut Vec<Node<'a>>,
+) {
+    match node.kind() {
+        kind if is_class_like(kind) => {
+            nested_classes.push(*node);
+            return;
+        }
+        "type_identifier" | "scoped_type_identifier" => {
+            *references.entry(get_type_name(node, source)).or_default() += 1;
+            return;
+        }
+        // The class of a static member, e.g. `Math.max(a, b)`, by convention capitalized
+        "method_invocation" | "field_access" => {
+            if let Some(object) = node
+                .child_by_field_name("object")
+                .filter(|object| object.kind() == "identifier")
+            {
+                let name = get_text(&object, source);
+                if name.starts_with(|c: char| c.is_uppercase()) {
+                    *references.entry(name.to_string()).or_default() += 1;
+                }
+            }
+        }
+        _ => {}
+    }
+
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_java_references(&child, source, references, nested_classes);
+    }
+}
+
+/// The dotted module of a Python file, e.g. `shop.models` for `shop/models.py` and `shop` for
+/// `shop/__init__.py`
+fn get_python_module(file_path: &str) -> Vec<String> {
+    let path = Path::new(file_path).with_extension("");
+    let mut module: Vec<String> = path
+        .components()
+        .filter_map(|component| match component {
+            std::path::Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect();
+    if module.last().is_some_and(|part| part == "__init__") {
+        module.pop();
+    }
+    module
+}
+
+/// Index the names imported by a Python file, anywhere in it, relative imports being resolved
+/// against its module
+fn index_python_imports(
+    node: &Node,
+    source: &str,
+    module: &[String],
+    imports: &mut HashMap<String, String>,
+) {
+    let mut add_import = |name: &Node, base: Option<&str>| {
+        let (imported, alias) = match name.kind() {
+            "aliased_import" => (
+                name.child_by_field_name("name"),
+                name.child_by_field_name("alias"),
+            ),
+            _ => (Some(*name), None),
+        };
+        let Some(imported) = imported else {
+            return;
+        };
+        let imported = get_text(&imported, source);
+        let qualified_name = match base {
+            Some(base) if !base.is_empty() => format!("{}.{}", base, imported),
+            _ => imported.to_string(),
+        };
+        let (bound_name, qualified_name) = match (alias, base) {
+            (Some(alias), _) => (get_text(&alias, source).to_string(), qualified_name),
+            (None, Some(_)) => (imported.to_string(), qualified_name),
+            // `import shop.models` binds `shop`, which `shop.models.Cart` is reached from
+            (None, None) => {
+                let package = imported.split('.').next().unwrap_or(imported);
+                (package.to_string(), package.to_string())
+            }
+        };
+        imports.insert(bound_name, qualified_name);
+    };
+
+    match node.kind() {
+        "import_statement" => {
+            let mut cursor = node.walk();
+            for name in node.children_by_field_name("name", &mut cursor) {
+                add_import(&name, None);
+            }
+            return;
+        }
+        "import_from_statement" => {
+            let Some(module_name) = node.child_by_field_name("module_name") else {
+                return;
+            };
+            let text = get_text(&module_name, source);
+            let dots = text.chars().take_while(|c| *c == '.').count();
+            // `from . import x` in `shop/models.py` imports from `shop`
+            let base = if dots > 0 {
+                let package_len = module.len().saturating_sub(dots);
+                let mut base = module[..package_len].to_vec();
+                base.extend(
+                    text[dots..]
+                        .split('.')
+                        .filter(|part| !part.is_empty())
+                        .map(String::from),
+                );
+                base.join(".")
+            } else {
+                text.to_string()
+            };
+            let mut cursor = node.walk();
+            for name in node.children_by_field_name("name", &mut cursor) {
+                add_import(&name, Some(&base));
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        index_python_imports(&child, source, module, imports);
+    }
+}
+
+/// Index the Python classes of a subtree, nested ones included, and the names they use.
+fn index_python_classes(
+    file_path: &str,
+    node: &Node,
+    source: &str,
+    outer: &str,
+    index: &mut TypeIndex,
+) {
+    if node.kind() != "class_definition" {
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            index_python_classes(file_path, &child, source, outer, index);
+        }
+        return;
+    }
+
+    let Some(name_node) = node.child_by_field_name("name") else {
+        return;
+    };
+    let name = get_text(&name_node, source).to_string();
+    let qualified_name = if outer.is_empty() {
+        name.clone()
+    } else {
+        format!("{}.{}", outer, name)
+    };
+    let mut class = TypeInfo {
+        id: NodeId::from_node(file_path, node),
+        name,
+        qualified_name: qualified_name.clone(),
+        references: BTreeMap::new(),
+    };
+
+    let mut nested_classes = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if child != name_node {
+            collect_python_references(&child, source, &mut class.references, &mut nested_classes);
+        }
+    }
+    index.classes.push(class);
+    for nested_class in nested_classes {
+        index_python_classes(file_path, &nested_class, source, &qualified_name, index);
+    }
+}
+
+/// Collect the names and dotted names read in a subtree of a Python class, e.g. `Cart` or
+/// `models.Cart`, leaving its nested classes out
+fn collect_python_references<'a>(
+    node: &Node<'a>,
+    source: &str,
+    references: &mut BTreeMap<String, usize>,
+    nested_classes: &mut Vec<Node<'a>>,
+) {
+    match node.kind() {
+        "class_definition" => {
+            nested_classes.push(*node);
+            return;
+        }
+        "identifier" => {
+            *references
+                .entry(get_text(node, source).to_string())
+                .or_default() += 1;
+            return;
+        }
+        "attribute" => {
+            let text: String = get_text(node, source)
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect();
+            if text
+                .split('.')
+                .all(|part| part.chars().all(|c| c.is_alphanumeric() || c == '_'))
+            {
+                *references.entry(text).or_default() += 1;
+                return;
+            }
+        }
+        _ => {}
+    }
+
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_python_references(&child, source, references, nested_classes);
+    }
+}
+
+/// Resolve a name used in a Python file to a class of the project: a class declared at the top
+/// level of the file, or one reached from an import
+fn resolve_python_type<'a>(
+    index: &'a TypeIndex,
+    reference: &str,
+    classes: &[(&[String], &'a TypeInfo)],
+) -> Option<&'a NodeId> {
+    let (first, rest) = match reference.split_once('.') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (reference, None),
+    };
+    let Some(imported) = index.python_imports.get(first) else {
+        return index
+            .classes
+            .iter()
+            .find(|class| class.qualified_name == reference)
+            .map(|class| &class.id);
+    };
+    let qualified_name = match rest {
+        Some(rest) => format!("{}.{}", imported, rest),
+        None => imported.clone(),
+    };
+
+    // The module is matched by its last parts, the files being relative to the target rather
+    // than to the root of the packages
+    let parts: Vec<&str> = qualified_name.split('.').collect();
+    for split in (1..parts.len()).rev() {
+        let (module, class_name) = (&parts[..split], parts[split..].join("."));
+        let found = classes.iter().find(|(class_module, class)| {
+            class.qualified_name == class_name
+                && class_module.len() >= module.len()
+                && class_module[class_module.len() - module.len()..]
+                    .iter()
+                    .zip(module)
+                    .all(|(a, b)| a == b)
+        });
+        if let Some((_, class)) = found {
+            return Some(&class.id);
+        }
+    }
+    None
+}
@@ -0,0 +1,311 @@
+use super::{get_text, LanguageProfile};
+use crate::config::Language;
+use crate::metrics::NamingStyle;
+use crate::ts::{Node, TSLanguage};
+
+// A line comment ending a macro is part of its value, e.g. `10 // items` of
+// `#define MAX 10 // items`, unless its `//` is in a string
+const C_BASE_QUERY: &str = concat!(
+    "(comment) @comment",
+    "((preproc_arg) @comment (#match? @comment \"^[^\\\"']*//\"))",
+    "(preproc_include) @import",
+    "[(struct_specifier body: (_)) @class_definition (union_specifier body: (_)) @class_definition (enum_specifier body: (_)) @class_definition]",
+    "(function_definition) @method_definition",
+);
+
+const CPP_BASE_QUERY: &str = concat!(
+    "(comment) @comment",
+    "((preproc_arg) @comment (#match? @comment \"^[^\\\"']*//\"))",
+    "(preproc_include) @import",
+    "[(class_specifier body: (_)) @class_definition (struct_specifier body: (_)) @class_definition (union_specifier body: (_)) @class_definition (enum_specifier body: (_)) @class_definition]",
+    "[(function_definition) @method_definition (lambda_expression) @method_definition]",
+);
+
+// The calls ending the process, as there are no exceptions to handle in C
+const ROBUSTNESS_QUERY: &str =
+    "(call_expression function: (identifier) @name (#match? @name \"^(abort|exit|_Exit|quick_exit)$\")) @panic";
+
+const CPP_ROBUSTNESS_QUERY: &str = concat!(
+    "(catch_clause) @catch",
+    "(call_expression function: (identifier) @name (#match? @name \"^(abort|exit|_Exit|quick_exit)$\")) @panic",
+);
+
+// The variables, parameters and constants declared, the macros and enumerators being constants
+const NAMING_QUERY: &str = concat!(
+    "[(declaration declarator: (identifier) @variable) (init_declarator declarator: (identifier) @variable)]",
+    "[(parameter_declaration declarator: (identifier) @variable) (pointer_declarator declarator: (identifier) @variable)]",
+    "[(preproc_def name: (identifier) @constant) (enumerator name: (identifier) @constant)]",
+);
+
+const C_DECISION_POINTS: &[&str] = &[
+    "if_statement",
+    "for_statement",
+    "while_statement",
+    "do_statement",
+    "case_statement",
+    "conditional_expression",
+];
+
+const CPP_DECISION_POINTS: &[&str] = &[
+    "if_statement",
+    "for_statement",
+    "for_range_loop",
+    "while_statement",
+    "do_statement",
+    "case_statement",
+    "catch_clause",
+    "conditional_expression",
+    "lambda_expression",
+];
+
+const C_DECISION_POINTS_SKIP_NODES: &[&str] = &[
+    "struct_specifier",
+    "union_specifier",
+    "enum_specifier",
+    "function_definition",
+];
+
+const CPP_DECISION_POINTS_SKIP_NODES: &[&str] = &[
+    "class_specifier",
+    "struct_specifier",
+    "union_specifier",
+    "enum_specifier",
+    "function_definition",
+    "lambda_expression",
+];
+
+const C_NESTING_NODES: &[&str] = &[
+    "if_statement",
+    "for_statement",
+    "while_statement",
+    "do_statement",
+    "switch_statement",
+];
+
+const CPP_NESTING_NODES: &[&str] = &[
+    "if_statement",
+    "for_statement",
+    "for_range_loop",
+    "while_statement",
+    "do_statement",
+    "switch_statement",
+    "try_statement",
+];
+
+const C_HALSTEAD_LITERALS: &[&str] = &["string_literal", "char_literal"];
+
+const CPP_HALSTEAD_LITERALS: &[&str] = &["string_literal", "raw_string_literal", "char_literal"];
+
+const C_STRINGS: &[&str] = &["string_literal"];
+
+const CPP_STRINGS: &[&str] = &["string_literal", "raw_string_literal"];
+
+// The statements leaving a function early or with a value, counted by NORET
+const C_EXIT_QUERY: &str = "(return_statement) @exit";
+
+const CPP_EXIT_QUERY: &str = "[(return_statement) @exit (throw_statement) @exit]";
+
+// The functions and lambdas whose exit statements are their own rather than those of the
+// function enclosing them
+const C_EXIT_SKIP_NODES: &[&str] = &["function_definition"];
+
+const CPP_EXIT_SKIP_NODES: &[&str] = &["function_definition", "lambda_expression"];
+
+// The parameters with a default value, e.g. `int count = 1`
+const CPP_DEFAULT_PARAMETERS: &[&str] = &["optional_parameter_declaration"];
+
+// The `...` of the C functions
+const C_VARIADIC_PARAMETERS: &[&str] = &["variadic_parameter"];
+
+// The parameter packs of the templates, e.g. `Args... args`, the `...` of C++ being unnamed
+const CPP_VARIADIC_PARAMETERS: &[&str] = &["variadic_parameter_declaration"];
+
+/// The declarator of a function or lambda naming it and holding its parameters, under those of
+/// the pointers and references it returns, e.g. `*next(node_t *node)` of
+/// `node_t *next(node_t *node)`
+fn get_function_declarator<'t>(method_node: &Node<'t>) -> Option<Node<'t>> {
+    let mut declarator = method_node.child_by_field_name("declarator")?;
+    while !matches!(
+        declarator.kind(),
+        "function_declarator" | "abstract_function_declarator"
+    ) {
+        declarator = declarator.child_by_field_name("declarator").or_else(|| {
+            // The references of C++ name their declarator without a field
+            declarator.named_child(declarator.named_child_count().checked_sub(1)?)
+        })?;
+    }
+    Some(declarator)
+}
+
+/// Functions have no name, they are named by their declarator, qualified by their class when
+/// defined outside of it, e.g. `Stack::push`
+fn get_function_name(method_node: &Node, source_code: &str) -> Option<String> {
+    let name = get_function_declarator(method_node)?.child_by_field_name("declarator")?;
+    Some(get_text(&name, source_code))
+}
+
+/// The anonymous structs and enums of a `typedef` are named after the type it declares
+fn get_assigned_name_field(parent_kind: &str) -> Option<&'static str> {
+    match parent_kind {
+        "type_definition" | "init_declarator" => Some("declarator"),
+        _ => None,
+    }
+}
+
+pub struct C;
+
+impl LanguageProfile for C {
+    fn language(&self) -> Language {
+        Language::C
+    }
+
+    fn names(&self) -> &'static [&'static str] {
+        &["c"]
+    }
+
+    fn grammar(&self) -> TSLanguage {
+        tree_sitter_c::LANGUAGE.into()
+    }
+
+    fn grammar_crate(&self) -> &'static str {
+        "tree-sitter-c"
+    }
+
+    /// Headers are taken for C, those of C++ libraries may be set with `extension-languages`
+    fn extensions(&self) -> &'static [&'static str] {
+        &[".c", ".h"]
+    }
+
+    fn doc_comment_prefixes(&self) -> &'static [&'static str] {
+        &["/**", "/*!", "///", "//!"]
+    }
+
+    fn query(&self, query_name: &str) -> Option<&'static str> {
+        match query_name {
+            "base_query" => Some(C_BASE_QUERY),
+            "robustness_query" => Some(ROBUSTNESS_QUERY),
+            "naming_query" => Some(NAMING_QUERY),
+            "exit_query" => Some(C_EXIT_QUERY),
+            _ => None,
+        }
+    }
+
+    fn node_group(&self, group_name: &str) -> Option<&'static [&'static str]> {
+        match group_name {
+            "decision_point_nodes" => Some(C_DECISION_POINTS),
+            "decision_point_skip_nodes" => Some(C_DECISION_POINTS_SKIP_NODES),
+            "nesting_nodes" => Some(C_NESTING_NODES),
+            "halstead_literal_nodes" => Some(C_HALSTEAD_LITERALS),
+            "string_nodes" => Some(C_STRINGS),
+            "exit_skip_nodes" => Some(C_EXIT_SKIP_NODES),
+            "variadic_parameter_nodes" => Some(C_VARIADIC_PARAMETERS),
+            _ => None,
+        }
+    }
+
+    fn entity_nodes(&self) -> &'static [&'static str] {
+        C_DECISION_POINTS_SKIP_NODES
+    }
+
+    fn naming_style(&self, _: &str) -> Option<NamingStyle> {
+        None
+    }
+
+    fn is_generated_method(&self, _: &str, _: &Node, _: &str) -> bool {
+        false
+    }
+
+    fn get_method_name(&self, method_node: &Node, source_code: &str) -> Option<String> {
+        get_function_name(method_node, source_code)
+    }
+
+    fn get_parameter_list<'t>(&self, method_node: &Node<'t>) -> Option<Node<'t>> {
+        get_function_declarator(method_node)?.child_by_field_name("parameters")
+    }
+
+    fn get_assigned_name_field(&self, parent_kind: &str) -> Option<&'static str> {
+        get_assigned_name_field(parent_kind)
+    }
+}
+
+pub struct Cpp;
+
+impl LanguageProfile for Cpp {
+    fn language(&self) -> Language {
+        Language::Cpp
+    }
+
+    fn names(&self) -> &'static [&'static str] {
+        &["cpp", "c++"]
+    }
+
+    fn grammar(&self) -> TSLanguage {
+        tree_sitter_cpp::LANGUAGE.into()
+    }
+
+    fn grammar_crate(&self) -> &'static str {
+        "tree-sitter-cpp"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &[".cpp", ".cc", ".cxx", ".c++", ".hpp", ".hh", ".hxx", ".h++"]
+    }
+
+    fn doc_comment_prefixes(&self) -> &'static [&'static str] {
+        &["/**", "/*!", "///", "//!"]
+    }
+
+    fn query(&self, query_name: &str) -> Option<&'static str> {
+        match query_name {
+            "base_query" => Some(CPP_BASE_QUERY),
+            "robustness_query" => Some(CPP_ROBUSTNESS_QUERY),
+            "naming_query" => Some(NAMING_QUERY),
+            "exit_query" => Some(CPP_EXIT_QUERY),
+            _ => None,
+        }
+    }
+
+    fn node_group(&self, group_name: &str) -> Option<&'static [&'static str]> {
+        match group_name {
+            "decision_point_nodes" => Some(CPP_DECISION_POINTS),
+            "decision_point_skip_nodes" => Some(CPP_DECISION_POINTS_SKIP_NODES),
+            "nesting_nodes" => Some(CPP_NESTING_NODES),
+            "halstead_literal_nodes" => Some(CPP_HALSTEAD_LITERALS),
+            "string_nodes" => Some(CPP_STRINGS),
+            "exit_skip_nodes" => Some(CPP_EXIT_SKIP_NODES),
+            "default_parameter_nodes" => Some(CPP_DEFAULT_PARAMETERS),
+            "variadic_parameter_nodes" => Some(CPP_VARIADIC_PARAMETERS),
+            _ => None,
+        }
+    }
+
+    fn entity_nodes(&self) -> &'static [&'static str] {
+        &[
+            "class_specifier",
+            "struct_specifier",
+            "union_specifier",
+            "enum_specifier",
+            "function_definition",
+        ]
+    }
+
+    fn naming_style(&self, _: &str) -> Option<NamingStyle> {
+        None
+    }
+
+    fn is_generated_method(&self, _: &str, _: &Node, _: &str) -> bool {
+        false
+    }
+
+    fn get_method_name(&self, method_node: &Node, source_code: &str) -> Option<String> {
+        get_function_name(method_node, source_code)
+    }
+
+    fn get_parameter_list<'t>(&self, method_node: &Node<'t>) -> Option<Node<'t>> {
+        get_function_declarator(method_node)?.child_by_field_name("parameters")
+    }
+
+    fn get_assigned_name_field(&self, parent_kind: &str) -> Option<&'static str> {
+        get_assigned_name_field(parent_kind)
+    }
+}
@@ -0,0 +1,350 @@
+use super::LanguageProfile;
+use crate::config::Language;
+use crate::error::XStatsError;
+use crate::metrics::NamingStyle;
+use crate::ts::{Node, TSLanguage};
+use crate::utils::read_file;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// The profiles loaded from files, indexed by `Language::Custom`
+static CUSTOM_PROFILES: RwLock<Vec<&'static CustomProfile>> = RwLock::new(Vec::new());
+
+/// A language described by a TOML file, whose grammar is loaded from a shared library, e.g.
+///
+/// ```toml
+/// name = "Snake"
+/// extensions = [".snk"]
+/// library = "libtree-sitter-snake.so"
+///
+/// [queries]
+/// comment = "(comment)"
+/// class = "(class_definition)"
+/// method = "(function_definition)"
+///
+/// [node-groups]
+/// decision-points = ["if_statement", "for_statement"]
+/// entities = ["class_definition", "function_definition"]
+/// ```
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct ProfileFile {
+    /// The name the language is reported under
+    name: String,
+    /// Other names of the language, e.g. as declared by a notebook kernel
+    #[serde(default)]
+    aliases: Vec<String>,
+    extensions: Vec<String>,
+    /// The shared library of the grammar, relative to the profile
+    library: String,
+    /// The function of the library returning the grammar, `tree_sitter_<name>` by default
+    symbol: Option<String>,
+    #[serde(default)]
+    doc_comment_prefixes: Vec<String>,
+    queries: ProfileQueries,
+    #[serde(default)]
+    node_groups: ProfileNodeGroups,
+}
+
+/// The patterns of the nodes of each kind of the base query, without captures
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct ProfileQueries {
+    comment: Option<String>,
+    import: Option<String>,
+    class: Option<String>,
+    method: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct ProfileNodeGroups {
+    decision_points: Vec<String>,
+    nesting: Vec<String>,
+    strings: Vec<String>,
+    /// The node kinds of the classes and methods
+    entities: Vec<String>,
+}
+
+/// The profile of a language described by a file, living for the rest of the process
+struct CustomProfile {
+    language: Language,
+    name: &'static str,
+    names: &'static [&'static str],
+    grammar: TSLanguage,
+    library: &'static str,
+    extensions: &'static [&'static str],
+    doc_comment_prefixes: &'static [&'static str],
+    base_query: &'static str,
+    decision_points: &'static [&'static str],
+    nesting_nodes: &'static [&'static str],
+    string_nodes: &'static [&'static str],
+    entity_nodes: &'static [&'static str],
+}
+
+impl LanguageProfile for CustomProfile {
+    fn language(&self) -> Language {
+        self.language
+    }
+
+    fn names(&self) -> &'static [&'static str] {
+        self.names
+    }
+
+    fn grammar(&self) -> TSLanguage {
+        self.grammar.clone()
+    }
+
+    fn grammar_crate(&self) -> &'static str {
+        self.library
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        self.extensions
+    }
+
+    fn doc_comment_prefixes(&self) -> &'static [&'static str] {
+        self.doc_comment_prefixes
+    }
+
+    fn query(&self, query_name: &str) -> Option<&'static str> {
+        match query_name {
+            "base_query" => Some(self.base_query),
+            _ => None,
+        }
+    }
+
+    fn node_group(&self, group_name: &str) -> Option<&'static [&'static str]> {
+        match group_name {
+            "decision_point_nodes" => Some(self.decision_points),
+            "decision_point_skip_nodes" => Some(self.entity_nodes),
+            "nesting_nodes" => Some(self.nesting_nodes),
+            "halstead_literal_nodes" | "string_nodes" => Some(self.string_nodes),
+            _ => None,
+        }
+    }
+
+    fn entity_nodes(&self) -> &'static [&'static str] {
+        self.entity_nodes
+    }
+
+    fn naming_style(&self, _: &str) -> Option<NamingStyle> {
+        None
+    }
+
+    fn is_generated_method(&self, _: &str, _: &Node, _: &str) -> bool {
+        false
+    }
+}
+
+/// The name a language loaded from a file is reported under
+pub fn get_custom_name(idx: u16) -> String {
+    let profiles = CUSTOM_PROFILES.read().unwrap_or_else(|e| e.into_inner());
+    profiles[idx as usize].name.to_string()
+}
+
+/// The profile of a language loaded from a file
+pub(super) fn get_custom_profile(idx: u16) -> &'static dyn LanguageProfile {
+    let profiles = CUSTOM_PROFILES.read().unwrap_or_else(|e| e.into_inner());
+    // Custom languages are only created by `load_language_profile`
+    profiles[idx as usize]
+}
+
+/// The languages loaded from files, in the order they were loaded
+pub fn get_custom_languages() -> Vec<Language> {
+    let profiles = CUSTOM_PROFILES.read().unwrap_or_else(|e| e.into_inner());
+    profiles.iter().map(|profile| profile.language).collect()
+}
+
+/// Load the language profiles of a directory, every `*.toml` file of it, in the order of their
+/// names
+pub fn load_language_profiles(dir_path: &str) -> Result<Vec<Language>, XStatsError> {
+    let entries = fs::read_dir(dir_path).map_err(|e| XStatsError::Read {
+        path: dir_path.to_string(),
+        source: e,
+    })?;
+    let mut file_paths: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    file_paths.sort();
+    file_paths
+        .iter()
+        .map(|file_path| load_language_profile(file_path))
+        .collect()
+}
+
+/// Load a language profile and its grammar, and register the language. A profile loaded again
+/// replaces the language of the same name.
+pub fn load_language_profile(file_path: &str) -> Result<Language, XStatsError> {
+    let invalid = |reason: String| {
+        XStatsError::Config(format!(
+            "Invalid language profile {}: {}",
+            file_path, reason
+        ))
+    };
+    let profile_file: ProfileFile =
+        toml::from_str(&read_file(file_path)?).map_err(|e| invalid(e.to_string()))?;
+
+    let name = profile_file.name.trim().to_string();
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(invalid(format!("invalid name {:?}", profile_file.name)));
+    }
+    let mut names = vec![name.to_lowercase()];
+    names.extend(
+        profile_file
+            .aliases
+            .iter()
+            .map(|alias| alias.to_lowercase()),
+    );
+    let is_taken = |language: &Language| {
+        !matches!(language, Language::Custom(_))
+            && super::get_profile(*language)
+                .names()
+                .iter()
+                .any(|taken| names.contains(&taken.to_string()))
+    };
+    if Language::all().iter().any(is_taken) {
+        return Err(invalid(format!("{} is a supported language", name)));
+    }
+    if profile_file.extensions.is_empty() {
+        return Err(invalid("no extensions".to_string()));
+    }
+    let extensions: Vec<String> = profile_file
+        .extensions
+        .iter()
+        .map(|extension| match extension.starts_with('.') {
+            true => extension.clone(),
+            false => format!(".{}", extension),
+        })
+        .collect();
+    // An extension of two languages would be analyzed as either of them, the language of the
+    // profile loaded again aside
+    let taken_extension = Language::all().into_iter().find_map(|language| {
+        let profile = super::get_profile(language);
+        if profile.names()[0] == names[0] {
+            return None;
+        }
+        let extension = extensions
+            .iter()
+            .find(|extension| profile.extensions().contains(&extension.as_str()))?;
+        Some((extension.clone(), language))
+    });
+    if let Some((extension, language)) = taken_extension {
+        return Err(invalid(format!(
+            "extension {} is taken by {}",
+            extension, language
+        )));
+    }
+
+    let queries = profile_file.queries;
+    let base_query: String = [
+        (queries.comment, "comment"),
+        (queries.import, "import"),
+        (queries.class, "class_definition"),
+        (queries.method, "method_definition"),
+    ]
+    .into_iter()
+    .filter_map(|(pattern, capture_name)| Some(format!("{} @{}", pattern?, capture_name)))
+    .collect();
+    if base_query.is_empty() {
+        return Err(invalid("no queries".to_string()));
+    }
+
+    let profile_dir = Path::new(file_path).parent().unwrap_or(Path::new(""));
+    let library = profile_dir.join(&profile_file.library);
+    let symbol = profile_file
+        .symbol
+        .unwrap_or_else(|| format!("tree_sitter_{}", name.to_lowercase()));
+    let grammar = load_grammar(&library, &symbol).map_err(invalid)?;
+
+    let node_groups = profile_file.node_groups;
+    let mut profiles = CUSTOM_PROFILES.write().unwrap_or_else(|e| e.into_inner());
+    let idx = profiles
+        .iter()
+        .position(|profile| profile.names[0] == names[0])
+        .unwrap_or(profiles.len());
+    let language = Language::Custom(idx as u16);
+    let profile = CustomProfile {
+        language,
+        name: leak_string(name),
+        names: leak_strings(names),
+        grammar,
+        library: leak_string(library.to_string_lossy().to_string()),
+        extensions: leak_strings(extensions),
+        doc_comment_prefixes: leak_strings(profile_file.doc_comment_prefixes),
+        base_query: leak_string(base_query),
+        decision_points: leak_strings(node_groups.decision_points),
+        nesting_nodes: leak_strings(node_groups.nesting),
+        string_nodes: leak_strings(node_groups.strings),
+        entity_nodes: leak_strings(node_groups.entities),
+    };
+    let profile: &'static CustomProfile = Box::leak(Box::new(profile));
+    match profiles.get_mut(idx) {
+        Some(registered) => *registered = profile,
+        None => profiles.push(profile),
+    }
+    Ok(language)
+}
+
+/// Load the grammar returned by a function of a shared library. The library stays loaded for
+/// the rest of the process, as the grammar lives in it.
+#[cfg(unix)]
+fn load_grammar(library: &Path, symbol: &str) -> Result<TSLanguage, String> {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+    use tree_sitter_language::LanguageFn;
+
+    let get_error = || {
+        // SAFETY: dlerror returns null or a valid C string describing the last error
+        let error = unsafe { libc::dlerror() };
+        match error.is_null() {
+            true => "unknown error".to_string(),
+            false => unsafe { CStr::from_ptr(error) }
+                .to_string_lossy()
+                .to_string(),
+        }
+    };
+    let library_path = CString::new(library.as_os_str().as_bytes()).map_err(|e| e.to_string())?;
+    let symbol_name = CString::new(symbol).map_err(|e| e.to_string())?;
+
+    // SAFETY: the paths are valid C strings, and the symbol is that of a tree-sitter grammar,
+    // a function taking no arguments and returning a pointer to the language
+    unsafe {
+        let handle = libc::dlopen(library_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL);
+        if handle.is_null() {
+            return Err(get_error());
+        }
+        let function = libc::dlsym(handle, symbol_name.as_ptr());
+        if function.is_null() {
+            return Err(format!("{} not found in {}", symbol, library.display()));
+        }
+        let function =
+            std::mem::transmute::<*mut libc::c_void, unsafe extern "C" fn() -> *const ()>(function);
+        Ok(LanguageFn::from_raw(function).into())
+    }
+}
+
+#[cfg(not(unix))]
+fn load_grammar(library: &Path, _: &str) -> Result<TSLanguage, String> {
+    Err(format!(
+        "loading {} is only supported on Unix",
+        library.display()
+    ))
+}
+
+fn leak_string(string: String) -> &'static str {
+    Box::leak(string.into_boxed_str())
+}
+
+fn leak_strings(strings: Vec<String>) -> &'static [&'static str] {
+    let strings: Vec<&'static str> = strings.into_iter().map(leak_string).collect();
+    Box::leak(strings.into_boxed_slice())
+}
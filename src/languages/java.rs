@@ -0,0 +1,166 @@
+use super::{get_text, LanguageProfile};
+use crate::config::Language;
+use crate::metrics::NamingStyle;
+use crate::ts::{Node, TSLanguage};
+
+const BASE_QUERY: &str = concat!(
+    "[(line_comment) @comment (block_comment) @comment]",
+    "(import_declaration) @import",
+    "[(class_declaration) @class_definition (object_creation_expression (class_body)) @class_definition]",
+    "[(constructor_declaration) @method_definition (method_declaration) @method_definition (lambda_expression) @method_definition]",
+);
+
+// The members a method uses: those of `this`, and the bare names
+const COHESION_QUERY: &str = concat!(
+    "(field_access object: (this) field: (identifier) @member)",
+    "(method_invocation object: (this) name: (identifier) @member)",
+    "(method_invocation !object name: (identifier) @member)",
+    "(identifier) @name",
+);
+
+// The variables, parameters and constants declared. A name captured both as a variable and
+// as a constant is a constant.
+const NAMING_QUERY: &str = concat!(
+    "(variable_declarator name: (identifier) @variable)",
+    "(field_declaration (modifiers) @modifiers declarator: (variable_declarator name: (identifier) @constant) (#match? @modifiers \"\\\\bstatic\\\\b\") (#match? @modifiers \"\\\\bfinal\\\\b\"))",
+    "(constant_declaration declarator: (variable_declarator name: (identifier) @constant))",
+    "(enum_constant name: (identifier) @constant)",
+    "[(formal_parameter name: (identifier) @variable) (catch_formal_parameter name: (identifier) @variable) (enhanced_for_statement name: (identifier) @variable)]",
+    "[(inferred_parameters (identifier) @variable) (lambda_expression parameters: (identifier) @variable)]",
+);
+
+const DECISION_POINTS: &[&str] = &[
+    "if_statement",
+    "for_statement",
+    "while_statement",
+    "do_statement",
+    "switch_expression",
+    "catch_clause",
+    "ternary_expression",
+    "lambda_expression",
+    "method_reference",
+];
+
+const DECISION_POINTS_SKIP_NODES: &[&str] = &[
+    "class_declaration",
+    "method_declaration",
+    "constructor_declaration",
+    "lambda_expression",
+    // The body of an anonymous class
+    "class_body",
+];
+
+const NESTING_NODES: &[&str] = &[
+    "if_statement",
+    "for_statement",
+    "enhanced_for_statement",
+    "while_statement",
+    "do_statement",
+    "try_statement",
+    "try_with_resources_statement",
+    "switch_expression",
+    "synchronized_statement",
+];
+
+const HALSTEAD_LITERALS: &[&str] = &["string_literal", "character_literal"];
+
+const STRINGS: &[&str] = &["string_literal"];
+
+// The statements leaving a function early or with a value, counted by NORET
+const EXIT_QUERY: &str = "[(return_statement) @exit (throw_statement) @exit]";
+
+// The functions and lambdas whose exit statements are their own rather than those of the
+// function enclosing them
+const EXIT_SKIP_NODES: &[&str] = &[
+    "method_declaration",
+    "constructor_declaration",
+    "lambda_expression",
+];
+
+// The explicit receiver of `void m(Foo this)`, not a parameter
+const PARAMETER_SKIP_NODES: &[&str] = &["receiver_parameter"];
+
+const VARIADIC_PARAMETERS: &[&str] = &["spread_parameter"];
+
+pub struct Java;
+
+impl LanguageProfile for Java {
+    fn language(&self) -> Language {
+        Language::Java
+    }
+
+    fn names(&self) -> &'static [&'static str] {
+        &["java"]
+    }
+
+    fn grammar(&self) -> TSLanguage {
+        tree_sitter_java::LANGUAGE.into()
+    }
+
+    fn grammar_crate(&self) -> &'static str {
+        "tree-sitter-java"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &[".java"]
+    }
+
+    fn doc_comment_prefixes(&self) -> &'static [&'static str] {
+        &["/**"]
+    }
+
+    fn query(&self, query_name: &str) -> Option<&'static str> {
+        match query_name {
+            "base_query" => Some(BASE_QUERY),
+            "cohesion_query" => Some(COHESION_QUERY),
+            "robustness_query" => Some("(catch_clause) @catch"),
+            "naming_query" => Some(NAMING_QUERY),
+            "exit_query" => Some(EXIT_QUERY),
+            _ => None,
+        }
+    }
+
+    fn node_group(&self, group_name: &str) -> Option<&'static [&'static str]> {
+        match group_name {
+            "decision_point_nodes" => Some(DECISION_POINTS),
+            "decision_point_skip_nodes" => Some(DECISION_POINTS_SKIP_NODES),
+            "nesting_nodes" => Some(NESTING_NODES),
+            "halstead_literal_nodes" => Some(HALSTEAD_LITERALS),
+            "string_nodes" => Some(STRINGS),
+            "exit_skip_nodes" => Some(EXIT_SKIP_NODES),
+            "parameter_skip_nodes" => Some(PARAMETER_SKIP_NODES),
+            "variadic_parameter_nodes" => Some(VARIADIC_PARAMETERS),
+            _ => None,
+        }
+    }
+
+    fn entity_nodes(&self) -> &'static [&'static str] {
+        &[
+            "class_declaration",
+            "method_declaration",
+            "constructor_declaration",
+        ]
+    }
+
+    fn naming_style(&self, node_type: &str) -> Option<NamingStyle> {
+        match node_type {
+            "constructor_declaration" => Some(NamingStyle::PascalCase),
+            "method_declaration" => Some(NamingStyle::CamelCase),
+            _ => None,
+        }
+    }
+
+    fn is_generated_method(&self, method_name: &str, _: &Node, _: &str) -> bool {
+        ["equals", "hashCode", "toString"].contains(&method_name)
+    }
+
+    /// e.g. `com.shop` for `package com.shop;`
+    fn get_package_name(&self, root_node: &Node, source_code: &str) -> Option<String> {
+        let mut cursor = root_node.walk();
+        let declaration = root_node
+            .named_children(&mut cursor)
+            .find(|child| child.kind() == "package_declaration")?;
+        let name = declaration.named_child(0)?;
+        Some(get_text(&name, source_code))
+    }
+}
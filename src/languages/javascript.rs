@@ -0,0 +1,272 @@
+use super::LanguageProfile;
+use crate::config::Language;
+use crate::metrics::NamingStyle;
+use crate::ts::{Node, TSLanguage};
+
+const JAVASCRIPT_BASE_QUERY: &str = concat!(
+    "(comment) @comment",
+    "(import_statement) @import",
+    "[(class_declaration) @class_definition (class) @class_definition]",
+    "[(function_declaration) @method_definition (generator_function_declaration) @method_definition (function_expression) @method_definition (generator_function) @method_definition (arrow_function) @method_definition (method_definition) @method_definition]",
+);
+
+const TYPESCRIPT_BASE_QUERY: &str = concat!(
+    "(comment) @comment",
+    "(import_statement) @import",
+    "[(class_declaration) @class_definition (abstract_class_declaration) @class_definition (class) @class_definition]",
+    "[(function_declaration) @method_definition (generator_function_declaration) @method_definition (function_expression) @method_definition (generator_function) @method_definition (arrow_function) @method_definition (method_definition) @method_definition]",
+);
+
+const JAVASCRIPT_NAMING_QUERY: &str = concat!(
+    "(variable_declarator name: (identifier) @variable)",
+    "[(formal_parameters (identifier) @variable) (formal_parameters (assignment_pattern left: (identifier) @variable))]",
+    "[(arrow_function parameter: (identifier) @variable) (catch_clause parameter: (identifier) @variable) (for_in_statement left: (identifier) @variable)]",
+);
+
+const TYPESCRIPT_NAMING_QUERY: &str = concat!(
+    "(variable_declarator name: (identifier) @variable)",
+    "[(required_parameter pattern: (identifier) @variable) (optional_parameter pattern: (identifier) @variable)]",
+    "[(arrow_function parameter: (identifier) @variable) (catch_clause parameter: (identifier) @variable) (for_in_statement left: (identifier) @variable)]",
+);
+
+const JAVASCRIPT_DECISION_POINTS: &[&str] = &[
+    "if_statement",
+    "for_statement",
+    "for_in_statement",
+    "while_statement",
+    "do_statement",
+    "switch_case",
+    "catch_clause",
+    "ternary_expression",
+];
+
+const JAVASCRIPT_DECISION_POINTS_SKIP_NODES: &[&str] = &[
+    "class_declaration",
+    "class",
+    "function_declaration",
+    "generator_function_declaration",
+    "function_expression",
+    "generator_function",
+    "arrow_function",
+    "method_definition",
+];
+
+const TYPESCRIPT_DECISION_POINTS_SKIP_NODES: &[&str] = &[
+    "class_declaration",
+    "abstract_class_declaration",
+    "class",
+    "function_declaration",
+    "generator_function_declaration",
+    "function_expression",
+    "generator_function",
+    "arrow_function",
+    "method_definition",
+];
+
+const JAVASCRIPT_NESTING_NODES: &[&str] = &[
+    "if_statement",
+    "for_statement",
+    "for_in_statement",
+    "while_statement",
+    "do_statement",
+    "try_statement",
+    "switch_statement",
+];
+
+const JAVASCRIPT_HALSTEAD_LITERALS: &[&str] = &["string", "template_string", "regex"];
+
+const JAVASCRIPT_STRINGS: &[&str] = &["string", "template_string"];
+
+// The parameters with a default value, also those of TypeScript with a `value`
+const JAVASCRIPT_DEFAULT_PARAMETERS: &[&str] = &["assignment_pattern"];
+
+const JAVASCRIPT_VARIADIC_PARAMETERS: &[&str] = &["rest_pattern"];
+
+// The statements leaving a function early or with a value, counted by NORET
+const EXIT_QUERY: &str = "[(return_statement) @exit (throw_statement) @exit]";
+
+// The functions whose exit statements are their own rather than those of the function
+// enclosing them
+const EXIT_SKIP_NODES: &[&str] = &[
+    "function_declaration",
+    "generator_function_declaration",
+    "function_expression",
+    "generator_function",
+    "arrow_function",
+    "method_definition",
+];
+
+/// The node groups of JavaScript, also those of TypeScript but for the skipped nodes
+fn get_node_group(group_name: &str) -> Option<&'static [&'static str]> {
+    match group_name {
+        "decision_point_nodes" => Some(JAVASCRIPT_DECISION_POINTS),
+        "decision_point_skip_nodes" => Some(JAVASCRIPT_DECISION_POINTS_SKIP_NODES),
+        "nesting_nodes" => Some(JAVASCRIPT_NESTING_NODES),
+        "halstead_literal_nodes" => Some(JAVASCRIPT_HALSTEAD_LITERALS),
+        "string_nodes" => Some(JAVASCRIPT_STRINGS),
+        "default_parameter_nodes" => Some(JAVASCRIPT_DEFAULT_PARAMETERS),
+        "variadic_parameter_nodes" => Some(JAVASCRIPT_VARIADIC_PARAMETERS),
+        "exit_skip_nodes" => Some(EXIT_SKIP_NODES),
+        _ => None,
+    }
+}
+
+fn get_naming_style(node_type: &str) -> Option<NamingStyle> {
+    match node_type {
+        "function_declaration"
+        | "generator_function_declaration"
+        | "function_expression"
+        | "generator_function"
+        | "arrow_function"
+        | "method_definition" => Some(NamingStyle::CamelCase),
+        _ => None,
+    }
+}
+
+fn get_assigned_name_field(parent_kind: &str) -> Option<&'static str> {
+    match parent_kind {
+        "variable_declarator" | "public_field_definition" => Some("name"),
+        "field_definition" => Some("property"),
+        "pair" => Some("key"),
+        "assignment_expression" => Some("left"),
+        _ => None,
+    }
+}
+
+pub struct JavaScript;
+
+impl LanguageProfile for JavaScript {
+    fn language(&self) -> Language {
+        Language::JavaScript
+    }
+
+    fn names(&self) -> &'static [&'static str] {
+        &["javascript", "js"]
+    }
+
+    fn grammar(&self) -> TSLanguage {
+        tree_sitter_javascript::LANGUAGE.into()
+    }
+
+    fn grammar_crate(&self) -> &'static str {
+        "tree-sitter-javascript"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &[".js", ".mjs", ".cjs", ".jsx"]
+    }
+
+    fn doc_comment_prefixes(&self) -> &'static [&'static str] {
+        &["/**"]
+    }
+
+    fn query(&self, query_name: &str) -> Option<&'static str> {
+        match query_name {
+            "base_query" => Some(JAVASCRIPT_BASE_QUERY),
+            "robustness_query" => Some("(catch_clause) @catch"),
+            "naming_query" => Some(JAVASCRIPT_NAMING_QUERY),
+            "exit_query" => Some(EXIT_QUERY),
+            _ => None,
+        }
+    }
+
+    fn node_group(&self, group_name: &str) -> Option<&'static [&'static str]> {
+        get_node_group(group_name)
+    }
+
+    fn entity_nodes(&self) -> &'static [&'static str] {
+        TYPESCRIPT_DECISION_POINTS_SKIP_NODES
+    }
+
+    fn naming_style(&self, node_type: &str) -> Option<NamingStyle> {
+        get_naming_style(node_type)
+    }
+
+    fn is_generated_method(&self, method_name: &str, _: &Node, _: &str) -> bool {
+        method_name == "toString"
+    }
+
+    fn get_assigned_name_field(&self, parent_kind: &str) -> Option<&'static str> {
+        get_assigned_name_field(parent_kind)
+    }
+}
+
+/// TypeScript, or TSX for the files mixing it with JSX, whose grammar differs
+pub struct TypeScript {
+    pub tsx: bool,
+}
+
+impl LanguageProfile for TypeScript {
+    fn language(&self) -> Language {
+        if self.tsx {
+            Language::Tsx
+        } else {
+            Language::TypeScript
+        }
+    }
+
+    fn names(&self) -> &'static [&'static str] {
+        if self.tsx {
+            &["tsx"]
+        } else {
+            &["typescript", "ts"]
+        }
+    }
+
+    fn grammar(&self) -> TSLanguage {
+        if self.tsx {
+            tree_sitter_typescript::LANGUAGE_TSX.into()
+        } else {
+            tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into()
+        }
+    }
+
+    fn grammar_crate(&self) -> &'static str {
+        "tree-sitter-typescript"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        if self.tsx {
+            &[".tsx"]
+        } else {
+            &[".ts", ".mts", ".cts"]
+        }
+    }
+
+    fn doc_comment_prefixes(&self) -> &'static [&'static str] {
+        &["/**"]
+    }
+
+    fn query(&self, query_name: &str) -> Option<&'static str> {
+        match query_name {
+            "base_query" => Some(TYPESCRIPT_BASE_QUERY),
+            "robustness_query" => Some("(catch_clause) @catch"),
+            "naming_query" => Some(TYPESCRIPT_NAMING_QUERY),
+            "exit_query" => Some(EXIT_QUERY),
+            _ => None,
+        }
+    }
+
+    fn node_group(&self, group_name: &str) -> Option<&'static [&'static str]> {
+        match group_name {
+            "decision_point_skip_nodes" => Some(TYPESCRIPT_DECISION_POINTS_SKIP_NODES),
+            _ => get_node_group(group_name),
+        }
+    }
+
+    fn entity_nodes(&self) -> &'static [&'static str] {
+        TYPESCRIPT_DECISION_POINTS_SKIP_NODES
+    }
+
+    fn naming_style(&self, node_type: &str) -> Option<NamingStyle> {
+        get_naming_style(node_type)
+    }
+
+    fn is_generated_method(&self, method_name: &str, _: &Node, _: &str) -> bool {
+        method_name == "toString"
+    }
+
+    fn get_assigned_name_field(&self, parent_kind: &str) -> Option<&'static str> {
+        get_assigned_name_field(parent_kind)
+    }
+}
@@ -0,0 +1,114 @@
+mod c;
+mod custom;
+mod java;
+mod javascript;
+mod python;
+mod rust;
+pub use c::{Cpp, C};
+pub use custom::{
+    get_custom_languages, get_custom_name, load_language_profile, load_language_profiles,
+};
+pub use java::Java;
+pub use javascript::{JavaScript, TypeScript};
+pub use python::Python;
+pub use rust::Rust;
+
+use crate::config::Language;
+use crate::metrics::NamingStyle;
+use crate::ts::{Node, TSLanguage};
+
+/// Everything the analysis needs to know about a language: its grammar and files, the queries
+/// and node groups measuring its code, and how to name its entities.
+///
+/// Supporting a new language takes a `Language` variant, an implementation of this trait in a
+/// module of its own, and its entry in `get_profile`. Languages may also be described by profile
+/// files, see `load_language_profiles`.
+pub trait LanguageProfile: Sync {
+    fn language(&self) -> Language;
+
+    /// The lowercase names the language goes by, e.g. as declared by a notebook kernel
+    fn names(&self) -> &'static [&'static str];
+
+    fn grammar(&self) -> TSLanguage;
+
+    /// The tree-sitter crate providing the grammar of the language
+    fn grammar_crate(&self) -> &'static str;
+
+    /// The extensions of the files of the language, with their leading dot
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// The prefixes marking a comment as a doc comment by default
+    fn doc_comment_prefixes(&self) -> &'static [&'static str];
+
+    /// The query of one of the `QUERY_GROUPS`, if the language defines it
+    fn query(&self, query_name: &str) -> Option<&'static str>;
+
+    /// The node kinds of one of the `NODE_GROUPS`, if the language defines it
+    fn node_group(&self, group_name: &str) -> Option<&'static [&'static str]>;
+
+    /// The node kinds of the classes and methods, whose syntax errors are their own rather than
+    /// those of the entities enclosing them
+    fn entity_nodes(&self) -> &'static [&'static str];
+
+    /// The naming convention of the entities of the given node type, besides the classes, which
+    /// are in PascalCase in every language
+    fn naming_style(&self, node_type: &str) -> Option<NamingStyle>;
+
+    /// Check if a method is one of the methods usually generated by IDEs, or derived
+    fn is_generated_method(&self, method_name: &str, method_node: &Node, source_code: &str)
+        -> bool;
+
+    /// The parameter names referring to the object or class itself, left out of the parameters
+    /// and identifiers, e.g. `self` in Python
+    fn receiver_names(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// The name of a class node which has no `name` field, if the language names it otherwise
+    fn get_class_name(&self, _class_node: &Node, _source_code: &str) -> Option<String> {
+        None
+    }
+
+    /// The name of a method node which has no `name` field, if the language names it otherwise
+    fn get_method_name(&self, _method_node: &Node, _source_code: &str) -> Option<String> {
+        None
+    }
+
+    /// The parameter list of a method node which has no `parameters` field, e.g. that of the
+    /// declarator of a C function
+    fn get_parameter_list<'t>(&self, _method_node: &Node<'t>) -> Option<Node<'t>> {
+        None
+    }
+
+    /// The field of the parent of an anonymous class or function naming it, e.g. `name` of the
+    /// `variable_declarator` of `const add = (a, b) => a + b;`
+    fn get_assigned_name_field(&self, _parent_kind: &str) -> Option<&'static str> {
+        None
+    }
+
+    /// The package declared by a file, if the language declares packages
+    fn get_package_name(&self, _root_node: &Node, _source_code: &str) -> Option<String> {
+        None
+    }
+}
+
+/// The profile of a supported language
+pub fn get_profile(language: Language) -> &'static dyn LanguageProfile {
+    match language {
+        Language::Java => &Java,
+        Language::Python => &Python,
+        Language::Rust => &Rust,
+        Language::JavaScript => &JavaScript,
+        Language::TypeScript => &TypeScript { tsx: false },
+        Language::Tsx => &TypeScript { tsx: true },
+        Language::C => &C,
+        Language::Cpp => &Cpp,
+        Language::Custom(idx) => custom::get_custom_profile(idx),
+    }
+}
+
+fn get_text(node: &Node, source_code: &str) -> String {
+    node.utf8_text(source_code.as_bytes())
+        .unwrap_or_default()
+        .to_string()
+}
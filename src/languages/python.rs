@@ -0,0 +1,139 @@
+use super::LanguageProfile;
+use crate::config::Language;
+use crate::metrics::NamingStyle;
+use crate::ts::{Node, TSLanguage};
+
+const BASE_QUERY: &str = concat!(
+    "[(comment) @comment (expression_statement (string) @comment)]",
+    "[(import_statement) @import (import_from_statement) @import]",
+    "(class_definition) @class_definition",
+    "[(function_definition) @method_definition (lambda) @method_definition]",
+);
+
+// The members a method uses: the attributes of `self`
+const COHESION_QUERY: &str =
+    "(attribute object: (identifier) @object attribute: (identifier) @member (#eq? @object \"self\"))";
+
+const NAMING_QUERY: &str = concat!(
+    "[(parameters (identifier) @variable) (lambda_parameters (identifier) @variable) (typed_parameter (identifier) @variable)]",
+    "[(default_parameter name: (identifier) @variable) (typed_default_parameter name: (identifier) @variable)]",
+    "[(assignment left: (identifier) @variable) (assignment left: (pattern_list (identifier) @variable))]",
+    "[(for_statement left: (identifier) @variable) (for_statement left: (pattern_list (identifier) @variable))]",
+    "(assignment left: (identifier) @constant type: (type) @type (#match? @type \"^(typing\\\\.)?Final\\\\b\"))",
+);
+
+const DECISION_POINTS: &[&str] = &[
+    "if_statement",
+    "elif_clause",
+    "for_statement",
+    "while_statement",
+    "with_statement",
+    "try_statement",
+    "except_clause",
+    "match_statement",
+    "case_clause",
+    "conditional_expression",
+    "lambda",
+];
+
+const DECISION_POINTS_SKIP_NODES: &[&str] = &["class_definition", "function_definition", "lambda"];
+
+const NESTING_NODES: &[&str] = &[
+    "if_statement",
+    "for_statement",
+    "while_statement",
+    "try_statement",
+    "with_statement",
+    "match_statement",
+];
+
+const HALSTEAD_LITERALS: &[&str] = &["string", "concatenated_string"];
+
+const STRINGS: &[&str] = &["string"];
+
+// The statements leaving a function early or with a value, counted by NORET
+const EXIT_QUERY: &str = "[(return_statement) @exit (raise_statement) @exit]";
+
+// The functions and lambdas whose exit statements are their own rather than those of the
+// function enclosing them
+const EXIT_SKIP_NODES: &[&str] = &["function_definition"];
+
+// The bare `*` and `/` separating keyword-only and positional-only parameters
+const PARAMETER_SKIP_NODES: &[&str] = &["keyword_separator", "positional_separator"];
+
+const DEFAULT_PARAMETERS: &[&str] = &["default_parameter", "typed_default_parameter"];
+
+const VARIADIC_PARAMETERS: &[&str] = &["list_splat_pattern", "dictionary_splat_pattern"];
+
+pub struct Python;
+
+impl LanguageProfile for Python {
+    fn language(&self) -> Language {
+        Language::Python
+    }
+
+    fn names(&self) -> &'static [&'static str] {
+        &["python", "python3"]
+    }
+
+    fn grammar(&self) -> TSLanguage {
+        tree_sitter_python::LANGUAGE.into()
+    }
+
+    fn grammar_crate(&self) -> &'static str {
+        "tree-sitter-python"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &[".py"]
+    }
+
+    fn doc_comment_prefixes(&self) -> &'static [&'static str] {
+        &["\"\"\"", "'''"]
+    }
+
+    fn query(&self, query_name: &str) -> Option<&'static str> {
+        match query_name {
+            "base_query" => Some(BASE_QUERY),
+            "cohesion_query" => Some(COHESION_QUERY),
+            "robustness_query" => Some("(except_clause) @catch"),
+            "naming_query" => Some(NAMING_QUERY),
+            "exit_query" => Some(EXIT_QUERY),
+            _ => None,
+        }
+    }
+
+    fn node_group(&self, group_name: &str) -> Option<&'static [&'static str]> {
+        match group_name {
+            "decision_point_nodes" => Some(DECISION_POINTS),
+            "decision_point_skip_nodes" => Some(DECISION_POINTS_SKIP_NODES),
+            "nesting_nodes" => Some(NESTING_NODES),
+            "halstead_literal_nodes" => Some(HALSTEAD_LITERALS),
+            "string_nodes" => Some(STRINGS),
+            "exit_skip_nodes" => Some(EXIT_SKIP_NODES),
+            "parameter_skip_nodes" => Some(PARAMETER_SKIP_NODES),
+            "default_parameter_nodes" => Some(DEFAULT_PARAMETERS),
+            "variadic_parameter_nodes" => Some(VARIADIC_PARAMETERS),
+            _ => None,
+        }
+    }
+
+    fn entity_nodes(&self) -> &'static [&'static str] {
+        &["class_definition", "function_definition"]
+    }
+
+    fn naming_style(&self, node_type: &str) -> Option<NamingStyle> {
+        match node_type {
+            "function_definition" => Some(NamingStyle::SnakeCase),
+            _ => None,
+        }
+    }
+
+    fn is_generated_method(&self, method_name: &str, _: &Node, _: &str) -> bool {
+        ["__eq__", "__ne__", "__hash__", "__repr__", "__str__"].contains(&method_name)
+    }
+
+    fn receiver_names(&self) -> &'static [&'static str] {
+        &["self", "cls"]
+    }
+}
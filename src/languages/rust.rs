@@ -0,0 +1,180 @@
+use super::{get_text, LanguageProfile};
+use crate::config::Language;
+use crate::metrics::NamingStyle;
+use crate::ts::{Node, TSLanguage};
+
+const BASE_QUERY: &str = concat!(
+    "[(line_comment) @comment (block_comment) @comment]",
+    "[(use_declaration) @import (extern_crate_declaration) @import]",
+    "[(struct_item) @class_definition (enum_item) @class_definition (union_item) @class_definition (trait_item) @class_definition (impl_item) @class_definition (mod_item body: (declaration_list)) @class_definition]",
+    "[(function_item) @method_definition (closure_expression) @method_definition]",
+);
+
+// The calls panicking, as there are no exceptions to handle
+const ROBUSTNESS_QUERY: &str = concat!(
+    "(call_expression function: (field_expression field: (field_identifier) @name) (#match? @name \"^(unwrap|expect)$\")) @panic",
+    "(macro_invocation macro: (identifier) @name (#match? @name \"^(panic|todo|unimplemented|unreachable)$\")) @panic",
+);
+
+const NAMING_QUERY: &str = concat!(
+    "[(let_declaration pattern: (identifier) @variable) (let_declaration pattern: (tuple_pattern (identifier) @variable))]",
+    "[(parameter pattern: (identifier) @variable) (closure_parameters (identifier) @variable) (for_expression pattern: (identifier) @variable)]",
+    "[(const_item name: (identifier) @constant) (static_item name: (identifier) @constant)]",
+);
+
+const DECISION_POINTS: &[&str] = &[
+    "if_expression",
+    "for_expression",
+    "while_expression",
+    "loop_expression",
+    "match_arm",
+    "try_expression",
+    "closure_expression",
+];
+
+const DECISION_POINTS_SKIP_NODES: &[&str] = &[
+    "impl_item",
+    "trait_item",
+    "mod_item",
+    "function_item",
+    "closure_expression",
+];
+
+const NESTING_NODES: &[&str] = &[
+    "if_expression",
+    "for_expression",
+    "while_expression",
+    "loop_expression",
+    "match_expression",
+];
+
+const HALSTEAD_LITERALS: &[&str] = &["string_literal", "raw_string_literal", "char_literal"];
+
+const STRINGS: &[&str] = &["string_literal", "raw_string_literal"];
+
+// The statements leaving a function early or with a value, counted by NORET
+const EXIT_QUERY: &str = "(return_expression) @exit";
+
+// The functions and lambdas whose exit statements are their own rather than those of the
+// function enclosing them
+const EXIT_SKIP_NODES: &[&str] = &["function_item", "closure_expression", "async_block"];
+
+const PARAMETER_SKIP_NODES: &[&str] = &["self_parameter", "attribute_item"];
+
+// The `...` of the functions of `extern` blocks
+const VARIADIC_PARAMETERS: &[&str] = &["variadic_parameter"];
+
+/// The traits usually derived, whose methods are generated
+const DERIVED_TRAITS: &[&str] = &[
+    "Clone",
+    "Debug",
+    "Default",
+    "Display",
+    "Eq",
+    "Hash",
+    "PartialEq",
+];
+
+pub struct Rust;
+
+impl LanguageProfile for Rust {
+    fn language(&self) -> Language {
+        Language::Rust
+    }
+
+    fn names(&self) -> &'static [&'static str] {
+        &["rust"]
+    }
+
+    fn grammar(&self) -> TSLanguage {
+        tree_sitter_rust::LANGUAGE.into()
+    }
+
+    fn grammar_crate(&self) -> &'static str {
+        "tree-sitter-rust"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &[".rs"]
+    }
+
+    fn doc_comment_prefixes(&self) -> &'static [&'static str] {
+        &["///", "//!", "/**", "/*!"]
+    }
+
+    fn query(&self, query_name: &str) -> Option<&'static str> {
+        match query_name {
+            "base_query" => Some(BASE_QUERY),
+            "robustness_query" => Some(ROBUSTNESS_QUERY),
+            "naming_query" => Some(NAMING_QUERY),
+            "exit_query" => Some(EXIT_QUERY),
+            _ => None,
+        }
+    }
+
+    fn node_group(&self, group_name: &str) -> Option<&'static [&'static str]> {
+        match group_name {
+            "decision_point_nodes" => Some(DECISION_POINTS),
+            "decision_point_skip_nodes" => Some(DECISION_POINTS_SKIP_NODES),
+            "nesting_nodes" => Some(NESTING_NODES),
+            "halstead_literal_nodes" => Some(HALSTEAD_LITERALS),
+            "string_nodes" => Some(STRINGS),
+            "exit_skip_nodes" => Some(EXIT_SKIP_NODES),
+            "parameter_skip_nodes" => Some(PARAMETER_SKIP_NODES),
+            "variadic_parameter_nodes" => Some(VARIADIC_PARAMETERS),
+            _ => None,
+        }
+    }
+
+    fn entity_nodes(&self) -> &'static [&'static str] {
+        &[
+            "struct_item",
+            "enum_item",
+            "union_item",
+            "trait_item",
+            "impl_item",
+            "mod_item",
+            "function_item",
+        ]
+    }
+
+    fn naming_style(&self, node_type: &str) -> Option<NamingStyle> {
+        match node_type {
+            "function_item" | "mod_item" => Some(NamingStyle::SnakeCase),
+            _ => None,
+        }
+    }
+
+    /// Methods implementing the traits usually derived
+    fn is_generated_method(&self, _: &str, method_node: &Node, source_code: &str) -> bool {
+        let impl_node = method_node
+            .parent()
+            .and_then(|parent| parent.parent())
+            .filter(|parent| parent.kind() == "impl_item");
+        let trait_name = impl_node
+            .and_then(|impl_node| impl_node.child_by_field_name("trait"))
+            .map(|trait_node| get_text(&trait_node, source_code));
+        trait_name.is_some_and(|trait_name| {
+            let trait_name = trait_name.rsplit("::").next().unwrap_or(&trait_name);
+            DERIVED_TRAITS.contains(&trait_name)
+        })
+    }
+
+    /// Impl blocks have no name, they are named after the implemented trait and type
+    fn get_class_name(&self, class_node: &Node, source_code: &str) -> Option<String> {
+        if class_node.kind() != "impl_item" {
+            return None;
+        }
+        let get_field_text = |field_name| {
+            class_node
+                .child_by_field_name(field_name)
+                .map(|field_node| get_text(&field_node, source_code))
+                .unwrap_or_default()
+        };
+        let type_name = get_field_text("type");
+        Some(match class_node.child_by_field_name("trait") {
+            Some(_) => format!("{} for {}", get_field_text("trait"), type_name),
+            None => type_name,
+        })
+    }
+}
@@ -2,10 +2,15 @@
 #![allow(dead_code)]
 #![allow(unused_variables)]
 
+pub mod analysis;
 pub mod config;
 pub mod core;
+pub mod error;
+pub mod extract;
 pub mod graph;
+pub mod languages;
 pub mod metrics;
+pub mod output;
 pub mod ts;
 pub mod utils;
 pub mod visitor;
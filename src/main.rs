@@ -1,33 +1,933 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use indicatif::MultiProgress;
+use log::{error, info, warn, LevelFilter};
+use std::sync::OnceLock;
+use std::time::Duration;
+use xstats::config::ConfigFile;
 use xstats::core;
+use xstats::core::{Portfolio, PortfolioManifest, Progress};
+use xstats::error::XStatsError;
+use xstats::languages;
+use xstats::metrics::{CodeMetric, CodeMetricBlock, DiffStatus, MetricsFilter};
+use xstats::output::create_sink;
+use xstats::utils::csv_dialect::CsvDialect;
+use xstats::utils::encoding::Encoding;
+use xstats::utils::logger::Logger;
+use xstats::utils::path_filter::PathFilter;
+use xstats::utils::progress_bar::CustomProgressBar;
+use xstats::utils::version_control::{CommitFilter, CommitScope, RepositoryError, RevwalkConfig};
+use xstats::utils::SymlinkPolicy;
 
 #[derive(Parser)]
-#[clap(version = "0.1.0", author = "Gautam Shetty")]
+#[clap(
+    version = "0.1.0",
+    author = "Gautam Shetty",
+    subcommand_negates_reqs = true
+)]
 struct Options {
-    #[clap(short = 't', long = "target")]
-    target: String,
+    #[clap(subcommand)]
+    command: Option<Command>,
 
-    #[clap(short = 'o', long = "output")]
-    output: String,
+    #[clap(short = 't', long = "target", required = true)]
+    target: Option<String>,
+
+    #[clap(short = 'o', long = "output", required_unless_present = "stdout")]
+    output: Option<String>,
+
+    #[clap(long = "stdout", default_value = "false", conflicts_with = "output")]
+    stdout: bool,
 
     #[clap(short = 'a', long = "all-commits", default_value = "false")]
     all_commits: bool,
 
-    #[clap(long = "format", default_value = "json")]
-    format: String,
+    #[clap(long = "clone-depth")]
+    clone_depth: Option<u32>,
+
+    #[clap(long = "format")]
+    format: Option<String>,
+
+    #[clap(long = "csv-delimiter", global = true)]
+    csv_delimiter: Option<String>,
+
+    #[clap(long = "csv-quote", global = true)]
+    csv_quote: Option<String>,
+
+    #[clap(long = "decimal-separator", global = true)]
+    decimal_separator: Option<String>,
+
+    #[clap(short = 'q', long = "quiet", default_value = "false", global = true)]
+    quiet: bool,
+
+    #[clap(
+        short = 'v',
+        long = "verbose",
+        action = clap::ArgAction::Count,
+        conflicts_with = "quiet",
+        global = true
+    )]
+    verbose: u8,
+
+    #[clap(long = "log-file", global = true)]
+    log_file: Option<String>,
+
+    #[clap(long = "doc-comment")]
+    doc_comments: Vec<String>,
+
+    #[clap(long = "custom-query")]
+    custom_queries: Vec<String>,
+
+    #[clap(long = "extension-language")]
+    extension_languages: Vec<String>,
+
+    #[clap(long = "encoding")]
+    encoding: Option<String>,
+
+    #[clap(long = "symlinks")]
+    symlinks: Option<String>,
+
+    #[clap(long = "max-depth")]
+    max_depth: Option<usize>,
+
+    #[clap(long = "active-code", default_value = "false")]
+    active_code: bool,
+
+    #[clap(long = "half-life")]
+    half_life: Option<f64>,
+
+    #[clap(long = "only", value_delimiter = ',')]
+    only: Vec<String>,
+
+    #[clap(long = "min-aloc")]
+    min_aloc: Option<u32>,
+
+    #[clap(long = "exclude-boilerplate", default_value = "false")]
+    exclude_boilerplate: bool,
+
+    #[clap(long = "since")]
+    since: Option<String>,
+
+    #[clap(long = "until")]
+    until: Option<String>,
+
+    #[clap(long = "max-commits")]
+    max_commits: Option<usize>,
+
+    #[clap(long = "commit-jobs")]
+    commit_jobs: Option<usize>,
+
+    #[clap(long = "stream", default_value = "false")]
+    stream: bool,
+
+    #[clap(long = "resume", default_value = "false")]
+    resume: bool,
+
+    #[clap(long = "skip-commit-message")]
+    skip_commit_messages: Vec<String>,
+
+    #[clap(long = "only-commit-message")]
+    only_commit_messages: Vec<String>,
+
+    #[clap(long = "skip-merges", default_value = "false")]
+    skip_merges: bool,
+
+    #[clap(long = "first-parent", default_value = "false")]
+    first_parent: bool,
+
+    #[clap(long = "author")]
+    author_patterns: Vec<String>,
+
+    #[clap(long = "path")]
+    path_patterns: Vec<String>,
+
+    #[clap(long = "after")]
+    after: Option<String>,
+
+    #[clap(long = "before")]
+    before: Option<String>,
+
+    #[clap(long = "include")]
+    include: Vec<String>,
+
+    #[clap(long = "exclude")]
+    exclude: Vec<String>,
+
+    #[clap(long = "cache", default_value = "false")]
+    cache: bool,
+
+    #[clap(long = "min-similarity")]
+    min_similarity: Option<f64>,
+
+    #[clap(long = "provenance", default_value = "false")]
+    provenance: bool,
+
+    #[clap(long = "sarif", default_value = "false")]
+    sarif: bool,
+
+    #[clap(long = "graphml", default_value = "false")]
+    graphml: bool,
+
+    #[clap(long = "gexf", default_value = "false")]
+    gexf: bool,
+
+    #[clap(long = "naming", default_value = "false")]
+    naming: bool,
+
+    #[clap(long = "todos", default_value = "false")]
+    todos: bool,
+
+    #[clap(long = "track-entities", default_value = "false")]
+    track_entities: bool,
+
+    #[clap(long = "authors", default_value = "false")]
+    authors: bool,
+
+    #[clap(long = "ownership", default_value = "false")]
+    ownership: bool,
+
+    #[clap(long = "markers", value_delimiter = ',')]
+    markers: Vec<String>,
+
+    #[clap(long = "export-state")]
+    export_state: Option<String>,
+
+    #[clap(long = "fail-on")]
+    fail_on: Option<String>,
+
+    #[clap(long = "baseline-ref")]
+    baseline_ref: Option<String>,
+
+    #[clap(long = "diff-base")]
+    diff_base: Option<String>,
+
+    #[clap(long = "rev", conflicts_with_all = ["diff_base", "all_commits"])]
+    rev: Option<String>,
+
+    #[clap(long = "file-timeout")]
+    file_timeout: Option<f64>,
+
+    #[clap(long = "total-timeout")]
+    total_timeout: Option<f64>,
+
+    #[clap(long = "max-trees")]
+    max_trees: Option<usize>,
+
+    #[clap(long = "max-trees-memory")]
+    max_trees_memory: Option<usize>,
+
+    #[clap(long = "languages-dir")]
+    languages_dir: Option<String>,
+
+    #[clap(short = 'c', long = "config")]
+    config: Option<String>,
+
+    #[clap(short = 'f', long = "force", default_value = "false")]
+    force: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compare the grades, trends and hotspots of the repositories listed in a manifest
+    Portfolio {
+        #[clap(short = 'm', long = "manifest")]
+        manifest: String,
+
+        #[clap(short = 'o', long = "output")]
+        output: String,
+
+        #[clap(long = "format", default_value = "json")]
+        format: String,
+
+        #[clap(short = 'f', long = "force", default_value = "false")]
+        force: bool,
+    },
+    /// Save the outputs of an analysis exported with --export-state, without analyzing again
+    ImportState {
+        #[clap(short = 's', long = "state")]
+        state: String,
+
+        #[clap(short = 'o', long = "output")]
+        output: String,
+
+        #[clap(long = "format", default_value = "json")]
+        format: String,
+
+        #[clap(long = "min-similarity")]
+        min_similarity: Option<f64>,
+
+        #[clap(long = "graphml", default_value = "false")]
+        graphml: bool,
+
+        #[clap(long = "gexf", default_value = "false")]
+        gexf: bool,
+
+        #[clap(short = 'f', long = "force", default_value = "false")]
+        force: bool,
+    },
+    /// Report the entities added, removed or regressed between two sets of metrics: two
+    /// metrics.json saved by previous runs, or the target as of a revision and as it is
+    Diff {
+        #[clap(
+            long = "baseline",
+            requires = "current",
+            required_unless_present = "against"
+        )]
+        baseline: Option<String>,
+
+        #[clap(long = "current", requires = "baseline")]
+        current: Option<String>,
+
+        #[clap(long = "against", conflicts_with_all = ["baseline", "current"])]
+        against: Option<String>,
+
+        #[clap(short = 't', long = "target", default_value = ".")]
+        target: String,
+
+        #[clap(short = 'o', long = "output")]
+        output: String,
+
+        #[clap(long = "format", default_value = "json")]
+        format: String,
+
+        #[clap(short = 'f', long = "force", default_value = "false")]
+        force: bool,
+    },
+    /// Report how the entities changed between two revisions, e.g. two release tags, both read
+    /// from git
+    Compare {
+        #[clap(long = "from")]
+        from: String,
+
+        #[clap(long = "to")]
+        to: String,
+
+        #[clap(short = 't', long = "target", default_value = ".")]
+        target: String,
+
+        #[clap(short = 'o', long = "output")]
+        output: String,
+
+        #[clap(long = "format", default_value = "json")]
+        format: String,
+
+        #[clap(short = 'f', long = "force", default_value = "false")]
+        force: bool,
+    },
 }
 
 fn main() {
     let options: Options = Options::parse();
+    // The logger is not installed yet to log its own errors
+    let logger = init_logger(&options).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    if let Some(command) = options.command {
+        let csv_dialect = exit_on_error(CsvDialect::from_options(
+            options.csv_delimiter.as_deref(),
+            options.csv_quote.as_deref(),
+            options.decimal_separator.as_deref(),
+        ));
+        match command {
+            Command::Portfolio {
+                manifest,
+                output,
+                format,
+                force,
+            } => run_portfolio(&manifest, &output, &format, csv_dialect, force),
+            Command::ImportState {
+                state,
+                output,
+                format,
+                min_similarity,
+                graphml,
+                gexf,
+                force,
+            } => {
+                let mut xstats = core::XStats::new(String::new(), output);
+                xstats.csv_dialect = csv_dialect;
+                xstats.graphml = graphml;
+                xstats.gexf = gexf;
+                xstats.force = force;
+                let min_similarity = exit_on_error(get_similarity(min_similarity.unwrap_or(0.8)));
+                run_import_state(&mut xstats, &state, &format, min_similarity);
+            }
+            Command::Diff {
+                baseline,
+                current,
+                against,
+                target,
+                output,
+                format,
+                force,
+            } => {
+                let to_stdout = output == "-";
+                let mut xstats = core::XStats::new(target, output);
+                show_progress(&mut xstats.progress);
+                xstats.csv_dialect = csv_dialect;
+                xstats.force = force;
+                let inputs = match (against, baseline, current) {
+                    (Some(against), _, _) => DiffInputs::Revision(against),
+                    (None, Some(baseline), Some(current)) => DiffInputs::Files(baseline, current),
+                    _ => unreachable!("--against, or --baseline and --current, are required"),
+                };
+                run_diff(&mut xstats, inputs, &format, to_stdout);
+            }
+            Command::Compare {
+                from,
+                to,
+                target,
+                output,
+                format,
+                force,
+            } => {
+                let to_stdout = output == "-";
+                let mut xstats = core::XStats::new(target, output);
+                show_progress(&mut xstats.progress);
+                xstats.csv_dialect = csv_dialect;
+                xstats.force = force;
+                let inputs = DiffInputs::Revisions(from, to);
+                run_diff(&mut xstats, inputs, &format, to_stdout);
+            }
+        }
+        print_warnings(logger);
+        return;
+    }
+    // --stdout is short for --output -
+    let output = options.output.unwrap_or_else(|| "-".to_string());
+    let Some(target) = options.target else {
+        unreachable!("--target is required without a subcommand");
+    };
+    // The metrics are written to stdout, and the rest of the results to stderr
+    let to_stdout = output == "-";
+    // Remote repositories, and bare ones unless only their git objects are read, are analyzed
+    // from a clone, removed once done
+    let target_clone = exit_on_error(core::TargetClone::new(
+        &target,
+        options.all_commits || options.rev.is_some(),
+        options.clone_depth,
+    ));
+    let target = match &target_clone {
+        Some(target_clone) => target_clone.get_path().to_string_lossy().to_string(),
+        None => target,
+    };
+
+    // The languages of profile files are analyzed like the built-in ones
+    if let Some(languages_dir) = &options.languages_dir {
+        exit_on_error(languages::load_language_profiles(languages_dir));
+    }
+    let config_file = match &options.config {
+        Some(config_path) => Some(exit_on_error(ConfigFile::load(config_path))),
+        None => exit_on_error(ConfigFile::find(&target)),
+    }
+    .unwrap_or_default();
 
-    let mut xstats = core::XStats::new(options.target, options.output);
+    let cache_dir = (options.cache || config_file.cache).then(|| core::get_cache_dir(&target));
+    let mut xstats = core::XStats::new(target, output);
+    show_progress(&mut xstats.progress);
+    xstats.force = options.force;
+    xstats.graphml = options.graphml || config_file.graphml;
+    xstats.gexf = options.gexf || config_file.gexf;
+    xstats.track_entities = options.track_entities || config_file.track_entities;
+    xstats.cache_dir = cache_dir;
+    xstats.diff_base = options.diff_base.or(config_file.diff_base);
+    xstats.rev = options.rev;
+    xstats.csv_dialect = exit_on_error(CsvDialect::from_options(
+        options
+            .csv_delimiter
+            .or(config_file.csv_delimiter)
+            .as_deref(),
+        options.csv_quote.or(config_file.csv_quote).as_deref(),
+        options
+            .decimal_separator
+            .or(config_file.decimal_separator)
+            .as_deref(),
+    ));
 
-    if options.all_commits {
-        xstats.run_multi_commit();
-        xstats.save_metrics_map(options.format.as_str());
+    // Command line flags take precedence over the config file
+    for doc_comment in config_file.doc_comments.iter().chain(&options.doc_comments) {
+        exit_on_error(xstats.config.add_doc_comment_prefix(doc_comment));
+    }
+    for custom_query in config_file
+        .custom_queries
+        .iter()
+        .chain(&options.custom_queries)
+    {
+        exit_on_error(xstats.config.add_custom_query(custom_query));
+    }
+    for extension_language in config_file
+        .extension_languages
+        .iter()
+        .chain(&options.extension_languages)
+    {
+        exit_on_error(xstats.config.add_extension_language(extension_language));
+    }
+    if let Some(encoding) = options.encoding.or(config_file.encoding) {
+        xstats.config.encoding = exit_on_error(Encoding::from_name(&encoding));
+    }
+    exit_on_error(xstats.config.set_languages(&config_file.languages));
+    let markers = if options.markers.is_empty() {
+        &config_file.markers
     } else {
-        xstats.run_default();
-        xstats.save_metrics(options.format.as_str());
-        xstats.save_tdg();
+        &options.markers
+    };
+    exit_on_error(xstats.config.set_markers(markers));
+    let grammar_errors = xstats.check_grammars();
+    for e in &grammar_errors {
+        error!("{}", e);
+    }
+    if !grammar_errors.is_empty() {
+        std::process::exit(1);
+    }
+    // Include patterns replace those of the config file, exclude patterns add up to them
+    let include = if options.include.is_empty() {
+        config_file.include
+    } else {
+        options.include
+    };
+    let exclude = [config_file.exclude, options.exclude].concat();
+    xstats.config.path_filter = exit_on_error(PathFilter::from_patterns(&include, &exclude));
+    if let Some(symlinks) = options.symlinks.or(config_file.symlinks) {
+        xstats.config.traversal.symlinks = exit_on_error(SymlinkPolicy::from_name(&symlinks));
+    }
+    xstats.config.traversal.max_depth = options.max_depth.or(config_file.max_depth);
+    xstats.config.thresholds = config_file.thresholds;
+    if let Some(fail_on) = options.fail_on.or(config_file.fail_on) {
+        exit_on_error(xstats.config.set_fail_on(&fail_on));
+    }
+    xstats.config.budgets = config_file.budgets;
+    xstats.config.score_weights = config_file.score_weights;
+    xstats.config.file_timeout = options
+        .file_timeout
+        .or(config_file.file_timeout)
+        .map(|seconds| exit_on_error(get_timeout("--file-timeout", seconds)));
+    if let Some(seconds) = options.total_timeout.or(config_file.total_timeout) {
+        xstats.set_total_timeout(exit_on_error(get_timeout("--total-timeout", seconds)));
+    }
+    xstats.set_trees_budget(
+        options.max_trees.or(config_file.max_trees),
+        options
+            .max_trees_memory
+            .or(config_file.max_trees_memory)
+            .map(|megabytes| megabytes * 1024 * 1024),
+    );
+
+    xstats.metrics_map.filter = MetricsFilter {
+        node_types: if options.only.is_empty() {
+            config_file.only
+        } else {
+            options.only
+        },
+        min_aloc: options.min_aloc.or(config_file.min_aloc).unwrap_or(0),
+        exclude_boilerplate: options.exclude_boilerplate || config_file.exclude_boilerplate,
+        metrics: config_file.metrics,
+    };
+
+    // Same for the commit message patterns
+    let only_commit_messages = if options.only_commit_messages.is_empty() {
+        config_file.only_commit_messages
+    } else {
+        options.only_commit_messages
+    };
+    let skip_commit_messages = [
+        config_file.skip_commit_messages,
+        options.skip_commit_messages,
+    ]
+    .concat();
+    let commit_authors = if options.author_patterns.is_empty() {
+        config_file.commit_authors
+    } else {
+        options.author_patterns
+    };
+    let commit_paths = if options.path_patterns.is_empty() {
+        config_file.commit_paths
+    } else {
+        options.path_patterns
+    };
+    xstats.revwalk_config = RevwalkConfig {
+        since: options.since.or(config_file.since),
+        until: options.until.or(config_file.until),
+        max_commits: options.max_commits.or(config_file.max_commits),
+        commit_filter: exit_on_error(CommitFilter::from_patterns(
+            &skip_commit_messages,
+            &only_commit_messages,
+        )),
+        commit_scope: exit_on_error(CommitScope::new(
+            &commit_authors,
+            &commit_paths,
+            options.after.or(config_file.after).as_deref(),
+            options.before.or(config_file.before).as_deref(),
+        )),
+        skip_merges: options.skip_merges || config_file.skip_merges,
+        first_parent: options.first_parent || config_file.first_parent,
+    };
+    if let Some(commit_jobs) = options.commit_jobs.or(config_file.commit_jobs) {
+        xstats.commit_jobs = commit_jobs;
+    }
+
+    let format = options
+        .format
+        .or(config_file.format)
+        .unwrap_or_else(|| "json".to_string());
+    let mut all_commits = options.all_commits || config_file.all_commits;
+    let resume = all_commits && options.resume;
+    // The outputs of the interrupted run are saved again
+    if resume {
+        xstats.force = true;
+    }
+    // Fail on an unsupported format before the analysis rather than after it
+    let mut sink = exit_on_error(xstats.get_sink(&format));
+    let half_life = options.half_life.or(config_file.half_life).unwrap_or(180.0);
+    let active_code = options.active_code || config_file.active_code;
+    let min_similarity = exit_on_error(get_similarity(
+        options
+            .min_similarity
+            .or(config_file.min_similarity)
+            .unwrap_or(0.8),
+    ));
+
+    if options.provenance || config_file.provenance {
+        xstats.provenance = Some(core::Provenance::new());
+    }
+    let stream = all_commits && (options.stream || config_file.stream || resume);
+    if to_stdout && (stream || options.sarif || config_file.sarif) {
+        error!("--stream, --resume and --sarif save files, and cannot write to stdout");
+        std::process::exit(1);
+    }
+    if resume {
+        let completed_count = exit_on_error(xstats.resume_stream(&format));
+        info!(
+            "Resuming after {} commits already analyzed",
+            completed_count
+        );
+    } else if stream {
+        exit_on_error(xstats.start_stream(&format, false));
+    }
+    if all_commits {
+        match xstats.run_multi_commit() {
+            Ok(()) => {}
+            // Targets without history are still analyzed as they are
+            Err(
+                e @ XStatsError::Repository(
+                    RepositoryError::NotARepository(_) | RepositoryError::NoCommits(_),
+                ),
+            ) => {
+                warn!("{}. Falling back to default mode", e);
+                all_commits = false;
+            }
+            Err(e) => {
+                error!("Failed to analyze commits: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    exit_on_error(xstats.save_run_manifest(sink.as_mut()));
+    if all_commits {
+        // The results of each commit are already streamed
+        if !stream {
+            exit_on_error(xstats.save_metrics_map(sink.as_mut()));
+            exit_on_error(xstats.save_changes_map(sink.as_mut()));
+            exit_on_error(xstats.save_churn_metrics(sink.as_mut()));
+            exit_on_error(xstats.save_commits(sink.as_mut()));
+            if xstats.track_entities {
+                exit_on_error(xstats.save_entity_history(sink.as_mut()));
+            }
+        }
+        exit_on_error(xstats.save_hotspots(sink.as_mut()));
+        exit_on_error(xstats.save_budgets(sink.as_mut()));
+        if options.authors || config_file.authors {
+            exit_on_error(xstats.save_authors(sink.as_mut()));
+        }
+    } else {
+        exit_on_error(xstats.run_default());
+        exit_on_error(xstats.save_metrics(sink.as_mut()));
+        exit_on_error(xstats.save_summary(sink.as_mut()));
+        exit_on_error(xstats.save_scores(sink.as_mut()));
+        exit_on_error(xstats.save_packages(sink.as_mut()));
+        exit_on_error(xstats.save_near_duplicates(sink.as_mut(), min_similarity));
+        exit_on_error(xstats.save_suggestions(sink.as_mut()));
+        exit_on_error(xstats.save_robustness(sink.as_mut()));
+        if options.naming || config_file.naming {
+            exit_on_error(xstats.save_naming(sink.as_mut()));
+        }
+        if options.todos || config_file.todos {
+            exit_on_error(xstats.save_todos(sink.as_mut()));
+        }
+        exit_on_error(xstats.save_tdg(sink.as_mut()));
+        exit_on_error(xstats.save_cycles(sink.as_mut()));
+        exit_on_error(xstats.save_call_graph(sink.as_mut()));
+
+        if let Some(baseline_ref) = options.baseline_ref.or(config_file.baseline_ref) {
+            exit_on_error(xstats.run_baseline(&baseline_ref));
+            exit_on_error(xstats.save_baseline_diff(sink.as_mut()));
+            exit_on_error(xstats.save_budgets(sink.as_mut()));
+        }
+
+        let cycles = xstats.find_cycles();
+        if !cycles.is_empty() {
+            warn!("{} dependency cycles found", cycles.len());
+        }
+
+        let violations = xstats.check_thresholds();
+        if !violations.is_empty() {
+            warn!("{} metrics exceed their thresholds", violations.len());
+            for violation in &violations {
+                warn!("  {}", violation);
+            }
+        }
+
+        if options.sarif || config_file.sarif {
+            exit_on_error(xstats.save_sarif());
+        }
+
+        if active_code {
+            let result = xstats
+                .run_activity(half_life)
+                .and_then(|()| xstats.save_activity_metrics(sink.as_mut()));
+            if let Err(e) = result {
+                error!("Failed to compute activity metrics: {}", e);
+            }
+        }
+
+        if options.ownership || config_file.ownership {
+            let result = xstats
+                .run_ownership()
+                .and_then(|()| xstats.save_ownership(sink.as_mut()));
+            if let Err(e) = result {
+                error!("Failed to compute ownership: {}", e);
+            }
+        }
+    }
+
+    exit_on_error(sink.finish());
+
+    if let Some(state_path) = &options.export_state {
+        exit_on_error(xstats.export_state(state_path));
+    }
+
+    if let Some(summary) = xstats.metrics_map.get_summary() {
+        print_result(to_stdout, &summary.to_string());
     }
+
+    // The warnings were logged as they happened
+    print_warnings(logger);
+
+    // The outputs are saved first, for the report of a failed gate to be at hand
+    let gate_violations = xstats.check_quality_gate();
+    if !gate_violations.is_empty() {
+        print_result(
+            to_stdout,
+            &format!(
+                "Quality gate failed: {} metrics exceed their --fail-on thresholds",
+                gate_violations.len()
+            ),
+        );
+        for violation in &gate_violations {
+            print_result(to_stdout, &format!("  {}", violation));
+        }
+        std::process::exit(1);
+    }
+}
+
+/// Print a line of the results of the run, to stderr when the metrics are written to stdout
+fn print_result(to_stdout: bool, line: &str) {
+    if to_stdout {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+/// Save the outputs of the analysis exported at `state_path`, those of the history mode if it
+/// was run in it
+fn run_import_state(
+    xstats: &mut core::XStats,
+    state_path: &str,
+    format: &str,
+    min_similarity: f64,
+) {
+    let mut sink = exit_on_error(xstats.get_sink(format));
+    exit_on_error(xstats.import_state(state_path));
+    if xstats.metrics_map.get_default_metrics().is_none() {
+        exit_on_error(xstats.save_metrics_map(sink.as_mut()));
+        exit_on_error(xstats.save_changes_map(sink.as_mut()));
+        exit_on_error(xstats.save_churn_metrics(sink.as_mut()));
+        exit_on_error(xstats.save_commits(sink.as_mut()));
+        exit_on_error(xstats.save_hotspots(sink.as_mut()));
+    } else {
+        exit_on_error(xstats.save_metrics(sink.as_mut()));
+        exit_on_error(xstats.save_summary(sink.as_mut()));
+        exit_on_error(xstats.save_scores(sink.as_mut()));
+        exit_on_error(xstats.save_packages(sink.as_mut()));
+        exit_on_error(xstats.save_near_duplicates(sink.as_mut(), min_similarity));
+        exit_on_error(xstats.save_suggestions(sink.as_mut()));
+        exit_on_error(xstats.save_robustness(sink.as_mut()));
+        exit_on_error(xstats.save_tdg(sink.as_mut()));
+        exit_on_error(xstats.save_cycles(sink.as_mut()));
+        exit_on_error(xstats.save_call_graph(sink.as_mut()));
+        exit_on_error(xstats.save_baseline_diff(sink.as_mut()));
+    }
+    exit_on_error(sink.finish());
+}
+
+/// Summarize the repositories of the manifest, and save their comparison in the output path
+fn run_portfolio(
+    manifest_path: &str,
+    output: &str,
+    format: &str,
+    csv_dialect: CsvDialect,
+    force: bool,
+) {
+    let manifest = exit_on_error(PortfolioManifest::load(manifest_path));
+    let mut sink = exit_on_error(create_sink(output, format, csv_dialect, force));
+    let mut progress = Progress::new();
+    show_progress(&mut progress);
+    let portfolio = Portfolio::with_progress(&manifest, &progress);
+    exit_on_error(portfolio.save(sink.as_mut()));
+    exit_on_error(sink.finish());
+
+    for warning in &portfolio.warnings {
+        warn!("{}", warning);
+    }
+}
+
+/// What the `diff` command compares
+enum DiffInputs {
+    /// The target as of a revision, and as it is
+    Revision(String),
+    /// The metrics.json of two runs, before and after
+    Files(String, String),
+    /// The target as of two revisions, before and after
+    Revisions(String, String),
+}
+
+/// Save how the entities changed between the inputs, and print those which regressed, to stderr
+/// when the changes are written to stdout
+fn run_diff(xstats: &mut core::XStats, inputs: DiffInputs, format: &str, to_stdout: bool) {
+    let mut sink = exit_on_error(xstats.get_sink(format));
+    let (old_key, new_key, metrics) = match inputs {
+        DiffInputs::Revision(revision) => {
+            exit_on_error(xstats.run_default());
+            exit_on_error(xstats.run_baseline(&revision));
+            let old_key = xstats.baseline_id.clone().unwrap_or_default();
+            (old_key, "default".to_string(), CodeMetric::get_names())
+        }
+        DiffInputs::Revisions(from, to) => {
+            let old_key = exit_on_error(xstats.run_revision(&from));
+            let new_key = exit_on_error(xstats.run_revision(&to));
+            (old_key, new_key, CodeMetric::get_names())
+        }
+        DiffInputs::Files(baseline, current) => {
+            let old_metrics = exit_on_error(xstats.load_metrics("baseline", &baseline));
+            let new_metrics = exit_on_error(xstats.load_metrics("current", &current));
+            // Only the metrics of both files are compared
+            let metrics = old_metrics
+                .into_iter()
+                .filter(|name| new_metrics.contains(name))
+                .collect();
+            ("baseline".to_string(), "current".to_string(), metrics)
+        }
+    };
+    exit_on_error(xstats.save_diff(sink.as_mut(), &old_key, &new_key, &metrics));
+    exit_on_error(sink.finish());
+
+    let Some(diffs) = xstats.metrics_map.compare(&old_key, &new_key) else {
+        return;
+    };
+    let count = |status: DiffStatus| diffs.diffs.iter().filter(|d| d.status == status).count();
+    let regressed: Vec<(&CodeMetricBlock, Vec<String>)> = diffs
+        .diffs
+        .iter()
+        .filter_map(|diff| {
+            let regressions = diff.get_regressions(&metrics);
+            let block = diff.new.as_ref()?;
+            (!regressions.is_empty()).then_some((block, regressions))
+        })
+        .collect();
+    print_result(
+        to_stdout,
+        &format!(
+            "{} entities added, {} removed, {} regressed",
+            count(DiffStatus::Added),
+            count(DiffStatus::Removed),
+            regressed.len()
+        ),
+    );
+    for (block, regressions) in &regressed {
+        print_result(
+            to_stdout,
+            &format!(
+                "  {}:{} {} ({}): {}",
+                block.meta_data.file_path,
+                block.meta_data.start_row,
+                block.meta_data.node_name,
+                block.meta_data.node_type,
+                regressions.join(", ")
+            ),
+        );
+    }
+}
+
+/// Convert a timeout from seconds
+fn get_timeout(option: &str, seconds: f64) -> Result<Duration, XStatsError> {
+    Duration::try_from_secs_f64(seconds)
+        .ok()
+        .filter(|timeout| !timeout.is_zero())
+        .ok_or_else(|| {
+            XStatsError::Config(format!("{} must be a positive number of seconds", option))
+        })
+}
+
+fn get_similarity(similarity: f64) -> Result<f64, XStatsError> {
+    if similarity > 0.0 && similarity <= 1.0 {
+        Ok(similarity)
+    } else {
+        Err(XStatsError::Config(
+            "--min-similarity must be between 0 (excluded) and 1".to_string(),
+        ))
+    }
+}
+
+/// Where the progress bars are drawn, unless `--quiet`, for the logs to be written above them
+static MULTI_PROGRESS: OnceLock<MultiProgress> = OnceLock::new();
+
+/// Log to stderr, up to the level of `--quiet` or `-v`, and to the `--log-file` if any
+fn init_logger(options: &Options) -> Result<&'static Logger, XStatsError> {
+    let level = match (options.quiet, options.verbose) {
+        (true, _) => LevelFilter::Error,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    let mut logger = Logger::new(level);
+    if let Some(log_file) = &options.log_file {
+        logger.set_log_file(log_file)?;
+    }
+    if !options.quiet {
+        logger.set_multi_progress(MULTI_PROGRESS.get_or_init(MultiProgress::new).clone());
+    }
+    logger.init()
+}
+
+/// Render the progress of the analysis as progress bars, unless `--quiet`
+fn show_progress(progress: &mut Progress) {
+    if let Some(multi_progress) = MULTI_PROGRESS.get() {
+        let progress_bar = CustomProgressBar::from_multi_progress(multi_progress.clone());
+        progress.add_listener(move |event| progress_bar.handle(event));
+    }
+}
+
+/// Print the number of warnings of each kind logged during the run
+fn print_warnings(logger: &Logger) {
+    if let Some(summary) = logger.get_summary() {
+        info!("{}", summary);
+    }
+}
+
+/// Log the error and exit, if the result is one
+fn exit_on_error<T>(result: Result<T, XStatsError>) -> T {
+    result.unwrap_or_else(|e| {
+        error!("{}", e);
+        std::process::exit(1);
+    })
 }
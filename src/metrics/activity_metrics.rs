@@ -0,0 +1,126 @@
+use crate::metrics::code_metrics::{CodeMetaData, CodeMetric, CodeMetricBlock};
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+pub struct ActivityBlock {
+    pub meta_data: CodeMetaData,
+    pub metric: CodeMetric,
+    /// Whether the node is a file (or sub-document) rather than a class or method.
+    pub is_file_level: bool,
+    /// The mean recency weight of the lines of the node, between 0 (dormant) and 1 (active).
+    pub activity: f64,
+    /// The time (in seconds since the epoch) the most recently modified line of the node was
+    /// committed, if any of its lines is committed.
+    pub last_modified: Option<i64>,
+}
+
+impl ActivityBlock {
+    /// The actual lines of code of the node, weighted by activity
+    pub fn active_aloc(&self) -> f64 {
+        self.metric.aloc as f64 * self.activity
+    }
+
+    /// The cyclomatic complexity of the node, weighted by activity
+    pub fn active_cc(&self) -> f64 {
+        self.metric.cc as f64 * self.activity
+    }
+}
+
+/// Metrics weighted by how recently their lines were last modified.
+///
+/// The weight of a line halves every `half_life_days` days between the time it was last
+/// committed and `reference_time`. Lines that are not committed yet have a weight of 1.
+pub struct ActivityMetrics {
+    pub reference_time: i64,
+    pub half_life_days: f64,
+    pub activity_blocks: Vec<ActivityBlock>,
+}
+
+impl ActivityMetrics {
+    pub fn new(reference_time: i64, half_life_days: f64) -> ActivityMetrics {
+        ActivityMetrics {
+            reference_time,
+            half_life_days,
+            activity_blocks: Vec::new(),
+        }
+    }
+
+    fn get_line_weight(&self, line_time: Option<i64>) -> f64 {
+        match line_time {
+            Some(time) => {
+                let age_days = (self.reference_time - time).max(0) as f64 / SECONDS_PER_DAY;
+                0.5_f64.powf(age_days / self.half_life_days)
+            }
+            None => 1.0,
+        }
+    }
+
+    /// Weight the metric blocks of a file by the last modification time of each of its lines
+    pub fn add_file(&mut self, metric_blocks: &[CodeMetricBlock], line_times: &[Option<i64>]) {
+        for (idx, block) in metric_blocks.iter().enumerate() {
+            let (start_row, end_row) = (block.meta_data.start_row, block.meta_data.end_row);
+            let start = (start_row as usize).saturating_sub(1).min(line_times.len());
+            let end = (end_row as usize).clamp(start, line_times.len());
+            let block_times = &line_times[start..end];
+
+            let activity = if block_times.is_empty() {
+                0.0
+            } else {
+                let total_weight: f64 = block_times
+                    .iter()
+                    .map(|time| self.get_line_weight(*time))
+                    .sum();
+                total_weight / block_times.len() as f64
+            };
+
+            // File-level blocks come before the blocks nested in them
+            let is_file_level = !metric_blocks[..idx].iter().any(|other| {
+                other.meta_data.start_row <= start_row && other.meta_data.end_row >= end_row
+            });
+
+            self.activity_blocks.push(ActivityBlock {
+                meta_data: block.meta_data.clone(),
+                metric: block.metric.clone(),
+                is_file_level,
+                activity,
+                last_modified: block_times.iter().flatten().max().copied(),
+            });
+        }
+    }
+
+    /// Share of the actual lines of code and of the cyclomatic complexity of all the files
+    /// that is active
+    pub fn get_active_shares(&self) -> (f64, f64) {
+        let (mut aloc, mut cc, mut active_aloc, mut active_cc) = (0.0, 0.0, 0.0, 0.0);
+        for block in self.activity_blocks.iter().filter(|b| b.is_file_level) {
+            aloc += block.metric.aloc as f64;
+            cc += block.metric.cc as f64;
+            active_aloc += block.active_aloc();
+            active_cc += block.active_cc();
+        }
+        (active_aloc / aloc.max(1.0), active_cc / cc.max(1.0))
+    }
+
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = Vec::new();
+        // Add header row
+        let mut header = CodeMetaData::get_names();
+        header.extend(
+            ["activity", "last_modified", "active_aloc", "active_cc"]
+                .iter()
+                .map(|s| s.to_string()),
+        );
+        table.push(header);
+
+        for block in &self.activity_blocks {
+            let mut row = block.meta_data.get_values();
+            row.push(format!("{:.4}", block.activity));
+            row.push(block.last_modified.map_or(String::new(), |t| t.to_string()));
+            row.push(format!("{:.2}", block.active_aloc()));
+            row.push(format!("{:.2}", block.active_cc()));
+            table.push(row);
+        }
+
+        table
+    }
+}
@@ -0,0 +1,230 @@
+use crate::metrics::code_metrics::{CodeMetricBlock, CodeMetrics, EntityKind};
+use crate::utils::format_utc_time;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Result};
+use std::path::Path;
+
+/// The state of the code a budget is checked in: a commit of the history, the baseline or the
+/// working tree
+pub struct BudgetSnapshot<'a> {
+    /// The commit id, or `working_tree` for the target as it is
+    pub label: String,
+    /// When the commit was made, in seconds since the epoch, if known
+    pub committed_at: Option<i64>,
+    pub metrics: &'a CodeMetrics,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BudgetStatus {
+    /// The entity never exceeded its budget
+    Within,
+    /// The entity exceeds its budget in the last snapshot it is in
+    Exceeded,
+    /// The entity exceeded its budget, then went back within it
+    Recovered,
+    /// No entity of the snapshots matches the budget
+    NotFound,
+}
+
+impl Display for BudgetStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let name = match self {
+            BudgetStatus::Within => "within",
+            BudgetStatus::Exceeded => "exceeded",
+            BudgetStatus::Recovered => "recovered",
+            BudgetStatus::NotFound => "not_found",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A budget of an entity, with the snapshot which first exceeded it
+pub struct BudgetCheck {
+    pub entity: String,
+    pub metric: String,
+    pub budget: f64,
+    pub status: BudgetStatus,
+    /// The label of the first snapshot exceeding the budget
+    pub exceeded_by: Option<String>,
+    pub exceeded_at: Option<i64>,
+    /// The value of the metric in the first snapshot exceeding the budget
+    pub exceeded_value: Option<f64>,
+    /// The value of the metric in the last snapshot the entity is in
+    pub latest_value: Option<f64>,
+}
+
+/// The complexity budgets of the configured entities, checked over snapshots in order, to tell
+/// which commit is accountable for each regression.
+pub struct BudgetReport {
+    pub checks: Vec<BudgetCheck>,
+}
+
+impl BudgetReport {
+    /// Check the budgets, the maximum value of metrics by entity name, over the snapshots from
+    /// the oldest.
+    ///
+    /// An entity is named by its file path, e.g. `src/app.py`, or by the dotted names of its
+    /// package or module, enclosing classes and itself, e.g. `com.foo.Bar` for the class `Bar` of
+    /// `com/foo/Bar.java` or `app.models.Cart.total` for a method of `app/models.py`. The leading
+    /// parts of the path may be left out. When several entities match, the highest value counts.
+    pub fn new(
+        budgets: &BTreeMap<String, BTreeMap<String, f64>>,
+        snapshots: &[BudgetSnapshot],
+    ) -> Self {
+        let named_snapshots: Vec<_> = snapshots
+            .iter()
+            .map(|snapshot| (snapshot, get_entity_names(snapshot.metrics)))
+            .collect();
+
+        let mut checks = Vec::new();
+        for (entity, metric_budgets) in budgets {
+            for (metric, budget) in metric_budgets {
+                let mut check = BudgetCheck {
+                    entity: entity.clone(),
+                    metric: metric.clone(),
+                    budget: *budget,
+                    status: BudgetStatus::NotFound,
+                    exceeded_by: None,
+                    exceeded_at: None,
+                    exceeded_value: None,
+                    latest_value: None,
+                };
+                for (snapshot, names) in &named_snapshots {
+                    let value = names
+                        .iter()
+                        .filter(|(block, name)| matches_entity(entity, block, name))
+                        .filter_map(|(block, _)| block.metric.get_value(metric))
+                        .reduce(f64::max);
+                    let Some(value) = value else {
+                        continue;
+                    };
+                    if value > *budget && check.exceeded_by.is_none() {
+                        check.exceeded_by = Some(snapshot.label.clone());
+                        check.exceeded_at = snapshot.committed_at;
+                        check.exceeded_value = Some(value);
+                    }
+                    check.latest_value = Some(value);
+                }
+
+                check.status = match (check.latest_value, check.exceeded_by.is_some()) {
+                    (None, _) => BudgetStatus::NotFound,
+                    (Some(_), false) => BudgetStatus::Within,
+                    (Some(value), true) if value > *budget => BudgetStatus::Exceeded,
+                    (Some(_), true) => BudgetStatus::Recovered,
+                };
+                checks.push(check);
+            }
+        }
+        BudgetReport { checks }
+    }
+
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![[
+            "entity",
+            "metric",
+            "budget",
+            "status",
+            "exceeded_by",
+            "exceeded_at",
+            "exceeded_value",
+            "latest_value",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>()];
+
+        let format_value = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+        for check in &self.checks {
+            table.push(vec![
+                check.entity.clone(),
+                check.metric.clone(),
+                check.budget.to_string(),
+                check.status.to_string(),
+                check.exceeded_by.clone().unwrap_or_default(),
+                check.exceeded_at.map(format_utc_time).unwrap_or_default(),
+                format_value(check.exceeded_value),
+                format_value(check.latest_value),
+            ]);
+        }
+
+        table
+    }
+}
+
+/// Name the classes and methods of every file by the names of the classes and methods
+/// enclosing them and their own, e.g. `Bar.render`, files being left unnamed
+fn get_entity_names(metrics: &CodeMetrics) -> Vec<(&CodeMetricBlock, Option<String>)> {
+    let mut names = Vec::new();
+    // Metric blocks of the same file are contiguous
+    for file_blocks in metrics
+        .metric_blocks
+        .chunk_by(|a, b| a.meta_data.file_path == b.meta_data.file_path)
+    {
+        for block in file_blocks {
+            if block.meta_data.node_kind == EntityKind::File {
+                names.push((block, None));
+                continue;
+            }
+            let mut enclosing: Vec<&CodeMetricBlock> = file_blocks
+                .iter()
+                .filter(|other| {
+                    other.meta_data.node_kind != EntityKind::File
+                        && other.meta_data.start_row <= block.meta_data.start_row
+                        && other.meta_data.end_row >= block.meta_data.end_row
+                })
+                .collect();
+            enclosing.sort_by_key(|other| {
+                (
+                    other.meta_data.start_row,
+                    std::cmp::Reverse(other.meta_data.end_row),
+                )
+            });
+            let name = enclosing
+                .iter()
+                .map(|other| other.meta_data.node_name.as_str())
+                .collect::<Vec<&str>>()
+                .join(".");
+            names.push((block, Some(name)));
+        }
+    }
+    names
+}
+
+/// Check if a budget names an entity, see `BudgetReport::new`
+fn matches_entity(entity: &str, block: &CodeMetricBlock, name: &Option<String>) -> bool {
+    let file_path = block.meta_data.file_path.replace('\\', "/");
+    let Some(name) = name else {
+        return file_path == entity || file_path.ends_with(&format!("/{}", entity));
+    };
+
+    let Some(prefix) = entity.strip_suffix(name.as_str()) else {
+        return false;
+    };
+    if prefix.is_empty() {
+        return true;
+    }
+    let Some(prefix) = prefix.strip_suffix('.') else {
+        return false;
+    };
+
+    // The package is the directory of the file, e.g. in Java, the module its path, e.g. in Python
+    let path = Path::new(&file_path);
+    let mut dirs: Vec<String> = path
+        .parent()
+        .map(|parent| {
+            parent
+                .iter()
+                .map(|part| part.to_string_lossy().to_string())
+                .filter(|part| part != "." && part != "/")
+                .collect()
+        })
+        .unwrap_or_default();
+    let prefix_parts: Vec<String> = prefix.split('.').map(|part| part.to_string()).collect();
+    if dirs.ends_with(&prefix_parts) {
+        return true;
+    }
+    if let Some(stem) = path.file_stem() {
+        dirs.push(stem.to_string_lossy().to_string());
+    }
+    dirs.ends_with(&prefix_parts)
+}
@@ -0,0 +1,110 @@
+use crate::metrics::code_metrics::CodeMetricBlock;
+use crate::utils::get_file_name;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeType {
+    Added,
+    Modified,
+    Deleted,
+    /// Moved to another path, possibly modified along
+    Renamed,
+}
+
+impl ChangeType {
+    /// Label used for the progress bar messages
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChangeType::Added => "ADDED",
+            ChangeType::Modified => "MODIFIED",
+            ChangeType::Deleted => "DELETED",
+            ChangeType::Renamed => "RENAMED",
+        }
+    }
+}
+
+impl Display for ChangeType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.label().to_lowercase())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CodeChange {
+    /// The file path of the changed entity.
+    pub file_path: String,
+    /// The name of the changed entity (e.g., file, class or method name).
+    pub node_name: String,
+    /// The type of the changed entity (e.g., module, class, method).
+    pub node_type: String,
+    /// The starting line number of the entity after the change.
+    pub start_row: u32,
+    /// The ending line number of the entity after the change.
+    pub end_row: u32,
+    /// Whether the entity was added, modified or deleted.
+    pub change_type: ChangeType,
+    /// The number of changed lines falling inside the entity.
+    pub changed_lines: u32,
+}
+
+pub struct CodeChanges {
+    pub changes: Vec<CodeChange>,
+}
+
+impl Default for CodeChanges {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CodeChanges {
+    pub fn new() -> CodeChanges {
+        CodeChanges {
+            changes: Vec::new(),
+        }
+    }
+
+    /// Record every entity of a file that overlaps with at least one of the changed line ranges
+    pub fn map_changed_lines(
+        &mut self,
+        metric_blocks: &[CodeMetricBlock],
+        changed_lines: &[(u32, u32)],
+        change_type: ChangeType,
+    ) {
+        for block in metric_blocks {
+            let (start_row, end_row) = (block.meta_data.start_row, block.meta_data.end_row);
+
+            let overlap: u32 = changed_lines
+                .iter()
+                .filter(|(start, end)| *start <= end_row && *end >= start_row)
+                .map(|(start, end)| end.min(&end_row) - start.max(&start_row) + 1)
+                .sum();
+
+            if overlap > 0 {
+                self.changes.push(CodeChange {
+                    file_path: block.meta_data.file_path.clone(),
+                    node_name: block.meta_data.node_name.clone(),
+                    node_type: block.meta_data.node_type.clone(),
+                    start_row,
+                    end_row,
+                    change_type,
+                    changed_lines: overlap,
+                });
+            }
+        }
+    }
+
+    /// Record a deleted file, its entities are no longer available in the new tree
+    pub fn add_deleted_file(&mut self, file_path: &str) {
+        self.changes.push(CodeChange {
+            file_path: file_path.to_string(),
+            node_name: get_file_name(file_path),
+            node_type: String::new(),
+            start_row: 0,
+            end_row: 0,
+            change_type: ChangeType::Deleted,
+            changed_lines: 0,
+        });
+    }
+}
@@ -0,0 +1,70 @@
+use crate::metrics::change_metrics::ChangeType;
+use serde::{Deserialize, Serialize};
+
+/// The lines changed in a file by a commit
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FileChurn {
+    /// The id of the commit changing the file.
+    pub commit_id: String,
+    pub file_path: String,
+    pub change_type: ChangeType,
+    pub lines_added: u32,
+    pub lines_deleted: u32,
+    /// The number of hunks of the diff, i.e. of separate places changed in the file.
+    pub hunks_changed: u32,
+    /// The path of the file before the commit, if the commit renamed it.
+    #[serde(default)]
+    pub old_file_path: Option<String>,
+}
+
+/// How much each file changed in each commit, to find the files that change often.
+///
+/// Combined with the complexity of the files, it points to the hotspots of a project: complex
+/// code that keeps changing.
+pub struct ChurnMetrics {
+    pub file_churns: Vec<FileChurn>,
+}
+
+impl Default for ChurnMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChurnMetrics {
+    pub fn new() -> ChurnMetrics {
+        ChurnMetrics {
+            file_churns: Vec::new(),
+        }
+    }
+
+    pub fn add_file_churn(&mut self, file_churn: FileChurn) {
+        self.file_churns.push(file_churn);
+    }
+
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![vec![
+            "commit_id".to_string(),
+            "file_path".to_string(),
+            "change_type".to_string(),
+            "lines_added".to_string(),
+            "lines_deleted".to_string(),
+            "hunks_changed".to_string(),
+            "old_file_path".to_string(),
+        ]];
+
+        for file_churn in &self.file_churns {
+            table.push(vec![
+                file_churn.commit_id.clone(),
+                file_churn.file_path.clone(),
+                file_churn.change_type.to_string(),
+                file_churn.lines_added.to_string(),
+                file_churn.lines_deleted.to_string(),
+                file_churn.hunks_changed.to_string(),
+                file_churn.old_file_path.clone().unwrap_or_default(),
+            ]);
+        }
+
+        table
+    }
+}
@@ -1,8 +1,23 @@
-use crate::config::Language;
+use super::extraction_metrics::ExtractCandidate;
+use super::halstead_metrics::HalsteadMetrics;
+use super::marker_metrics::MarkerComment;
+use super::naming_metrics::NamingStats;
+use super::robustness_metrics::RiskyConstruct;
+use crate::config::{AnalysisConfig, Language};
+use crate::error::XStatsError;
+use crate::languages::get_profile;
 use crate::ts::{Node, TSParsers, Tree};
-use crate::utils::get_file_name;
+use crate::utils::{fnv1a_hash, get_file_name};
 use crate::visitor::TreeVisitor;
+use log::error;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::path::Path;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CodeMetaData {
     /// The programming language of the source file.
     pub language: Language,
@@ -10,8 +25,18 @@ pub struct CodeMetaData {
     pub file_path: String,
     /// The name of the node (e.g., method or function name).
     pub node_name: String,
+    /// The name of the node qualified by the package or module and the entities enclosing it
+    /// (e.g., `com.shop.Cart.add`).
+    #[serde(default)]
+    pub qualified_name: String,
+    /// The hash of the language, qualified name and type of the node, identifying it across
+    /// commits whatever its position.
+    #[serde(default)]
+    pub entity_id: String,
     /// The type of the node (e.g., function, method, class).
     pub node_type: String,
+    /// Whether the node is a file, a class or a method, whatever its type in the language.
+    pub node_kind: EntityKind,
     /// The starting line number of the node in the source file.
     pub start_row: u32,
     /// The starting column number of the node in the source file.
@@ -20,67 +45,307 @@ pub struct CodeMetaData {
     pub end_row: u32,
     /// The ending column number of the node in the source file.
     pub end_col: u32,
+    /// The metrics suppressed by an `xstats:ignore` comment above the node, `all` for all of
+    /// them.
+    pub suppressed: Vec<String>,
 }
 
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct CodeMetric {
     /// Indicates whether the node is broken or has missing elements (e.g., syntax error).
     pub is_broken: bool,
+    /// Indicates whether the node is a boilerplate method (e.g., trivial getter or setter).
+    pub is_boilerplate: bool,
     /// The number of actual lines of code in the node.
     pub aloc: u32,
+    /// The number of source lines of code in the node, neither empty nor only comments.
+    pub sloc: u32,
     /// The number of empty lines of code in the node.
     pub eloc: u32,
     /// The number of comment lines of code in the node.
     pub cloc: u32,
     /// The number of document comment lines of code in the node.
     pub dcloc: u32,
+    /// The number of comment lines flagging unfinished work, e.g. `TODO`.
+    pub todos: u32,
     /// Number of imports in the node.
     pub noi: u32,
     /// Number of classes in the node.
     pub noc: u32,
     /// Number of methods in the node.
     pub nom: u32,
+    /// The actual lines of code of the longest method of a class.
+    pub max_method_aloc: u32,
+    /// The average actual lines of code of the methods of a class.
+    pub avg_method_aloc: f64,
+    /// The cyclomatic complexity of the most complex method of a class.
+    pub max_method_cc: u32,
     /// The cyclomatic complexity of the node.
     pub cc: u32,
+    /// The number of return, throw and raise statements of a method.
+    pub noret: u32,
     /// The number of parameters the node takes.
     pub pc: u32,
+    /// The number of parameters with a default value.
+    pub dpc: u32,
+    /// Indicates whether the node takes a variable number of arguments (e.g., `*args`).
+    pub is_variadic: bool,
+    /// The maximum nesting depth of the control-flow blocks in the node.
+    pub nd: u32,
+    /// The lack of cohesion of the methods of a class (LCOM4), in groups of unrelated methods.
+    pub lcom: u32,
+    /// The number of classes of the project a class is coupled to, using them or used by them.
+    pub cbo: u32,
+    /// The number of classes of the project using a class.
+    pub fan_in: u32,
+    /// The number of classes of the project a class uses.
+    pub fan_out: u32,
+    /// The Halstead volume of the node.
+    pub hv: f64,
+    /// The Halstead difficulty of the node.
+    pub hd: f64,
+    /// The Halstead effort of the node.
+    pub he: f64,
+    /// The maintainability index of the node, from 0 (hard to maintain) to 100.
+    pub mi: f64,
 }
 
+impl CodeMetaData {
+    /// Names of the metadata columns, in the order of `get_values`
+    pub fn get_names() -> Vec<String> {
+        [
+            "language",
+            "file_path",
+            "start_row",
+            "start_col",
+            "end_row",
+            "end_col",
+            "node_name",
+            "qualified_name",
+            "entity_id",
+            "node_type",
+            "node_kind",
+            "suppressed",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+    }
+
+    pub fn get_values(&self) -> Vec<String> {
+        vec![
+            self.language.to_string(),
+            self.file_path.clone(),
+            self.start_row.to_string(),
+            self.start_col.to_string(),
+            self.end_row.to_string(),
+            self.end_col.to_string(),
+            self.node_name.clone(),
+            self.qualified_name.clone(),
+            self.entity_id.clone(),
+            self.node_type.clone(),
+            self.node_kind.to_string(),
+            self.suppressed.join(","),
+        ]
+    }
+
+    /// Set the qualified name of the node, and its entity id along
+    pub fn set_qualified_name(&mut self, qualified_name: String) {
+        let key = format!("{}:{}:{}", self.language, qualified_name, self.node_type);
+        self.entity_id = format!("{:016x}", fnv1a_hash(key.as_bytes()));
+        self.qualified_name = qualified_name;
+    }
+
+    /// Check if a metric, e.g. `cc`, is suppressed for the node by an `xstats:ignore` comment
+    pub fn is_suppressed(&self, metric: &str) -> bool {
+        self.suppressed
+            .iter()
+            .any(|suppressed| suppressed == "all" || suppressed == metric)
+    }
+}
+
+impl CodeMetric {
+    /// Names of the metric columns, in the order of `get_values`
+    pub fn get_names() -> Vec<String> {
+        [
+            "is_broken",
+            "is_boilerplate",
+            "aloc",
+            "sloc",
+            "eloc",
+            "cloc",
+            "dcloc",
+            "todos",
+            "noi",
+            "noc",
+            "nom",
+            "max_method_aloc",
+            "avg_method_aloc",
+            "max_method_cc",
+            "cc",
+            "noret",
+            "pc",
+            "dpc",
+            "is_variadic",
+            "nd",
+            "lcom",
+            "cbo",
+            "fan_in",
+            "fan_out",
+            "hv",
+            "hd",
+            "he",
+            "mi",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+    }
+
+    pub fn get_values(&self) -> Vec<String> {
+        vec![
+            self.is_broken.to_string(),
+            self.is_boilerplate.to_string(),
+            self.aloc.to_string(),
+            self.sloc.to_string(),
+            self.eloc.to_string(),
+            self.cloc.to_string(),
+            self.dcloc.to_string(),
+            self.todos.to_string(),
+            self.noi.to_string(),
+            self.noc.to_string(),
+            self.nom.to_string(),
+            self.max_method_aloc.to_string(),
+            format!("{:.2}", self.avg_method_aloc),
+            self.max_method_cc.to_string(),
+            self.cc.to_string(),
+            self.noret.to_string(),
+            self.pc.to_string(),
+            self.dpc.to_string(),
+            self.is_variadic.to_string(),
+            self.nd.to_string(),
+            self.lcom.to_string(),
+            self.cbo.to_string(),
+            self.fan_in.to_string(),
+            self.fan_out.to_string(),
+            format!("{:.2}", self.hv),
+            format!("{:.2}", self.hd),
+            format!("{:.2}", self.he),
+            format!("{:.2}", self.mi),
+        ]
+    }
+
+    /// Get the numeric value of a metric by name, e.g. `cc`
+    pub fn get_value(&self, name: &str) -> Option<f64> {
+        let index = Self::get_names().iter().position(|n| n == name)?;
+        self.get_values()[index].parse().ok()
+    }
+}
+
+/// What a metric block measures, whatever the node type is called in its language
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    File,
+    Class,
+    Method,
+}
+
+impl Display for EntityKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CodeMetricBlock {
     pub meta_data: CodeMetaData,
     pub metric: CodeMetric,
+    /// A hash of the tokens of a method body, equal for exact duplicates
+    pub fingerprint: Option<u64>,
+    /// The MinHash signature of the shingles of a method body, close for near duplicates
+    pub minhash: Option<Vec<u64>>,
+    /// The statements of a long method worth extracting into methods of their own
+    pub extract_candidates: Vec<ExtractCandidate>,
+    /// The constructs of a file hiding failures or crashing on them, e.g. empty catch blocks
+    pub risky_constructs: Vec<RiskyConstruct>,
+    /// The comment lines of a file flagging unfinished work, e.g. `// TODO: handle timeouts`
+    #[serde(default)]
+    pub markers: Vec<MarkerComment>,
+    /// The variables, parameters and constants the entity declares
+    pub naming: NamingStats,
+    /// The package a file declares, e.g. `com.shop` in Java, for files only
+    pub package: Option<String>,
+    /// The number of matches of each custom query valid for the language, by name
+    #[serde(default)]
+    pub custom: BTreeMap<String, u32>,
 }
 
 impl CodeMetricBlock {
     pub fn new(
         language: Language,
-        file_path: &String,
+        file_path: &str,
         node_name: String,
         node_type: String,
+        node_kind: EntityKind,
     ) -> CodeMetricBlock {
-        CodeMetricBlock {
+        let mut metric_block = CodeMetricBlock {
             meta_data: CodeMetaData {
-                language: language,
+                language,
                 file_path: file_path.to_string(),
+                qualified_name: String::new(),
+                entity_id: String::new(),
                 node_name,
                 node_type,
+                node_kind,
                 start_row: 0,
                 start_col: 0,
                 end_row: 0,
                 end_col: 0,
+                suppressed: Vec::new(),
             },
             metric: CodeMetric {
                 is_broken: false,
+                is_boilerplate: false,
                 aloc: 0,
+                sloc: 0,
                 eloc: 0,
                 cloc: 0,
                 dcloc: 0,
+                todos: 0,
                 noi: 0,
                 noc: 0,
                 nom: 0,
+                max_method_aloc: 0,
+                avg_method_aloc: 0.0,
+                max_method_cc: 0,
                 cc: 0,
+                noret: 0,
                 pc: 0,
+                dpc: 0,
+                is_variadic: false,
+                nd: 0,
+                lcom: 0,
+                cbo: 0,
+                fan_in: 0,
+                fan_out: 0,
+                hv: 0.0,
+                hd: 0.0,
+                he: 0.0,
+                mi: 0.0,
             },
-        }
+            fingerprint: None,
+            minhash: None,
+            extract_candidates: Vec::new(),
+            risky_constructs: Vec::new(),
+            markers: Vec::new(),
+            naming: NamingStats::default(),
+            package: None,
+            custom: BTreeMap::new(),
+        };
+        let node_name = metric_block.meta_data.node_name.clone();
+        metric_block.meta_data.set_qualified_name(node_name);
+        metric_block
     }
 
     /// Generate metrics for the node - start and end positions, aloc, and broken status
@@ -100,98 +365,168 @@ impl CodeMetricBlock {
         self.metric.aloc = (end.row - start.row + 1) as u32;
     }
 
-    /// Load the parameter count of the node
-    pub fn load_pc(&mut self, pc: u32) {
-        self.metric.pc = pc;
+    /// Shift the position of the node by the offset of the sub-document it was parsed from
+    pub fn shift_position(&mut self, row_offset: u32, col_offset: u32) {
+        // Only the first row of a sub-document is shifted horizontally
+        if self.meta_data.start_row == 1 {
+            self.meta_data.start_col += col_offset;
+        }
+        if self.meta_data.end_row == 1 {
+            self.meta_data.end_col += col_offset;
+        }
+        self.meta_data.start_row += row_offset;
+        self.meta_data.end_row += row_offset;
+        for candidate in &mut self.extract_candidates {
+            candidate.start_row += row_offset;
+            candidate.end_row += row_offset;
+        }
+        for construct in &mut self.risky_constructs {
+            construct.row += row_offset;
+        }
+        for marker in &mut self.markers {
+            marker.row += row_offset;
+        }
+    }
+
+    /// Count the parameters of the node, those with a default value, and whether it is variadic
+    pub fn calculate_pc(&mut self, visitor: &TreeVisitor, node: &Node) {
+        let default_nodes =
+            get_node_group_or_empty(self.meta_data.language, "default_parameter_nodes");
+        let variadic_nodes =
+            get_node_group_or_empty(self.meta_data.language, "variadic_parameter_nodes");
+
+        let parameters = visitor.get_parameter_nodes(node);
+        self.metric.pc = parameters.len() as u32;
+        self.metric.dpc = parameters
+            .iter()
+            .filter(|parameter| visitor.is_default_parameter(parameter, &default_nodes))
+            .count() as u32;
+        self.metric.is_variadic = parameters
+            .iter()
+            .any(|parameter| visitor.is_variadic_parameter(parameter, &variadic_nodes));
     }
 
     /// Calculate the number of empty lines in the node
     pub fn calculate_eloc(&mut self, visitor: &TreeVisitor, node: &Node) {
-        self.metric.eloc = visitor.count_empty_lines(*node) as u32;
+        let string_nodes = get_node_group(self.meta_data.language, "string_nodes");
+        self.metric.eloc = visitor.count_empty_lines(*node, &string_nodes) as u32;
     }
 
-    pub fn calculate_cloc_dcloc(&mut self, visitor: &TreeVisitor, comment_nodes: &Vec<Node>) {
+    /// Calculate the number of lines of the node holding code, outside of its comments
+    pub fn calculate_sloc(&mut self, visitor: &TreeVisitor, node: &Node, comment_nodes: &[Node]) {
+        let string_nodes = get_node_group(self.meta_data.language, "string_nodes");
+        self.metric.sloc = visitor.count_source_lines(*node, comment_nodes, &string_nodes) as u32;
+    }
+
+    pub fn calculate_cloc_dcloc(&mut self, visitor: &TreeVisitor, comment_nodes: &[Node]) {
         let (cloc, dcloc) = visitor.count_comments(comment_nodes);
         self.metric.cloc = cloc as u32;
         self.metric.dcloc = dcloc as u32;
+        self.metric.todos = visitor.get_marker_comments(comment_nodes).len() as u32;
     }
 
     /// Calculate the number of imports in the node
-    pub fn calculate_noi(&mut self, import_nodes: &Vec<Node>) {
+    pub fn calculate_noi(&mut self, import_nodes: &[Node]) {
         self.metric.noi = import_nodes.len() as u32;
     }
 
     /// Calculate the number of classes in the node
-    pub fn calculate_noc(&mut self, class_nodes: &Vec<Node>) {
+    pub fn calculate_noc(&mut self, class_nodes: &[Node]) {
         self.metric.noc = class_nodes.len() as u32;
     }
 
     /// Calculate the number of methods in the node
-    pub fn calculate_nom(&mut self, method_nodes: &Vec<Node>) {
+    pub fn calculate_nom(&mut self, method_nodes: &[Node]) {
         self.metric.nom = method_nodes.len() as u32;
     }
 
-    fn count_decision_points(
-        &self,
-        node: Node,
-        decision_points: &[String],
-        skip_nodes: &[String],
-    ) -> usize {
-        let mut count = 0;
-
-        let node_kind = node.kind().to_string();
-
-        // // Check if the child node is a decision point
-        // if decision_points.contains(&node_kind) {
-        //     println!("Found decision point: {}", node_kind);
-        //     count += 1;
-        // }
-
-        // // Traverse child nodes to count decision points
-        // if !skip_nodes.contains(&node_kind) {
-        //     for i in 0..node.child_count() {
-        //         if let Some(child) = node.child(i) {
-        //             count += self.count_decision_points(child, decision_points, skip_nodes);
-        //         }
-        //     }
-        // } else {
-        //     println!("Skipping node: {}", node_kind);
-        // }
-
-        if skip_nodes.contains(&node_kind) {
-            // Don't count this node, but still traverse its children
-            for i in 0..node.child_count() {
-                if let Some(child) = node.child(i) {
-                    count += self.count_decision_points(child, decision_points, skip_nodes);
-                }
+    /// Calculate the cyclomatic complexity of the node
+    pub fn calculate_cc(&mut self, visitor: &TreeVisitor, node: &Node) {
+        let decision_points = get_node_group(self.meta_data.language, "decision_point_nodes");
+        let skip_nodes = get_node_group(self.meta_data.language, "decision_point_skip_nodes");
+
+        // The cc of a class or file sums those of its methods, but that of a method leaves out
+        // the lambdas and classes nested in it, which have their own rows
+        let count = match self.meta_data.node_kind {
+            EntityKind::Method => {
+                visitor.count_own_decision_points(*node, &decision_points, &skip_nodes)
             }
-            return count;
-        }
+            _ => visitor.count_decision_points(*node, &decision_points, &skip_nodes),
+        };
+        self.metric.cc = count as u32 + 1;
+    }
 
-        // Count this node if it's a decision point
-        if decision_points.contains(&node_kind) {
-            count += 1;
-        }
+    /// Calculate the number of exit points of a method, its return, throw and raise statements
+    pub fn calculate_noret(
+        &mut self,
+        visitor: &TreeVisitor,
+        node: &Node,
+        tree: &Tree,
+    ) -> Result<(), XStatsError> {
+        self.metric.noret = visitor.count_exit_points(node, tree)? as u32;
+        Ok(())
+    }
 
-        // Traverse children
-        for i in 0..node.child_count() {
-            if let Some(child) = node.child(i) {
-                count += self.count_decision_points(child, decision_points, skip_nodes);
-            }
+    /// Calculate the maximum nesting depth of the node
+    pub fn calculate_nd(&mut self, visitor: &TreeVisitor, node: &Node) {
+        let nesting_nodes = get_node_group(self.meta_data.language, "nesting_nodes");
+        let skip_nodes = get_node_group(self.meta_data.language, "decision_point_skip_nodes");
+
+        self.metric.nd = visitor.get_nesting_depth(*node, &nesting_nodes, &skip_nodes) as u32;
+    }
+
+    /// Calculate the LCOM4 of a class, left at 0 for the languages without fields to relate
+    /// its methods, i.e. without a cohesion query
+    pub fn calculate_lcom(
+        &mut self,
+        visitor: &TreeVisitor,
+        node: &Node,
+        tree: &Tree,
+    ) -> Result<(), XStatsError> {
+        if get_profile(self.meta_data.language)
+            .query("cohesion_query")
+            .is_some()
+        {
+            self.metric.lcom = visitor.get_lcom4(node, tree)?;
         }
+        Ok(())
+    }
 
-        count
+    pub fn calculate_halstead(&mut self, visitor: &TreeVisitor, node: &Node) {
+        let halstead_metrics =
+            HalsteadMetrics::from_node(node, visitor.source_code, self.meta_data.language);
+        self.metric.hv = halstead_metrics.volume();
+        self.metric.hd = halstead_metrics.difficulty();
+        self.metric.he = halstead_metrics.effort();
     }
 
-    /// Calculate the cyclomatic complexity of the node
-    pub fn calculate_cc(&mut self, node: &Node) {
-        let decision_points =
-            get_node_group(self.meta_data.language.clone(), "decision_point_nodes");
-        let skip_nodes =
-            get_node_group(self.meta_data.language.clone(), "decision_point_skip_nodes");
+    /// Count the matches of the custom queries valid for the language of the node
+    pub fn calculate_custom(
+        &mut self,
+        visitor: &TreeVisitor,
+        node: &Node,
+    ) -> Result<(), XStatsError> {
+        let language = self.meta_data.language;
+        let parser = visitor
+            .parsers
+            .get_parser(&language)
+            .ok_or(XStatsError::ParserNotFound(language))?;
+        for (name, custom_query) in &visitor.config.custom_queries {
+            if custom_query.languages.contains(&language) {
+                let count = parser.count_matches(node, visitor.source_code, &custom_query.query)?;
+                self.custom.insert(name.clone(), count as u32);
+            }
+        }
+        Ok(())
+    }
 
-        self.metric.cc =
-            self.count_decision_points(*node, &decision_points, &skip_nodes) as u32 + 1;
+    /// Calculate the maintainability index of the node from its Halstead volume, cyclomatic
+    /// complexity and aloc, normalized to 0-100. Must be called after those are calculated.
+    pub fn calculate_mi(&mut self) {
+        let volume = self.metric.hv.max(1.0);
+        let aloc = (self.metric.aloc as f64).max(1.0);
+        let mi = 171.0 - 5.2 * volume.ln() - 0.23 * self.metric.cc as f64 - 16.2 * aloc.ln();
+        self.metric.mi = (mi * 100.0 / 171.0).clamp(0.0, 100.0);
     }
 }
 
@@ -199,6 +534,12 @@ pub struct CodeMetrics {
     pub metric_blocks: Vec<CodeMetricBlock>,
 }
 
+impl Default for CodeMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CodeMetrics {
     pub fn new() -> CodeMetrics {
         CodeMetrics {
@@ -206,36 +547,105 @@ impl CodeMetrics {
         }
     }
 
+    /// Read the metric blocks back from the records of a metrics table, e.g. those of
+    /// `metrics.json`. The metrics left out of the records, e.g. with `metrics`, are zero.
+    pub fn from_records(records: &[Value]) -> Result<CodeMetrics, String> {
+        let mut code_metrics = CodeMetrics::new();
+        for record in records {
+            let meta_data: CodeMetaData =
+                serde_json::from_value(record.clone()).map_err(|e| e.to_string())?;
+            let mut metric =
+                serde_json::to_value(CodeMetric::default()).map_err(|e| e.to_string())?;
+            for name in CodeMetric::get_names() {
+                if let Some(value) = record.get(&name) {
+                    metric[&name] = value.clone();
+                }
+            }
+            let mut metric_block = CodeMetricBlock::new(
+                meta_data.language,
+                &meta_data.file_path,
+                meta_data.node_name.clone(),
+                meta_data.node_type.clone(),
+                meta_data.node_kind,
+            );
+            metric_block.meta_data = meta_data;
+            metric_block.metric = serde_json::from_value(metric).map_err(|e| e.to_string())?;
+            code_metrics.add_metric_block(metric_block);
+        }
+        Ok(code_metrics)
+    }
+
     fn add_metric_block(&mut self, code_metric_block: CodeMetricBlock) {
         self.metric_blocks.push(code_metric_block);
     }
 
+    /// Shift the positions of the metric blocks starting at `first_block`
+    pub fn shift_positions(&mut self, first_block: usize, row_offset: u32, col_offset: u32) {
+        for metric_block in &mut self.metric_blocks[first_block..] {
+            metric_block.shift_position(row_offset, col_offset);
+        }
+    }
+
     pub fn generate_root_metrics(
         &mut self,
         parsers: &TSParsers,
+        config: &AnalysisConfig,
         source_code: &str,
         language: Language,
-        file_path: &String,
+        file_path: &str,
         tree: &Tree,
-    ) {
-        let visitor = TreeVisitor::new(parsers, language, source_code);
+    ) -> Result<(), XStatsError> {
+        self.regenerate_root_metrics(
+            parsers,
+            config,
+            source_code,
+            language,
+            file_path,
+            tree,
+            &[],
+            &[],
+        )
+    }
+
+    /// Generate the metrics of a file after an edit, reusing from `old_blocks`, the metrics of
+    /// the file before the edit, those of the classes and methods outside of the `changed_ranges`
+    /// of bytes. The file itself is always measured again.
+    #[allow(clippy::too_many_arguments)]
+    pub fn regenerate_root_metrics(
+        &mut self,
+        parsers: &TSParsers,
+        config: &AnalysisConfig,
+        source_code: &str,
+        language: Language,
+        file_path: &str,
+        tree: &Tree,
+        old_blocks: &[CodeMetricBlock],
+        changed_ranges: &[Range<usize>],
+    ) -> Result<(), XStatsError> {
+        let visitor = TreeVisitor::new(parsers, config, language, source_code);
+        let first_block = self.metric_blocks.len();
 
         let root_node = tree.root_node();
         let root_type = root_node.kind();
         let mut metric_block = CodeMetricBlock::new(
             language,
-            &file_path,
-            get_file_name(&file_path),
+            file_path,
+            get_file_name(file_path),
             root_type.to_string(),
+            EntityKind::File,
         );
         metric_block.generate_simple_node_metrics(&visitor, &root_node);
         metric_block.calculate_eloc(&visitor, &root_node);
         let (comment_nodes, import_nodes, class_nodes, method_nodes) =
-            visitor.perform_base_query(&root_node, tree);
+            visitor.perform_base_query(&root_node, tree)?;
 
         metric_block.calculate_cloc_dcloc(&visitor, &comment_nodes);
+        metric_block.calculate_sloc(&visitor, &root_node, &comment_nodes);
         metric_block.calculate_noi(&import_nodes);
-        metric_block.calculate_cc(&root_node);
+        metric_block.calculate_cc(&visitor, &root_node);
+        metric_block.calculate_nd(&visitor, &root_node);
+        metric_block.calculate_halstead(&visitor, &root_node);
+        metric_block.calculate_mi();
 
         // let class_nodes = visitor.get_class_nodes(&root_node, tree, source_code);
         // metric.noc = class_nodes.len() as u32;
@@ -244,150 +654,403 @@ impl CodeMetrics {
         // let method_nodes = visitor.get_method_nodes(&root_node, tree, source_code);
         // metric.nom = method_nodes.len() as u32;
         metric_block.calculate_nom(&method_nodes);
+        metric_block.risky_constructs = visitor.get_risky_constructs(&root_node, tree)?;
+        metric_block.markers = visitor.get_marker_comments(&comment_nodes);
+        metric_block.naming = visitor.get_naming_stats(&root_node, tree)?;
+        metric_block.package = visitor.get_package_name(&root_node);
+        metric_block.calculate_custom(&visitor, &root_node)?;
 
         self.add_metric_block(metric_block);
 
-        self.generate_class_metrics(
-            &parsers,
-            &source_code,
-            language,
-            file_path.to_string(),
-            &tree,
-            &class_nodes,
-            &visitor,
-        );
-        self.generate_function_metrics(
-            &parsers,
-            &source_code,
-            language,
-            file_path.to_string(),
-            &tree,
-            &method_nodes,
-            &visitor,
-        );
+        let mut reused_blocks = ReusedBlocks::new(old_blocks, changed_ranges);
+        for node in &class_nodes {
+            let class_name = visitor.get_class_name(node);
+            let mut metric_block = match reused_blocks.take(node, &class_name) {
+                Some(metric_block) => metric_block,
+                None => Self::generate_class_block(file_path, tree, node, class_name, &visitor)?,
+            };
+            // The comments above a reused block may have changed
+            metric_block.meta_data.suppressed = visitor.get_suppressed_metrics(node);
+            self.add_metric_block(metric_block);
+        }
+        for node in &method_nodes {
+            let method_name = visitor.get_method_name(node);
+            let mut metric_block = match reused_blocks.take(node, &method_name) {
+                Some(metric_block) => metric_block,
+                None => Self::generate_method_block(file_path, tree, node, method_name, &visitor)?,
+            };
+            metric_block.meta_data.suppressed = visitor.get_suppressed_metrics(node);
+            self.add_metric_block(metric_block);
+        }
+        self.summarize_class_methods(first_block);
+        self.qualify_names(first_block);
+        Ok(())
+    }
+
+    /// Qualify the names of the blocks of a file, starting at `first_block`, by its namespace and
+    /// the classes and methods enclosing them, e.g. `com.shop.Cart.add`. The namespace is the
+    /// package of a Java file, and the module of the other files, e.g. `cart` for `cart.py`.
+    /// Files are qualified by their package only, e.g. `com.shop.Cart.java`.
+    pub fn qualify_names(&mut self, first_block: usize) {
+        let blocks = &self.metric_blocks[first_block..];
+        let Some(file_block) = blocks.first() else {
+            return;
+        };
+        let namespace = get_namespace(file_block);
+        let get_range = |block: &CodeMetricBlock| {
+            let meta_data = &block.meta_data;
+            (
+                (meta_data.start_row, meta_data.start_col),
+                (meta_data.end_row, meta_data.end_col),
+            )
+        };
+
+        let qualified_names: Vec<String> = blocks
+            .iter()
+            .enumerate()
+            .map(|(idx, block)| {
+                let mut names: Vec<&str> = Vec::new();
+                if block.meta_data.node_kind == EntityKind::File {
+                    names.extend(file_block.package.as_deref());
+                } else {
+                    names.extend(Some(namespace.as_str()).filter(|name| !name.is_empty()));
+                    let (start, end) = get_range(block);
+                    // The enclosing entities, outermost first
+                    let mut enclosing: Vec<&CodeMetricBlock> = blocks
+                        .iter()
+                        .enumerate()
+                        .filter(|(other_idx, other)| {
+                            let (other_start, other_end) = get_range(other);
+                            *other_idx != idx
+                                && other.meta_data.node_kind != EntityKind::File
+                                && other_start <= start
+                                && end <= other_end
+                        })
+                        .map(|(_, other)| other)
+                        .collect();
+                    enclosing.sort_by_key(|other| get_range(other).0);
+                    names.extend(
+                        enclosing
+                            .iter()
+                            .map(|other| other.meta_data.node_name.as_str()),
+                    );
+                }
+                names.push(&block.meta_data.node_name);
+                names.join(".")
+            })
+            .collect();
+        for (block, qualified_name) in self.metric_blocks[first_block..]
+            .iter_mut()
+            .zip(qualified_names)
+        {
+            block.meta_data.set_qualified_name(qualified_name);
+        }
+    }
+
+    /// Summarize on the row of every class among the blocks of a file, starting at
+    /// `first_block`, the length and complexity of its methods: those it encloses, but not
+    /// those of the classes nested in it
+    fn summarize_class_methods(&mut self, first_block: usize) {
+        let blocks = &self.metric_blocks[first_block..];
+        let get_range = |block: &CodeMetricBlock| {
+            let meta_data = &block.meta_data;
+            (
+                (meta_data.start_row, meta_data.start_col),
+                (meta_data.end_row, meta_data.end_col),
+            )
+        };
+        let mut class_methods: HashMap<usize, Vec<&CodeMetric>> = HashMap::new();
+        for method in blocks
+            .iter()
+            .filter(|block| block.meta_data.node_kind == EntityKind::Method)
+        {
+            let (start, end) = get_range(method);
+            // The innermost class enclosing the method starts last
+            let class = blocks
+                .iter()
+                .enumerate()
+                .filter(|(_, block)| block.meta_data.node_kind == EntityKind::Class)
+                .filter(|(_, class)| {
+                    let (class_start, class_end) = get_range(class);
+                    class_start <= start && end <= class_end
+                })
+                .max_by_key(|(_, class)| get_range(class).0);
+            if let Some((idx, _)) = class {
+                class_methods.entry(idx).or_default().push(&method.metric);
+            }
+        }
+
+        let summaries: Vec<(usize, u32, f64, u32)> = class_methods
+            .into_iter()
+            .map(|(idx, methods)| {
+                let total_aloc: u32 = methods.iter().map(|metric| metric.aloc).sum();
+                (
+                    idx,
+                    methods.iter().map(|metric| metric.aloc).max().unwrap_or(0),
+                    total_aloc as f64 / methods.len() as f64,
+                    methods.iter().map(|metric| metric.cc).max().unwrap_or(0),
+                )
+            })
+            .collect();
+        for (idx, max_aloc, avg_aloc, max_cc) in summaries {
+            let metric = &mut self.metric_blocks[first_block + idx].metric;
+            metric.max_method_aloc = max_aloc;
+            metric.avg_method_aloc = avg_aloc;
+            metric.max_method_cc = max_cc;
+        }
     }
 
     pub fn generate_class_metrics(
         &mut self,
-        parsers: &TSParsers,
-        source_code: &str,
-        language: Language,
-        file_path: String,
+        file_path: &str,
         tree: &Tree,
-        class_nodes: &Vec<Node>,
+        class_nodes: &[Node],
         visitor: &TreeVisitor,
-    ) {
+    ) -> Result<(), XStatsError> {
         for node in class_nodes {
-            let node_type = node.kind();
-
             let class_name = visitor.get_class_name(node);
+            let metric_block =
+                Self::generate_class_block(file_path, tree, node, class_name, visitor)?;
+            self.add_metric_block(metric_block);
+        }
+        Ok(())
+    }
 
-            let (comment_nodes, import_nodes, class_nodes, method_nodes) =
-                visitor.perform_base_query(&node, tree);
+    fn generate_class_block(
+        file_path: &str,
+        tree: &Tree,
+        node: &Node,
+        class_name: String,
+        visitor: &TreeVisitor,
+    ) -> Result<CodeMetricBlock, XStatsError> {
+        let node_type = node.kind();
 
-            let mut metric_block =
-                CodeMetricBlock::new(language, &file_path, class_name, node_type.to_string());
-            metric_block.generate_simple_node_metrics(&visitor, &node);
-            metric_block.calculate_eloc(visitor, node);
-            metric_block.calculate_cloc_dcloc(&visitor, &comment_nodes);
-            metric_block.calculate_noi(&import_nodes);
-            metric_block.calculate_noc(&class_nodes);
-            metric_block.metric.noc -= 1; // Exclude the class itself
-            metric_block.calculate_nom(&method_nodes);
-            metric_block.calculate_cc(node);
+        let (comment_nodes, import_nodes, class_nodes, method_nodes) =
+            visitor.perform_base_query(node, tree)?;
 
-            self.add_metric_block(metric_block);
-        }
+        let mut metric_block = CodeMetricBlock::new(
+            visitor.language,
+            file_path,
+            class_name,
+            node_type.to_string(),
+            EntityKind::Class,
+        );
+        metric_block.generate_simple_node_metrics(visitor, node);
+        metric_block.calculate_eloc(visitor, node);
+        metric_block.calculate_cloc_dcloc(visitor, &comment_nodes);
+        metric_block.calculate_sloc(visitor, node, &comment_nodes);
+        metric_block.calculate_noi(&import_nodes);
+        metric_block.calculate_noc(&class_nodes);
+        metric_block.metric.noc -= 1; // Exclude the class itself
+        metric_block.calculate_nom(&method_nodes);
+        metric_block.calculate_lcom(visitor, node, tree)?;
+        metric_block.calculate_cc(visitor, node);
+        metric_block.calculate_nd(visitor, node);
+        metric_block.calculate_halstead(visitor, node);
+        metric_block.calculate_mi();
+        metric_block.naming = visitor.get_naming_stats(node, tree)?;
+        metric_block.calculate_custom(visitor, node)?;
+
+        Ok(metric_block)
     }
 
     pub fn generate_function_metrics(
         &mut self,
-        parsers: &TSParsers,
-        source_code: &str,
-        language: Language,
-        file_path: String,
+        file_path: &str,
         tree: &Tree,
-        method_nodes: &Vec<Node>,
+        method_nodes: &[Node],
         visitor: &TreeVisitor,
-    ) {
+    ) -> Result<(), XStatsError> {
         for node in method_nodes {
-            let node_type = node.kind();
-
             let method_name = visitor.get_method_name(node);
+            let metric_block =
+                Self::generate_method_block(file_path, tree, node, method_name, visitor)?;
+            self.add_metric_block(metric_block);
+        }
+        Ok(())
+    }
+
+    fn generate_method_block(
+        file_path: &str,
+        tree: &Tree,
+        node: &Node,
+        method_name: String,
+        visitor: &TreeVisitor,
+    ) -> Result<CodeMetricBlock, XStatsError> {
+        let node_type = node.kind();
 
-            let (comment_nodes, import_nodes, class_nodes, method_nodes) =
-                visitor.perform_base_query(&node, tree);
+        let (comment_nodes, import_nodes, class_nodes, method_nodes) =
+            visitor.perform_base_query(node, tree)?;
 
-            let mut metric_block =
-                CodeMetricBlock::new(language, &file_path, method_name, node_type.to_string());
-            metric_block.generate_simple_node_metrics(&visitor, &node);
+        let mut metric_block = CodeMetricBlock::new(
+            visitor.language,
+            file_path,
+            method_name,
+            node_type.to_string(),
+            EntityKind::Method,
+        );
+        metric_block.generate_simple_node_metrics(visitor, node);
 
-            metric_block.calculate_eloc(visitor, node);
-            metric_block.calculate_cloc_dcloc(&visitor, &comment_nodes);
-            metric_block.calculate_noi(&import_nodes);
-            metric_block.calculate_noc(&class_nodes);
-            metric_block.calculate_nom(&method_nodes);
-            metric_block.metric.nom -= 1; // Exclude the method itself
-            metric_block.calculate_cc(node);
+        metric_block.calculate_eloc(visitor, node);
+        metric_block.calculate_cloc_dcloc(visitor, &comment_nodes);
+        metric_block.calculate_sloc(visitor, node, &comment_nodes);
+        metric_block.calculate_noi(&import_nodes);
+        metric_block.calculate_noc(&class_nodes);
+        metric_block.calculate_nom(&method_nodes);
+        metric_block.metric.nom -= 1; // Exclude the method itself
+        metric_block.calculate_cc(visitor, node);
+        metric_block.calculate_nd(visitor, node);
+        metric_block.calculate_halstead(visitor, node);
+        metric_block.calculate_mi();
 
-            let parameters_count = visitor.count_parameters(node);
-            metric_block.load_pc(parameters_count as u32);
+        metric_block.calculate_pc(visitor, node);
+        metric_block.calculate_noret(visitor, node, tree)?;
+        metric_block.metric.is_boilerplate = visitor.check_if_boilerplate(node);
+        metric_block.fingerprint = Some(visitor.get_body_fingerprint(node));
+        metric_block.minhash = visitor.get_body_minhash(node);
+        metric_block.extract_candidates = visitor.get_extract_candidates(node);
+        metric_block.naming = visitor.get_naming_stats(node, tree)?;
+        metric_block.calculate_custom(visitor, node)?;
 
-            self.add_metric_block(metric_block);
-        }
+        Ok(metric_block)
     }
 }
 
-pub fn get_node_group(language: Language, group_name: &str) -> Vec<String> {
-    const JAVA_DECISION_POINTS: &[&str] = &[
-        "if_statement",
-        "else_clause",
-        "for_statement",
-        "while_statement",
-        "do_statement",
-        "switch_expression",
-        "switch_statement",
-        "catch_clause",
-        "conditional_expression",
-        "lambda_expression",
-        "method_reference",
-    ];
-
-    const JAVA_DECISION_POINTS_SKIP_NODES: &[&str] = &[
-        "class_declaration",
-        "method_declaration",
-        "constructor_declaration",
-    ];
-
-    const PYTHON_DECISION_POINTS: &[&str] = &[
-        "if_statement",
-        "elif_clause",
-        "for_statement",
-        "while_statement",
-        "with_statement",
-        "try_statement",
-        "except_clause",
-        "match_statement",
-        "case_clause",
-        "conditional_expression",
-        "lambda",
-    ];
-
-    const PYTHON_DECISION_POINTS_SKIP_NODES: &[&str] = &["class_definition", "function_definition"];
-
-    let vec = match (&language, group_name) {
-        (Language::Java, "decision_point_nodes") => JAVA_DECISION_POINTS,
-        (Language::Python, "decision_point_nodes") => PYTHON_DECISION_POINTS,
-        (Language::Java, "decision_point_skip_nodes") => JAVA_DECISION_POINTS_SKIP_NODES,
-        (Language::Python, "decision_point_skip_nodes") => PYTHON_DECISION_POINTS_SKIP_NODES,
-        _ => {
-            eprintln!(
-                "Unsupported language or group name: {} - {}",
-                language, group_name
+/// The metric blocks of a file before an edit, handed back for the entities the edit left alone
+struct ReusedBlocks<'a> {
+    old_blocks: HashMap<(&'a str, &'a str), Vec<&'a CodeMetricBlock>>,
+    /// How many entities of each type and name were already met in the edited file
+    seen: HashMap<(String, String), usize>,
+    changed_ranges: &'a [Range<usize>],
+}
+
+impl<'a> ReusedBlocks<'a> {
+    fn new(old_blocks: &'a [CodeMetricBlock], changed_ranges: &'a [Range<usize>]) -> Self {
+        let mut blocks: HashMap<_, Vec<_>> = HashMap::new();
+        for block in old_blocks {
+            let key = (
+                block.meta_data.node_type.as_str(),
+                block.meta_data.node_name.as_str(),
             );
-            &[]
+            blocks.entry(key).or_default().push(block);
         }
+        ReusedBlocks {
+            old_blocks: blocks,
+            seen: HashMap::new(),
+            changed_ranges,
+        }
+    }
+
+    /// The old block of the same entity, moved to the node, unless the node was changed.
+    ///
+    /// Entities are matched by type and name in order of appearance, as when comparing metrics,
+    /// and must span as many lines as before.
+    fn take(&mut self, node: &Node, node_name: &str) -> Option<CodeMetricBlock> {
+        let key = (node.kind().to_string(), node_name.to_string());
+        let occurrence = self.seen.entry(key).or_insert(0);
+        let old_block = self
+            .old_blocks
+            .get(&(node.kind(), node_name))
+            .and_then(|blocks| blocks.get(*occurrence));
+        *occurrence += 1;
+
+        let is_changed = self
+            .changed_ranges
+            .iter()
+            .any(|range| range.start <= node.end_byte() && node.start_byte() <= range.end);
+        if is_changed {
+            return None;
+        }
+        let mut metric_block = (*old_block?).clone();
+        let aloc = metric_block.metric.aloc;
+        metric_block.load_range_aloc(node);
+        (metric_block.metric.aloc == aloc).then_some(metric_block)
+    }
+}
+
+/// The node groups a language may define, see `get_node_group`
+pub const NODE_GROUPS: &[&str] = &[
+    "decision_point_nodes",
+    "decision_point_skip_nodes",
+    "nesting_nodes",
+    "halstead_literal_nodes",
+    "string_nodes",
+    "parameter_skip_nodes",
+    "default_parameter_nodes",
+    "variadic_parameter_nodes",
+    "exit_skip_nodes",
+];
+
+/// The namespace qualifying the names of the entities of a file, from its root block: its
+/// package, or its module, e.g. `cart` for `cart.py`
+fn get_namespace(file_block: &CodeMetricBlock) -> String {
+    let file_meta_data = &file_block.meta_data;
+    match (&file_block.package, file_meta_data.language) {
+        (Some(package), _) => package.clone(),
+        (None, Language::Java) => String::new(),
+        (None, _) => Path::new(&file_meta_data.file_path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// Give the entities of a renamed file the ids they had before the rename, in `old_blocks`, so
+/// that they can be followed across it. Entities are matched by their type and their name within
+/// the file, i.e. qualified without the namespace, which a rename may change.
+pub fn inherit_entity_ids(blocks: &mut [CodeMetricBlock], old_blocks: &[CodeMetricBlock]) {
+    let get_keys = |blocks: &[CodeMetricBlock]| -> Vec<(String, String)> {
+        let namespace = blocks.first().map(get_namespace).unwrap_or_default();
+        blocks
+            .iter()
+            .map(|block| {
+                let meta_data = &block.meta_data;
+                let name = match meta_data.node_kind {
+                    EntityKind::File => String::new(),
+                    _ => meta_data
+                        .qualified_name
+                        .strip_prefix(&format!("{}.", namespace))
+                        .unwrap_or(&meta_data.qualified_name)
+                        .to_string(),
+                };
+                (meta_data.node_type.clone(), name)
+            })
+            .collect()
     };
+    let old_ids: HashMap<(String, String), &str> = get_keys(old_blocks)
+        .into_iter()
+        .zip(old_blocks)
+        .map(|(key, block)| (key, block.meta_data.entity_id.as_str()))
+        .collect();
+    for (key, block) in get_keys(blocks).into_iter().zip(blocks.iter_mut()) {
+        if let Some(old_id) = old_ids.get(&key) {
+            block.meta_data.entity_id = old_id.to_string();
+        }
+    }
+}
+
+pub fn get_node_group(language: Language, group_name: &str) -> Vec<String> {
+    let kinds = find_node_group(language, group_name).unwrap_or_else(|| {
+        error!(
+            "Unsupported language or group name: {} - {}",
+            language, group_name
+        );
+        &[]
+    });
+    kinds.iter().map(|s| s.to_string()).collect()
+}
+
+/// The node kinds of the group for the language, none if it does not define it, as for the
+/// optional groups, e.g. `default_parameter_nodes` of the languages without default values
+fn get_node_group_or_empty(language: Language, group_name: &str) -> Vec<String> {
+    find_node_group(language, group_name)
+        .unwrap_or_default()
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
 
-    vec.iter().map(|s| s.to_string()).collect()
+/// The node kinds of the group for the language, if it defines one
+pub fn find_node_group(language: Language, group_name: &str) -> Option<&'static [&'static str]> {
+    get_profile(language).node_group(group_name)
 }
@@ -0,0 +1,130 @@
+use crate::utils::format_utc_time;
+use serde::{Deserialize, Serialize};
+
+/// A commit analyzed in multi-commit mode, with the issues its message refers to
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CommitInfo {
+    pub commit_id: String,
+    /// The email of the author of the commit, or their name without one
+    #[serde(default)]
+    pub author: String,
+    /// When the commit was made, in seconds since the epoch
+    pub committed_at: i64,
+    /// The first line of the message
+    pub summary: String,
+    /// The issues referred to by the message, e.g. `JIRA-123` or `#456`, in order of appearance
+    pub issue_refs: Vec<String>,
+}
+
+impl CommitInfo {
+    pub fn new(commit_id: String, author: String, committed_at: i64, message: &str) -> Self {
+        CommitInfo {
+            commit_id,
+            author,
+            committed_at,
+            summary: message.lines().next().unwrap_or_default().to_string(),
+            issue_refs: get_issue_refs(message),
+        }
+    }
+}
+
+/// The commits of the history, to join the metric changes of each with the issue tracker
+pub struct CommitInfos {
+    pub commits: Vec<CommitInfo>,
+}
+
+impl Default for CommitInfos {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommitInfos {
+    pub fn new() -> CommitInfos {
+        CommitInfos {
+            commits: Vec::new(),
+        }
+    }
+
+    pub fn add_commit(&mut self, commit: CommitInfo) {
+        self.commits.push(commit);
+    }
+
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![vec![
+            "commit_id".to_string(),
+            "committed_at".to_string(),
+            "summary".to_string(),
+            "issue_refs".to_string(),
+            "author".to_string(),
+        ]];
+
+        for commit in &self.commits {
+            table.push(vec![
+                commit.commit_id.clone(),
+                format_utc_time(commit.committed_at),
+                commit.summary.clone(),
+                commit.issue_refs.join(","),
+                commit.author.clone(),
+            ]);
+        }
+
+        table
+    }
+}
+
+/// Find the issue references of a commit message, without duplicates: the keys of issue
+/// trackers like Jira, e.g. `JIRA-123`, an uppercase project key then a number, and the numbers
+/// of GitHub or GitLab issues, e.g. `#456`.
+pub fn get_issue_refs(message: &str) -> Vec<String> {
+    let chars: Vec<char> = message.chars().collect();
+    let is_word_char = |idx: usize| {
+        chars
+            .get(idx)
+            .is_some_and(|c| c.is_alphanumeric() || *c == '_')
+    };
+    let count_digits = |start: usize| {
+        chars[start.min(chars.len())..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count()
+    };
+
+    let mut issue_refs: Vec<String> = Vec::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        // References start a word
+        if idx > 0 && (is_word_char(idx - 1) || chars[idx - 1] == '&') {
+            idx += 1;
+            continue;
+        }
+        let key_length = chars[idx..]
+            .iter()
+            .take_while(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || **c == '_')
+            .count();
+        let length = if chars[idx] == '#' {
+            let digits = count_digits(idx + 1);
+            (digits > 0).then_some(digits + 1)
+        } else if chars[idx].is_ascii_uppercase()
+            && key_length >= 2
+            && chars.get(idx + key_length) == Some(&'-')
+        {
+            let digits = count_digits(idx + key_length + 1);
+            (digits > 0).then_some(key_length + 1 + digits)
+        } else {
+            None
+        };
+
+        match length.filter(|length| !is_word_char(idx + length)) {
+            Some(length) => {
+                let issue_ref: String = chars[idx..idx + length].iter().collect();
+                if !issue_refs.contains(&issue_ref) {
+                    issue_refs.push(issue_ref);
+                }
+                idx += length;
+            }
+            None => idx += 1,
+        }
+    }
+    issue_refs
+}
@@ -0,0 +1,306 @@
+use crate::metrics::code_metrics::{CodeMetric, CodeMetricBlock, CodeMetrics};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffStatus {
+    Added,
+    Removed,
+    Modified,
+    Renamed,
+}
+
+impl Display for DiffStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+pub struct EntityDiff {
+    /// Whether the entity was added, removed, modified or renamed.
+    pub status: DiffStatus,
+    /// The entity before the change, if it existed.
+    pub old: Option<CodeMetricBlock>,
+    /// The entity after the change, if it still exists.
+    pub new: Option<CodeMetricBlock>,
+}
+
+/// The metrics whose increase makes an entity harder to maintain, its size aside
+const HIGHER_IS_WORSE: &[&str] = &["cc", "pc", "nd", "lcom", "cbo", "fan_out", "hd", "he"];
+
+/// The metrics whose decrease makes an entity harder to maintain
+const LOWER_IS_WORSE: &[&str] = &["mi"];
+
+impl EntityDiff {
+    fn get_block(&self) -> &CodeMetricBlock {
+        self.new.as_ref().or(self.old.as_ref()).unwrap()
+    }
+
+    /// The metrics among `metrics` that got worse, e.g. a higher `cc` or a lower `mi`. Only
+    /// entities present before and after the change regress.
+    pub fn get_regressions(&self, metrics: &[String]) -> Vec<String> {
+        let (Some(old), Some(new)) = (&self.old, &self.new) else {
+            return Vec::new();
+        };
+        metrics
+            .iter()
+            .filter(|metric| {
+                if metric.as_str() == "is_broken" {
+                    return new.metric.is_broken && !old.metric.is_broken;
+                }
+                let old_value = old.metric.get_value(metric);
+                let new_value = new.metric.get_value(metric);
+                match (old_value, new_value) {
+                    (Some(old_value), Some(new_value)) => {
+                        (HIGHER_IS_WORSE.contains(&metric.as_str()) && new_value > old_value)
+                            || (LOWER_IS_WORSE.contains(&metric.as_str()) && new_value < old_value)
+                    }
+                    _ => false,
+                }
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// Key matching the same entity across two sets of metrics, by its qualified name, or its name
+/// when comparing with metrics saved before qualified names
+fn get_entity_key(block: &CodeMetricBlock, is_qualified: bool) -> (String, String, String) {
+    let meta_data = &block.meta_data;
+    let name = if is_qualified {
+        &meta_data.qualified_name
+    } else {
+        &meta_data.node_name
+    };
+    (
+        meta_data.file_path.clone(),
+        meta_data.node_type.clone(),
+        name.clone(),
+    )
+}
+
+pub struct EntityDiffs {
+    pub diffs: Vec<EntityDiff>,
+}
+
+impl EntityDiffs {
+    /// Compare two sets of metrics and annotate each changed entity.
+    ///
+    /// Entities are matched by file path, node type and qualified name, in order of appearance when
+    /// the same name is used more than once. An entity that disappears while another one with
+    /// the same type and metrics appears in the same file is reported as renamed.
+    pub fn compare(old_metrics: &CodeMetrics, new_metrics: &CodeMetrics) -> Self {
+        let is_qualified = old_metrics
+            .metric_blocks
+            .iter()
+            .chain(&new_metrics.metric_blocks)
+            .all(|block| !block.meta_data.qualified_name.is_empty());
+        let mut old_entities: HashMap<_, Vec<&CodeMetricBlock>> = HashMap::new();
+        for block in &old_metrics.metric_blocks {
+            old_entities
+                .entry(get_entity_key(block, is_qualified))
+                .or_default()
+                .push(block);
+        }
+
+        let mut diffs = Vec::new();
+        let mut added = Vec::new();
+        let mut seen: HashMap<_, usize> = HashMap::new();
+        for block in &new_metrics.metric_blocks {
+            let key = get_entity_key(block, is_qualified);
+            let occurrence = seen.entry(key.clone()).or_insert(0);
+            let old_block = old_entities
+                .get(&key)
+                .and_then(|blocks| blocks.get(*occurrence));
+            *occurrence += 1;
+
+            match old_block {
+                Some(old_block) if old_block.metric != block.metric => diffs.push(EntityDiff {
+                    status: DiffStatus::Modified,
+                    old: Some((*old_block).clone()),
+                    new: Some(block.clone()),
+                }),
+                Some(_) => {}
+                None => added.push(block),
+            }
+        }
+
+        // Old entities left unmatched were either removed or renamed
+        let mut removed = Vec::new();
+        let mut occurrences: HashMap<_, usize> = HashMap::new();
+        for block in &old_metrics.metric_blocks {
+            let key = get_entity_key(block, is_qualified);
+            let matched = seen.get(&key).copied().unwrap_or(0);
+            let occurrence = occurrences.entry(key).or_insert(0);
+            if *occurrence >= matched {
+                removed.push(block);
+            }
+            *occurrence += 1;
+        }
+
+        for block in added {
+            let renamed_from = removed.iter().position(|old_block| {
+                old_block.meta_data.file_path == block.meta_data.file_path
+                    && old_block.meta_data.node_type == block.meta_data.node_type
+                    && old_block.metric == block.metric
+            });
+            let (status, old) = match renamed_from {
+                Some(idx) => (DiffStatus::Renamed, Some(removed.remove(idx).clone())),
+                None => (DiffStatus::Added, None),
+            };
+            diffs.push(EntityDiff {
+                status,
+                old,
+                new: Some(block.clone()),
+            });
+        }
+
+        diffs.extend(removed.into_iter().map(|block| EntityDiff {
+            status: DiffStatus::Removed,
+            old: Some(block.clone()),
+            new: None,
+        }));
+
+        Self { diffs }
+    }
+
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = Vec::new();
+        // Add header row, each metric is reported before and after the change
+        let mut header: Vec<String> = [
+            "status",
+            "language",
+            "file_path",
+            "node_type",
+            "old_node_name",
+            "new_node_name",
+            "old_start_row",
+            "new_start_row",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        for name in CodeMetric::get_names() {
+            header.push(format!("old_{}", name));
+            header.push(format!("new_{}", name));
+        }
+        table.push(header);
+
+        for diff in &self.diffs {
+            let block = diff.get_block();
+            let old_values = diff.old.as_ref().map(|b| b.metric.get_values());
+            let new_values = diff.new.as_ref().map(|b| b.metric.get_values());
+            let mut row = vec![
+                diff.status.to_string(),
+                block.meta_data.language.to_string(),
+                block.meta_data.file_path.clone(),
+                block.meta_data.node_type.clone(),
+                diff.old
+                    .as_ref()
+                    .map_or(String::new(), |b| b.meta_data.node_name.clone()),
+                diff.new
+                    .as_ref()
+                    .map_or(String::new(), |b| b.meta_data.node_name.clone()),
+                diff.old
+                    .as_ref()
+                    .map_or(String::new(), |b| b.meta_data.start_row.to_string()),
+                diff.new
+                    .as_ref()
+                    .map_or(String::new(), |b| b.meta_data.start_row.to_string()),
+            ];
+            for idx in 0..CodeMetric::get_names().len() {
+                row.push(
+                    old_values
+                        .as_ref()
+                        .map_or(String::new(), |v| v[idx].clone()),
+                );
+                row.push(
+                    new_values
+                        .as_ref()
+                        .map_or(String::new(), |v| v[idx].clone()),
+                );
+            }
+            table.push(row);
+        }
+
+        table
+    }
+
+    /// The changed entities with the metrics among `metrics` that got worse, and the value of
+    /// each metric before and after the change along with its delta, for reviews
+    pub fn get_review_table(&self, metrics: &[String]) -> Vec<Vec<String>> {
+        let mut header: Vec<String> = [
+            "status",
+            "regressions",
+            "language",
+            "file_path",
+            "node_type",
+            "old_node_name",
+            "new_node_name",
+            "old_start_row",
+            "new_start_row",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        for name in metrics {
+            header.push(format!("old_{}", name));
+            header.push(format!("new_{}", name));
+            header.push(format!("delta_{}", name));
+        }
+        let mut table = vec![header];
+
+        let names = CodeMetric::get_names();
+        for diff in &self.diffs {
+            let block = diff.get_block();
+            let mut row = vec![
+                diff.status.to_string(),
+                diff.get_regressions(metrics).join(","),
+                block.meta_data.language.to_string(),
+                block.meta_data.file_path.clone(),
+                block.meta_data.node_type.clone(),
+                diff.old
+                    .as_ref()
+                    .map_or(String::new(), |b| b.meta_data.node_name.clone()),
+                diff.new
+                    .as_ref()
+                    .map_or(String::new(), |b| b.meta_data.node_name.clone()),
+                diff.old
+                    .as_ref()
+                    .map_or(String::new(), |b| b.meta_data.start_row.to_string()),
+                diff.new
+                    .as_ref()
+                    .map_or(String::new(), |b| b.meta_data.start_row.to_string()),
+            ];
+            let old_values = diff.old.as_ref().map(|b| b.metric.get_values());
+            let new_values = diff.new.as_ref().map(|b| b.metric.get_values());
+            for name in metrics {
+                let Some(idx) = names.iter().position(|n| n == name) else {
+                    continue;
+                };
+                let old_value = diff.old.as_ref().and_then(|b| b.metric.get_value(name));
+                let new_value = diff.new.as_ref().and_then(|b| b.metric.get_value(name));
+                // Rounded, for the deltas of the Halstead metrics not to show float noise
+                let delta = old_value
+                    .zip(new_value)
+                    .map(|(old_value, new_value)| ((new_value - old_value) * 100.0).round() / 100.0)
+                    .map(|delta| delta.to_string())
+                    .unwrap_or_default();
+                row.push(
+                    old_values
+                        .as_ref()
+                        .map_or(String::new(), |v| v[idx].clone()),
+                );
+                row.push(
+                    new_values
+                        .as_ref()
+                        .map_or(String::new(), |v| v[idx].clone()),
+                );
+                row.push(delta);
+            }
+            table.push(row);
+        }
+
+        table
+    }
+}
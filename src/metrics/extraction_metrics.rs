@@ -0,0 +1,60 @@
+use crate::metrics::code_metrics::{CodeMetricBlock, CodeMetrics, EntityKind};
+use crate::metrics::MetricsFilter;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// A run of consecutive statements of a long method that could be extracted into a method of
+/// its own, as its variables are mostly declared and used within it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExtractCandidate {
+    /// The first line of the statements, 1-based
+    pub start_row: u32,
+    /// The last line of the statements, 1-based
+    pub end_row: u32,
+    /// The variables declared before the statements and used in them, the parameters of the
+    /// extracted method
+    pub inputs: Vec<String>,
+    /// The variable assigned in the statements and used after them, the return value of the
+    /// extracted method if any
+    pub outputs: Vec<String>,
+    /// The share of the variables of the statements that are neither inputs nor outputs, from
+    /// 0 to 1
+    pub cohesion: f64,
+}
+
+/// A long method along with the statements worth extracting from it
+#[derive(Serialize)]
+pub struct ExtractSuggestion<'a> {
+    pub file_path: &'a str,
+    pub node_name: &'a str,
+    pub start_row: u32,
+    pub end_row: u32,
+    pub aloc: u32,
+    pub candidates: &'a [ExtractCandidate],
+}
+
+/// The extract-method candidates of the long methods kept by the filter, from the longest method.
+pub struct ExtractSuggestions<'a> {
+    pub suggestions: Vec<ExtractSuggestion<'a>>,
+}
+
+impl<'a> ExtractSuggestions<'a> {
+    pub fn new(metrics: &'a CodeMetrics, filter: &MetricsFilter) -> Self {
+        let mut suggestions: Vec<ExtractSuggestion> = metrics
+            .metric_blocks
+            .iter()
+            .filter(|block| block.meta_data.node_kind == EntityKind::Method)
+            .filter(|block| !block.extract_candidates.is_empty() && filter.matches(block))
+            .map(|block: &CodeMetricBlock| ExtractSuggestion {
+                file_path: &block.meta_data.file_path,
+                node_name: &block.meta_data.node_name,
+                start_row: block.meta_data.start_row,
+                end_row: block.meta_data.end_row,
+                aloc: block.metric.aloc,
+                candidates: &block.extract_candidates,
+            })
+            .collect();
+        suggestions.sort_by_key(|suggestion| Reverse(suggestion.aloc));
+        ExtractSuggestions { suggestions }
+    }
+}
@@ -0,0 +1,85 @@
+use super::code_metrics::get_node_group;
+use crate::config::Language;
+use crate::ts::Node;
+use std::collections::HashMap;
+
+/// Closing delimiters, counted once along with their opening counterpart.
+const CLOSING_DELIMITERS: &[&str] = &[")", "]", "}"];
+
+/// Operators and operands of a node, as counted by Halstead.
+///
+/// Operands are the identifiers and literals of the node, operators are the rest of its tokens
+/// (keywords, punctuation and operators). Comments are ignored.
+#[derive(Default)]
+pub struct HalsteadMetrics {
+    operators: HashMap<String, u32>,
+    operands: HashMap<String, u32>,
+}
+
+impl HalsteadMetrics {
+    /// Collect the operators and operands of the node and all its descendants
+    pub fn from_node(node: &Node, source_code: &str, language: Language) -> Self {
+        let literal_nodes = get_node_group(language, "halstead_literal_nodes");
+        let mut halstead_metrics = HalsteadMetrics::default();
+        halstead_metrics.collect(*node, source_code, &literal_nodes);
+        halstead_metrics
+    }
+
+    fn collect(&mut self, node: Node, source_code: &str, literal_nodes: &[String]) {
+        let node_kind = node.kind();
+        if node.is_missing() || node_kind.contains("comment") {
+            return;
+        }
+
+        // Literals made of several tokens, e.g. strings, are a single operand
+        let is_literal = literal_nodes.iter().any(|kind| kind == node_kind);
+        if is_literal || node.child_count() == 0 {
+            let text = &source_code[node.start_byte()..node.end_byte()];
+            if is_literal || node.is_named() {
+                *self.operands.entry(text.to_string()).or_insert(0) += 1;
+            } else if !CLOSING_DELIMITERS.contains(&node_kind) {
+                *self.operators.entry(node_kind.to_string()).or_insert(0) += 1;
+            }
+            return;
+        }
+
+        for i in 0..node.child_count() {
+            if let Some(child) = node.child(i) {
+                self.collect(child, source_code, literal_nodes);
+            }
+        }
+    }
+
+    /// The number of distinct operators and operands
+    pub fn vocabulary(&self) -> u32 {
+        (self.operators.len() + self.operands.len()) as u32
+    }
+
+    /// The total number of operators and operands
+    pub fn length(&self) -> u32 {
+        self.operators.values().sum::<u32>() + self.operands.values().sum::<u32>()
+    }
+
+    /// The volume, i.e. `length * log2(vocabulary)`
+    pub fn volume(&self) -> f64 {
+        let vocabulary = self.vocabulary();
+        if vocabulary == 0 {
+            return 0.0;
+        }
+        self.length() as f64 * (vocabulary as f64).log2()
+    }
+
+    /// The difficulty, i.e. `distinct_operators / 2 * total_operands / distinct_operands`
+    pub fn difficulty(&self) -> f64 {
+        if self.operands.is_empty() {
+            return 0.0;
+        }
+        let total_operands: u32 = self.operands.values().sum();
+        self.operators.len() as f64 / 2.0 * total_operands as f64 / self.operands.len() as f64
+    }
+
+    /// The effort, i.e. `difficulty * volume`
+    pub fn effort(&self) -> f64 {
+        self.difficulty() * self.volume()
+    }
+}
@@ -0,0 +1,68 @@
+use crate::metrics::code_metrics::CodeMetric;
+use crate::metrics::CodeMetricsMap;
+
+/// The metrics of the entities at every analyzed commit in long format, one row per commit,
+/// entity and metric, to chart how an entity evolved, e.g. the `cc` of a method. Entities are
+/// told apart by their `entity_id`, which stays the same when they move within their file.
+pub struct EntityHistory<'a> {
+    metrics_map: &'a CodeMetricsMap,
+    commit_ids: Vec<&'a str>,
+}
+
+impl<'a> EntityHistory<'a> {
+    /// The history of the given commits, in their order
+    pub fn new(metrics_map: &'a CodeMetricsMap, commit_ids: &'a [String]) -> Self {
+        EntityHistory {
+            metrics_map,
+            commit_ids: commit_ids.iter().map(|id| id.as_str()).collect(),
+        }
+    }
+
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![[
+            "commit_id",
+            "entity_id",
+            "qualified_name",
+            "metric",
+            "value",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>()];
+
+        let filter = &self.metrics_map.filter;
+        let metric_names = CodeMetric::get_names();
+        for commit_id in &self.commit_ids {
+            let Some(metrics) = self.metrics_map.get_metrics(&commit_id.to_string()) else {
+                continue;
+            };
+            for block in metrics
+                .metric_blocks
+                .iter()
+                .filter(|block| filter.matches(block))
+            {
+                let values = metric_names
+                    .iter()
+                    .zip(block.metric.get_values())
+                    .filter(|(name, _)| filter.metrics.is_empty() || filter.metrics.contains(name))
+                    .map(|(name, value)| (name.clone(), value));
+                // The custom query columns are always kept, as in the metrics tables
+                let custom_values = block
+                    .custom
+                    .iter()
+                    .map(|(name, count)| (name.clone(), count.to_string()));
+                for (metric, value) in values.chain(custom_values) {
+                    table.push(vec![
+                        commit_id.to_string(),
+                        block.meta_data.entity_id.clone(),
+                        block.meta_data.qualified_name.clone(),
+                        metric,
+                        value,
+                    ]);
+                }
+            }
+        }
+
+        table
+    }
+}
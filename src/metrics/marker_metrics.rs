@@ -0,0 +1,80 @@
+use crate::metrics::code_metrics::{CodeMetrics, EntityKind};
+use serde::{Deserialize, Serialize};
+
+/// The markers of the comments flagging unfinished work, counted by default
+pub const DEFAULT_MARKERS: &[&str] = &["TODO", "FIXME", "HACK", "XXX"];
+
+/// A line of a comment flagging unfinished work, e.g. `// TODO: handle timeouts`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MarkerComment {
+    /// The marker found, e.g. `TODO`
+    pub marker: String,
+    /// The line of the marker, 1-based
+    pub row: u32,
+    /// The text of the comment from the marker to the end of its line
+    pub text: String,
+}
+
+/// Find the first of the markers in a line of a comment, as a whole word, and return it with
+/// the text following it, e.g. `TODO: handle timeouts` for `// TODO: handle timeouts`
+pub fn find_marker<'a>(line: &'a str, markers: &[String]) -> Option<(&'a str, &'a str)> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut found: Option<(usize, &str)> = None;
+    for marker in markers {
+        let occurrence = line.match_indices(marker.as_str()).find(|(start, _)| {
+            let end = start + marker.len();
+            !line[..*start].ends_with(is_word_char) && !line[end..].starts_with(is_word_char)
+        });
+        if let Some((start, matched)) = occurrence {
+            if found.is_none_or(|(first, _)| start < first) {
+                found = Some((start, matched));
+            }
+        }
+    }
+    found.map(|(start, marker)| {
+        let text = line[start..].trim_end();
+        // The end of a block comment on the same line
+        let text = text.strip_suffix("*/").unwrap_or(text).trim_end();
+        (marker, text)
+    })
+}
+
+/// The comments flagging unfinished work in the analyzed files, one row per marker line.
+pub struct MarkerReport {
+    rows: Vec<(String, MarkerComment)>,
+}
+
+impl MarkerReport {
+    pub fn new(metrics: &CodeMetrics) -> Self {
+        let rows = metrics
+            .metric_blocks
+            .iter()
+            .filter(|block| block.meta_data.node_kind == EntityKind::File)
+            .flat_map(|block| {
+                block
+                    .markers
+                    .iter()
+                    .map(|marker| (block.meta_data.file_path.clone(), marker.clone()))
+            })
+            .collect();
+        MarkerReport { rows }
+    }
+
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![["file_path", "row", "marker", "text"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>()];
+
+        for (file_path, marker) in &self.rows {
+            table.push(vec![
+                file_path.clone(),
+                marker.row.to_string(),
+                marker.marker.clone(),
+                marker.text.clone(),
+            ]);
+        }
+
+        table
+    }
+}
@@ -1,19 +1,119 @@
+mod activity_metrics;
+mod budget_metrics;
+mod change_metrics;
+mod churn_metrics;
 mod code_metrics;
-pub use code_metrics::CodeMetrics;
-use std::collections::{hash_map::Iter as HashMapIter, HashMap};
+mod commit_metrics;
+mod diff_metrics;
+mod extraction_metrics;
+mod halstead_metrics;
+mod history_metrics;
+mod marker_metrics;
+mod naming_metrics;
+mod ownership_metrics;
+mod package_metrics;
+mod quality_metrics;
+mod robustness_metrics;
+mod similarity_metrics;
+mod summary_metrics;
+mod threshold_metrics;
+use crate::utils::parquet::ColumnType;
+pub use activity_metrics::{ActivityBlock, ActivityMetrics};
+pub use budget_metrics::{BudgetCheck, BudgetReport, BudgetSnapshot, BudgetStatus};
+pub use change_metrics::{ChangeType, CodeChange, CodeChanges};
+pub use churn_metrics::{ChurnMetrics, FileChurn};
+pub use code_metrics::{
+    find_node_group, inherit_entity_ids, CodeMetaData, CodeMetric, CodeMetricBlock, CodeMetrics,
+    EntityKind, NODE_GROUPS,
+};
+pub use commit_metrics::{get_issue_refs, CommitInfo, CommitInfos};
+pub use diff_metrics::{DiffStatus, EntityDiff, EntityDiffs};
+pub use extraction_metrics::{ExtractCandidate, ExtractSuggestion, ExtractSuggestions};
+pub use halstead_metrics::HalsteadMetrics;
+pub use history_metrics::EntityHistory;
+pub use marker_metrics::{find_marker, MarkerComment, MarkerReport, DEFAULT_MARKERS};
+pub use naming_metrics::{NamingReport, NamingStats, NamingStyle};
+pub use ownership_metrics::{FileOwnership, OwnershipReport, RISKY_MIN_CC, SIGNIFICANT_SHARE};
+pub use package_metrics::{PackageBlock, PackageMetrics, DEFAULT_PACKAGE};
+pub use quality_metrics::{get_grade, QualityScore, QualityScores, ScoreComponents, ScoreWeights};
+pub use robustness_metrics::{RiskyConstruct, RiskyConstructKind, RobustnessReport};
+use serde::Serialize;
+use serde_json::{Map, Value};
+pub use similarity_metrics::{
+    estimate_similarity, get_minhash, NearDuplicate, NearDuplicates, SIGNATURE_SIZE,
+};
+use std::collections::{hash_map::Iter as HashMapIter, BTreeSet, HashMap};
+pub use summary_metrics::{LanguageSummary, ProjectSummary};
+pub use threshold_metrics::ThresholdViolation;
+
+/// Which entities are kept in the metrics tables
+#[derive(Clone, Default)]
+pub struct MetricsFilter {
+    /// Node types to keep, e.g. `class_declaration`. All are kept when empty.
+    pub node_types: Vec<String>,
+    /// Minimum number of actual lines of code
+    pub min_aloc: u32,
+    /// Leave out boilerplate methods, e.g. trivial getters and setters
+    pub exclude_boilerplate: bool,
+    /// Metric columns to keep, e.g. `cc`. All are kept when empty.
+    pub metrics: Vec<String>,
+}
+
+impl MetricsFilter {
+    pub fn new() -> MetricsFilter {
+        MetricsFilter::default()
+    }
+
+    pub fn matches(&self, block: &CodeMetricBlock) -> bool {
+        let node_type_matches =
+            self.node_types.is_empty() || self.node_types.contains(&block.meta_data.node_type);
+        node_type_matches
+            && block.metric.aloc >= self.min_aloc
+            && !(self.exclude_boilerplate && block.metric.is_boilerplate)
+    }
+
+    /// Keep the enabled metric columns of a row, the metadata and custom query columns are always
+    /// kept
+    fn select_columns(&self, row: Vec<String>) -> Vec<String> {
+        if self.metrics.is_empty() {
+            return row;
+        }
+
+        let meta_data_count = CodeMetaData::get_names().len();
+        let metric_names = CodeMetric::get_names();
+        row.into_iter()
+            .enumerate()
+            .filter(|(i, _)| {
+                *i < meta_data_count
+                    || *i >= meta_data_count + metric_names.len()
+                    || self.metrics.contains(&metric_names[i - meta_data_count])
+            })
+            .map(|(_, value)| value)
+            .collect()
+    }
+}
 
 pub struct CodeMetricsMap {
     pub metrics: HashMap<String, CodeMetrics>,
+    /// Applied when building the tables, the metrics themselves are kept whole
+    pub filter: MetricsFilter,
+}
+
+impl Default for CodeMetricsMap {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CodeMetricsMap {
     pub fn new() -> CodeMetricsMap {
         CodeMetricsMap {
             metrics: HashMap::new(),
+            filter: MetricsFilter::new(),
         }
     }
 
-    pub fn iter(&self) -> HashMapIter<String, CodeMetrics> {
+    pub fn iter(&self) -> HashMapIter<'_, String, CodeMetrics> {
         self.metrics.iter()
     }
 
@@ -30,64 +130,181 @@ impl CodeMetricsMap {
     }
 
     pub fn get_default_metrics(&self) -> Option<&CodeMetrics> {
-        self.metrics.values().next()
+        self.metrics.get("default")
+    }
+
+    pub fn get_default_metrics_mut(&mut self) -> Option<&mut CodeMetrics> {
+        self.metrics.get_mut("default")
+    }
+
+    /// Total the default metrics of the project, e.g. its files and the complexity of its methods
+    pub fn get_summary(&self) -> Option<ProjectSummary> {
+        self.get_default_metrics().map(ProjectSummary::new)
+    }
+
+    /// Compare the metrics saved under two keys, e.g. two commits
+    pub fn compare(&self, old_key: &str, new_key: &str) -> Option<EntityDiffs> {
+        let old_metrics = self.get_metrics(&old_key.to_string())?;
+        let new_metrics = self.get_metrics(&new_key.to_string())?;
+        Some(EntityDiffs::compare(old_metrics, new_metrics))
     }
 
     pub fn get_table(&self, name: Option<&str>) -> Vec<Vec<String>> {
+        let mut table = Vec::new();
+        // Add header row
+        let mut header = CodeMetaData::get_names();
+        header.extend(CodeMetric::get_names());
+        let custom_names = self.get_custom_names(name);
+        header.extend(custom_names.iter().cloned());
+        table.push(self.filter.select_columns(header));
+
+        for block in self.get_filtered_blocks(name) {
+            let mut row = block.meta_data.get_values();
+            row.extend(block.metric.get_values());
+            // Left empty for the languages the custom query is not valid for
+            row.extend(custom_names.iter().map(|custom_name| {
+                block
+                    .custom
+                    .get(custom_name)
+                    .map(|count| count.to_string())
+                    .unwrap_or_default()
+            }));
+            table.push(self.filter.select_columns(row));
+        }
+
+        table
+    }
+
+    /// The rows of `get_table` as typed records, with numbers as numbers rather than strings
+    pub fn get_records(&self, name: Option<&str>) -> Vec<Value> {
+        let custom_names = self.get_custom_names(name);
+        self.get_filtered_blocks(name)
+            .map(|block| {
+                let mut record = to_record(&block.meta_data);
+                record.extend(to_record(&block.metric).into_iter().filter(|(name, _)| {
+                    self.filter.metrics.is_empty() || self.filter.metrics.contains(name)
+                }));
+                for custom_name in &custom_names {
+                    let count = block.custom.get(custom_name).copied();
+                    record.insert(custom_name.clone(), count.into());
+                }
+                Value::Object(record)
+            })
+            .collect()
+    }
+
+    /// The types of the columns of a table of `get_table`, its provenance included, the same
+    /// for every commit whatever the values
+    pub fn get_column_types(&self, name: Option<&str>, header: &[String]) -> Vec<ColumnType> {
+        let metric_record = to_record(&CodeMetric::default());
+        let custom_names = self.get_custom_names(name);
+        header
+            .iter()
+            .map(|column| match column.as_str() {
+                "start_row" | "start_col" | "end_row" | "end_col" => ColumnType::Int64,
+                column if custom_names.contains(column) => ColumnType::Int64,
+                column => metric_record
+                    .get(column)
+                    .map_or(ColumnType::String, ColumnType::from_value),
+            })
+            .collect()
+    }
+
+    /// The names of the custom queries counted in the blocks saved under the given key
+    fn get_custom_names(&self, name: Option<&str>) -> BTreeSet<String> {
+        self.get_filtered_blocks(name)
+            .flat_map(|block| block.custom.keys().cloned())
+            .collect()
+    }
+
+    /// The blocks saved under the given key, or the default ones, kept by the filter
+    fn get_filtered_blocks(&self, name: Option<&str>) -> impl Iterator<Item = &CodeMetricBlock> {
+        let metrics = if let Some(name) = name {
+            self.get_metrics(&name.to_string())
+        } else {
+            self.get_default_metrics()
+        };
+        metrics
+            .into_iter()
+            .flat_map(|metrics| &metrics.metric_blocks)
+            .filter(|block| self.filter.matches(block))
+    }
+}
+
+fn to_record<T: Serialize>(value: &T) -> Map<String, Value> {
+    match serde_json::to_value(value) {
+        Ok(Value::Object(record)) => record,
+        _ => Map::new(),
+    }
+}
+
+pub struct CodeChangesMap {
+    pub changes: HashMap<String, CodeChanges>,
+}
+
+impl Default for CodeChangesMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CodeChangesMap {
+    pub fn new() -> CodeChangesMap {
+        CodeChangesMap {
+            changes: HashMap::new(),
+        }
+    }
+
+    pub fn iter(&self) -> HashMapIter<'_, String, CodeChanges> {
+        self.changes.iter()
+    }
+
+    pub fn add_changes(&mut self, commit_id: String, changes: CodeChanges) {
+        self.changes.insert(commit_id, changes);
+    }
+
+    pub fn get_changes(&self, commit_id: &String) -> Option<&CodeChanges> {
+        self.changes.get(commit_id)
+    }
+
+    pub fn get_table(&self, name: &str) -> Vec<Vec<String>> {
         let mut table = Vec::new();
         // Add header row
         table.push(vec![
-            "language".to_string(),
             "file_path".to_string(),
             "start_row".to_string(),
-            "start_col".to_string(),
             "end_row".to_string(),
-            "end_col".to_string(),
             "node_name".to_string(),
             "node_type".to_string(),
-            "is_broken".to_string(),
-            "aloc".to_string(),
-            "eloc".to_string(),
-            "cloc".to_string(),
-            "dcloc".to_string(),
-            "noi".to_string(),
-            "noc".to_string(),
-            "nom".to_string(),
-            "cc".to_string(),
-            "pc".to_string(),
+            "change_type".to_string(),
+            "changed_lines".to_string(),
         ]);
 
-        let metrics = if let Some(name) = name {
-            self.get_metrics(&name.to_string())
-        } else {
-            self.get_default_metrics()
-        };
-
-        if let Some(metrics) = metrics {
-            for block in &metrics.metric_blocks {
+        if let Some(changes) = self.get_changes(&name.to_string()) {
+            for change in &changes.changes {
                 table.push(vec![
-                    block.meta_data.language.to_string(),
-                    block.meta_data.file_path.clone(),
-                    block.meta_data.start_row.to_string(),
-                    block.meta_data.start_col.to_string(),
-                    block.meta_data.end_row.to_string(),
-                    block.meta_data.end_col.to_string(),
-                    block.meta_data.node_name.clone(),
-                    block.meta_data.node_type.clone(),
-                    block.metric.is_broken.to_string(),
-                    block.metric.aloc.to_string(),
-                    block.metric.eloc.to_string(),
-                    block.metric.cloc.to_string(),
-                    block.metric.dcloc.to_string(),
-                    block.metric.noi.to_string(),
-                    block.metric.noc.to_string(),
-                    block.metric.nom.to_string(),
-                    block.metric.cc.to_string(),
-                    block.metric.pc.to_string(),
+                    change.file_path.clone(),
+                    change.start_row.to_string(),
+                    change.end_row.to_string(),
+                    change.node_name.clone(),
+                    change.node_type.clone(),
+                    change.change_type.to_string(),
+                    change.changed_lines.to_string(),
                 ]);
             }
         }
 
         table
     }
+
+    /// The types of the columns of a table of `get_table`, its provenance included
+    pub fn get_column_types(header: &[String]) -> Vec<ColumnType> {
+        header
+            .iter()
+            .map(|column| match column.as_str() {
+                "start_row" | "end_row" | "changed_lines" => ColumnType::Int64,
+                _ => ColumnType::String,
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,175 @@
+use crate::config::Language;
+use crate::languages::get_profile;
+use crate::metrics::code_metrics::{CodeMetaData, CodeMetrics};
+use crate::metrics::MetricsFilter;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// A casing convention of identifiers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NamingStyle {
+    /// e.g. `ShoppingCart`
+    PascalCase,
+    /// e.g. `addItem`
+    CamelCase,
+    /// e.g. `add_item`
+    SnakeCase,
+    /// e.g. `MAX_ITEMS`
+    UpperSnakeCase,
+}
+
+impl Display for NamingStyle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            NamingStyle::PascalCase => "pascal_case",
+            NamingStyle::CamelCase => "camel_case",
+            NamingStyle::SnakeCase => "snake_case",
+            NamingStyle::UpperSnakeCase => "upper_snake_case",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl NamingStyle {
+    /// The convention of the names of an entity of the given node type, if the language has one
+    pub fn for_node_type(language: Language, node_type: &str) -> Option<NamingStyle> {
+        match (language, node_type) {
+            (
+                _,
+                "class_declaration"
+                | "abstract_class_declaration"
+                | "class"
+                | "class_definition"
+                | "struct_item"
+                | "enum_item"
+                | "union_item"
+                | "trait_item",
+            ) => Some(NamingStyle::PascalCase),
+            _ => get_profile(language).naming_style(node_type),
+        }
+    }
+
+    /// Check if a name follows the convention, leading and trailing underscores aside, e.g. for
+    /// private members or Python's `__init__`. Names which are not identifiers, e.g.
+    /// `run$lambda@L42`, are left unchecked.
+    pub fn matches(&self, name: &str) -> Option<bool> {
+        if !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+        let name = name.trim_matches('_');
+        let Some(first) = name.chars().next() else {
+            return Some(true);
+        };
+        let matches = match self {
+            NamingStyle::PascalCase => first.is_uppercase() && !name.contains('_'),
+            NamingStyle::CamelCase => first.is_lowercase() && !name.contains('_'),
+            NamingStyle::SnakeCase => !name.chars().any(|c| c.is_uppercase()),
+            NamingStyle::UpperSnakeCase => !name.chars().any(|c| c.is_lowercase()),
+        };
+        Some(matches)
+    }
+}
+
+/// The identifiers an entity declares, its variables, parameters and constants
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NamingStats {
+    /// The number of variables, parameters and constants declared
+    pub identifiers: u32,
+    /// The sum of their lengths, in characters
+    pub identifier_length_sum: u32,
+    pub max_identifier_length: u32,
+    /// The number of identifiers of a single letter, e.g. `x`, `_` aside
+    pub single_letter_identifiers: u32,
+    /// The constants not in upper snake case, e.g. `maxItems` for a Java `static final` field
+    pub nonconforming_constants: Vec<String>,
+}
+
+impl NamingStats {
+    /// Count a declared identifier, checking its casing if it is a constant
+    pub fn add_identifier(&mut self, name: &str, is_constant: bool) {
+        let length = name.chars().count() as u32;
+        self.identifiers += 1;
+        self.identifier_length_sum += length;
+        self.max_identifier_length = self.max_identifier_length.max(length);
+        if length == 1 && name != "_" {
+            self.single_letter_identifiers += 1;
+        }
+        if is_constant && NamingStyle::UpperSnakeCase.matches(name) == Some(false) {
+            self.nonconforming_constants.push(name.to_string());
+        }
+    }
+
+    pub fn get_mean_identifier_length(&self) -> f64 {
+        if self.identifiers == 0 {
+            return 0.0;
+        }
+        self.identifier_length_sum as f64 / self.identifiers as f64
+    }
+}
+
+/// The naming conventions of the entities kept by the metrics filter
+pub struct NamingReport<'a> {
+    metrics: &'a CodeMetrics,
+    filter: &'a MetricsFilter,
+}
+
+impl<'a> NamingReport<'a> {
+    pub fn new(metrics: &'a CodeMetrics, filter: &'a MetricsFilter) -> Self {
+        NamingReport { metrics, filter }
+    }
+
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![[
+            "language",
+            "file_path",
+            "start_row",
+            "node_name",
+            "node_type",
+            "name_style",
+            "name_ok",
+            "nonconforming_constants",
+            "identifiers",
+            "mean_identifier_length",
+            "max_identifier_length",
+            "single_letter_identifiers",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>()];
+
+        for block in self
+            .metrics
+            .metric_blocks
+            .iter()
+            .filter(|block| self.filter.matches(block))
+        {
+            let CodeMetaData {
+                language,
+                file_path,
+                start_row,
+                node_name,
+                node_type,
+                ..
+            } = &block.meta_data;
+            let style = NamingStyle::for_node_type(*language, node_type);
+            let name_ok = style.and_then(|style| style.matches(node_name));
+            let naming = &block.naming;
+            table.push(vec![
+                language.to_string(),
+                file_path.clone(),
+                start_row.to_string(),
+                node_name.clone(),
+                node_type.clone(),
+                style.map(|style| style.to_string()).unwrap_or_default(),
+                name_ok.map(|ok| ok.to_string()).unwrap_or_default(),
+                naming.nonconforming_constants.join(","),
+                naming.identifiers.to_string(),
+                format!("{:.2}", naming.get_mean_identifier_length()),
+                naming.max_identifier_length.to_string(),
+                naming.single_letter_identifiers.to_string(),
+            ]);
+        }
+
+        table
+    }
+}
@@ -0,0 +1,168 @@
+use crate::metrics::code_metrics::{CodeMetricBlock, EntityKind};
+use std::collections::HashMap;
+
+/// The share of the lines of a file an author must have written to be a significant contributor
+pub const SIGNIFICANT_SHARE: f64 = 0.1;
+/// The cyclomatic complexity from which a file with a single significant contributor is risky
+pub const RISKY_MIN_CC: u32 = 10;
+
+/// Who wrote the lines of a file, from blame
+pub struct FileOwnership {
+    pub file_path: String,
+    /// The number of committed lines, those not committed yet being left out.
+    pub lines: u32,
+    /// The number of authors of the lines.
+    pub authors: u32,
+    /// The author of most of the lines, the first one in alphabetical order on a tie.
+    pub top_author: String,
+    /// The share of the lines written by the top author, from 0 to 1.
+    pub top_share: f64,
+    /// The number of authors of at least `SIGNIFICANT_SHARE` of the lines.
+    pub significant_authors: u32,
+    /// The fewest authors who wrote more than half of the lines together.
+    pub bus_factor: u32,
+    /// The cyclomatic complexity of the file.
+    pub cc: u32,
+    /// Whether the file is complex, by `RISKY_MIN_CC`, and known to a single significant author.
+    pub risky: bool,
+}
+
+/// How concentrated the knowledge of each file is among its authors, from blame.
+///
+/// A file mostly written by one author is lost knowledge when they leave, all the more so when
+/// it is complex.
+pub struct OwnershipReport {
+    pub files: Vec<FileOwnership>,
+    /// The number of lines written by each author over all the files
+    author_lines: HashMap<String, u32>,
+}
+
+impl Default for OwnershipReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OwnershipReport {
+    pub fn new() -> OwnershipReport {
+        OwnershipReport {
+            files: Vec::new(),
+            author_lines: HashMap::new(),
+        }
+    }
+
+    /// Add a file from its metric blocks and the author of each of its lines, if committed
+    pub fn add_file(&mut self, metric_blocks: &[CodeMetricBlock], line_authors: &[Option<String>]) {
+        let Some(first_block) = metric_blocks.first() else {
+            return;
+        };
+        let mut line_counts: HashMap<&str, u32> = HashMap::new();
+        for author in line_authors.iter().flatten() {
+            *line_counts.entry(author).or_default() += 1;
+            *self.author_lines.entry(author.clone()).or_default() += 1;
+        }
+        let counts = get_sorted_counts(line_counts);
+        let lines: u32 = counts.iter().map(|(_, count)| count).sum();
+        let share = |count: u32| count as f64 / lines.max(1) as f64;
+
+        let significant_authors = counts
+            .iter()
+            .filter(|(_, count)| share(*count) >= SIGNIFICANT_SHARE)
+            .count() as u32;
+        // Host files have a root block per sub-document
+        let cc = metric_blocks
+            .iter()
+            .filter(|block| block.meta_data.node_kind == EntityKind::File)
+            .map(|block| block.metric.cc)
+            .sum();
+        self.files.push(FileOwnership {
+            file_path: first_block.meta_data.file_path.clone(),
+            lines,
+            authors: counts.len() as u32,
+            top_author: counts
+                .first()
+                .map(|(author, _)| author.to_string())
+                .unwrap_or_default(),
+            top_share: counts.first().map_or(0.0, |(_, count)| share(*count)),
+            significant_authors,
+            bus_factor: get_bus_factor(&counts),
+            cc,
+            risky: significant_authors == 1 && cc >= RISKY_MIN_CC,
+        });
+    }
+
+    /// The fewest authors who wrote more than half of the lines of all the files together
+    pub fn get_bus_factor(&self) -> u32 {
+        let counts = get_sorted_counts(
+            self.author_lines
+                .iter()
+                .map(|(author, count)| (author.as_str(), *count))
+                .collect(),
+        );
+        get_bus_factor(&counts)
+    }
+
+    /// The files, the risky ones first, then from the most to the least concentrated and complex
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![[
+            "file_path",
+            "lines",
+            "authors",
+            "top_author",
+            "top_share",
+            "significant_authors",
+            "bus_factor",
+            "cc",
+            "risky",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>()];
+
+        let mut files: Vec<&FileOwnership> = self.files.iter().collect();
+        files.sort_by(|a, b| {
+            b.risky
+                .cmp(&a.risky)
+                .then_with(|| b.top_share.total_cmp(&a.top_share))
+                .then_with(|| b.cc.cmp(&a.cc))
+                .then_with(|| a.file_path.cmp(&b.file_path))
+        });
+        for file in files {
+            table.push(vec![
+                file.file_path.clone(),
+                file.lines.to_string(),
+                file.authors.to_string(),
+                file.top_author.clone(),
+                format!("{:.2}", file.top_share),
+                file.significant_authors.to_string(),
+                file.bus_factor.to_string(),
+                file.cc.to_string(),
+                file.risky.to_string(),
+            ]);
+        }
+
+        table
+    }
+}
+
+/// The authors with their number of lines, from the most to the fewest, then alphabetically
+fn get_sorted_counts(line_counts: HashMap<&str, u32>) -> Vec<(&str, u32)> {
+    let mut counts: Vec<(&str, u32)> = line_counts.into_iter().collect();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    counts
+}
+
+/// The fewest authors, given sorted, who wrote more than half of the lines together
+fn get_bus_factor(counts: &[(&str, u32)]) -> u32 {
+    let lines: u32 = counts.iter().map(|(_, count)| count).sum();
+    let mut covered = 0;
+    let mut bus_factor = 0;
+    for (_, count) in counts {
+        if covered * 2 > lines {
+            break;
+        }
+        covered += count;
+        bus_factor += 1;
+    }
+    bus_factor
+}
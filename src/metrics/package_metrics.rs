@@ -0,0 +1,157 @@
+use crate::config::Language;
+use crate::metrics::code_metrics::{CodeMetrics, EntityKind};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// The name of the package of the files outside of any, e.g. Java files without a package
+/// declaration or files right in the target directory
+pub const DEFAULT_PACKAGE: &str = "<default>";
+
+/// The file metrics of a package, summed over its files
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackageBlock {
+    pub language: Language,
+    pub package: String,
+    pub files: u32,
+    pub noc: u32,
+    pub nom: u32,
+    pub aloc: u32,
+    pub cc: u32,
+    /// The highest cyclomatic complexity of the methods of the package
+    pub max_cc: u32,
+}
+
+impl PackageBlock {
+    /// The mean of a sum over the files of the package
+    fn get_mean(&self, sum: u32) -> f64 {
+        if self.files == 0 {
+            return 0.0;
+        }
+        sum as f64 / self.files as f64
+    }
+}
+
+/// The metrics of the files rolled up to their packages: the declared package of Java files,
+/// e.g. `com.shop`, the dotted directories of Python files below the target, e.g. `app.models`,
+/// and the directories of the other files below the target, e.g. `src/cart`
+pub struct PackageMetrics {
+    pub packages: Vec<PackageBlock>,
+}
+
+impl PackageMetrics {
+    pub fn new(metrics: &CodeMetrics, target_path: &str) -> Self {
+        let mut file_packages: HashMap<&str, String> = HashMap::new();
+        let mut packages: BTreeMap<(String, String), PackageBlock> = BTreeMap::new();
+        for block in &metrics.metric_blocks {
+            let meta_data = &block.meta_data;
+            if meta_data.node_kind != EntityKind::File {
+                continue;
+            }
+            let package = match (&block.package, meta_data.language) {
+                (Some(package), _) => package.clone(),
+                (None, Language::Java) => DEFAULT_PACKAGE.to_string(),
+                (None, language) => {
+                    get_directory_package(&meta_data.file_path, target_path, language)
+                }
+            };
+            file_packages.insert(&meta_data.file_path, package.clone());
+
+            let package_block = packages
+                .entry((meta_data.language.to_string(), package.clone()))
+                .or_insert_with(|| PackageBlock {
+                    language: meta_data.language,
+                    package,
+                    files: 0,
+                    noc: 0,
+                    nom: 0,
+                    aloc: 0,
+                    cc: 0,
+                    max_cc: 0,
+                });
+            package_block.files += 1;
+            package_block.noc += block.metric.noc;
+            package_block.nom += block.metric.nom;
+            package_block.aloc += block.metric.aloc;
+            package_block.cc += block.metric.cc;
+        }
+
+        for block in &metrics.metric_blocks {
+            let meta_data = &block.meta_data;
+            if meta_data.node_kind != EntityKind::Method {
+                continue;
+            }
+            let Some(package) = file_packages.get(meta_data.file_path.as_str()) else {
+                continue;
+            };
+            let key = (meta_data.language.to_string(), package.clone());
+            if let Some(package_block) = packages.get_mut(&key) {
+                package_block.max_cc = package_block.max_cc.max(block.metric.cc);
+            }
+        }
+
+        PackageMetrics {
+            packages: packages.into_values().collect(),
+        }
+    }
+
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![[
+            "language",
+            "package",
+            "files",
+            "noc",
+            "nom",
+            "mean_nom",
+            "aloc",
+            "mean_aloc",
+            "cc",
+            "mean_cc",
+            "max_cc",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>()];
+
+        for block in &self.packages {
+            table.push(vec![
+                block.language.to_string(),
+                block.package.clone(),
+                block.files.to_string(),
+                block.noc.to_string(),
+                block.nom.to_string(),
+                format!("{:.2}", block.get_mean(block.nom)),
+                block.aloc.to_string(),
+                format!("{:.2}", block.get_mean(block.aloc)),
+                block.cc.to_string(),
+                format!("{:.2}", block.get_mean(block.cc)),
+                block.max_cc.to_string(),
+            ]);
+        }
+
+        table
+    }
+}
+
+/// The package of a file from its directory below the target, dotted for Python
+fn get_directory_package(file_path: &str, target_path: &str, language: Language) -> String {
+    let directory = Path::new(file_path)
+        .parent()
+        .and_then(|parent| parent.strip_prefix(target_path).ok());
+    let parts: Vec<String> = directory
+        .map(|directory| {
+            directory
+                .iter()
+                .map(|part| part.to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    if parts.is_empty() {
+        return DEFAULT_PACKAGE.to_string();
+    }
+    let separator = if language == Language::Python {
+        "."
+    } else {
+        "/"
+    };
+    parts.join(separator)
+}
@@ -0,0 +1,335 @@
+use crate::metrics::code_metrics::{CodeMetricBlock, CodeMetrics, EntityKind};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Methods shorter than this are too small to count as duplicates of each other
+const MIN_DUPLICATE_ALOC: u32 = 3;
+
+/// The weight of each component in the quality score, relative to the others
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoreWeights {
+    pub complexity: f64,
+    pub size: f64,
+    pub duplication: f64,
+    pub documentation: f64,
+    pub smells: f64,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        ScoreWeights {
+            complexity: 0.3,
+            size: 0.15,
+            duplication: 0.2,
+            documentation: 0.15,
+            smells: 0.2,
+        }
+    }
+}
+
+/// The components of the quality score, each from 0 (worst) to 100 (best)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScoreComponents {
+    /// From the average cyclomatic complexity of the methods
+    pub complexity: f64,
+    /// From the number of lines of the file
+    pub size: f64,
+    /// From the share of method lines duplicated elsewhere
+    pub duplication: f64,
+    /// From the share of comment lines
+    pub documentation: f64,
+    /// From the share of entities with a smell, e.g. long methods or large classes
+    pub smells: f64,
+}
+
+impl ScoreComponents {
+    fn get_values(&self) -> [f64; 5] {
+        [
+            self.complexity,
+            self.size,
+            self.duplication,
+            self.documentation,
+            self.smells,
+        ]
+    }
+
+    fn add_weighted(&mut self, other: &ScoreComponents, weight: f64) {
+        self.complexity += other.complexity * weight;
+        self.size += other.size * weight;
+        self.duplication += other.duplication * weight;
+        self.documentation += other.documentation * weight;
+        self.smells += other.smells * weight;
+    }
+
+    fn scaled(&self, factor: f64) -> ScoreComponents {
+        ScoreComponents {
+            complexity: self.complexity * factor,
+            size: self.size * factor,
+            duplication: self.duplication * factor,
+            documentation: self.documentation * factor,
+            smells: self.smells * factor,
+        }
+    }
+
+    /// The weighted average of the components
+    pub fn get_score(&self, weights: &ScoreWeights) -> f64 {
+        let weights = [
+            weights.complexity,
+            weights.size,
+            weights.duplication,
+            weights.documentation,
+            weights.smells,
+        ];
+        let total_weight: f64 = weights.iter().sum();
+        if total_weight <= 0.0 {
+            return 0.0;
+        }
+        let weighted_sum: f64 = weights
+            .iter()
+            .zip(self.get_values())
+            .map(|(weight, value)| weight * value)
+            .sum();
+        weighted_sum / total_weight
+    }
+}
+
+/// Get the letter grade of a score, from A (90 and above) to F (below 60)
+pub fn get_grade(score: f64) -> char {
+    match score {
+        s if s >= 90.0 => 'A',
+        s if s >= 80.0 => 'B',
+        s if s >= 70.0 => 'C',
+        s if s >= 60.0 => 'D',
+        _ => 'F',
+    }
+}
+
+/// The quality score of a file or of a directory
+pub struct QualityScore {
+    pub path: String,
+    pub is_directory: bool,
+    pub file_count: usize,
+    pub aloc: u32,
+    pub components: ScoreComponents,
+    pub score: f64,
+    pub grade: char,
+}
+
+/// Quality scores per file and per directory, for readers who don't need every metric.
+///
+/// Directory scores are averaged from the scores of all the files below them, weighted by their
+/// size, up to the target path.
+pub struct QualityScores {
+    pub scores: Vec<QualityScore>,
+}
+
+impl QualityScores {
+    pub fn new(metrics: &CodeMetrics, weights: &ScoreWeights, target_path: &str) -> Self {
+        let duplicated = get_duplicated_fingerprints(metrics);
+
+        let mut file_scores = Vec::new();
+        for file_blocks in metrics
+            .metric_blocks
+            .chunk_by(|a, b| a.meta_data.file_path == b.meta_data.file_path)
+        {
+            let path = file_blocks[0].meta_data.file_path.clone();
+            let aloc = file_blocks
+                .iter()
+                .filter(|block| block.meta_data.node_kind == EntityKind::File)
+                .map(|block| block.metric.aloc)
+                .sum();
+            let components = score_file(file_blocks, &duplicated);
+            file_scores.push(new_score(path, false, 1, aloc, components, weights));
+        }
+
+        // Average the components of the files below each directory, weighted by their size
+        let mut directories: BTreeMap<String, (usize, u32, f64, ScoreComponents)> = BTreeMap::new();
+        for file_score in &file_scores {
+            let weight = file_score.aloc.max(1) as f64;
+            for directory in get_directories(Path::new(&file_score.path), Path::new(target_path)) {
+                let (file_count, aloc, total_weight, components) = directories
+                    .entry(directory.to_string_lossy().to_string())
+                    .or_default();
+                *file_count += 1;
+                *aloc += file_score.aloc;
+                *total_weight += weight;
+                components.add_weighted(&file_score.components, weight);
+            }
+        }
+
+        let mut scores: Vec<QualityScore> = directories
+            .into_iter()
+            .map(|(path, (file_count, aloc, total_weight, components))| {
+                let components = components.scaled(1.0 / total_weight);
+                new_score(path, true, file_count, aloc, components, weights)
+            })
+            .collect();
+        scores.extend(file_scores);
+
+        QualityScores { scores }
+    }
+
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![[
+            "path",
+            "kind",
+            "files",
+            "aloc",
+            "complexity",
+            "size",
+            "duplication",
+            "documentation",
+            "smells",
+            "score",
+            "grade",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()];
+
+        for score in &self.scores {
+            let mut row = vec![
+                score.path.clone(),
+                if score.is_directory {
+                    "directory".to_string()
+                } else {
+                    "file".to_string()
+                },
+                score.file_count.to_string(),
+                score.aloc.to_string(),
+            ];
+            row.extend(
+                score
+                    .components
+                    .get_values()
+                    .iter()
+                    .map(|value| format!("{:.2}", value)),
+            );
+            row.push(format!("{:.2}", score.score));
+            row.push(score.grade.to_string());
+            table.push(row);
+        }
+
+        table
+    }
+}
+
+fn new_score(
+    path: String,
+    is_directory: bool,
+    file_count: usize,
+    aloc: u32,
+    components: ScoreComponents,
+    weights: &ScoreWeights,
+) -> QualityScore {
+    let score = components.get_score(weights);
+    QualityScore {
+        path,
+        is_directory,
+        file_count,
+        aloc,
+        components,
+        score,
+        grade: get_grade(score),
+    }
+}
+
+/// The directories of a file, from its parent up to the target path. Only its parent if the file
+/// is not below the target path, e.g. when the target is the file itself.
+fn get_directories<'a>(file_path: &'a Path, target_path: &Path) -> Vec<&'a Path> {
+    let directories: Vec<&Path> = file_path
+        .ancestors()
+        .skip(1)
+        .take_while(|directory| directory.starts_with(target_path))
+        .collect();
+    if !directories.is_empty() {
+        return directories;
+    }
+    file_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .into_iter()
+        .collect()
+}
+
+/// The fingerprints shared by several methods
+fn get_duplicated_fingerprints(metrics: &CodeMetrics) -> HashMap<u64, usize> {
+    let mut counts = HashMap::new();
+    for block in &metrics.metric_blocks {
+        if let Some(fingerprint) = block.fingerprint {
+            if block.metric.aloc >= MIN_DUPLICATE_ALOC {
+                *counts.entry(fingerprint).or_insert(0) += 1;
+            }
+        }
+    }
+    counts.retain(|_, count| *count > 1);
+    counts
+}
+
+fn score_file(
+    file_blocks: &[CodeMetricBlock],
+    duplicated: &HashMap<u64, usize>,
+) -> ScoreComponents {
+    let roots: Vec<&CodeMetricBlock> = file_blocks
+        .iter()
+        .filter(|block| block.meta_data.node_kind == EntityKind::File)
+        .collect();
+    let methods: Vec<&CodeMetricBlock> = file_blocks
+        .iter()
+        .filter(|block| block.meta_data.node_kind == EntityKind::Method)
+        .collect();
+    let classes = file_blocks
+        .iter()
+        .filter(|block| block.meta_data.node_kind == EntityKind::Class);
+
+    // Scripts without methods are as complex as their top-level code
+    let average_cc = if methods.is_empty() {
+        roots.iter().map(|block| block.metric.cc).max().unwrap_or(1) as f64
+    } else {
+        methods.iter().map(|block| block.metric.cc).sum::<u32>() as f64 / methods.len() as f64
+    };
+
+    let aloc: u32 = roots.iter().map(|block| block.metric.aloc).sum();
+    let eloc: u32 = roots.iter().map(|block| block.metric.eloc).sum();
+    let cloc: u32 = roots.iter().map(|block| block.metric.cloc).sum();
+    let comment_ratio = cloc as f64 / (aloc.saturating_sub(eloc)).max(1) as f64;
+
+    let method_aloc: u32 = methods.iter().map(|block| block.metric.aloc).sum();
+    let duplicated_aloc: u32 = methods
+        .iter()
+        .filter(|block| {
+            block
+                .fingerprint
+                .is_some_and(|fingerprint| duplicated.contains_key(&fingerprint))
+        })
+        .map(|block| block.metric.aloc)
+        .sum();
+    let duplicated_ratio = duplicated_aloc as f64 / method_aloc.max(1) as f64;
+
+    let smelly_methods = methods
+        .iter()
+        .filter(|block| block.metric.aloc > 50 || block.metric.cc > 10 || block.metric.pc > 5)
+        .count();
+    let classes: Vec<&CodeMetricBlock> = classes.collect();
+    let smelly_classes = classes
+        .iter()
+        .filter(|block| block.metric.nom > 20 || block.metric.aloc > 500)
+        .count();
+    let entity_count = (methods.len() + classes.len()).max(1);
+    let smell_ratio = (smelly_methods + smelly_classes) as f64 / entity_count as f64;
+
+    ScoreComponents {
+        complexity: scale_down(average_cc, 2.0, 15.0),
+        size: scale_down(aloc as f64, 300.0, 1500.0),
+        duplication: 100.0 * (1.0 - duplicated_ratio),
+        documentation: 100.0 * (comment_ratio / 0.2).min(1.0),
+        smells: 100.0 * (1.0 - smell_ratio),
+    }
+}
+
+/// 100 up to `good`, 0 from `bad`, and linear in between
+fn scale_down(value: f64, good: f64, bad: f64) -> f64 {
+    100.0 * ((bad - value) / (bad - good)).clamp(0.0, 1.0)
+}
@@ -0,0 +1,83 @@
+use crate::metrics::code_metrics::{CodeMetrics, EntityKind};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+
+/// A construct that hides failures or crashes on them, which reviews should look at first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum RiskyConstructKind {
+    /// A catch or except block doing nothing, comments aside, e.g. `catch (IOException e) {}`
+    EmptyCatch,
+    /// An `except:` catching everything, `SystemExit` and `KeyboardInterrupt` included
+    BareExcept,
+    /// A handler of the root of all errors, e.g. `catch (Throwable t)` or `except BaseException`
+    CatchAll,
+    /// A handler of `InterruptedException` neither restoring the interrupt nor rethrowing, or of
+    /// `KeyboardInterrupt` not raising again
+    SwallowedInterrupt,
+    /// A call panicking on failure, e.g. `unwrap()`, `expect()` or `panic!`
+    Panic,
+}
+
+impl Display for RiskyConstructKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            RiskyConstructKind::EmptyCatch => "empty_catch",
+            RiskyConstructKind::BareExcept => "bare_except",
+            RiskyConstructKind::CatchAll => "catch_all",
+            RiskyConstructKind::SwallowedInterrupt => "swallowed_interrupt",
+            RiskyConstructKind::Panic => "panic",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An occurrence of a risky construct in a file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RiskyConstruct {
+    pub kind: RiskyConstructKind,
+    /// The line of the construct, 1-based
+    pub row: u32,
+}
+
+/// The risky constructs of the analyzed files, counted per language.
+pub struct RobustnessReport {
+    /// The number of occurrences and the files they are in, by language and kind
+    counts: BTreeMap<(String, RiskyConstructKind), (usize, BTreeSet<String>)>,
+}
+
+impl RobustnessReport {
+    pub fn new(metrics: &CodeMetrics) -> Self {
+        let mut counts: BTreeMap<_, (usize, BTreeSet<String>)> = BTreeMap::new();
+        for block in &metrics.metric_blocks {
+            if block.meta_data.node_kind != EntityKind::File {
+                continue;
+            }
+            for construct in &block.risky_constructs {
+                let language = block.meta_data.language.to_string();
+                let (count, files) = counts.entry((language, construct.kind)).or_default();
+                *count += 1;
+                files.insert(block.meta_data.file_path.clone());
+            }
+        }
+        RobustnessReport { counts }
+    }
+
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![["language", "construct", "count", "files"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>()];
+
+        for ((language, kind), (count, files)) in &self.counts {
+            table.push(vec![
+                language.clone(),
+                kind.to_string(),
+                count.to_string(),
+                files.len().to_string(),
+            ]);
+        }
+
+        table
+    }
+}
@@ -0,0 +1,133 @@
+use crate::metrics::code_metrics::{CodeMetricBlock, CodeMetrics, EntityKind};
+use std::collections::{BTreeSet, HashMap};
+
+/// The number of hashes of a MinHash signature
+pub const SIGNATURE_SIZE: usize = 64;
+
+/// Signatures are split in bands of this many hashes, methods sharing a band being compared
+const BAND_SIZE: usize = 4;
+
+/// The MinHash signature of a set of shingles, each given by its hash.
+///
+/// The share of equal hashes between two signatures estimates the Jaccard similarity of their
+/// shingles, i.e. how much of the two methods is the same.
+pub fn get_minhash(shingle_hashes: impl Iterator<Item = u64>) -> Vec<u64> {
+    let mut signature = vec![u64::MAX; SIGNATURE_SIZE];
+    for shingle_hash in shingle_hashes {
+        for (i, min_hash) in signature.iter_mut().enumerate() {
+            // A different permutation of the hashes for each slot of the signature
+            let hash = mix(shingle_hash ^ mix(i as u64 + 1));
+            *min_hash = (*min_hash).min(hash);
+        }
+    }
+    signature
+}
+
+/// The estimated similarity of two signatures, from 0 to 1
+pub fn estimate_similarity(signature: &[u64], other: &[u64]) -> f64 {
+    let equal = signature.iter().zip(other).filter(|(a, b)| a == b).count();
+    equal as f64 / SIGNATURE_SIZE as f64
+}
+
+/// The finalizer of SplitMix64, spreading the bits of a value over the whole hash
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Two methods of different files sharing most of their tokens
+pub struct NearDuplicate<'a> {
+    pub method: &'a CodeMetricBlock,
+    pub duplicate: &'a CodeMetricBlock,
+    /// The estimated share of shingles the two methods have in common, from 0 to 1
+    pub similarity: f64,
+}
+
+/// The methods copied from another file then tweaked, which exact fingerprints miss.
+///
+/// Candidates are found by locality-sensitive hashing: methods are only compared when a band of
+/// their signatures is equal, which is all but certain for similar methods and rare otherwise.
+pub struct NearDuplicates<'a> {
+    pub pairs: Vec<NearDuplicate<'a>>,
+}
+
+impl<'a> NearDuplicates<'a> {
+    /// Find the pairs of methods at least `min_similarity` similar, from the most similar
+    pub fn new(metrics: &'a CodeMetrics, min_similarity: f64) -> Self {
+        let methods: Vec<(&CodeMetricBlock, &Vec<u64>)> = metrics
+            .metric_blocks
+            .iter()
+            .filter(|block| block.meta_data.node_kind == EntityKind::Method)
+            .filter_map(|block| block.minhash.as_ref().map(|minhash| (block, minhash)))
+            .collect();
+
+        let mut candidates = BTreeSet::new();
+        for band_start in (0..SIGNATURE_SIZE).step_by(BAND_SIZE) {
+            let mut buckets: HashMap<&[u64], Vec<usize>> = HashMap::new();
+            for (idx, (_, minhash)) in methods.iter().enumerate() {
+                buckets
+                    .entry(&minhash[band_start..band_start + BAND_SIZE])
+                    .or_default()
+                    .push(idx);
+            }
+            for bucket in buckets.values() {
+                for (i, first) in bucket.iter().enumerate() {
+                    for second in &bucket[i + 1..] {
+                        candidates.insert((*first, *second));
+                    }
+                }
+            }
+        }
+
+        let mut pairs: Vec<NearDuplicate> = candidates
+            .into_iter()
+            .filter_map(|(first, second)| {
+                let (method, method_minhash) = methods[first];
+                let (duplicate, duplicate_minhash) = methods[second];
+                if method.meta_data.file_path == duplicate.meta_data.file_path {
+                    return None;
+                }
+                let similarity = estimate_similarity(method_minhash, duplicate_minhash);
+                (similarity >= min_similarity).then_some(NearDuplicate {
+                    method,
+                    duplicate,
+                    similarity,
+                })
+            })
+            .collect();
+        pairs.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+
+        NearDuplicates { pairs }
+    }
+
+    pub fn get_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![[
+            "file_path",
+            "node_name",
+            "start_row",
+            "duplicate_file_path",
+            "duplicate_node_name",
+            "duplicate_start_row",
+            "similarity",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()];
+
+        for pair in &self.pairs {
+            table.push(vec![
+                pair.method.meta_data.file_path.clone(),
+                pair.method.meta_data.node_name.clone(),
+                pair.method.meta_data.start_row.to_string(),
+                pair.duplicate.meta_data.file_path.clone(),
+                pair.duplicate.meta_data.node_name.clone(),
+                pair.duplicate.meta_data.start_row.to_string(),
+                format!("{:.2}", pair.similarity),
+            ]);
+        }
+
+        table
+    }
+}
@@ -0,0 +1,91 @@
+use crate::metrics::code_metrics::{CodeMetrics, EntityKind};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Result};
+
+/// The size of the code of a language
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct LanguageSummary {
+    pub files: u32,
+    pub aloc: u32,
+    pub methods: u32,
+}
+
+/// The totals of a project, for dashboards not to aggregate the metrics tables themselves
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ProjectSummary {
+    pub files: u32,
+    pub classes: u32,
+    pub methods: u32,
+    pub aloc: u32,
+    /// The mean, median and maximum cyclomatic complexity of the methods
+    pub mean_cc: f64,
+    pub median_cc: f64,
+    pub max_cc: u32,
+    /// By language name, e.g. `Java`
+    pub languages: BTreeMap<String, LanguageSummary>,
+}
+
+impl ProjectSummary {
+    pub fn new(metrics: &CodeMetrics) -> Self {
+        let mut summary = ProjectSummary::default();
+        let mut method_ccs = Vec::new();
+        for block in &metrics.metric_blocks {
+            let language = summary
+                .languages
+                .entry(block.meta_data.language.to_string())
+                .or_default();
+            match block.meta_data.node_kind {
+                EntityKind::File => {
+                    summary.files += 1;
+                    summary.aloc += block.metric.aloc;
+                    language.files += 1;
+                    language.aloc += block.metric.aloc;
+                }
+                EntityKind::Class => summary.classes += 1,
+                EntityKind::Method => {
+                    summary.methods += 1;
+                    language.methods += 1;
+                    method_ccs.push(block.metric.cc);
+                }
+            }
+        }
+
+        method_ccs.sort_unstable();
+        if let Some(max_cc) = method_ccs.last() {
+            summary.max_cc = *max_cc;
+            summary.mean_cc =
+                method_ccs.iter().map(|cc| *cc as f64).sum::<f64>() / method_ccs.len() as f64;
+            let middle = method_ccs.len() / 2;
+            summary.median_cc = if method_ccs.len() % 2 == 0 {
+                (method_ccs[middle - 1] + method_ccs[middle]) as f64 / 2.0
+            } else {
+                method_ccs[middle] as f64
+            };
+        }
+        summary
+    }
+}
+
+impl Display for ProjectSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(
+            f,
+            "{} files, {} classes, {} methods, {} actual lines of code",
+            self.files, self.classes, self.methods, self.aloc
+        )?;
+        write!(
+            f,
+            "Method cc: mean {:.2}, median {:.1}, max {}",
+            self.mean_cc, self.median_cc, self.max_cc
+        )?;
+        for (name, language) in &self.languages {
+            write!(
+                f,
+                "\n  {}: {} files, {} methods, {} actual lines of code",
+                name, language.files, language.methods, language.aloc
+            )?;
+        }
+        Ok(())
+    }
+}
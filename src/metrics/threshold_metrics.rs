@@ -0,0 +1,62 @@
+use crate::metrics::code_metrics::{CodeMetaData, CodeMetrics};
+use crate::metrics::MetricsFilter;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Result};
+
+/// A metric of an entity exceeding its configured threshold
+pub struct ThresholdViolation {
+    pub meta_data: CodeMetaData,
+    pub metric: String,
+    pub value: f64,
+    pub threshold: f64,
+}
+
+impl Display for ThresholdViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "{}:{} {} ({}): {} = {} > {}",
+            self.meta_data.file_path,
+            self.meta_data.start_row + 1,
+            self.meta_data.node_name,
+            self.meta_data.node_type,
+            self.metric,
+            self.value,
+            self.threshold
+        )
+    }
+}
+
+impl ThresholdViolation {
+    /// Check the entities kept by the filter against the maximum value of each metric, except
+    /// the metrics suppressed for them
+    pub fn check(
+        metrics: &CodeMetrics,
+        filter: &MetricsFilter,
+        thresholds: &BTreeMap<String, f64>,
+    ) -> Vec<ThresholdViolation> {
+        let mut violations = Vec::new();
+        for block in metrics
+            .metric_blocks
+            .iter()
+            .filter(|block| filter.matches(block))
+        {
+            for (metric, threshold) in thresholds {
+                if block.meta_data.is_suppressed(metric) {
+                    continue;
+                }
+                if let Some(value) = block.metric.get_value(metric) {
+                    if value > *threshold {
+                        violations.push(ThresholdViolation {
+                            meta_data: block.meta_data.clone(),
+                            metric: metric.clone(),
+                            value,
+                            threshold: *threshold,
+                        });
+                    }
+                }
+            }
+        }
+        violations
+    }
+}
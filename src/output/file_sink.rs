@@ -0,0 +1,159 @@
+use super::OutputSink;
+use crate::error::XStatsError;
+use crate::utils::csv_dialect::CsvDialect;
+use crate::utils::parquet::{infer_column_types, save_to_parquet, ColumnType};
+use crate::utils::{
+    get_json_records, save_records_to_json, save_records_to_jsonl, save_records_to_yaml,
+    save_to_csv, save_to_json, save_to_xml, write_atomically,
+};
+use log::info;
+use std::io::Write;
+
+/// The formats tables can be saved in
+pub const OUTPUT_FORMATS: &[&str] = &["csv", "json", "jsonl", "parquet", "yaml", "xml"];
+
+/// Saves the results as files in the output directory: tables at `<name>.<format>`, summaries
+/// at `<name>.json` and graphs at `<name>.dot`. In the `jsonl` format, tables are saved one JSON
+/// record per line, and in the `yaml` and `xml` formats, one typed record or `<row>` per row.
+pub struct FileSink {
+    output_path: String,
+    format: String,
+    csv_dialect: CsvDialect,
+    /// Overwrite the outputs of a previous run instead of failing
+    overwrite: bool,
+}
+
+impl FileSink {
+    pub fn new(
+        output_path: &str,
+        format: &str,
+        csv_dialect: CsvDialect,
+        overwrite: bool,
+    ) -> Result<Self, XStatsError> {
+        if !OUTPUT_FORMATS.contains(&format) {
+            return Err(XStatsError::UnsupportedFormat(format.to_string()));
+        }
+        Ok(FileSink {
+            output_path: output_path.to_string(),
+            format: format.to_string(),
+            csv_dialect,
+            overwrite,
+        })
+    }
+
+    fn get_file_path(&self, name: &str, extension: &str) -> String {
+        format!("{}/{}.{}", self.output_path, name, extension)
+    }
+}
+
+impl OutputSink for FileSink {
+    fn write_table(
+        &mut self,
+        name: &str,
+        header: &[String],
+        rows: &[Vec<String>],
+    ) -> Result<(), XStatsError> {
+        let file_path = self.get_file_path(name, &self.format);
+        match self.format.as_str() {
+            "parquet" => {
+                let column_types = infer_column_types(header, rows);
+                save_to_parquet(&file_path, header, rows, &column_types, self.overwrite)?
+            }
+            "csv" => save_to_csv(&file_path, header, rows, &self.csv_dialect, self.overwrite)?,
+            "jsonl" => {
+                save_records_to_jsonl(&file_path, &get_json_records(header, rows), self.overwrite)?
+            }
+            "yaml" => {
+                save_records_to_yaml(&file_path, &get_json_records(header, rows), self.overwrite)?
+            }
+            "xml" => save_to_xml(&file_path, name, header, rows, self.overwrite)?,
+            _ => save_to_json(&file_path, header, rows, self.overwrite)?,
+        }
+        info!("{} saved at {}", name, file_path);
+        Ok(())
+    }
+
+    fn write_typed_table(
+        &mut self,
+        name: &str,
+        header: &[String],
+        rows: &[Vec<String>],
+        column_types: &[ColumnType],
+    ) -> Result<(), XStatsError> {
+        if self.format != "parquet" {
+            return self.write_table(name, header, rows);
+        }
+        let file_path = self.get_file_path(name, &self.format);
+        save_to_parquet(&file_path, header, rows, column_types, self.overwrite)?;
+        info!("{} saved at {}", name, file_path);
+        Ok(())
+    }
+
+    fn write_records(
+        &mut self,
+        name: &str,
+        header: &[String],
+        rows: &[Vec<String>],
+        records: &[serde_json::Value],
+        column_types: &[ColumnType],
+    ) -> Result<(), XStatsError> {
+        let file_path = self.get_file_path(name, &self.format);
+        match self.format.as_str() {
+            "json" => save_records_to_json(&file_path, records, self.overwrite)?,
+            "jsonl" => save_records_to_jsonl(&file_path, records, self.overwrite)?,
+            "yaml" => save_records_to_yaml(&file_path, records, self.overwrite)?,
+            _ => return self.write_typed_table(name, header, rows, column_types),
+        }
+        info!("{} saved at {}", name, file_path);
+        Ok(())
+    }
+
+    fn write_summary(
+        &mut self,
+        name: &str,
+        summary: &serde_json::Value,
+    ) -> Result<(), XStatsError> {
+        let file_path = self.get_file_path(name, "json");
+        write_atomically(&file_path, self.overwrite, |file| {
+            serde_json::to_writer_pretty(file, summary)
+        })?;
+        info!("{} saved at {}", name, file_path);
+        Ok(())
+    }
+
+    fn write_graph(&mut self, name: &str, dot: &str) -> Result<(), XStatsError> {
+        let file_path = self.get_file_path(name, "dot");
+        write_atomically(&file_path, self.overwrite, |file| write!(file, "{}", dot))?;
+        info!("{} saved at {}", name, file_path);
+        Ok(())
+    }
+
+    fn write_json_graph(
+        &mut self,
+        name: &str,
+        graph: &serde_json::Value,
+    ) -> Result<(), XStatsError> {
+        let file_path = self.get_file_path(name, "json");
+        write_atomically(&file_path, self.overwrite, |file| {
+            serde_json::to_writer(file, graph)
+        })?;
+        info!("{} saved at {}", name, file_path);
+        Ok(())
+    }
+
+    fn write_graphml(&mut self, name: &str, graphml: &str) -> Result<(), XStatsError> {
+        let file_path = self.get_file_path(name, "graphml");
+        write_atomically(&file_path, self.overwrite, |file| {
+            write!(file, "{}", graphml)
+        })?;
+        info!("{} saved at {}", name, file_path);
+        Ok(())
+    }
+
+    fn write_gexf(&mut self, name: &str, gexf: &str) -> Result<(), XStatsError> {
+        let file_path = self.get_file_path(name, "gexf");
+        write_atomically(&file_path, self.overwrite, |file| write!(file, "{}", gexf))?;
+        info!("{} saved at {}", name, file_path);
+        Ok(())
+    }
+}
@@ -0,0 +1,351 @@
+use super::OutputSink;
+use crate::error::XStatsError;
+use crate::utils::write_atomically;
+use log::info;
+use std::collections::HashSet;
+use std::io::Write;
+
+/// Tables rendered in the report, by name, with their title
+const TABLE_TITLES: &[(&str, &str)] = &[
+    ("portfolio", "Portfolio"),
+    ("scores", "Quality scores"),
+    ("packages", "Packages"),
+    ("near_duplicates", "Near duplicates"),
+    ("robustness", "Robustness"),
+    ("naming", "Naming"),
+    ("hotspots", "Hotspots"),
+    ("activity", "Activity"),
+    ("calls", "Calls"),
+    ("churn", "Churn"),
+];
+
+/// The entity kinds the metrics are split by, with the title of their table
+const KIND_TITLES: &[(&str, &str)] = &[
+    ("file", "Files"),
+    ("class", "Classes"),
+    ("method", "Methods"),
+];
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+.meta { color: #666; }
+.cards { display: flex; flex-wrap: wrap; gap: 1em; margin: 1em 0; }
+.card { border: 1px solid #ddd; border-radius: 6px; padding: 0.8em 1.2em; min-width: 8em; }
+.card .value { font-size: 1.8em; font-weight: bold; }
+.card .label { color: #666; }
+table { border-collapse: collapse; font-size: 0.9em; margin-bottom: 2em; }
+th, td { border: 1px solid #ddd; padding: 0.3em 0.6em; text-align: left; }
+th { background: #f4f4f4; cursor: pointer; user-select: none; }
+tr:nth-child(even) td { background: #fafafa; }
+.added { fill: #4caf50; }
+.deleted { fill: #e57373; }
+";
+
+/// Sorts a table by the clicked column, numerically when both values are numbers
+const SCRIPT: &str = "
+document.querySelectorAll('th').forEach(function (th) {
+  th.addEventListener('click', function () {
+    var table = th.closest('table');
+    var column = Array.prototype.indexOf.call(th.parentNode.children, th);
+    var ascending = th.dataset.order !== 'asc';
+    th.dataset.order = ascending ? 'asc' : 'desc';
+    var rows = Array.prototype.slice.call(table.tBodies[0].rows);
+    rows.sort(function (a, b) {
+      var x = a.cells[column].textContent, y = b.cells[column].textContent;
+      var order = (x === '' || y === '' || isNaN(x) || isNaN(y))
+        ? x.localeCompare(y) : Number(x) - Number(y);
+      return ascending ? order : -order;
+    });
+    rows.forEach(function (row) { table.tBodies[0].appendChild(row); });
+  });
+});
+";
+
+/// Saves a self-contained HTML report at `report.html` in the output directory, once all the
+/// results are received: summary cards, a chart of the churn per commit and sortable tables.
+///
+/// The tables of each commit, e.g. `metrics/<commit_id>`, and the graphs are left out.
+pub struct HtmlSink {
+    output_path: String,
+    /// Overwrite the report of a previous run instead of failing
+    overwrite: bool,
+    summary: Option<serde_json::Value>,
+    tables: Vec<(String, Vec<String>, Vec<Vec<String>>)>,
+}
+
+impl HtmlSink {
+    pub fn new(output_path: &str, overwrite: bool) -> Self {
+        HtmlSink {
+            output_path: output_path.to_string(),
+            overwrite,
+            summary: None,
+            tables: Vec::new(),
+        }
+    }
+
+    fn get_table(&self, name: &str) -> Option<(&[String], &[Vec<String>])> {
+        self.tables
+            .iter()
+            .find(|(table_name, ..)| table_name == name)
+            .map(|(_, header, rows)| (header.as_slice(), rows.as_slice()))
+    }
+
+    /// The commit id, file path, lines added and lines deleted of each row of the churn table
+    fn get_churn_rows(&self) -> Option<Vec<(&str, &str, f64, f64)>> {
+        let (header, rows) = self.get_table("churn")?;
+        let column = |name: &str| header.iter().position(|column| column == name);
+        let (commit_id, file_path) = (column("commit_id")?, column("file_path")?);
+        let (lines_added, lines_deleted) = (column("lines_added")?, column("lines_deleted")?);
+        let churn_rows = rows
+            .iter()
+            .map(|row| {
+                (
+                    row[commit_id].as_str(),
+                    row[file_path].as_str(),
+                    parse(&row[lines_added]),
+                    parse(&row[lines_deleted]),
+                )
+            })
+            .collect();
+        Some(churn_rows)
+    }
+
+    /// Render the whole report
+    pub fn render(&self) -> String {
+        let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str("<title>xStats report</title>\n");
+        html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+        html.push_str("<h1>xStats report</h1>\n");
+        html.push_str(&self.render_meta());
+        html.push_str(&self.render_cards());
+        html.push_str(&self.render_churn_chart());
+
+        if let Some((header, rows)) = self.get_table("metrics") {
+            let kind_column = header.iter().position(|name| name == "node_kind");
+            for (kind, title) in KIND_TITLES {
+                let kind_rows: Vec<&Vec<String>> = rows
+                    .iter()
+                    .filter(|row| kind_column.is_some_and(|column| row[column] == *kind))
+                    .collect();
+                html.push_str(&render_table(title, header, &kind_rows));
+            }
+        }
+        for (name, title) in TABLE_TITLES {
+            if let Some((header, rows)) = self.get_table(name) {
+                html.push_str(&render_table(
+                    title,
+                    header,
+                    &rows.iter().collect::<Vec<_>>(),
+                ));
+            }
+        }
+
+        html.push_str(&format!("<script>{}</script>\n</body>\n</html>\n", SCRIPT));
+        html
+    }
+
+    /// The target, commit and version of the run, from the run manifest
+    fn render_meta(&self) -> String {
+        let Some(summary) = &self.summary else {
+            return String::new();
+        };
+        let get = |key: &str| summary[key].as_str().unwrap_or("-").to_string();
+        format!(
+            "<p class=\"meta\">Target: {} &middot; Commit: {} &middot; xStats {}</p>\n",
+            escape(&get("target_path")),
+            escape(&get("commit")),
+            escape(&get("xstats_version"))
+        )
+    }
+
+    fn render_cards(&self) -> String {
+        let mut cards: Vec<(String, String)> = Vec::new();
+
+        if let Some((header, rows)) = self.get_table("metrics") {
+            let column = |name: &str| header.iter().position(|column| column == name);
+            let kind_column = column("node_kind");
+            let rows_of = |kind: &str| -> Vec<&Vec<String>> {
+                rows.iter()
+                    .filter(|row| kind_column.is_some_and(|column| row[column] == kind))
+                    .collect()
+            };
+            let (files, classes, methods) = (rows_of("file"), rows_of("class"), rows_of("method"));
+            cards.push(("Files".to_string(), files.len().to_string()));
+            cards.push(("Classes".to_string(), classes.len().to_string()));
+            cards.push(("Methods".to_string(), methods.len().to_string()));
+            if let Some(aloc) = column("aloc") {
+                let total: f64 = files.iter().map(|row| parse(&row[aloc])).sum();
+                cards.push(("Lines of code".to_string(), total.to_string()));
+            }
+            if let Some(cc) = column("cc") {
+                if !methods.is_empty() {
+                    let total: f64 = methods.iter().map(|row| parse(&row[cc])).sum();
+                    let average = total / methods.len() as f64;
+                    cards.push((
+                        "Average cc per method".to_string(),
+                        format!("{:.1}", average),
+                    ));
+                }
+            }
+        }
+
+        // The target directory is the one covering the most files
+        if let Some((header, rows)) = self.get_table("scores") {
+            let files = header.iter().position(|name| name == "files");
+            let grade = header.iter().position(|name| name == "grade");
+            if let (Some(files), Some(grade)) = (files, grade) {
+                if let Some(row) = rows.iter().max_by_key(|row| parse(&row[files]) as u64) {
+                    cards.push(("Grade".to_string(), row[grade].clone()));
+                }
+            }
+        }
+
+        if let Some(churn_rows) = self.get_churn_rows() {
+            let commits: HashSet<&str> = churn_rows.iter().map(|row| row.0).collect();
+            let files: HashSet<&str> = churn_rows.iter().map(|row| row.1).collect();
+            let added: f64 = churn_rows.iter().map(|row| row.2).sum();
+            let deleted: f64 = churn_rows.iter().map(|row| row.3).sum();
+            cards.push(("Commits".to_string(), commits.len().to_string()));
+            cards.push(("Files changed".to_string(), files.len().to_string()));
+            cards.push(("Lines added".to_string(), added.to_string()));
+            cards.push(("Lines deleted".to_string(), deleted.to_string()));
+        }
+
+        if cards.is_empty() {
+            return String::new();
+        }
+        let mut html = String::from("<div class=\"cards\">\n");
+        for (label, value) in cards {
+            html.push_str(&format!(
+                "<div class=\"card\"><div class=\"value\">{}</div><div class=\"label\">{}</div></div>\n",
+                escape(&value),
+                escape(&label)
+            ));
+        }
+        html.push_str("</div>\n");
+        html
+    }
+
+    /// A bar chart of the lines added (up) and deleted (down) by each commit, oldest first
+    fn render_churn_chart(&self) -> String {
+        let Some(churn_rows) = self.get_churn_rows() else {
+            return String::new();
+        };
+        // The rows of a commit are contiguous
+        let mut commits: Vec<(&str, f64, f64)> = Vec::new();
+        for (commit_id, _, added, deleted) in churn_rows {
+            match commits.last_mut() {
+                Some(commit) if commit.0 == commit_id => {
+                    commit.1 += added;
+                    commit.2 += deleted;
+                }
+                _ => commits.push((commit_id, added, deleted)),
+            }
+        }
+        if commits.is_empty() {
+            return String::new();
+        }
+
+        let (width, half_height) = (800.0, 100.0);
+        let bar_width = width / commits.len() as f64;
+        let max_lines = commits
+            .iter()
+            .map(|(_, added, deleted)| added.max(*deleted))
+            .fold(1.0, f64::max);
+        let scale = half_height / max_lines;
+
+        let mut html = String::from("<h2>Churn per commit</h2>\n");
+        html.push_str(&format!(
+            "<svg width=\"{}\" height=\"{}\" role=\"img\">\n",
+            width,
+            half_height * 2.0
+        ));
+        for (i, (commit_id, added, deleted)) in commits.iter().enumerate() {
+            let x = i as f64 * bar_width;
+            let title = format!("{}: +{} -{}", commit_id, added, deleted);
+            html.push_str(&format!(
+                "<g><title>{}</title><rect class=\"added\" x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\"/><rect class=\"deleted\" x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\"/></g>\n",
+                escape(&title),
+                x,
+                half_height - added * scale,
+                bar_width.max(1.0),
+                added * scale,
+                x,
+                half_height,
+                bar_width.max(1.0),
+                deleted * scale
+            ));
+        }
+        html.push_str("</svg>\n");
+        html
+    }
+}
+
+impl OutputSink for HtmlSink {
+    fn write_table(
+        &mut self,
+        name: &str,
+        header: &[String],
+        rows: &[Vec<String>],
+    ) -> Result<(), XStatsError> {
+        if !name.contains('/') {
+            self.tables
+                .push((name.to_string(), header.to_vec(), rows.to_vec()));
+        }
+        Ok(())
+    }
+
+    fn write_summary(
+        &mut self,
+        name: &str,
+        summary: &serde_json::Value,
+    ) -> Result<(), XStatsError> {
+        if name == "run_manifest" {
+            self.summary = Some(summary.clone());
+        }
+        Ok(())
+    }
+
+    fn write_graph(&mut self, name: &str, dot: &str) -> Result<(), XStatsError> {
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), XStatsError> {
+        let file_path = format!("{}/report.html", self.output_path);
+        let html = self.render();
+        write_atomically(&file_path, self.overwrite, |file| write!(file, "{}", html))?;
+        info!("report saved at {}", file_path);
+        Ok(())
+    }
+}
+
+fn render_table(title: &str, header: &[String], rows: &[&Vec<String>]) -> String {
+    if rows.is_empty() {
+        return String::new();
+    }
+    let mut html = format!("<h2>{}</h2>\n<table>\n<thead><tr>", escape(title));
+    for name in header {
+        html.push_str(&format!("<th>{}</th>", escape(name)));
+    }
+    html.push_str("</tr></thead>\n<tbody>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for value in row.iter() {
+            html.push_str(&format!("<td>{}</td>", escape(value)));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</tbody>\n</table>\n");
+    html
+}
+
+fn parse(value: &str) -> f64 {
+    value.parse().unwrap_or(0.0)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
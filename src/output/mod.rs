@@ -0,0 +1,109 @@
+mod file_sink;
+mod html_sink;
+mod stdout_sink;
+mod stream_writer;
+pub use file_sink::{FileSink, OUTPUT_FORMATS};
+pub use html_sink::HtmlSink;
+pub use stdout_sink::{StdoutSink, STDOUT_FORMATS, STDOUT_TABLES};
+pub use stream_writer::{StreamWriter, STREAM_FORMATS};
+
+use crate::error::XStatsError;
+use crate::utils::csv_dialect::CsvDialect;
+use crate::utils::parquet::ColumnType;
+
+/// A destination for the results of a run.
+///
+/// Results are named after the files they are saved to by default, e.g. `metrics`,
+/// `changes/<commit_id>` or `tdg`. Implement it to stream the results elsewhere, e.g. into a
+/// message queue or an internal API, and pass it to the `save_*` functions of `XStats`.
+pub trait OutputSink {
+    /// Receive a table, e.g. the metrics of every entity, as its header and its rows
+    fn write_table(
+        &mut self,
+        name: &str,
+        header: &[String],
+        rows: &[Vec<String>],
+    ) -> Result<(), XStatsError>;
+
+    /// Receive a table whose columns have the types of its schema, whatever its values, e.g. the
+    /// changes of a commit, for the tables of every commit to share it. Sinks without typed
+    /// columns only receive the table.
+    fn write_typed_table(
+        &mut self,
+        name: &str,
+        header: &[String],
+        rows: &[Vec<String>],
+        _column_types: &[ColumnType],
+    ) -> Result<(), XStatsError> {
+        self.write_table(name, header, rows)
+    }
+
+    /// Receive a table along with its rows as typed records, e.g. the metrics with numbers as
+    /// numbers, and the types of its columns. Sinks without typed values only receive the table
+    /// with the types of its columns.
+    fn write_records(
+        &mut self,
+        name: &str,
+        header: &[String],
+        rows: &[Vec<String>],
+        _records: &[serde_json::Value],
+        column_types: &[ColumnType],
+    ) -> Result<(), XStatsError> {
+        self.write_typed_table(name, header, rows, column_types)
+    }
+
+    /// Receive a summary of the run, e.g. the run manifest
+    fn write_summary(&mut self, name: &str, summary: &serde_json::Value)
+        -> Result<(), XStatsError>;
+
+    /// Receive a graph in the DOT format, e.g. the type dependency graph
+    fn write_graph(&mut self, name: &str, dot: &str) -> Result<(), XStatsError>;
+
+    /// Receive a graph as JSON nodes and edges, for web visualizations. Sinks without JSON graph
+    /// support ignore it, as they already receive the graph in the DOT format.
+    fn write_json_graph(
+        &mut self,
+        _name: &str,
+        _graph: &serde_json::Value,
+    ) -> Result<(), XStatsError> {
+        Ok(())
+    }
+
+    /// Receive a graph in the GraphML format, when enabled. Sinks without GraphML support ignore
+    /// it, as they already receive the graph in the DOT format.
+    fn write_graphml(&mut self, _name: &str, _graphml: &str) -> Result<(), XStatsError> {
+        Ok(())
+    }
+
+    /// Receive a graph in the GEXF format, when enabled. Sinks without GEXF support ignore it, as
+    /// they already receive the graph in the DOT format.
+    fn write_gexf(&mut self, _name: &str, _gexf: &str) -> Result<(), XStatsError> {
+        Ok(())
+    }
+
+    /// Called once all the results are received, e.g. to flush them
+    fn finish(&mut self) -> Result<(), XStatsError> {
+        Ok(())
+    }
+}
+
+/// Create the sink saving the results as files in the output path, in the given format: `csv`,
+/// `json`, `jsonl`, `parquet`, `yaml`, `xml` or `html`, CSV files being written in the given
+/// dialect. The output path `-` writes the metrics to stdout instead.
+pub fn create_sink(
+    output_path: &str,
+    format: &str,
+    csv_dialect: CsvDialect,
+    overwrite: bool,
+) -> Result<Box<dyn OutputSink>, XStatsError> {
+    match format {
+        _ if output_path == "-" => Ok(Box::new(StdoutSink::new(format, csv_dialect)?)),
+        "html" => Ok(Box::new(HtmlSink::new(output_path, overwrite))),
+        _ => Ok(Box::new(FileSink::new(
+            output_path,
+            format,
+            csv_dialect,
+            overwrite,
+        )?)),
+    }
+}
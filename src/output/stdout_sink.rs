@@ -0,0 +1,129 @@
+use super::OutputSink;
+use crate::error::XStatsError;
+use crate::utils::csv_dialect::CsvDialect;
+use crate::utils::{get_json_records, get_versioned_records, get_xml, write_csv, write_jsonl};
+use std::io::{self, Write};
+
+/// The formats results can be written to stdout in
+pub const STDOUT_FORMATS: &[&str] = &["csv", "json", "jsonl", "yaml", "xml"];
+
+/// The tables written to stdout, along with those of each commit, e.g. `metrics/<commit_id>`
+pub const STDOUT_TABLES: &[&str] = &["metrics", "diff", "portfolio"];
+
+/// Writes the metrics to stdout instead of files, for pipelines such as `xStats -t . --stdout |
+/// jq ...`.
+///
+/// Only the tables of `STDOUT_TABLES` are written, once all of them are received, as a single
+/// table: in multi-commit mode, the rows of every commit follow each other, with a `commit_id`
+/// column first. Tables other than the first, or with other columns, are refused, since they
+/// would be written under its header. Summaries and graphs are left out.
+pub struct StdoutSink {
+    format: String,
+    csv_dialect: CsvDialect,
+    writer: Box<dyn Write>,
+    name: String,
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl StdoutSink {
+    pub fn new(format: &str, csv_dialect: CsvDialect) -> Result<Self, XStatsError> {
+        Self::with_writer(format, csv_dialect, Box::new(io::stdout()))
+    }
+
+    /// Write the metrics to the writer, e.g. a buffer, in place of stdout
+    pub fn with_writer(
+        format: &str,
+        csv_dialect: CsvDialect,
+        writer: Box<dyn Write>,
+    ) -> Result<Self, XStatsError> {
+        if !STDOUT_FORMATS.contains(&format) {
+            return Err(XStatsError::UnsupportedFormat(format.to_string()));
+        }
+        Ok(StdoutSink {
+            format: format.to_string(),
+            csv_dialect,
+            writer,
+            name: String::new(),
+            header: Vec::new(),
+            rows: Vec::new(),
+        })
+    }
+}
+
+impl OutputSink for StdoutSink {
+    fn write_table(
+        &mut self,
+        name: &str,
+        header: &[String],
+        rows: &[Vec<String>],
+    ) -> Result<(), XStatsError> {
+        let (table, commit_id) = match name.split_once('/') {
+            Some((table, commit_id)) => (table, Some(commit_id)),
+            None => (name, None),
+        };
+        if !STDOUT_TABLES.contains(&table) {
+            return Ok(());
+        }
+        let header = match commit_id {
+            Some(_) => [&["commit_id".to_string()], header].concat(),
+            None => header.to_vec(),
+        };
+        if self.header.is_empty() {
+            self.name = table.to_string();
+            self.header = header;
+        } else if table != self.name || header != self.header {
+            return Err(XStatsError::InvalidTable {
+                path: name.to_string(),
+                reason: format!(
+                    "only one table can be written to stdout, and its columns differ from those \
+                     of {}",
+                    self.name
+                ),
+            });
+        }
+        for row in rows {
+            self.rows.push(match commit_id {
+                Some(commit_id) => [&[commit_id.to_string()], row.as_slice()].concat(),
+                None => row.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    fn write_summary(
+        &mut self,
+        _name: &str,
+        _summary: &serde_json::Value,
+    ) -> Result<(), XStatsError> {
+        Ok(())
+    }
+
+    fn write_graph(&mut self, _name: &str, _dot: &str) -> Result<(), XStatsError> {
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), XStatsError> {
+        if self.header.is_empty() {
+            return Ok(());
+        }
+        let write_error = |e| XStatsError::write("stdout", e);
+        let records = get_json_records(&self.header, &self.rows);
+        let writer = &mut self.writer;
+        match self.format.as_str() {
+            "csv" => write_csv(&mut *writer, &self.header, &self.rows, &self.csv_dialect)
+                .map_err(|e| write_error(e.into()))?,
+            "jsonl" => write_jsonl(writer, &records).map_err(write_error)?,
+            "yaml" => serde_yaml::to_writer(&mut *writer, &get_versioned_records(&records))
+                .map_err(|e| write_error(e.into()))?,
+            "xml" => write!(writer, "{}", get_xml(&self.name, &self.header, &self.rows))
+                .map_err(|e| write_error(e.into()))?,
+            _ => {
+                serde_json::to_writer(&mut *writer, &get_versioned_records(&records))
+                    .map_err(|e| write_error(e.into()))?;
+                writeln!(writer).map_err(|e| write_error(e.into()))?;
+            }
+        }
+        writer.flush().map_err(|e| write_error(e.into()))
+    }
+}
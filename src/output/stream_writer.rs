@@ -0,0 +1,362 @@
+use super::{FileSink, OutputSink};
+use crate::error::XStatsError;
+use crate::utils::csv_dialect::CsvDialect;
+use crate::utils::parquet::{infer_column_types, ColumnType};
+use csv::{ReaderBuilder, WriterBuilder};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// The formats results can be streamed in
+pub const STREAM_FORMATS: &[&str] = &["csv", "json", "jsonl"];
+
+/// Saves the results as soon as they are computed, e.g. those of each commit in multi-commit
+/// mode, so that an interrupted run keeps the results so far.
+///
+/// Tables written whole, e.g. `metrics/<commit_id>`, are saved as files as by `FileSink`. Those
+/// growing along the run, e.g. `churn`, are appended to `<name>.jsonl`, one JSON record per line,
+/// in the `json` and `jsonl` formats, or to `<name>.csv` in the `csv` format, and flushed after
+/// every batch of rows. When appending, the files of a previous run are continued rather than
+/// refused.
+pub struct StreamWriter {
+    output_path: String,
+    format: String,
+    csv_dialect: CsvDialect,
+    overwrite: bool,
+    append: bool,
+    sink: FileSink,
+    /// The files appended to so far, by table name
+    files: HashMap<String, BufWriter<File>>,
+}
+
+impl StreamWriter {
+    pub fn new(
+        output_path: &str,
+        format: &str,
+        csv_dialect: CsvDialect,
+        overwrite: bool,
+        append: bool,
+    ) -> Result<Self, XStatsError> {
+        if !STREAM_FORMATS.contains(&format) {
+            return Err(XStatsError::UnsupportedFormat(format.to_string()));
+        }
+        // The tables of the commits analyzed again after an interruption are saved again
+        let sink = FileSink::new(
+            output_path,
+            format,
+            csv_dialect.clone(),
+            overwrite || append,
+        )?;
+        Ok(StreamWriter {
+            output_path: output_path.to_string(),
+            format: format.to_string(),
+            csv_dialect,
+            overwrite,
+            append,
+            sink,
+            files: HashMap::new(),
+        })
+    }
+
+    /// The path of the file the rows of a table are appended to
+    pub fn get_stream_path(&self, name: &str) -> String {
+        let extension = if self.format == "csv" { "csv" } else { "jsonl" };
+        format!("{}/{}.{}", self.output_path, name, extension)
+    }
+
+    /// Append rows to a table, creating its file along with its header if needed
+    pub fn append_table(
+        &mut self,
+        name: &str,
+        header: &[String],
+        rows: &[Vec<String>],
+    ) -> Result<(), XStatsError> {
+        let file_path = self.get_stream_path(name);
+        if !self.files.contains_key(name) {
+            let file = self.open_file(&file_path, header)?;
+            self.files.insert(name.to_string(), file);
+        }
+        let Some(file) = self.files.get_mut(name) else {
+            return Ok(());
+        };
+
+        if self.format == "csv" {
+            let mut writer = WriterBuilder::new()
+                .delimiter(self.csv_dialect.delimiter)
+                .quote_style(self.csv_dialect.quote_style)
+                .has_headers(false)
+                .from_writer(&mut *file);
+            for row in rows {
+                writer
+                    .write_record(
+                        row.iter()
+                            .map(|value| self.csv_dialect.format_value(value).into_owned()),
+                    )
+                    .map_err(|e| XStatsError::write(&file_path, e))?;
+            }
+            writer
+                .flush()
+                .map_err(|e| XStatsError::write(&file_path, e))?;
+        } else {
+            let column_types = infer_column_types(header, rows);
+            for row in rows {
+                let record: serde_json::Map<String, serde_json::Value> = header
+                    .iter()
+                    .zip(row)
+                    .zip(&column_types)
+                    .map(|((name, value), column_type)| (name.clone(), column_type.to_json(value)))
+                    .collect();
+                serde_json::to_writer(&mut *file, &record)
+                    .map_err(|e| XStatsError::write(&file_path, e))?;
+                writeln!(file).map_err(|e| XStatsError::write(&file_path, e))?;
+            }
+        }
+        file.flush().map_err(|e| XStatsError::write(&file_path, e))
+    }
+
+    /// The values of a column of the rows appended to a table by a previous run, if any
+    pub fn get_streamed_values(
+        &self,
+        name: &str,
+        column: &str,
+    ) -> Result<Vec<String>, XStatsError> {
+        Ok(self
+            .read_rows(name, column)?
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect())
+    }
+
+    /// Only keep the rows appended to a table by a previous run whose value of a column is one of
+    /// `values`, along with the header. Rows cut short by an interruption are dropped.
+    pub fn retain_streamed_rows(
+        &self,
+        name: &str,
+        column: &str,
+        values: &HashSet<String>,
+    ) -> Result<(), XStatsError> {
+        let file_path = self.get_stream_path(name);
+        if !Path::new(&file_path).exists() {
+            return Ok(());
+        }
+        let mut content = Vec::new();
+        let rows = self.read_rows(name, column)?;
+        let kept_rows = rows.into_iter().filter(|(_, value)| values.contains(value));
+        if self.format == "csv" {
+            let mut reader = self.get_csv_reader(&file_path)?;
+            let header = reader
+                .headers()
+                .map_err(|e| get_csv_read_error(&file_path, e))?
+                .clone();
+            let mut writer = WriterBuilder::new()
+                .delimiter(self.csv_dialect.delimiter)
+                .quote_style(self.csv_dialect.quote_style)
+                .from_writer(&mut content);
+            writer
+                .write_record(&header)
+                .map_err(|e| XStatsError::write(&file_path, e))?;
+            for (row, _) in kept_rows {
+                writer
+                    .write_record(row)
+                    .map_err(|e| XStatsError::write(&file_path, e))?;
+            }
+            writer
+                .flush()
+                .map_err(|e| XStatsError::write(&file_path, e))?;
+        } else {
+            for (row, _) in kept_rows {
+                content.extend(row[0].as_bytes());
+                content.push(b'\n');
+            }
+        }
+        fs::write(&file_path, content).map_err(|e| XStatsError::write(&file_path, e))
+    }
+
+    /// The rows appended to a table by a previous run, with their value of a column: the fields
+    /// of each CSV row, or the line of each JSON record. Rows without the column are skipped.
+    fn read_rows(
+        &self,
+        name: &str,
+        column: &str,
+    ) -> Result<Vec<(Vec<String>, String)>, XStatsError> {
+        let file_path = self.get_stream_path(name);
+        if !Path::new(&file_path).exists() {
+            return Ok(Vec::new());
+        }
+        let mut rows = Vec::new();
+        if self.format == "csv" {
+            let mut reader = self.get_csv_reader(&file_path)?;
+            let header = reader
+                .headers()
+                .map_err(|e| get_csv_read_error(&file_path, e))?
+                .clone();
+            let Some(idx) = header.iter().position(|name| name == column) else {
+                return Ok(rows);
+            };
+            for record in reader.records().map_while(Result::ok) {
+                if record.len() == header.len() {
+                    let row: Vec<String> = record.iter().map(|value| value.to_string()).collect();
+                    let value = row[idx].clone();
+                    rows.push((row, value));
+                }
+            }
+        } else {
+            let read_error = |source| XStatsError::Read {
+                path: file_path.clone(),
+                source,
+            };
+            let file = File::open(&file_path).map_err(read_error)?;
+            for line in BufReader::new(file).lines() {
+                let line = line.map_err(read_error)?;
+                let value = serde_json::from_str::<serde_json::Value>(&line)
+                    .ok()
+                    .and_then(|record| record.get(column)?.as_str().map(|v| v.to_string()));
+                if let Some(value) = value {
+                    rows.push((vec![line], value));
+                }
+            }
+        }
+        Ok(rows)
+    }
+
+    fn get_csv_reader(&self, file_path: &str) -> Result<csv::Reader<File>, XStatsError> {
+        ReaderBuilder::new()
+            .delimiter(self.csv_dialect.delimiter)
+            .flexible(true)
+            .from_path(file_path)
+            .map_err(|e| get_csv_read_error(file_path, e))
+    }
+
+    /// Open the file of a table to append to, writing the header of a new CSV file and checking
+    /// that of a continued one
+    fn open_file(
+        &self,
+        file_path: &str,
+        header: &[String],
+    ) -> Result<BufWriter<File>, XStatsError> {
+        let path = Path::new(file_path);
+        let is_continued = self.append && path.exists();
+        if !is_continued && !self.overwrite && path.exists() {
+            return Err(XStatsError::OutputExists(file_path.to_string()));
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| XStatsError::write(file_path, e))?;
+        }
+
+        let mut csv_header = Vec::new();
+        if self.format == "csv" {
+            let mut writer = WriterBuilder::new()
+                .delimiter(self.csv_dialect.delimiter)
+                .quote_style(self.csv_dialect.quote_style)
+                .from_writer(&mut csv_header);
+            writer
+                .write_record(header)
+                .and_then(|()| writer.flush().map_err(csv::Error::from))
+                .map_err(|e| XStatsError::write(file_path, e))?;
+        }
+
+        let file = if is_continued {
+            let read_error = |source| XStatsError::Read {
+                path: file_path.to_string(),
+                source,
+            };
+            let file = File::open(path).map_err(read_error)?;
+            let mut first_line = String::new();
+            BufReader::new(file)
+                .read_line(&mut first_line)
+                .map_err(read_error)?;
+            // The header of a continued CSV file is already written
+            if !first_line.is_empty() {
+                if first_line.as_bytes() != csv_header.as_slice() && self.format == "csv" {
+                    return Err(XStatsError::InvalidTable {
+                        path: file_path.to_string(),
+                        reason: "its header differs from that of the table".to_string(),
+                    });
+                }
+                csv_header.clear();
+            }
+            OpenOptions::new()
+                .append(true)
+                .open(path)
+                .map_err(|e| XStatsError::write(file_path, e))?
+        } else {
+            File::create(path).map_err(|e| XStatsError::write(file_path, e))?
+        };
+
+        let mut file = BufWriter::new(file);
+        file.write_all(&csv_header)
+            .map_err(|e| XStatsError::write(file_path, e))?;
+        Ok(file)
+    }
+}
+
+/// The error of reading a streamed CSV file: that of the file itself, or else its malformed
+/// header, e.g. not in UTF-8
+fn get_csv_read_error(file_path: &str, error: csv::Error) -> XStatsError {
+    let reason = format!("its header is malformed: {}", error);
+    match error.into_kind() {
+        csv::ErrorKind::Io(source) => XStatsError::Read {
+            path: file_path.to_string(),
+            source,
+        },
+        _ => XStatsError::InvalidTable {
+            path: file_path.to_string(),
+            reason,
+        },
+    }
+}
+
+impl OutputSink for StreamWriter {
+    fn write_table(
+        &mut self,
+        name: &str,
+        header: &[String],
+        rows: &[Vec<String>],
+    ) -> Result<(), XStatsError> {
+        self.sink.write_table(name, header, rows)
+    }
+
+    fn write_typed_table(
+        &mut self,
+        name: &str,
+        header: &[String],
+        rows: &[Vec<String>],
+        column_types: &[ColumnType],
+    ) -> Result<(), XStatsError> {
+        self.sink
+            .write_typed_table(name, header, rows, column_types)
+    }
+
+    fn write_records(
+        &mut self,
+        name: &str,
+        header: &[String],
+        rows: &[Vec<String>],
+        records: &[serde_json::Value],
+        column_types: &[ColumnType],
+    ) -> Result<(), XStatsError> {
+        self.sink
+            .write_records(name, header, rows, records, column_types)
+    }
+
+    fn write_summary(
+        &mut self,
+        name: &str,
+        summary: &serde_json::Value,
+    ) -> Result<(), XStatsError> {
+        self.sink.write_summary(name, summary)
+    }
+
+    fn write_graph(&mut self, name: &str, dot: &str) -> Result<(), XStatsError> {
+        self.sink.write_graph(name, dot)
+    }
+
+    fn finish(&mut self) -> Result<(), XStatsError> {
+        for (name, file) in &mut self.files {
+            file.flush()
+                .map_err(|e| XStatsError::write(name.as_str(), e))?;
+        }
+        Ok(())
+    }
+}
@@ -1,47 +1,59 @@
 use crate::config;
+use crate::error::XStatsError;
+use crate::languages::get_profile;
 use crate::utils;
 use config::Language;
-use std::collections::HashMap;
+use log::info;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 pub use tree_sitter::{
-    InputEdit, Language as TSLanguage, Node, Parser, Point, Query, QueryCaptures, QueryCursor,
-    QueryMatches, Tree,
+    InputEdit, Language as TSLanguage, LanguageError, Node, Parser, Point, Query, QueryCaptures,
+    QueryCursor, QueryMatches, Tree,
 };
 
 pub fn get_grammar_info() -> Vec<(Language, TSLanguage, Vec<&'static str>)> {
-    vec![
-        (
-            Language::Java,
-            tree_sitter_java::LANGUAGE.into(),
-            vec![".java"],
-        ),
-        (
-            Language::Python,
-            tree_sitter_python::LANGUAGE.into(),
-            vec![".py"],
-        ),
-    ]
+    Language::all()
+        .into_iter()
+        .map(|language| {
+            let profile = get_profile(language);
+            (language, profile.grammar(), profile.extensions().to_vec())
+        })
+        .collect()
 }
 
 pub struct TSParser {
     language: TSLanguage,
     parser: Parser,
     supported_extensions: Vec<&'static str>,
+    /// The queries compiled so far, by query string, as the same ones run on every entity
+    queries: RefCell<HashMap<String, Query>>,
 }
 
 impl TSParser {
-    pub fn new(grammar: TSLanguage) -> Self {
+    /// Create a parser of the grammar, which fails if the tree-sitter library does not support
+    /// its ABI version
+    pub fn new(grammar: TSLanguage) -> Result<Self, LanguageError> {
         let language = grammar;
 
         let mut parser = Parser::new();
-        parser
-            .set_language(&language)
-            .expect("Error setting language");
+        parser.set_language(&language)?;
 
-        Self {
+        Ok(Self {
             language,
             parser,
             supported_extensions: vec![],
-        }
+            queries: RefCell::new(HashMap::new()),
+        })
+    }
+
+    pub fn get_grammar(&self) -> &TSLanguage {
+        &self.language
+    }
+
+    /// The number of distinct queries compiled so far
+    pub fn num_compiled_queries(&self) -> usize {
+        self.queries.borrow().len()
     }
 
     /// Query the syntax tree for matches
@@ -51,71 +63,141 @@ impl TSParser {
         tree: &'a Tree,
         source_code: &'a str,
         query_string: &str,
-    ) -> Vec<(Node<'a>, String)> {
-        let query = Query::new(&self.language, query_string).expect("Invalid query");
+    ) -> Result<Vec<(Node<'a>, String)>, XStatsError> {
+        if !self.queries.borrow().contains_key(query_string) {
+            let query = Query::new(&self.language, query_string)?;
+            self.queries
+                .borrow_mut()
+                .insert(query_string.to_string(), query);
+        }
+        let queries = self.queries.borrow();
+        let query = &queries[query_string];
         let mut query_cursor = QueryCursor::new();
 
         let mut results = Vec::new();
-        for (query_match, index) in query_cursor.captures(&query, *node, source_code.as_bytes()) {
-            let captures = query_match.captures;
-            for capture in captures {
-                let tag = query.capture_names()[capture.index as usize].to_string();
-                let node = tree
-                    .root_node()
-                    .descendant_for_byte_range(capture.node.start_byte(), capture.node.end_byte())
-                    .unwrap();
+        // Every capture of a match is yielded in turn, along with the whole match
+        for (query_match, index) in query_cursor.captures(query, *node, source_code.as_bytes()) {
+            let capture = query_match.captures[index];
+            let tag = query.capture_names()[capture.index as usize].to_string();
+            let node = tree
+                .root_node()
+                .descendant_for_byte_range(capture.node.start_byte(), capture.node.end_byte());
+            if let Some(node) = node {
                 results.push((node, tag));
             }
         }
-        results
+        Ok(results)
+    }
+
+    /// Count the matches of a query in the node, each counted once whatever its captures
+    pub fn count_matches(
+        &self,
+        node: &Node,
+        source_code: &str,
+        query_string: &str,
+    ) -> Result<usize, XStatsError> {
+        if !self.queries.borrow().contains_key(query_string) {
+            let query = Query::new(&self.language, query_string)?;
+            self.queries
+                .borrow_mut()
+                .insert(query_string.to_string(), query);
+        }
+        let queries = self.queries.borrow();
+        let mut query_cursor = QueryCursor::new();
+        Ok(query_cursor
+            .matches(&queries[query_string], *node, source_code.as_bytes())
+            .count())
     }
 }
 
 pub struct TSParsers {
     ts_parsers: HashMap<Language, TSParser>,
+    /// The languages whose grammar could not be loaded, with the reason
+    pub load_errors: HashMap<Language, String>,
+    /// The languages of the extensions set by the user, taking precedence over the built-in ones
+    extension_languages: BTreeMap<String, Language>,
+}
+
+impl Default for TSParsers {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TSParsers {
     pub fn new() -> Self {
-        let ts_parsers = get_grammar_info()
-            .into_iter()
-            .map(|(name, grammar, extensions)| {
-                let mut parser = TSParser::new(grammar);
-                parser.supported_extensions = extensions;
-                (name, parser)
-            })
-            .collect::<HashMap<Language, TSParser>>();
-        Self { ts_parsers }
+        let mut ts_parsers = HashMap::new();
+        let mut load_errors = HashMap::new();
+        for (name, grammar, extensions) in get_grammar_info() {
+            match TSParser::new(grammar) {
+                Ok(mut parser) => {
+                    parser.supported_extensions = extensions;
+                    ts_parsers.insert(name, parser);
+                }
+                Err(e) => {
+                    load_errors.insert(name, e.to_string());
+                }
+            }
+        }
+        Self {
+            ts_parsers,
+            load_errors,
+            extension_languages: BTreeMap::new(),
+        }
+    }
+
+    /// Parse the files of these extensions as the given languages, e.g. `.pyw` as Python,
+    /// instead of those of their built-in extensions
+    pub fn set_extension_languages(&mut self, extension_languages: &BTreeMap<String, Language>) {
+        if &self.extension_languages != extension_languages {
+            self.extension_languages = extension_languages.clone();
+        }
     }
 
     pub fn get_parser(&self, language: &Language) -> Option<&TSParser> {
         self.ts_parsers.get(language)
     }
 
+    /// Get the language of a file from its extension
+    pub fn get_language(&self, file_path: &str) -> Option<Language> {
+        let file_extension = utils::get_file_extension(file_path);
+        if let Some(language) = self.extension_languages.get(&file_extension) {
+            return Some(*language);
+        }
+        self.ts_parsers
+            .iter()
+            .find(|(_, ts_parser)| {
+                ts_parser
+                    .supported_extensions
+                    .contains(&file_extension.as_str())
+            })
+            .map(|(language, _)| *language)
+    }
+
+    /// Get the language of a file from its extension, or else from the shebang of its source
+    /// code, e.g. `#!/usr/bin/env python3` for scripts without an extension
+    pub fn detect_language(&self, file_path: &str, source_code: &str) -> Option<Language> {
+        self.get_language(file_path)
+            .or_else(|| get_shebang_language(source_code))
+            .filter(|language| self.ts_parsers.contains_key(language))
+    }
+
+    /// Parse a file, from its content if given, or read from disk otherwise, in the language of
+    /// its extension or else of its shebang.
+    ///
+    /// A tree of the file in the trees bin is reused as its previous tree, so it must have been
+    /// edited to match the new content, e.g. from the hunks of a diff.
     pub fn generate_tree(
         &mut self,
         trees_bin: &mut TSTreesBin,
         file_path: &str,
         content: Option<String>,
-    ) -> Option<(Language, Tree, String)> {
-        let file_extension = utils::get_file_extension(file_path);
-
-        for (lang, ts_parser) in &mut self.ts_parsers {
-            if ts_parser
-                .supported_extensions
-                .contains(&file_extension.as_str())
-            {
-                let source_code = match content {
-                    Some(ref content) => content.clone(),
-                    None => utils::read_file(file_path),
-                };
-
-                if let Some(tree) = Self::parse_with_ts(&mut ts_parser.parser, &source_code, None) {
-                    return Some((lang.clone(), tree, source_code.to_string()));
-                }
-            }
-        }
-        None
+    ) -> Result<Option<(Language, Tree, String)>, XStatsError> {
+        let source_code = match content {
+            Some(content) => content,
+            None => utils::read_file(file_path)?,
+        };
+        Ok(self.generate_tree_from_blob(trees_bin, file_path, &source_code))
     }
 
     pub fn generate_tree_from_blob(
@@ -124,27 +206,25 @@ impl TSParsers {
         file_path: &str,
         source_code: &str,
     ) -> Option<(Language, Tree, String)> {
-        let file_extension = utils::get_file_extension(file_path);
+        let language = self.detect_language(file_path, source_code)?;
+        let ts_parser = self.ts_parsers.get_mut(&language)?;
+        let old_tree = trees_bin.get_tree(file_path);
+        let tree = Self::parse_with_ts(&mut ts_parser.parser, source_code, old_tree.as_deref())?;
+        Some((language, tree, source_code.to_string()))
+    }
 
-        for (lang, ts_parser) in &mut self.ts_parsers {
-            if ts_parser
-                .supported_extensions
-                .contains(&file_extension.as_str())
-            {
-                let source_code = source_code.to_string();
-                let old_tree = match trees_bin.get_tree(file_path) {
-                    Some(tree) => Some(tree),
-                    None => None,
-                };
-
-                if let Some(tree) =
-                    Self::parse_with_ts(&mut ts_parser.parser, &source_code, old_tree.as_deref())
-                {
-                    return Some((lang.clone(), tree, source_code));
-                }
-            }
+    /// Abandon the parsing of a source code once it takes longer than `timeout`, if any
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        let timeout_micros = timeout.map_or(0, |timeout| timeout.as_micros() as u64);
+        for ts_parser in self.ts_parsers.values_mut() {
+            ts_parser.parser.set_timeout_micros(timeout_micros);
         }
-        None
+    }
+
+    /// Parse source code whose language is already known, e.g. code embedded in a host file
+    pub fn parse_source(&mut self, language: &Language, source_code: &str) -> Option<Tree> {
+        let ts_parser = self.ts_parsers.get_mut(language)?;
+        Self::parse_with_ts(&mut ts_parser.parser, source_code, None)
     }
 
     fn parse_with_ts(
@@ -152,66 +232,221 @@ impl TSParsers {
         source_code: &str,
         old_tree: Option<&Tree>,
     ) -> Option<Tree> {
-        parser.parse(source_code, old_tree)
+        let tree = parser.parse(source_code, old_tree);
+        // An abandoned parse would be resumed by the next one otherwise
+        if tree.is_none() {
+            parser.reset();
+        }
+        tree
     }
 
     pub fn get_all_supported_extensions(&self) -> Vec<&'static str> {
         self.ts_parsers
-            .iter()
-            .flat_map(|(_, parser)| parser.supported_extensions.iter())
+            .values()
+            .flat_map(|parser| parser.supported_extensions.iter())
             .cloned()
             .collect()
     }
 }
 
+/// Get the language of a script from the interpreter of its shebang, e.g. Python for
+/// `#!/usr/bin/env python3` or `#!/usr/bin/python3.12`, JavaScript for `node`
+pub fn get_shebang_language(source_code: &str) -> Option<Language> {
+    let shebang = source_code.lines().next()?.strip_prefix("#!")?;
+    let mut words = shebang.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    // `env` runs the interpreter named next, after its own options, e.g. `env -S node`
+    if interpreter == "env" {
+        interpreter = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+    }
+    // Versioned interpreters, e.g. `python3.12`, are named after their language
+    let name = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    match name {
+        "node" | "nodejs" | "deno" | "bun" => Some(Language::JavaScript),
+        "ts-node" | "tsx" => Some(Language::TypeScript),
+        _ => Language::from_name(name),
+    }
+}
+
+/// Roughly the heap size of a node of a tree-sitter tree, with its slot among the children of its
+/// parent, to estimate the memory held by a tree from its number of nodes
+const TREE_NODE_SIZE: usize = 80;
+
+/// A tree of the bin, with its estimated size and when it was last used
+struct BinnedTree {
+    tree: Tree,
+    /// The version of the file the tree was parsed from, e.g. the id of its git blob, if known
+    version: Option<String>,
+    size: usize,
+    last_used: u64,
+}
+
 /// A structure that holds the history of trees.
 ///
+/// The trees are evicted, least recently used first, once there are more than `max_trees` of them
+/// or they hold more than `max_bytes`, if limited, so the files they were parsed from are parsed
+/// again in full the next time.
+///
 /// # Fields
 ///
 /// * `trees` - A `HashMap` where the key is a `String` representing the path,
 ///   and the value is a `Tree` which is of the Tree-sitter tree type.
+/// * `recency` - The paths of the trees by when they were last used, oldest first.
 pub struct TSTreesBin {
-    trees: HashMap<String, Tree>,
+    trees: HashMap<String, BinnedTree>,
+    recency: BTreeMap<u64, String>,
+    clock: u64,
+    total_size: usize,
+    max_trees: Option<usize>,
+    max_bytes: Option<usize>,
+    evictions: usize,
+}
+
+impl Default for TSTreesBin {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TSTreesBin {
     pub fn new() -> Self {
         Self {
             trees: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+            total_size: 0,
+            max_trees: None,
+            max_bytes: None,
+            evictions: 0,
         }
     }
 
-    pub fn get_trees(&self) -> &HashMap<String, Tree> {
-        &self.trees
+    /// Limit the number of trees kept and the memory they hold, evicting the trees over budget
+    pub fn set_budget(&mut self, max_trees: Option<usize>, max_bytes: Option<usize>) {
+        self.max_trees = max_trees;
+        self.max_bytes = max_bytes;
+        self.evict(None);
     }
 
     pub fn num_trees(&self) -> usize {
         self.trees.len()
     }
 
+    /// The estimated memory held by the trees, in bytes
+    pub fn get_size(&self) -> usize {
+        self.total_size
+    }
+
+    /// The number of trees evicted to stay within the budget
+    pub fn num_evictions(&self) -> usize {
+        self.evictions
+    }
+
     pub fn get_tree(&mut self, file_path: &str) -> Option<&mut Tree> {
-        self.trees.get_mut(file_path)
+        self.clock += 1;
+        let binned_tree = self.trees.get_mut(file_path)?;
+        self.recency.remove(&binned_tree.last_used);
+        self.recency.insert(self.clock, file_path.to_string());
+        binned_tree.last_used = self.clock;
+        Some(&mut binned_tree.tree)
     }
 
     pub fn delete_tree(&mut self, file_path: &str) {
-        self.trees.remove(file_path);
+        if let Some(binned_tree) = self.trees.remove(file_path) {
+            self.recency.remove(&binned_tree.last_used);
+            self.total_size -= binned_tree.size;
+        }
     }
 
+    /// Move the tree of a renamed file to its new path, with its version, to parse the file
+    /// incrementally after the rename
+    pub fn rename_tree(&mut self, old_path: &str, new_path: &str) {
+        if old_path == new_path {
+            return;
+        }
+        self.delete_tree(new_path);
+        if let Some(binned_tree) = self.trees.remove(old_path) {
+            self.recency
+                .insert(binned_tree.last_used, new_path.to_string());
+            self.trees.insert(new_path.to_string(), binned_tree);
+        }
+    }
+
+    /// The version of the file the tree of a file was parsed from, if it is known
+    pub fn get_version(&self, file_path: &str) -> Option<&str> {
+        self.trees
+            .get(file_path)
+            .and_then(|binned_tree| binned_tree.version.as_deref())
+    }
+
+    /// The limits on the number of trees and the memory they hold, if any
+    pub fn get_budget(&self) -> (Option<usize>, Option<usize>) {
+        (self.max_trees, self.max_bytes)
+    }
+
+    /// Keep the tree of a file, in place of its previous one. A tree larger than the whole memory
+    /// budget is not kept.
     pub fn insert_tree(&mut self, file_path: &str, tree: Tree) {
-        self.trees.insert(file_path.to_string(), tree);
+        self.insert_tree_version(file_path, tree, None);
     }
 
-    pub fn get_stats(&self) {
-        let trees = &self.get_trees();
-        let num_trees = self.num_trees();
+    /// Same as `insert_tree`, along with the version of the file the tree was parsed from
+    pub fn insert_tree_version(&mut self, file_path: &str, tree: Tree, version: Option<String>) {
+        self.delete_tree(file_path);
+        self.clock += 1;
+        let size = get_tree_size(&tree);
+        self.trees.insert(
+            file_path.to_string(),
+            BinnedTree {
+                tree,
+                version,
+                size,
+                last_used: self.clock,
+            },
+        );
+        self.recency.insert(self.clock, file_path.to_string());
+        self.total_size += size;
+        self.evict(Some(file_path));
+    }
 
-        let history_size = std::mem::size_of_val(&trees);
-        let entries_size: usize = trees
-            .iter()
-            .map(|(k, v)| std::mem::size_of_val(k) + std::mem::size_of_val(v))
-            .sum();
-        let total_size = history_size + entries_size;
-        println!("Number of trees in TSHistory: {}", num_trees);
-        println!("Size of the HashMap TSHistory: {} bytes", total_size);
+    /// Evict the least recently used trees until the bin is within its budget, the tree of `kept`
+    /// last
+    fn evict(&mut self, kept: Option<&str>) {
+        while self
+            .max_trees
+            .is_some_and(|max_trees| self.trees.len() > max_trees)
+            || self
+                .max_bytes
+                .is_some_and(|max_bytes| self.total_size > max_bytes)
+        {
+            let oldest = self
+                .recency
+                .values()
+                .find(|path| Some(path.as_str()) != kept)
+                .or_else(|| self.recency.values().next())
+                .cloned();
+            let Some(oldest) = oldest else {
+                break;
+            };
+            self.delete_tree(&oldest);
+            self.evictions += 1;
+        }
     }
+
+    pub fn get_stats(&self) {
+        info!("Number of trees in TSHistory: {}", self.num_trees());
+        info!(
+            "Estimated size of the trees in TSHistory: {} bytes",
+            self.get_size()
+        );
+        info!(
+            "Number of trees evicted from TSHistory: {}",
+            self.num_evictions()
+        );
+    }
+}
+
+/// Estimate the memory held by a tree, from its number of nodes
+fn get_tree_size(tree: &Tree) -> usize {
+    std::mem::size_of::<Tree>() + tree.root_node().descendant_count() * TREE_NODE_SIZE
 }
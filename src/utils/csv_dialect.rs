@@ -0,0 +1,102 @@
+use crate::error::XStatsError;
+use csv::QuoteStyle;
+
+/// The quoting styles accepted by `--csv-quote`
+pub const QUOTE_STYLES: &[&str] = &["necessary", "always", "non-numeric", "never"];
+
+/// How CSV files are written, for spreadsheets and BI tools expecting another dialect than the
+/// default one, e.g. `;` as delimiter and `,` as decimal separator in most European locales.
+#[derive(Clone, Debug)]
+pub struct CsvDialect {
+    pub delimiter: u8,
+    pub quote_style: QuoteStyle,
+    /// Replaces the `.` of decimal numbers
+    pub decimal_separator: char,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CsvDialect {
+    /// Comma-delimited, with quotes only where needed and `.` as decimal separator
+    pub fn new() -> Self {
+        CsvDialect {
+            delimiter: b',',
+            quote_style: QuoteStyle::Necessary,
+            decimal_separator: '.',
+        }
+    }
+
+    /// Build a dialect from `--csv-delimiter`, `--csv-quote` and `--decimal-separator`, keeping
+    /// the defaults for the options not given
+    pub fn from_options(
+        delimiter: Option<&str>,
+        quote_style: Option<&str>,
+        decimal_separator: Option<&str>,
+    ) -> Result<Self, XStatsError> {
+        let mut dialect = Self::new();
+        if let Some(delimiter) = delimiter {
+            dialect.delimiter = match delimiter {
+                "\\t" | "tab" => b'\t',
+                _ if delimiter.len() == 1 => delimiter.as_bytes()[0],
+                _ => {
+                    return Err(XStatsError::Config(format!(
+                        "CSV delimiter must be a single ASCII character: {}",
+                        delimiter
+                    )))
+                }
+            };
+        }
+        if let Some(quote_style) = quote_style {
+            dialect.quote_style = match quote_style {
+                "necessary" => QuoteStyle::Necessary,
+                "always" => QuoteStyle::Always,
+                "non-numeric" => QuoteStyle::NonNumeric,
+                "never" => QuoteStyle::Never,
+                _ => {
+                    return Err(XStatsError::Config(format!(
+                        "Unknown CSV quoting style {}, expected one of {}",
+                        quote_style,
+                        QUOTE_STYLES.join(", ")
+                    )))
+                }
+            };
+        }
+        if let Some(decimal_separator) = decimal_separator {
+            let mut chars = decimal_separator.chars();
+            dialect.decimal_separator = match (chars.next(), chars.next()) {
+                (Some(separator), None) => separator,
+                _ => {
+                    return Err(XStatsError::Config(format!(
+                        "Decimal separator must be a single character: {}",
+                        decimal_separator
+                    )))
+                }
+            };
+        }
+
+        // Unquoted numbers would be split in two
+        if matches!(dialect.quote_style, QuoteStyle::Never)
+            && dialect.decimal_separator as u32 == dialect.delimiter as u32
+        {
+            return Err(XStatsError::Config(
+                "Decimal separator cannot be the CSV delimiter without quoting".to_string(),
+            ));
+        }
+        Ok(dialect)
+    }
+
+    /// Format a cell, with the decimal separator of the dialect if it is a decimal number
+    pub fn format_value<'a>(&self, value: &'a str) -> std::borrow::Cow<'a, str> {
+        if self.decimal_separator != '.' && value.contains('.') && value.parse::<f64>().is_ok() {
+            value
+                .replace('.', &self.decimal_separator.to_string())
+                .into()
+        } else {
+            value.into()
+        }
+    }
+}
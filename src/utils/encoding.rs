@@ -0,0 +1,92 @@
+use crate::error::XStatsError;
+use crate::utils::logger::WarningKind;
+use log::warn;
+use std::fs;
+
+/// The encodings accepted by `--encoding`
+pub const ENCODINGS: &[&str] = &["utf-8", "latin1", "windows-1252"];
+
+/// The characters of the bytes 0x80 to 0x9F in Windows-1252, the others being those of Latin-1.
+/// The bytes left undefined are decoded as the C1 control characters, as in Latin-1.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+/// The encoding source files that are not valid UTF-8 are decoded with, e.g. those of old Java
+/// codebases with Latin-1 comments
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, the invalid bytes being replaced with `U+FFFD`.
+    #[default]
+    Utf8,
+    /// ISO-8859-1, each byte being the character of the same code point.
+    Latin1,
+    /// Windows-1252, Latin-1 with printable characters in place of most C1 controls.
+    Windows1252,
+}
+
+impl Encoding {
+    /// Get the encoding from its name, e.g. `latin1` or `iso-8859-1`
+    pub fn from_name(name: &str) -> Result<Self, XStatsError> {
+        match name.trim().to_lowercase().replace('_', "-").as_str() {
+            "utf-8" | "utf8" => Ok(Encoding::Utf8),
+            "latin1" | "latin-1" | "iso-8859-1" => Ok(Encoding::Latin1),
+            "windows-1252" | "cp1252" => Ok(Encoding::Windows1252),
+            _ => Err(XStatsError::Config(format!(
+                "Unknown encoding {}, expected one of {}",
+                name,
+                ENCODINGS.join(", ")
+            ))),
+        }
+    }
+
+    /// Decode bytes that are not valid UTF-8
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            Encoding::Utf8 => String::from_utf8_lossy(bytes).to_string(),
+            Encoding::Latin1 => bytes.iter().map(|byte| *byte as char).collect(),
+            Encoding::Windows1252 => bytes
+                .iter()
+                .map(|byte| match byte {
+                    0x80..=0x9f => WINDOWS_1252_HIGH[(byte - 0x80) as usize],
+                    _ => *byte as char,
+                })
+                .collect(),
+        }
+    }
+
+    /// Decode the content of a source file as UTF-8, or else with this encoding, in which case
+    /// a warning is logged, for the file to be analyzed rather than dropped
+    pub fn decode_source(&self, file_path: &str, bytes: Vec<u8>) -> String {
+        String::from_utf8(bytes).unwrap_or_else(|e| {
+            warn!(
+                target: WarningKind::DecodedFile.target(),
+                "{} is not valid UTF-8, decoded as {}",
+                file_path,
+                self
+            );
+            self.decode(e.as_bytes())
+        })
+    }
+
+    /// Read a source file, decoded as by `decode_source`
+    pub fn read_source(&self, file_path: &str) -> Result<String, XStatsError> {
+        let bytes = fs::read(file_path).map_err(|source| XStatsError::Read {
+            path: file_path.to_string(),
+            source,
+        })?;
+        Ok(self.decode_source(file_path, bytes))
+    }
+}
+
+impl std::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Encoding::Utf8 => "UTF-8 with replacement characters",
+            Encoding::Latin1 => "Latin-1",
+            Encoding::Windows1252 => "Windows-1252",
+        };
+        write!(f, "{}", name)
+    }
+}
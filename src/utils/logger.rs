@@ -0,0 +1,183 @@
+use crate::error::XStatsError;
+use crate::utils::format_utc_time;
+use indicatif::MultiProgress;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The kinds of warnings counted in the summary of a run, logged with their own target, e.g.
+/// `warn!(target: WarningKind::BrokenParse.target(), ...)`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WarningKind {
+    /// A file that is not valid UTF-8, decoded with the fallback encoding.
+    DecodedFile,
+    /// A file skipped for an extension no language supports.
+    UnsupportedFile,
+    /// A file with syntax errors, whose metrics may be partial.
+    BrokenParse,
+    /// A file abandoned on timeout, or an analysis cut short.
+    Timeout,
+    /// A git operation that failed, e.g. blaming a file.
+    Git,
+}
+
+impl WarningKind {
+    pub fn all() -> [WarningKind; 5] {
+        [
+            WarningKind::DecodedFile,
+            WarningKind::UnsupportedFile,
+            WarningKind::BrokenParse,
+            WarningKind::Timeout,
+            WarningKind::Git,
+        ]
+    }
+
+    pub fn target(&self) -> &'static str {
+        match self {
+            WarningKind::DecodedFile => "xstats::decoded_file",
+            WarningKind::UnsupportedFile => "xstats::unsupported_file",
+            WarningKind::BrokenParse => "xstats::broken_parse",
+            WarningKind::Timeout => "xstats::timeout",
+            WarningKind::Git => "xstats::git",
+        }
+    }
+
+    pub fn from_target(target: &str) -> Option<Self> {
+        WarningKind::all()
+            .into_iter()
+            .find(|kind| kind.target() == target)
+    }
+
+    /// The name of the kind in the summary, e.g. `2 broken parses`
+    pub fn get_label(&self, count: usize) -> String {
+        let (singular, plural) = match self {
+            WarningKind::DecodedFile => ("decoded file", "decoded files"),
+            WarningKind::UnsupportedFile => ("unsupported file", "unsupported files"),
+            WarningKind::BrokenParse => ("broken parse", "broken parses"),
+            WarningKind::Timeout => ("timeout", "timeouts"),
+            WarningKind::Git => ("git error", "git errors"),
+        };
+        format!("{} {}", count, if count == 1 { singular } else { plural })
+    }
+}
+
+/// Writes the log records of xStats to stderr, and to a log file if set, and counts the warnings
+/// by kind, whatever the level, for the summary of the run.
+///
+/// Records are written above the progress bars, if any, so that they do not garble them.
+pub struct Logger {
+    level: LevelFilter,
+    log_file: Option<Mutex<File>>,
+    multi_progress: Option<MultiProgress>,
+    counts: Mutex<BTreeMap<WarningKind, usize>>,
+}
+
+impl Logger {
+    /// A logger writing the records up to `level`, e.g. `LevelFilter::Info`
+    pub fn new(level: LevelFilter) -> Self {
+        Logger {
+            level,
+            log_file: None,
+            multi_progress: None,
+            counts: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Also append the records to the file, with their time
+    pub fn set_log_file(&mut self, file_path: &str) -> Result<(), XStatsError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_path)
+            .map_err(|e| XStatsError::write(file_path, e))?;
+        self.log_file = Some(Mutex::new(file));
+        Ok(())
+    }
+
+    /// Write the records above these progress bars
+    pub fn set_multi_progress(&mut self, multi_progress: MultiProgress) {
+        self.multi_progress = Some(multi_progress);
+    }
+
+    /// Install the logger for the rest of the process, and return it to read its counts
+    pub fn init(self) -> Result<&'static Logger, XStatsError> {
+        let logger: &'static Logger = Box::leak(Box::new(self));
+        log::set_logger(logger).map_err(|e| XStatsError::Config(e.to_string()))?;
+        // Every record is counted, only those up to the level are written
+        log::set_max_level(LevelFilter::Trace);
+        Ok(logger)
+    }
+
+    pub fn get_counts(&self) -> BTreeMap<WarningKind, usize> {
+        self.counts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// The number of warnings of each kind, e.g. `Warnings: 1 decoded file, 2 broken parses`,
+    /// if any
+    pub fn get_summary(&self) -> Option<String> {
+        let counts = self.get_counts();
+        if counts.is_empty() {
+            return None;
+        }
+        let labels: Vec<String> = counts
+            .iter()
+            .map(|(kind, count)| kind.get_label(*count))
+            .collect();
+        Some(format!("Warnings: {}", labels.join(", ")))
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level && metadata.target().to_lowercase().starts_with("xstats")
+    }
+
+    fn log(&self, record: &Record) {
+        if let Some(kind) = WarningKind::from_target(record.target()) {
+            *self
+                .counts
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .entry(kind)
+                .or_default() += 1;
+        }
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = match record.level() {
+            Level::Info => record.args().to_string(),
+            level => format!("{}: {}", level, record.args()),
+        };
+        match &self.multi_progress {
+            Some(multi_progress) => multi_progress.suspend(|| eprintln!("{}", line)),
+            None => eprintln!("{}", line),
+        }
+        if let Some(log_file) = &self.log_file {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs() as i64);
+            let mut log_file = log_file.lock().unwrap_or_else(|e| e.into_inner());
+            let _ = writeln!(
+                log_file,
+                "{} {} [{}] {}",
+                format_utc_time(now),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(log_file) = &self.log_file {
+            let _ = log_file.lock().unwrap_or_else(|e| e.into_inner()).flush();
+        }
+    }
+}
@@ -1,105 +1,667 @@
-use csv::Writer;
-use serde_json::to_writer;
+use crate::error::XStatsError;
+use crate::metrics::ThresholdViolation;
+use csv::WriterBuilder;
+use log::debug;
+use serde_json::{json, to_writer, Value};
+use std::collections::{BTreeSet, HashSet};
 use std::error::Error;
 use std::fs;
 use std::fs::read_dir;
 use std::fs::File;
+use std::io::{BufWriter, Read, Write};
 use std::path::Path;
 
+pub mod binary_codec;
+pub mod csv_dialect;
+pub mod encoding;
+pub mod logger;
+pub mod parquet;
+pub mod path_filter;
 pub mod progress_bar;
 pub mod version_control;
 
-pub fn read_file(file_path: &str) -> String {
-    fs::read_to_string(file_path).expect(&format!("Failed to read the file {}", file_path))
+use csv_dialect::CsvDialect;
+use logger::WarningKind;
+use parquet::ColumnType;
+use path_filter::PathFilter;
+
+pub fn read_file(file_path: &str) -> Result<String, XStatsError> {
+    fs::read_to_string(file_path).map_err(|source| XStatsError::Read {
+        path: file_path.to_string(),
+        source,
+    })
+}
+
+/// Read the first `size` bytes of a file as text, to recognize its content, or nothing if it
+/// cannot be read or is binary
+pub fn read_file_head(file_path: &str, size: usize) -> Option<String> {
+    let mut head = Vec::new();
+    File::open(file_path)
+        .and_then(|file| file.take(size as u64).read_to_end(&mut head))
+        .ok()?;
+    if head.contains(&0) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&head).to_string())
 }
 
 pub fn get_file_name(file_path: &str) -> String {
     Path::new(file_path)
         .file_name()
-        .unwrap()
-        .to_str()
-        .unwrap()
-        .to_string()
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .unwrap_or_else(|| file_path.to_string())
 }
 
 pub fn get_file_extension(file_path: &str) -> String {
     if let Some(extension) = Path::new(file_path).extension() {
-        format!(".{}", extension.to_str().unwrap())
+        format!(".{}", extension.to_string_lossy())
     } else {
         String::new()
     }
 }
 
-pub fn traverse_path(dir_path: &str) -> Result<Vec<String>, String> {
-    let mut files = Vec::new();
+/// 64-bit FNV-1a, which unlike `DefaultHasher` is stable across Rust releases
+pub fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Parse a UTC date, `YYYY-MM-DD`, or time, `YYYY-MM-DDTHH:MM:SSZ` as written by
+/// `format_utc_time`, into seconds since the epoch
+pub fn parse_utc_time(time: &str) -> Option<i64> {
+    let (date, time) = match time.split_once('T') {
+        Some((date, time)) => (date, Some(time.strip_suffix('Z')?)),
+        None => (time, None),
+    };
+    let parse_fields = |text: &str, separator: char| -> Option<Vec<i64>> {
+        text.split(separator)
+            .map(|field| field.parse::<i64>().ok())
+            .collect()
+    };
+    let [year, month, day] = parse_fields(date, '-')?[..] else {
+        return None;
+    };
+    let time = match time {
+        Some(time) => parse_fields(time, ':')?,
+        None => vec![0, 0, 0],
+    };
+    let [hours, minutes, seconds] = time[..] else {
+        return None;
+    };
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || !(0..24).contains(&hours)
+        || !(0..60).contains(&minutes)
+        || !(0..60).contains(&seconds)
+    {
+        return None;
+    }
+
+    // Days since 1970-01-01 from the civil date, the inverse of `format_utc_time`
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_index = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+    Some(days * 86400 + hours * 3600 + minutes * 60 + seconds)
+}
+
+/// Format a time in seconds since the epoch as an RFC 3339 UTC timestamp, e.g.
+/// `2024-05-01T12:30:00Z`
+pub fn format_utc_time(seconds: i64) -> String {
+    let days = seconds.div_euclid(86400);
+    let time = seconds.rem_euclid(86400);
+
+    // Civil date from the days since 1970-01-01, in eras of 400 years starting on March 1st
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// The accepted values of `--symlinks`
+pub const SYMLINK_POLICIES: &[&str] = &["skip", "follow"];
+
+/// How the symbolic links under the target are traversed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Leave out the links, to files as to directories, as in history mode.
+    #[default]
+    Skip,
+    /// List the files and directories linked to, even outside the target, each of them once.
+    Follow,
+}
+
+impl SymlinkPolicy {
+    pub fn from_name(name: &str) -> Result<Self, XStatsError> {
+        match name {
+            "skip" => Ok(SymlinkPolicy::Skip),
+            "follow" => Ok(SymlinkPolicy::Follow),
+            _ => Err(XStatsError::Config(format!(
+                "Unknown symlink policy {}, expected one of {}",
+                name,
+                SYMLINK_POLICIES.join(", ")
+            ))),
+        }
+    }
+}
+
+/// How the files under the target are listed
+#[derive(Clone, Debug, Default)]
+pub struct TraversalOptions {
+    pub symlinks: SymlinkPolicy,
+    /// The number of directories the files may be nested in below the target, if limited
+    pub max_depth: Option<usize>,
+}
+
+/// The number of bytes sniffed from a file for a NUL byte, which text files never contain, as
+/// done by git
+const BINARY_SNIFF_SIZE: u64 = 8000;
+
+/// List the files under a path, or the path itself if it is a file, in a stable order.
+///
+/// Only the files kept by the filter are listed, matched on their path relative to `dir_path`.
+pub fn traverse_path(dir_path: &str, filter: &PathFilter) -> Result<Vec<String>, XStatsError> {
+    traverse_path_with_options(dir_path, filter, &TraversalOptions::default())
+}
+
+/// Same as `traverse_path`, following the symbolic links and limiting the depth as set in the
+/// options.
+///
+/// Each file or directory is listed once, whatever the links to it, so that cycles of links end,
+/// and binary files are left out.
+pub fn traverse_path_with_options(
+    dir_path: &str,
+    filter: &PathFilter,
+    options: &TraversalOptions,
+) -> Result<Vec<String>, XStatsError> {
     let path = Path::new(dir_path);
 
     if !path.exists() {
-        return Err(format!("Path does not exist: {}", dir_path));
+        return Err(XStatsError::PathNotFound(dir_path.to_string()));
     }
 
+    let mut files = Vec::new();
     if path.is_file() {
-        files.push(path.to_str().unwrap().to_string());
+        if filter.matches(&get_file_name(dir_path)) {
+            files.push(path_to_string(path)?);
+        }
     } else if path.is_dir() {
-        for entry in
-            read_dir(path).map_err(|e| format!("Failed to read directory '{}': {}", dir_path, e))?
-        {
-            let entry =
-                entry.map_err(|e| format!("Failed to read entry in '{}': {}", dir_path, e))?;
-            let path = entry.path();
-
-            if path.is_dir() {
-                files.extend(traverse_path(path.to_str().unwrap())?);
+        let mut visited = HashSet::new();
+        if let Ok(metadata) = fs::metadata(path) {
+            visited.insert(get_file_id(path, &metadata));
+        }
+        let mut traversal = Traversal {
+            root: path,
+            filter,
+            options,
+            visited,
+            files: &mut files,
+        };
+        traversal.traverse_dir(path, 0)?;
+    }
+
+    Ok(files)
+}
+
+/// The state of a traversal of the target directory
+struct Traversal<'a> {
+    root: &'a Path,
+    filter: &'a PathFilter,
+    options: &'a TraversalOptions,
+    /// The files and directories listed so far, by their id
+    visited: HashSet<String>,
+    files: &'a mut Vec<String>,
+}
+
+impl Traversal<'_> {
+    fn traverse_dir(&mut self, dir: &Path, depth: usize) -> Result<(), XStatsError> {
+        let mut entries = Vec::new();
+        let read_error = |source| XStatsError::Read {
+            path: dir.to_string_lossy().to_string(),
+            source,
+        };
+        for entry in read_dir(dir).map_err(read_error)? {
+            entries.push(entry.map_err(read_error)?.path());
+        }
+        // Sort entries so that the output order does not depend on the file system
+        entries.sort();
+
+        for path in entries {
+            let is_symlink = fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_symlink());
+            if is_symlink && self.options.symlinks == SymlinkPolicy::Skip {
+                debug!("Skipped {}: symbolic link", path.display());
+                continue;
+            }
+            // Broken links are left out
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            // Hard links and links to what is already listed are only listed once
+            if !self.visited.insert(get_file_id(&path, &metadata)) {
+                debug!("Skipped {}: already listed", path.display());
+                continue;
+            }
+
+            if metadata.is_dir() {
+                if self
+                    .options
+                    .max_depth
+                    .is_some_and(|max_depth| depth >= max_depth)
+                {
+                    debug!("Skipped {}: deeper than the maximum depth", path.display());
+                    continue;
+                }
+                self.traverse_dir(&path, depth + 1)?;
             } else {
-                files.push(path.to_str().unwrap().to_string());
+                let relative_path = path.strip_prefix(self.root).unwrap_or(&path);
+                if !self.filter.matches(&path_to_string(relative_path)?) {
+                    continue;
+                }
+                if is_binary_file(&path) {
+                    // Files without an extension, e.g. git objects, are not even candidates
+                    if path.extension().is_some() {
+                        debug!(
+                            target: WarningKind::UnsupportedFile.target(),
+                            "Skipped {}: binary file",
+                            path.display()
+                        );
+                    }
+                    continue;
+                }
+                self.files.push(path_to_string(&path)?);
             }
         }
+        Ok(())
     }
+}
 
-    Ok(files)
+/// The id of a file or directory, the same for all the links to it
+#[cfg(unix)]
+fn get_file_id(_path: &Path, metadata: &fs::Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+    format!("{}:{}", metadata.dev(), metadata.ino())
+}
+
+/// The id of a file or directory, the same for all the symbolic links to it
+#[cfg(not(unix))]
+fn get_file_id(path: &Path, _metadata: &fs::Metadata) -> String {
+    path.canonicalize()
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .to_string()
 }
 
-pub fn save_to_csv(file_path: &str, data: Vec<Vec<String>>) -> Result<(), Box<dyn Error>> {
+/// Check whether a file is binary from the NUL bytes at its start. Files that cannot be read are
+/// left to fail once analyzed.
+fn is_binary_file(path: &Path) -> bool {
+    let mut head = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(BINARY_SNIFF_SIZE).read_to_end(&mut head))
+        .is_ok_and(|_| head.contains(&0))
+}
+
+fn path_to_string(path: &Path) -> Result<String, XStatsError> {
+    path.to_str()
+        .map(|path| path.to_string())
+        .ok_or_else(|| XStatsError::InvalidPath(path.to_string_lossy().to_string()))
+}
+
+/// Write a file through a temporary file next to it, renamed over it once complete, so that an
+/// interrupted run never leaves a partially written file behind.
+///
+/// Fails with `XStatsError::OutputExists` if the file already exists, unless `overwrite` is set.
+pub fn write_atomically<E>(
+    file_path: &str,
+    overwrite: bool,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<(), E>,
+) -> Result<(), XStatsError>
+where
+    E: Into<Box<dyn Error + Send + Sync>>,
+{
     let path = Path::new(file_path);
+    if !overwrite && path.exists() {
+        return Err(XStatsError::OutputExists(file_path.to_string()));
+    }
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+        fs::create_dir_all(parent).map_err(|e| XStatsError::write(file_path, e))?;
     }
 
-    let mut writer = Writer::from_path(file_path)?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        get_file_name(file_path),
+        std::process::id()
+    ));
+    let result = File::create(&temp_path)
+        .map_err(|e| XStatsError::write(file_path, e))
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            write(&mut writer).map_err(|e| XStatsError::write(file_path, e))?;
+            let file = writer
+                .into_inner()
+                .map_err(|e| XStatsError::write(file_path, e.into_error()))?;
+            file.sync_all()
+                .map_err(|e| XStatsError::write(file_path, e))
+        })
+        .and_then(|()| fs::rename(&temp_path, path).map_err(|e| XStatsError::write(file_path, e)));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+pub fn save_to_csv(
+    file_path: &str,
+    header: &[String],
+    rows: &[Vec<String>],
+    dialect: &CsvDialect,
+    overwrite: bool,
+) -> Result<(), XStatsError> {
+    write_atomically(file_path, overwrite, |file| {
+        write_csv(file, header, rows, dialect)
+    })
+}
+
+/// Write a table as CSV in the given dialect, e.g. to a file or to stdout
+pub fn write_csv(
+    writer: impl Write,
+    header: &[String],
+    rows: &[Vec<String>],
+    dialect: &CsvDialect,
+) -> std::io::Result<()> {
+    let mut writer = WriterBuilder::new()
+        .delimiter(dialect.delimiter)
+        .quote_style(dialect.quote_style)
+        .from_writer(writer);
 
     // Write rows to the CSV file
-    for row in data {
-        writer.write_record(&row)?;
+    writer.write_record(header)?;
+    for row in rows {
+        writer.write_record(
+            row.iter()
+                .map(|value| dialect.format_value(value).into_owned()),
+        )?;
     }
 
     // Flush to ensure all data is written to the file
-    writer.flush()?;
+    writer.flush()
+}
+
+/// The version of the structure of the JSON tables, increased whenever it changes
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Save a table as JSON records, typing each column as in Parquet so that numbers and booleans
+/// are not strings
+pub fn save_to_json(
+    file_path: &str,
+    header: &[String],
+    rows: &[Vec<String>],
+    overwrite: bool,
+) -> Result<(), XStatsError> {
+    save_records_to_json(file_path, &get_json_records(header, rows), overwrite)
+}
+
+/// The rows of a table as JSON records, typing each column as in Parquet
+pub fn get_json_records(header: &[String], rows: &[Vec<String>]) -> Vec<Value> {
+    let column_types: Vec<ColumnType> = (0..header.len())
+        .map(|idx| {
+            ColumnType::infer(
+                rows.iter()
+                    .map(|row| row.get(idx).map_or("", |value| value.as_str())),
+            )
+        })
+        .collect();
+    rows.iter()
+        .map(|row| {
+            let mut map = serde_json::Map::new();
+            for ((name, value), column_type) in header.iter().zip(row).zip(&column_types) {
+                map.insert(name.clone(), column_type.to_json(value));
+            }
+            Value::Object(map)
+        })
+        .collect()
+}
+
+/// Save typed records along with the schema version, e.g. `{"schema_version": 1, "rows": [...]}`
+pub fn save_records_to_json(
+    file_path: &str,
+    records: &[Value],
+    overwrite: bool,
+) -> Result<(), XStatsError> {
+    let json_data = get_versioned_records(records);
+    write_atomically(file_path, overwrite, |file| to_writer(file, &json_data))
+}
+
+/// Typed records along with the schema version, as saved in the JSON and YAML formats
+pub fn get_versioned_records(records: &[Value]) -> Value {
+    json!({
+        "schema_version": JSON_SCHEMA_VERSION,
+        "rows": records,
+    })
+}
+
+/// Save typed records as JSON Lines, one record per line without any wrapper, e.g. for jq or
+/// BigQuery
+pub fn save_records_to_jsonl(
+    file_path: &str,
+    records: &[Value],
+    overwrite: bool,
+) -> Result<(), XStatsError> {
+    write_atomically(file_path, overwrite, |file| write_jsonl(file, records))
+}
+
+/// Write typed records as JSON Lines, e.g. to a file or to stdout
+pub fn write_jsonl(
+    writer: &mut impl Write,
+    records: &[Value],
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    for record in records {
+        to_writer(&mut *writer, record)?;
+        writeln!(writer)?;
+    }
     Ok(())
 }
 
-pub fn save_to_json(file_path: &str, data: Vec<Vec<String>>) -> Result<(), Box<dyn Error>> {
-    let json_data: Vec<serde_json::Value> = if data.is_empty() {
-        Vec::new()
-    } else {
-        let headers = data[0].clone();
-        data[1..]
-            .iter()
-            .map(|row| {
-                let mut map = serde_json::Map::new();
-                for (header, value) in headers.iter().zip(row) {
-                    map.insert(header.clone(), serde_json::Value::String(value.clone()));
-                }
-                serde_json::Value::Object(map)
-            })
-            .collect()
-    };
+/// Save typed records as YAML along with the schema version, as in the JSON format
+pub fn save_records_to_yaml(
+    file_path: &str,
+    records: &[Value],
+    overwrite: bool,
+) -> Result<(), XStatsError> {
+    let yaml_data = get_versioned_records(records);
+    write_atomically(file_path, overwrite, |file| {
+        serde_yaml::to_writer(file, &yaml_data)
+    })
+}
 
-    let path = Path::new(file_path);
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+/// Save a table as XML, one `<row>` element per row with an element per column, e.g.
+/// `<table name="metrics" schema_version="1"><row><cc>3</cc></row></table>`. Empty values are
+/// saved as empty elements.
+pub fn save_to_xml(
+    file_path: &str,
+    name: &str,
+    header: &[String],
+    rows: &[Vec<String>],
+    overwrite: bool,
+) -> Result<(), XStatsError> {
+    let xml = get_xml(name, header, rows);
+    write_atomically(file_path, overwrite, |file| write!(file, "{}", xml))
+}
+
+/// A table as an XML document, as saved in the XML format
+pub fn get_xml(name: &str, header: &[String], rows: &[Vec<String>]) -> String {
+    let tags: Vec<String> = header.iter().map(|column| get_xml_tag(column)).collect();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<table name=\"{}\" schema_version=\"{}\">\n",
+        escape_xml(name),
+        JSON_SCHEMA_VERSION
+    ));
+    for row in rows {
+        xml.push_str("  <row>\n");
+        for (tag, value) in tags.iter().zip(row) {
+            if value.is_empty() {
+                xml.push_str(&format!("    <{}/>\n", tag));
+            } else {
+                xml.push_str(&format!("    <{}>{}</{}>\n", tag, escape_xml(value), tag));
+            }
+        }
+        xml.push_str("  </row>\n");
     }
+    xml.push_str("</table>\n");
+    xml
+}
 
-    let file = File::create(file_path)?;
-    to_writer(file, &json_data)?;
-    Ok(())
+/// The name of a column as an XML element name: characters other than letters, digits, `_`,
+/// `-` and `.` are replaced with `_`, and names not starting with a letter or `_` are prefixed
+/// with `_`
+fn get_xml_tag(column: &str) -> String {
+    let mut tag: String = column
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !tag.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        tag.insert(0, '_');
+    }
+    tag
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Read the records of a table saved as JSON, e.g. `metrics.json`, checking its schema version
+pub fn load_records_from_json(file_path: &str) -> Result<Vec<Value>, XStatsError> {
+    let invalid = |reason: String| XStatsError::InvalidTable {
+        path: file_path.to_string(),
+        reason,
+    };
+    let json_data: Value =
+        serde_json::from_str(&read_file(file_path)?).map_err(|e| invalid(e.to_string()))?;
+    let schema_version = json_data["schema_version"].as_u64();
+    if schema_version != Some(JSON_SCHEMA_VERSION as u64) {
+        return Err(invalid(format!(
+            "schema version {} instead of {}",
+            json_data["schema_version"], JSON_SCHEMA_VERSION
+        )));
+    }
+    match json_data.get("rows") {
+        Some(Value::Array(records)) => Ok(records.clone()),
+        _ => Err(invalid("no rows".to_string())),
+    }
+}
+
+/// Save the threshold violations as a SARIF log, for CI tools such as GitHub code scanning to
+/// annotate the offending code in place
+pub fn save_to_sarif(
+    file_path: &str,
+    violations: &[ThresholdViolation],
+    overwrite: bool,
+) -> Result<(), XStatsError> {
+    let sarif_log = get_sarif_log(violations);
+    write_atomically(file_path, overwrite, |file| {
+        serde_json::to_writer_pretty(file, &sarif_log)
+    })
+}
+
+/// A SARIF 2.1.0 log with one rule per metric and one result per violation
+pub fn get_sarif_log(violations: &[ThresholdViolation]) -> Value {
+    let metrics: Vec<&str> = violations
+        .iter()
+        .map(|violation| violation.metric.as_str())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let rules: Vec<Value> = metrics
+        .iter()
+        .map(|metric| {
+            json!({
+                "id": metric,
+                "shortDescription": { "text": format!("{} exceeds its threshold", metric) },
+            })
+        })
+        .collect();
+
+    let results: Vec<Value> = violations
+        .iter()
+        .map(|violation| {
+            let meta_data = &violation.meta_data;
+            let uri = meta_data.file_path.replace('\\', "/");
+            json!({
+                "ruleId": violation.metric,
+                "ruleIndex": metrics.iter().position(|metric| *metric == violation.metric),
+                "level": "warning",
+                "message": {
+                    "text": format!(
+                        "{} {} has {} = {}, above the threshold of {}",
+                        meta_data.node_type,
+                        meta_data.node_name,
+                        violation.metric,
+                        violation.value,
+                        violation.threshold
+                    ),
+                },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": uri.trim_start_matches("./") },
+                        "region": {
+                            "startLine": meta_data.start_row,
+                            "startColumn": meta_data.start_col,
+                            "endLine": meta_data.end_row,
+                            "endColumn": meta_data.end_col,
+                        },
+                    },
+                }],
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "xStats",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/gautam-shetty/xStats",
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
 }
@@ -0,0 +1,381 @@
+use super::write_atomically;
+use crate::error::XStatsError;
+use serde_json::Value;
+use std::io::Write;
+
+const MAGIC: &[u8] = b"PAR1";
+
+/// The physical type of a column, inferred from its values or pinned by the schema of its table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    Boolean,
+    Int64,
+    Double,
+    String,
+}
+
+impl ColumnType {
+    /// The narrowest type all the non-empty values fit, strings for columns without any
+    pub fn infer<'a>(values: impl Iterator<Item = &'a str>) -> ColumnType {
+        let mut values = values.filter(|value| !value.is_empty());
+        let Some(first) = values.next() else {
+            return ColumnType::String;
+        };
+        let mut candidates = [ColumnType::Boolean, ColumnType::Int64, ColumnType::Double].to_vec();
+        for value in std::iter::once(first).chain(values) {
+            candidates.retain(|candidate| candidate.accepts(value));
+            if candidates.is_empty() {
+                return ColumnType::String;
+            }
+        }
+        candidates[0]
+    }
+
+    /// The type of a typed value, e.g. of a field of the metrics, strings for nulls
+    pub fn from_value(value: &Value) -> ColumnType {
+        match value {
+            Value::Bool(_) => ColumnType::Boolean,
+            Value::Number(number) if number.is_f64() => ColumnType::Double,
+            Value::Number(_) => ColumnType::Int64,
+            _ => ColumnType::String,
+        }
+    }
+
+    fn accepts(&self, value: &str) -> bool {
+        match self {
+            ColumnType::Boolean => value == "true" || value == "false",
+            ColumnType::Int64 => value.parse::<i64>().is_ok(),
+            ColumnType::Double => value.parse::<f64>().is_ok_and(|value| value.is_finite()),
+            ColumnType::String => true,
+        }
+    }
+
+    /// A value of the column as JSON, empty values being nulls unless they are strings
+    pub fn to_json(&self, value: &str) -> Value {
+        let parsed = match self {
+            ColumnType::Boolean => value.parse::<bool>().ok().map(Value::from),
+            ColumnType::Int64 => value.parse::<i64>().ok().map(Value::from),
+            ColumnType::Double => value.parse::<f64>().ok().map(Value::from),
+            ColumnType::String => Some(Value::from(value)),
+        };
+        parsed.unwrap_or(Value::Null)
+    }
+
+    /// The `Type` of the Parquet format
+    fn get_physical_type(&self) -> i32 {
+        match self {
+            ColumnType::Boolean => 0,
+            ColumnType::Int64 => 2,
+            ColumnType::Double => 5,
+            ColumnType::String => 6,
+        }
+    }
+
+    /// Encode the values of a column with the plain encoding
+    fn encode_plain(&self, values: &[&str]) -> Vec<u8> {
+        let mut data = Vec::new();
+        match self {
+            ColumnType::Boolean => {
+                data.resize(values.len().div_ceil(8), 0);
+                for (i, value) in values.iter().enumerate() {
+                    if *value == "true" {
+                        data[i / 8] |= 1 << (i % 8);
+                    }
+                }
+            }
+            ColumnType::Int64 => {
+                for value in values {
+                    data.extend(value.parse::<i64>().unwrap_or_default().to_le_bytes());
+                }
+            }
+            ColumnType::Double => {
+                for value in values {
+                    data.extend(value.parse::<f64>().unwrap_or_default().to_le_bytes());
+                }
+            }
+            ColumnType::String => {
+                for value in values {
+                    data.extend((value.len() as u32).to_le_bytes());
+                    data.extend(value.as_bytes());
+                }
+            }
+        }
+        data
+    }
+}
+
+/// Infer the type of each column of a table from its values, for tables without a schema
+pub fn infer_column_types(header: &[String], rows: &[Vec<String>]) -> Vec<ColumnType> {
+    (0..header.len())
+        .map(|idx| {
+            ColumnType::infer(
+                rows.iter()
+                    .map(|row| row.get(idx).map_or("", |value| value.as_str())),
+            )
+        })
+        .collect()
+}
+
+/// Save a table as a Parquet file, with one row group and an uncompressed page per column.
+///
+/// Columns of `true` or `false` are booleans, of integers 64-bit integers, of numbers doubles,
+/// and the others UTF-8 strings, so that data-science tools load them with their proper type.
+/// The types are those of the schema of the table, for the tables of every commit to share it,
+/// or else inferred from the values with `infer_column_types`.
+pub fn save_to_parquet(
+    file_path: &str,
+    header: &[String],
+    rows: &[Vec<String>],
+    column_types: &[ColumnType],
+    overwrite: bool,
+) -> Result<(), XStatsError> {
+    let content = encode_parquet(header, rows, column_types);
+    write_atomically(file_path, overwrite, |file| file.write_all(&content))
+}
+
+pub fn encode_parquet(
+    header: &[String],
+    rows: &[Vec<String>],
+    column_types: &[ColumnType],
+) -> Vec<u8> {
+    debug_assert_eq!(header.len(), column_types.len());
+    let mut content = MAGIC.to_vec();
+    let mut column_chunks = Vec::new();
+    for (idx, (name, column_type)) in header.iter().zip(column_types).enumerate() {
+        let values: Vec<&str> = rows
+            .iter()
+            .map(|row| row.get(idx).map_or("", |value| value.as_str()))
+            .collect();
+        // Columns other than strings are optional whatever their values, for their schema not
+        // to depend on them: empty values, or those not of the type, are nulls
+        let page_data = if *column_type == ColumnType::String {
+            column_type.encode_plain(&values)
+        } else {
+            let values: Vec<&str> = values
+                .into_iter()
+                .map(|value| {
+                    if column_type.accepts(value) {
+                        value
+                    } else {
+                        ""
+                    }
+                })
+                .collect();
+            let present: Vec<&str> = values
+                .iter()
+                .copied()
+                .filter(|value| !value.is_empty())
+                .collect();
+            let definition_levels = encode_definition_levels(&values);
+            let mut page_data = (definition_levels.len() as u32).to_le_bytes().to_vec();
+            page_data.extend(definition_levels);
+            page_data.extend(column_type.encode_plain(&present));
+            page_data
+        };
+
+        let mut page_header = ThriftWriter::new();
+        page_header.write_i32(1, 0); // DATA_PAGE
+        page_header.write_i32(2, page_data.len() as i32);
+        page_header.write_i32(3, page_data.len() as i32);
+        page_header.begin_struct(5);
+        page_header.write_i32(1, rows.len() as i32);
+        page_header.write_i32(2, 0); // PLAIN
+        page_header.write_i32(3, 3); // RLE, for the definition levels
+        page_header.write_i32(4, 3); // RLE, for the repetition levels there are none of
+        page_header.end_struct();
+        page_header.end_struct();
+
+        let data_page_offset = content.len() as i64;
+        content.extend(&page_header.buffer);
+        content.extend(&page_data);
+        let chunk_size = (page_header.buffer.len() + page_data.len()) as i64;
+
+        column_chunks.push((name, data_page_offset, chunk_size, rows.len() as i64));
+    }
+
+    let mut metadata = ThriftWriter::new();
+    metadata.write_i32(1, 1);
+    metadata.begin_list(2, ThriftWriter::STRUCT, header.len() + 1);
+    metadata.begin_element();
+    metadata.write_binary(4, b"schema");
+    metadata.write_i32(5, header.len() as i32);
+    metadata.end_struct();
+    for (name, column_type) in header.iter().zip(column_types) {
+        let is_optional = *column_type != ColumnType::String;
+        metadata.begin_element();
+        metadata.write_i32(1, column_type.get_physical_type());
+        metadata.write_i32(3, if is_optional { 1 } else { 0 }); // OPTIONAL or REQUIRED
+        metadata.write_binary(4, name.as_bytes());
+        if *column_type == ColumnType::String {
+            metadata.write_i32(6, 0); // UTF8
+            metadata.begin_struct(10);
+            metadata.begin_struct(1); // STRING
+            metadata.end_struct();
+            metadata.end_struct();
+        }
+        metadata.end_struct();
+    }
+    metadata.write_i64(3, rows.len() as i64);
+    metadata.begin_list(4, ThriftWriter::STRUCT, 1);
+    metadata.begin_element();
+    metadata.begin_list(1, ThriftWriter::STRUCT, column_chunks.len());
+    let mut total_byte_size = 0;
+    for ((name, data_page_offset, chunk_size, num_values), column_type) in
+        column_chunks.iter().zip(column_types)
+    {
+        metadata.begin_element();
+        metadata.write_i64(2, *data_page_offset);
+        metadata.begin_struct(3);
+        metadata.write_i32(1, column_type.get_physical_type());
+        metadata.begin_list(2, ThriftWriter::I32, 2);
+        metadata.write_list_i32(0); // PLAIN
+        metadata.write_list_i32(3); // RLE
+        metadata.begin_list(3, ThriftWriter::BINARY, 1);
+        metadata.write_list_binary(name.as_bytes());
+        metadata.write_i32(4, 0); // UNCOMPRESSED
+        metadata.write_i64(5, *num_values);
+        metadata.write_i64(6, *chunk_size);
+        metadata.write_i64(7, *chunk_size);
+        metadata.write_i64(9, *data_page_offset);
+        metadata.end_struct();
+        metadata.end_struct();
+        total_byte_size += chunk_size;
+    }
+    metadata.write_i64(2, total_byte_size);
+    metadata.write_i64(3, rows.len() as i64);
+    metadata.end_struct();
+    metadata.write_binary(
+        6,
+        format!("xStats version {}", env!("CARGO_PKG_VERSION")).as_bytes(),
+    );
+    metadata.end_struct();
+
+    content.extend(&metadata.buffer);
+    content.extend((metadata.buffer.len() as u32).to_le_bytes());
+    content.extend(MAGIC);
+    content
+}
+
+/// Encode whether each value is present, i.e. not empty, as bit-packed definition levels
+fn encode_definition_levels(values: &[&str]) -> Vec<u8> {
+    let group_count = values.len().div_ceil(8);
+    let mut levels = Vec::new();
+    let mut header = ((group_count as u64) << 1) | 1;
+    while header >= 0x80 {
+        levels.push(header as u8 | 0x80);
+        header >>= 7;
+    }
+    levels.push(header as u8);
+    for group in values.chunks(8) {
+        let bits = group
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| !value.is_empty())
+            .fold(0u8, |bits, (i, _)| bits | 1 << i);
+        levels.push(bits);
+    }
+    levels
+}
+
+/// Writes structs with the Thrift compact protocol, the encoding of the Parquet metadata.
+///
+/// Fields must be written in increasing order of id within a struct, and every struct is closed
+/// with `end_struct`, the top-level one included.
+struct ThriftWriter {
+    buffer: Vec<u8>,
+    /// The id of the last field written in each of the structs being written
+    last_field_ids: Vec<i16>,
+}
+
+impl ThriftWriter {
+    const I32: u8 = 5;
+    const I64: u8 = 6;
+    const BINARY: u8 = 8;
+    const LIST: u8 = 9;
+    const STRUCT: u8 = 12;
+
+    fn new() -> Self {
+        ThriftWriter {
+            buffer: Vec::new(),
+            last_field_ids: vec![0],
+        }
+    }
+
+    fn write_field_header(&mut self, field_id: i16, field_type: u8) {
+        let last_field_id = self
+            .last_field_ids
+            .last_mut()
+            .expect("field outside of a struct");
+        let delta = field_id - std::mem::replace(last_field_id, field_id);
+        if (1..=15).contains(&delta) {
+            self.buffer.push((delta as u8) << 4 | field_type);
+        } else {
+            self.buffer.push(field_type);
+            self.write_varint(zigzag(field_id as i64));
+        }
+    }
+
+    fn write_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buffer.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.buffer.push(value as u8);
+    }
+
+    fn write_i32(&mut self, field_id: i16, value: i32) {
+        self.write_field_header(field_id, Self::I32);
+        self.write_varint(zigzag(value as i64));
+    }
+
+    fn write_i64(&mut self, field_id: i16, value: i64) {
+        self.write_field_header(field_id, Self::I64);
+        self.write_varint(zigzag(value));
+    }
+
+    fn write_binary(&mut self, field_id: i16, value: &[u8]) {
+        self.write_field_header(field_id, Self::BINARY);
+        self.write_list_binary(value);
+    }
+
+    fn begin_struct(&mut self, field_id: i16) {
+        self.write_field_header(field_id, Self::STRUCT);
+        self.last_field_ids.push(0);
+    }
+
+    /// Start a struct element of a list
+    fn begin_element(&mut self) {
+        self.last_field_ids.push(0);
+    }
+
+    /// Write the stop field of the current struct
+    fn end_struct(&mut self) {
+        self.buffer.push(0);
+        self.last_field_ids.pop();
+    }
+
+    /// Start a list field, whose elements are written next, struct elements between
+    /// `begin_element` and `end_struct`
+    fn begin_list(&mut self, field_id: i16, element_type: u8, size: usize) {
+        self.write_field_header(field_id, Self::LIST);
+        if size < 15 {
+            self.buffer.push((size as u8) << 4 | element_type);
+        } else {
+            self.buffer.push(0xf0 | element_type);
+            self.write_varint(size as u64);
+        }
+    }
+
+    fn write_list_i32(&mut self, value: i32) {
+        self.write_varint(zigzag(value as i64));
+    }
+
+    fn write_list_binary(&mut self, value: &[u8]) {
+        self.write_varint(value.len() as u64);
+        self.buffer.extend(value);
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
@@ -0,0 +1,67 @@
+use crate::error::XStatsError;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+/// Selects the files to analyze from `--include` and `--exclude` glob patterns.
+///
+/// Patterns are matched against paths relative to the target, where `*` does not cross
+/// directories and `**` does, e.g. `**/test/**`. A file is kept if it matches one of the include
+/// patterns, or if there are none, and none of the exclude patterns.
+#[derive(Clone, Debug)]
+pub struct PathFilter {
+    pub include_patterns: Vec<String>,
+    pub exclude_patterns: Vec<String>,
+    include_set: GlobSet,
+    exclude_set: GlobSet,
+}
+
+impl Default for PathFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PathFilter {
+    /// A filter keeping all the files
+    pub fn new() -> Self {
+        PathFilter {
+            include_patterns: Vec::new(),
+            exclude_patterns: Vec::new(),
+            include_set: GlobSet::empty(),
+            exclude_set: GlobSet::empty(),
+        }
+    }
+
+    pub fn from_patterns(include: &[String], exclude: &[String]) -> Result<Self, XStatsError> {
+        Ok(PathFilter {
+            include_patterns: include.to_vec(),
+            exclude_patterns: exclude.to_vec(),
+            include_set: build_glob_set(include, true)?,
+            exclude_set: build_glob_set(exclude, true)?,
+        })
+    }
+
+    /// Check whether a file is kept, from its path relative to the target
+    pub fn matches(&self, relative_path: &str) -> bool {
+        let relative_path = relative_path.trim_start_matches("./");
+        (self.include_patterns.is_empty() || self.include_set.is_match(relative_path))
+            && !self.exclude_set.is_match(relative_path)
+    }
+}
+
+/// Compile glob patterns, where `*` crosses `/` unless `literal_separator` is set
+pub(crate) fn build_glob_set(
+    patterns: &[String],
+    literal_separator: bool,
+) -> Result<GlobSet, XStatsError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(literal_separator)
+            .build()
+            .map_err(|e| XStatsError::Config(format!("Invalid glob {}: {}", pattern, e)))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| XStatsError::Config(e.to_string()))
+}
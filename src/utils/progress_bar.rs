@@ -1,13 +1,33 @@
+use crate::core::ProgressEvent;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::sync::Mutex;
 
+/// Renders the progress events of an analysis as progress bars: one of the commits, in history
+/// mode, above one of the files being analyzed
 pub struct CustomProgressBar {
     pub mp: MultiProgress,
+    commits_bar: Mutex<Option<ProgressBar>>,
+    files_bar: Mutex<Option<ProgressBar>>,
+}
+
+impl Default for CustomProgressBar {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CustomProgressBar {
     pub fn new() -> Self {
-        let multi_progress = MultiProgress::new();
-        CustomProgressBar { mp: multi_progress }
+        Self::from_multi_progress(MultiProgress::new())
+    }
+
+    /// Draw the bars in the given set of bars, e.g. one the logs are written above
+    pub fn from_multi_progress(multi_progress: MultiProgress) -> Self {
+        CustomProgressBar {
+            mp: multi_progress,
+            commits_bar: Mutex::new(None),
+            files_bar: Mutex::new(None),
+        }
     }
 
     pub fn generate_commits_bar(&self, length: u64) -> ProgressBar {
@@ -31,4 +51,47 @@ impl CustomProgressBar {
         );
         pb
     }
+
+    /// Update the bars from an event, warnings being left to the end of the run
+    pub fn handle(&self, event: &ProgressEvent) {
+        let mut commits_bar = self.commits_bar.lock().unwrap_or_else(|e| e.into_inner());
+        let mut files_bar = self.files_bar.lock().unwrap_or_else(|e| e.into_inner());
+        match event {
+            ProgressEvent::CommitsStarted { total } => {
+                *commits_bar = Some(self.generate_commits_bar(*total as u64));
+            }
+            ProgressEvent::CommitDone { commit_id } => {
+                if let Some(pb) = commits_bar.as_ref() {
+                    pb.set_message(commit_id.clone());
+                    pb.inc(1);
+                }
+            }
+            ProgressEvent::CommitsDone => {
+                if let Some(pb) = commits_bar.take() {
+                    pb.finish_and_clear();
+                    self.mp.remove(&pb);
+                }
+            }
+            ProgressEvent::FilesStarted { total } => {
+                *files_bar = Some(self.generate_files_bar(*total as u64));
+            }
+            ProgressEvent::FileStarted { file_path } => {
+                if let Some(pb) = files_bar.as_ref() {
+                    pb.set_message(file_path.clone());
+                }
+            }
+            ProgressEvent::FileDone { .. } => {
+                if let Some(pb) = files_bar.as_ref() {
+                    pb.inc(1);
+                }
+            }
+            ProgressEvent::FilesDone => {
+                if let Some(pb) = files_bar.take() {
+                    pb.finish_and_clear();
+                    self.mp.remove(&pb);
+                }
+            }
+            ProgressEvent::Warning { .. } => {}
+        }
+    }
 }
@@ -1,31 +1,511 @@
-pub use git2::{Delta, DiffOptions, Repository, Revwalk, Sort, Tree};
-use std::process;
+use super::parse_utc_time;
+use super::path_filter::{build_glob_set, PathFilter};
+use crate::error::XStatsError;
+use git2::build::RepoBuilder;
+use git2::{BlameOptions, ErrorCode, FetchOptions, Signature, StatusOptions};
+pub use git2::{
+    Commit, Delta, Diff, DiffFindOptions, DiffOptions, Oid, Patch, Repository, Revwalk, Sort, Tree,
+};
+use git2::{ObjectType, TreeWalkMode, TreeWalkResult};
+use globset::GlobSet;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
 
-pub fn open_repo(path: &str) -> Repository {
-    let repo = match Repository::open(path) {
-        Ok(repo) => repo,
-        Err(e) => {
-            println!("Failed to open repository: {}", e);
-            process::exit(1);
+/// The branches tried in turn as the default branch, when `auto` is given as baseline
+pub const DEFAULT_BRANCHES: &[&str] = &[
+    "origin/HEAD",
+    "origin/main",
+    "origin/master",
+    "main",
+    "master",
+];
+
+#[derive(Debug)]
+pub enum RepositoryError {
+    /// The target path is not a git repository.
+    NotARepository(String),
+    /// The repository has no commits yet, i.e. its HEAD is unborn.
+    NoCommits(String),
+    /// A revision, e.g. given with `--since`, does not resolve to a commit.
+    InvalidRevision(String),
+    /// None of the usual default branches exists, to find the baseline of a branch from.
+    NoDefaultBranch(String),
+    /// Any other failure reported by git.
+    Git(git2::Error),
+}
+
+impl Display for RepositoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepositoryError::NotARepository(path) => {
+                write!(f, "Target path is not a git repository: {}", path)
+            }
+            RepositoryError::NoCommits(path) => {
+                write!(f, "Repository has no commits yet: {}", path)
+            }
+            RepositoryError::InvalidRevision(revision) => {
+                write!(f, "Revision does not resolve to a commit: {}", revision)
+            }
+            RepositoryError::NoDefaultBranch(path) => {
+                write!(
+                    f,
+                    "No default branch among {} in repository: {}",
+                    DEFAULT_BRANCHES.join(", "),
+                    path
+                )
+            }
+            RepositoryError::Git(e) => write!(f, "Git error: {}", e),
         }
-    };
+    }
+}
+
+impl std::error::Error for RepositoryError {}
+
+impl From<git2::Error> for RepositoryError {
+    fn from(e: git2::Error) -> Self {
+        RepositoryError::Git(e)
+    }
+}
+
+pub fn open_repo(path: &str) -> Result<Repository, RepositoryError> {
+    Repository::open(path).map_err(|e| match e.code() {
+        ErrorCode::NotFound => RepositoryError::NotARepository(path.to_string()),
+        _ => RepositoryError::Git(e),
+    })
+}
+
+/// Whether a target is the URL of a remote repository, e.g. `https://host/repo.git` or the
+/// scp-like `git@host:repo.git`, rather than a local path
+pub fn is_remote_url(target: &str) -> bool {
+    if target.contains("://") {
+        return true;
+    }
+    // Unlike a Windows drive, e.g. `C:\repo`, the host of an scp-like URL has several letters
+    match target.split_once(':') {
+        Some((host, _)) => host.len() > 1 && !host.contains('/') && !Path::new(target).exists(),
+        None => false,
+    }
+}
+
+/// Clone a repository into a directory, with the working tree of its default branch, fetching
+/// only the last `depth` commits if given
+pub fn clone_repo(
+    url: &str,
+    path: &Path,
+    depth: Option<u32>,
+) -> Result<Repository, RepositoryError> {
+    let mut fetch_options = FetchOptions::new();
+    if let Some(depth) = depth {
+        fetch_options.depth(depth.min(i32::MAX as u32) as i32);
+    }
+    Ok(RepoBuilder::new()
+        .fetch_options(fetch_options)
+        .clone(url, path)?)
+}
+
+/// Open the repository containing the path, which may be one of its subdirectories
+pub fn discover_repo(path: &str) -> Result<Repository, RepositoryError> {
+    Repository::discover(path).map_err(|e| match e.code() {
+        ErrorCode::NotFound => RepositoryError::NotARepository(path.to_string()),
+        _ => RepositoryError::Git(e),
+    })
+}
+
+/// Selects the commits to analyze from glob patterns matched against the first line of their
+/// message, e.g. `Merge pull request*` or `feat:*`.
+///
+/// A commit is kept if it matches one of the `only` patterns, or if there are none, and none of
+/// the `skip` patterns.
+#[derive(Clone, Debug, Default)]
+pub struct CommitFilter {
+    pub skip_patterns: Vec<String>,
+    pub only_patterns: Vec<String>,
+    skip_set: GlobSet,
+    only_set: GlobSet,
+}
+
+impl CommitFilter {
+    pub fn from_patterns(skip: &[String], only: &[String]) -> Result<Self, XStatsError> {
+        Ok(CommitFilter {
+            skip_patterns: skip.to_vec(),
+            only_patterns: only.to_vec(),
+            skip_set: build_glob_set(skip, false)?,
+            only_set: build_glob_set(only, false)?,
+        })
+    }
 
-    repo
+    pub fn matches(&self, summary: &str) -> bool {
+        (self.only_patterns.is_empty() || self.only_set.is_match(summary))
+            && !self.skip_set.is_match(summary)
+    }
 }
 
-pub fn generate_revwalk(repo: &Repository) -> Revwalk {
-    let mut revwalk = match repo.revwalk() {
-        Ok(walk) => walk,
-        Err(e) => {
-            println!("Failed to create revwalk: {}", e);
-            process::exit(1);
+/// Selects the commits to analyze by their author, the files they change and when they were
+/// committed, to scope a study of the history.
+///
+/// A commit is kept if its author's name or email matches one of the `author_patterns`, glob
+/// patterns, e.g. `*@example.com`, if any, if it changes a file matching one of the
+/// `path_patterns`, relative to the repository root, if any, and if it was committed from `after`
+/// on and before `before`, in seconds since the epoch, if set.
+#[derive(Clone, Debug, Default)]
+pub struct CommitScope {
+    pub author_patterns: Vec<String>,
+    pub path_patterns: Vec<String>,
+    pub after: Option<i64>,
+    pub before: Option<i64>,
+    author_set: GlobSet,
+    path_filter: PathFilter,
+}
+
+impl CommitScope {
+    /// A scope from glob patterns and UTC dates, `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SSZ`
+    pub fn new(
+        authors: &[String],
+        paths: &[String],
+        after: Option<&str>,
+        before: Option<&str>,
+    ) -> Result<Self, XStatsError> {
+        let parse_date = |date: Option<&str>| {
+            date.map(|date| {
+                parse_utc_time(date).ok_or_else(|| {
+                    XStatsError::Config(format!(
+                        "Invalid date '{}', expected YYYY-MM-DD or YYYY-MM-DDTHH:MM:SSZ",
+                        date
+                    ))
+                })
+            })
+            .transpose()
+        };
+        let after = parse_date(after)?;
+        let before = parse_date(before)?;
+        Ok(CommitScope {
+            author_patterns: authors.to_vec(),
+            path_patterns: paths.to_vec(),
+            after,
+            before,
+            author_set: build_glob_set(authors, false)?,
+            path_filter: PathFilter::from_patterns(paths, &[])?,
+        })
+    }
+
+    pub fn matches(&self, repo: &Repository, commit: &Commit) -> Result<bool, git2::Error> {
+        let time = commit.time().seconds();
+        if self.after.is_some_and(|after| time < after)
+            || self.before.is_some_and(|before| time >= before)
+        {
+            return Ok(false);
+        }
+        let author = commit.author();
+        if !self.author_patterns.is_empty()
+            && ![author.name(), author.email()]
+                .into_iter()
+                .flatten()
+                .any(|identity| self.author_set.is_match(identity))
+        {
+            return Ok(false);
+        }
+        if self.path_patterns.is_empty() {
+            return Ok(true);
         }
-    };
 
-    revwalk.push_head().expect("Failed to push HEAD to revwalk");
-    revwalk
-        .set_sorting(Sort::REVERSE)
-        .expect("Failed to set sorting");
+        // The files changed compared to the first parent
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        Ok(diff.deltas().any(|delta| {
+            [delta.old_file().path(), delta.new_file().path()]
+                .into_iter()
+                .flatten()
+                .any(|path| self.path_filter.matches(&path.to_string_lossy()))
+        }))
+    }
+}
+
+/// The range of commits walked in multi-commit mode.
+///
+/// # Fields
+///
+/// * `since` - Only walk the commits after this revision, which is excluded, as in `since..until`.
+/// * `until` - Walk the history from this revision instead of `HEAD`.
+/// * `max_commits` - Only walk the most recent commits of the range.
+/// * `commit_filter` - Only walk the commits whose message matches the filter.
+/// * `commit_scope` - Only walk the commits of the authors, paths and dates of the scope.
+/// * `skip_merges` - Leave out the merge commits, whose changes were made by the merged commits.
+/// * `first_parent` - Only follow the first parent of the merge commits, so that the changes of a
+///   merged branch are taken as those of the merge commit, as the mainline saw them.
+#[derive(Clone, Debug, Default)]
+pub struct RevwalkConfig {
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub max_commits: Option<usize>,
+    pub commit_filter: CommitFilter,
+    pub commit_scope: CommitScope,
+    pub skip_merges: bool,
+    pub first_parent: bool,
+}
+
+/// List the commits of the range, oldest first
+pub fn generate_revwalk(
+    repo: &Repository,
+    config: &RevwalkConfig,
+) -> Result<Vec<Oid>, RepositoryError> {
+    // An unborn HEAD has no history to walk
+    if let Err(e) = repo.head() {
+        return match e.code() {
+            ErrorCode::UnbornBranch | ErrorCode::NotFound => Err(RepositoryError::NoCommits(
+                repo.path().to_string_lossy().to_string(),
+            )),
+            _ => Err(RepositoryError::Git(e)),
+        };
+    }
+
+    let mut revwalk = repo.revwalk()?;
+    match &config.until {
+        Some(until) => revwalk.push(resolve_commit(repo, until)?)?,
+        None => revwalk.push_head()?,
+    }
+    if let Some(since) = &config.since {
+        revwalk.hide(resolve_commit(repo, since)?)?;
+    }
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    if config.first_parent {
+        revwalk.simplify_first_parent()?;
+    }
+
+    // Most recent first, so that the limit keeps the latest commits
+    let mut oids = Vec::new();
+    for oid in revwalk {
+        let oid = oid?;
+        if config
+            .max_commits
+            .is_some_and(|max_commits| oids.len() >= max_commits)
+        {
+            break;
+        }
+        let commit = repo.find_commit(oid)?;
+        if config.skip_merges && commit.parent_count() > 1 {
+            continue;
+        }
+        if config
+            .commit_filter
+            .matches(commit.summary().unwrap_or_default())
+            && config.commit_scope.matches(repo, &commit)?
+        {
+            oids.push(oid);
+        }
+    }
+    oids.reverse();
+
+    Ok(oids)
+}
+
+/// Resolve a revision, e.g. a tag, a branch or an abbreviated commit id, to a commit
+pub fn resolve_commit(repo: &Repository, revision: &str) -> Result<Oid, RepositoryError> {
+    repo.revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .map(|commit| commit.id())
+        .map_err(|e| match e.code() {
+            ErrorCode::NotFound | ErrorCode::Ambiguous | ErrorCode::InvalidSpec => {
+                RepositoryError::InvalidRevision(revision.to_string())
+            }
+            _ => RepositoryError::Git(e),
+        })
+}
+
+/// Resolve the baseline to compare the working tree with, either a revision or `auto` for the
+/// merge-base of HEAD and the default branch, i.e. where the current branch forked from
+pub fn resolve_baseline(repo: &Repository, baseline_ref: &str) -> Result<Oid, RepositoryError> {
+    if baseline_ref != "auto" {
+        return resolve_commit(repo, baseline_ref);
+    }
+    let head = repo.head()?.peel_to_commit()?.id();
+    let default_branch = DEFAULT_BRANCHES
+        .iter()
+        .find_map(|branch| resolve_commit(repo, branch).ok())
+        .ok_or_else(|| {
+            RepositoryError::NoDefaultBranch(repo.path().to_string_lossy().to_string())
+        })?;
+    Ok(repo.merge_base(head, default_branch)?)
+}
+
+/// List the files of a commit below a directory of the repository, with their path relative to
+/// it and the id of their blob
+pub fn get_commit_files(commit: &Commit, dir: &Path) -> Result<Vec<(PathBuf, Oid)>, git2::Error> {
+    let mut files = Vec::new();
+    commit.tree()?.walk(TreeWalkMode::PreOrder, |root, entry| {
+        if entry.kind() == Some(ObjectType::Blob) {
+            if let Some(name) = entry.name() {
+                let path = Path::new(root).join(name);
+                if let Ok(relative_path) = path.strip_prefix(dir) {
+                    files.push((relative_path.to_path_buf(), entry.id()));
+                }
+            }
+        }
+        TreeWalkResult::Ok
+    })?;
+    Ok(files)
+}
+
+/// List the files of the working tree added or modified since a commit, uncommitted and
+/// untracked changes included, with their path relative to the repository
+pub fn get_changed_files(repo: &Repository, base: Oid) -> Result<Vec<PathBuf>, git2::Error> {
+    let base_tree = repo.find_commit(base)?.tree()?;
+    let mut diff_opts = DiffOptions::new();
+    diff_opts
+        .include_untracked(true)
+        .recurse_untracked_dirs(true);
+    let diff = repo.diff_tree_to_workdir_with_index(Some(&base_tree), Some(&mut diff_opts))?;
+    let files = diff
+        .deltas()
+        .filter(|delta| delta.status() != Delta::Deleted)
+        .filter_map(|delta| delta.new_file().path().map(|path| path.to_path_buf()))
+        .collect();
+    Ok(files)
+}
+
+/// Check if the repository has uncommitted changes, untracked files included
+pub fn is_dirty(repo: &Repository) -> Result<bool, git2::Error> {
+    let mut status_opts = StatusOptions::new();
+    status_opts.include_untracked(true).include_ignored(false);
+    Ok(!repo.statuses(Some(&mut status_opts))?.is_empty())
+}
+
+/// Collect the line ranges (1-based, inclusive) touched by each hunk of the
+/// delta at `idx`, on the new side of the diff.
+pub fn get_changed_lines(diff: &Diff, idx: usize) -> Result<Vec<(u32, u32)>, git2::Error> {
+    let mut changed_lines = Vec::new();
+
+    if let Some(patch) = Patch::from_diff(diff, idx)? {
+        for hunk_idx in 0..patch.num_hunks() {
+            let (hunk, _) = patch.hunk(hunk_idx)?;
+            // A pure deletion has no new lines, it is anchored to the line preceding it
+            let start = hunk.new_start().max(1);
+            let end = start + hunk.new_lines().max(1) - 1;
+            changed_lines.push((start, end));
+        }
+    }
+
+    Ok(changed_lines)
+}
+
+/// Get the hunks of the delta at `idx`, as their old start and lines and new start and lines.
+///
+/// The starts are 1-based. A hunk without old lines inserts its new lines after its old start,
+/// and one without new lines deletes its old lines after its new start.
+pub fn get_hunks(diff: &Diff, idx: usize) -> Result<Vec<(u32, u32, u32, u32)>, git2::Error> {
+    let mut hunks = Vec::new();
+
+    if let Some(patch) = Patch::from_diff(diff, idx)? {
+        for hunk_idx in 0..patch.num_hunks() {
+            let (hunk, _) = patch.hunk(hunk_idx)?;
+            hunks.push((
+                hunk.old_start(),
+                hunk.old_lines(),
+                hunk.new_start(),
+                hunk.new_lines(),
+            ));
+        }
+    }
+
+    Ok(hunks)
+}
+
+/// Count the lines added and deleted, and the hunks, of the delta at `idx`
+pub fn get_line_stats(diff: &Diff, idx: usize) -> Result<(u32, u32, u32), git2::Error> {
+    match Patch::from_diff(diff, idx)? {
+        Some(patch) => {
+            let (_, additions, deletions) = patch.line_stats()?;
+            Ok((additions as u32, deletions as u32, patch.num_hunks() as u32))
+        }
+        None => Ok((0, 0, 0)),
+    }
+}
+
+/// Get the time (in seconds since the epoch) each line of a file was last modified, from blame.
+///
+/// `file_path` is relative to the repository root and `content` is the current content of the
+/// file, so lines that are not committed yet have no time.
+pub fn get_line_times(
+    repo: &Repository,
+    file_path: &Path,
+    content: &[u8],
+) -> Result<Vec<Option<i64>>, git2::Error> {
+    let committed_blame = repo.blame_file(file_path, Some(&mut BlameOptions::new()))?;
+    let blame = committed_blame.blame_buffer(content)?;
+
+    let mut commit_times: HashMap<Oid, i64> = HashMap::new();
+    let mut line_times = Vec::new();
+    for hunk in blame.iter() {
+        let commit_id = hunk.final_commit_id();
+        let time = if commit_id.is_zero() {
+            None
+        } else if let Some(time) = commit_times.get(&commit_id) {
+            Some(*time)
+        } else {
+            let time = repo.find_commit(commit_id)?.time().seconds();
+            commit_times.insert(commit_id, time);
+            Some(time)
+        };
+        line_times.extend(std::iter::repeat_n(time, hunk.lines_in_hunk()));
+    }
+
+    Ok(line_times)
+}
+
+/// Blame a file, returning the author of each of its lines, by `get_author`.
+///
+/// As for `get_line_times`, `file_path` is relative to the repository root and `content` is the
+/// current content of the file, so lines that are not committed yet have no author.
+pub fn get_line_authors(
+    repo: &Repository,
+    file_path: &Path,
+    content: &[u8],
+) -> Result<Vec<Option<String>>, git2::Error> {
+    let committed_blame = repo.blame_file(file_path, Some(&mut BlameOptions::new()))?;
+    let blame = committed_blame.blame_buffer(content)?;
+
+    let mut line_authors = Vec::new();
+    for hunk in blame.iter() {
+        let author =
+            (!hunk.final_commit_id().is_zero()).then(|| get_author(&hunk.final_signature()));
+        line_authors.extend(std::iter::repeat_n(author, hunk.lines_in_hunk()));
+    }
+
+    Ok(line_authors)
+}
+
+/// The identity of the author of a commit or a line: their email, or their name without one
+pub fn get_author(signature: &Signature) -> String {
+    signature
+        .email()
+        .filter(|email| !email.is_empty())
+        .or(signature.name())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// The author of most of the lines of a file as of a commit, from blame, the first one in
+/// alphabetical order on a tie
+pub fn get_file_owner(
+    repo: &Repository,
+    file_path: &Path,
+    commit_id: Oid,
+) -> Result<Option<String>, git2::Error> {
+    let mut options = BlameOptions::new();
+    options.newest_commit(commit_id);
+    let blame = repo.blame_file(file_path, Some(&mut options))?;
 
-    revwalk
+    let mut line_counts: HashMap<String, usize> = HashMap::new();
+    for hunk in blame.iter() {
+        *line_counts
+            .entry(get_author(&hunk.final_signature()))
+            .or_default() += hunk.lines_in_hunk();
+    }
+    Ok(line_counts
+        .into_iter()
+        .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then_with(|| b.cmp(a)))
+        .map(|(author, _)| author))
 }
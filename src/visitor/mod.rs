@@ -1,45 +1,133 @@
-use crate::config::Language;
-use crate::ts::{Node, TSParsers, Tree};
+use crate::config::{AnalysisConfig, Language};
+use crate::error::XStatsError;
+use crate::languages::get_profile;
+use crate::metrics::{
+    find_marker, find_node_group, get_minhash, CodeMetric, ExtractCandidate, MarkerComment,
+    NamingStats, RiskyConstruct, RiskyConstructKind, NODE_GROUPS,
+};
+use crate::ts::{Node, Query, TSLanguage, TSParsers, Tree};
+use log::error;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+
+/// The number of consecutive tokens making a shingle of a method body
+const SHINGLE_SIZE: usize = 5;
+
+/// Methods with fewer tokens are too small to count as near duplicates of each other
+const MIN_SIMILARITY_TOKENS: usize = 30;
+
+/// Methods with fewer actual lines of code are not searched for statements to extract
+const LONG_METHOD_ALOC: usize = 20;
+
+/// The fewest consecutive statements, and lines, suggested for extraction
+const MIN_EXTRACT_STATEMENTS: usize = 3;
+const MIN_EXTRACT_LINES: usize = 5;
+
+/// The most variables statements may take from the rest of the method to be extracted
+const MAX_EXTRACT_INPUTS: usize = 3;
+
+/// The least share of their variables extracted statements keep to themselves
+const MIN_EXTRACT_COHESION: f64 = 0.5;
+
+/// The most extract-method candidates suggested per method
+const MAX_EXTRACT_CANDIDATES: usize = 3;
+
+/// The variables a statement assigns and uses, and whether it leaves the method or a loop
+/// around it
+struct StatementUsage<'t> {
+    node: Node<'t>,
+    assigned: HashSet<String>,
+    used: HashSet<String>,
+    escapes: bool,
+}
+
+/// The query groups a language may define, see `get_query_group`
+pub const QUERY_GROUPS: &[&str] = &[
+    "base_query",
+    "cohesion_query",
+    "robustness_query",
+    "naming_query",
+    "exit_query",
+];
 
 pub fn get_query_group<'a>(language: &Language, query_name: &'a str) -> &'a str {
-    const JAVA_BASE_QUERY: &str = concat!(
-        "[(line_comment) @comment (block_comment) @comment]",
-        "(import_declaration) @import",
-        "(class_declaration) @class_definition",
-        "[(constructor_declaration) @method_definition (method_declaration) @method_definition]",
-    );
-
-    const PYTHON_BASE_QUERY: &str = concat!(
-        "[(comment) @comment (expression_statement (string) @comment)]",
-        "[(import_statement) @import (import_from_statement) @import]",
-        "(class_definition) @class_definition",
-        "(function_definition ) @method_definition",
-    );
-
-    match (&language, query_name) {
-        (Language::Java, "base_query") => JAVA_BASE_QUERY,
-        (Language::Python, "base_query") => PYTHON_BASE_QUERY,
-        _ => {
-            eprintln!(
-                "Unsupported language or group name: {} - {}",
-                language, query_name
-            );
-            ""
+    find_query_group(language, query_name).unwrap_or_else(|| {
+        error!(
+            "Unsupported language or group name: {} - {}",
+            language, query_name
+        );
+        ""
+    })
+}
+
+/// The query of the group for the language, if it defines one
+fn find_query_group(language: &Language, query_name: &str) -> Option<&'static str> {
+    get_profile(*language).query(query_name)
+}
+
+/// Check the queries and node groups of a language against its grammar: every query must
+/// compile, and every node kind must exist in the grammar. Grammar versions renaming or removing
+/// node kinds are thus reported up front, instead of failing every file or silently skewing the
+/// metrics.
+pub fn get_grammar_problems(grammar: &TSLanguage, language: Language) -> Vec<String> {
+    let mut problems = Vec::new();
+    for query_name in QUERY_GROUPS {
+        if let Some(query) = find_query_group(&language, query_name) {
+            if let Err(e) = Query::new(grammar, query) {
+                problems.push(format!("{} does not compile ({})", query_name, e.message));
+            }
         }
     }
+    for group_name in NODE_GROUPS {
+        let unknown_kinds: Vec<&str> = find_node_group(language, group_name)
+            .unwrap_or_default()
+            .iter()
+            .filter(|kind| grammar.id_for_node_kind(kind, true) == 0)
+            .copied()
+            .collect();
+        if !unknown_kinds.is_empty() {
+            problems.push(format!(
+                "{} refers to unknown node kinds {}",
+                group_name,
+                unknown_kinds.join(", ")
+            ));
+        }
+    }
+    problems
 }
 
+/// The comment, import, class and method nodes found by the base query
+pub type BaseQueryNodes<'a> = (Vec<Node<'a>>, Vec<Node<'a>>, Vec<Node<'a>>, Vec<Node<'a>>);
+
 pub struct TreeVisitor<'a> {
     pub parsers: &'a TSParsers,
+    pub config: &'a AnalysisConfig,
     pub language: Language,
     pub source_code: &'a str,
+    /// The groups of decision point and skip nodes counted with, whose indices key the cache
+    decision_point_groups: RefCell<Vec<(Vec<String>, Vec<String>)>>,
+    /// Decision points of the subtrees already counted, by node id and group index
+    decision_points_cache: RefCell<HashMap<(usize, usize), usize>>,
+    /// Nesting depths of the subtrees already measured, by node id
+    nesting_depth_cache: RefCell<HashMap<usize, usize>>,
 }
 impl<'a> TreeVisitor<'a> {
-    pub fn new(parsers: &'a TSParsers, language: Language, source_code: &'a str) -> Self {
+    pub fn new(
+        parsers: &'a TSParsers,
+        config: &'a AnalysisConfig,
+        language: Language,
+        source_code: &'a str,
+    ) -> Self {
         Self {
             parsers,
+            config,
             language,
             source_code,
+            decision_point_groups: RefCell::new(Vec::new()),
+            decision_points_cache: RefCell::new(HashMap::new()),
+            nesting_depth_cache: RefCell::new(HashMap::new()),
         }
     }
 
@@ -47,22 +135,19 @@ impl<'a> TreeVisitor<'a> {
         &self,
         node: &'a Node,
         tree: &'a Tree,
-    ) -> (Vec<Node<'a>>, Vec<Node<'a>>, Vec<Node<'a>>, Vec<Node<'a>>) {
+    ) -> Result<BaseQueryNodes<'a>, XStatsError> {
         let query_string = get_query_group(&self.language, "base_query");
         let mut comment_n = Vec::new();
         let mut import_n = Vec::new();
         let mut class_n = Vec::new();
         let mut method_n = Vec::new();
 
-        let parser = match self.parsers.get_parser(&self.language) {
-            Some(p) => p,
-            None => {
-                eprintln!("Parser not found for language: {}", self.language);
-                return (comment_n, import_n, class_n, method_n);
-            }
-        };
+        let parser = self
+            .parsers
+            .get_parser(&self.language)
+            .ok_or(XStatsError::ParserNotFound(self.language))?;
 
-        let query_result = parser.query_tree(&node, tree, self.source_code, query_string);
+        let query_result = parser.query_tree(node, tree, self.source_code, query_string)?;
 
         for (matched_node, capture_name) in query_result {
             match capture_name.as_str() {
@@ -74,40 +159,152 @@ impl<'a> TreeVisitor<'a> {
             }
         }
 
-        (comment_n, import_n, class_n, method_n)
+        Ok((comment_n, import_n, class_n, method_n))
     }
 
     pub fn get_class_name(&self, class_node: &Node) -> String {
-        let class_name_node = class_node.child_by_field_name("name").unwrap();
-        let class_name_text = class_name_node
-            .utf8_text(self.source_code.as_bytes())
-            .unwrap();
-        class_name_text.to_string()
+        get_profile(self.language)
+            .get_class_name(class_node, self.source_code)
+            .unwrap_or_else(|| self.get_node_name(class_node))
+    }
+
+    fn get_field_text(&self, node: &Node, field_name: &str) -> String {
+        node.child_by_field_name(field_name)
+            .map(|field_node| self.get_text(&field_node))
+            .unwrap_or_default()
+    }
+
+    /// Get the name of a node, or of what it is assigned to for anonymous
+    /// classes and functions (e.g. `const add = (a, b) => a + b;` in JavaScript)
+    fn get_node_name(&self, node: &Node) -> String {
+        if node.child_by_field_name("name").is_some() {
+            return self.get_field_text(node, "name");
+        }
+
+        if let Some(parent) = node.parent() {
+            let name_field = get_profile(self.language)
+                .get_assigned_name_field(parent.kind())
+                .unwrap_or_default();
+            if parent.child_by_field_name(name_field).is_some() {
+                return self.get_field_text(&parent, name_field);
+            }
+        }
+
+        self.get_anonymous_name(node)
+    }
+
+    /// Synthesize the name of an anonymous class or function from the entity enclosing it and
+    /// its line, e.g. `Outer$anon@L42` for an anonymous class and `run$lambda@L42` for a lambda
+    fn get_anonymous_name(&self, node: &Node) -> String {
+        let label = if node.child_by_field_name("parameters").is_some()
+            || node.child_by_field_name("parameter").is_some()
+        {
+            "lambda"
+        } else {
+            "anon"
+        };
+        let entity = format!("{}@L{}", label, node.start_position().row + 1);
+
+        // The entities are the skipped nodes, but for the bodies of the declarations, e.g. the
+        // `class_body` of a Java class, named after the declaration enclosing them
+        let skip_nodes =
+            find_node_group(self.language, "decision_point_skip_nodes").unwrap_or_default();
+        let mut ancestor = node.parent();
+        while let Some(outer) = ancestor {
+            let is_body = outer
+                .parent()
+                .and_then(|parent| parent.child_by_field_name("body"))
+                .is_some_and(|body| body.id() == outer.id());
+            if skip_nodes.contains(&outer.kind()) && !is_body {
+                return format!("{}${}", self.get_class_name(&outer), entity);
+            }
+            ancestor = outer.parent();
+        }
+        entity
     }
 
     pub fn get_method_name(&self, method_node: &Node) -> String {
-        let method_name_node = method_node.child_by_field_name("name").unwrap();
-        let method_name_text = method_name_node
-            .utf8_text(self.source_code.as_bytes())
-            .unwrap();
-        method_name_text.to_string()
+        get_profile(self.language)
+            .get_method_name(method_node, self.source_code)
+            .unwrap_or_else(|| self.get_node_name(method_node))
     }
 
-    pub fn count_parameters(&self, method_node: &Node) -> usize {
-        let parameters_node = method_node.child_by_field_name("parameters").unwrap();
-        let parameters_count = parameters_node.child_count();
-        parameters_count
+    /// Get the parameter nodes of a method, without the punctuation, comments and receivers (e.g.
+    /// `self`) of its parameter list
+    pub fn get_parameter_nodes<'t>(&self, method_node: &Node<'t>) -> Vec<Node<'t>> {
+        let Some(parameters) = method_node
+            .child_by_field_name("parameters")
+            .or_else(|| method_node.child_by_field_name("parameter"))
+            .or_else(|| get_profile(self.language).get_parameter_list(method_node))
+        else {
+            return Vec::new();
+        };
+        // JavaScript arrow functions may take a single parameter without parentheses
+        if parameters.kind() == "identifier" {
+            return vec![parameters];
+        }
+
+        let skip_nodes = find_node_group(self.language, "parameter_skip_nodes").unwrap_or_default();
+        let mut cursor = parameters.walk();
+        let nodes = parameters
+            .named_children(&mut cursor)
+            .filter(|parameter| {
+                !skip_nodes.contains(&parameter.kind()) && !parameter.kind().contains("comment")
+            })
+            .filter(|parameter| {
+                let name = self.get_text(&self.get_parameter_name_node(parameter));
+                !get_profile(self.language)
+                    .receiver_names()
+                    .contains(&name.as_str())
+            })
+            .collect();
+        nodes
     }
 
-    pub fn count_empty_lines(&self, node: Node) -> usize {
+    /// The node naming a parameter, or the parameter itself if it is a bare name or a pattern
+    fn get_parameter_name_node<'t>(&self, parameter: &Node<'t>) -> Node<'t> {
+        parameter
+            .child_by_field_name("name")
+            .or_else(|| parameter.child_by_field_name("pattern"))
+            .unwrap_or(*parameter)
+    }
+
+    /// Check if a parameter has a default value, by its kind or its `value`, e.g. `count = 1`
+    pub fn is_default_parameter(&self, parameter: &Node, default_nodes: &[String]) -> bool {
+        default_nodes.iter().any(|kind| kind == parameter.kind())
+            || parameter.child_by_field_name("value").is_some()
+    }
+
+    /// Check if a parameter takes a variable number of arguments, e.g. `String... names` or
+    /// `*args: int`, whose rest pattern may be wrapped in a typed parameter
+    pub fn is_variadic_parameter(&self, parameter: &Node, variadic_nodes: &[String]) -> bool {
+        let is_variadic = |node: &Node| variadic_nodes.iter().any(|kind| kind == node.kind());
+        is_variadic(parameter)
+            || is_variadic(&self.get_parameter_name_node(parameter))
+            || parameter
+                .named_child(0)
+                .is_some_and(|child| is_variadic(&child))
+    }
+
+    /// Count the empty lines of the node, leaving out those inside multi-line strings, e.g. Java
+    /// text blocks or Python docstrings, which are part of the string rather than blank code
+    pub fn count_empty_lines(&self, node: Node, string_nodes: &[String]) -> usize {
         let mut empty_lines_count = 0;
 
+        let mut string_rows = Vec::new();
+        self.collect_multiline_string_rows(node, string_nodes, &mut string_rows);
+
         // Extract the text of the node
         if let Some(node_text) = self.source_code.get(node.start_byte()..node.end_byte()) {
             // Iterate through lines in the node's text
-            for line in node_text.lines() {
+            for (i, line) in node_text.lines().enumerate() {
+                let row = node.start_position().row + i;
                 // Check if the line is empty or contains only whitespace
-                if line.trim().is_empty() {
+                if line.trim().is_empty()
+                    && !string_rows
+                        .iter()
+                        .any(|(start, end)| *start < row && row <= *end)
+                {
                     empty_lines_count += 1;
                 }
             }
@@ -116,7 +313,73 @@ impl<'a> TreeVisitor<'a> {
         empty_lines_count
     }
 
-    pub fn count_comments(&self, comment_nodes: &Vec<Node>) -> (usize, usize) {
+    /// Count the lines of the node holding code, i.e. any character outside of its comments. The
+    /// blank lines inside multi-line strings are code, as part of the string, but those of
+    /// docstrings are not, which are comments.
+    pub fn count_source_lines(
+        &self,
+        node: Node,
+        comment_nodes: &[Node],
+        string_nodes: &[String],
+    ) -> usize {
+        let mut string_rows = Vec::new();
+        self.collect_multiline_string_rows(node, string_nodes, &mut string_rows);
+        string_rows.retain(|(start, end)| {
+            !comment_nodes.iter().any(|comment| {
+                (comment.start_position().row, comment.end_position().row) == (*start, *end)
+            })
+        });
+        let is_comment = |byte: usize| {
+            comment_nodes
+                .iter()
+                .any(|comment| self.get_comment_range(comment).contains(&byte))
+        };
+
+        let Some(node_text) = self.source_code.get(node.start_byte()..node.end_byte()) else {
+            return 0;
+        };
+        let mut source_lines_count = 0;
+        let mut line_start = node.start_byte();
+        for (i, line) in node_text.split('\n').enumerate() {
+            let row = node.start_position().row + i;
+            let is_in_string = string_rows
+                .iter()
+                .any(|(start, end)| *start < row && row <= *end);
+            let has_code = line
+                .char_indices()
+                .any(|(j, c)| !c.is_whitespace() && !is_comment(line_start + j));
+            if is_in_string || has_code {
+                source_lines_count += 1;
+            }
+            line_start += line.len() + 1;
+        }
+        source_lines_count
+    }
+
+    /// Collect the start and end rows of the strings spanning several lines
+    fn collect_multiline_string_rows(
+        &self,
+        node: Node,
+        string_nodes: &[String],
+        string_rows: &mut Vec<(usize, usize)>,
+    ) {
+        let (start_row, end_row) = (node.start_position().row, node.end_position().row);
+        if start_row == end_row {
+            return;
+        }
+        if string_nodes.iter().any(|kind| kind == node.kind()) {
+            string_rows.push((start_row, end_row));
+            return;
+        }
+
+        for i in 0..node.child_count() {
+            if let Some(child) = node.child(i) {
+                self.collect_multiline_string_rows(child, string_nodes, string_rows);
+            }
+        }
+    }
+
+    pub fn count_comments(&self, comment_nodes: &[Node]) -> (usize, usize) {
         let mut total_comments_count = 0;
         let mut doc_comments_count = 0;
 
@@ -124,17 +387,10 @@ impl<'a> TreeVisitor<'a> {
             total_comments_count += 1;
 
             // Extract the text of the comment
-            if let Ok(comment_text) = node.utf8_text(self.source_code.as_bytes()) {
-                if self.language == Language::Java {
-                    // Check for Java doc comments (start with /**)
-                    if comment_text.starts_with("/**") {
-                        doc_comments_count += 1;
-                    }
-                } else if self.language == Language::Python {
-                    // Check for Python docstrings (triple quotes)
-                    if comment_text.starts_with("\"\"\"") || comment_text.starts_with("'''") {
-                        doc_comments_count += 1;
-                    }
+            if let Some(comment_text) = self.source_code.get(self.get_comment_range(node)) {
+                // Check for doc comments (e.g. /** in Java, triple quotes in Python)
+                if self.config.is_doc_comment(&self.language, comment_text) {
+                    doc_comments_count += 1;
                 }
             }
         }
@@ -142,17 +398,53 @@ impl<'a> TreeVisitor<'a> {
         (total_comments_count, doc_comments_count)
     }
 
+    /// The bytes of a comment node, or of the comment ending a node which holds one, e.g. the
+    /// `// items` of the value `10 // items` of a C macro, which the grammar does not split
+    fn get_comment_range(&self, node: &Node) -> Range<usize> {
+        let range = node.byte_range();
+        if node.kind().contains("comment") {
+            return range;
+        }
+        let text = self.source_code.get(range.clone()).unwrap_or_default();
+        match text.find("//") {
+            Some(start) => range.start + start..range.end,
+            None => range,
+        }
+    }
+
+    /// Get the comment lines containing one of the markers of unfinished work, e.g. `TODO`
+    pub fn get_marker_comments(&self, comment_nodes: &[Node]) -> Vec<MarkerComment> {
+        let mut markers = Vec::new();
+        for node in comment_nodes {
+            let comment_range = self.get_comment_range(node);
+            let comment_text = self
+                .source_code
+                .get(comment_range.clone())
+                .unwrap_or_default();
+            let start_row = node.start_position().row
+                + self.source_code[node.start_byte()..comment_range.start]
+                    .matches('\n')
+                    .count();
+            for (i, line) in comment_text.lines().enumerate() {
+                if let Some((marker, text)) = find_marker(line, &self.config.markers) {
+                    markers.push(MarkerComment {
+                        marker: marker.to_string(),
+                        row: (start_row + i) as u32 + 1,
+                        text: text.to_string(),
+                    });
+                }
+            }
+        }
+        markers
+    }
+
     pub fn check_if_broken(&self, node: Node) -> bool {
-        // NOTE: COMPUTE HEAVY FUNCTION, maybe?
+        // Most subtrees are fine, and tree-sitter tracks whether they hold any error or missing node
+        if !node.has_error() {
+            return false;
+        }
 
-        let skip_nodes = match self.language {
-            Language::Java => vec![
-                "class_declaration",
-                "method_declaration",
-                "constructor_declaration",
-            ],
-            Language::Python => vec!["class_definition", "function_definition"],
-        };
+        let skip_nodes = get_profile(self.language).entity_nodes();
 
         let mut is_broken = false;
 
@@ -171,7 +463,930 @@ impl<'a> TreeVisitor<'a> {
             }
         }
 
-        traverse(node, &mut is_broken, &skip_nodes);
+        traverse(node, &mut is_broken, skip_nodes);
         is_broken
     }
+
+    /// Count the decision points in the subtree of the node, those of the nested entities in
+    /// `skip_nodes` included.
+    ///
+    /// Nested entities are counted once per file: the count of every skipped node is memoized,
+    /// for the same groups of nodes, so the entities enclosing it reuse it.
+    pub fn count_decision_points(
+        &self,
+        node: Node,
+        decision_points: &[String],
+        skip_nodes: &[String],
+    ) -> usize {
+        let group = self.get_decision_point_group(decision_points, skip_nodes);
+        self.count_group_decision_points(node, decision_points, skip_nodes, group)
+    }
+
+    /// Get the index of the groups of nodes in the cache of the decision points, adding them if
+    /// they are new
+    fn get_decision_point_group(&self, decision_points: &[String], skip_nodes: &[String]) -> usize {
+        let mut groups = self.decision_point_groups.borrow_mut();
+        let position = groups.iter().position(|(points, skipped)| {
+            points.as_slice() == decision_points && skipped.as_slice() == skip_nodes
+        });
+        position.unwrap_or_else(|| {
+            groups.push((decision_points.to_vec(), skip_nodes.to_vec()));
+            groups.len() - 1
+        })
+    }
+
+    fn count_group_decision_points(
+        &self,
+        node: Node,
+        decision_points: &[String],
+        skip_nodes: &[String],
+        group: usize,
+    ) -> usize {
+        let key = (node.id(), group);
+        if let Some(count) = self.decision_points_cache.borrow().get(&key) {
+            return *count;
+        }
+
+        let node_kind = node.kind().to_string();
+        let is_skipped = skip_nodes.contains(&node_kind);
+
+        // Count this node if it's a decision point, e.g. a lambda, but not the keyword of
+        // Python's `lambda`, a node of the same kind
+        let mut count = 0;
+        if node.is_named() && decision_points.contains(&node_kind) {
+            count += 1;
+        }
+
+        // Traverse children
+        for i in 0..node.child_count() {
+            if let Some(child) = node.child(i) {
+                count +=
+                    self.count_group_decision_points(child, decision_points, skip_nodes, group);
+            }
+        }
+
+        if is_skipped {
+            self.decision_points_cache.borrow_mut().insert(key, count);
+        }
+        count
+    }
+
+    /// Count the decision points of a function itself, without the interiors of the entities
+    /// nested in it, the nodes in `skip_nodes`. A nested entity which is a decision point, e.g. a
+    /// lambda, still counts as one.
+    pub fn count_own_decision_points(
+        &self,
+        node: Node,
+        decision_points: &[String],
+        skip_nodes: &[String],
+    ) -> usize {
+        let mut count = 0;
+        for i in 0..node.child_count() {
+            if let Some(child) = node.child(i) {
+                let child_kind = child.kind().to_string();
+                if child.is_named() && decision_points.contains(&child_kind) {
+                    count += 1;
+                }
+                if !skip_nodes.contains(&child_kind) {
+                    count += self.count_own_decision_points(child, decision_points, skip_nodes);
+                }
+            }
+        }
+        count
+    }
+
+    /// Get the maximum nesting depth of the blocks in the subtree of the node, e.g. 2 for an
+    /// `if` inside a `for`. An `else if` is at the depth of the `if` it continues.
+    ///
+    /// As for decision points, the depth of every node in `skip_nodes` is memoized, so the
+    /// entities enclosing it reuse it.
+    pub fn get_nesting_depth(
+        &self,
+        node: Node,
+        nesting_nodes: &[String],
+        skip_nodes: &[String],
+    ) -> usize {
+        if let Some(depth) = self.nesting_depth_cache.borrow().get(&node.id()) {
+            return *depth;
+        }
+
+        let mut depth = 0;
+        for i in 0..node.child_count() {
+            if let Some(child) = node.child(i) {
+                depth = depth.max(self.get_nesting_depth(child, nesting_nodes, skip_nodes));
+            }
+        }
+
+        let node_kind = node.kind().to_string();
+        if nesting_nodes.contains(&node_kind) && !is_else_if(&node) {
+            depth += 1;
+        }
+        if skip_nodes.contains(&node_kind) {
+            self.nesting_depth_cache
+                .borrow_mut()
+                .insert(node.id(), depth);
+        }
+        depth
+    }
+
+    /// Get the LCOM4 of a Java or Python class: the number of groups of its methods that share
+    /// no field and do not call each other, 1 for a cohesive class and 0 for a class without
+    /// methods. Constructors are left out, as they usually initialize every field.
+    pub fn get_lcom4(&self, class_node: &Node, tree: &'a Tree) -> Result<u32, XStatsError> {
+        let Some(body) = class_node.child_by_field_name("body") else {
+            return Ok(0);
+        };
+        let parser = self
+            .parsers
+            .get_parser(&self.language)
+            .ok_or(XStatsError::ParserNotFound(self.language))?;
+        let query_string = get_query_group(&self.language, "cohesion_query");
+
+        let mut fields = HashSet::new();
+        let mut methods = Vec::new();
+        let mut cursor = body.walk();
+        for member in body.named_children(&mut cursor) {
+            match member.kind() {
+                "field_declaration" => {
+                    let mut declarators = member.walk();
+                    for declarator in member.children_by_field_name("declarator", &mut declarators)
+                    {
+                        fields.insert(self.get_field_text(&declarator, "name"));
+                    }
+                }
+                // Class attributes, e.g. `count = 0`
+                "expression_statement" => {
+                    if let Some(left) = member
+                        .named_child(0)
+                        .filter(|child| child.kind() == "assignment")
+                        .and_then(|assignment| assignment.child_by_field_name("left"))
+                    {
+                        fields.insert(self.get_text(&left));
+                    }
+                }
+                "method_declaration" | "function_definition" | "decorated_definition" => {
+                    let method = member.child_by_field_name("definition").unwrap_or(member);
+                    let name = self.get_field_text(&method, "name");
+                    if name != "__init__" {
+                        methods.push((name, method));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // The fields and methods of the class each method uses
+        let method_names: HashSet<String> = methods.iter().map(|(name, _)| name.clone()).collect();
+        let mut members_used = Vec::new();
+        for (_, method) in &methods {
+            let mut members = HashSet::new();
+            for (node, capture_name) in
+                parser.query_tree(method, tree, self.source_code, query_string)?
+            {
+                // Bare names are the fields in Java, unless they name a member of something else
+                let is_member_name = node.parent().is_some_and(|parent| {
+                    parent.child_by_field_name("name") == Some(node)
+                        || parent.child_by_field_name("field") == Some(node)
+                });
+                if capture_name == "member" || (capture_name == "name" && !is_member_name) {
+                    members.insert(self.get_text(&node));
+                }
+            }
+            // Python fields are the attributes of `self` that are not methods
+            if self.language == Language::Python {
+                fields.extend(
+                    members
+                        .iter()
+                        .filter(|member| !method_names.contains(*member))
+                        .cloned(),
+                );
+            }
+            members_used.push(members);
+        }
+
+        // Methods are connected when they use the same field or one calls the other
+        let mut groups: Vec<usize> = (0..methods.len()).collect();
+        fn find_group(groups: &mut [usize], idx: usize) -> usize {
+            let mut root = idx;
+            while groups[root] != root {
+                root = groups[root];
+            }
+            groups[idx] = root;
+            root
+        }
+        for (i, members) in members_used.iter().enumerate() {
+            for (j, (name, _)) in methods.iter().enumerate() {
+                let is_connected = members.contains(name)
+                    || members_used[j]
+                        .iter()
+                        .any(|member| fields.contains(member) && members.contains(member));
+                if i != j && is_connected {
+                    let (root_i, root_j) = (find_group(&mut groups, i), find_group(&mut groups, j));
+                    groups[root_i] = root_j;
+                }
+            }
+        }
+        let group_count = (0..methods.len())
+            .filter(|idx| find_group(&mut groups, *idx) == *idx)
+            .count();
+        Ok(group_count as u32)
+    }
+
+    /// Hash the tokens of a method body, comments and whitespace aside, so that exact duplicates
+    /// have the same fingerprint whatever the name of the method
+    pub fn get_body_fingerprint(&self, method_node: &Node) -> u64 {
+        fn hash_tokens(node: Node, source_code: &str, hasher: &mut DefaultHasher) {
+            if node.kind().contains("comment") {
+                return;
+            }
+            if node.child_count() == 0 {
+                source_code[node.start_byte()..node.end_byte()].hash(hasher);
+                return;
+            }
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                hash_tokens(child, source_code, hasher);
+            }
+        }
+
+        let body = method_node
+            .child_by_field_name("body")
+            .unwrap_or(*method_node);
+        let mut hasher = DefaultHasher::new();
+        hash_tokens(body, self.source_code, &mut hasher);
+        hasher.finish()
+    }
+
+    /// The MinHash signature of the shingles of a method body, comments aside and identifiers
+    /// replaced by a placeholder, so that methods copied then tweaked have close signatures.
+    /// `None` for bodies too short to tell apart from the many similar small methods.
+    pub fn get_body_minhash(&self, method_node: &Node) -> Option<Vec<u64>> {
+        fn collect_tokens<'a>(node: Node, source_code: &'a str, tokens: &mut Vec<&'a str>) {
+            if node.kind().contains("comment") {
+                return;
+            }
+            if node.child_count() == 0 {
+                if node.kind().contains("identifier") {
+                    tokens.push("$id");
+                } else {
+                    tokens.push(&source_code[node.start_byte()..node.end_byte()]);
+                }
+                return;
+            }
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                collect_tokens(child, source_code, tokens);
+            }
+        }
+
+        let body = method_node
+            .child_by_field_name("body")
+            .unwrap_or(*method_node);
+        let mut tokens = Vec::new();
+        collect_tokens(body, self.source_code, &mut tokens);
+        if tokens.len() < MIN_SIMILARITY_TOKENS {
+            return None;
+        }
+
+        let shingle_hashes = tokens.windows(SHINGLE_SIZE).map(|shingle| {
+            let mut hasher = DefaultHasher::new();
+            shingle.hash(&mut hasher);
+            hasher.finish()
+        });
+        Some(get_minhash(shingle_hashes))
+    }
+
+    /// The metrics suppressed for a class or method by the `xstats:ignore` comments right above
+    /// it, among its other comments, attributes and decorators, e.g. `// xstats:ignore cc nd`.
+    /// A comment naming no metric suppresses them all, as `all`.
+    pub fn get_suppressed_metrics(&self, entity_node: &Node) -> Vec<String> {
+        // The comments of a decorated, exported or assigned entity are above the whole statement
+        const WRAPPER_NODES: &[&str] = &[
+            "decorated_definition",
+            "export_statement",
+            "variable_declarator",
+            "lexical_declaration",
+            "variable_declaration",
+        ];
+        let mut head = *entity_node;
+        while let Some(parent) = head
+            .parent()
+            .filter(|parent| WRAPPER_NODES.contains(&parent.kind()))
+        {
+            head = parent;
+        }
+
+        // Python puts the comments above the first statement of a block before the block
+        let mut sibling = head.prev_sibling().or_else(|| {
+            head.parent()
+                .filter(|parent| parent.kind() == "block")
+                .and_then(|parent| parent.prev_sibling())
+        });
+        let mut suppressed = Vec::new();
+        while let Some(node) = sibling {
+            if node.kind().contains("comment") {
+                suppressed.extend(parse_suppression(&self.get_text(&node)).unwrap_or_default());
+            } else if !matches!(node.kind(), "attribute_item" | "decorator") {
+                break;
+            }
+            sibling = node.prev_sibling();
+        }
+        suppressed.sort();
+        suppressed.dedup();
+        suppressed
+    }
+
+    /// Find the runs of consecutive statements of a long method worth extracting into methods
+    /// of their own: statements that take few variables from the rest of the method, assign at
+    /// most one it uses later, keep most of their variables to themselves, and neither return nor
+    /// break out of a loop around them. The most cohesive runs that do not overlap are kept.
+    pub fn get_extract_candidates(&self, method_node: &Node) -> Vec<ExtractCandidate> {
+        let aloc = method_node.end_position().row - method_node.start_position().row + 1;
+        let Some(body) = method_node.child_by_field_name("body") else {
+            return Vec::new();
+        };
+        if aloc < LONG_METHOD_ALOC {
+            return Vec::new();
+        }
+
+        let mut cursor = body.walk();
+        let statements: Vec<StatementUsage> = body
+            .named_children(&mut cursor)
+            .filter(|statement| !statement.kind().contains("comment"))
+            .map(|statement| self.get_statement_usage(statement))
+            .collect();
+        let parameters: HashSet<String> =
+            self.get_parameter_names(method_node).into_iter().collect();
+        let mut variables = parameters.clone();
+        for statement in &statements {
+            variables.extend(statement.assigned.iter().cloned());
+        }
+
+        let mut candidates = Vec::new();
+        for start in 0..statements.len() {
+            let mut assigned_before = parameters.clone();
+            for statement in &statements[..start] {
+                assigned_before.extend(statement.assigned.iter().cloned());
+            }
+            for end in start + MIN_EXTRACT_STATEMENTS - 1..statements.len() {
+                let run = &statements[start..=end];
+                // Longer runs would contain the escaping statement too
+                if run.iter().any(|statement| statement.escapes) {
+                    break;
+                }
+                // The whole body is the method itself
+                if start == 0 && end == statements.len() - 1 {
+                    continue;
+                }
+                let (first, last) = (run[0].node, run[run.len() - 1].node);
+                if last.end_position().row - first.start_position().row + 1 < MIN_EXTRACT_LINES {
+                    continue;
+                }
+
+                let mut run_assigned = HashSet::new();
+                let mut run_used = HashSet::new();
+                for statement in run {
+                    run_assigned.extend(statement.assigned.iter());
+                    run_used.extend(
+                        statement
+                            .used
+                            .iter()
+                            .filter(|name| variables.contains(*name)),
+                    );
+                }
+                let mut used_after = HashSet::new();
+                for statement in &statements[end + 1..] {
+                    used_after.extend(statement.used.iter());
+                }
+                let mut inputs: Vec<String> = run_used
+                    .iter()
+                    .filter(|name| assigned_before.contains(**name))
+                    .map(|name| name.to_string())
+                    .collect();
+                let mut outputs: Vec<String> = run_assigned
+                    .iter()
+                    .filter(|name| used_after.contains(*name))
+                    .map(|name| name.to_string())
+                    .collect();
+                if inputs.len() > MAX_EXTRACT_INPUTS || outputs.len() > 1 {
+                    continue;
+                }
+
+                let run_variables: HashSet<&String> =
+                    run_assigned.union(&run_used).copied().collect();
+                let shared = run_variables
+                    .iter()
+                    .filter(|name| inputs.contains(name) || outputs.contains(name))
+                    .count();
+                if run_variables.is_empty() {
+                    continue;
+                }
+                let cohesion = 1.0 - shared as f64 / run_variables.len() as f64;
+                if cohesion < MIN_EXTRACT_COHESION {
+                    continue;
+                }
+                inputs.sort();
+                outputs.sort();
+                candidates.push((
+                    run.len(),
+                    ExtractCandidate {
+                        start_row: first.start_position().row as u32 + 1,
+                        end_row: last.end_position().row as u32 + 1,
+                        inputs,
+                        outputs,
+                        cohesion: (cohesion * 100.0).round() / 100.0,
+                    },
+                ));
+            }
+        }
+
+        // The most cohesive, then the longest, runs first
+        candidates.sort_by(|(a_len, a), (b_len, b)| {
+            b.cohesion
+                .total_cmp(&a.cohesion)
+                .then(b_len.cmp(a_len))
+                .then(a.start_row.cmp(&b.start_row))
+        });
+        let mut kept: Vec<ExtractCandidate> = Vec::new();
+        for (_, candidate) in candidates {
+            let overlaps = kept.iter().any(|other| {
+                candidate.start_row <= other.end_row && other.start_row <= candidate.end_row
+            });
+            if !overlaps && kept.len() < MAX_EXTRACT_CANDIDATES {
+                kept.push(candidate);
+            }
+        }
+        kept.sort_by_key(|candidate| candidate.start_row);
+        kept
+    }
+
+    /// Get the variables a statement assigns and the names it uses, and whether it returns,
+    /// yields or breaks out of a loop it is not part of
+    fn get_statement_usage<'t>(&self, statement: Node<'t>) -> StatementUsage<'t> {
+        fn visit(
+            visitor: &TreeVisitor,
+            node: Node,
+            loop_depth: usize,
+            in_function: bool,
+            usage: &mut StatementUsage,
+        ) {
+            let kind = node.kind();
+            match kind {
+                "identifier" => {
+                    usage.used.insert(visitor.get_text(&node));
+                }
+                // Nested functions and lambdas return from themselves
+                _ if in_function => {}
+                "return_statement" | "return_expression" | "yield" | "yield_expression"
+                | "yield_statement" | "try_expression" => usage.escapes = true,
+                "break_statement"
+                | "continue_statement"
+                | "break_expression"
+                | "continue_expression"
+                    if loop_depth == 0 =>
+                {
+                    usage.escapes = true
+                }
+                _ => {}
+            }
+
+            // The variables declared or assigned, e.g. `int total = 0` or `total = 0`
+            let target = match kind {
+                "variable_declarator" | "enhanced_for_statement" => {
+                    node.child_by_field_name("name")
+                }
+                "let_declaration" | "for_expression" => node.child_by_field_name("pattern"),
+                "assignment"
+                | "assignment_expression"
+                | "augmented_assignment"
+                | "augmented_assignment_expression"
+                | "compound_assignment_expr"
+                | "for_statement"
+                | "for_in_statement" => node.child_by_field_name("left"),
+                "named_expression" => node.child_by_field_name("name"),
+                // `count++`
+                "update_expression" => node
+                    .child_by_field_name("argument")
+                    .or_else(|| node.named_child(0)),
+                _ => None,
+            };
+            if let Some(target) = target {
+                let target_kind = target.kind();
+                if target_kind == "identifier"
+                    || target_kind.contains("pattern")
+                    || target_kind.contains("list")
+                    || target_kind.contains("tuple")
+                {
+                    collect_identifiers(visitor, target, &mut usage.assigned);
+                }
+            }
+
+            // Loops and switches catch the breaks and continues inside them
+            let is_loop = ["for", "while", "loop", "do_statement", "switch"]
+                .iter()
+                .any(|loop_kind| kind.contains(loop_kind));
+            let is_function = [
+                "function",
+                "lambda",
+                "closure",
+                "method_declaration",
+                "method_definition",
+            ]
+            .iter()
+            .any(|function_kind| kind.contains(function_kind));
+            let mut cursor = node.walk();
+            for child in node.named_children(&mut cursor) {
+                visit(
+                    visitor,
+                    child,
+                    loop_depth + is_loop as usize,
+                    in_function || is_function,
+                    usage,
+                );
+            }
+        }
+
+        fn collect_identifiers(visitor: &TreeVisitor, node: Node, names: &mut HashSet<String>) {
+            if node.kind() == "identifier" {
+                names.insert(visitor.get_text(&node));
+            }
+            let mut cursor = node.walk();
+            for child in node.named_children(&mut cursor) {
+                collect_identifiers(visitor, child, names);
+            }
+        }
+
+        let mut usage = StatementUsage {
+            node: statement,
+            assigned: HashSet::new(),
+            used: HashSet::new(),
+            escapes: false,
+        };
+        visit(self, statement, 0, false, &mut usage);
+        usage
+    }
+
+    /// Check if a method is boilerplate: a trivial getter or setter, a method only delegating
+    /// to another call, or one of the methods usually generated (e.g. `equals`, `hashCode` and
+    /// `toString` in Java)
+    pub fn check_if_boilerplate(&self, method_node: &Node) -> bool {
+        if self.is_generated_method(method_node) {
+            return true;
+        }
+
+        let statements = self.get_body_statements(method_node);
+        let [statement] = statements.as_slice() else {
+            return false;
+        };
+        let parameters = self.get_parameter_names(method_node);
+
+        match statement.kind() {
+            // Setter, e.g. `this.owner = owner;`
+            "assignment_expression" | "assignment" => {
+                let (Some(left), Some(right)) = (
+                    statement.child_by_field_name("left"),
+                    statement.child_by_field_name("right"),
+                ) else {
+                    return false;
+                };
+                parameters.len() == 1
+                    && self.is_plain_reference(&left)
+                    && right.kind() == "identifier"
+                    && parameters.contains(&self.get_text(&right))
+            }
+            // Delegation, e.g. `return this.ledger.post(amount);`
+            "method_invocation" | "call_expression" | "call" => {
+                let callee = statement
+                    .child_by_field_name("function")
+                    .or_else(|| statement.child_by_field_name("object"));
+                let callee_is_plain = callee.is_none_or(|callee| self.is_plain_reference(&callee));
+                let arguments_are_plain =
+                    statement
+                        .child_by_field_name("arguments")
+                        .is_some_and(|arguments| {
+                            let mut cursor = arguments.walk();
+                            let all_plain = arguments
+                                .named_children(&mut cursor)
+                                .all(|argument| self.is_plain_reference(&argument));
+                            all_plain
+                        });
+                callee_is_plain && arguments_are_plain
+            }
+            // Getter, e.g. `return this.owner;`
+            _ => parameters.is_empty() && self.is_plain_reference(statement),
+        }
+    }
+
+    /// Check if a method is one of the methods usually generated by IDEs, or derived in Rust
+    fn is_generated_method(&self, method_node: &Node) -> bool {
+        let method_name = self.get_method_name(method_node);
+        get_profile(self.language).is_generated_method(&method_name, method_node, self.source_code)
+    }
+
+    /// Get the statements of a method body, each unwrapped down to its expression, without the
+    /// comments and docstrings
+    fn get_body_statements<'t>(&self, method_node: &Node<'t>) -> Vec<Node<'t>> {
+        let Some(body) = method_node.child_by_field_name("body") else {
+            return Vec::new();
+        };
+        // Arrow functions may have a single expression as body
+        if !matches!(
+            body.kind(),
+            "block" | "statement_block" | "constructor_body"
+        ) {
+            return vec![body];
+        }
+
+        let mut statements = Vec::new();
+        let mut cursor = body.walk();
+        for statement in body.named_children(&mut cursor) {
+            if statement.kind().contains("comment") {
+                continue;
+            }
+            let statement = match statement.kind() {
+                "expression_statement" | "return_statement" | "return_expression" => {
+                    match statement.named_child(0) {
+                        Some(expression) => expression,
+                        None => statement,
+                    }
+                }
+                _ => statement,
+            };
+            // Python docstrings
+            if statement.kind() == "string" && self.language == Language::Python {
+                continue;
+            }
+            statements.push(statement);
+        }
+        statements
+    }
+
+    /// Get the names of the parameters of a method, without the receiver (e.g. `self`)
+    fn get_parameter_names(&self, method_node: &Node) -> Vec<String> {
+        self.get_parameter_nodes(method_node)
+            .iter()
+            .map(|parameter| self.get_text(&self.get_parameter_name_node(parameter)))
+            .collect()
+    }
+
+    /// Check if a node only references a variable or a field, e.g. `owner` or `&self.owner`
+    fn is_plain_reference(&self, node: &Node) -> bool {
+        match node.kind() {
+            "identifier"
+            | "field_identifier"
+            | "property_identifier"
+            | "this"
+            | "self"
+            | "super" => true,
+            "field_access"
+            | "attribute"
+            | "member_expression"
+            | "field_expression"
+            | "reference_expression" => {
+                let mut cursor = node.walk();
+                let all_plain = node
+                    .named_children(&mut cursor)
+                    .all(|child| self.is_plain_reference(&child));
+                all_plain
+            }
+            _ => false,
+        }
+    }
+
+    /// Find the constructs hiding failures or crashing on them, e.g. empty catch blocks, bare
+    /// `except:` or `unwrap()`, with their 1-based row
+    pub fn get_risky_constructs(
+        &self,
+        node: &Node,
+        tree: &Tree,
+    ) -> Result<Vec<RiskyConstruct>, XStatsError> {
+        let parser = self
+            .parsers
+            .get_parser(&self.language)
+            .ok_or(XStatsError::ParserNotFound(self.language))?;
+        // Languages loaded from profile files may not define the query
+        let Some(query_string) = find_query_group(&self.language, "robustness_query") else {
+            return Ok(Vec::new());
+        };
+
+        let mut constructs = Vec::new();
+        for (node, capture_name) in parser.query_tree(node, tree, self.source_code, query_string)? {
+            let kinds = match capture_name.as_str() {
+                "catch" => self.get_handler_risks(&node),
+                "panic" => vec![RiskyConstructKind::Panic],
+                _ => Vec::new(),
+            };
+            let row = node.start_position().row as u32 + 1;
+            constructs.extend(kinds.into_iter().map(|kind| RiskyConstruct { kind, row }));
+        }
+        Ok(constructs)
+    }
+
+    /// Count the return, throw and raise statements of a method, leaving out those of the
+    /// functions and lambdas nested in it, which exit them rather than the method
+    pub fn count_exit_points(&self, node: &Node, tree: &Tree) -> Result<usize, XStatsError> {
+        let parser = self
+            .parsers
+            .get_parser(&self.language)
+            .ok_or(XStatsError::ParserNotFound(self.language))?;
+        let Some(query_string) = find_query_group(&self.language, "exit_query") else {
+            return Ok(0);
+        };
+        let skip_nodes = find_node_group(self.language, "exit_skip_nodes").unwrap_or_default();
+
+        let mut count = 0;
+        for (exit_node, capture_name) in
+            parser.query_tree(node, tree, self.source_code, query_string)?
+        {
+            if capture_name != "exit" {
+                continue;
+            }
+            let mut ancestor = exit_node.parent();
+            let mut is_nested = false;
+            while let Some(parent) = ancestor.filter(|parent| parent.id() != node.id()) {
+                is_nested |= skip_nodes.contains(&parent.kind());
+                ancestor = parent.parent();
+            }
+            if !is_nested {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// The package declared by a file, e.g. `com.shop` for `package com.shop;` in Java
+    pub fn get_package_name(&self, root_node: &Node) -> Option<String> {
+        get_profile(self.language).get_package_name(root_node, self.source_code)
+    }
+
+    /// Count the variables, parameters and constants declared in the node, and check the casing
+    /// of the constants. The receivers `self` and `cls` of Python methods are left out.
+    pub fn get_naming_stats(&self, node: &Node, tree: &Tree) -> Result<NamingStats, XStatsError> {
+        let parser = self
+            .parsers
+            .get_parser(&self.language)
+            .ok_or(XStatsError::ParserNotFound(self.language))?;
+        let Some(query_string) = find_query_group(&self.language, "naming_query") else {
+            return Ok(NamingStats::default());
+        };
+
+        // Whether each name is a constant, by position, as a name may be captured twice
+        let mut names: BTreeMap<usize, (String, bool)> = BTreeMap::new();
+        for (node, capture_name) in parser.query_tree(node, tree, self.source_code, query_string)? {
+            let is_constant = match capture_name.as_str() {
+                "variable" => false,
+                "constant" => true,
+                _ => continue,
+            };
+            let name = names
+                .entry(node.start_byte())
+                .or_insert_with(|| (self.get_text(&node), false));
+            name.1 |= is_constant;
+        }
+
+        let mut stats = NamingStats::default();
+        for (name, is_constant) in names.values() {
+            if get_profile(self.language)
+                .receiver_names()
+                .contains(&name.as_str())
+            {
+                continue;
+            }
+            stats.add_identifier(name, *is_constant);
+        }
+        Ok(stats)
+    }
+
+    /// The risks of a catch or except clause: what it catches and whether it does anything
+    fn get_handler_risks(&self, handler: &Node) -> Vec<RiskyConstructKind> {
+        let Some(body) = handler.child_by_field_name("body").or_else(|| {
+            let mut cursor = handler.walk();
+            let block = handler
+                .named_children(&mut cursor)
+                .find(|child| child.kind() == "block");
+            block
+        }) else {
+            return Vec::new();
+        };
+
+        // The simple names of the caught types, e.g. `Throwable` for `java.lang.Throwable`
+        let mut caught_types = Vec::new();
+        let mut stack = vec![*handler];
+        while let Some(node) = stack.pop() {
+            if node.id() == body.id() {
+                continue;
+            }
+            match node.kind() {
+                "type_identifier" | "identifier"
+                    if node.parent().is_some_and(|parent| {
+                        parent.kind() != "as_pattern_target"
+                            && parent.kind() != "catch_formal_parameter"
+                            && parent.kind() != "catch_clause"
+                    }) =>
+                {
+                    caught_types.push(self.get_text(&node));
+                }
+                _ => {
+                    let mut cursor = node.walk();
+                    stack.extend(node.named_children(&mut cursor));
+                }
+            }
+        }
+
+        let mut cursor = body.walk();
+        let statements: Vec<Node> = body
+            .named_children(&mut cursor)
+            .filter(|statement| !statement.kind().contains("comment"))
+            .collect();
+        let does_nothing = statements.iter().all(|statement| {
+            statement.kind() == "pass_statement"
+                || (statement.kind() == "expression_statement"
+                    && statement
+                        .named_child(0)
+                        .is_some_and(|child| child.kind() == "ellipsis"))
+        });
+
+        let mut kinds = Vec::new();
+        if does_nothing {
+            kinds.push(RiskyConstructKind::EmptyCatch);
+        }
+        let is_python = self.language == Language::Python;
+        if is_python && caught_types.is_empty() {
+            kinds.push(RiskyConstructKind::BareExcept);
+        }
+        let catch_all = if is_python {
+            "BaseException"
+        } else {
+            "Throwable"
+        };
+        if caught_types.iter().any(|name| name == catch_all) {
+            kinds.push(RiskyConstructKind::CatchAll);
+        }
+        // An interrupt is handled by restoring it, or by throwing again
+        let interrupt = if is_python {
+            "KeyboardInterrupt"
+        } else {
+            "InterruptedException"
+        };
+        if caught_types.iter().any(|name| name == interrupt) && !self.contains_rethrow(&body) {
+            kinds.push(RiskyConstructKind::SwallowedInterrupt);
+        }
+        kinds
+    }
+
+    /// Whether a handler body throws or raises, or restores the interrupt of the thread
+    fn contains_rethrow(&self, body: &Node) -> bool {
+        let mut stack = vec![*body];
+        while let Some(node) = stack.pop() {
+            let is_interrupt_call = node.kind() == "method_invocation"
+                && node
+                    .child_by_field_name("name")
+                    .is_some_and(|name| self.get_text(&name) == "interrupt");
+            if matches!(node.kind(), "throw_statement" | "raise_statement") || is_interrupt_call {
+                return true;
+            }
+            let mut cursor = node.walk();
+            stack.extend(node.named_children(&mut cursor));
+        }
+        false
+    }
+
+    fn get_text(&self, node: &Node) -> String {
+        node.utf8_text(self.source_code.as_bytes())
+            .unwrap_or_default()
+            .to_string()
+    }
+}
+
+/// The metrics named by an `xstats:ignore` comment, `all` if it names none, or `None` if the
+/// comment is not one
+fn parse_suppression(comment: &str) -> Option<Vec<String>> {
+    let (_, directive) = comment.split_once("xstats:")?;
+    let arguments = directive.trim_start().strip_prefix("ignore")?;
+    if arguments.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+
+    let metric_names = CodeMetric::get_names();
+    let metrics: Vec<String> = arguments
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | '*' | '/'))
+        .filter(|token| !token.is_empty())
+        .take_while(|token| metric_names.iter().any(|name| name == token))
+        .map(|token| token.to_string())
+        .collect();
+    if metrics.is_empty() {
+        return Some(vec!["all".to_string()]);
+    }
+    Some(metrics)
+}
+
+/// Check if a conditional is the `else if` of another, either directly its alternative (Java) or
+/// in its else clause (JavaScript and Rust)
+fn is_else_if(node: &Node) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    parent.kind() == "else_clause"
+        || (parent.kind() == node.kind()
+            && parent.child_by_field_name("alternative") == Some(*node))
 }
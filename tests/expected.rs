@@ -7,147 +7,81 @@ pub static EXPECTED_METRICS_EXAMPLE1: &[&[&str]] = &[
         "end_row",
         "end_col",
         "node_name",
+        "qualified_name",
+        "entity_id",
         "node_type",
+        "node_kind",
+        "suppressed",
+        "is_broken",
+        "is_boilerplate",
         "aloc",
+        "sloc",
         "eloc",
+        "cloc",
+        "dcloc",
+        "todos",
+        "noi",
         "noc",
+        "nom",
+        "max_method_aloc",
+        "avg_method_aloc",
+        "max_method_cc",
         "cc",
+        "noret",
         "pc",
+        "dpc",
+        "is_variadic",
+        "nd",
+        "lcom",
+        "cbo",
+        "fan_in",
+        "fan_out",
+        "hv",
+        "hd",
+        "he",
+        "mi",
     ],
     &[
-        "Python",
-        "tests/samples/example1/example.py",
+        "Java",
+        "tests/samples/example1/example.java",
         "1",
         "1",
-        "41",
-        "42",
-        "example.py",
-        "module",
-        "41",
-        "10",
-        "4",
+        "24",
         "2",
-        "0",
-    ],
-    &[
-        "Python",
-        "tests/samples/example1/example.py",
-        "22",
-        "1",
-        "35",
-        "31",
-        "ExampleClass",
-        "class_definition",
+        "example.java",
+        "example.java",
+        "a8438ce16b67dee3",
+        "program",
+        "file",
+        "",
+        "false",
+        "false",
+        "24",
         "14",
         "3",
-        "1",
-        "1",
-        "0",
-    ],
-    &[
-        "Python",
-        "tests/samples/example1/example.py",
         "3",
         "1",
-        "14",
-        "29",
-        "greet",
-        "function_definition",
-        "12",
-        "2",
-        "2",
-        "1",
-        "1",
-    ],
-    &[
-        "Python",
-        "tests/samples/example1/example.py",
-        "16",
-        "1",
-        "17",
-        "25",
-        "add_numbers",
-        "function_definition",
-        "2",
         "0",
         "0",
         "1",
         "4",
-    ],
-    &[
-        "Python",
-        "tests/samples/example1/example.py",
-        "19",
-        "1",
-        "20",
-        "27",
-        "say_hello_world",
-        "function_definition",
-        "2",
         "0",
+        "0.00",
         "0",
         "1",
         "0",
-    ],
-    &[
-        "Python",
-        "tests/samples/example1/example.py",
-        "25",
-        "5",
-        "26",
-        "25",
-        "__init__",
-        "function_definition",
-        "2",
-        "0",
         "0",
-        "1",
-        "2",
-    ],
-    &[
-        "Python",
-        "tests/samples/example1/example.py",
-        "28",
-        "5",
-        "29",
-        "38",
-        "greet",
-        "function_definition",
-        "2",
         "0",
+        "false",
         "0",
-        "1",
-        "1",
-    ],
-    &[
-        "Python",
-        "tests/samples/example1/example.py",
-        "31",
-        "5",
-        "32",
-        "29",
-        "add_numbers",
-        "function_definition",
-        "2",
         "0",
         "0",
-        "1",
-        "5",
-    ],
-    &[
-        "Python",
-        "tests/samples/example1/example.py",
-        "34",
-        "5",
-        "35",
-        "31",
-        "say_hello_world",
-        "function_definition",
-        "2",
         "0",
         "0",
-        "1",
-        "1",
+        "331.71",
+        "12.00",
+        "3980.49",
+        "52.11",
     ],
     &[
         "Java",
@@ -156,28 +90,40 @@ pub static EXPECTED_METRICS_EXAMPLE1: &[&[&str]] = &[
         "1",
         "24",
         "2",
-        "example.java",
-        "program",
+        "Example",
+        "Example",
+        "c997a457c45afd90",
+        "class_declaration",
+        "class",
+        "",
+        "false",
+        "false",
         "24",
+        "14",
         "3",
         "3",
         "1",
         "0",
-    ],
-    &[
-        "Java",
-        "tests/samples/example1/example.java",
-        "1",
+        "0",
+        "0",
+        "4",
+        "6",
+        "4.75",
         "1",
-        "24",
-        "2",
-        "Example",
-        "class_declaration",
-        "24",
-        "3",
-        "3",
         "1",
         "0",
+        "0",
+        "0",
+        "false",
+        "0",
+        "4",
+        "0",
+        "0",
+        "0",
+        "331.71",
+        "12.00",
+        "3980.49",
+        "52.11",
     ],
     &[
         "Java",
@@ -187,12 +133,39 @@ pub static EXPECTED_METRICS_EXAMPLE1: &[&[&str]] = &[
         "4",
         "6",
         "main",
+        "Example.main",
+        "3865e4bf8c350a08",
         "method_declaration",
+        "method",
+        "",
+        "false",
+        "false",
+        "3",
         "3",
         "0",
         "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
         "1",
+        "0",
         "1",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "66.42",
+        "3.50",
+        "232.46",
+        "76.70",
     ],
     &[
         "Java",
@@ -202,12 +175,39 @@ pub static EXPECTED_METRICS_EXAMPLE1: &[&[&str]] = &[
         "9",
         "6",
         "add",
+        "Example.add",
+        "397b92a47eec2adc",
         "method_declaration",
+        "method",
+        "",
+        "false",
+        "false",
         "4",
+        "3",
+        "0",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
         "0",
         "1",
         "1",
         "2",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "57.36",
+        "7.50",
+        "430.20",
+        "74.42",
     ],
     &[
         "Java",
@@ -217,12 +217,39 @@ pub static EXPECTED_METRICS_EXAMPLE1: &[&[&str]] = &[
         "16",
         "6",
         "subtract",
+        "Example.subtract",
+        "7d3811bf934d73a7",
         "method_declaration",
+        "method",
+        "",
+        "false",
+        "false",
         "6",
+        "3",
+        "0",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
         "0",
         "1",
         "1",
         "2",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "57.36",
+        "7.50",
+        "430.20",
+        "70.58",
     ],
     &[
         "Java",
@@ -232,11 +259,1895 @@ pub static EXPECTED_METRICS_EXAMPLE1: &[&[&str]] = &[
         "23",
         "6",
         "multiply",
+        "Example.multiply",
+        "674106cf085100c3",
         "method_declaration",
+        "method",
+        "",
+        "false",
+        "false",
         "6",
+        "3",
+        "0",
+        "1",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "1",
+        "2",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "57.36",
+        "7.50",
+        "430.20",
+        "70.58",
+    ],
+    &[
+        "Python",
+        "tests/samples/example1/example.py",
+        "1",
+        "1",
+        "41",
+        "42",
+        "example.py",
+        "example.py",
+        "ca7de3ec943fcc9a",
+        "module",
+        "file",
+        "",
+        "false",
+        "false",
+        "41",
+        "20",
+        "8",
+        "4",
+        "2",
+        "0",
+        "1",
+        "1",
+        "7",
+        "0",
+        "0.00",
+        "0",
+        "2",
         "0",
+        "0",
+        "0",
+        "false",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "665.24",
+        "14.44",
+        "9609.04",
+        "44.78",
+    ],
+    &[
+        "Python",
+        "tests/samples/example1/example.py",
+        "22",
+        "1",
+        "35",
+        "31",
+        "ExampleClass",
+        "example.ExampleClass",
+        "442b61c5c8d82db9",
+        "class_definition",
+        "class",
+        "",
+        "false",
+        "false",
+        "14",
+        "9",
+        "3",
         "1",
         "1",
+        "0",
+        "0",
+        "0",
+        "4",
         "2",
+        "2.00",
+        "1",
+        "1",
+        "0",
+        "0",
+        "0",
+        "false",
+        "0",
+        "3",
+        "0",
+        "0",
+        "0",
+        "230.70",
+        "7.71",
+        "1779.70",
+        "58.32",
+    ],
+    &[
+        "Python",
+        "tests/samples/example1/example.py",
+        "3",
+        "1",
+        "14",
+        "29",
+        "greet",
+        "example.greet",
+        "44c963fa8fb713be",
+        "function_definition",
+        "method",
+        "",
+        "false",
+        "false",
+        "12",
+        "2",
+        "0",
+        "2",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "1",
+        "1",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "33.22",
+        "2.50",
+        "83.05",
+        "65.67",
+    ],
+    &[
+        "Python",
+        "tests/samples/example1/example.py",
+        "16",
+        "1",
+        "17",
+        "25",
+        "add_numbers",
+        "example.add_numbers",
+        "056981c3fdba4e5d",
+        "function_definition",
+        "method",
+        "",
+        "false",
+        "false",
+        "2",
+        "2",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "1",
+        "4",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "65.73",
+        "5.40",
+        "354.94",
+        "80.57",
+    ],
+    &[
+        "Python",
+        "tests/samples/example1/example.py",
+        "19",
+        "1",
+        "20",
+        "27",
+        "say_hello_world",
+        "example.say_hello_world",
+        "cec5765bb1b48b7c",
+        "function_definition",
+        "method",
+        "",
+        "false",
+        "false",
+        "2",
+        "2",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "1",
+        "0",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "15.51",
+        "2.00",
+        "31.02",
+        "84.96",
+    ],
+    &[
+        "Python",
+        "tests/samples/example1/example.py",
+        "25",
+        "5",
+        "26",
+        "25",
+        "__init__",
+        "example.ExampleClass.__init__",
+        "318f12a9193003d9",
+        "function_definition",
+        "method",
+        "",
+        "false",
+        "true",
+        "2",
+        "2",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "0",
+        "1",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "38.04",
+        "6.00",
+        "228.23",
+        "82.23",
+    ],
+    &[
+        "Python",
+        "tests/samples/example1/example.py",
+        "28",
+        "5",
+        "29",
+        "38",
+        "greet",
+        "example.ExampleClass.greet",
+        "b05555c5015096e4",
+        "function_definition",
+        "method",
+        "",
+        "false",
+        "false",
+        "2",
+        "2",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "1",
+        "0",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "19.65",
+        "2.00",
+        "39.30",
+        "84.24",
+    ],
+    &[
+        "Python",
+        "tests/samples/example1/example.py",
+        "31",
+        "5",
+        "32",
+        "29",
+        "add_numbers",
+        "example.ExampleClass.add_numbers",
+        "3e51e933a058accf",
+        "function_definition",
+        "method",
+        "",
+        "false",
+        "false",
+        "2",
+        "2",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "1",
+        "4",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "75.28",
+        "5.00",
+        "376.42",
+        "80.16",
+    ],
+    &[
+        "Python",
+        "tests/samples/example1/example.py",
+        "34",
+        "5",
+        "35",
+        "31",
+        "say_hello_world",
+        "example.ExampleClass.say_hello_world",
+        "15e888968a3b57ee",
+        "function_definition",
+        "method",
+        "",
+        "false",
+        "false",
+        "2",
+        "2",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "1",
+        "0",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "19.65",
+        "2.00",
+        "39.30",
+        "84.24",
+    ],
+];
+
+pub static EXPECTED_METRICS_EXAMPLE3: &[&[&str]] = &[
+    &[
+        "language",
+        "file_path",
+        "start_row",
+        "start_col",
+        "end_row",
+        "end_col",
+        "node_name",
+        "qualified_name",
+        "entity_id",
+        "node_type",
+        "node_kind",
+        "suppressed",
+        "is_broken",
+        "is_boilerplate",
+        "aloc",
+        "sloc",
+        "eloc",
+        "cloc",
+        "dcloc",
+        "todos",
+        "noi",
+        "noc",
+        "nom",
+        "max_method_aloc",
+        "avg_method_aloc",
+        "max_method_cc",
+        "cc",
+        "noret",
+        "pc",
+        "dpc",
+        "is_variadic",
+        "nd",
+        "lcom",
+        "cbo",
+        "fan_in",
+        "fan_out",
+        "hv",
+        "hd",
+        "he",
+        "mi",
+    ],
+    &[
+        "Rust",
+        "tests/samples/example3/example.rs",
+        "1",
+        "1",
+        "40",
+        "1",
+        "example.rs",
+        "example.rs",
+        "d8235ced421b1622",
+        "source_file",
+        "file",
+        "",
+        "false",
+        "false",
+        "40",
+        "31",
+        "5",
+        "3",
+        "2",
+        "0",
+        "1",
+        "4",
+        "4",
+        "0",
+        "0.00",
+        "0",
+        "6",
+        "0",
+        "0",
+        "0",
+        "false",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "906.37",
+        "29.50",
+        "26737.91",
+        "43.54",
+    ],
+    &[
+        "Rust",
+        "tests/samples/example3/example.rs",
+        "5",
+        "1",
+        "8",
+        "2",
+        "Point",
+        "example.Point",
+        "d08838d054470898",
+        "struct_item",
+        "class",
+        "",
+        "false",
+        "false",
+        "4",
+        "4",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "0",
+        "0",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "38.04",
+        "3.12",
+        "118.87",
+        "75.67",
+    ],
+    &[
+        "Rust",
+        "tests/samples/example3/example.rs",
+        "10",
+        "1",
+        "24",
+        "2",
+        "Point",
+        "example.Point",
+        "7296d7feae66ae0d",
+        "impl_item",
+        "class",
+        "",
+        "false",
+        "false",
+        "15",
+        "13",
+        "1",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "2",
+        "8",
+        "5.50",
+        "5",
+        "5",
+        "0",
+        "0",
+        "0",
+        "false",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "354.63",
+        "12.32",
+        "4369.58",
+        "55.82",
+    ],
+    &[
+        "Rust",
+        "tests/samples/example3/example.rs",
+        "26",
+        "1",
+        "30",
+        "2",
+        "fmt::Display for Point",
+        "example.fmt::Display for Point",
+        "eb9ad05d9d661ab3",
+        "impl_item",
+        "class",
+        "",
+        "false",
+        "false",
+        "5",
+        "5",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "1",
+        "3",
+        "3.00",
+        "1",
+        "1",
+        "0",
+        "0",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "178.81",
+        "9.00",
+        "1609.32",
+        "68.85",
+    ],
+    &[
+        "Rust",
+        "tests/samples/example3/example.rs",
+        "32",
+        "1",
+        "39",
+        "2",
+        "geometry",
+        "example.geometry",
+        "bc960b0914f36321",
+        "mod_item",
+        "class",
+        "",
+        "false",
+        "false",
+        "8",
+        "8",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "1",
+        "6",
+        "6.00",
+        "2",
+        "2",
+        "0",
+        "0",
+        "0",
+        "false",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "133.78",
+        "11.79",
+        "1576.73",
+        "65.14",
+    ],
+    &[
+        "Rust",
+        "tests/samples/example3/example.rs",
+        "11",
+        "5",
+        "13",
+        "6",
+        "new",
+        "example.Point.new",
+        "ad1ee95983cf9ebf",
+        "function_item",
+        "method",
+        "",
+        "false",
+        "false",
+        "3",
+        "3",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "0",
+        "2",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "70.31",
+        "5.25",
+        "369.12",
+        "76.52",
+    ],
+    &[
+        "Rust",
+        "tests/samples/example3/example.rs",
+        "16",
+        "5",
+        "23",
+        "6",
+        "quadrant",
+        "example.Point.quadrant",
+        "6c7f7ae8b383accf",
+        "function_item",
+        "method",
+        "",
+        "false",
+        "false",
+        "8",
+        "8",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "5",
+        "0",
+        "0",
+        "0",
+        "false",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "230.70",
+        "8.45",
+        "1949.43",
+        "63.08",
+    ],
+    &[
+        "Rust",
+        "tests/samples/example3/example.rs",
+        "27",
+        "5",
+        "29",
+        "6",
+        "fmt",
+        "example.fmt::Display for Point.fmt",
+        "e6b9b0df71c11378",
+        "function_item",
+        "method",
+        "",
+        "false",
+        "true",
+        "3",
+        "3",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "0",
+        "1",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "138.30",
+        "7.50",
+        "1037.26",
+        "74.47",
+    ],
+    &[
+        "Rust",
+        "tests/samples/example3/example.rs",
+        "33",
+        "5",
+        "38",
+        "6",
+        "is_origin",
+        "example.geometry.is_origin",
+        "b1bb1b1d984aef9a",
+        "function_item",
+        "method",
+        "",
+        "false",
+        "false",
+        "6",
+        "6",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "2",
+        "1",
+        "2",
+        "0",
+        "false",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "116.69",
+        "11.67",
+        "1361.41",
+        "68.28",
+    ],
+];
+
+pub static EXPECTED_METRICS_EXAMPLE4: &[&[&str]] = &[
+    &[
+        "language",
+        "file_path",
+        "start_row",
+        "start_col",
+        "end_row",
+        "end_col",
+        "node_name",
+        "qualified_name",
+        "entity_id",
+        "node_type",
+        "node_kind",
+        "suppressed",
+        "is_broken",
+        "is_boilerplate",
+        "aloc",
+        "sloc",
+        "eloc",
+        "cloc",
+        "dcloc",
+        "todos",
+        "noi",
+        "noc",
+        "nom",
+        "max_method_aloc",
+        "avg_method_aloc",
+        "max_method_cc",
+        "cc",
+        "noret",
+        "pc",
+        "dpc",
+        "is_variadic",
+        "nd",
+        "lcom",
+        "cbo",
+        "fan_in",
+        "fan_out",
+        "hv",
+        "hd",
+        "he",
+        "mi",
+    ],
+    &[
+        "JavaScript",
+        "tests/samples/example4/example.js",
+        "1",
+        "1",
+        "31",
+        "1",
+        "example.js",
+        "example.js",
+        "2b788babebeff839",
+        "program",
+        "file",
+        "",
+        "false",
+        "false",
+        "31",
+        "21",
+        "5",
+        "2",
+        "1",
+        "0",
+        "1",
+        "1",
+        "5",
+        "0",
+        "0.00",
+        "0",
+        "4",
+        "0",
+        "0",
+        "0",
+        "false",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "417.89",
+        "14.29",
+        "5969.84",
+        "48.58",
+    ],
+    &[
+        "JavaScript",
+        "tests/samples/example4/example.js",
+        "6",
+        "1",
+        "14",
+        "2",
+        "Shape",
+        "example.Shape",
+        "21409d40338e1dbc",
+        "class_declaration",
+        "class",
+        "",
+        "false",
+        "false",
+        "9",
+        "8",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "2",
+        "3",
+        "3.00",
+        "2",
+        "2",
+        "0",
+        "0",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "100.00",
+        "7.07",
+        "707.14",
+        "64.91",
+    ],
+    &[
+        "JavaScript",
+        "tests/samples/example4/example.js",
+        "7",
+        "5",
+        "9",
+        "6",
+        "constructor",
+        "example.Shape.constructor",
+        "f0efd3462ab92992",
+        "method_definition",
+        "method",
+        "",
+        "false",
+        "true",
+        "3",
+        "3",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "0",
+        "1",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "30.00",
+        "4.17",
+        "125.00",
+        "79.11",
+    ],
+    &[
+        "JavaScript",
+        "tests/samples/example4/example.js",
+        "11",
+        "5",
+        "13",
+        "6",
+        "describe",
+        "example.Shape.describe",
+        "6d1c95b2597359f1",
+        "method_definition",
+        "method",
+        "",
+        "false",
+        "false",
+        "3",
+        "3",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "2",
+        "1",
+        "0",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "43.02",
+        "3.50",
+        "150.57",
+        "77.88",
+    ],
+    &[
+        "JavaScript",
+        "tests/samples/example4/example.js",
+        "17",
+        "13",
+        "17",
+        "28",
+        "add",
+        "example.add",
+        "35274d4fab687e61",
+        "arrow_function",
+        "method",
+        "",
+        "false",
+        "false",
+        "1",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "0",
+        "2",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "20.68",
+        "4.00",
+        "82.72",
+        "90.65",
+    ],
+    &[
+        "JavaScript",
+        "tests/samples/example4/example.js",
+        "19",
+        "16",
+        "19",
+        "26",
+        "square",
+        "example.square",
+        "8077b14930239f1d",
+        "arrow_function",
+        "method",
+        "",
+        "false",
+        "false",
+        "1",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "0",
+        "1",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "7.92",
+        "3.00",
+        "23.77",
+        "93.57",
+    ],
+    &[
+        "JavaScript",
+        "tests/samples/example4/example.js",
+        "21",
+        "1",
+        "30",
+        "2",
+        "classify",
+        "example.classify",
+        "af39e6911ba8cacb",
+        "function_declaration",
+        "method",
+        "",
+        "false",
+        "false",
+        "10",
+        "10",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "3",
+        "3",
+        "1",
+        "0",
+        "false",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "104.00",
+        "5.14",
+        "534.86",
+        "63.66",
+    ],
+    &[
+        "TypeScript",
+        "tests/samples/example4/example.ts",
+        "1",
+        "1",
+        "22",
+        "1",
+        "example.ts",
+        "example.ts",
+        "554df2efda656d73",
+        "program",
+        "file",
+        "",
+        "false",
+        "false",
+        "22",
+        "17",
+        "3",
+        "1",
+        "1",
+        "0",
+        "1",
+        "1",
+        "2",
+        "0",
+        "0.00",
+        "0",
+        "3",
+        "0",
+        "0",
+        "0",
+        "false",
+        "2",
+        "0",
+        "0",
+        "0",
+        "0",
+        "566.57",
+        "22.29",
+        "12626.37",
+        "51.03",
+    ],
+    &[
+        "TypeScript",
+        "tests/samples/example4/example.ts",
+        "4",
+        "8",
+        "17",
+        "2",
+        "LineReader",
+        "example.LineReader",
+        "183dc29794144dbf",
+        "abstract_class_declaration",
+        "class",
+        "",
+        "false",
+        "false",
+        "14",
+        "13",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "1",
+        "10",
+        "10.00",
+        "3",
+        "3",
+        "0",
+        "0",
+        "0",
+        "false",
+        "2",
+        "0",
+        "0",
+        "0",
+        "0",
+        "385.50",
+        "15.88",
+        "6122.64",
+        "56.49",
+    ],
+    &[
+        "TypeScript",
+        "tests/samples/example4/example.ts",
+        "7",
+        "5",
+        "16",
+        "6",
+        "readLines",
+        "example.LineReader.readLines",
+        "8f07d415ed24371d",
+        "method_definition",
+        "method",
+        "",
+        "false",
+        "false",
+        "10",
+        "10",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "3",
+        "1",
+        "2",
+        "1",
+        "false",
+        "2",
+        "0",
+        "0",
+        "0",
+        "0",
+        "310.00",
+        "13.03",
+        "4040.33",
+        "60.34",
+    ],
+    &[
+        "TypeScript",
+        "tests/samples/example4/example.ts",
+        "19",
+        "8",
+        "21",
+        "2",
+        "isBlank",
+        "example.isBlank",
+        "055459e26550ef0f",
+        "function_declaration",
+        "method",
+        "",
+        "false",
+        "false",
+        "3",
+        "3",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "1",
+        "1",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "74.23",
+        "5.25",
+        "389.71",
+        "76.36",
+    ],
+];
+
+pub static EXPECTED_METRICS_EXAMPLE8: &[&[&str]] = &[
+    &[
+        "language",
+        "file_path",
+        "start_row",
+        "start_col",
+        "end_row",
+        "end_col",
+        "node_name",
+        "qualified_name",
+        "entity_id",
+        "node_type",
+        "node_kind",
+        "suppressed",
+        "is_broken",
+        "is_boilerplate",
+        "aloc",
+        "sloc",
+        "eloc",
+        "cloc",
+        "dcloc",
+        "todos",
+        "noi",
+        "noc",
+        "nom",
+        "max_method_aloc",
+        "avg_method_aloc",
+        "max_method_cc",
+        "cc",
+        "noret",
+        "pc",
+        "dpc",
+        "is_variadic",
+        "nd",
+        "lcom",
+        "cbo",
+        "fan_in",
+        "fan_out",
+        "hv",
+        "hd",
+        "he",
+        "mi",
+    ],
+    &[
+        "C",
+        "tests/samples/example8/example.c",
+        "1",
+        "1",
+        "45",
+        "1",
+        "example.c",
+        "example.c",
+        "7a8965b03e4738df",
+        "translation_unit",
+        "file",
+        "",
+        "false",
+        "false",
+        "45",
+        "35",
+        "6",
+        "5",
+        "1",
+        "1",
+        "3",
+        "2",
+        "3",
+        "0",
+        "0.00",
+        "0",
+        "5",
+        "0",
+        "0",
+        "0",
+        "false",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "779.00",
+        "27.93",
+        "21754.22",
+        "43.02",
+    ],
+    &[
+        "C",
+        "tests/samples/example8/example.c",
+        "9",
+        "9",
+        "12",
+        "2",
+        "stack_t",
+        "example.stack_t",
+        "5739ed5e8c62e585",
+        "struct_specifier",
+        "class",
+        "",
+        "false",
+        "false",
+        "4",
+        "4",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "0",
+        "0",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "30.00",
+        "2.50",
+        "75.00",
+        "76.39",
+    ],
+    &[
+        "C",
+        "tests/samples/example8/example.c",
+        "14",
+        "1",
+        "17",
+        "2",
+        "node",
+        "example.node",
+        "30bcab6b23ba8488",
+        "struct_specifier",
+        "class",
+        "",
+        "false",
+        "false",
+        "4",
+        "4",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "0",
+        "0",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "33.00",
+        "2.50",
+        "82.50",
+        "76.10",
+    ],
+    &[
+        "C",
+        "tests/samples/example8/example.c",
+        "20",
+        "1",
+        "26",
+        "2",
+        "push",
+        "example.push",
+        "1c256ea1ab9e5436",
+        "function_definition",
+        "method",
+        "",
+        "false",
+        "false",
+        "7",
+        "7",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "2",
+        "2",
+        "2",
+        "0",
+        "false",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "156.08",
+        "9.60",
+        "1498.37",
+        "65.94",
+    ],
+    &[
+        "C",
+        "tests/samples/example8/example.c",
+        "29",
+        "1",
+        "34",
+        "2",
+        "next",
+        "example.next",
+        "43efe9d8353ebe1f",
+        "function_definition",
+        "method",
+        "",
+        "false",
+        "false",
+        "6",
+        "6",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "2",
+        "1",
+        "1",
+        "0",
+        "false",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "120.00",
+        "18.00",
+        "2160.00",
+        "68.20",
+    ],
+    &[
+        "C",
+        "tests/samples/example8/example.c",
+        "36",
+        "1",
+        "44",
+        "2",
+        "print_all",
+        "example.print_all",
+        "1bd6500a438c0605",
+        "function_definition",
+        "method",
+        "",
+        "false",
+        "false",
+        "9",
+        "9",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "3",
+        "2",
+        "2",
+        "0",
+        "true",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "131.77",
+        "8.12",
+        "1070.63",
+        "63.94",
+    ],
+    &[
+        "Cpp",
+        "tests/samples/example8/example.cpp",
+        "1",
+        "1",
+        "33",
+        "1",
+        "example.cpp",
+        "example.cpp",
+        "baa36eefaeeaed77",
+        "translation_unit",
+        "file",
+        "",
+        "false",
+        "false",
+        "33",
+        "26",
+        "5",
+        "1",
+        "1",
+        "0",
+        "2",
+        "1",
+        "4",
+        "0",
+        "0.00",
+        "0",
+        "5",
+        "0",
+        "0",
+        "0",
+        "false",
+        "2",
+        "0",
+        "0",
+        "0",
+        "0",
+        "678.35",
+        "29.63",
+        "20099.87",
+        "46.38",
+    ],
+    &[
+        "Cpp",
+        "tests/samples/example8/example.cpp",
+        "7",
+        "1",
+        "17",
+        "2",
+        "Shape",
+        "example.Shape",
+        "247779cd9ce56ee8",
+        "class_specifier",
+        "class",
+        "",
+        "false",
+        "false",
+        "11",
+        "10",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "2",
+        "3",
+        "2.00",
+        "1",
+        "1",
+        "0",
+        "0",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "146.95",
+        "14.00",
+        "2057.24",
+        "61.97",
+    ],
+    &[
+        "Cpp",
+        "tests/samples/example8/example.cpp",
+        "9",
+        "5",
+        "9",
+        "32",
+        "~Shape",
+        "example.Shape.~Shape",
+        "5c2433d232d4195c",
+        "function_definition",
+        "method",
+        "",
+        "false",
+        "false",
+        "1",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "0",
+        "0",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "19.65",
+        "3.00",
+        "58.95",
+        "90.81",
+    ],
+    &[
+        "Cpp",
+        "tests/samples/example8/example.cpp",
+        "10",
+        "5",
+        "12",
+        "6",
+        "area",
+        "example.Shape.area",
+        "b335d7ba5f985aea",
+        "function_definition",
+        "method",
+        "",
+        "false",
+        "false",
+        "3",
+        "3",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "1",
+        "1",
+        "0",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "33.22",
+        "3.00",
+        "99.66",
+        "78.80",
+    ],
+    &[
+        "Cpp",
+        "tests/samples/example8/example.cpp",
+        "19",
+        "1",
+        "30",
+        "2",
+        "total_area",
+        "example.total_area",
+        "02295705ac6ce4d0",
+        "function_definition",
+        "method",
+        "",
+        "false",
+        "false",
+        "12",
+        "12",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "1",
+        "0",
+        "0.00",
+        "0",
+        "4",
+        "2",
+        "2",
+        "1",
+        "false",
+        "2",
+        "0",
+        "0",
+        "0",
+        "0",
+        "406.98",
+        "18.67",
+        "7596.88",
+        "57.65",
+    ],
+    &[
+        "Cpp",
+        "tests/samples/example8/example.cpp",
+        "21",
+        "21",
+        "21",
+        "61",
+        "positive",
+        "example.total_area.positive",
+        "693eff6f3e0585db",
+        "lambda_expression",
+        "method",
+        "",
+        "false",
+        "false",
+        "1",
+        "1",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0.00",
+        "0",
+        "2",
+        "1",
+        "1",
+        "0",
+        "false",
+        "0",
+        "0",
+        "0",
+        "0",
+        "0",
+        "50.19",
+        "6.00",
+        "301.14",
+        "87.82",
     ],
 ];
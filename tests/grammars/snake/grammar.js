@@ -0,0 +1,16 @@
+module.exports = grammar({
+  name: 'snake',
+  extras: $ => [/\s/, $.comment],
+  rules: {
+    source_file: $ => repeat(choice($.class_definition, $.function_definition)),
+    class_definition: $ => seq('class', field('name', $.identifier), '{', repeat($.function_definition), '}'),
+    function_definition: $ => seq('def', field('name', $.identifier), $.block),
+    block: $ => seq('{', repeat($._statement), '}'),
+    _statement: $ => choice($.if_statement, $.while_statement, $.call),
+    if_statement: $ => seq('if', $.identifier, $.block),
+    while_statement: $ => seq('while', $.identifier, $.block),
+    call: $ => seq($.identifier, ';'),
+    identifier: $ => /[a-z_]+/,
+    comment: $ => /#.*/,
+  }
+});
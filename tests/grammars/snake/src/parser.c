@@ -0,0 +1,853 @@
+#include "tree_sitter/parser.h"
+
+#if defined(__GNUC__) || defined(__clang__)
+#pragma GCC diagnostic ignored "-Wmissing-field-initializers"
+#endif
+
+#define LANGUAGE_VERSION 14
+#define STATE_COUNT 32
+#define LARGE_STATE_COUNT 2
+#define SYMBOL_COUNT 21
+#define ALIAS_COUNT 0
+#define TOKEN_COUNT 10
+#define EXTERNAL_TOKEN_COUNT 0
+#define FIELD_COUNT 1
+#define MAX_ALIAS_SEQUENCE_LENGTH 5
+#define PRODUCTION_ID_COUNT 2
+
+enum ts_symbol_identifiers {
+  anon_sym_class = 1,
+  anon_sym_LBRACE = 2,
+  anon_sym_RBRACE = 3,
+  anon_sym_def = 4,
+  anon_sym_if = 5,
+  anon_sym_while = 6,
+  anon_sym_SEMI = 7,
+  sym_identifier = 8,
+  sym_comment = 9,
+  sym_source_file = 10,
+  sym_class_definition = 11,
+  sym_function_definition = 12,
+  sym_block = 13,
+  sym__statement = 14,
+  sym_if_statement = 15,
+  sym_while_statement = 16,
+  sym_call = 17,
+  aux_sym_source_file_repeat1 = 18,
+  aux_sym_class_definition_repeat1 = 19,
+  aux_sym_block_repeat1 = 20,
+};
+
+static const char * const ts_symbol_names[] = {
+  [ts_builtin_sym_end] = "end",
+  [anon_sym_class] = "class",
+  [anon_sym_LBRACE] = "{",
+  [anon_sym_RBRACE] = "}",
+  [anon_sym_def] = "def",
+  [anon_sym_if] = "if",
+  [anon_sym_while] = "while",
+  [anon_sym_SEMI] = ";",
+  [sym_identifier] = "identifier",
+  [sym_comment] = "comment",
+  [sym_source_file] = "source_file",
+  [sym_class_definition] = "class_definition",
+  [sym_function_definition] = "function_definition",
+  [sym_block] = "block",
+  [sym__statement] = "_statement",
+  [sym_if_statement] = "if_statement",
+  [sym_while_statement] = "while_statement",
+  [sym_call] = "call",
+  [aux_sym_source_file_repeat1] = "source_file_repeat1",
+  [aux_sym_class_definition_repeat1] = "class_definition_repeat1",
+  [aux_sym_block_repeat1] = "block_repeat1",
+};
+
+static const TSSymbol ts_symbol_map[] = {
+  [ts_builtin_sym_end] = ts_builtin_sym_end,
+  [anon_sym_class] = anon_sym_class,
+  [anon_sym_LBRACE] = anon_sym_LBRACE,
+  [anon_sym_RBRACE] = anon_sym_RBRACE,
+  [anon_sym_def] = anon_sym_def,
+  [anon_sym_if] = anon_sym_if,
+  [anon_sym_while] = anon_sym_while,
+  [anon_sym_SEMI] = anon_sym_SEMI,
+  [sym_identifier] = sym_identifier,
+  [sym_comment] = sym_comment,
+  [sym_source_file] = sym_source_file,
+  [sym_class_definition] = sym_class_definition,
+  [sym_function_definition] = sym_function_definition,
+  [sym_block] = sym_block,
+  [sym__statement] = sym__statement,
+  [sym_if_statement] = sym_if_statement,
+  [sym_while_statement] = sym_while_statement,
+  [sym_call] = sym_call,
+  [aux_sym_source_file_repeat1] = aux_sym_source_file_repeat1,
+  [aux_sym_class_definition_repeat1] = aux_sym_class_definition_repeat1,
+  [aux_sym_block_repeat1] = aux_sym_block_repeat1,
+};
+
+static const TSSymbolMetadata ts_symbol_metadata[] = {
+  [ts_builtin_sym_end] = {
+    .visible = false,
+    .named = true,
+  },
+  [anon_sym_class] = {
+    .visible = true,
+    .named = false,
+  },
+  [anon_sym_LBRACE] = {
+    .visible = true,
+    .named = false,
+  },
+  [anon_sym_RBRACE] = {
+    .visible = true,
+    .named = false,
+  },
+  [anon_sym_def] = {
+    .visible = true,
+    .named = false,
+  },
+  [anon_sym_if] = {
+    .visible = true,
+    .named = false,
+  },
+  [anon_sym_while] = {
+    .visible = true,
+    .named = false,
+  },
+  [anon_sym_SEMI] = {
+    .visible = true,
+    .named = false,
+  },
+  [sym_identifier] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_comment] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_source_file] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_class_definition] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_function_definition] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_block] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym__statement] = {
+    .visible = false,
+    .named = true,
+  },
+  [sym_if_statement] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_while_statement] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_call] = {
+    .visible = true,
+    .named = true,
+  },
+  [aux_sym_source_file_repeat1] = {
+    .visible = false,
+    .named = false,
+  },
+  [aux_sym_class_definition_repeat1] = {
+    .visible = false,
+    .named = false,
+  },
+  [aux_sym_block_repeat1] = {
+    .visible = false,
+    .named = false,
+  },
+};
+
+enum ts_field_identifiers {
+  field_name = 1,
+};
+
+static const char * const ts_field_names[] = {
+  [0] = NULL,
+  [field_name] = "name",
+};
+
+static const TSFieldMapSlice ts_field_map_slices[PRODUCTION_ID_COUNT] = {
+  [1] = {.index = 0, .length = 1},
+};
+
+static const TSFieldMapEntry ts_field_map_entries[] = {
+  [0] =
+    {field_name, 1},
+};
+
+static const TSSymbol ts_alias_sequences[PRODUCTION_ID_COUNT][MAX_ALIAS_SEQUENCE_LENGTH] = {
+  [0] = {0},
+};
+
+static const uint16_t ts_non_terminal_alias_map[] = {
+  0,
+};
+
+static const TSStateId ts_primary_state_ids[STATE_COUNT] = {
+  [0] = 0,
+  [1] = 1,
+  [2] = 2,
+  [3] = 3,
+  [4] = 4,
+  [5] = 2,
+  [6] = 3,
+  [7] = 7,
+  [8] = 8,
+  [9] = 9,
+  [10] = 10,
+  [11] = 11,
+  [12] = 12,
+  [13] = 12,
+  [14] = 14,
+  [15] = 15,
+  [16] = 16,
+  [17] = 17,
+  [18] = 18,
+  [19] = 17,
+  [20] = 20,
+  [21] = 21,
+  [22] = 22,
+  [23] = 23,
+  [24] = 24,
+  [25] = 25,
+  [26] = 26,
+  [27] = 27,
+  [28] = 28,
+  [29] = 29,
+  [30] = 30,
+  [31] = 31,
+};
+
+static bool ts_lex(TSLexer *lexer, TSStateId state) {
+  START_LEXER();
+  eof = lexer->eof(lexer);
+  switch (state) {
+    case 0:
+      if (eof) ADVANCE(14);
+      ADVANCE_MAP(
+        '#', 30,
+        ';', 23,
+        'c', 10,
+        'd', 5,
+        'i', 6,
+        'w', 8,
+        '{', 16,
+        '}', 17,
+      );
+      if (('\t' <= lookahead && lookahead <= '\r') ||
+          lookahead == ' ') SKIP(0);
+      END_STATE();
+    case 1:
+      if (lookahead == '#') ADVANCE(30);
+      if (lookahead == 'i') ADVANCE(25);
+      if (lookahead == 'w') ADVANCE(26);
+      if (lookahead == '}') ADVANCE(17);
+      if (('\t' <= lookahead && lookahead <= '\r') ||
+          lookahead == ' ') SKIP(1);
+      if (lookahead == '_' ||
+          ('a' <= lookahead && lookahead <= 'z')) ADVANCE(29);
+      END_STATE();
+    case 2:
+      if (lookahead == '#') ADVANCE(30);
+      if (('\t' <= lookahead && lookahead <= '\r') ||
+          lookahead == ' ') SKIP(2);
+      if (lookahead == '_' ||
+          ('a' <= lookahead && lookahead <= 'z')) ADVANCE(29);
+      END_STATE();
+    case 3:
+      if (lookahead == 'a') ADVANCE(13);
+      END_STATE();
+    case 4:
+      if (lookahead == 'e') ADVANCE(21);
+      END_STATE();
+    case 5:
+      if (lookahead == 'e') ADVANCE(7);
+      END_STATE();
+    case 6:
+      if (lookahead == 'f') ADVANCE(19);
+      END_STATE();
+    case 7:
+      if (lookahead == 'f') ADVANCE(18);
+      END_STATE();
+    case 8:
+      if (lookahead == 'h') ADVANCE(9);
+      END_STATE();
+    case 9:
+      if (lookahead == 'i') ADVANCE(11);
+      END_STATE();
+    case 10:
+      if (lookahead == 'l') ADVANCE(3);
+      END_STATE();
+    case 11:
+      if (lookahead == 'l') ADVANCE(4);
+      END_STATE();
+    case 12:
+      if (lookahead == 's') ADVANCE(15);
+      END_STATE();
+    case 13:
+      if (lookahead == 's') ADVANCE(12);
+      END_STATE();
+    case 14:
+      ACCEPT_TOKEN(ts_builtin_sym_end);
+      END_STATE();
+    case 15:
+      ACCEPT_TOKEN(anon_sym_class);
+      END_STATE();
+    case 16:
+      ACCEPT_TOKEN(anon_sym_LBRACE);
+      END_STATE();
+    case 17:
+      ACCEPT_TOKEN(anon_sym_RBRACE);
+      END_STATE();
+    case 18:
+      ACCEPT_TOKEN(anon_sym_def);
+      END_STATE();
+    case 19:
+      ACCEPT_TOKEN(anon_sym_if);
+      END_STATE();
+    case 20:
+      ACCEPT_TOKEN(anon_sym_if);
+      if (lookahead == '_' ||
+          ('a' <= lookahead && lookahead <= 'z')) ADVANCE(29);
+      END_STATE();
+    case 21:
+      ACCEPT_TOKEN(anon_sym_while);
+      END_STATE();
+    case 22:
+      ACCEPT_TOKEN(anon_sym_while);
+      if (lookahead == '_' ||
+          ('a' <= lookahead && lookahead <= 'z')) ADVANCE(29);
+      END_STATE();
+    case 23:
+      ACCEPT_TOKEN(anon_sym_SEMI);
+      END_STATE();
+    case 24:
+      ACCEPT_TOKEN(sym_identifier);
+      if (lookahead == 'e') ADVANCE(22);
+      if (lookahead == '_' ||
+          ('a' <= lookahead && lookahead <= 'z')) ADVANCE(29);
+      END_STATE();
+    case 25:
+      ACCEPT_TOKEN(sym_identifier);
+      if (lookahead == 'f') ADVANCE(20);
+      if (lookahead == '_' ||
+          ('a' <= lookahead && lookahead <= 'z')) ADVANCE(29);
+      END_STATE();
+    case 26:
+      ACCEPT_TOKEN(sym_identifier);
+      if (lookahead == 'h') ADVANCE(27);
+      if (lookahead == '_' ||
+          ('a' <= lookahead && lookahead <= 'z')) ADVANCE(29);
+      END_STATE();
+    case 27:
+      ACCEPT_TOKEN(sym_identifier);
+      if (lookahead == 'i') ADVANCE(28);
+      if (lookahead == '_' ||
+          ('a' <= lookahead && lookahead <= 'z')) ADVANCE(29);
+      END_STATE();
+    case 28:
+      ACCEPT_TOKEN(sym_identifier);
+      if (lookahead == 'l') ADVANCE(24);
+      if (lookahead == '_' ||
+          ('a' <= lookahead && lookahead <= 'z')) ADVANCE(29);
+      END_STATE();
+    case 29:
+      ACCEPT_TOKEN(sym_identifier);
+      if (lookahead == '_' ||
+          ('a' <= lookahead && lookahead <= 'z')) ADVANCE(29);
+      END_STATE();
+    case 30:
+      ACCEPT_TOKEN(sym_comment);
+      if (lookahead != 0 &&
+          lookahead != '\n') ADVANCE(30);
+      END_STATE();
+    default:
+      return false;
+  }
+}
+
+static const TSLexMode ts_lex_modes[STATE_COUNT] = {
+  [0] = {.lex_state = 0},
+  [1] = {.lex_state = 0},
+  [2] = {.lex_state = 1},
+  [3] = {.lex_state = 1},
+  [4] = {.lex_state = 1},
+  [5] = {.lex_state = 1},
+  [6] = {.lex_state = 1},
+  [7] = {.lex_state = 0},
+  [8] = {.lex_state = 0},
+  [9] = {.lex_state = 0},
+  [10] = {.lex_state = 0},
+  [11] = {.lex_state = 0},
+  [12] = {.lex_state = 0},
+  [13] = {.lex_state = 1},
+  [14] = {.lex_state = 1},
+  [15] = {.lex_state = 1},
+  [16] = {.lex_state = 1},
+  [17] = {.lex_state = 0},
+  [18] = {.lex_state = 0},
+  [19] = {.lex_state = 1},
+  [20] = {.lex_state = 0},
+  [21] = {.lex_state = 0},
+  [22] = {.lex_state = 0},
+  [23] = {.lex_state = 0},
+  [24] = {.lex_state = 0},
+  [25] = {.lex_state = 2},
+  [26] = {.lex_state = 0},
+  [27] = {.lex_state = 2},
+  [28] = {.lex_state = 0},
+  [29] = {.lex_state = 2},
+  [30] = {.lex_state = 0},
+  [31] = {.lex_state = 2},
+};
+
+static const uint16_t ts_parse_table[LARGE_STATE_COUNT][SYMBOL_COUNT] = {
+  [0] = {
+    [ts_builtin_sym_end] = ACTIONS(1),
+    [anon_sym_class] = ACTIONS(1),
+    [anon_sym_LBRACE] = ACTIONS(1),
+    [anon_sym_RBRACE] = ACTIONS(1),
+    [anon_sym_def] = ACTIONS(1),
+    [anon_sym_if] = ACTIONS(1),
+    [anon_sym_while] = ACTIONS(1),
+    [anon_sym_SEMI] = ACTIONS(1),
+    [sym_comment] = ACTIONS(3),
+  },
+  [1] = {
+    [sym_source_file] = STATE(28),
+    [sym_class_definition] = STATE(8),
+    [sym_function_definition] = STATE(8),
+    [aux_sym_source_file_repeat1] = STATE(8),
+    [ts_builtin_sym_end] = ACTIONS(5),
+    [anon_sym_class] = ACTIONS(7),
+    [anon_sym_def] = ACTIONS(9),
+    [sym_comment] = ACTIONS(3),
+  },
+};
+
+static const uint16_t ts_small_parse_table[] = {
+  [0] = 6,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(11), 1,
+      anon_sym_RBRACE,
+    ACTIONS(13), 1,
+      anon_sym_if,
+    ACTIONS(15), 1,
+      anon_sym_while,
+    ACTIONS(17), 1,
+      sym_identifier,
+    STATE(3), 5,
+      sym__statement,
+      sym_if_statement,
+      sym_while_statement,
+      sym_call,
+      aux_sym_block_repeat1,
+  [23] = 6,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(13), 1,
+      anon_sym_if,
+    ACTIONS(15), 1,
+      anon_sym_while,
+    ACTIONS(17), 1,
+      sym_identifier,
+    ACTIONS(19), 1,
+      anon_sym_RBRACE,
+    STATE(4), 5,
+      sym__statement,
+      sym_if_statement,
+      sym_while_statement,
+      sym_call,
+      aux_sym_block_repeat1,
+  [46] = 6,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(21), 1,
+      anon_sym_RBRACE,
+    ACTIONS(23), 1,
+      anon_sym_if,
+    ACTIONS(26), 1,
+      anon_sym_while,
+    ACTIONS(29), 1,
+      sym_identifier,
+    STATE(4), 5,
+      sym__statement,
+      sym_if_statement,
+      sym_while_statement,
+      sym_call,
+      aux_sym_block_repeat1,
+  [69] = 6,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(13), 1,
+      anon_sym_if,
+    ACTIONS(15), 1,
+      anon_sym_while,
+    ACTIONS(17), 1,
+      sym_identifier,
+    ACTIONS(32), 1,
+      anon_sym_RBRACE,
+    STATE(6), 5,
+      sym__statement,
+      sym_if_statement,
+      sym_while_statement,
+      sym_call,
+      aux_sym_block_repeat1,
+  [92] = 6,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(13), 1,
+      anon_sym_if,
+    ACTIONS(15), 1,
+      anon_sym_while,
+    ACTIONS(17), 1,
+      sym_identifier,
+    ACTIONS(34), 1,
+      anon_sym_RBRACE,
+    STATE(4), 5,
+      sym__statement,
+      sym_if_statement,
+      sym_while_statement,
+      sym_call,
+      aux_sym_block_repeat1,
+  [115] = 5,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(36), 1,
+      ts_builtin_sym_end,
+    ACTIONS(38), 1,
+      anon_sym_class,
+    ACTIONS(41), 1,
+      anon_sym_def,
+    STATE(7), 3,
+      sym_class_definition,
+      sym_function_definition,
+      aux_sym_source_file_repeat1,
+  [133] = 5,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(7), 1,
+      anon_sym_class,
+    ACTIONS(9), 1,
+      anon_sym_def,
+    ACTIONS(44), 1,
+      ts_builtin_sym_end,
+    STATE(7), 3,
+      sym_class_definition,
+      sym_function_definition,
+      aux_sym_source_file_repeat1,
+  [151] = 4,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(9), 1,
+      anon_sym_def,
+    ACTIONS(46), 1,
+      anon_sym_RBRACE,
+    STATE(11), 2,
+      sym_function_definition,
+      aux_sym_class_definition_repeat1,
+  [165] = 2,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(48), 4,
+      ts_builtin_sym_end,
+      anon_sym_class,
+      anon_sym_RBRACE,
+      anon_sym_def,
+  [175] = 4,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(9), 1,
+      anon_sym_def,
+    ACTIONS(50), 1,
+      anon_sym_RBRACE,
+    STATE(18), 2,
+      sym_function_definition,
+      aux_sym_class_definition_repeat1,
+  [189] = 2,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(52), 4,
+      ts_builtin_sym_end,
+      anon_sym_class,
+      anon_sym_RBRACE,
+      anon_sym_def,
+  [199] = 3,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(52), 1,
+      anon_sym_RBRACE,
+    ACTIONS(54), 3,
+      anon_sym_if,
+      anon_sym_while,
+      sym_identifier,
+  [211] = 3,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(56), 1,
+      anon_sym_RBRACE,
+    ACTIONS(58), 3,
+      anon_sym_if,
+      anon_sym_while,
+      sym_identifier,
+  [223] = 3,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(60), 1,
+      anon_sym_RBRACE,
+    ACTIONS(62), 3,
+      anon_sym_if,
+      anon_sym_while,
+      sym_identifier,
+  [235] = 3,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(64), 1,
+      anon_sym_RBRACE,
+    ACTIONS(66), 3,
+      anon_sym_if,
+      anon_sym_while,
+      sym_identifier,
+  [247] = 2,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(68), 4,
+      ts_builtin_sym_end,
+      anon_sym_class,
+      anon_sym_RBRACE,
+      anon_sym_def,
+  [257] = 4,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(70), 1,
+      anon_sym_RBRACE,
+    ACTIONS(72), 1,
+      anon_sym_def,
+    STATE(18), 2,
+      sym_function_definition,
+      aux_sym_class_definition_repeat1,
+  [271] = 3,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(68), 1,
+      anon_sym_RBRACE,
+    ACTIONS(75), 3,
+      anon_sym_if,
+      anon_sym_while,
+      sym_identifier,
+  [283] = 2,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(77), 3,
+      ts_builtin_sym_end,
+      anon_sym_class,
+      anon_sym_def,
+  [292] = 2,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(79), 3,
+      ts_builtin_sym_end,
+      anon_sym_class,
+      anon_sym_def,
+  [301] = 3,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(81), 1,
+      anon_sym_LBRACE,
+    STATE(10), 1,
+      sym_block,
+  [311] = 3,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(83), 1,
+      anon_sym_LBRACE,
+    STATE(16), 1,
+      sym_block,
+  [321] = 3,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(83), 1,
+      anon_sym_LBRACE,
+    STATE(15), 1,
+      sym_block,
+  [331] = 2,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(85), 1,
+      sym_identifier,
+  [338] = 2,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(87), 1,
+      anon_sym_LBRACE,
+  [345] = 2,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(89), 1,
+      sym_identifier,
+  [352] = 2,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(91), 1,
+      ts_builtin_sym_end,
+  [359] = 2,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(93), 1,
+      sym_identifier,
+  [366] = 2,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(95), 1,
+      anon_sym_SEMI,
+  [373] = 2,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(97), 1,
+      sym_identifier,
+};
+
+static const uint32_t ts_small_parse_table_map[] = {
+  [SMALL_STATE(2)] = 0,
+  [SMALL_STATE(3)] = 23,
+  [SMALL_STATE(4)] = 46,
+  [SMALL_STATE(5)] = 69,
+  [SMALL_STATE(6)] = 92,
+  [SMALL_STATE(7)] = 115,
+  [SMALL_STATE(8)] = 133,
+  [SMALL_STATE(9)] = 151,
+  [SMALL_STATE(10)] = 165,
+  [SMALL_STATE(11)] = 175,
+  [SMALL_STATE(12)] = 189,
+  [SMALL_STATE(13)] = 199,
+  [SMALL_STATE(14)] = 211,
+  [SMALL_STATE(15)] = 223,
+  [SMALL_STATE(16)] = 235,
+  [SMALL_STATE(17)] = 247,
+  [SMALL_STATE(18)] = 257,
+  [SMALL_STATE(19)] = 271,
+  [SMALL_STATE(20)] = 283,
+  [SMALL_STATE(21)] = 292,
+  [SMALL_STATE(22)] = 301,
+  [SMALL_STATE(23)] = 311,
+  [SMALL_STATE(24)] = 321,
+  [SMALL_STATE(25)] = 331,
+  [SMALL_STATE(26)] = 338,
+  [SMALL_STATE(27)] = 345,
+  [SMALL_STATE(28)] = 352,
+  [SMALL_STATE(29)] = 359,
+  [SMALL_STATE(30)] = 366,
+  [SMALL_STATE(31)] = 373,
+};
+
+static const TSParseActionEntry ts_parse_actions[] = {
+  [0] = {.entry = {.count = 0, .reusable = false}},
+  [1] = {.entry = {.count = 1, .reusable = false}}, RECOVER(),
+  [3] = {.entry = {.count = 1, .reusable = true}}, SHIFT_EXTRA(),
+  [5] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_source_file, 0, 0, 0),
+  [7] = {.entry = {.count = 1, .reusable = true}}, SHIFT(25),
+  [9] = {.entry = {.count = 1, .reusable = true}}, SHIFT(27),
+  [11] = {.entry = {.count = 1, .reusable = true}}, SHIFT(12),
+  [13] = {.entry = {.count = 1, .reusable = false}}, SHIFT(29),
+  [15] = {.entry = {.count = 1, .reusable = false}}, SHIFT(31),
+  [17] = {.entry = {.count = 1, .reusable = false}}, SHIFT(30),
+  [19] = {.entry = {.count = 1, .reusable = true}}, SHIFT(17),
+  [21] = {.entry = {.count = 1, .reusable = true}}, REDUCE(aux_sym_block_repeat1, 2, 0, 0),
+  [23] = {.entry = {.count = 2, .reusable = false}}, REDUCE(aux_sym_block_repeat1, 2, 0, 0), SHIFT_REPEAT(29),
+  [26] = {.entry = {.count = 2, .reusable = false}}, REDUCE(aux_sym_block_repeat1, 2, 0, 0), SHIFT_REPEAT(31),
+  [29] = {.entry = {.count = 2, .reusable = false}}, REDUCE(aux_sym_block_repeat1, 2, 0, 0), SHIFT_REPEAT(30),
+  [32] = {.entry = {.count = 1, .reusable = true}}, SHIFT(13),
+  [34] = {.entry = {.count = 1, .reusable = true}}, SHIFT(19),
+  [36] = {.entry = {.count = 1, .reusable = true}}, REDUCE(aux_sym_source_file_repeat1, 2, 0, 0),
+  [38] = {.entry = {.count = 2, .reusable = true}}, REDUCE(aux_sym_source_file_repeat1, 2, 0, 0), SHIFT_REPEAT(25),
+  [41] = {.entry = {.count = 2, .reusable = true}}, REDUCE(aux_sym_source_file_repeat1, 2, 0, 0), SHIFT_REPEAT(27),
+  [44] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_source_file, 1, 0, 0),
+  [46] = {.entry = {.count = 1, .reusable = true}}, SHIFT(20),
+  [48] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_function_definition, 3, 0, 1),
+  [50] = {.entry = {.count = 1, .reusable = true}}, SHIFT(21),
+  [52] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_block, 2, 0, 0),
+  [54] = {.entry = {.count = 1, .reusable = false}}, REDUCE(sym_block, 2, 0, 0),
+  [56] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_call, 2, 0, 0),
+  [58] = {.entry = {.count = 1, .reusable = false}}, REDUCE(sym_call, 2, 0, 0),
+  [60] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_if_statement, 3, 0, 0),
+  [62] = {.entry = {.count = 1, .reusable = false}}, REDUCE(sym_if_statement, 3, 0, 0),
+  [64] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_while_statement, 3, 0, 0),
+  [66] = {.entry = {.count = 1, .reusable = false}}, REDUCE(sym_while_statement, 3, 0, 0),
+  [68] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_block, 3, 0, 0),
+  [70] = {.entry = {.count = 1, .reusable = true}}, REDUCE(aux_sym_class_definition_repeat1, 2, 0, 0),
+  [72] = {.entry = {.count = 2, .reusable = true}}, REDUCE(aux_sym_class_definition_repeat1, 2, 0, 0), SHIFT_REPEAT(27),
+  [75] = {.entry = {.count = 1, .reusable = false}}, REDUCE(sym_block, 3, 0, 0),
+  [77] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_class_definition, 4, 0, 1),
+  [79] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_class_definition, 5, 0, 1),
+  [81] = {.entry = {.count = 1, .reusable = true}}, SHIFT(2),
+  [83] = {.entry = {.count = 1, .reusable = true}}, SHIFT(5),
+  [85] = {.entry = {.count = 1, .reusable = true}}, SHIFT(26),
+  [87] = {.entry = {.count = 1, .reusable = true}}, SHIFT(9),
+  [89] = {.entry = {.count = 1, .reusable = true}}, SHIFT(22),
+  [91] = {.entry = {.count = 1, .reusable = true}},  ACCEPT_INPUT(),
+  [93] = {.entry = {.count = 1, .reusable = true}}, SHIFT(24),
+  [95] = {.entry = {.count = 1, .reusable = true}}, SHIFT(14),
+  [97] = {.entry = {.count = 1, .reusable = true}}, SHIFT(23),
+};
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+#ifdef TREE_SITTER_HIDE_SYMBOLS
+#define TS_PUBLIC
+#elif defined(_WIN32)
+#define TS_PUBLIC __declspec(dllexport)
+#else
+#define TS_PUBLIC __attribute__((visibility("default")))
+#endif
+
+TS_PUBLIC const TSLanguage *tree_sitter_snake(void) {
+  static const TSLanguage language = {
+    .version = LANGUAGE_VERSION,
+    .symbol_count = SYMBOL_COUNT,
+    .alias_count = ALIAS_COUNT,
+    .token_count = TOKEN_COUNT,
+    .external_token_count = EXTERNAL_TOKEN_COUNT,
+    .state_count = STATE_COUNT,
+    .large_state_count = LARGE_STATE_COUNT,
+    .production_id_count = PRODUCTION_ID_COUNT,
+    .field_count = FIELD_COUNT,
+    .max_alias_sequence_length = MAX_ALIAS_SEQUENCE_LENGTH,
+    .parse_table = &ts_parse_table[0][0],
+    .small_parse_table = ts_small_parse_table,
+    .small_parse_table_map = ts_small_parse_table_map,
+    .parse_actions = ts_parse_actions,
+    .symbol_names = ts_symbol_names,
+    .field_names = ts_field_names,
+    .field_map_slices = ts_field_map_slices,
+    .field_map_entries = ts_field_map_entries,
+    .symbol_metadata = ts_symbol_metadata,
+    .public_symbol_map = ts_symbol_map,
+    .alias_map = ts_non_terminal_alias_map,
+    .alias_sequences = &ts_alias_sequences[0][0],
+    .lex_modes = ts_lex_modes,
+    .lex_fn = ts_lex,
+    .primary_state_ids = ts_primary_state_ids,
+  };
+  return &language;
+}
+#ifdef __cplusplus
+}
+#endif
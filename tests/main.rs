@@ -4,16 +4,97 @@ mod expected;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use git2::build::RepoBuilder;
+    use git2::{Oid, Repository, Signature, Time};
+    use log::{Level, LevelFilter, Log, Record};
+    use parquet::basic::Type as PhysicalType;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::{Field, Row};
+    use std::collections::BTreeMap;
+    use std::fs::{self, File};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use xstats::analysis::Hotspots;
+    use xstats::config::{AnalysisConfig, ConfigFile, Language};
+    use xstats::core::{
+        Portfolio, PortfolioManifest, ProgressEvent, Provenance, RunManifest, XStatsBuilder,
+    };
+    use xstats::error::XStatsError;
+    use xstats::graph::{CallConfidence, NodeMetricsIndex};
+    use xstats::languages::{self, get_profile};
+    use xstats::metrics::{
+        estimate_similarity, find_node_group, get_grade, get_minhash, BudgetReport, BudgetSnapshot,
+        CodeMetrics, EntityDiffs, EntityHistory, EntityKind, ExtractSuggestions, LanguageSummary,
+        MarkerReport, MetricsFilter, NamingReport, NearDuplicates, PackageMetrics, QualityScores,
+        RiskyConstructKind, RobustnessReport, ScoreWeights, SIGNATURE_SIZE,
+    };
+    use xstats::output::{create_sink, HtmlSink, OutputSink, StdoutSink};
+    use xstats::ts::{
+        get_shebang_language, InputEdit, Node, Point, TSLanguage, TSParsers, TSTreesBin,
+    };
+    use xstats::utils::csv_dialect::CsvDialect;
+    use xstats::utils::encoding::Encoding;
+    use xstats::utils::logger::{Logger, WarningKind};
+    use xstats::utils::parquet::{encode_parquet, ColumnType};
+    use xstats::utils::path_filter::PathFilter;
+    use xstats::utils::version_control::{
+        is_remote_url, CommitFilter, CommitScope, RepositoryError, RevwalkConfig,
+    };
+    use xstats::utils::{
+        format_utc_time, parse_utc_time, traverse_path, traverse_path_with_options, SymlinkPolicy,
+        TraversalOptions, JSON_SCHEMA_VERSION,
+    };
+    use xstats::visitor::{get_grammar_problems, TreeVisitor};
 
     const SAMPLES_DIR: &str = "tests/samples";
 
+    /// Create an empty git repository in a fresh temporary directory
+    fn init_repo(name: &str) -> (PathBuf, Repository) {
+        let repo_dir = std::env::temp_dir().join(format!("xstats_{}", name));
+        let _ = fs::remove_dir_all(&repo_dir);
+        fs::create_dir_all(&repo_dir).unwrap();
+        let repo = Repository::init(&repo_dir).unwrap();
+        (repo_dir, repo)
+    }
+
+    /// Write the given files into the repository and commit them on HEAD
+    fn commit_files(repo: &Repository, files: &[(&str, &str)], message: &str) -> Oid {
+        let signature = Signature::now("xStats", "xstats@example.com").unwrap();
+        commit_files_as(repo, files, message, &signature)
+    }
+
+    /// Same as `commit_files`, with the given author and committer signature
+    fn commit_files_as(
+        repo: &Repository,
+        files: &[(&str, &str)],
+        message: &str,
+        signature: &Signature,
+    ) -> Oid {
+        let workdir = repo.workdir().unwrap().to_path_buf();
+        let mut index = repo.index().unwrap();
+        for (path, content) in files {
+            let full_path = workdir.join(path);
+            fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+            fs::write(&full_path, content).unwrap();
+            index.add_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        let parents: Vec<_> = parent.iter().collect();
+        repo.commit(Some("HEAD"), signature, signature, message, &tree, &parents)
+            .unwrap()
+    }
+
     #[test]
     fn metric_test_example1() {
         let target_dir = format!("{}/example1", SAMPLES_DIR);
         let output_dir = SAMPLES_DIR.to_string();
 
         let mut xstats = core::XStats::new(target_dir, output_dir);
-        xstats.run_default();
+        xstats.run_default().unwrap();
         let metrics: Vec<Vec<String>> = xstats.metrics_map.get_table(None);
         let metrics_ref: Vec<Vec<&str>> = metrics
             .iter()
@@ -25,4 +106,5052 @@ mod tests {
             expected::EXPECTED_METRICS_EXAMPLE1
         );
     }
+
+    #[test]
+    fn metrics_filter_test_example1() {
+        let target_dir = format!("{}/example1", SAMPLES_DIR);
+        let mut xstats = core::XStats::new(target_dir, SAMPLES_DIR.to_string());
+        xstats.run_default().unwrap();
+        xstats.metrics_map.filter = MetricsFilter {
+            node_types: vec!["method_declaration".to_string()],
+            min_aloc: 3,
+            ..MetricsFilter::new()
+        };
+
+        let metrics = xstats.metrics_map.get_table(None);
+        let expected: Vec<&[&str]> = expected::EXPECTED_METRICS_EXAMPLE1
+            .iter()
+            .filter(|row| row[9] == "method_declaration" && row[14].parse::<u32>().unwrap() >= 3)
+            .copied()
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(metrics[0], expected::EXPECTED_METRICS_EXAMPLE1[0]);
+        assert_eq!(&metrics[1..], expected.as_slice());
+    }
+
+    #[test]
+    fn boilerplate_test_example6() {
+        let target_dir = format!("{}/example6", SAMPLES_DIR);
+        let mut xstats = core::XStats::new(target_dir, SAMPLES_DIR.to_string());
+        xstats.run_default().unwrap();
+        xstats.metrics_map.filter.exclude_boilerplate = true;
+
+        let metrics = xstats.metrics_map.get_table(None);
+        let names: Vec<(&str, &str)> = metrics[1..]
+            .iter()
+            .map(|row| (row[0].as_str(), row[6].as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Java", "Account.java"),
+                ("Java", "Account"),
+                ("Java", "deposit"),
+                ("JavaScript", "account.js"),
+                ("JavaScript", "Account"),
+                ("JavaScript", "deposit"),
+                ("JavaScript", "owner"),
+                ("Python", "account.py"),
+                ("Python", "Account"),
+                ("Python", "__init__"),
+                ("Python", "deposit"),
+                ("Rust", "account.rs"),
+                ("Rust", "Account"),
+                ("Rust", "Account"),
+                ("Rust", "fmt::Display for Account"),
+                ("Rust", "deposit"),
+            ]
+        );
+    }
+
+    #[test]
+    fn metric_test_example3() {
+        let target_dir = format!("{}/example3", SAMPLES_DIR);
+        let mut xstats = core::XStats::new(target_dir, SAMPLES_DIR.to_string());
+        xstats.run_default().unwrap();
+        let metrics: Vec<Vec<String>> = xstats.metrics_map.get_table(None);
+        let metrics_ref: Vec<Vec<&str>> = metrics
+            .iter()
+            .map(|v| v.iter().map(|s| s.as_str()).collect())
+            .collect();
+        let metrics_slice: Vec<&[&str]> = metrics_ref.iter().map(|v| v.as_slice()).collect();
+        assert_eq!(
+            metrics_slice.as_slice(),
+            expected::EXPECTED_METRICS_EXAMPLE3
+        );
+    }
+
+    #[test]
+    fn metric_test_example4() {
+        let target_dir = format!("{}/example4", SAMPLES_DIR);
+        let mut xstats = core::XStats::new(target_dir, SAMPLES_DIR.to_string());
+        xstats.run_default().unwrap();
+        let metrics: Vec<Vec<String>> = xstats.metrics_map.get_table(None);
+        let metrics_ref: Vec<Vec<&str>> = metrics
+            .iter()
+            .map(|v| v.iter().map(|s| s.as_str()).collect())
+            .collect();
+        let metrics_slice: Vec<&[&str]> = metrics_ref.iter().map(|v| v.as_slice()).collect();
+        assert_eq!(
+            metrics_slice.as_slice(),
+            expected::EXPECTED_METRICS_EXAMPLE4
+        );
+    }
+
+    #[test]
+    fn eloc_test_example7() {
+        let target_dir = format!("{}/example7", SAMPLES_DIR);
+        let mut xstats = core::XStats::new(target_dir, SAMPLES_DIR.to_string());
+        xstats.run_default().unwrap();
+
+        // Blank lines inside text blocks, template strings and raw strings are not empty lines
+        let metrics = xstats.metrics_map.get_table(None);
+        let eloc: Vec<(&str, &str)> = metrics[1..]
+            .iter()
+            .map(|row| (row[6].as_str(), row[16].as_str()))
+            .collect();
+        assert_eq!(
+            eloc,
+            vec![
+                ("Query.java", "2"),
+                ("Query", "2"),
+                ("select", "1"),
+                ("query.js", "1"),
+                ("select", "1"),
+                ("query.rs", "1"),
+                ("select", "1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn sloc_test() {
+        let target_dir = std::env::temp_dir().join("xstats_sloc");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(
+            target_dir.join("A.java"),
+            "class A {\n    /*\n\n     * Sums.\n     */\n    int f(int a) { // inline\n\n        String s = \"\"\"\n            a\n\n            \"\"\";\n        return a; /* done */\n    }\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            target_dir.join("a.py"),
+            "def f(a):\n    \"\"\"Sum.\n\n    More.\n    \"\"\"\n    # note\n    return a\n",
+        )
+        .unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        let sloc: Vec<(&str, u32, u32)> = metrics
+            .metric_blocks
+            .iter()
+            .map(|block| {
+                let metric = &block.metric;
+                (block.meta_data.node_name.as_str(), metric.aloc, metric.sloc)
+            })
+            .collect();
+        // Lines mixing code and comments are code, blank lines of strings too but not of docstrings
+        assert_eq!(
+            sloc,
+            [
+                ("A.java", 15, 9),
+                ("A", 14, 9),
+                ("f", 8, 7),
+                ("a.py", 8, 2),
+                ("f", 7, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn doc_comment_prefix_test_example1() {
+        let target_dir = format!("{}/example1/example.java", SAMPLES_DIR);
+        let mut xstats = core::XStats::new(target_dir, SAMPLES_DIR.to_string());
+        xstats.config.add_doc_comment_prefix("java=//").unwrap();
+        xstats.run_default().unwrap();
+
+        // The `// This is a comment` line comment now counts as a doc comment
+        let metrics = xstats.metrics_map.get_table(None);
+        let dcloc: Vec<(&str, &str)> = metrics[1..]
+            .iter()
+            .map(|row| (row[6].as_str(), row[18].as_str()))
+            .collect();
+        assert_eq!(
+            dcloc,
+            vec![
+                ("example.java", "2"),
+                ("Example", "2"),
+                ("main", "0"),
+                ("add", "1"),
+                ("subtract", "0"),
+                ("multiply", "1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn embedded_code_test_example2() {
+        let target_dir = format!("{}/example2", SAMPLES_DIR);
+        let mut xstats = core::XStats::new(target_dir, SAMPLES_DIR.to_string());
+        xstats.run_default().unwrap();
+
+        let metrics = xstats.metrics_map.get_table(None);
+        let positions: Vec<Vec<&str>> = metrics[1..]
+            .iter()
+            .map(|row| row[2..7].iter().map(|s| s.as_str()).collect())
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                vec!["1", "1", "4", "33", "notebook.ipynb[1]"],
+                vec!["3", "1", "4", "33", "area"],
+                vec!["5", "1", "6", "24", "notebook.ipynb[2]"],
+                vec!["6", "1", "10", "5", "page.html[0]"],
+                vec!["6", "1", "9", "19", "greet"],
+                vec!["13", "32", "13", "53", "page.html[1]"],
+            ]
+        );
+    }
+
+    #[test]
+    fn graph_metrics_test_example5() {
+        let target_dir = format!("{}/example5", SAMPLES_DIR);
+        let output_dir = std::env::temp_dir().join("xstats_graph_metrics");
+        let _ = fs::remove_dir_all(&output_dir);
+        let mut xstats = core::XStats::new(target_dir, output_dir.to_string_lossy().to_string());
+        xstats.graphml = true;
+        xstats.gexf = true;
+        xstats.run_default().unwrap();
+        let mut sink = xstats.get_sink("json").unwrap();
+        xstats.save_tdg(sink.as_mut()).unwrap();
+        xstats.save_call_graph(sink.as_mut()).unwrap();
+
+        // Every entity of the graphs carries its key metrics, the project root excepted
+        let tdg = fs::read_to_string(output_dir.join("tdg.dot")).unwrap();
+        let tdg_nodes: Vec<&str> = tdg
+            .lines()
+            .filter(|line| line.contains("label") && !line.contains("->"))
+            .collect();
+        assert!(tdg_nodes.len() > 1);
+        assert!(tdg_nodes[1..].iter().all(|line| line.contains("cc = ")
+            && line.contains("aloc = ")
+            && line.contains("nom = ")));
+
+        let calls = fs::read_to_string(output_dir.join("calls.dot")).unwrap();
+        assert!(calls.contains(r#"label = "com.shop.Cart.total" cc = 1 aloc = 7 nom = 0"#));
+
+        // The same graphs in the GraphML format
+        let calls = fs::read_to_string(output_dir.join("calls.graphml")).unwrap();
+        assert!(calls.contains(r#"<key id="node_cc" for="node" attr.name="cc" attr.type="int"/>"#));
+        assert!(calls.contains(
+            "<data key=\"node_label\">com.shop.Cart.total</data>\n      \
+             <data key=\"node_file\">tests/samples/example5/com/shop/Cart.java</data>\n      \
+             <data key=\"node_kind\">method_declaration</data>\n      \
+             <data key=\"node_start_row\">11</data>\n      \
+             <data key=\"node_cc\">1</data>\n      \
+             <data key=\"node_aloc\">7</data>"
+        ));
+        assert_eq!(calls.matches("<edge ").count(), 8);
+        assert!(calls.contains(r#"<data key="edge_weight">2</data>"#));
+        let tdg = fs::read_to_string(output_dir.join("tdg.graphml")).unwrap();
+        assert!(tdg.contains(r#"<data key="edge_label">uses</data>"#));
+
+        // And in the GEXF format, the weight of the edges being that of GEXF too
+        let calls = fs::read_to_string(output_dir.join("calls.gexf")).unwrap();
+        assert!(calls.contains(r#"<attribute id="cc" title="cc" type="integer"/>"#));
+        assert!(calls.contains(
+            "<node id=\"n0\" label=\"com.shop.Cart.total\">\n        <attvalues>\n          \
+             <attvalue for=\"file\" value=\"tests/samples/example5/com/shop/Cart.java\"/>"
+        ));
+        assert_eq!(calls.matches("<edge ").count(), 8);
+        assert!(calls.contains(r#"weight="2">"#));
+        let tdg = fs::read_to_string(output_dir.join("tdg.gexf")).unwrap();
+        assert!(tdg.contains(r#"<attvalue for="label" value="uses"/>"#));
+
+        // And as JSON nodes and edges, every edge linking the ids of two nodes
+        let tdg: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(output_dir.join("tdg.json")).unwrap())
+                .unwrap();
+        let nodes = tdg["nodes"].as_array().unwrap();
+        let node_ids: Vec<&str> = nodes
+            .iter()
+            .map(|node| node["id"].as_str().unwrap())
+            .collect();
+        let cart = nodes
+            .iter()
+            .find(|node| {
+                node["label"]
+                    == "tests/samples/example5/com/shop/Cart.java:class_declaration:99-609"
+            })
+            .unwrap();
+        assert_eq!(cart["kind"], "class_declaration");
+        assert!(cart["cc"].is_u64() && cart["nom"].is_u64());
+        let edges = tdg["edges"].as_array().unwrap();
+        assert!(edges.iter().all(|edge| {
+            node_ids.contains(&edge["source"].as_str().unwrap())
+                && node_ids.contains(&edge["target"].as_str().unwrap())
+                && edge["weight"].is_u64()
+        }));
+        assert!(edges.iter().any(|edge| edge["label"] == "uses"));
+    }
+
+    #[test]
+    fn coupling_test() {
+        let coupling = |xstats: &core::XStats| -> Vec<(String, [u32; 3])> {
+            let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+            metrics
+                .metric_blocks
+                .iter()
+                .filter(|block| block.meta_data.node_kind == EntityKind::Class)
+                .map(|block| {
+                    let metric = &block.metric;
+                    (
+                        block.meta_data.node_name.clone(),
+                        [metric.cbo, metric.fan_in, metric.fan_out],
+                    )
+                })
+                .collect()
+        };
+
+        // `Item` is imported from another package, `Inventory` is used through a static call
+        let target_dir = format!("{}/example5", SAMPLES_DIR);
+        let mut xstats = core::XStats::new(target_dir, SAMPLES_DIR.to_string());
+        xstats.run_default().unwrap();
+        let mut classes = coupling(&xstats);
+        classes.sort();
+        assert_eq!(
+            classes,
+            [
+                ("Cart".to_string(), [3, 1, 3]),
+                ("Inventory".to_string(), [1, 1, 0]),
+                ("Item".to_string(), [1, 1, 0]),
+                ("Store".to_string(), [1, 1, 1]),
+            ]
+        );
+        // Repeated references make a single heavier edge, e.g. the 4 uses of `Item` in `Cart`
+        let mut uses_weights: Vec<usize> = xstats
+            .tdg
+            .graph
+            .edge_weights()
+            .filter(|edge| edge.label == "uses")
+            .map(|edge| edge.weight)
+            .collect();
+        uses_weights.sort();
+        assert_eq!(uses_weights, [1, 1, 3, 4]);
+
+        let target_dir = std::env::temp_dir().join("xstats_coupling");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(target_dir.join("shop")).unwrap();
+        fs::write(target_dir.join("shop/__init__.py"), "").unwrap();
+        fs::write(
+            target_dir.join("shop/models.py"),
+            "class Item:\n    pass\n\n\nclass Cart:\n    def add(self):\n        return Item()\n",
+        )
+        .unwrap();
+        fs::write(
+            target_dir.join("shop/store.py"),
+            "import json\nfrom .models import Cart as Basket\nimport shop.models\n\n\nclass Store:\n    def open(self):\n        return Basket(), shop.models.Item(), json.dumps({})\n",
+        )
+        .unwrap();
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.run_default().unwrap();
+        assert_eq!(
+            coupling(&xstats),
+            [
+                ("Item".to_string(), [2, 2, 0]),
+                ("Cart".to_string(), [2, 1, 1]),
+                ("Store".to_string(), [2, 0, 2]),
+            ]
+        );
+    }
+
+    #[test]
+    fn cycles_test() {
+        let target_dir = std::env::temp_dir().join("xstats_cycles");
+        let _ = fs::remove_dir_all(&target_dir);
+        for (path, content) in [
+            (
+                "com/a/A.java",
+                "package com.a;\n\nimport com.b.B;\n\nclass A {\n    B b;\n}\n",
+            ),
+            (
+                "com/b/B.java",
+                "package com.b;\n\nimport com.a.C;\n\nclass B {\n    C c;\n    C other;\n}\n",
+            ),
+            ("com/a/C.java", "package com.a;\n\nclass C {\n    D d;\n}\n"),
+            (
+                "com/a/D.java",
+                "package com.a;\n\nclass D {\n    C c;\n    D next;\n}\n",
+            ),
+        ] {
+            let file_path = target_dir.join(path);
+            fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+            fs::write(file_path, content).unwrap();
+        }
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path.clone());
+        xstats.run_default().unwrap();
+
+        // C and D use each other, and the classes of com/a and com/b go round through A, B and C
+        let cycles = xstats.find_cycles();
+        let summary: Vec<_> = cycles
+            .iter()
+            .map(|cycle| {
+                (
+                    cycle.level,
+                    cycle
+                        .members
+                        .iter()
+                        .map(|member| member.name.trim_start_matches(&target_path))
+                        .collect::<Vec<_>>(),
+                    cycle
+                        .dependencies
+                        .iter()
+                        .map(|dependency| {
+                            (
+                                dependency.from.trim_start_matches(&target_path),
+                                dependency.to.trim_start_matches(&target_path),
+                                dependency.weight,
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("class", vec!["C", "D"], vec![("C", "D", 1), ("D", "C", 1)]),
+                (
+                    "package",
+                    vec!["/com/a", "/com/b"],
+                    vec![("/com/a", "/com/b", 1), ("/com/b", "/com/a", 2)]
+                ),
+            ]
+        );
+        assert_eq!(cycles[0].members[0].start_row, Some(3));
+        assert_eq!(cycles[1].members[0].file_path, None);
+
+        let mut sink = xstats.get_sink("csv").unwrap();
+        xstats.save_cycles(sink.as_mut()).unwrap();
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(target_dir.join("cycles.json")).unwrap())
+                .unwrap();
+        assert_eq!(saved[1]["level"], "package");
+    }
+
+    /// Keeps the results in memory, by name
+    #[derive(Default)]
+    struct MemorySink {
+        tables: Vec<(String, Vec<String>, usize)>,
+        summaries: Vec<String>,
+        graphs: Vec<(String, String)>,
+    }
+
+    impl OutputSink for MemorySink {
+        fn write_table(
+            &mut self,
+            name: &str,
+            header: &[String],
+            rows: &[Vec<String>],
+        ) -> Result<(), XStatsError> {
+            self.tables
+                .push((name.to_string(), header.to_vec(), rows.len()));
+            Ok(())
+        }
+
+        fn write_summary(
+            &mut self,
+            name: &str,
+            summary: &serde_json::Value,
+        ) -> Result<(), XStatsError> {
+            assert!(summary["xstats_version"].is_string());
+            self.summaries.push(name.to_string());
+            Ok(())
+        }
+
+        fn write_graph(&mut self, name: &str, dot: &str) -> Result<(), XStatsError> {
+            self.graphs.push((name.to_string(), dot.to_string()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn output_sink_test_example5() {
+        let target_dir = format!("{}/example5", SAMPLES_DIR);
+        let mut xstats = core::XStats::new(target_dir, "unused".to_string());
+        xstats.run_default().unwrap();
+
+        let mut sink = MemorySink::default();
+        xstats.save_run_manifest(&mut sink).unwrap();
+        xstats.save_metrics(&mut sink).unwrap();
+        xstats.save_tdg(&mut sink).unwrap();
+        xstats.save_call_graph(&mut sink).unwrap();
+
+        assert_eq!(sink.summaries, ["run_manifest"]);
+        let (name, header, row_count) = &sink.tables[0];
+        assert_eq!(name, "metrics");
+        assert_eq!(header[0], "language");
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        assert_eq!(*row_count, metrics.metric_blocks.len());
+        assert_eq!(sink.tables[1].0, "calls");
+        let graph_names: Vec<&str> = sink.graphs.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(graph_names, ["tdg", "calls"]);
+        assert!(sink.graphs[0].1.starts_with("digraph"));
+        assert!(!Path::new("unused").exists());
+    }
+
+    #[test]
+    fn call_graph_test_example5() {
+        let target_dir = format!("{}/example5", SAMPLES_DIR);
+        let mut xstats = core::XStats::new(target_dir, SAMPLES_DIR.to_string());
+        xstats.run_default().unwrap();
+
+        let calls = xstats.call_graph.get_table();
+        let edges: Vec<Vec<&str>> = calls[1..]
+            .iter()
+            .map(|row| {
+                vec![
+                    row[1].as_str(),
+                    row[3].as_str(),
+                    row[5].as_str(),
+                    row[6].as_str(),
+                ]
+            })
+            .collect();
+        // The two calls of `price` in `total` make a single edge of weight 2
+        assert_eq!(
+            edges,
+            vec![
+                vec![
+                    "com.shop.Cart.total",
+                    "com.shop.model.Item.Item",
+                    "high",
+                    "1"
+                ],
+                vec![
+                    "com.shop.Cart.total",
+                    "com.shop.model.Item.price",
+                    "high",
+                    "2"
+                ],
+                vec!["com.shop.Cart.total", "com.shop.Cart.log", "high", "1"],
+                vec![
+                    "com.shop.Cart.total",
+                    "com.shop.Inventory.count",
+                    "high",
+                    "1"
+                ],
+                vec!["com.shop.Cart.sizeOf", "com.shop.Cart.size", "low", "1"],
+                vec!["com.shop.Cart.sizeOf", "com.shop.Store.size", "low", "1"],
+                vec!["com.shop.Cart.sizeOf", "com.shop.Store.cart", "high", "1"],
+                vec![
+                    "com.shop.model.Item.add",
+                    "com.shop.model.Item.price",
+                    "high",
+                    "1"
+                ],
+            ]
+        );
+        let dot = xstats.call_graph.to_dot(&NodeMetricsIndex::default());
+        assert!(dot.contains("weight = 2"));
+
+        // Calls on library types, e.g. `items.add(extra)` on a `List`, are not mistaken for
+        // project methods sharing their name
+        let (add, _) = xstats
+            .call_graph
+            .node_names
+            .iter()
+            .find(|(_, name)| name.as_str() == "com.shop.model.Item.add")
+            .unwrap();
+        assert_eq!(xstats.call_graph.fan_in(add, CallConfidence::Low), 0);
+        let (price, _) = xstats
+            .call_graph
+            .node_names
+            .iter()
+            .find(|(_, name)| name.as_str() == "com.shop.model.Item.price")
+            .unwrap();
+        assert_eq!(xstats.call_graph.fan_in(price, CallConfidence::High), 2);
+    }
+
+    #[test]
+    fn changed_lines_test_history() {
+        let (repo_dir, repo) = init_repo("changed_lines");
+        let original = "class A {\n    int one() {\n        return 1;\n    }\n\n    int two() {\n        return 2;\n    }\n}\n";
+        let modified = "class A {\n    int one() {\n        return 1;\n    }\n\n    int two() {\n        int two = 2;\n        return two;\n    }\n}\n";
+        commit_files(&repo, &[("A.java", original)], "Add A");
+        let second = commit_files(&repo, &[("A.java", modified)], "Update A.two");
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+
+        let changes = xstats.changes_map.get_table(&second.to_string());
+        let changed: Vec<(&str, &str, &str)> = changes[1..]
+            .iter()
+            .map(|row| (row[3].as_str(), row[5].as_str(), row[6].as_str()))
+            .collect();
+        assert_eq!(
+            changed,
+            vec![
+                ("A.java", "modified", "2"),
+                ("A", "modified", "2"),
+                ("two", "modified", "2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn churn_test_history() {
+        let (repo_dir, repo) = init_repo("churn");
+        let original = "class A {\n    int one() {\n        return 1;\n    }\n\n    int two() {\n        return 2;\n    }\n}\n";
+        let modified = "class A {\n    int one() {\n        return 0 + 1;\n    }\n\n    int two() {\n        int two = 2;\n        return two;\n    }\n}\n";
+        let first = commit_files(
+            &repo,
+            &[("A.java", original), ("notes.txt", "Not analyzed\n")],
+            "Add A",
+        );
+        let second = commit_files(&repo, &[("A.java", modified)], "Update A");
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+
+        let churn = xstats.churn_metrics.get_table();
+        assert_eq!(
+            churn[0],
+            [
+                "commit_id",
+                "file_path",
+                "change_type",
+                "lines_added",
+                "lines_deleted",
+                "hunks_changed",
+                "old_file_path"
+            ]
+        );
+        // Files of unsupported languages are left out
+        assert_eq!(
+            churn[1..],
+            [
+                [&first.to_string(), "A.java", "added", "9", "0", "1", ""],
+                [&second.to_string(), "A.java", "modified", "3", "2", "2", ""],
+            ]
+        );
+    }
+
+    #[test]
+    fn provenance_test_history() {
+        let (repo_dir, repo) = init_repo("provenance");
+        let commit = commit_files(
+            &repo,
+            &[("A.java", "class A {}\n"), ("b.py", "def b():\n    pass\n")],
+            "Add A and b",
+        );
+
+        let output_dir = repo_dir.join("out");
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            output_dir.to_string_lossy().to_string(),
+        );
+        xstats.provenance = Some(Provenance::new());
+        xstats.run_multi_commit().unwrap();
+        let mut sink = xstats.get_sink("csv").unwrap();
+        xstats.save_metrics_map(sink.as_mut()).unwrap();
+        xstats.save_churn_metrics(sink.as_mut()).unwrap();
+
+        let provenance = xstats.provenance.as_ref().unwrap();
+        let read_rows = |name: &str| -> Vec<Vec<String>> {
+            let content = fs::read_to_string(output_dir.join(name)).unwrap();
+            content
+                .lines()
+                .map(|line| line.split(',').map(|value| value.to_string()).collect())
+                .collect()
+        };
+        let metrics = read_rows(&format!("metrics/{}.csv", commit));
+        assert!(metrics[0].ends_with(&Provenance::get_names()));
+        let java_row = metrics.iter().find(|row| row[0] == "Java").unwrap();
+        assert!(java_row.ends_with(&[
+            commit.to_string(),
+            provenance.analyzed_at.clone(),
+            env!("CARGO_PKG_VERSION").to_string(),
+            format!(
+                "tree-sitter-java {}",
+                provenance.grammar_versions["tree-sitter-java"]
+            ),
+        ]));
+
+        // Rows without a language column take the grammar of their file extension
+        let churn = read_rows("churn.csv");
+        let python_row = churn.iter().find(|row| row[1] == "b.py").unwrap();
+        assert_eq!(python_row[7], commit.to_string());
+        assert!(python_row[10].starts_with("tree-sitter-python "));
+
+        assert_eq!(format_utc_time(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_utc_time(951827696), "2000-02-29T12:34:56Z");
+    }
+
+    #[test]
+    fn hotspots_test_history() {
+        let (repo_dir, repo) = init_repo("hotspots");
+        let complex = |n: u32| {
+            format!(
+                "class A {{\n    int f(int a) {{\n        if (a > {}) {{ return 1; }}\n        if (a < 0) {{ return 2; }}\n        return 0;\n    }}\n}}\n",
+                n
+            )
+        };
+        let simple = |n: u32| format!("class B {{\n    int f() {{ return {}; }}\n}}\n", n);
+        commit_files(
+            &repo,
+            &[
+                ("A.java", &complex(1)),
+                ("B.java", &simple(1)),
+                ("C.java", &complex(1).replace("class A", "class C")),
+            ],
+            "Add A, B and C",
+        );
+        for n in 2..4 {
+            commit_files(
+                &repo,
+                &[("A.java", &complex(n)), ("B.java", &simple(n))],
+                "Update A and B",
+            );
+        }
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+
+        // Complex files changing often come first
+        let hotspots = Hotspots::new(&xstats.churn_metrics, &xstats.metrics_map).get_table();
+        assert_eq!(
+            hotspots,
+            [
+                [
+                    "file_path",
+                    "commits",
+                    "lines_changed",
+                    "cc",
+                    "aloc",
+                    "score"
+                ],
+                ["A.java", "3", "11", "3", "8", "9"],
+                ["B.java", "3", "7", "1", "4", "3"],
+                ["C.java", "1", "7", "3", "8", "3"],
+            ]
+        );
+    }
+
+    #[test]
+    fn html_report_test_history() {
+        let (repo_dir, repo) = init_repo("html_report");
+        commit_files(&repo, &[("A.java", "class A {}\n")], "Add A");
+        commit_files(
+            &repo,
+            &[("A.java", "class A {\n    int one() { return 1; }\n}\n")],
+            "Add A.one",
+        );
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+        let mut sink = HtmlSink::new(&repo_dir.join("out").to_string_lossy(), false);
+        xstats.save_metrics_map(&mut sink).unwrap();
+        xstats.save_churn_metrics(&mut sink).unwrap();
+        xstats.save_hotspots(&mut sink).unwrap();
+
+        // The churn of each commit is charted, the tables of each commit are left out
+        let html = sink.render();
+        assert_eq!(html.matches("<rect class=\"added\"").count(), 2);
+        assert!(html.contains("<div class=\"value\">2</div><div class=\"label\">Commits</div>"));
+        assert!(html.contains("<h2>Hotspots</h2>"));
+        assert!(!html.contains("<h2>Methods</h2>"));
+
+        sink.finish().unwrap();
+        assert!(repo_dir.join("out/report.html").is_file());
+    }
+
+    #[test]
+    fn path_filter_test_example6() {
+        let target_dir = format!("{}/example6", SAMPLES_DIR);
+        let include = vec!["*.java".to_string(), "*.py".to_string()];
+        let exclude = vec!["Account*".to_string()];
+        let path_filter = PathFilter::from_patterns(&include, &exclude).unwrap();
+
+        let files = traverse_path(&target_dir, &path_filter).unwrap();
+        assert_eq!(files, [format!("{}/account.py", target_dir)]);
+        assert!(PathFilter::from_patterns(&[], &["a/[b".to_string()]).is_err());
+    }
+
+    #[test]
+    fn path_filter_test_history() {
+        let (repo_dir, repo) = init_repo("path_filter");
+        let files = [
+            ("src/A.java", "class A {}\n"),
+            ("src/test/ATest.java", "class ATest {}\n"),
+            ("vendor/B.java", "class B {}\n"),
+        ];
+        let commit = commit_files(&repo, &files, "Add A, ATest and B");
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        let exclude = vec!["**/test/**".to_string(), "vendor/**".to_string()];
+        xstats.config.path_filter = PathFilter::from_patterns(&[], &exclude).unwrap();
+        xstats.run_multi_commit().unwrap();
+
+        let metrics = xstats.metrics_map.get_table(Some(&commit.to_string()));
+        let changes = xstats.changes_map.get_table(&commit.to_string());
+        assert!(metrics[1..].iter().all(|row| row[1] == "src/A.java"));
+        assert!(changes[1..].iter().all(|row| row[0] == "src/A.java"));
+        assert_eq!(changes.len(), 3);
+    }
+
+    #[test]
+    fn incremental_parsing_test_history() {
+        let (repo_dir, repo) = init_repo("incremental_parsing");
+        let versions = [
+            "class Cart {\n    int total() {\n        return 0;\n    }\n}\n",
+            // Insert a method in the middle
+            "class Cart {\n    int total() {\n        return 0;\n    }\n\n    void add(int i) {\n        if (i > 0) {\n            total();\n        }\n    }\n}\n",
+            // Change a line, delete others and drop the final newline
+            "class Cart {\n    int total() {\n        return 1;\n    }\n\n    void add(int i) {\n        total();\n    }\n}",
+            // Prepend a field and append a class
+            "class Cart {\n    int count;\n    int total() {\n        return count > 0 ? 1 : 0;\n    }\n\n    void add(int i) {\n        total();\n    }\n}\nclass Item {}\n",
+        ];
+        let commits: Vec<Oid> = versions
+            .iter()
+            .enumerate()
+            .map(|(i, content)| commit_files(&repo, &[("src/Cart.java", content)], &i.to_string()))
+            .collect();
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+
+        // Each version parsed from the tree of the previous one has the metrics of a fresh parse
+        let without_path = |table: Vec<Vec<String>>| -> Vec<Vec<String>> {
+            table
+                .into_iter()
+                .map(|mut row| {
+                    row.remove(1);
+                    row
+                })
+                .collect()
+        };
+        for (commit, content) in commits.iter().zip(versions) {
+            let version_dir = std::env::temp_dir().join("xstats_incremental_parsing_version");
+            let _ = fs::remove_dir_all(&version_dir);
+            fs::create_dir_all(version_dir.join("src")).unwrap();
+            fs::write(version_dir.join("src/Cart.java"), content).unwrap();
+            let mut fresh = core::XStats::new(
+                version_dir.to_string_lossy().to_string(),
+                version_dir.join("out").to_string_lossy().to_string(),
+            );
+            fresh.run_default().unwrap();
+
+            assert_eq!(
+                without_path(xstats.metrics_map.get_table(Some(&commit.to_string()))),
+                without_path(fresh.metrics_map.get_table(None))
+            );
+        }
+    }
+
+    #[test]
+    fn query_cache_test() {
+        let mut parsers = TSParsers::new();
+        let source_code = "class A {\n    void f() {}\n    void g() {}\n}\n";
+        let tree = parsers.parse_source(&Language::Java, source_code).unwrap();
+        let parser = parsers.get_parser(&Language::Java).unwrap();
+
+        // The same query is compiled once, whatever the node it runs on
+        let query = "(method_declaration name: (identifier) @name)";
+        let root_node = tree.root_node();
+        let names = parser
+            .query_tree(&root_node, &tree, source_code, query)
+            .unwrap();
+        assert_eq!(names.len(), 2);
+        for (node, _) in &names {
+            let method = node.parent().unwrap();
+            let method_names = parser
+                .query_tree(&method, &tree, source_code, query)
+                .unwrap();
+            assert_eq!(method_names, vec![(*node, "name".to_string())]);
+        }
+        assert_eq!(parser.num_compiled_queries(), 1);
+
+        // Invalid queries fail every time, without being cached
+        for _ in 0..2 {
+            assert!(parser
+                .query_tree(&root_node, &tree, source_code, "(not_a_node)")
+                .is_err());
+        }
+        assert_eq!(parser.num_compiled_queries(), 1);
+    }
+
+    #[test]
+    fn trees_budget_test() {
+        let mut parsers = TSParsers::new();
+        let mut trees_bin = TSTreesBin::new();
+        trees_bin.set_budget(Some(2), None);
+        for name in ["A", "B", "C"] {
+            let source_code = format!("class {} {{\n    void f() {{}}\n}}\n", name);
+            let tree = parsers.parse_source(&Language::Java, &source_code).unwrap();
+            trees_bin.insert_tree(name, tree);
+            // The least recently used tree is evicted, A is kept by using it
+            trees_bin.get_tree("A");
+        }
+        assert!(trees_bin.get_tree("A").is_some());
+        assert!(trees_bin.get_tree("B").is_none());
+        assert!(trees_bin.get_tree("C").is_some());
+        assert_eq!(trees_bin.num_evictions(), 1);
+
+        // The size of a tree grows with its nodes
+        let size = trees_bin.get_size();
+        assert!(size > 0);
+        trees_bin.delete_tree("C");
+        assert!(trees_bin.get_size() < size);
+        trees_bin.set_budget(None, Some(1));
+        assert_eq!(trees_bin.num_trees(), 0);
+        assert_eq!(trees_bin.get_size(), 0);
+
+        // Files whose tree is evicted are parsed in full, with the same metrics
+        let (repo_dir, repo) = init_repo("trees_budget");
+        for i in 0..3 {
+            let files: Vec<(String, String)> = ["A", "B"]
+                .iter()
+                .map(|name| {
+                    let content = format!(
+                        "class {0} {{\n    int f() {{\n        return {1};\n    }}\n}}\n",
+                        name,
+                        "1 + ".repeat(i) + "0"
+                    );
+                    (format!("src/{}.java", name), content)
+                })
+                .collect();
+            let file_refs: Vec<(&str, &str)> = files
+                .iter()
+                .map(|(path, content)| (path.as_str(), content.as_str()))
+                .collect();
+            commit_files(&repo, &file_refs, &i.to_string());
+        }
+        let run = |max_trees: Option<usize>| {
+            let mut xstats = core::XStats::new(
+                repo_dir.to_string_lossy().to_string(),
+                repo_dir.join("out").to_string_lossy().to_string(),
+            );
+            xstats.set_trees_budget(max_trees, None);
+            xstats.run_multi_commit().unwrap();
+            xstats
+                .commit_infos
+                .commits
+                .iter()
+                .map(|commit| xstats.metrics_map.get_table(Some(&commit.commit_id)))
+                .collect::<Vec<_>>()
+        };
+        let metrics = run(None);
+        assert_eq!(metrics.len(), 3);
+        assert!(metrics.iter().all(|table| table.len() == 1 + 2 * 3));
+        assert_eq!(run(Some(1)), metrics);
+    }
+
+    #[test]
+    fn commit_jobs_test_history() {
+        let (repo_dir, repo) = init_repo("commit_jobs");
+        for i in 0..12 {
+            let content = format!(
+                "class C{0} {{\n    int f() {{\n        return {1};\n    }}\n}}\n",
+                i % 3,
+                "x > 0 ? 1 : ".repeat(i) + "0"
+            );
+            let path = format!("src/C{}.java", i % 3);
+            commit_files(&repo, &[(&path, &content)], &format!("Change {}", i));
+        }
+
+        let run = |commit_jobs: usize| {
+            let mut xstats = core::XStats::new(
+                repo_dir.to_string_lossy().to_string(),
+                repo_dir.join("out").to_string_lossy().to_string(),
+            );
+            xstats.commit_jobs = commit_jobs;
+            xstats.run_multi_commit().unwrap();
+            let commits: Vec<String> = xstats
+                .commit_infos
+                .commits
+                .iter()
+                .map(|commit| commit.commit_id.clone())
+                .collect();
+            let metrics: Vec<Vec<Vec<String>>> = commits
+                .iter()
+                .map(|commit| xstats.metrics_map.get_table(Some(commit)))
+                .collect();
+            let changes: Vec<Vec<Vec<String>>> = commits
+                .iter()
+                .map(|commit| xstats.changes_map.get_table(commit))
+                .collect();
+            (commits, metrics, changes, xstats.churn_metrics.get_table())
+        };
+        // Commits analyzed in parallel are merged in the order of the history
+        let serial = run(1);
+        assert_eq!(serial.0.len(), 12);
+        // The metrics of every commit cover all the files so far, changed or not
+        for (i, metrics) in serial.1.iter().enumerate() {
+            assert_eq!(metrics.len(), 1 + 3 * (i + 1).min(3));
+        }
+        assert_eq!(run(4), serial);
+    }
+
+    #[test]
+    fn stream_test_history() {
+        let (repo_dir, repo) = init_repo("stream");
+        for i in 0..3 {
+            let content = format!(
+                "class C {{\n    int f{0}() {{\n        return {0};\n    }}\n}}\n",
+                i
+            );
+            commit_files(&repo, &[("src/C.java", &content)], &format!("Change {}", i));
+        }
+        let output_dir = repo_dir.join("out");
+
+        let run = |append: bool| {
+            let mut xstats = core::XStats::new(
+                repo_dir.to_string_lossy().to_string(),
+                output_dir.to_string_lossy().to_string(),
+            );
+            xstats.start_stream("json", append)?;
+            xstats.run_multi_commit()?;
+            Ok::<_, XStatsError>(xstats)
+        };
+        let count_lines = |name: &str| {
+            fs::read_to_string(output_dir.join(name))
+                .unwrap()
+                .lines()
+                .count()
+        };
+
+        // The results of each commit are saved as soon as it is analyzed
+        let xstats = run(false).unwrap();
+        assert_eq!(count_lines("commits.jsonl"), 3);
+        assert_eq!(
+            count_lines("churn.jsonl"),
+            xstats.churn_metrics.get_table().len() - 1
+        );
+        for (i, commit) in xstats.commit_infos.commits.iter().enumerate() {
+            let metrics =
+                fs::read_to_string(output_dir.join(format!("metrics/{}.json", commit.commit_id)))
+                    .unwrap();
+            assert!(metrics.contains(&format!("f{}", i)));
+            assert!(output_dir
+                .join(format!("changes/{}.json", commit.commit_id))
+                .exists());
+        }
+        let commit: serde_json::Value = serde_json::from_str(
+            fs::read_to_string(output_dir.join("commits.jsonl"))
+                .unwrap()
+                .lines()
+                .next()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            commit["commit_id"],
+            xstats.commit_infos.commits[0].commit_id
+        );
+
+        // The streamed files of a previous run are only continued when appending
+        assert!(matches!(run(false), Err(XStatsError::OutputExists(_))));
+        run(true).unwrap();
+        assert_eq!(count_lines("commits.jsonl"), 6);
+    }
+
+    #[test]
+    fn resume_test_history() {
+        let (repo_dir, repo) = init_repo("resume");
+        for i in 0..4 {
+            let content = format!(
+                "class C{0} {{\n    int f() {{\n        return {0};\n    }}\n}}\n",
+                i
+            );
+            let path = format!("src/C{}.java", i);
+            commit_files(&repo, &[(&path, &content)], &format!("Add C{}", i));
+        }
+        let output_dir = repo_dir.join("out");
+        let new_xstats = || {
+            core::XStats::new(
+                repo_dir.to_string_lossy().to_string(),
+                output_dir.to_string_lossy().to_string(),
+            )
+        };
+        let read_lines = |name: &str| -> Vec<String> {
+            let content = fs::read_to_string(output_dir.join(name)).unwrap();
+            content.lines().map(|line| line.to_string()).collect()
+        };
+
+        let mut xstats = new_xstats();
+        xstats.start_stream("csv", false).unwrap();
+        xstats.run_multi_commit().unwrap();
+        let commits = read_lines("commits.csv");
+        let churn = read_lines("churn.csv");
+        assert_eq!((commits.len(), churn.len()), (1 + 4, 1 + 4));
+
+        // Interrupt the run while the third commit is saved, its churn appended but not its row
+        // of the commits, the last of which is cut short
+        fs::write(
+            output_dir.join("commits.csv"),
+            commits[..3].join("\n") + "\n" + &commits[3][..10],
+        )
+        .unwrap();
+        fs::write(output_dir.join("churn.csv"), churn[..4].join("\n") + "\n").unwrap();
+
+        let mut xstats = new_xstats();
+        xstats.force = true;
+        assert_eq!(xstats.resume_stream("csv").unwrap(), 2);
+        xstats.run_multi_commit().unwrap();
+        assert_eq!(xstats.commit_infos.commits.len(), 2);
+        assert_eq!(read_lines("commits.csv"), commits);
+        assert_eq!(read_lines("churn.csv"), churn);
+
+        // A streamed file which cannot be read, or whose header is malformed, is not continued
+        fs::write(output_dir.join("commits.csv"), b"commit_id\xff\n").unwrap();
+        assert!(matches!(
+            new_xstats().resume_stream("csv"),
+            Err(XStatsError::InvalidTable { .. })
+        ));
+        fs::remove_file(output_dir.join("commits.csv")).unwrap();
+        fs::create_dir(output_dir.join("commits.csv")).unwrap();
+        assert!(matches!(
+            new_xstats().resume_stream("csv"),
+            Err(XStatsError::Read { .. })
+        ));
+    }
+
+    #[test]
+    fn parallel_test_history() {
+        let (repo_dir, repo) = init_repo("parallel");
+        let files: Vec<(String, String)> = (0..40)
+            .map(|i| {
+                let content = format!(
+                    "class C{0} {{\n    int f{0}() {{\n        return {0};\n    }}\n}}\n",
+                    i
+                );
+                (format!("src/C{:02}.java", i), content)
+            })
+            .collect();
+        let file_refs: Vec<(&str, &str)> = files
+            .iter()
+            .map(|(path, content)| (path.as_str(), content.as_str()))
+            .collect();
+        let commit = commit_files(&repo, &file_refs, "Add 40 classes");
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+
+        // Files analyzed in parallel are still reported in the order of the diff
+        let metrics = xstats.metrics_map.get_table(Some(&commit.to_string()));
+        let mut metric_files: Vec<&str> = metrics[1..].iter().map(|row| row[1].as_str()).collect();
+        metric_files.dedup();
+        let expected_files: Vec<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(metric_files, expected_files);
+        assert_eq!(metrics.len(), 1 + 40 * 3);
+
+        let changes = xstats.changes_map.get_table(&commit.to_string());
+        assert_eq!(changes.len(), 1 + 40 * 3);
+    }
+
+    #[test]
+    fn commit_range_test_history() {
+        let (repo_dir, repo) = init_repo("commit_range");
+        let commits: Vec<Oid> = (1..=4)
+            .map(|i| {
+                let content = format!(
+                    "class A {{\n    int f() {{\n        return {};\n    }}\n}}\n",
+                    i
+                );
+                commit_files(&repo, &[("A.java", &content)], &format!("Commit {}", i))
+            })
+            .collect();
+        let target_path = repo_dir.to_string_lossy().to_string();
+        let output_path = repo_dir.join("out").to_string_lossy().to_string();
+        let analyzed_commits = |revwalk_config: RevwalkConfig| {
+            let mut xstats = core::XStats::new(target_path.clone(), output_path.clone());
+            xstats.revwalk_config = revwalk_config;
+            xstats.run_multi_commit().map(|()| {
+                let mut keys: Vec<String> = xstats.metrics_map.metrics.into_keys().collect();
+                keys.sort();
+                keys
+            })
+        };
+        let sorted_ids = |oids: &[Oid]| {
+            let mut ids: Vec<String> = oids.iter().map(|oid| oid.to_string()).collect();
+            ids.sort();
+            ids
+        };
+
+        let range = RevwalkConfig {
+            since: Some(commits[0].to_string()),
+            until: Some(commits[2].to_string()[..8].to_string()),
+            ..RevwalkConfig::default()
+        };
+        assert_eq!(analyzed_commits(range).unwrap(), sorted_ids(&commits[1..3]));
+
+        let latest = RevwalkConfig {
+            max_commits: Some(1),
+            ..RevwalkConfig::default()
+        };
+        assert_eq!(analyzed_commits(latest).unwrap(), sorted_ids(&commits[3..]));
+
+        let invalid = RevwalkConfig {
+            since: Some("v9.9".to_string()),
+            ..RevwalkConfig::default()
+        };
+        assert!(matches!(
+            analyzed_commits(invalid),
+            Err(XStatsError::Repository(RepositoryError::InvalidRevision(_)))
+        ));
+    }
+
+    #[test]
+    fn commit_filter_test_history() {
+        let (repo_dir, repo) = init_repo("commit_filter");
+        let messages = [
+            "feat: add A",
+            "Merge pull request #1 from fork/feature",
+            "feat(a): update A",
+            "fix: update A",
+        ];
+        let commits: Vec<Oid> = messages
+            .iter()
+            .enumerate()
+            .map(|(i, message)| {
+                let content = format!(
+                    "class A {{\n    int f() {{\n        return {};\n    }}\n}}\n",
+                    i
+                );
+                commit_files(&repo, &[("A.java", &content)], message)
+            })
+            .collect();
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        let skip = vec!["Merge pull request*".to_string()];
+        let only = vec!["feat:*".to_string(), "feat(*):*".to_string()];
+        xstats.revwalk_config.commit_filter = CommitFilter::from_patterns(&skip, &only).unwrap();
+        xstats.run_multi_commit().unwrap();
+
+        let mut analyzed: Vec<String> = xstats.metrics_map.metrics.into_keys().collect();
+        analyzed.sort();
+        let mut expected = vec![commits[0].to_string(), commits[2].to_string()];
+        expected.sort();
+        assert_eq!(analyzed, expected);
+    }
+
+    #[test]
+    fn issue_refs_test_history() {
+        let (repo_dir, repo) = init_repo("issue_refs");
+        let first = commit_files(
+            &repo,
+            &[("A.java", "class A {}\n")],
+            "PROJ-12: add A\n\nCloses #34, see also PROJ-12 and OPS_2-7.",
+        );
+        let second = commit_files(
+            &repo,
+            &[("A.java", "class A { int a; }\n")],
+            "Switch to UTF8 and C# 10, not abc#5 or x-1",
+        );
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+        let table = xstats.commit_infos.get_table();
+        assert_eq!(
+            table[0],
+            [
+                "commit_id",
+                "committed_at",
+                "summary",
+                "issue_refs",
+                "author"
+            ]
+        );
+        assert_eq!(table[1][0], first.to_string());
+        assert_eq!(table[1][2], "PROJ-12: add A");
+        assert_eq!(table[1][3], "PROJ-12,#34,OPS_2-7");
+        assert_eq!(table[2][0], second.to_string());
+        assert_eq!(table[2][3], "");
+
+        let mut sink = xstats.get_sink("csv").unwrap();
+        xstats.save_commits(sink.as_mut()).unwrap();
+        assert!(repo_dir.join("out/commits.csv").exists());
+    }
+
+    #[test]
+    fn budget_test_history() {
+        let (repo_dir, repo) = init_repo("budgets");
+        let simple = "package com.foo;\n\nclass Bar {\n    int render(int x) {\n        return x;\n    }\n}\n";
+        let branchy = "package com.foo;\n\nclass Bar {\n    int render(int x) {\n        if (x > 0) {\n            return x;\n        }\n        return 0;\n    }\n}\n";
+        let first = commit_files(&repo, &[("src/com/foo/Bar.java", simple)], "Add Bar");
+        let second = commit_files(&repo, &[("src/com/foo/Bar.java", branchy)], "Branch");
+        let third = commit_files(
+            &repo,
+            &[(
+                "app/models.py",
+                "class Cart:\n    def total(self, items):\n        if items:\n            return sum(items)\n        return 0\n",
+            )],
+            "Add Cart",
+        );
+        commit_files(&repo, &[("src/com/foo/Bar.java", simple)], "Simplify");
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+
+        let budgets: BTreeMap<String, BTreeMap<String, f64>> = [
+            ("com.foo.Bar.render", "cc", 1.0),
+            ("foo.Bar", "cc", 5.0),
+            ("com/foo/Bar.java", "aloc", 4.0),
+            ("app.models.Cart.total", "cc", 1.0),
+            ("org.foo.Bar", "cc", 1.0),
+        ]
+        .into_iter()
+        .map(|(entity, metric, budget)| {
+            (
+                entity.to_string(),
+                BTreeMap::from([(metric.to_string(), budget)]),
+            )
+        })
+        .collect();
+        let snapshots: Vec<BudgetSnapshot> = xstats
+            .commit_infos
+            .commits
+            .iter()
+            .map(|commit| BudgetSnapshot {
+                label: commit.commit_id.clone(),
+                committed_at: Some(commit.committed_at),
+                metrics: xstats.metrics_map.get_metrics(&commit.commit_id).unwrap(),
+            })
+            .collect();
+        let table = BudgetReport::new(&budgets, &snapshots).get_table();
+        let rows: Vec<Vec<&str>> = table
+            .iter()
+            .map(|row| {
+                [0, 1, 2, 3, 4, 6, 7]
+                    .iter()
+                    .map(|idx| row[*idx].as_str())
+                    .collect()
+            })
+            .collect();
+        let (first, second, third) = (first.to_string(), second.to_string(), third.to_string());
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    "entity",
+                    "metric",
+                    "budget",
+                    "status",
+                    "exceeded_by",
+                    "exceeded_value",
+                    "latest_value",
+                ],
+                vec![
+                    "app.models.Cart.total",
+                    "cc",
+                    "1",
+                    "exceeded",
+                    &third,
+                    "2",
+                    "2"
+                ],
+                vec![
+                    "com.foo.Bar.render",
+                    "cc",
+                    "1",
+                    "recovered",
+                    &second,
+                    "2",
+                    "1"
+                ],
+                vec![
+                    "com/foo/Bar.java",
+                    "aloc",
+                    "4",
+                    "exceeded",
+                    &first,
+                    "8",
+                    "8"
+                ],
+                vec!["foo.Bar", "cc", "5", "within", "", "", "1"],
+                vec!["org.foo.Bar", "cc", "1", "not_found", "", "", ""],
+            ]
+        );
+
+        xstats.config.budgets = budgets;
+        let mut sink = xstats.get_sink("csv").unwrap();
+        xstats.save_budgets(sink.as_mut()).unwrap();
+        assert!(repo_dir.join("out/budgets.csv").exists());
+    }
+
+    #[test]
+    fn entity_diff_test_history() {
+        let (repo_dir, repo) = init_repo("entity_diff");
+        let original = "class A {\n    int one() {\n        return 1;\n    }\n\n    int two(int x) {\n        return x;\n    }\n}\n";
+        let updated = "class A {\n    int uno() {\n        return 1;\n    }\n\n    int two(int x) {\n        if (x > 0) {\n            return x;\n        }\n        return 0;\n    }\n}\n";
+        let first = commit_files(&repo, &[("A.java", original)], "Add A");
+        let second = commit_files(&repo, &[("A.java", updated)], "Rename A.one, update A.two");
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+
+        let diffs = xstats
+            .metrics_map
+            .compare(&first.to_string(), &second.to_string())
+            .unwrap();
+        let table = diffs.get_table();
+        let cc_idx = table[0].iter().position(|name| name == "old_cc").unwrap();
+        let annotated: Vec<Vec<&str>> = table[1..]
+            .iter()
+            .map(|row| {
+                vec![
+                    row[0].as_str(),
+                    row[4].as_str(),
+                    row[5].as_str(),
+                    row[cc_idx].as_str(),
+                    row[cc_idx + 1].as_str(),
+                ]
+            })
+            .collect();
+        assert_eq!(
+            annotated,
+            vec![
+                vec!["modified", "A.java", "A.java", "1", "2"],
+                vec!["modified", "A", "A", "1", "2"],
+                vec!["modified", "two", "two", "1", "2"],
+                vec!["renamed", "one", "uno", "1", "1"],
+            ]
+        );
+    }
+
+    #[test]
+    fn nesting_depth_test() {
+        let target_dir = std::env::temp_dir().join("xstats_nesting_depth");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        let java = "class A {\n    int f(int[] xs) {\n        for (int x : xs) {\n            if (x > 0) {\n                return 1;\n            } else if (x < 0) {\n                while (x < 0) { x++; }\n            }\n        }\n        return 0;\n    }\n\n    int g() { return 0; }\n}\n";
+        let python = "def f(xs):\n    try:\n        for x in xs:\n            if x:\n                pass\n            elif not x:\n                pass\n    except ValueError:\n        pass\n";
+        let rust = "fn f(x: i32) -> i32 {\n    if x > 0 {\n        1\n    } else if x < 0 {\n        -1\n    } else {\n        0\n    }\n}\n";
+        fs::write(target_dir.join("A.java"), java).unwrap();
+        fs::write(target_dir.join("f.py"), python).unwrap();
+        fs::write(target_dir.join("f.rs"), rust).unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        let nd: Vec<(&str, u32)> = metrics
+            .metric_blocks
+            .iter()
+            .map(|block| (block.meta_data.node_name.as_str(), block.metric.nd))
+            .collect();
+        // An `else if` is at the depth of its `if`, and enclosing entities take the deepest
+        assert_eq!(
+            nd,
+            [
+                ("A.java", 3),
+                ("A", 3),
+                ("f", 3),
+                ("g", 0),
+                ("f.py", 3),
+                ("f", 3),
+                ("f.rs", 1),
+                ("f", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn parameters_test() {
+        let target_dir = std::env::temp_dir().join("xstats_parameters");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        let java = "class A {\n    void f(int a, String b) {}\n    void g(A this, String... names) {}\n}\n";
+        let python = "class B:\n    def f(self, a, b=1, *, c: int = 2, **kwargs):\n        pass\n\ndef g(*args: int, /):\n    pass\n";
+        let rust = "struct C;\nimpl C {\n    fn f(&self, a: u8, #[allow(unused)] b: u8) {}\n}\n";
+        let typescript =
+            "function f(a: number, b = 1, c?: string, ...rest: number[]) {}\nconst g = x => x;\n";
+        fs::write(target_dir.join("A.java"), java).unwrap();
+        fs::write(target_dir.join("b.py"), python).unwrap();
+        fs::write(target_dir.join("c.rs"), rust).unwrap();
+        fs::write(target_dir.join("d.ts"), typescript).unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        let parameters: Vec<(String, u32, u32, bool)> = metrics
+            .metric_blocks
+            .iter()
+            .filter(|block| block.meta_data.node_kind == EntityKind::Method)
+            .map(|block| {
+                let file_name = Path::new(&block.meta_data.file_path).file_name().unwrap();
+                (
+                    format!(
+                        "{}:{}",
+                        file_name.to_string_lossy(),
+                        block.meta_data.node_name
+                    ),
+                    block.metric.pc,
+                    block.metric.dpc,
+                    block.metric.is_variadic,
+                )
+            })
+            .collect();
+        // Punctuation, receivers and separators are not parameters
+        assert_eq!(
+            parameters,
+            [
+                ("A.java:f".to_string(), 2, 0, false),
+                ("A.java:g".to_string(), 1, 0, true),
+                ("b.py:f".to_string(), 4, 2, true),
+                ("b.py:g".to_string(), 1, 0, true),
+                ("c.rs:f".to_string(), 2, 0, false),
+                ("d.ts:f".to_string(), 4, 1, true),
+                ("d.ts:g".to_string(), 1, 0, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn exit_points_test() {
+        let target_dir = std::env::temp_dir().join("xstats_exit_points");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        let java = "class A {\n    int f(int x) {\n        if (x < 0) throw new IllegalArgumentException();\n        Runnable r = () -> { return; };\n        return x;\n    }\n}\n";
+        let python = "def f(x):\n    def g():\n        return 1\n    if x:\n        raise ValueError()\n    return g()\n";
+        let rust = "fn f(x: i32) -> i32 {\n    let g = |y: i32| { return y; };\n    if x < 0 {\n        return 0;\n    }\n    g(x)\n}\n";
+        fs::write(target_dir.join("A.java"), java).unwrap();
+        fs::write(target_dir.join("f.py"), python).unwrap();
+        fs::write(target_dir.join("f.rs"), rust).unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        let noret: Vec<(&str, u32)> = metrics
+            .metric_blocks
+            .iter()
+            .map(|block| (block.meta_data.node_name.as_str(), block.metric.noret))
+            .collect();
+        // The exits of nested functions and lambdas are their own, and only methods are counted
+        assert_eq!(
+            noret,
+            [
+                ("A.java", 0),
+                ("A", 0),
+                ("f", 2),
+                ("f$lambda@L4", 1),
+                ("f.py", 0),
+                ("f", 2),
+                ("g", 1),
+                ("f.rs", 0),
+                ("f", 1),
+                ("f$lambda@L2", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn class_method_summary_test() {
+        let target_dir = std::env::temp_dir().join("xstats_class_method_summary");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        let java = "class A {\n    void f(int x) {\n        if (x > 0) {\n            x++;\n        }\n    }\n\n    void g() {}\n\n    static class B {\n        void h(int x) {\n            if (x > 0) { x++; } else if (x < 0) { x--; }\n            x = 0;\n            x = 1;\n            x = 2;\n            x = 3;\n        }\n    }\n}\n";
+        fs::write(target_dir.join("A.java"), java).unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        let summaries: Vec<(&str, u32, f64, u32)> = metrics
+            .metric_blocks
+            .iter()
+            .map(|block| {
+                (
+                    block.meta_data.node_name.as_str(),
+                    block.metric.max_method_aloc,
+                    block.metric.avg_method_aloc,
+                    block.metric.max_method_cc,
+                )
+            })
+            .collect();
+        // The methods of a nested class are only those of the nested class
+        assert_eq!(
+            summaries,
+            [
+                ("A.java", 0, 0.0, 0),
+                ("A", 5, 3.0, 2),
+                ("B", 7, 7.0, 3),
+                ("f", 0, 0.0, 0),
+                ("g", 0, 0.0, 0),
+                ("h", 0, 0.0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn lcom_test() {
+        let target_dir = std::env::temp_dir().join("xstats_lcom");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        let java = "class Account {\n    private int balance;\n    private String owner;\n\n    Account(int balance, String owner) { this.balance = balance; this.owner = owner; }\n\n    void deposit(int amount) { balance += amount; }\n    boolean canWithdraw(int amount) { return this.balance >= amount; }\n    void withdraw(int amount) { if (canWithdraw(amount)) { log(); } }\n    void log() {}\n    String getOwner() { return owner; }\n}\n";
+        let python = "class Stack:\n    def __init__(self):\n        self.items = []\n        self.name = ''\n\n    def push(self, item):\n        self.items.append(item)\n\n    def pop(self):\n        return self.items.pop()\n\n    def rename(self, name):\n        self.name = name\n\n\nclass Empty:\n    pass\n";
+        fs::write(target_dir.join("Account.java"), java).unwrap();
+        fs::write(target_dir.join("stack.py"), python).unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        let lcom: Vec<(&str, u32)> = metrics
+            .metric_blocks
+            .iter()
+            .filter(|block| block.meta_data.node_kind == EntityKind::Class)
+            .map(|block| (block.meta_data.node_name.as_str(), block.metric.lcom))
+            .collect();
+        // The constructor does not relate the methods using `balance` to `getOwner`
+        assert_eq!(lcom, [("Account", 2), ("Stack", 2), ("Empty", 0)]);
+    }
+
+    #[test]
+    fn suppression_comment_test() {
+        let target_dir = std::env::temp_dir().join("xstats_suppression_comment");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        let java = "// xstats:ignore\nclass A {\n    /** Max */\n    // xstats:ignore cc, nd\n    @Override\n    int max(int a, int b) { if (a > b) { return a; } return b; }\n\n    int min(int a, int b) { if (a < b) { return a; } return b; }\n}\n";
+        let python = "class B:\n    # xstats: ignore cc\n    @staticmethod\n    def f(x):\n        if x:\n            return 1\n        return 0\n";
+        fs::write(target_dir.join("A.java"), java).unwrap();
+        fs::write(target_dir.join("b.py"), python).unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.config.thresholds = std::collections::BTreeMap::from([("cc".to_string(), 1.0)]);
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        let suppressed: Vec<(&str, String)> = metrics
+            .metric_blocks
+            .iter()
+            .map(|block| {
+                (
+                    block.meta_data.node_name.as_str(),
+                    block.meta_data.suppressed.join(","),
+                )
+            })
+            .collect();
+        assert_eq!(
+            suppressed,
+            [
+                ("A.java", String::new()),
+                ("A", "all".to_string()),
+                ("max", "cc,nd".to_string()),
+                ("min", String::new()),
+                ("b.py", String::new()),
+                ("B", String::new()),
+                ("f", "cc".to_string()),
+            ]
+        );
+
+        // Suppressed metrics are still measured, but not checked
+        let max = &metrics.metric_blocks[2];
+        assert_eq!(max.metric.cc, 2);
+        let violations: Vec<String> = xstats
+            .check_thresholds()
+            .into_iter()
+            .map(|v| v.meta_data.node_name)
+            .collect();
+        assert_eq!(violations, ["A.java", "min", "b.py", "B"]);
+    }
+
+    #[test]
+    fn markers_test() {
+        let target_dir = std::env::temp_dir().join("xstats_markers");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(
+            target_dir.join("A.java"),
+            "// TODO: split this class\nclass A {\n    /* FIXME handle nulls */\n    void f() {\n        // TODOS are not markers, nor is XXXL\n        g(); // HACK\n    }\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            target_dir.join("a.py"),
+            "def f():\n    \"\"\"Compute.\n\n    XXX: slow\n    \"\"\"\n    # BUG: off by one\n    return 1\n",
+        )
+        .unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        let todos: Vec<(&str, u32)> = metrics
+            .metric_blocks
+            .iter()
+            .map(|block| (block.meta_data.node_name.as_str(), block.metric.todos))
+            .collect();
+        assert_eq!(
+            todos,
+            [("A.java", 3), ("A", 2), ("f", 1), ("a.py", 1), ("f", 1)]
+        );
+        let table = MarkerReport::new(metrics).get_table();
+        let rows: Vec<Vec<&str>> = table
+            .iter()
+            .map(|row| row[1..].iter().map(|value| value.as_str()).collect())
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ["row", "marker", "text"],
+                ["1", "TODO", "TODO: split this class"],
+                ["3", "FIXME", "FIXME handle nulls"],
+                ["6", "HACK", "HACK"],
+                ["4", "XXX", "XXX: slow"],
+            ]
+        );
+
+        // The markers can be replaced
+        xstats
+            .config
+            .set_markers(&["BUG".to_string(), "TODO".to_string()])
+            .unwrap();
+        assert!(xstats
+            .config
+            .set_markers(&["NOTE THIS".to_string()])
+            .is_err());
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        let table = MarkerReport::new(metrics).get_table();
+        assert_eq!(table.len(), 3);
+        assert_eq!(table[2][2..], ["BUG", "BUG: off by one"]);
+    }
+
+    #[test]
+    fn robustness_test() {
+        let target_dir = std::env::temp_dir().join("xstats_robustness");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(
+            target_dir.join("A.java"),
+            "class A {\n    void f() {\n        try { g(); } catch (java.lang.Throwable t) { }\n        try { Thread.sleep(1); } catch (InterruptedException | IllegalStateException e) { log(e); }\n        try { Thread.sleep(1); } catch (InterruptedException e) { Thread.currentThread().interrupt(); }\n        try { g(); } catch (Exception e) {\n            // ignored\n        }\n    }\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            target_dir.join("a.py"),
+            "try:\n    f()\nexcept:\n    pass\ntry:\n    f()\nexcept (KeyboardInterrupt, ValueError) as e:\n    log(e)\nexcept BaseException:\n    raise\nexcept KeyboardInterrupt:\n    raise\nexcept ValueError:\n    # nothing\n    ...\n",
+        )
+        .unwrap();
+        fs::write(
+            target_dir.join("a.js"),
+            "try { f() } catch (e) { }\ntry { g() } catch { h() }\n",
+        )
+        .unwrap();
+        fs::write(
+            target_dir.join("a.rs"),
+            "fn f() {\n    x.unwrap();\n    y.expect(\"a\");\n    panic!(\"b\");\n    Some(1).unwrap_or(2);\n}\n",
+        )
+        .unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+
+        let java_constructs: Vec<(RiskyConstructKind, u32)> = metrics
+            .metric_blocks
+            .iter()
+            .find(|block| block.meta_data.node_name == "A.java")
+            .unwrap()
+            .risky_constructs
+            .iter()
+            .map(|construct| (construct.kind, construct.row))
+            .collect();
+        assert_eq!(
+            java_constructs,
+            [
+                (RiskyConstructKind::EmptyCatch, 3),
+                (RiskyConstructKind::CatchAll, 3),
+                (RiskyConstructKind::SwallowedInterrupt, 4),
+                (RiskyConstructKind::EmptyCatch, 6),
+            ]
+        );
+        assert_eq!(
+            RobustnessReport::new(metrics).get_table(),
+            [
+                ["language", "construct", "count", "files"],
+                ["Java", "empty_catch", "2", "1"],
+                ["Java", "catch_all", "1", "1"],
+                ["Java", "swallowed_interrupt", "1", "1"],
+                ["JavaScript", "empty_catch", "1", "1"],
+                ["Python", "empty_catch", "2", "1"],
+                ["Python", "bare_except", "1", "1"],
+                ["Python", "catch_all", "1", "1"],
+                ["Python", "swallowed_interrupt", "1", "1"],
+                ["Rust", "panic", "3", "1"],
+            ]
+        );
+    }
+
+    #[test]
+    fn naming_test() {
+        let target_dir = std::env::temp_dir().join("xstats_naming");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(
+            target_dir.join("Cart.java"),
+            "class shopping_cart {\n    static final int maxItems = 10;\n    static final int MIN_ITEMS = 1;\n    private int count;\n    void Add_item(int n, String itemName) {\n        for (int i = 0; i < n; i++) { count++; }\n    }\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            target_dir.join("cart.py"),
+            "from typing import Final\nRATE: Final = 0.2\nlimit: Final[int] = 3\nclass Cart:\n    def __total__(self, items, d=0):\n        s = 0\n        for x, y in items:\n            s += x\n        return s\n",
+        )
+        .unwrap();
+        fs::write(
+            target_dir.join("cart.rs"),
+            "const maxSize: usize = 3;\nfn Compute(values: &[u32]) -> u32 {\n    let mut total = 0;\n    for v in values { total += v; }\n    let f = |a| a + 1;\n    f(total)\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            target_dir.join("cart.js"),
+            "function addItem(cart, count = 1) {\n    const total = cart.length;\n    return total + count;\n}\n",
+        )
+        .unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        let table = NamingReport::new(metrics, &xstats.metrics_map.filter).get_table();
+        // The file paths are those of the temporary directory
+        let rows: Vec<Vec<&str>> = table
+            .iter()
+            .map(|row| {
+                let mut row: Vec<&str> = row.iter().map(|value| value.as_str()).collect();
+                row.remove(1);
+                row
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                [
+                    "language",
+                    "start_row",
+                    "node_name",
+                    "node_type",
+                    "name_style",
+                    "name_ok",
+                    "nonconforming_constants",
+                    "identifiers",
+                    "mean_identifier_length",
+                    "max_identifier_length",
+                    "single_letter_identifiers"
+                ],
+                [
+                    "Java",
+                    "1",
+                    "Cart.java",
+                    "program",
+                    "",
+                    "",
+                    "maxItems",
+                    "6",
+                    "5.33",
+                    "9",
+                    "2"
+                ],
+                [
+                    "Java",
+                    "1",
+                    "shopping_cart",
+                    "class_declaration",
+                    "pascal_case",
+                    "false",
+                    "maxItems",
+                    "6",
+                    "5.33",
+                    "9",
+                    "2"
+                ],
+                [
+                    "Java",
+                    "5",
+                    "Add_item",
+                    "method_declaration",
+                    "camel_case",
+                    "false",
+                    "",
+                    "3",
+                    "3.33",
+                    "8",
+                    "2"
+                ],
+                [
+                    "JavaScript",
+                    "1",
+                    "cart.js",
+                    "program",
+                    "",
+                    "",
+                    "",
+                    "3",
+                    "4.67",
+                    "5",
+                    "0"
+                ],
+                [
+                    "JavaScript",
+                    "1",
+                    "addItem",
+                    "function_declaration",
+                    "camel_case",
+                    "true",
+                    "",
+                    "3",
+                    "4.67",
+                    "5",
+                    "0"
+                ],
+                ["Python", "1", "cart.py", "module", "", "", "limit", "7", "2.57", "5", "4"],
+                [
+                    "Python",
+                    "4",
+                    "Cart",
+                    "class_definition",
+                    "pascal_case",
+                    "true",
+                    "",
+                    "5",
+                    "1.80",
+                    "5",
+                    "4"
+                ],
+                [
+                    "Python",
+                    "5",
+                    "__total__",
+                    "function_definition",
+                    "snake_case",
+                    "true",
+                    "",
+                    "5",
+                    "1.80",
+                    "5",
+                    "4"
+                ],
+                [
+                    "Rust",
+                    "1",
+                    "cart.rs",
+                    "source_file",
+                    "",
+                    "",
+                    "maxSize",
+                    "6",
+                    "3.50",
+                    "7",
+                    "3"
+                ],
+                [
+                    "Rust",
+                    "2",
+                    "Compute",
+                    "function_item",
+                    "snake_case",
+                    "false",
+                    "",
+                    "5",
+                    "2.80",
+                    "6",
+                    "3"
+                ],
+                [
+                    "Rust",
+                    "5",
+                    "Compute$lambda@L5",
+                    "closure_expression",
+                    "",
+                    "",
+                    "",
+                    "1",
+                    "1.00",
+                    "1",
+                    "1"
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_query_test() {
+        let target_dir = std::env::temp_dir().join("xstats_custom_query");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(
+            target_dir.join("App.java"),
+            "class App {\n    void run() {\n        System.out.println(1);\n        System.out.println(2);\n    }\n    void stop() {\n        System.out.println(3);\n    }\n}\n",
+        )
+        .unwrap();
+        fs::write(target_dir.join("app.py"), "def run():\n    print(1)\n").unwrap();
+        let query_path = target_dir.join("println.query");
+        fs::write(
+            &query_path,
+            "(method_invocation object: (field_access) @object name: (identifier) @name (#eq? @name \"println\"))",
+        )
+        .unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        let spec = format!("println={}", query_path.to_string_lossy());
+        xstats.config.add_custom_query(&spec).unwrap();
+        // The query is only valid for the Java grammar
+        assert_eq!(
+            xstats.config.custom_queries["println"].languages,
+            [Language::Java]
+        );
+        assert!(xstats.config.add_custom_query("cc=println.query").is_err());
+        let invalid_path = target_dir.join("invalid.query");
+        fs::write(&invalid_path, "(no_such_node)").unwrap();
+        let spec = format!("invalid={}", invalid_path.to_string_lossy());
+        let error = xstats.config.add_custom_query(&spec).err().unwrap();
+        assert!(error
+            .to_string()
+            .starts_with("Invalid custom query invalid"));
+
+        xstats.run_default().unwrap();
+        let table = xstats.metrics_map.get_table(None);
+        assert_eq!(table[0].last().unwrap(), "println");
+        let counts: Vec<(&str, &str)> = table[1..]
+            .iter()
+            .map(|row| (row[6].as_str(), row.last().unwrap().as_str()))
+            .collect();
+        for expected in [("App.java", "3"), ("App", "3"), ("run", "2"), ("stop", "1")] {
+            assert!(counts.contains(&expected));
+        }
+        // Left empty for the languages the query is not valid for
+        assert!(counts.contains(&("app.py", "")));
+
+        // The custom columns are kept whatever the metrics selected
+        xstats.metrics_map.filter.metrics = vec!["cc".to_string()];
+        let records = xstats.metrics_map.get_records(None);
+        let record = records
+            .iter()
+            .find(|record| record["node_name"] == "run")
+            .unwrap();
+        assert_eq!(record["println"], 2);
+        assert!(record.get("aloc").is_none());
+        assert!(xstats.metrics_map.get_table(None)[0].contains(&"println".to_string()));
+    }
+
+    #[test]
+    fn package_metrics_test() {
+        let target_dir = std::env::temp_dir().join("xstats_packages");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(target_dir.join("src/shop")).unwrap();
+        fs::create_dir_all(target_dir.join("app/models")).unwrap();
+        fs::write(
+            target_dir.join("src/shop/Cart.java"),
+            "package com.shop;\n\nclass Cart {\n    int total(int a) {\n        if (a > 0) {\n            return a;\n        }\n        return 0;\n    }\n}\n",
+        )
+        .unwrap();
+        // The declared package wins over the directory
+        fs::write(
+            target_dir.join("Order.java"),
+            "package com.shop;\n\nclass Order {\n    void place() {\n    }\n\n    void cancel() {\n    }\n}\n",
+        )
+        .unwrap();
+        fs::write(target_dir.join("Main.java"), "class Main {\n}\n").unwrap();
+        fs::write(
+            target_dir.join("app/models/cart.py"),
+            "def total(a):\n    return a\n",
+        )
+        .unwrap();
+        fs::write(
+            target_dir.join("app/models/order.py"),
+            "class Order:\n    def place(self, a):\n        if a:\n            return 1\n        return 0\n",
+        )
+        .unwrap();
+        fs::write(target_dir.join("run.py"), "print(1)\n").unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path.clone());
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        assert_eq!(
+            PackageMetrics::new(metrics, &target_path).get_table(),
+            [
+                [
+                    "language",
+                    "package",
+                    "files",
+                    "noc",
+                    "nom",
+                    "mean_nom",
+                    "aloc",
+                    "mean_aloc",
+                    "cc",
+                    "mean_cc",
+                    "max_cc"
+                ],
+                [
+                    "Java",
+                    "<default>",
+                    "1",
+                    "1",
+                    "0",
+                    "0.00",
+                    "3",
+                    "3.00",
+                    "1",
+                    "1.00",
+                    "0"
+                ],
+                ["Java", "com.shop", "2", "2", "3", "1.50", "21", "10.50", "3", "1.50", "2"],
+                [
+                    "Python",
+                    "<default>",
+                    "1",
+                    "0",
+                    "0",
+                    "0.00",
+                    "2",
+                    "2.00",
+                    "1",
+                    "1.00",
+                    "0"
+                ],
+                [
+                    "Python",
+                    "app.models",
+                    "2",
+                    "1",
+                    "2",
+                    "1.00",
+                    "9",
+                    "4.50",
+                    "3",
+                    "1.50",
+                    "2"
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn summary_test() {
+        let target_dir = std::env::temp_dir().join("xstats_summary");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(
+            target_dir.join("Cart.java"),
+            "class Cart {\n    int total(int a) {\n        if (a > 0) {\n            return a;\n        }\n        return 0;\n    }\n\n    void clear() {\n    }\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            target_dir.join("cart.py"),
+            "def total(a, b):\n    if a and b:\n        return 1\n    for x in a:\n        return x\n    return 0\n",
+        )
+        .unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.run_default().unwrap();
+        let summary = xstats.metrics_map.get_summary().unwrap();
+        assert_eq!(
+            (
+                summary.files,
+                summary.classes,
+                summary.methods,
+                summary.aloc
+            ),
+            (2, 1, 3, 19)
+        );
+        // The methods have a cc of 2, 1 and 3
+        assert_eq!(
+            (summary.mean_cc, summary.median_cc, summary.max_cc),
+            (2.0, 2.0, 3)
+        );
+        assert_eq!(
+            summary.languages["Java"],
+            LanguageSummary {
+                files: 1,
+                aloc: 12,
+                methods: 2
+            }
+        );
+        assert_eq!(
+            summary.to_string().lines().next(),
+            Some("2 files, 1 classes, 3 methods, 19 actual lines of code")
+        );
+
+        let mut sink = xstats.get_sink("json").unwrap();
+        xstats.save_summary(sink.as_mut()).unwrap();
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(target_dir.join("summary.json")).unwrap())
+                .unwrap();
+        assert_eq!(saved["median_cc"], 2.0);
+        assert_eq!(saved["languages"]["Python"]["aloc"], 7);
+    }
+
+    #[test]
+    fn near_duplicates_test() {
+        let target_dir = std::env::temp_dir().join("xstats_near_duplicates");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        let total = "class Cart {\n    double total(List<Item> items) {\n        double sum = 0;\n        for (Item item : items) {\n            if (item.quantity > 0) {\n                sum += item.price * item.quantity;\n            }\n        }\n        return sum * 1.2;\n    }\n}\n";
+        // Copied, with renamed variables and a tweaked tax rate
+        let copied = "class Order {\n    double amount(List<Line> lines) {\n        double result = 0;\n        for (Line line : lines) {\n            if (line.quantity > 0) {\n                result += line.price * line.quantity;\n            }\n        }\n        return result * 1.25;\n    }\n}\n";
+        let unrelated = "class Parser {\n    String parse(String text) {\n        StringBuilder out = new StringBuilder();\n        while (text.length() > 0) {\n            out.append(text.charAt(0));\n            text = text.substring(1);\n        }\n        return out.toString();\n    }\n}\n";
+        fs::write(target_dir.join("Cart.java"), total).unwrap();
+        fs::write(target_dir.join("Order.java"), copied).unwrap();
+        fs::write(target_dir.join("Parser.java"), unrelated).unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+
+        let near_duplicates = NearDuplicates::new(metrics, 0.5);
+        assert_eq!(near_duplicates.pairs.len(), 1);
+        let pair = &near_duplicates.pairs[0];
+        assert_eq!(pair.method.meta_data.node_name, "total");
+        assert_eq!(pair.duplicate.meta_data.node_name, "amount");
+        assert!(pair.similarity > 0.5 && pair.similarity < 1.0);
+        assert_eq!(
+            near_duplicates.get_table()[1][3],
+            target_dir.join("Order.java").to_string_lossy()
+        );
+
+        // Identical shingles give identical signatures
+        let signature = get_minhash([1, 2, 3].into_iter());
+        assert_eq!(signature.len(), SIGNATURE_SIZE);
+        assert_eq!(estimate_similarity(&signature, &signature), 1.0);
+        assert!(estimate_similarity(&signature, &get_minhash([4, 5, 6].into_iter())) < 0.2);
+    }
+
+    #[test]
+    fn extract_method_test() {
+        let target_dir = std::env::temp_dir().join("xstats_extract_method");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        let report = "class Report {\n    String render(List<Order> orders, String title) {\n        StringBuilder out = new StringBuilder();\n        out.append(title);\n        out.append(\"\\n\");\n        int total = 0;\n        for (Order order : orders) {\n            total += order.amount;\n        }\n        int count = orders.size();\n        double average = 0;\n        if (count > 0) {\n            average = (double) total / count;\n        }\n        String line = String.format(\"%d orders\", count);\n        String summary = line + \" averaging \" + average;\n        out.append(summary);\n        out.append(\"\\n\");\n        out.append(\"--\");\n        out.append(\"\\n\");\n        out.append(\"end\");\n        return out.toString();\n    }\n\n    int size() { return 1; }\n}\n";
+        fs::write(target_dir.join("Report.java"), report).unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path.clone());
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+
+        let suggestions = ExtractSuggestions::new(metrics, &xstats.metrics_map.filter);
+        assert_eq!(suggestions.suggestions.len(), 1);
+        let suggestion = &suggestions.suggestions[0];
+        assert_eq!(suggestion.node_name, "render");
+        assert_eq!(suggestion.aloc, 22);
+        // Everything between the header and the return, which only needs the orders and the builder
+        assert_eq!(suggestion.candidates.len(), 1);
+        let candidate = &suggestion.candidates[0];
+        assert_eq!((candidate.start_row, candidate.end_row), (5, 21));
+        assert_eq!(candidate.inputs, ["orders", "out"]);
+        assert!(candidate.outputs.is_empty());
+        assert_eq!(candidate.cohesion, 0.75);
+
+        let mut sink = xstats.get_sink("json").unwrap();
+        xstats.save_suggestions(sink.as_mut()).unwrap();
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(target_dir.join("suggestions.json")).unwrap())
+                .unwrap();
+        assert_eq!(saved[0]["node_name"], "render");
+        assert_eq!(saved[0]["candidates"][0]["start_row"], 5);
+    }
+
+    #[test]
+    fn file_cache_test() {
+        let target_dir = std::env::temp_dir().join("xstats_file_cache");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(
+            target_dir.join("A.java"),
+            "class A {\n    int a() { return 1; }\n}\n",
+        )
+        .unwrap();
+        fs::write(target_dir.join("B.java"), "class B {}\n").unwrap();
+        fs::write(target_dir.join("notes.txt"), "Not analyzed\n").unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let cache_dir = core::get_cache_dir(&target_path);
+        assert_eq!(Path::new(&cache_dir), target_dir.join(core::CACHE_DIR_NAME));
+        let run = || {
+            let mut xstats = core::XStats::new(target_path.clone(), target_path.clone());
+            xstats.cache_dir = Some(cache_dir.clone());
+            xstats.run_default().unwrap();
+            xstats
+        };
+        let get_aloc = |xstats: &core::XStats, node_name: &str| {
+            xstats
+                .metrics_map
+                .get_default_metrics()
+                .unwrap()
+                .metric_blocks
+                .iter()
+                .find(|block| block.meta_data.node_name == node_name)
+                .unwrap()
+                .metric
+                .aloc
+        };
+
+        let first = run();
+        let mut uncached = core::XStats::new(target_path.clone(), target_path.clone());
+        uncached.run_default().unwrap();
+        assert_eq!(
+            first.metrics_map.get_table(None),
+            uncached.metrics_map.get_table(None)
+        );
+        let entries: Vec<PathBuf> = fs::read_dir(&cache_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(entries.len(), 2);
+
+        // Cached metrics are reused as long as the content is the same
+        for entry in &entries {
+            let mut cached: serde_json::Value =
+                serde_json::from_str(&fs::read_to_string(entry).unwrap()).unwrap();
+            for block in cached["metric_blocks"].as_array_mut().unwrap() {
+                block["metric"]["aloc"] = serde_json::json!(42);
+            }
+            fs::write(entry, cached.to_string()).unwrap();
+        }
+        fs::write(target_dir.join("B.java"), "class B {\n}\n").unwrap();
+        let second = run();
+        assert_eq!(get_aloc(&second, "A.java"), 42);
+        assert_eq!(get_aloc(&second, "B.java"), get_aloc(&first, "B.java") + 1);
+        // Cached files are still parsed for the graphs
+        assert_eq!(
+            second.tdg.graph.node_count(),
+            uncached.tdg.graph.node_count()
+        );
+
+        // The entries of deleted files are removed
+        fs::remove_file(target_dir.join("B.java")).unwrap();
+        run();
+        assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn reanalyze_test() {
+        let target_dir = std::env::temp_dir().join("xstats_reanalyze");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        let file_path = target_dir.join("A.java").to_string_lossy().to_string();
+        let original = "class A {\n    int one() {\n        return 1;\n    }\n\n    int two(int x) {\n        return x;\n    }\n}\n";
+        fs::write(&file_path, original).unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path.clone());
+        xstats.run_default().unwrap();
+
+        // Replace a range of the content, as an editor would report it
+        let get_point = |text: &str, byte: usize| {
+            let before = &text[..byte];
+            let row = before.matches('\n').count();
+            Point::new(row, byte - before.rfind('\n').map_or(0, |idx| idx + 1))
+        };
+        let apply_edit = |text: &str, old: &str, new: &str| {
+            let start_byte = text.find(old).unwrap();
+            let updated = text.replacen(old, new, 1);
+            let edit = InputEdit {
+                start_byte,
+                old_end_byte: start_byte + old.len(),
+                new_end_byte: start_byte + new.len(),
+                start_position: get_point(text, start_byte),
+                old_end_position: get_point(text, start_byte + old.len()),
+                new_end_position: get_point(&updated, start_byte + new.len()),
+            };
+            (updated, edit)
+        };
+        let (updated, first_edit) = apply_edit(
+            original,
+            "return x;",
+            "if (x > 0) {\n            return x;\n        }\n        return 0;",
+        );
+        let (updated, second_edit) = apply_edit(
+            &updated,
+            "\n}\n",
+            "\n\n    int three() {\n        return 3;\n    }\n}\n",
+        );
+
+        let diffs = xstats
+            .reanalyze_file(&file_path, updated.clone(), &[first_edit, second_edit])
+            .unwrap();
+        let annotated: Vec<(String, String)> = diffs
+            .diffs
+            .iter()
+            .map(|diff| {
+                let block = diff.new.as_ref().or(diff.old.as_ref()).unwrap();
+                (diff.status.to_string(), block.meta_data.node_name.clone())
+            })
+            .collect();
+        assert_eq!(
+            annotated,
+            vec![
+                ("modified".to_string(), "A.java".to_string()),
+                ("modified".to_string(), "A".to_string()),
+                ("modified".to_string(), "two".to_string()),
+                ("added".to_string(), "three".to_string()),
+            ]
+        );
+
+        // The metrics are the same as those of a full analysis of the new content
+        fs::write(&file_path, &updated).unwrap();
+        let mut fresh = core::XStats::new(target_path.clone(), target_path);
+        fresh.run_default().unwrap();
+        assert_eq!(
+            xstats.metrics_map.get_table(None),
+            fresh.metrics_map.get_table(None)
+        );
+    }
+
+    #[test]
+    fn baseline_test_history() {
+        let (repo_dir, repo) = init_repo("baseline");
+        let original = "class A {\n    int one() {\n        return 1;\n    }\n}\n";
+        let base = commit_files(&repo, &[("src/A.java", original)], "Add A");
+
+        // The default branch moves on after the current branch forked from it
+        let base_commit = repo.find_commit(base).unwrap();
+        repo.branch("feature", &base_commit, false).unwrap();
+        let signature = Signature::now("xStats", "xstats@example.com").unwrap();
+        repo.commit(
+            Some("refs/heads/main"),
+            &signature,
+            &signature,
+            "Keep main busy",
+            &base_commit.tree().unwrap(),
+            &[&base_commit],
+        )
+        .unwrap();
+        repo.set_head("refs/heads/feature").unwrap();
+        let updated = "class A {\n    int one(int x) {\n        if (x > 0) {\n            return 1;\n        }\n        return 0;\n    }\n}\n";
+        commit_files(&repo, &[("src/A.java", updated)], "Update A.one");
+        fs::write(repo_dir.join("src/B.java"), "class B {\n}\n").unwrap();
+
+        let target_dir = repo_dir.join("src").to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(
+            target_dir,
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_default().unwrap();
+        xstats.run_baseline("auto").unwrap();
+        assert_eq!(xstats.baseline_id, Some(base.to_string()));
+
+        let diffs = xstats
+            .metrics_map
+            .compare(&base.to_string(), "default")
+            .unwrap();
+        let annotated: Vec<(String, String)> = diffs
+            .diffs
+            .iter()
+            .map(|diff| {
+                let block = diff.new.as_ref().or(diff.old.as_ref()).unwrap();
+                (diff.status.to_string(), block.meta_data.node_name.clone())
+            })
+            .collect();
+        assert_eq!(
+            annotated,
+            vec![
+                ("modified".to_string(), "A.java".to_string()),
+                ("modified".to_string(), "A".to_string()),
+                ("modified".to_string(), "one".to_string()),
+                ("added".to_string(), "B.java".to_string()),
+                ("added".to_string(), "B".to_string()),
+            ]
+        );
+
+        assert!(matches!(
+            xstats.run_baseline("missing"),
+            Err(XStatsError::Repository(RepositoryError::InvalidRevision(_)))
+        ));
+    }
+
+    #[test]
+    fn diff_test() {
+        let target_dir = std::env::temp_dir().join("xstats_diff");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(target_dir.join("src")).unwrap();
+        let source_path = target_dir.join("src/A.java");
+        let target_path = target_dir.join("src").to_string_lossy().to_string();
+        let save_metrics = |output: &str| {
+            let output_path = target_dir.join(output).to_string_lossy().to_string();
+            let mut xstats = core::XStats::new(target_path.clone(), output_path.clone());
+            xstats.metrics_map.filter.metrics = vec!["cc".to_string(), "mi".to_string()];
+            xstats.run_default().unwrap();
+            let mut sink = xstats.get_sink("json").unwrap();
+            xstats.save_metrics(sink.as_mut()).unwrap();
+            format!("{}/metrics.json", output_path)
+        };
+        fs::write(
+            &source_path,
+            "class A {\n    int one() {\n        return 1;\n    }\n\n    int two() {\n        return 2;\n    }\n}\n",
+        )
+        .unwrap();
+        let old_path = save_metrics("old");
+        fs::write(
+            &source_path,
+            "class A {\n    int one(int x) {\n        if (x > 0) {\n            return 1;\n        }\n        return 0;\n    }\n\n    int three() {\n        return 3;\n    }\n\n    int four(int y) {\n        return y;\n    }\n}\n",
+        )
+        .unwrap();
+        let new_path = save_metrics("new");
+
+        let mut xstats = core::XStats::new(
+            String::new(),
+            target_dir.join("diff").to_string_lossy().to_string(),
+        );
+        // Only the metrics saved are compared
+        let metrics = xstats.load_metrics("baseline", &old_path).unwrap();
+        assert_eq!(metrics, ["cc", "mi"]);
+        xstats.load_metrics("current", &new_path).unwrap();
+        let diffs = xstats.metrics_map.compare("baseline", "current").unwrap();
+        let summary: Vec<(String, String, Vec<String>)> = diffs
+            .diffs
+            .iter()
+            .map(|diff| {
+                let block = diff.new.as_ref().or(diff.old.as_ref()).unwrap();
+                (
+                    diff.status.to_string(),
+                    block.meta_data.node_name.clone(),
+                    diff.get_regressions(&metrics),
+                )
+            })
+            .collect();
+        let regressed = vec!["cc".to_string(), "mi".to_string()];
+        assert_eq!(
+            summary,
+            [
+                (
+                    "modified".to_string(),
+                    "A.java".to_string(),
+                    regressed.clone()
+                ),
+                ("modified".to_string(), "A".to_string(), regressed.clone()),
+                ("modified".to_string(), "one".to_string(), regressed),
+                ("renamed".to_string(), "three".to_string(), Vec::new()),
+                ("added".to_string(), "four".to_string(), Vec::new()),
+            ]
+        );
+
+        let table = diffs.get_review_table(&metrics);
+        assert_eq!(
+            table[0][..3],
+            ["status", "regressions", "language"].map(String::from)
+        );
+        assert_eq!(
+            table[0][9..],
+            ["old_cc", "new_cc", "delta_cc", "old_mi", "new_mi", "delta_mi"].map(String::from)
+        );
+        assert_eq!(table[3][..2], ["modified", "cc,mi"].map(String::from));
+        assert_eq!(table[3][9..12], ["1", "2", "1"].map(String::from));
+        // An added entity has no value before
+        assert_eq!(table[5][9..12], ["", "1", ""].map(String::from));
+
+        let mut sink = xstats.get_sink("json").unwrap();
+        xstats
+            .save_diff(sink.as_mut(), "baseline", "current", &metrics)
+            .unwrap();
+        assert!(target_dir.join("diff/diff.json").is_file());
+        assert!(matches!(
+            xstats.load_metrics("baseline", &source_path.to_string_lossy()),
+            Err(XStatsError::InvalidTable { .. })
+        ));
+    }
+
+    #[test]
+    fn diff_base_test_history() {
+        let (repo_dir, repo) = init_repo("diff_base");
+        let base = commit_files(
+            &repo,
+            &[
+                ("src/A.java", "class A {\n}\n"),
+                ("src/B.java", "class B {\n}\n"),
+                ("src/c.py", "x = 1\n"),
+            ],
+            "Add A, B and c",
+        );
+        commit_files(
+            &repo,
+            &[("src/B.java", "class B {\n    void f() {}\n}\n")],
+            "Update B",
+        );
+        // Uncommitted and untracked changes count as well
+        fs::write(repo_dir.join("src/c.py"), "x = 2\n").unwrap();
+        fs::write(repo_dir.join("src/D.java"), "class D {\n}\n").unwrap();
+
+        let mut xstats = core::XStats::new(
+            repo_dir.join("src").to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.diff_base = Some(base.to_string());
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        let mut file_names: Vec<&str> = metrics
+            .metric_blocks
+            .iter()
+            .filter(|block| block.meta_data.node_kind == EntityKind::File)
+            .map(|block| block.meta_data.node_name.as_str())
+            .collect();
+        file_names.sort();
+        assert_eq!(file_names, ["B.java", "D.java", "c.py"]);
+
+        xstats.diff_base = Some("missing".to_string());
+        assert!(matches!(
+            xstats.run_default(),
+            Err(XStatsError::Repository(RepositoryError::InvalidRevision(_)))
+        ));
+    }
+
+    #[test]
+    fn portfolio_test_history() {
+        let (repo_dir, repo) = init_repo("portfolio");
+        let simple = "class A {\n    int one() {\n        return 1;\n    }\n}\n";
+        let complex = "class A {\n    int one(int x) {\n        if (x > 0) {\n            return 1;\n        }\n        if (x < -9) {\n            return 2;\n        }\n        if (x < -99) {\n            return 3;\n        }\n        return 0;\n    }\n}\n";
+        commit_files(
+            &repo,
+            &[("A.java", simple), ("B.java", "class B {\n}\n")],
+            "Add A and B",
+        );
+        commit_files(&repo, &[("A.java", complex)], "Update A");
+        let manifest_path = std::env::temp_dir().join("xstats_portfolio.toml");
+        fs::write(
+            &manifest_path,
+            "trend-ref = \"HEAD~1\"\ntop-hotspots = 1\ncache-dir = \"xstats_portfolio_cache\"\n\n[[repos]]\nname = \"app\"\npath = \"xstats_portfolio\"\n\n[[repos]]\nname = \"missing\"\npath = \"xstats_portfolio_missing\"\n",
+        )
+        .unwrap();
+        let cache_dir = std::env::temp_dir().join("xstats_portfolio_cache");
+        let _ = fs::remove_dir_all(&cache_dir);
+
+        let manifest = PortfolioManifest::load(&manifest_path.to_string_lossy()).unwrap();
+        assert_eq!(manifest.repos[0].path, repo_dir.to_string_lossy());
+        assert_eq!(manifest.max_commits, 100);
+        let portfolio = Portfolio::new(&manifest);
+        assert_eq!(portfolio.summaries.len(), 1);
+        assert!(portfolio.warnings[0].starts_with("Failed to analyze missing: "));
+
+        let summary = &portfolio.summaries[0];
+        assert_eq!(summary.file_count, 2);
+        assert_eq!(summary.hotspots, ["A.java"]);
+        assert!(summary.trend.unwrap() < 0.0);
+        assert_eq!(summary.grade, summary.score.map(get_grade));
+        let table = portfolio.get_table();
+        assert_eq!(table[1][0], "app");
+        assert_eq!(table[1][6], "A.java");
+
+        // The summary is reused while the repository stays the same
+        let cache_path = cache_dir.join("app.json");
+        let mut cached: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&cache_path).unwrap()).unwrap();
+        cached["aloc"] = serde_json::json!(1234);
+        fs::write(&cache_path, cached.to_string()).unwrap();
+        assert_eq!(Portfolio::new(&manifest).summaries[0].aloc, 1234);
+        commit_files(&repo, &[("B.java", "class B {\n\n}\n")], "Update B");
+        assert_ne!(Portfolio::new(&manifest).summaries[0].aloc, 1234);
+    }
+
+    #[test]
+    fn state_test_example5() {
+        let target_dir = format!("{}/example5", SAMPLES_DIR);
+        let output_dir = std::env::temp_dir().join("xstats_state");
+        let _ = fs::remove_dir_all(&output_dir);
+        let state_path = output_dir
+            .join("example5.state")
+            .to_string_lossy()
+            .to_string();
+        let mut xstats = core::XStats::new(target_dir, output_dir.to_string_lossy().to_string());
+        xstats.run_default().unwrap();
+        xstats.export_state(&state_path).unwrap();
+        assert!(matches!(
+            xstats.export_state(&state_path),
+            Err(XStatsError::OutputExists(_))
+        ));
+
+        // The imported results are those of the analysis, the graphs included
+        let mut imported = core::XStats::new(String::new(), String::new());
+        imported.import_state(&state_path).unwrap();
+        assert_eq!(
+            imported.metrics_map.get_table(None),
+            xstats.metrics_map.get_table(None)
+        );
+        let node_metrics = NodeMetricsIndex::default();
+        assert_eq!(
+            imported.tdg.to_dot(&node_metrics),
+            xstats.tdg.to_dot(&node_metrics)
+        );
+        assert_eq!(
+            imported.call_graph.get_table(),
+            xstats.call_graph.get_table()
+        );
+
+        // Other files are refused rather than misread
+        fs::write(&state_path, "metrics").unwrap();
+        assert!(matches!(
+            imported.import_state(&state_path),
+            Err(XStatsError::InvalidState { .. })
+        ));
+    }
+
+    #[test]
+    fn state_test_history() {
+        let (repo_dir, repo) = init_repo("state");
+        commit_files(&repo, &[("A.java", "class A {}\n")], "Add A");
+        commit_files(
+            &repo,
+            &[("A.java", "class A {\n    int f() { return 1; }\n}\n")],
+            "PROJ-1: add f",
+        );
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+        let state_path = repo_dir
+            .join("out/history.state")
+            .to_string_lossy()
+            .to_string();
+        xstats.export_state(&state_path).unwrap();
+
+        let mut imported = core::XStats::new(String::new(), String::new());
+        imported.import_state(&state_path).unwrap();
+        for (commit_id, _) in xstats.changes_map.iter() {
+            assert_eq!(
+                imported.metrics_map.get_table(Some(commit_id)),
+                xstats.metrics_map.get_table(Some(commit_id))
+            );
+            assert_eq!(
+                imported.changes_map.get_table(commit_id),
+                xstats.changes_map.get_table(commit_id)
+            );
+        }
+        assert_eq!(
+            imported.churn_metrics.get_table(),
+            xstats.churn_metrics.get_table()
+        );
+        assert_eq!(
+            imported.commit_infos.get_table(),
+            xstats.commit_infos.get_table()
+        );
+        assert_eq!(
+            Hotspots::new(&imported.churn_metrics, &imported.metrics_map).get_table(),
+            Hotspots::new(&xstats.churn_metrics, &xstats.metrics_map).get_table()
+        );
+    }
+
+    #[test]
+    fn history_test_without_commits() {
+        let (repo_dir, _repo) = init_repo("no_commits");
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        assert!(matches!(
+            xstats.run_multi_commit(),
+            Err(XStatsError::Repository(RepositoryError::NoCommits(_)))
+        ));
+
+        let mut xstats =
+            core::XStats::new(format!("{}/example1", SAMPLES_DIR), SAMPLES_DIR.to_string());
+        assert!(matches!(
+            xstats.run_multi_commit(),
+            Err(XStatsError::Repository(RepositoryError::NotARepository(_)))
+        ));
+    }
+
+    #[test]
+    fn timeout_test_example5() {
+        let target_dir = format!("{}/example5", SAMPLES_DIR);
+
+        // Files are abandoned, and the run goes on
+        let mut xstats = core::XStats::new(target_dir.clone(), SAMPLES_DIR.to_string());
+        xstats.config.file_timeout = Some(Duration::from_nanos(1));
+        xstats.run_default().unwrap();
+        assert!(xstats
+            .metrics_map
+            .get_default_metrics()
+            .unwrap()
+            .metric_blocks
+            .is_empty());
+        assert_eq!(xstats.warnings.len(), 4);
+        assert!(xstats.warnings[0].starts_with("Timed out analyzing "));
+
+        // The results so far are kept
+        let mut xstats = core::XStats::new(target_dir, SAMPLES_DIR.to_string());
+        xstats.set_total_timeout(Duration::ZERO);
+        xstats.run_default().unwrap();
+        assert!(xstats.metrics_map.get_default_metrics().is_some());
+        assert_eq!(
+            xstats.warnings,
+            ["Total timeout exceeded, 0 of 4 files analyzed"]
+        );
+    }
+
+    #[test]
+    fn grammar_compatibility_test() {
+        // The queries and node groups of every language match the bundled grammars
+        let xstats = core::XStats::new(String::new(), String::new());
+        let errors: Vec<String> = xstats
+            .check_grammars()
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(errors, Vec::<String>::new());
+
+        // A grammar renaming the node kinds, here that of another language, is reported
+        let python_grammar: TSLanguage = tree_sitter_python::LANGUAGE.into();
+        let problems = get_grammar_problems(&python_grammar, Language::Java);
+        assert_eq!(problems.len(), 13);
+        assert!(problems[0].starts_with("base_query does not compile"));
+        assert!(problems.iter().any(|problem| problem
+            .starts_with("decision_point_nodes refers to unknown node kinds")
+            && problem.contains("do_statement")));
+    }
+
+    #[test]
+    fn language_profile_test() {
+        // Every language is described by its profile, found by its names and extensions
+        let parsers = TSParsers::new();
+        for language in Language::all() {
+            let profile = get_profile(language);
+            assert_eq!(profile.language(), language);
+            for name in profile.names() {
+                assert_eq!(Language::from_name(name), Some(language));
+            }
+            for extension in profile.extensions() {
+                let file_path = format!("src/main{}", extension);
+                assert_eq!(parsers.get_language(&file_path), Some(language));
+            }
+            assert!(profile.query("base_query").is_some());
+            assert!(profile.node_group("decision_point_nodes").is_some());
+        }
+        assert_eq!(Language::from_name("Python3"), Some(Language::Python));
+        assert_eq!(
+            get_profile(Language::Tsx).grammar_crate(),
+            "tree-sitter-typescript"
+        );
+    }
+
+    #[test]
+    fn language_profile_file_test() {
+        let profiles_dir = std::env::temp_dir().join("xstats_language_profiles");
+        let _ = fs::remove_dir_all(&profiles_dir);
+        fs::create_dir_all(&profiles_dir).unwrap();
+        let load = |profile: &str| {
+            let file_path = profiles_dir.join("lang.toml");
+            fs::write(&file_path, profile).unwrap();
+            languages::load_language_profile(&file_path.to_string_lossy())
+        };
+        let profile = concat!(
+            "name = \"Snake\"\n",
+            "extensions = [\".snk\"]\n",
+            "library = \"libtree-sitter-snake.so\"\n",
+            "[queries]\n",
+            "method = \"(function_definition)\"\n",
+        );
+
+        // The grammar is loaded from the library next to the profile
+        let error = load(profile).err().unwrap().to_string();
+        assert!(error.starts_with("Invalid language profile"));
+        assert!(error.contains("libtree-sitter-snake.so"));
+        let error = load(&profile.replace("Snake", "Python")).err().unwrap();
+        assert!(error
+            .to_string()
+            .ends_with("Python is a supported language"));
+        let error = load(&profile.replace(".snk", ".py")).err().unwrap();
+        assert!(error
+            .to_string()
+            .ends_with("extension .py is taken by Python"));
+        let error = load(&profile.replace("\".snk\"", "\"snk\", \"h\""))
+            .err()
+            .unwrap();
+        assert!(error.to_string().ends_with("extension .h is taken by C"));
+        assert!(load(&profile.replace("[queries]", "[queries]\nfunction = \"(x)\"")).is_err());
+        assert!(load(&profile.replace("method", "# method")).is_err());
+        assert!(languages::get_custom_languages().is_empty());
+
+        // Languages are saved by name
+        let json = serde_json::to_string(&Language::Tsx).unwrap();
+        assert_eq!(json, "\"Tsx\"");
+        assert_eq!(
+            serde_json::from_str::<Language>(&json).unwrap(),
+            Language::Tsx
+        );
+        assert!(serde_json::from_str::<Language>("\"Snake\"").is_err());
+    }
+
+    #[test]
+    fn error_test() {
+        let mut xstats =
+            core::XStats::new(format!("{}/missing", SAMPLES_DIR), SAMPLES_DIR.to_string());
+        assert!(matches!(
+            xstats.run_default(),
+            Err(XStatsError::PathNotFound(_))
+        ));
+        assert!(matches!(
+            xstats.get_sink("toml"),
+            Err(XStatsError::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            xstats.config.add_doc_comment_prefix("cobol=*>"),
+            Err(XStatsError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn output_test_example1() {
+        let output_dir = std::env::temp_dir().join("xstats_output");
+        let _ = fs::remove_dir_all(&output_dir);
+        let output_path = output_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(format!("{}/example1", SAMPLES_DIR), output_path);
+        xstats.run_default().unwrap();
+        let mut metrics = CodeMetrics::new();
+        metrics.metric_blocks = xstats
+            .metrics_map
+            .get_default_metrics()
+            .unwrap()
+            .metric_blocks
+            .clone();
+        xstats
+            .metrics_map
+            .add_metrics("commit".to_string(), metrics);
+
+        let mut csv_sink = xstats.get_sink("csv").unwrap();
+        xstats.save_metrics(csv_sink.as_mut()).unwrap();
+        xstats
+            .save_metrics_map(xstats.get_sink("json").unwrap().as_mut())
+            .unwrap();
+        assert!(output_dir.join("metrics/commit.json").is_file());
+
+        // Prior results are only overwritten on demand
+        assert!(matches!(
+            xstats.save_metrics(csv_sink.as_mut()),
+            Err(XStatsError::OutputExists(_))
+        ));
+        xstats.force = true;
+        xstats
+            .save_metrics(xstats.get_sink("csv").unwrap().as_mut())
+            .unwrap();
+
+        // No temporary file is left behind
+        let mut file_names: Vec<String> = fs::read_dir(&output_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        file_names.sort();
+        assert_eq!(file_names, ["metrics", "metrics.csv"]);
+    }
+
+    #[test]
+    fn csv_dialect_test_example1() {
+        let output_dir = std::env::temp_dir().join("xstats_csv_dialect");
+        let _ = fs::remove_dir_all(&output_dir);
+        let mut xstats = core::XStats::new(
+            format!("{}/example1", SAMPLES_DIR),
+            output_dir.to_string_lossy().to_string(),
+        );
+        xstats.csv_dialect =
+            CsvDialect::from_options(Some(";"), Some("always"), Some(",")).unwrap();
+        xstats.run_default().unwrap();
+        xstats
+            .save_metrics(xstats.get_sink("csv").unwrap().as_mut())
+            .unwrap();
+
+        let content = fs::read_to_string(output_dir.join("metrics.csv")).unwrap();
+        let mut lines = content.lines();
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("\"language\";\"file_path\";"));
+        let row: Vec<&str> = lines.next().unwrap().split("\";\"").collect();
+        let hv_idx = xstats.metrics_map.get_table(None)[0]
+            .iter()
+            .position(|name| name == "hv")
+            .unwrap();
+        let hv = &xstats.metrics_map.get_table(None)[1][hv_idx];
+        assert!(hv.contains('.'));
+        assert_eq!(row[hv_idx], hv.replace('.', ","));
+
+        assert!(CsvDialect::from_options(Some(";;"), None, None).is_err());
+        assert!(CsvDialect::from_options(None, Some("minimal"), None).is_err());
+        assert!(CsvDialect::from_options(None, Some("never"), Some(",")).is_err());
+        assert_eq!(
+            CsvDialect::from_options(Some("tab"), None, None)
+                .unwrap()
+                .delimiter,
+            b'\t'
+        );
+    }
+
+    #[test]
+    fn typed_json_test_example1() {
+        let output_dir = std::env::temp_dir().join("xstats_typed_json");
+        let _ = fs::remove_dir_all(&output_dir);
+        let mut xstats = core::XStats::new(
+            format!("{}/example1", SAMPLES_DIR),
+            output_dir.to_string_lossy().to_string(),
+        );
+        xstats.run_default().unwrap();
+        let mut sink = xstats.get_sink("json").unwrap();
+        xstats.save_metrics(sink.as_mut()).unwrap();
+        xstats.save_scores(sink.as_mut()).unwrap();
+
+        let read_json = |name: &str| -> serde_json::Value {
+            serde_json::from_str(&fs::read_to_string(output_dir.join(name)).unwrap()).unwrap()
+        };
+        let metrics = read_json("metrics.json");
+        assert_eq!(metrics["schema_version"], JSON_SCHEMA_VERSION);
+        let rows = metrics["rows"].as_array().unwrap();
+        assert_eq!(rows.len(), xstats.metrics_map.get_table(None).len() - 1);
+        let file_row = rows.iter().find(|row| row["node_kind"] == "file").unwrap();
+        assert_eq!(file_row["language"], "Java");
+        assert!(file_row["start_row"].is_u64());
+        assert!(file_row["aloc"].is_u64());
+        assert!(file_row["hv"].is_f64());
+        assert_eq!(file_row["is_broken"], false);
+
+        // The other tables are typed by column
+        let scores = read_json("scores.json");
+        assert_eq!(scores["schema_version"], JSON_SCHEMA_VERSION);
+        let score_row = &scores["rows"][0];
+        assert!(score_row["files"].is_i64());
+        assert!(score_row["score"].is_f64());
+        assert!(score_row["grade"].is_string());
+    }
+
+    #[test]
+    fn parquet_test_example1() {
+        let output_dir = std::env::temp_dir().join("xstats_parquet");
+        let _ = fs::remove_dir_all(&output_dir);
+        let mut xstats = core::XStats::new(
+            format!("{}/example1", SAMPLES_DIR),
+            output_dir.to_string_lossy().to_string(),
+        );
+        xstats.run_default().unwrap();
+        xstats
+            .save_metrics(xstats.get_sink("parquet").unwrap().as_mut())
+            .unwrap();
+
+        let content = fs::read(output_dir.join("metrics.parquet")).unwrap();
+        assert_eq!(&content[..4], b"PAR1");
+        assert_eq!(&content[content.len() - 4..], b"PAR1");
+        let footer = &content[content.len() - 8..content.len() - 4];
+        let metadata_len = u32::from_le_bytes(footer.try_into().unwrap()) as usize;
+        let metadata = &content[content.len() - 8 - metadata_len..content.len() - 8];
+        // Every column is named in the schema
+        let table = xstats.metrics_map.get_table(None);
+        for name in &table[0] {
+            assert!(metadata
+                .windows(name.len())
+                .any(|window| window == name.as_bytes()));
+        }
+
+        // A reference reader gets the values back with their type
+        let reader =
+            SerializedFileReader::new(File::open(output_dir.join("metrics.parquet")).unwrap())
+                .unwrap();
+        let rows: Vec<Row> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(rows.len(), table.len() - 1);
+        for (row, values) in rows.iter().zip(&table[1..]) {
+            for ((name, field), value) in row.get_column_iter().zip(values) {
+                let expected = match field {
+                    Field::Str(string) => string.clone(),
+                    Field::Long(long) => long.to_string(),
+                    Field::Double(double) => format!("{:.2}", double),
+                    Field::Bool(boolean) => boolean.to_string(),
+                    field => panic!("Unexpected value {} of {}", field, name),
+                };
+                assert_eq!(&expected, value, "{}", name);
+            }
+        }
+
+        // Numbers keep their type, empty values are nulls
+        let infer = |values: &[&str]| ColumnType::infer(values.iter().copied());
+        assert_eq!(infer(&["1", "", "-3"]), ColumnType::Int64);
+        assert_eq!(infer(&["1", "2.5"]), ColumnType::Double);
+        assert_eq!(infer(&["true", "false"]), ColumnType::Boolean);
+        assert_eq!(infer(&["1", "A.java"]), ColumnType::String);
+        assert_eq!(infer(&["", ""]), ColumnType::String);
+    }
+
+    #[test]
+    fn quality_score_test() {
+        let target_dir = std::env::temp_dir().join("xstats_quality_score");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(target_dir.join("util")).unwrap();
+        // The same method, apart from its name and comments, in two files
+        let method =
+            "(int a, int b) {\n    if (a > b) {\n        return a;\n    }\n    return b;\n}";
+        fs::write(
+            target_dir.join("A.java"),
+            format!("class A {{\nint max{}\n}}\n", method),
+        )
+        .unwrap();
+        fs::write(
+            target_dir.join("util/B.java"),
+            format!(
+                "class B {{\n// Larger of the two\nint larger{}\n}}\n",
+                method
+            ),
+        )
+        .unwrap();
+        fs::write(
+            target_dir.join("util/C.java"),
+            "class C {\n// One\nint one() { return 1; }\n}\n",
+        )
+        .unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path.clone());
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+
+        // Only the duplicated methods count
+        let weights = ScoreWeights {
+            complexity: 0.0,
+            size: 0.0,
+            duplication: 1.0,
+            documentation: 0.0,
+            smells: 0.0,
+        };
+        let scores = QualityScores::new(metrics, &weights, &target_path);
+        let get_score = |path: &str| {
+            let path = target_dir.join(path).to_string_lossy().to_string();
+            scores
+                .scores
+                .iter()
+                .find(|score| score.path == path)
+                .unwrap()
+        };
+        assert_eq!(get_score("A.java").score, 0.0);
+        assert_eq!(get_score("A.java").grade, 'F');
+        assert_eq!(get_score("util/C.java").score, 100.0);
+        assert_eq!(get_score("util/C.java").components.documentation, 100.0);
+
+        // Directories are averaged by size, up to the target
+        let util = get_score("util");
+        assert!(util.is_directory);
+        assert_eq!(util.file_count, 2);
+        assert!(util.score > 0.0 && util.score < 100.0);
+        let root = scores
+            .scores
+            .iter()
+            .find(|score| score.path == target_path)
+            .unwrap();
+        assert_eq!(root.file_count, 3);
+
+        let table = scores.get_table();
+        assert_eq!(table[0].last().unwrap(), "grade");
+        assert_eq!(table.len(), 6);
+        assert_eq!(get_grade(89.99), 'B');
+    }
+
+    #[test]
+    fn quality_gate_test_example1() {
+        let mut xstats =
+            core::XStats::new(format!("{}/example1", SAMPLES_DIR), "unused".to_string());
+        xstats.config.set_fail_on("cc>1, aloc>100").unwrap();
+        assert_eq!(
+            xstats.config.fail_on,
+            std::collections::BTreeMap::from([
+                ("aloc".to_string(), 100.0),
+                ("cc".to_string(), 1.0)
+            ])
+        );
+        assert!(matches!(
+            xstats.config.set_fail_on("cc>=1"),
+            Err(XStatsError::Config(_))
+        ));
+        assert!(matches!(
+            xstats.config.set_fail_on("complexity>1"),
+            Err(XStatsError::Config(_))
+        ));
+        xstats.run_default().unwrap();
+
+        // The gate fails on the same entities as thresholds, which only warn
+        let gate_violations: Vec<String> = xstats
+            .check_quality_gate()
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert!(!gate_violations.is_empty());
+        assert!(xstats.check_thresholds().is_empty());
+        xstats.config.thresholds = xstats.config.fail_on.clone();
+        let violations: Vec<String> = xstats
+            .check_thresholds()
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert_eq!(gate_violations, violations);
+    }
+
+    #[test]
+    fn sarif_test_example1() {
+        let output_dir = std::env::temp_dir().join("xstats_sarif");
+        let _ = fs::remove_dir_all(&output_dir);
+        let mut xstats = core::XStats::new(
+            format!("{}/example1", SAMPLES_DIR),
+            output_dir.to_string_lossy().to_string(),
+        );
+        xstats.config.thresholds.insert("cc".to_string(), 1.0);
+        xstats.config.thresholds.insert("aloc".to_string(), 100.0);
+        xstats.run_default().unwrap();
+        xstats.save_sarif().unwrap();
+
+        let content = fs::read_to_string(output_dir.join("violations.sarif")).unwrap();
+        let sarif_log: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(sarif_log["version"], "2.1.0");
+        let run = &sarif_log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "xStats");
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0]["id"], "cc");
+
+        let violations = xstats.check_thresholds();
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), violations.len());
+        let location = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(
+            location["artifactLocation"]["uri"],
+            violations[0].meta_data.file_path.as_str()
+        );
+        assert_eq!(
+            location["region"]["startLine"],
+            violations[0].meta_data.start_row
+        );
+        assert_eq!(results[0]["ruleIndex"], 0);
+    }
+
+    #[test]
+    fn config_file_test() {
+        let target_dir = std::env::temp_dir().join("xstats_config_file");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(target_dir.join("generated")).unwrap();
+        let method = "int max(int a, int b) { if (a > b) { return a; } return b; }";
+        fs::write(
+            target_dir.join("A.java"),
+            format!("class A {{ {} }}\n", method),
+        )
+        .unwrap();
+        fs::write(target_dir.join("generated/B.java"), "class B {}\n").unwrap();
+        fs::write(target_dir.join("c.py"), "def c():\n    pass\n").unwrap();
+        fs::write(
+            target_dir.join(".xstats.toml"),
+            "languages = [\"java\"]\nexclude = [\"**/generated/**\"]\nmetrics = [\"aloc\", \"cc\"]\n\n[thresholds]\ncc = 1\n",
+        )
+        .unwrap();
+        let target_path = target_dir.to_string_lossy().to_string();
+
+        let config_file = ConfigFile::find(&target_path).unwrap().unwrap();
+        let mut xstats = core::XStats::new(target_path, SAMPLES_DIR.to_string());
+        xstats.config.set_languages(&config_file.languages).unwrap();
+        xstats.config.path_filter =
+            PathFilter::from_patterns(&config_file.include, &config_file.exclude).unwrap();
+        xstats.config.thresholds = config_file.thresholds;
+        xstats.metrics_map.filter.metrics = config_file.metrics;
+        xstats.run_default().unwrap();
+
+        let metrics = xstats.metrics_map.get_table(None);
+        assert_eq!(&metrics[0][12..], ["aloc", "cc"]);
+        assert!(metrics[1..].iter().all(|row| row[1].ends_with("A.java")));
+        let violations = xstats.check_thresholds();
+        assert!(!violations.is_empty());
+        assert!(violations.iter().all(|v| v.metric == "cc" && v.value > 1.0));
+
+        // YAML is accepted too, unknown keys and metrics are not
+        fs::write(target_dir.join("xstats.yaml"), "format: csv\nmin-aloc: 2\n").unwrap();
+        let config_file =
+            ConfigFile::load(&target_dir.join("xstats.yaml").to_string_lossy()).unwrap();
+        assert_eq!(config_file.format.as_deref(), Some("csv"));
+        assert_eq!(config_file.min_aloc, Some(2));
+        fs::write(target_dir.join(".xstats.toml"), "colour = true\n").unwrap();
+        assert!(matches!(
+            ConfigFile::find(&target_dir.to_string_lossy()),
+            Err(XStatsError::Config(_))
+        ));
+        fs::write(target_dir.join(".xstats.toml"), "metrics = [\"loc\"]\n").unwrap();
+        assert!(matches!(
+            ConfigFile::find(&target_dir.to_string_lossy()),
+            Err(XStatsError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn run_manifest_test_history() {
+        let (repo_dir, repo) = init_repo("run_manifest");
+        let head = commit_files(&repo, &[("A.java", "class A {}\n")], "Add A");
+        let target_path = repo_dir.to_string_lossy().to_string();
+        let config = AnalysisConfig::new();
+        let filter = MetricsFilter::new();
+
+        let manifest = RunManifest::new(&target_path, &config, &filter);
+        assert_eq!(manifest.commit, Some(head.to_string()));
+        assert_eq!(manifest.dirty, Some(false));
+        assert!(manifest.grammar_versions.contains_key("tree-sitter-java"));
+
+        // Same settings, same hash
+        let other_filter = MetricsFilter {
+            min_aloc: 2,
+            ..MetricsFilter::new()
+        };
+        let other_manifest = RunManifest::new(&target_path, &config, &other_filter);
+        assert_eq!(
+            manifest.config_hash,
+            RunManifest::new(&target_path, &config, &filter).config_hash
+        );
+        assert_ne!(manifest.config_hash, other_manifest.config_hash);
+
+        fs::write(repo_dir.join("B.java"), "class B {}\n").unwrap();
+        let manifest = RunManifest::new(&target_path, &config, &filter);
+        assert_eq!(manifest.dirty, Some(true));
+    }
+
+    #[test]
+    fn activity_test_history() {
+        let (repo_dir, repo) = init_repo("activity");
+        let original = "class A {\n    int one() {\n        return 1;\n    }\n\n    int two() {\n        return 2;\n    }\n}\n";
+        let updated = "class A {\n    int one() {\n        return 1;\n    }\n\n    int two() {\n        return 22;\n    }\n}\n";
+        let now = Signature::now("xStats", "xstats@example.com").unwrap();
+        let year_ago = Time::new(now.when().seconds() - 365 * 86_400, 0);
+        let old = Signature::new("xStats", "xstats@example.com", &year_ago).unwrap();
+        commit_files_as(&repo, &[("A.java", original)], "Add A", &old);
+        commit_files(&repo, &[("A.java", updated)], "Update A.two");
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_default().unwrap();
+        xstats.run_activity(30.0).unwrap();
+
+        let activity_metrics = xstats.activity_metrics.unwrap();
+        let activity = |name: &str| {
+            activity_metrics
+                .activity_blocks
+                .iter()
+                .find(|b| b.meta_data.node_name == name)
+                .unwrap()
+                .activity
+        };
+        // Only one of the three lines of `two` was modified recently
+        assert!(activity("one") < 0.001);
+        assert!((activity("two") - 1.0 / 3.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn anonymous_entities_test() {
+        let target_dir = std::env::temp_dir().join("xstats_anonymous_entities");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        let java = "class Outer {\n    void run(int x) {\n        if (x > 0) {\n            Runnable r = new Runnable() {\n                public void run() { if (x > 1) {} }\n            };\n        }\n        Runnable s = () -> { while (x > 2) {} };\n    }\n}\n";
+        let python = "def f(x):\n    def g(y):\n        if y:\n            return 1\n    key = lambda v: v if v else 0\n    return g(key(x))\n";
+        fs::write(target_dir.join("Outer.java"), java).unwrap();
+        fs::write(target_dir.join("f.py"), python).unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        let cc: Vec<(&str, u32)> = metrics
+            .metric_blocks
+            .iter()
+            .map(|block| (block.meta_data.node_name.as_str(), block.metric.cc))
+            .collect();
+        // The methods leave out the decision points of the entities nested in them, but a lambda
+        // is one of theirs
+        assert_eq!(
+            cc,
+            [
+                ("Outer.java", 5),
+                ("Outer", 5),
+                ("run$anon@L4", 2),
+                ("run", 3),
+                ("run", 2),
+                ("run$lambda@L8", 2),
+                ("f.py", 4),
+                ("f", 2),
+                ("g", 2),
+                ("f$lambda@L5", 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn qualified_name_test() {
+        let target_dir = std::env::temp_dir().join("xstats_qualified_name");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        let java = "package com.shop;\n\nclass Cart {\n    void add() {}\n}\n\nclass Item {\n    void add() {}\n\n    class Part {\n        void add() {}\n    }\n}\n";
+        let python = "class Cart:\n    def add(self):\n        def check():\n            pass\n";
+        fs::write(target_dir.join("Cart.java"), java).unwrap();
+        fs::write(target_dir.join("cart.py"), python).unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path.clone());
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        let old_blocks = metrics.metric_blocks.clone();
+        let qualified_names: Vec<&str> = old_blocks
+            .iter()
+            .map(|block| block.meta_data.qualified_name.as_str())
+            .collect();
+        assert_eq!(
+            qualified_names,
+            [
+                "com.shop.Cart.java",
+                "com.shop.Cart",
+                "com.shop.Item",
+                "com.shop.Item.Part",
+                "com.shop.Cart.add",
+                "com.shop.Item.add",
+                "com.shop.Item.Part.add",
+                "cart.py",
+                "cart.Cart",
+                "cart.Cart.add",
+                "cart.Cart.add.check",
+            ]
+        );
+
+        // The methods named alike are told apart once the classes are reordered
+        let reordered = "package com.shop;\n\nclass Item {\n    void add() {}\n\n    class Part {\n        void add() {}\n    }\n}\n\nclass Cart {\n    void add() { if (true) {} }\n}\n";
+        fs::write(target_dir.join("Cart.java"), reordered).unwrap();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.run_default().unwrap();
+        let mut old_metrics = CodeMetrics::new();
+        old_metrics.metric_blocks = old_blocks;
+        let diffs = EntityDiffs::compare(
+            &old_metrics,
+            xstats.metrics_map.get_default_metrics().unwrap(),
+        );
+        let modified: Vec<(&str, &str)> = diffs
+            .diffs
+            .iter()
+            .filter_map(|diff| Some((diff.old.as_ref()?, diff.new.as_ref()?)))
+            .filter(|(_, new)| new.meta_data.node_kind == EntityKind::Method)
+            .map(|(old, new)| {
+                (
+                    old.meta_data.qualified_name.as_str(),
+                    new.meta_data.qualified_name.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(modified, [("com.shop.Cart.add", "com.shop.Cart.add")]);
+    }
+
+    #[test]
+    fn entity_id_test() {
+        let (repo_dir, repo) = init_repo("entity_id");
+        let original = "class A {\n    int two(int x) {\n        return x;\n    }\n}\n";
+        let updated = "class A {\n    int one() {\n        return 1;\n    }\n\n    int two(int x) {\n        return x;\n    }\n}\n";
+        let first = commit_files(&repo, &[("A.java", original)], "Add A");
+        let second = commit_files(&repo, &[("A.java", updated)], "Add A.one");
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+
+        let get_method = |commit: &Oid| {
+            let metrics = xstats.metrics_map.get_metrics(&commit.to_string()).unwrap();
+            let block = metrics
+                .metric_blocks
+                .iter()
+                .find(|block| block.meta_data.node_name == "two")
+                .unwrap();
+            (block.meta_data.start_row, block.meta_data.entity_id.clone())
+        };
+        let (first_row, first_id) = get_method(&first);
+        let (second_row, second_id) = get_method(&second);
+        // The method moved down, but is still the same entity
+        assert_ne!(first_row, second_row);
+        assert_eq!(first_id, second_id);
+        assert_eq!(first_id.len(), 16);
+    }
+
+    #[test]
+    fn entity_history_test() {
+        let (repo_dir, repo) = init_repo("entity_history");
+        let original = "class A {\n    int two(int x) {\n        return x;\n    }\n}\n";
+        let updated = "class A {\n    int one() {\n        return 1;\n    }\n\n    int two(int x) {\n        if (x > 0) {\n            return x;\n        }\n        return 0;\n    }\n}\n";
+        let first = commit_files(&repo, &[("A.java", original)], "Add A");
+        let second = commit_files(&repo, &[("A.java", updated)], "Update A.two");
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.metrics_map.filter.metrics = vec!["cc".to_string()];
+        xstats.metrics_map.filter.node_types = vec!["method_declaration".to_string()];
+        xstats.run_multi_commit().unwrap();
+
+        let commit_ids = [first.to_string(), second.to_string()];
+        let table = EntityHistory::new(&xstats.metrics_map, &commit_ids).get_table();
+        assert_eq!(
+            table[0],
+            [
+                "commit_id",
+                "entity_id",
+                "qualified_name",
+                "metric",
+                "value"
+            ]
+        );
+        let history: Vec<(&str, &str, &str, &str)> = table[1..]
+            .iter()
+            .map(|row| {
+                (
+                    row[0].as_str(),
+                    row[2].as_str(),
+                    row[3].as_str(),
+                    row[4].as_str(),
+                )
+            })
+            .collect();
+        let (first, second) = (first.to_string(), second.to_string());
+        assert_eq!(
+            history,
+            [
+                (first.as_str(), "A.two", "cc", "1"),
+                (second.as_str(), "A.one", "cc", "1"),
+                (second.as_str(), "A.two", "cc", "2"),
+            ]
+        );
+        // The method is tracked although it moved
+        assert_eq!(table[1][1], table[3][1]);
+
+        xstats.track_entities = true;
+        let mut sink = xstats.get_sink("csv").unwrap();
+        xstats.save_entity_history(sink.as_mut()).unwrap();
+        assert!(repo_dir.join("out/entities.csv").exists());
+    }
+
+    #[test]
+    fn carry_forward_test_history() {
+        let (repo_dir, repo) = init_repo("carry_forward");
+        let first = commit_files(&repo, &[("A.java", "class A {}\n")], "Add A");
+        commit_files(&repo, &[("B.java", "class B {}\n")], "Add B");
+        let third = commit_files(&repo, &[("C.java", "class C {}\n")], "Add C");
+        let fourth = commit_files(
+            &repo,
+            &[("C.java", "class C {\n    void f() {}\n}\n")],
+            "Update C",
+        );
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        // The files of the commits left out are carried forward all the same
+        xstats.revwalk_config.since = Some(first.to_string());
+        xstats.revwalk_config.commit_filter =
+            CommitFilter::from_patterns(&["Add B".to_string()], &[]).unwrap();
+        xstats.run_multi_commit().unwrap();
+
+        let get_names = |commit: &Oid| -> Vec<String> {
+            xstats
+                .metrics_map
+                .get_metrics(&commit.to_string())
+                .unwrap()
+                .metric_blocks
+                .iter()
+                .map(|block| block.meta_data.qualified_name.clone())
+                .collect()
+        };
+        assert_eq!(
+            get_names(&third),
+            ["A.java", "A", "B.java", "B", "C.java", "C"]
+        );
+        assert_eq!(
+            get_names(&fourth),
+            ["A.java", "A", "B.java", "B", "C.java", "C", "C.f"]
+        );
+        // The changes are still those of the commit
+        assert_eq!(
+            xstats.changes_map.get_table(&fourth.to_string()).len(),
+            1 + 3
+        );
+    }
+
+    #[test]
+    fn rename_test_history() {
+        let (repo_dir, repo) = init_repo("rename");
+        let content = "def add(item):\n    if item:\n        return 1\n    return 0\n";
+        let first = commit_files(&repo, &[("cart.py", content)], "Add cart");
+
+        // Move the file to another path, as `git mv` does
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("cart.py")).unwrap();
+        fs::remove_file(repo_dir.join("cart.py")).unwrap();
+        index.write().unwrap();
+        let second = commit_files(&repo, &[("basket.py", content)], "Rename cart");
+        let third = commit_files(
+            &repo,
+            &[(
+                "basket.py",
+                &format!("{}\ndef remove():\n    pass\n", content),
+            )],
+            "Update basket",
+        );
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+
+        let churn = xstats.churn_metrics.get_table();
+        assert_eq!(
+            churn[2],
+            [
+                &second.to_string(),
+                "basket.py",
+                "renamed",
+                "0",
+                "0",
+                "0",
+                "cart.py"
+            ]
+        );
+
+        let get_entities = |commit: &Oid| -> Vec<(String, String)> {
+            xstats
+                .metrics_map
+                .get_metrics(&commit.to_string())
+                .unwrap()
+                .metric_blocks
+                .iter()
+                .map(|block| {
+                    (
+                        block.meta_data.qualified_name.clone(),
+                        block.meta_data.entity_id.clone(),
+                    )
+                })
+                .collect()
+        };
+        let before = get_entities(&first);
+        let after = get_entities(&second);
+        assert_eq!(
+            after.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["basket.py", "basket.add"]
+        );
+        // The entities keep their ids across the rename, and the commits after it
+        let ids = |entities: &[(String, String)]| -> Vec<String> {
+            entities.iter().map(|(_, id)| id.clone()).collect()
+        };
+        assert_eq!(ids(&after), ids(&before));
+        assert_eq!(ids(&get_entities(&third))[..2], ids(&before));
+        // No changes are recorded for a file renamed as it is
+        assert_eq!(xstats.changes_map.get_table(&second.to_string()).len(), 1);
+
+        let hotspots = Hotspots::new(&xstats.churn_metrics, &xstats.metrics_map).get_table();
+        assert_eq!(hotspots.len(), 1 + 1);
+        assert_eq!(hotspots[1][..2], ["basket.py", "3"]);
+    }
+
+    #[test]
+    fn target_clone_test_history() {
+        let (repo_dir, repo) = init_repo("target_clone");
+        commit_files(&repo, &[("A.java", "class A {}\n")], "Add A");
+        commit_files(&repo, &[("b.py", "def b():\n    pass\n")], "Add b");
+
+        let url = format!("file://{}", repo_dir.display());
+        assert!(is_remote_url(&url));
+        assert!(is_remote_url("git@github.com:gautam-shetty/xStats.git"));
+        assert!(!is_remote_url("tests/samples"));
+        assert!(!is_remote_url("C:\\repo"));
+
+        // Local repositories with a working tree are analyzed as they are
+        let path = repo_dir.to_string_lossy().to_string();
+        assert!(core::TargetClone::new(&path, false, None)
+            .unwrap()
+            .is_none());
+
+        // A remote repository is analyzed from a clone, removed once dropped
+        let target_clone = core::TargetClone::new(&url, false, None).unwrap().unwrap();
+        let clone_dir = target_clone.get_path().to_path_buf();
+        let mut xstats = core::XStats::new(
+            clone_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        assert_eq!(metrics.metric_blocks.len(), 4);
+        drop(target_clone);
+        assert!(!clone_dir.exists());
+
+        // A bare repository is analyzed in place in the history mode, and cloned otherwise
+        let bare_dir = std::env::temp_dir().join("xstats_target_clone_bare");
+        let _ = fs::remove_dir_all(&bare_dir);
+        RepoBuilder::new()
+            .bare(true)
+            .clone(&url, &bare_dir)
+            .unwrap();
+        let bare_path = bare_dir.to_string_lossy().to_string();
+        assert!(core::TargetClone::new(&bare_path, true, None)
+            .unwrap()
+            .is_none());
+        let mut xstats = core::XStats::new(
+            bare_path.clone(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+        assert_eq!(xstats.commit_infos.get_table().len(), 1 + 2);
+
+        let target_clone = core::TargetClone::new(&bare_path, false, None)
+            .unwrap()
+            .unwrap();
+        assert!(target_clone.get_path().join("b.py").is_file());
+    }
+
+    #[test]
+    fn rev_test_history() {
+        let (repo_dir, repo) = init_repo("rev");
+        let first = commit_files(&repo, &[("A.java", "class A {}\n")], "Add A");
+        let release = repo.find_object(first, None).unwrap();
+        repo.tag_lightweight("v1.0", &release, false).unwrap();
+        commit_files(&repo, &[("b.py", "def b():\n    pass\n")], "Add b");
+        // Uncommitted changes are left out as well
+        fs::write(repo_dir.join("A.java"), "class A {\n    void f() {}\n}\n").unwrap();
+
+        let get_names = |target: &Path| -> Vec<String> {
+            let mut xstats = core::XStats::new(
+                target.to_string_lossy().to_string(),
+                repo_dir.join("out").to_string_lossy().to_string(),
+            );
+            xstats.rev = Some("v1.0".to_string());
+            xstats.run_default().unwrap();
+            xstats
+                .metrics_map
+                .get_default_metrics()
+                .unwrap()
+                .metric_blocks
+                .iter()
+                .map(|block| block.meta_data.qualified_name.clone())
+                .collect()
+        };
+        assert_eq!(get_names(&repo_dir), ["A.java", "A"]);
+
+        // The files of a bare repository are read as they are
+        let bare_dir = std::env::temp_dir().join("xstats_rev_bare");
+        let _ = fs::remove_dir_all(&bare_dir);
+        RepoBuilder::new()
+            .bare(true)
+            .clone(&format!("file://{}", repo_dir.display()), &bare_dir)
+            .unwrap();
+        assert_eq!(get_names(&bare_dir), ["A.java", "A"]);
+    }
+
+    #[test]
+    fn compare_revisions_test_history() {
+        let (repo_dir, repo) = init_repo("compare_revisions");
+        let first = commit_files(
+            &repo,
+            &[(
+                "A.java",
+                "class A {\n    int one() {\n        return 1;\n    }\n}\n",
+            )],
+            "Release 1.2",
+        );
+        let tag = |name: &str, commit: Oid| {
+            let object = repo.find_object(commit, None).unwrap();
+            repo.tag_lightweight(name, &object, false).unwrap();
+        };
+        tag("v1.2", first);
+        let updated = "class A {\n    int one(int x) {\n        if (x > 0) {\n            return 1;\n        }\n        return 0;\n    }\n}\n";
+        let second = commit_files(
+            &repo,
+            &[("A.java", updated), ("b.py", "def b():\n    pass\n")],
+            "Release 1.3",
+        );
+        tag("v1.3", second);
+        // The working tree is left out
+        fs::write(repo_dir.join("c.py"), "def c():\n    pass\n").unwrap();
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        let old_key = xstats.run_revision("v1.2").unwrap();
+        let new_key = xstats.run_revision("v1.3").unwrap();
+        assert_eq!(old_key, first.to_string());
+        assert_eq!(new_key, second.to_string());
+
+        let diffs = xstats.metrics_map.compare(&old_key, &new_key).unwrap();
+        let annotated: Vec<(String, String)> = diffs
+            .diffs
+            .iter()
+            .map(|diff| {
+                let block = diff.new.as_ref().or(diff.old.as_ref()).unwrap();
+                (
+                    diff.status.to_string(),
+                    block.meta_data.qualified_name.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            annotated,
+            [
+                ("modified".to_string(), "A.java".to_string()),
+                ("modified".to_string(), "A".to_string()),
+                ("modified".to_string(), "A.one".to_string()),
+                ("added".to_string(), "b.py".to_string()),
+                ("added".to_string(), "b.b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn authors_test_history() {
+        let (repo_dir, repo) = init_repo("authors");
+        let ada = Signature::now("Ada", "ada@example.com").unwrap();
+        let bob = Signature::now("Bob", "bob@example.com").unwrap();
+        let first = "class A {\n    int one(int x) {\n        if (x > 0) {\n            return 1;\n        }\n        return 0;\n    }\n}\n";
+        commit_files_as(&repo, &[("A.java", first)], "Add A", &ada);
+        commit_files_as(&repo, &[("b.py", "def b():\n    pass\n")], "Add b", &bob);
+        let second = first.replace("return 0;", "return -1;");
+        commit_files_as(&repo, &[("A.java", &second)], "Update A.one", &ada);
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+        assert_eq!(xstats.commit_infos.get_table()[2][4], "bob@example.com");
+
+        let mut sink = xstats.get_sink("csv").unwrap();
+        xstats.save_authors(sink.as_mut()).unwrap();
+        sink.finish().unwrap();
+        let authors: Vec<String> = fs::read_to_string(repo_dir.join("out/authors.csv"))
+            .unwrap()
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        // The methods added are touched as well, with their cc as of their commit
+        assert_eq!(
+            authors,
+            [
+                "author,commits,lines_touched,methods_touched,avg_cc,files_owned",
+                "ada@example.com,2,10,2,2.00,1",
+                "bob@example.com,1,2,1,1.00,1",
+            ]
+        );
+    }
+
+    #[test]
+    fn ownership_test_history() {
+        let (repo_dir, repo) = init_repo("ownership");
+        let ada = Signature::now("Ada", "ada@example.com").unwrap();
+        let bob = Signature::now("Bob", "bob@example.com").unwrap();
+        let conditions: String = (0..10)
+            .map(|i| format!("        if (x > {}) {{ x--; }}\n", i))
+            .collect();
+        let java = format!(
+            "class A {{\n    void f(int x) {{\n{}    }}\n}}\n",
+            conditions
+        );
+        let python = |returned: &str| {
+            format!(
+                "def b(x):\n    if x:\n        return 1\n    return {}\n\n\ndef c():\n    pass\n",
+                returned
+            )
+        };
+        commit_files_as(
+            &repo,
+            &[("A.java", &java), ("b.py", &python("0"))],
+            "Add A and b",
+            &ada,
+        );
+        commit_files_as(&repo, &[("b.py", &python("2"))], "Update b", &bob);
+        // Lines not committed yet have no author
+        fs::write(
+            repo_dir.join("b.py"),
+            python("2") + "\n\ndef d():\n    pass\n",
+        )
+        .unwrap();
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        xstats.run_default().unwrap();
+        xstats.run_ownership().unwrap();
+
+        let ownership = xstats.ownership.as_ref().unwrap();
+        assert_eq!(ownership.get_bus_factor(), 1);
+        let table = ownership.get_table();
+        assert_eq!(
+            table[0],
+            [
+                "file_path",
+                "lines",
+                "authors",
+                "top_author",
+                "top_share",
+                "significant_authors",
+                "bus_factor",
+                "cc",
+                "risky"
+            ]
+        );
+        // The complex file known to a single author comes first
+        assert_eq!(
+            table[1][1..],
+            ["14", "1", "ada@example.com", "1.00", "1", "1", "11", "true"]
+        );
+        assert!(table[1][0].ends_with("A.java"));
+        assert_eq!(
+            table[2][1..],
+            ["8", "2", "ada@example.com", "0.88", "2", "1", "2", "false"]
+        );
+    }
+
+    #[test]
+    fn commit_scope_test_history() {
+        let (repo_dir, repo) = init_repo("commit_scope");
+        let signature = |email: &str, date: &str| {
+            let time = Time::new(parse_utc_time(date).unwrap(), 0);
+            Signature::new("xStats", email, &time).unwrap()
+        };
+        let alice = "alice@example.com";
+        let bob = "bob@other.org";
+        let commits = [
+            commit_files_as(
+                &repo,
+                &[("src/A.java", "class A {}\n")],
+                "Add A",
+                &signature(alice, "2024-01-10"),
+            ),
+            commit_files_as(
+                &repo,
+                &[("docs/B.java", "class B {}\n")],
+                "Add B",
+                &signature(alice, "2024-02-10"),
+            ),
+            commit_files_as(
+                &repo,
+                &[("src/A.java", "class A { int a; }\n")],
+                "Update A",
+                &signature(bob, "2024-03-10"),
+            ),
+            commit_files_as(
+                &repo,
+                &[("src/A.java", "class A { int a, b; }\n")],
+                "Update A again",
+                &signature(alice, "2024-04-10T12:00:00Z"),
+            ),
+        ];
+
+        let run = |scope: CommitScope| {
+            let mut xstats = core::XStats::new(
+                repo_dir.to_string_lossy().to_string(),
+                repo_dir.join("out").to_string_lossy().to_string(),
+            );
+            xstats.revwalk_config.commit_scope = scope;
+            xstats.run_multi_commit().unwrap();
+            let mut analyzed: Vec<String> = xstats.metrics_map.metrics.into_keys().collect();
+            analyzed.sort();
+            analyzed
+        };
+        let expect = |indices: &[usize]| {
+            let mut expected: Vec<String> =
+                indices.iter().map(|i| commits[*i].to_string()).collect();
+            expected.sort();
+            expected
+        };
+
+        let authors = vec!["*@example.com".to_string()];
+        let paths = vec!["src/**".to_string()];
+        assert_eq!(
+            run(CommitScope::new(&authors, &paths, None, None).unwrap()),
+            expect(&[0, 3])
+        );
+        assert_eq!(
+            run(
+                CommitScope::new(&[], &[], Some("2024-02-10"), Some("2024-04-10T12:00:00Z"))
+                    .unwrap()
+            ),
+            expect(&[1, 2])
+        );
+        assert!(matches!(
+            CommitScope::new(&[], &[], Some("2024-13-01"), None),
+            Err(XStatsError::Config(_))
+        ));
+        assert_eq!(parse_utc_time("2000-02-29T12:34:56Z"), Some(951827696));
+        assert_eq!(parse_utc_time("2024-02-10T25:00:00Z"), None);
+    }
+
+    #[test]
+    fn merges_test_history() {
+        let (repo_dir, repo) = init_repo("merges");
+        let base = commit_files(&repo, &[("A.java", "class A {}\n")], "Add A");
+        let feature = commit_files(&repo, &[("B.java", "class B {}\n")], "Add B");
+        // Back to the base, as if the feature were on a branch
+        let base_commit = repo.find_commit(base).unwrap();
+        repo.reset(base_commit.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+        let mainline = commit_files(&repo, &[("C.java", "class C {}\n")], "Add C");
+
+        // Merge the feature into the mainline
+        fs::write(repo_dir.join("B.java"), "class B {}\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("B.java")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("xStats", "xstats@example.com").unwrap();
+        let parents = [
+            repo.find_commit(mainline).unwrap(),
+            repo.find_commit(feature).unwrap(),
+        ];
+        let merge = repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "Merge feature",
+                &tree,
+                &parents.iter().collect::<Vec<_>>(),
+            )
+            .unwrap();
+
+        let run = |skip_merges: bool, first_parent: bool| {
+            let mut xstats = core::XStats::new(
+                repo_dir.to_string_lossy().to_string(),
+                repo_dir.join("out").to_string_lossy().to_string(),
+            );
+            xstats.revwalk_config.skip_merges = skip_merges;
+            xstats.revwalk_config.first_parent = first_parent;
+            xstats.run_multi_commit().unwrap();
+            xstats
+        };
+        let analyzed = |xstats: &core::XStats| {
+            let mut analyzed: Vec<Oid> = xstats
+                .metrics_map
+                .metrics
+                .keys()
+                .map(|commit_id| Oid::from_str(commit_id).unwrap())
+                .collect();
+            analyzed.sort();
+            analyzed
+        };
+        let sorted = |mut oids: Vec<Oid>| {
+            oids.sort();
+            oids
+        };
+
+        assert_eq!(
+            analyzed(&run(false, false)),
+            sorted(vec![base, feature, mainline, merge])
+        );
+        assert_eq!(
+            analyzed(&run(true, false)),
+            sorted(vec![base, feature, mainline])
+        );
+
+        // The merge commit brings the changes of the feature to the mainline
+        let xstats = run(false, true);
+        assert_eq!(analyzed(&xstats), sorted(vec![base, mainline, merge]));
+        let changes = xstats.changes_map.get_changes(&merge.to_string()).unwrap();
+        assert!(changes
+            .changes
+            .iter()
+            .any(|change| change.file_path.ends_with("B.java")));
+        let metrics = xstats.metrics_map.get_metrics(&merge.to_string()).unwrap();
+        assert_eq!(
+            metrics
+                .metric_blocks
+                .iter()
+                .filter(|block| block.meta_data.node_kind == EntityKind::File)
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn builder_test_example1() {
+        let target_dir = format!("{}/example1", SAMPLES_DIR);
+        let output_dir = std::env::temp_dir().join("xstats_builder");
+        let _ = fs::remove_dir_all(&output_dir);
+        let sink = create_sink(
+            &output_dir.to_string_lossy(),
+            "csv",
+            CsvDialect::new(),
+            false,
+        )
+        .unwrap();
+        let mut xstats = XStatsBuilder::new(&target_dir)
+            .languages(&["java".to_string()])
+            .metrics(&["cc".to_string()])
+            .threads(2)
+            .sink(sink)
+            .build()
+            .unwrap();
+        let results = xstats.analyze().unwrap();
+
+        assert!(!results.metric_blocks.is_empty());
+        assert!(results
+            .metric_blocks
+            .iter()
+            .all(|block| block.meta_data.language == Language::Java));
+        assert!(!results.scores.is_empty());
+        assert!(results.warnings.is_empty());
+        let metrics = fs::read_to_string(output_dir.join("metrics.csv")).unwrap();
+        assert!(metrics.lines().next().unwrap().ends_with(",cc"));
+
+        assert!(matches!(
+            XStatsBuilder::new(&target_dir)
+                .metrics(&["unknown".to_string()])
+                .build(),
+            Err(XStatsError::Config(_))
+        ));
+        assert!(matches!(
+            XStatsBuilder::new(&target_dir).threads(0).build(),
+            Err(XStatsError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn builder_test_history() {
+        let (repo_dir, repo) = init_repo("builder_history");
+        let first = commit_files(&repo, &[("A.java", "class A {}\n")], "Add A");
+        let second = commit_files(
+            &repo,
+            &[("A.java", "class A {\n    void f() {}\n}\n")],
+            "Update A",
+        );
+
+        let mut xstats = XStatsBuilder::new(&repo_dir.to_string_lossy())
+            .commit_jobs(2)
+            .build()
+            .unwrap();
+        let results = xstats.analyze_history().unwrap();
+
+        let commit_ids: Vec<&str> = results
+            .commits
+            .iter()
+            .map(|commit| commit.commit_id.as_str())
+            .collect();
+        assert_eq!(commit_ids, [first.to_string(), second.to_string()]);
+        assert!(results.changes[&second.to_string()]
+            .iter()
+            .any(|change| change.node_name == "f"));
+        assert_eq!(results.churn.len(), 2);
+        assert!(results
+            .metric_blocks
+            .iter()
+            .any(|block| block.meta_data.node_name == "f"));
+    }
+
+    #[test]
+    fn analyze_sources_test() {
+        let mut xstats = core::XStats::new(
+            "does/not/exist".to_string(),
+            "does/not/exist/out".to_string(),
+        );
+        let sources = [
+            (
+                "src/A.java",
+                "class A {\n    int f(int x) {\n        if (x > 0) {\n            return x;\n        }\n        return new B().g();\n    }\n}\n".to_string(),
+            ),
+            (
+                "src/B.java",
+                "class B {\n    int g() {\n        return 1;\n    }\n}\n".to_string(),
+            ),
+            ("README.md", "# Not analyzed\n".to_string()),
+        ];
+        let metrics = xstats.analyze_sources(sources).unwrap();
+
+        let method = |name: &str| {
+            metrics
+                .metric_blocks
+                .iter()
+                .find(|block| block.meta_data.node_name == name)
+                .unwrap()
+        };
+        assert_eq!(method("f").metric.cc, 2);
+        assert_eq!(method("f").meta_data.file_path, "src/A.java");
+        assert_eq!(method("g").metric.cc, 1);
+        assert!(metrics
+            .metric_blocks
+            .iter()
+            .all(|block| block.meta_data.file_path != "README.md"));
+        // Coupling is measured between the sources
+        assert_eq!(method("A").metric.cbo, 1);
+        assert!(xstats.metrics_map.get_default_metrics().is_none());
+        assert!(!Path::new("does/not/exist").exists());
+    }
+
+    #[test]
+    fn progress_test_history() {
+        let (repo_dir, repo) = init_repo("progress");
+        let first = commit_files(&repo, &[("A.java", "class A {}\n")], "Add A");
+        let second = commit_files(
+            &repo,
+            &[
+                ("A.java", "class A { int a; }\n"),
+                ("B.java", "class B {}\n"),
+            ],
+            "Update A, add B",
+        );
+
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            repo_dir.join("out").to_string_lossy().to_string(),
+        );
+        let receiver = xstats.progress.subscribe();
+        xstats.run_multi_commit().unwrap();
+        let events: Vec<ProgressEvent> = receiver.try_iter().collect();
+        assert_eq!(events[0], ProgressEvent::CommitsStarted { total: 2 });
+        assert_eq!(events.last(), Some(&ProgressEvent::CommitsDone));
+        let commit_ids: Vec<String> = events
+            .iter()
+            .filter_map(|event| match event {
+                ProgressEvent::CommitDone { commit_id } => Some(commit_id.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(commit_ids, [first.to_string(), second.to_string()]);
+        let done_count = events
+            .iter()
+            .filter(|event| matches!(event, ProgressEvent::FileDone { .. }))
+            .count();
+        assert_eq!(done_count, 3);
+        assert!(events.contains(&ProgressEvent::FilesStarted { total: 2 }));
+
+        // Warnings are reported as they are added
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let listener_warnings = Arc::clone(&warnings);
+        let mut xstats = XStatsBuilder::new(&repo_dir.to_string_lossy())
+            .on_progress(move |event| {
+                if let ProgressEvent::Warning { message } = event {
+                    listener_warnings.lock().unwrap().push(message.clone());
+                }
+            })
+            .build()
+            .unwrap();
+        xstats.set_total_timeout(Duration::ZERO);
+        let results = xstats.analyze().unwrap();
+        assert_eq!(*warnings.lock().unwrap(), results.warnings);
+        assert_eq!(results.warnings.len(), 1);
+        assert!(results.warnings[0].starts_with("Total timeout exceeded, 0 of"));
+    }
+
+    #[test]
+    fn logger_test() {
+        let log_path = std::env::temp_dir().join("xstats_logger.log");
+        let _ = fs::remove_file(&log_path);
+        let mut logger = Logger::new(LevelFilter::Warn);
+        logger.set_log_file(&log_path.to_string_lossy()).unwrap();
+
+        let log = |level: Level, target: &str, message: &str| {
+            logger.log(
+                &Record::builder()
+                    .level(level)
+                    .target(target)
+                    .args(format_args!("{}", message))
+                    .build(),
+            );
+        };
+        log(
+            Level::Warn,
+            WarningKind::BrokenParse.target(),
+            "Syntax errors in A.java",
+        );
+        log(
+            Level::Warn,
+            WarningKind::BrokenParse.target(),
+            "Syntax errors in B.java",
+        );
+        log(
+            Level::Debug,
+            WarningKind::UnsupportedFile.target(),
+            "Skipped README.md",
+        );
+        log(
+            Level::Info,
+            "xstats::output",
+            "metrics saved at out/metrics.csv",
+        );
+        log(Level::Error, "other_crate", "Not from xStats");
+        logger.flush();
+
+        // Every warning is counted, whatever the level
+        let counts: Vec<(WarningKind, usize)> = logger.get_counts().into_iter().collect();
+        assert_eq!(
+            counts,
+            [
+                (WarningKind::UnsupportedFile, 1),
+                (WarningKind::BrokenParse, 2)
+            ]
+        );
+        assert_eq!(
+            logger.get_summary().unwrap(),
+            "Warnings: 1 unsupported file, 2 broken parses"
+        );
+        // Only the records of xStats up to the level are written
+        let log_file = fs::read_to_string(&log_path).unwrap();
+        let lines: Vec<&str> = log_file.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("WARN [xstats::broken_parse] Syntax errors in A.java"));
+
+        for kind in WarningKind::all() {
+            assert_eq!(WarningKind::from_target(kind.target()), Some(kind));
+        }
+        assert!(Logger::new(LevelFilter::Info).get_summary().is_none());
+    }
+
+    #[test]
+    fn jsonl_test_history() {
+        let (repo_dir, repo) = init_repo("jsonl");
+        commit_files(&repo, &[("A.java", "class A {}\n")], "Add A");
+        commit_files(&repo, &[("B.java", "class B { void b() {} }\n")], "Add B");
+        let output_dir = repo_dir.join("out");
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            output_dir.to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+        let mut sink = xstats.get_sink("jsonl").unwrap();
+        xstats.save_commits(sink.as_mut()).unwrap();
+        xstats.save_metrics_map(sink.as_mut()).unwrap();
+
+        // One JSON object per commit, typed as in the JSON format, without any wrapper
+        let commits = fs::read_to_string(output_dir.join("commits.jsonl")).unwrap();
+        let records: Vec<serde_json::Value> = commits
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|record| record["commit_id"].is_string()));
+
+        // One JSON object per metric row
+        let commit_id = records[1]["commit_id"].as_str().unwrap();
+        let metrics =
+            fs::read_to_string(output_dir.join(format!("metrics/{}.jsonl", commit_id))).unwrap();
+        let rows: Vec<serde_json::Value> = metrics
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert!(rows.len() >= 3);
+        assert!(rows
+            .iter()
+            .all(|row| row.is_object() && row.get("rows").is_none()));
+        assert!(rows.iter().any(|row| row["cc"].is_number()));
+    }
+
+    #[test]
+    fn yaml_xml_test_example1() {
+        let output_dir = std::env::temp_dir().join("xstats_yaml_xml");
+        let _ = fs::remove_dir_all(&output_dir);
+        let mut xstats = core::XStats::new(
+            format!("{}/example1", SAMPLES_DIR),
+            output_dir.to_string_lossy().to_string(),
+        );
+        xstats.run_default().unwrap();
+        let metric_count = xstats
+            .metrics_map
+            .get_default_metrics()
+            .unwrap()
+            .metric_blocks
+            .len();
+        xstats
+            .save_metrics(xstats.get_sink("yaml").unwrap().as_mut())
+            .unwrap();
+        xstats
+            .save_metrics(xstats.get_sink("xml").unwrap().as_mut())
+            .unwrap();
+
+        // The same structure as in the JSON format, with numbers as numbers
+        let yaml: serde_json::Value =
+            serde_yaml::from_str(&fs::read_to_string(output_dir.join("metrics.yaml")).unwrap())
+                .unwrap();
+        assert_eq!(yaml["schema_version"], 1);
+        let rows = yaml["rows"].as_array().unwrap();
+        assert_eq!(rows.len(), metric_count);
+        assert!(rows.iter().any(|row| row["cc"].is_number()));
+
+        let xml = fs::read_to_string(output_dir.join("metrics.xml")).unwrap();
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+        assert!(xml.contains("<table name=\"metrics\" schema_version=\"1\">"));
+        assert_eq!(xml.matches("<row>").count(), metric_count);
+        assert!(xml.contains("<cc>"));
+        assert!(!xml.contains("&&") && !xml.contains("<<"));
+    }
+
+    #[test]
+    fn stdout_test_history() {
+        /// A buffer shared with the sink, in place of stdout
+        #[derive(Clone, Default)]
+        struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for SharedBuffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let (repo_dir, repo) = init_repo("stdout");
+        commit_files(&repo, &[("A.java", "class A {}\n")], "Add A");
+        commit_files(&repo, &[("B.java", "class B { void b() {} }\n")], "Add B");
+        let mut xstats = core::XStats::new(repo_dir.to_string_lossy().to_string(), "-".to_string());
+        xstats.run_multi_commit().unwrap();
+        let row_count: usize = xstats
+            .metrics_map
+            .metrics
+            .values()
+            .map(|metrics| metrics.metric_blocks.len())
+            .sum();
+
+        let buffer = SharedBuffer::default();
+        let mut sink =
+            StdoutSink::with_writer("csv", CsvDialect::default(), Box::new(buffer.clone()))
+                .unwrap();
+        xstats.save_run_manifest(&mut sink).unwrap();
+        xstats.save_metrics_map(&mut sink).unwrap();
+        xstats.save_commits(&mut sink).unwrap();
+        // Another table would be written under the header of the metrics
+        let commit_ids: Vec<String> = xstats.metrics_map.metrics.keys().cloned().collect();
+        assert_eq!(commit_ids.len(), 2);
+        assert!(matches!(
+            xstats.save_diff(
+                &mut sink,
+                &commit_ids[0],
+                &commit_ids[1],
+                &["aloc".to_string()]
+            ),
+            Err(XStatsError::InvalidTable { .. })
+        ));
+        sink.finish().unwrap();
+
+        // The metrics of every commit as a single table, the other results left out
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("commit_id,language,file_path,"));
+        assert_eq!(lines.len(), row_count + 1);
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|line| line.starts_with("commit_id,"))
+                .count(),
+            1
+        );
+        assert!(!output.contains("Add A"));
+        assert!(!repo_dir.join("-").exists());
+
+        // Formats that cannot be written to stdout are refused up front
+        assert!(matches!(
+            xstats.get_sink("parquet"),
+            Err(XStatsError::UnsupportedFormat(_))
+        ));
+        assert!(xstats.get_sink("jsonl").is_ok());
+    }
+
+    #[test]
+    fn language_detection_test() {
+        assert_eq!(
+            get_shebang_language("#!/usr/bin/env python3\nprint(1)\n"),
+            Some(Language::Python)
+        );
+        assert_eq!(
+            get_shebang_language("#!/usr/bin/python3.12 -u\n"),
+            Some(Language::Python)
+        );
+        assert_eq!(
+            get_shebang_language("#!/usr/bin/env -S node --no-warnings\n"),
+            Some(Language::JavaScript)
+        );
+        assert_eq!(get_shebang_language("#!/bin/sh\n"), None);
+        assert_eq!(get_shebang_language("print(1)\n#!/usr/bin/python\n"), None);
+
+        let target_dir = std::env::temp_dir().join("xstats_language_detection");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(
+            target_dir.join("deploy"),
+            "#!/usr/bin/env python3\ndef deploy():\n    pass\n",
+        )
+        .unwrap();
+        fs::write(target_dir.join("tool.pyw"), "def tool():\n    pass\n").unwrap();
+        fs::write(
+            target_dir.join("page.jsp"),
+            "<%@ page language=\"java\" %>\n<html>\n<script>\nfunction greet() {}\n</script>\n</html>\n",
+        )
+        .unwrap();
+        fs::write(target_dir.join("LICENSE"), "MIT License\n").unwrap();
+        fs::write(
+            target_dir.join("image.png"),
+            [0x89, b'P', b'N', b'G', 0, 0xff],
+        )
+        .unwrap();
+
+        let mut xstats = core::XStats::new(target_dir.to_string_lossy().to_string(), String::new());
+        xstats.config.add_extension_language("pyw=python").unwrap();
+        assert_eq!(
+            xstats.config.extension_languages.get(".pyw"),
+            Some(&Language::Python)
+        );
+        assert!(matches!(
+            xstats.config.add_extension_language(".pyw"),
+            Err(XStatsError::Config(_))
+        ));
+        assert!(matches!(
+            xstats.config.add_extension_language(".pyw=cobol"),
+            Err(XStatsError::Config(_))
+        ));
+        xstats.run_default().unwrap();
+
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        let get_language = |name: &str| {
+            metrics
+                .metric_blocks
+                .iter()
+                .find(|block| block.meta_data.node_name == name)
+                .map(|block| block.meta_data.language)
+        };
+        assert_eq!(get_language("deploy"), Some(Language::Python));
+        assert_eq!(get_language("tool"), Some(Language::Python));
+        assert_eq!(get_language("greet"), Some(Language::JavaScript));
+        assert!(metrics.metric_blocks.iter().all(|block| {
+            !block.meta_data.file_path.ends_with("LICENSE")
+                && !block.meta_data.file_path.ends_with("image.png")
+        }));
+    }
+
+    #[test]
+    fn encoding_test() {
+        assert_eq!(Encoding::from_name("ISO-8859-1").unwrap(), Encoding::Latin1);
+        assert_eq!(
+            Encoding::from_name("cp1252").unwrap(),
+            Encoding::Windows1252
+        );
+        assert!(matches!(
+            Encoding::from_name("ebcdic"),
+            Err(XStatsError::Config(_))
+        ));
+        let bytes = b"caf\xe9 \x80";
+        assert_eq!(Encoding::Latin1.decode(bytes), "caf\u{e9} \u{80}");
+        assert_eq!(Encoding::Windows1252.decode(bytes), "caf\u{e9} \u{20ac}");
+        assert_eq!(Encoding::Utf8.decode(bytes), "caf\u{fffd} \u{fffd}");
+        assert_eq!(
+            Encoding::Latin1.decode_source("A.java", "caf\u{e9}".as_bytes().to_vec()),
+            "caf\u{e9}"
+        );
+
+        // A Latin-1 file is analyzed instead of being skipped
+        let target_dir = std::env::temp_dir().join("xstats_encoding");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(
+            target_dir.join("Cafe.java"),
+            b"// Caf\xe9 cr\xe8me\nclass Cafe {\n    void serve() {}\n}\n",
+        )
+        .unwrap();
+        for encoding in [Encoding::Utf8, Encoding::Latin1] {
+            let mut xstats =
+                core::XStats::new(target_dir.to_string_lossy().to_string(), String::new());
+            xstats.config.encoding = encoding;
+            xstats.run_default().unwrap();
+            let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+            let names: Vec<&str> = metrics
+                .metric_blocks
+                .iter()
+                .map(|block| block.meta_data.node_name.as_str())
+                .collect();
+            assert_eq!(names, ["Cafe.java", "Cafe", "serve"]);
+            assert_eq!(metrics.metric_blocks[0].metric.cloc, 1);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn traversal_test() {
+        let target_dir = std::env::temp_dir().join("xstats_traversal");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(target_dir.join("src/deep/deeper")).unwrap();
+        fs::write(target_dir.join("src/A.java"), "class A {}\n").unwrap();
+        fs::write(target_dir.join("src/deep/deeper/B.java"), "class B {}\n").unwrap();
+        fs::write(
+            target_dir.join("src/logo.png"),
+            [0x89, b'P', b'N', b'G', 0, 0],
+        )
+        .unwrap();
+        // A cycle of links, a link to a file, and a hard link
+        std::os::unix::fs::symlink(&target_dir, target_dir.join("src/loop")).unwrap();
+        std::os::unix::fs::symlink(target_dir.join("src/A.java"), target_dir.join("Link.java"))
+            .unwrap();
+        fs::hard_link(
+            target_dir.join("src/A.java"),
+            target_dir.join("src/Hard.java"),
+        )
+        .unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let path_filter = PathFilter::new();
+        let relative = |files: Vec<String>| -> Vec<String> {
+            files
+                .iter()
+                .map(|file| file[target_path.len() + 1..].to_string())
+                .collect()
+        };
+
+        // Links are skipped by default, hard links listed once and binary files left out
+        let files = traverse_path(&target_path, &path_filter).unwrap();
+        assert_eq!(relative(files), ["src/A.java", "src/deep/deeper/B.java"]);
+
+        // Followed, the cycle ends and every file is listed once
+        let mut options = TraversalOptions {
+            symlinks: SymlinkPolicy::Follow,
+            max_depth: None,
+        };
+        let files = traverse_path_with_options(&target_path, &path_filter, &options).unwrap();
+        assert_eq!(relative(files), ["Link.java", "src/deep/deeper/B.java"]);
+
+        options.max_depth = Some(1);
+        let files = traverse_path_with_options(&target_path, &path_filter, &options).unwrap();
+        assert_eq!(relative(files), ["Link.java"]);
+        assert!(matches!(
+            SymlinkPolicy::from_name("ignore"),
+            Err(XStatsError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn stdout_warnings_test() {
+        let target_dir = std::env::temp_dir().join("xstats_stdout_warnings");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(
+            target_dir.join("Broken.java"),
+            "class Broken { void b( { }\n",
+        )
+        .unwrap();
+
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_xStats"))
+            .args(["-t", &target_dir.to_string_lossy(), "--stdout"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        // Only the metrics are written to stdout, the warnings and the summary to stderr
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let records: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        assert_eq!(records["rows"].as_array().unwrap().len(), 2);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("Syntax errors in"));
+        assert!(stderr.contains("Warnings: 1 broken parse"));
+    }
+
+    #[test]
+    fn decision_points_cache_test() {
+        let mut parsers = TSParsers::new();
+        let config = AnalysisConfig::new();
+        let source_code = "class A {
+    int f(int x) {
+        Runnable r = () -> { if (x > 0) { g(); } };
+        class B {
+            int g(int y) { return y > 0 && x > 0 ? y : x; }
+        }
+        for (int i = 0; i < x; i++) { if (i % 2 == 0) { x--; } }
+        return x;
+    }
+}
+";
+        let tree = parsers.parse_source(&Language::Java, source_code).unwrap();
+        let get_group = |name: &str| -> Vec<String> {
+            let kinds = find_node_group(Language::Java, name).unwrap();
+            kinds.iter().map(|kind| kind.to_string()).collect()
+        };
+        let decision_points = get_group("decision_point_nodes");
+        let skip_nodes = get_group("decision_point_skip_nodes");
+
+        // The nested entities, the innermost first for the outer ones to reuse their counts
+        fn collect<'t>(node: Node<'t>, skip_nodes: &[String], nodes: &mut Vec<Node<'t>>) {
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                collect(child, skip_nodes, nodes);
+            }
+            if skip_nodes.contains(&node.kind().to_string()) {
+                nodes.push(node);
+            }
+        }
+        let mut nodes = Vec::new();
+        collect(tree.root_node(), &skip_nodes, &mut nodes);
+        assert!(nodes.len() >= 4);
+
+        // The counts of a visitor reusing its cache match those of a fresh one, whatever the
+        // groups of nodes it counted with before
+        let visitor = TreeVisitor::new(&parsers, &config, Language::Java, source_code);
+        let if_statements = ["if_statement".to_string()];
+        for (points, skipped) in [
+            (decision_points.as_slice(), skip_nodes.as_slice()),
+            (&if_statements, skip_nodes.as_slice()),
+            (decision_points.as_slice(), &[]),
+        ] {
+            for node in nodes.iter().chain([&tree.root_node()]) {
+                let uncached = TreeVisitor::new(&parsers, &config, Language::Java, source_code)
+                    .count_decision_points(*node, points, skipped);
+                assert_eq!(
+                    visitor.count_decision_points(*node, points, skipped),
+                    uncached
+                );
+            }
+        }
+        let count = |points: &[String]| {
+            visitor.count_decision_points(tree.root_node(), points, &skip_nodes)
+        };
+        assert_eq!(count(&if_statements), 2);
+        assert_eq!(count(&decision_points), 5);
+    }
+
+    #[test]
+    fn parquet_schema_test_history() {
+        let read_parquet = |path: &Path| {
+            let reader = SerializedFileReader::new(File::open(path).unwrap()).unwrap();
+            let schema = reader.metadata().file_metadata().schema_descr_ptr();
+            let columns: Vec<(String, PhysicalType, bool)> = schema
+                .columns()
+                .iter()
+                .map(|column| {
+                    let is_optional = column.self_type().is_optional();
+                    (
+                        column.name().to_string(),
+                        column.physical_type(),
+                        is_optional,
+                    )
+                })
+                .collect();
+            let rows: Vec<Row> = reader
+                .get_row_iter(None)
+                .unwrap()
+                .map(Result::unwrap)
+                .collect();
+            (columns, rows)
+        };
+
+        // Empty values, and those not of the type of the column, are nulls
+        let header = ["name", "count", "share", "is_test"].map(String::from);
+        let rows = vec![
+            ["A.java", "3", "0.5", "true"].map(String::from).to_vec(),
+            ["", "", "1", "false"].map(String::from).to_vec(),
+            ["Ü.java", "n/a", "", ""].map(String::from).to_vec(),
+        ];
+        let column_types = [
+            ColumnType::String,
+            ColumnType::Int64,
+            ColumnType::Double,
+            ColumnType::Boolean,
+        ];
+        let file_path = std::env::temp_dir().join("xstats_parquet_types.parquet");
+        fs::write(&file_path, encode_parquet(&header, &rows, &column_types)).unwrap();
+        let (columns, read_rows) = read_parquet(&file_path);
+        assert_eq!(
+            columns,
+            [
+                ("name".to_string(), PhysicalType::BYTE_ARRAY, false),
+                ("count".to_string(), PhysicalType::INT64, true),
+                ("share".to_string(), PhysicalType::DOUBLE, true),
+                ("is_test".to_string(), PhysicalType::BOOLEAN, true),
+            ]
+        );
+        let fields: Vec<Vec<Field>> = read_rows
+            .iter()
+            .map(|row| {
+                row.get_column_iter()
+                    .map(|(_, field)| field.clone())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                vec![
+                    Field::Str("A.java".to_string()),
+                    Field::Long(3),
+                    Field::Double(0.5),
+                    Field::Bool(true)
+                ],
+                vec![
+                    Field::Str(String::new()),
+                    Field::Null,
+                    Field::Double(1.0),
+                    Field::Bool(false)
+                ],
+                vec![
+                    Field::Str("Ü.java".to_string()),
+                    Field::Null,
+                    Field::Null,
+                    Field::Null
+                ],
+            ]
+        );
+
+        // The tables of every commit share their schema, whatever their values, even without rows
+        let (repo_dir, repo) = init_repo("parquet_schema");
+        commit_files(&repo, &[("A.java", "class A { void a() {} }\n")], "Add A");
+        commit_files(&repo, &[("README.md", "# A\n")], "Add the readme");
+        let output_dir = std::env::temp_dir().join("xstats_parquet_schema_output");
+        let _ = fs::remove_dir_all(&output_dir);
+        let mut xstats = core::XStats::new(
+            repo_dir.to_string_lossy().to_string(),
+            output_dir.to_string_lossy().to_string(),
+        );
+        xstats.run_multi_commit().unwrap();
+        let mut sink = xstats.get_sink("parquet").unwrap();
+        xstats.save_metrics_map(sink.as_mut()).unwrap();
+        xstats.save_changes_map(sink.as_mut()).unwrap();
+        for table in ["metrics", "changes"] {
+            let tables: Vec<_> = fs::read_dir(output_dir.join(table))
+                .unwrap()
+                .map(|entry| read_parquet(&entry.unwrap().path()))
+                .collect();
+            assert_eq!(tables.len(), 2);
+            assert_eq!(tables[0].0, tables[1].0, "{}", table);
+        }
+        let changes_schema = fs::read_dir(output_dir.join("changes"))
+            .unwrap()
+            .map(|entry| read_parquet(&entry.unwrap().path()))
+            .find(|(_, rows)| rows.is_empty())
+            .unwrap()
+            .0;
+        assert!(changes_schema.contains(&("start_row".to_string(), PhysicalType::INT64, true)));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn language_profile_grammar_test() {
+        let test_dir = std::env::temp_dir().join("xstats_language_profile_grammar");
+        let _ = fs::remove_dir_all(&test_dir);
+        let languages_dir = test_dir.join("languages");
+        let target_dir = test_dir.join("code");
+        fs::create_dir_all(&languages_dir).unwrap();
+        fs::create_dir_all(&target_dir).unwrap();
+
+        // The tiny grammar of tests/grammars/snake, generated from its grammar.js
+        let status = std::process::Command::new("cc")
+            .args(["-shared", "-fPIC", "-I", "tests/grammars/snake/src"])
+            .arg("tests/grammars/snake/src/parser.c")
+            .arg("-o")
+            .arg(languages_dir.join("libtree-sitter-snake.so"))
+            .status()
+            .unwrap();
+        assert!(status.success());
+        fs::write(
+            languages_dir.join("snake.toml"),
+            concat!(
+                "name = \"Snake\"\n",
+                "extensions = [\".snk\"]\n",
+                "library = \"libtree-sitter-snake.so\"\n",
+                "[queries]\n",
+                "comment = \"(comment)\"\n",
+                "class = \"(class_definition)\"\n",
+                "method = \"(function_definition)\"\n",
+                "[node-groups]\n",
+                "decision-points = [\"if_statement\", \"while_statement\"]\n",
+                "nesting = [\"block\"]\n",
+                "entities = [\"class_definition\", \"function_definition\"]\n",
+            ),
+        )
+        .unwrap();
+        fs::write(
+            target_dir.join("main.snk"),
+            "# A greeter\nclass greeter {\n  def greet {\n    if loud {\n      while tired { rest; }\n    }\n    say;\n  }\n}\ndef main { run; }\n",
+        )
+        .unwrap();
+
+        // Analyzed in a process of its own, as loaded languages are registered for its lifetime
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_xStats"))
+            .args(["-t", &target_dir.to_string_lossy(), "--stdout"])
+            .args(["--languages-dir", &languages_dir.to_string_lossy()])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let records: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let rows = records["rows"].as_array().unwrap();
+        let get_row = |qualified_name: &str| {
+            rows.iter()
+                .find(|row| row["qualified_name"] == qualified_name)
+                .unwrap()
+        };
+        assert_eq!(rows.len(), 4);
+
+        let file = get_row("main.snk");
+        assert_eq!(file["language"], "Snake");
+        assert_eq!(file["noc"], 1);
+        assert_eq!(file["nom"], 2);
+        assert_eq!(file["cloc"], 1);
+        let class = get_row("main.greeter");
+        assert_eq!(class["node_kind"], "class");
+        assert_eq!(class["nom"], 1);
+        let greet = get_row("main.greeter.greet");
+        assert_eq!(greet["node_kind"], "method");
+        assert_eq!(greet["cc"], 3);
+        assert_eq!(greet["nd"], 3);
+        assert_eq!(get_row("main.main")["cc"], 1);
+
+        // Another profile may not claim the extensions of the language
+        fs::write(
+            languages_dir.join("viper.toml"),
+            concat!(
+                "name = \"Viper\"\n",
+                "extensions = [\".snk\"]\n",
+                "library = \"libtree-sitter-snake.so\"\n",
+                "symbol = \"tree_sitter_snake\"\n",
+                "[queries]\n",
+                "method = \"(function_definition)\"\n",
+            ),
+        )
+        .unwrap();
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_xStats"))
+            .args(["-t", &target_dir.to_string_lossy(), "--stdout"])
+            .args(["--languages-dir", &languages_dir.to_string_lossy()])
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        assert!(
+            String::from_utf8_lossy(&output.stderr).contains("extension .snk is taken by Snake")
+        );
+    }
+
+    #[test]
+    fn metric_test_example8() {
+        let target_dir = format!("{}/example8", SAMPLES_DIR);
+        let mut xstats = core::XStats::new(target_dir, SAMPLES_DIR.to_string());
+        xstats.run_default().unwrap();
+        let metrics: Vec<Vec<String>> = xstats.metrics_map.get_table(None);
+        let metrics_ref: Vec<Vec<&str>> = metrics
+            .iter()
+            .map(|v| v.iter().map(|s| s.as_str()).collect())
+            .collect();
+        let metrics_slice: Vec<&[&str]> = metrics_ref.iter().map(|v| v.as_slice()).collect();
+        assert_eq!(
+            metrics_slice.as_slice(),
+            expected::EXPECTED_METRICS_EXAMPLE8
+        );
+    }
+
+    #[test]
+    fn c_cpp_test() {
+        let target_dir = std::env::temp_dir().join("xstats_c_cpp");
+        let _ = fs::remove_dir_all(&target_dir);
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(
+            target_dir.join("list.c"),
+            concat!(
+                "#include <stdlib.h>\n",
+                "#include \"list.h\" // TODO drop\n",
+                "#define URL \"http://example.com\"\n",
+                "#define LIMIT 4 // FIXME per list\n",
+                "#define CHECK(x) \\\n",
+                "    check(x) // the check\n",
+                "typedef enum { RED, GREEN } color_t;\n",
+                "node_t *next(node_t *node, ...) {\n",
+                "    return node ? node->next : NULL;\n",
+                "}\n",
+            ),
+        )
+        .unwrap();
+        fs::write(
+            target_dir.join("list.cpp"),
+            concat!(
+                "#include <list>\n",
+                "template <typename T> class List {\n",
+                "    T &front(int index = 0) { return items[index]; }\n",
+                "};\n",
+                "int List::size() const {\n",
+                "    auto count = [](int n) { return n; };\n",
+                "    return count(0);\n",
+                "}\n",
+            ),
+        )
+        .unwrap();
+
+        let target_path = target_dir.to_string_lossy().to_string();
+        let mut xstats = core::XStats::new(target_path.clone(), target_path);
+        xstats.run_default().unwrap();
+        let metrics = xstats.metrics_map.get_default_metrics().unwrap();
+        let get_block = |qualified_name: &str| {
+            metrics
+                .metric_blocks
+                .iter()
+                .find(|block| block.meta_data.qualified_name == qualified_name)
+                .unwrap()
+        };
+
+        // The includes are imports, and the comments ending a macro count, those in strings
+        // aside, but not the lines of code they end
+        let file = get_block("list.c");
+        assert_eq!(file.meta_data.language, Language::C);
+        assert_eq!(file.metric.noi, 2);
+        assert_eq!(file.metric.cloc, 3);
+        assert_eq!(file.metric.sloc, 10);
+        assert_eq!(file.metric.todos, 2);
+        let table = MarkerReport::new(metrics).get_table();
+        assert_eq!(table[1][1..], ["2", "TODO", "TODO drop"]);
+        assert_eq!(table[2][1..], ["4", "FIXME", "FIXME per list"]);
+
+        // Functions are named by their declarator, and anonymous types by their typedef
+        assert_eq!(
+            get_block("list.color_t").meta_data.node_kind,
+            EntityKind::Class
+        );
+        let next = get_block("list.next");
+        assert_eq!((next.metric.pc, next.metric.cc), (2, 2));
+        assert!(next.metric.is_variadic);
+
+        let file = get_block("list.cpp");
+        assert_eq!(file.meta_data.language, Language::Cpp);
+        assert_eq!(
+            (file.metric.noi, file.metric.noc, file.metric.nom),
+            (1, 1, 3)
+        );
+        let front = get_block("list.List.front");
+        assert_eq!((front.metric.pc, front.metric.dpc), (1, 1));
+        assert_eq!(get_block("list.List::size").metric.cc, 2);
+        assert_eq!(get_block("list.List::size.count").metric.pc, 1);
+    }
 }
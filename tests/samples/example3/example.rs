@@ -0,0 +1,39 @@
+//! Example module
+use std::fmt;
+
+/// A point in the plane
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Self {
+        Point { x, y }
+    }
+
+    // Quadrant of the point
+    pub fn quadrant(&self) -> u8 {
+        match (self.x >= 0, self.y >= 0) {
+            (true, true) => 1,
+            (false, true) => 2,
+            (false, false) => 3,
+            (true, false) => 4,
+        }
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+mod geometry {
+    pub fn is_origin(x: i32, y: i32) -> bool {
+        if x == 0 && y == 0 {
+            return true;
+        }
+        false
+    }
+}
@@ -0,0 +1,30 @@
+import { readFile } from "fs";
+
+/**
+ * A shape with a name.
+ */
+class Shape {
+    constructor(name) {
+        this.name = name;
+    }
+
+    describe() {
+        return this.name ? `Shape ${this.name}` : "Unnamed shape";
+    }
+}
+
+// Add two numbers
+const add = (a, b) => a + b;
+
+const square = x => x * x;
+
+function classify(value) {
+    switch (value) {
+        case 0:
+            return "zero";
+        case 1:
+            return "one";
+        default:
+            return "many";
+    }
+}
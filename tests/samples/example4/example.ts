@@ -0,0 +1,21 @@
+import type { Reader } from "./reader";
+
+/** Reads every line of a source. */
+export abstract class LineReader implements Reader {
+    abstract source(): string;
+
+    readLines(limit: number, skipEmpty = true): string[] {
+        const lines: string[] = [];
+        for (const line of this.source().split("\n")) {
+            if (skipEmpty && line.length === 0) {
+                continue;
+            }
+            lines.push(line);
+        }
+        return lines.slice(0, limit);
+    }
+}
+
+export function isBlank(text: string): boolean {
+    return text.trim().length === 0;
+}
@@ -0,0 +1,30 @@
+package com.shop;
+
+import com.shop.model.Item;
+import java.util.ArrayList;
+import java.util.List;
+
+public class Cart {
+    private Item first;
+    private List<Item> items = new ArrayList<>();
+
+    public int total() {
+        Item extra = new Item(2);
+        int sum = first.price() + extra.price();
+        items.add(extra);
+        log(sum);
+        return sum + Inventory.count();
+    }
+
+    private void log(int value) {
+        System.out.println(value);
+    }
+
+    public int size() {
+        return items.size();
+    }
+
+    public int sizeOf(Store store) {
+        return store.cart().size();
+    }
+}
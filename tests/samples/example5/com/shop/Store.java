@@ -0,0 +1,13 @@
+package com.shop;
+
+public class Store {
+    private Cart cart = new Cart();
+
+    public Cart cart() {
+        return cart;
+    }
+
+    public int size() {
+        return 1;
+    }
+}
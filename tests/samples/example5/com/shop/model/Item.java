@@ -0,0 +1,17 @@
+package com.shop.model;
+
+public class Item {
+    private int price;
+
+    public Item(int price) {
+        this.price = price;
+    }
+
+    public int price() {
+        return price;
+    }
+
+    public void add(Item other) {
+        price += other.price();
+    }
+}
@@ -0,0 +1,43 @@
+public class Account {
+    private String owner;
+    private int balance;
+    private Ledger ledger;
+
+    public String getOwner() {
+        return owner;
+    }
+
+    public void setOwner(String owner) {
+        this.owner = owner;
+    }
+
+    public void post(int amount) {
+        ledger.post(amount);
+    }
+
+    public int deposit(int amount) {
+        if (amount <= 0) {
+            return balance;
+        }
+        balance += amount;
+        return balance;
+    }
+
+    @Override
+    public boolean equals(Object other) {
+        if (!(other instanceof Account)) {
+            return false;
+        }
+        return owner.equals(((Account) other).owner);
+    }
+
+    @Override
+    public int hashCode() {
+        return owner.hashCode();
+    }
+
+    @Override
+    public String toString() {
+        return "Account(" + owner + ")";
+    }
+}
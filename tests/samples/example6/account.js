@@ -0,0 +1,19 @@
+class Account {
+  getOwner() {
+    return this.owner;
+  }
+
+  setOwner(owner) {
+    this.owner = owner;
+  }
+
+  deposit(amount) {
+    if (amount <= 0) {
+      return this.balance;
+    }
+    this.balance += amount;
+    return this.balance;
+  }
+}
+
+const owner = (account) => account.owner;
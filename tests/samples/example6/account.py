@@ -0,0 +1,23 @@
+class Account:
+    def __init__(self, owner):
+        self.owner = owner
+        self.balance = 0
+
+    def get_owner(self):
+        """Owner of the account"""
+        return self.owner
+
+    def set_owner(self, owner):
+        self.owner = owner
+
+    def post(self, amount):
+        return self.ledger.post(amount)
+
+    def deposit(self, amount):
+        if amount <= 0:
+            return self.balance
+        self.balance += amount
+        return self.balance
+
+    def __repr__(self):
+        return f"Account({self.owner})"
@@ -0,0 +1,29 @@
+use std::fmt;
+
+pub struct Account {
+    owner: String,
+    balance: u32,
+}
+
+impl Account {
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    pub fn set_owner(&mut self, owner: String) {
+        self.owner = owner;
+    }
+
+    pub fn deposit(&mut self, amount: u32) -> u32 {
+        if amount > 0 {
+            self.balance += amount;
+        }
+        self.balance
+    }
+}
+
+impl fmt::Display for Account {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Account({})", self.owner)
+    }
+}
@@ -0,0 +1,13 @@
+class Query {
+
+    String select() {
+        String sql = """
+            SELECT *
+
+            FROM users
+
+            """;
+
+        return sql;
+    }
+}
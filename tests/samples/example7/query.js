@@ -0,0 +1,9 @@
+function select() {
+    const sql = `
+        SELECT *
+
+        FROM users
+    `;
+
+    return sql;
+}
@@ -0,0 +1,9 @@
+fn select() -> &'static str {
+    let sql = r#"
+        SELECT *
+
+        FROM users
+    "#;
+
+    sql
+}
@@ -0,0 +1,44 @@
+#include <stdio.h>
+#include <stdlib.h> // malloc
+#include "stack.h"
+
+#define MAX_ITEMS 16 // the capacity of a stack
+#define MIN(a, b) ((a) < (b) ? (a) : (b))
+
+/** A stack of integers */
+typedef struct {
+    int items[MAX_ITEMS];
+    int size;
+} stack_t;
+
+struct node {
+    int value;
+    struct node *next;
+};
+
+/* Push an item, unless the stack is full */
+int push(stack_t *stack, int item) {
+    if (stack->size >= MAX_ITEMS) {
+        return -1;
+    }
+    stack->items[stack->size++] = item;
+    return 0;
+}
+
+// TODO shrink
+static struct node *next(struct node *node) {
+    while (node && node->value < 0) {
+        node = node->next;
+    }
+    return node;
+}
+
+int print_all(const char *format, ...) {
+    switch (format[0]) {
+    case 'a':
+        return 1;
+    default:
+        abort();
+    }
+    return 0;
+}
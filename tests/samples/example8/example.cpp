@@ -0,0 +1,32 @@
+#include <vector>
+#include <string>
+
+namespace geometry {
+
+/// A shape with an area
+class Shape {
+public:
+    virtual ~Shape() = default;
+    double area() const {
+        return width * height;
+    }
+
+private:
+    double width = 0;
+    double height = 0;
+};
+
+double total_area(const std::vector<Shape> &shapes, double scale = 1.0) {
+    double total = 0;
+    auto positive = [](double a) { return a > 0 ? a : 0.0; };
+    for (const auto &shape : shapes) {
+        try {
+            total += positive(shape.area()) * scale;
+        } catch (const std::exception &e) {
+            throw;
+        }
+    }
+    return total;
+}
+
+}